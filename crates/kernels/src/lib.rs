@@ -6,3 +6,9 @@ pub mod dumb;
 
 #[cfg(feature = "miner")]
 pub mod miner;
+
+#[cfg(feature = "verifier")]
+pub mod verifier;
+
+#[cfg(feature = "miner-dev")]
+pub mod miner_dev;
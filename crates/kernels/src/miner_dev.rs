@@ -0,0 +1,11 @@
+//! INSECURE miner kernel proving with reduced STARK parameters, for
+//! development and CI only. Its proofs are rejected by the network.
+
+#[cfg(feature = "bazel_build")]
+pub static KERNEL: &[u8] = include_bytes!(env!("MINER_DEV_JAM_PATH"));
+
+#[cfg(not(feature = "bazel_build"))]
+pub const KERNEL: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../assets/miner-dev.jam"
+));
@@ -1,18 +1,21 @@
 use crate::kernel::checkpoint::JamPaths;
 use crate::kernel::form::Kernel;
+use crate::kernel::retention::RetentionPolicy;
 use crate::{default_data_dir, NockApp};
 use chrono;
-use clap::{arg, command, ColorChoice, Parser};
+use clap::{arg, command, value_parser, ColorChoice, Parser};
 use nockvm::jets::hot::HotEntry;
+use std::fmt::Write as _;
 use std::fs;
 use std::path::PathBuf;
+use tracing::field::{Field, Visit};
 use tracing::{debug, info, Level};
 use tracing_subscriber::fmt::format::Writer;
 use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{fmt, EnvFilter};
+use tracing_subscriber::{fmt, EnvFilter, Registry};
 
 const DEFAULT_SAVE_INTERVAL: u64 = 30000;
 const DEFAULT_LOG_FILTER: &str = "info,slogger=trace";
@@ -40,6 +43,20 @@ pub struct Cli {
     #[arg(long, help = "Control colored output", value_enum, default_value_t = ColorChoice::Auto)]
     pub color: ColorChoice,
 
+    #[arg(
+        long,
+        help = "Log format: 'auto' to pick by RUST_LOG and MINIMAL_LOG_FORMAT, 'minimal', 'full', or 'json' for one JSON object per line",
+        value_parser = value_parser!(LogFormat),
+        default_value = "auto"
+    )]
+    pub log_format: LogFormat,
+
+    #[arg(
+        long,
+        help = "Export tracing spans over OTLP gRPC to this endpoint, e.g. http://localhost:4317"
+    )]
+    pub otlp_endpoint: Option<String>,
+
     #[arg(
         long,
         help = "Path to a jam file containing existing kernel state. Supports both JammedCheckpoint and ExportedState formats."
@@ -51,6 +68,32 @@ pub struct Cli {
         help = "Path to export the kernel state as a jam file in the ExportedState format."
     )]
     pub export_state_jam: Option<String>,
+
+    #[arg(long, help = "Archive saved checkpoints, keeping at most this many")]
+    pub checkpoint_keep: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Archive saved checkpoints, keeping at most this many bytes of them"
+    )]
+    pub checkpoint_max_bytes: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Archive saved checkpoints, removing those older than this (in seconds)"
+    )]
+    pub checkpoint_max_age: Option<u64>,
+}
+
+impl Cli {
+    /// Checkpoint retention policy described by the command line
+    pub fn retention_policy(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            keep_last: self.checkpoint_keep,
+            max_total_bytes: self.checkpoint_max_bytes,
+            max_age: self.checkpoint_max_age.map(std::time::Duration::from_secs),
+        }
+    }
 }
 
 /// Result of setting up a NockApp
@@ -67,9 +110,172 @@ pub fn default_boot_cli(new: bool) -> Cli {
         new,
         trace: false,
         color: ColorChoice::Auto,
+        log_format: LogFormat::Auto,
+        otlp_endpoint: None,
         state_jam: None,
         export_state_jam: None,
+        checkpoint_keep: None,
+        checkpoint_max_bytes: None,
+        checkpoint_max_age: None,
+    }
+}
+
+/// How [`init_default_tracing`] formats events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// [`LogFormat::Full`] if `RUST_LOG` is set and `MINIMAL_LOG_FORMAT` is not,
+    /// [`LogFormat::Minimal`] otherwise
+    #[default]
+    Auto,
+    /// Short, colored lines for an interactive terminal
+    Minimal,
+    /// tracing's default format
+    Full,
+    /// One JSON object per event, see [`JsonFormatter`]
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(LogFormat::Auto),
+            "minimal" => Ok(LogFormat::Minimal),
+            "full" => Ok(LogFormat::Full),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!(
+                "Invalid log format '{s}', expected auto, minimal, full or json"
+            )),
+        }
+    }
+}
+
+/// An event formatter for log pipelines, writing every event as one line of
+/// JSON:
+///
+/// ```text
+/// {"timestamp":"2025-01-01T00:00:00.000000Z","level":"INFO","module":"nockchain::mining","event":"found a block","fields":{"height":7},"spans":["mine"]}
+/// ```
+///
+/// `event` is the message, `fields` every other field of the event, and
+/// `spans` the names of the spans the event is in, outermost first.
+pub struct JsonFormatter;
+
+impl<S, N> FormatEvent<S, N> for JsonFormatter
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> std::fmt::Result {
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+        let metadata = event.metadata();
+
+        let mut line = String::from("{\"timestamp\":");
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+        write_json_str(&mut line, &now);
+        line.push_str(",\"level\":");
+        write_json_str(&mut line, metadata.level().as_str());
+        line.push_str(",\"module\":");
+        write_json_str(&mut line, metadata.target());
+        line.push_str(",\"event\":");
+        write_json_str(&mut line, fields.message.as_deref().unwrap_or_default());
+        line.push_str(",\"fields\":{");
+        line.push_str(&fields.fields);
+        line.push_str("},\"spans\":[");
+        if let Some(scope) = ctx.event_scope() {
+            for (i, span) in scope.from_root().enumerate() {
+                if i > 0 {
+                    line.push(',');
+                }
+                write_json_str(&mut line, span.name());
+            }
+        }
+        line.push_str("]}");
+        writeln!(writer, "{line}")
+    }
+}
+
+/// An event's message and its other fields, as JSON members
+#[derive(Default)]
+struct JsonFields {
+    message: Option<String>,
+    fields: String,
+}
+
+impl JsonFields {
+    fn key(&mut self, field: &Field) {
+        if !self.fields.is_empty() {
+            self.fields.push(',');
+        }
+        write_json_str(&mut self.fields, field.name());
+        self.fields.push(':');
+    }
+
+    fn raw(&mut self, field: &Field, value: impl std::fmt::Display) {
+        self.key(field);
+        let _ = write!(self.fields, "{value}");
+    }
+}
+
+impl Visit for JsonFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        } else {
+            self.key(field);
+            write_json_str(&mut self.fields, value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record_str(field, &format!("{value:?}"));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.raw(field, value);
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.raw(field, value);
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.raw(field, value);
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        // JSON has no NaN or infinities
+        if value.is_finite() {
+            self.raw(field, value);
+        } else {
+            self.record_str(field, &value.to_string());
+        }
+    }
+}
+
+fn write_json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
     }
+    out.push('"');
 }
 
 /// A minimal event formatter for development mode
@@ -174,29 +380,87 @@ pub fn init_default_tracing(cli: &Cli) {
         std::env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_LOG_FILTER.to_string()),
     );
     let use_ansi = cli.color == ColorChoice::Auto || cli.color == ColorChoice::Always;
+    let telemetry = cli.otlp_endpoint.as_deref().and_then(otlp_telemetry);
 
     // Build and initialize the subscriber
     // If RUST_LOG is set and MINIMAL_LOG_FORMAT is unset, we will do production-grade logging.
     // Otherwise we will do more minimal logging suitable for an interactive terminal.
-    if std::env::var("MINIMAL_LOG_FORMAT").is_ok() || std::env::var("RUST_LOG").is_err() {
-        let fmt_layer = fmt::layer()
-            .with_ansi(use_ansi)
-            .event_format(MinimalFormatter);
-
-        tracing_subscriber::registry()
-            .with(fmt_layer)
-            .with(filter)
-            .init();
-    } else {
-        tracing_subscriber::registry()
-            .with(
-                fmt::layer()
-                    .with_ansi(use_ansi)
-                    .with_target(true)
-                    .with_level(true),
-            )
-            .with(filter)
-            .init();
+    let format = match cli.log_format {
+        LogFormat::Auto
+            if std::env::var("MINIMAL_LOG_FORMAT").is_ok()
+                || std::env::var("RUST_LOG").is_err() =>
+        {
+            LogFormat::Minimal
+        }
+        LogFormat::Auto => LogFormat::Full,
+        format => format,
+    };
+    match format {
+        LogFormat::Minimal => {
+            let fmt_layer = fmt::layer()
+                .with_ansi(use_ansi)
+                .event_format(MinimalFormatter);
+
+            tracing_subscriber::registry()
+                .with(telemetry)
+                .with(fmt_layer)
+                .with(filter)
+                .init();
+        }
+        // log pipelines want neither colors nor anything but JSON on a line
+        LogFormat::Json => {
+            tracing_subscriber::registry()
+                .with(telemetry)
+                .with(fmt::layer().with_ansi(false).event_format(JsonFormatter))
+                .with(filter)
+                .init();
+        }
+        LogFormat::Full | LogFormat::Auto => {
+            tracing_subscriber::registry()
+                .with(telemetry)
+                .with(
+                    fmt::layer()
+                        .with_ansi(use_ansi)
+                        .with_target(true)
+                        .with_level(true),
+                )
+                .with(filter)
+                .init();
+        }
+    }
+}
+
+/// OTLP exporter for `--otlp-endpoint`, named by `OTEL_SERVICE_NAME` or else
+/// the executable. A node that cannot export still runs, so failures are
+/// reported and the exporter left out.
+fn otlp_telemetry(
+    endpoint: &str,
+) -> Option<tracing_opentelemetry::OpenTelemetryLayer<Registry, opentelemetry_sdk::trace::Tracer>> {
+    let service_name = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| {
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| {
+                exe.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "nockapp".to_owned())
+    });
+    let resource = opentelemetry_sdk::Resource::new(vec![
+        opentelemetry::KeyValue::new("service.name", service_name.clone()),
+        opentelemetry::KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+    ]);
+    match crate::observability::otlp_layer(endpoint, &service_name, resource) {
+        Ok(layer) => {
+            eprintln!(
+                "Exporting spans over OTLP to {} as {}",
+                endpoint, service_name
+            );
+            Some(layer)
+        }
+        Err(e) => {
+            eprintln!("Not exporting spans over OTLP to {}: {}", endpoint, e);
+            None
+        }
     }
 }
 
@@ -255,6 +519,7 @@ pub async fn setup_(
     }
 
     let jam_paths = JamPaths::new(&jams_dir);
+    let retention = cli.retention_policy();
     info!("kernel: starting");
     debug!("kernel: pma directory: {:?}", pma_dir);
     debug!(
@@ -278,7 +543,8 @@ pub async fn setup_(
 
     let save_interval = std::time::Duration::from_millis(cli.save_interval);
 
-    let app = NockApp::new(kernel, save_interval).await;
+    let mut app = NockApp::new(kernel, save_interval).await;
+    app.set_checkpoint_retention(retention);
 
     Ok(SetupResult::App(app))
 }
@@ -294,3 +560,65 @@ async fn export_kernel_state(
     info!("Successfully exported kernel state to: {:?}", export_path);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_formatter_writes_one_object_per_event() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = fmt()
+            .with_ansi(false)
+            .event_format(JsonFormatter)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("mine");
+            let _mine = span.enter();
+            tracing::info!(
+                height = 7u64,
+                valid = true,
+                peer = "a\"b",
+                "found {}",
+                "a block"
+            );
+            tracing::warn!(rate = f64::NAN, "line\nbreak");
+        });
+
+        let out = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"timestamp\":\""));
+        assert!(lines[0].ends_with(
+            ",\"level\":\"INFO\",\"module\":\"nockapp::kernel::boot::tests\",\"event\":\"found a block\",\"fields\":{\"height\":7,\"valid\":true,\"peer\":\"a\\\"b\"},\"spans\":[\"mine\"]}"
+        ));
+        assert!(lines[1].ends_with(
+            ",\"level\":\"WARN\",\"module\":\"nockapp::kernel::boot::tests\",\"event\":\"line\\nbreak\",\"fields\":{\"rate\":\"NaN\"},\"spans\":[\"mine\"]}"
+        ));
+    }
+
+    #[test]
+    fn test_log_format_parses() {
+        assert_eq!("JSON".parse::<LogFormat>(), Ok(LogFormat::Json));
+        assert_eq!("minimal".parse::<LogFormat>(), Ok(LogFormat::Minimal));
+        assert!("yaml".parse::<LogFormat>().is_err());
+    }
+}
@@ -17,13 +17,14 @@ use nockvm_macros::tas;
 use std::any::Any;
 use std::fs::File;
 use std::future::Future;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use tracing::{debug, error, info, warn};
 
 use crate::kernel::checkpoint::{Checkpoint, ExportedState, JamPaths, JammedCheckpoint};
+use crate::kernel::migration::{self, MigrationError, SNAPSHOT_VERSION};
 use crate::nockapp::wire::{wire_to_noun, WireRepr};
 use crate::noun::slam;
 use crate::utils::{create_context, current_da, NOCK_STACK_SIZE, NOCK_STACK_SIZE_HUGE};
@@ -38,8 +39,6 @@ const LOAD_AXIS: u64 = 4;
 const PEEK_AXIS: u64 = 22;
 const POKE_AXIS: u64 = 23;
 
-const SNAPSHOT_VERSION: u32 = 0;
-
 const SERF_FINISHED_INTERVAL: Duration = Duration::from_millis(100);
 const SERF_THREAD_STACK_SIZE: usize = 8 * 1024 * 1024; // 8MB
 
@@ -77,8 +76,20 @@ pub enum SerfAction {
     Poke {
         wire: WireRepr,
         cause: NounSlab,
+        entropy: Entropy,
         result: oneshot::Sender<Result<NounSlab>>,
     },
+    // Run a poke, then throw away its effects and new state
+    WarmUp {
+        wire: WireRepr,
+        cause: NounSlab,
+        result: oneshot::Sender<Result<Duration>>,
+    },
+    // Boot a new kernel and load the current state into it
+    Reload {
+        kernel: Vec<u8>,
+        result: oneshot::Sender<Result<KernelReload>>,
+    },
     // Provide metrics
     ProvideMetrics {
         metrics: Arc<NockAppMetrics>,
@@ -88,6 +99,50 @@ pub enum SerfAction {
     Stop,
 }
 
+/// Entropy handed to the kernel as `eny` with each poke
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Entropy {
+    /// Fresh entropy from a CSPRNG for every poke
+    #[default]
+    Random,
+    /// Always the same value, for reproducible proofs and tests
+    Fixed(u64),
+}
+
+impl Entropy {
+    /// The value to poke with
+    pub fn value(&self) -> u64 {
+        match self {
+            Entropy::Random => rand::random::<u64>(),
+            Entropy::Fixed(eny) => *eny,
+        }
+    }
+}
+
+/// Memory usage of a kernel, updated by the serf after every poke and checkpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Size of the NockStack
+    pub stack_bytes: u64,
+    /// Most NockStack space in use at any point during the last poke
+    pub stack_high_water_bytes: u64,
+    /// Bytes allocated for the last poke's effects slab
+    pub effects_slab_bytes: u64,
+    /// Size of the jam in the last checkpoint taken
+    pub checkpoint_bytes: Option<u64>,
+}
+
+/// A kernel swapped in by [`Kernel::reload`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KernelReload {
+    /// Hash of the kernel jam that was running before
+    pub old_hash: Hash,
+    /// Hash of the kernel jam running now
+    pub new_hash: Hash,
+    /// Event number the state was carried over at; reloading is not an event
+    pub event_num: u64,
+}
+
 pub(crate) struct SerfThread {
     handle: Option<std::thread::JoinHandle<()>>,
     action_sender: mpsc::Sender<SerfAction>,
@@ -98,6 +153,7 @@ pub(crate) struct SerfThread {
     /// Buffer toggle for writing to the jam buffer.
     pub buffer_toggle: Arc<AtomicBool>,
     pub event_number: Arc<AtomicU64>,
+    pub memory_stats: Arc<Mutex<MemoryStats>>,
 }
 
 impl SerfThread {
@@ -110,11 +166,14 @@ impl SerfThread {
     ) -> Result<Self> {
         let jam_paths_cloned = jam_paths.clone();
         let (action_sender, action_receiver) = mpsc::channel(1);
-        let (buffer_toggle_sender, buffer_toggle_receiver) = oneshot::channel();
-        let (event_number_sender, event_number_receiver) = oneshot::channel();
-        let (cancel_token_sender, cancel_token_receiver) = oneshot::channel();
+        let (ready_sender, ready_receiver) = oneshot::channel();
         let inhibit = Arc::new(AtomicBool::new(false));
         let inhibit_clone = inhibit.clone();
+        let memory_stats = Arc::new(Mutex::new(MemoryStats {
+            stack_bytes: (nock_stack_size * 8) as u64,
+            ..Default::default()
+        }));
+        let memory_stats_clone = memory_stats.clone();
         std::fs::create_dir_all(jam_paths.0.parent().unwrap_or_else(|| {
             panic!(
                 "Panicked at {}:{} (git sha: {:?})",
@@ -135,35 +194,38 @@ impl SerfThread {
             .name("serf".to_string())
             .stack_size(SERF_THREAD_STACK_SIZE)
             .spawn(move || {
-                let mut stack = NockStack::new(nock_stack_size, 0);
-                let checkpoint = if jam_paths.checkpoint_exists() {
-                    info!("Found existing state - restoring from checkpoint");
-                    jam_paths.load_checkpoint(&mut stack).ok()
-                } else {
-                    info!("No existing state found - initializing fresh state");
-                    None
+                let booted = boot_serf(
+                    nock_stack_size, &jam_paths, &kernel_bytes, &constant_hot_state, trace,
+                );
+                let (serf, buffer_toggle) = match booted {
+                    Ok(booted) => booted,
+                    Err(source) => {
+                        let refused = CrownError::CheckpointRefused {
+                            paths: format!(
+                                "{} and {}",
+                                jam_paths.0.display(),
+                                jam_paths.1.display()
+                            ),
+                            source,
+                        };
+                        error!("{refused}");
+                        let _ = ready_sender.send(Err(refused));
+                        return;
+                    }
                 };
-                let buffer_toggle = Arc::new(AtomicBool::new(
-                    checkpoint
-                        .as_ref()
-                        .map_or_else(|| false, |checkpoint| !checkpoint.buff_index),
-                ));
-                buffer_toggle_sender
-                    .send(buffer_toggle.clone())
-                    .expect("Could not send buffer toggle out of serf thread");
-                let serf = Serf::new(stack, checkpoint, &kernel_bytes, &constant_hot_state, trace);
-                event_number_sender
-                    .send(serf.event_num.clone())
-                    .expect("Could not send event number out of serf thread");
-                cancel_token_sender
-                    .send(serf.context.cancel_token())
-                    .expect("Could not send cancel token out of serf thread");
-                serf_loop(serf, action_receiver, buffer_toggle, inhibit_clone);
+                ready_sender
+                    .send(Ok((
+                        buffer_toggle.clone(),
+                        serf.event_num.clone(),
+                        serf.context.cancel_token(),
+                    )))
+                    .unwrap_or_else(|_| panic!("Could not send boot result out of serf thread"));
+                serf_loop(
+                    serf, action_receiver, buffer_toggle, inhibit_clone, memory_stats_clone,
+                );
             })?;
 
-        let buffer_toggle = buffer_toggle_receiver.await?;
-        let event_number = event_number_receiver.await?;
-        let cancel_token = cancel_token_receiver.await?;
+        let (buffer_toggle, event_number, cancel_token) = ready_receiver.await??;
         Ok(SerfThread {
             inhibit,
             buffer_toggle,
@@ -172,6 +234,7 @@ impl SerfThread {
             jam_paths: jam_paths_cloned,
             event_number,
             cancel_token,
+            memory_stats,
         })
     }
 
@@ -249,6 +312,7 @@ impl SerfThread {
         &self,
         wire: WireRepr,
         cause: NounSlab,
+        entropy: Entropy,
     ) -> impl Future<Output = Result<NounSlab>> {
         let (result, result_fut) = oneshot::channel();
         let action_sender = self.action_sender.clone();
@@ -257,6 +321,7 @@ impl SerfThread {
                 .send(SerfAction::Poke {
                     wire,
                     cause,
+                    entropy,
                     result,
                 })
                 .await?;
@@ -269,6 +334,7 @@ impl SerfThread {
         self.action_sender.blocking_send(SerfAction::Poke {
             wire,
             cause,
+            entropy: Entropy::Random,
             result,
         })?;
         result_fut.blocking_recv()?
@@ -289,12 +355,15 @@ impl SerfThread {
         result_fut.await?
     }
 
-    pub(crate) async fn create_state_bytes(&self) -> Result<Vec<u8>> {
+    pub(crate) fn create_state_bytes(&self) -> impl Future<Output = Result<Vec<u8>>> {
         let (result, result_fut) = oneshot::channel();
-        self.action_sender
-            .send(SerfAction::GetStateBytes { result })
-            .await?;
-        result_fut.await?
+        let action_sender = self.action_sender.clone();
+        async move {
+            action_sender
+                .send(SerfAction::GetStateBytes { result })
+                .await?;
+            result_fut.await?
+        }
     }
 
     pub(crate) fn checkpoint(&self) -> impl Future<Output = Result<JammedCheckpoint>> {
@@ -307,10 +376,74 @@ impl SerfThread {
             Ok(result_fut.await?)
         }
     }
+
+    pub(crate) fn warm_up(
+        &self,
+        wire: WireRepr,
+        cause: NounSlab,
+    ) -> impl Future<Output = Result<Duration>> {
+        let (result, result_fut) = oneshot::channel();
+        let action_sender = self.action_sender.clone();
+        async move {
+            action_sender
+                .send(SerfAction::WarmUp {
+                    wire,
+                    cause,
+                    result,
+                })
+                .await?;
+            result_fut.await?
+        }
+    }
+
+    pub(crate) fn reload(&self, kernel: Vec<u8>) -> impl Future<Output = Result<KernelReload>> {
+        let (result, result_fut) = oneshot::channel();
+        let action_sender = self.action_sender.clone();
+        async move {
+            action_sender
+                .send(SerfAction::Reload { kernel, result })
+                .await?;
+            result_fut.await?
+        }
+    }
+}
+
+/// Boots the serf from the newest readable checkpoint, or fresh if there is none. Returns
+/// the serf and the buffer toggle for its next checkpoint. A checkpoint that can be read
+/// but not migrated to this build is refused rather than overwritten.
+fn boot_serf(
+    nock_stack_size: usize,
+    jam_paths: &JamPaths,
+    kernel_bytes: &[u8],
+    constant_hot_state: &[HotEntry],
+    trace: bool,
+) -> std::result::Result<(Serf, Arc<AtomicBool>), MigrationError> {
+    let mut stack = NockStack::new(nock_stack_size, 0);
+    let checkpoint = if jam_paths.checkpoint_exists() {
+        info!("Found existing state - restoring from checkpoint");
+        match jam_paths.load_checkpoint(&mut stack) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                warn!("Could not read checkpoints, initializing fresh state: {e}");
+                None
+            }
+        }
+    } else {
+        info!("No existing state found - initializing fresh state");
+        None
+    };
+    let buffer_toggle = Arc::new(AtomicBool::new(
+        checkpoint
+            .as_ref()
+            .map_or_else(|| false, |checkpoint| !checkpoint.buff_index),
+    ));
+    let serf = Serf::new(stack, checkpoint, kernel_bytes, constant_hot_state, trace)?;
+    Ok((serf, buffer_toggle))
 }
 
 fn load_state_from_bytes(serf: &mut Serf, state_bytes: &[u8]) -> Result<()> {
-    let noun = extract_state_from_bytes(serf.stack(), state_bytes)?;
+    let ker_hash = serf.ker_hash;
+    let noun = extract_state_from_bytes(serf.stack(), state_bytes, &ker_hash)?;
     let arvo = serf.load(noun)?;
     unsafe {
         serf.event_update(serf.event_num.load(Ordering::SeqCst), arvo);
@@ -325,6 +458,7 @@ fn serf_loop(
     mut action_receiver: mpsc::Receiver<SerfAction>,
     buffer_toggle: Arc<AtomicBool>,
     inhibit: Arc<AtomicBool>,
+    memory_stats: Arc<Mutex<MemoryStats>>,
 ) {
     loop {
         let start = std::time::Instant::now();
@@ -409,6 +543,15 @@ fn serf_loop(
             }
             SerfAction::Checkpoint { result } => {
                 let checkpoint = create_checkpoint(&mut serf, buffer_toggle.clone());
+                let checkpoint_bytes = checkpoint.jam.0.len() as u64;
+                if let Ok(mut stats) = memory_stats.lock() {
+                    stats.checkpoint_bytes = Some(checkpoint_bytes);
+                }
+                if let Some(nockapp_metrics) = &serf.metrics {
+                    nockapp_metrics
+                        .checkpoint_bytes
+                        .swap(checkpoint_bytes as f64);
+                };
                 //result.send(checkpoint).expect("Could not send checkpoint");
                 if result.send(checkpoint).is_err() {
                     debug!(
@@ -451,6 +594,7 @@ fn serf_loop(
             SerfAction::Poke {
                 wire,
                 cause,
+                entropy,
                 result,
             } => {
                 if inhibit.load(Ordering::SeqCst) {
@@ -461,13 +605,15 @@ fn serf_loop(
                             e
                         });
                 } else {
+                    serf.stack().reset_least_space();
                     let cause_noun = cause.copy_to_stack(serf.stack());
-                    let noun_res = serf.poke(wire, cause_noun);
+                    let noun_res = serf.poke_with_entropy(wire, cause_noun, entropy);
                     let noun_slab_res = noun_res.map(|noun| {
                         let mut slab = NounSlab::new();
                         slab.copy_into(noun);
                         slab
                     });
+                    record_poke_memory(&mut serf, &memory_stats, noun_slab_res.as_ref().ok());
                     let _ = result.send(noun_slab_res).map_err(|e| {
                         debug!("Failed to send poke result from serf thread");
                         e
@@ -478,6 +624,34 @@ fn serf_loop(
                     nockapp_metrics.serf_loop_poke.add_timing(&action_elapsed);
                 };
             }
+            SerfAction::WarmUp {
+                wire,
+                cause,
+                result,
+            } => {
+                let res = if inhibit.load(Ordering::SeqCst) {
+                    Err(CrownError::Unknown("Serf stopping".to_string()))
+                } else {
+                    let cause_noun = cause.copy_to_stack(serf.stack());
+                    serf.warm_up(wire, cause_noun)
+                        .map(|()| action_start.elapsed())
+                };
+                let _ = result.send(res).map_err(|e| {
+                    debug!("Failed to send warm-up result from serf thread");
+                    e
+                });
+            }
+            SerfAction::Reload { kernel, result } => {
+                let res = if inhibit.load(Ordering::SeqCst) {
+                    Err(CrownError::Unknown("Serf stopping".to_string()))
+                } else {
+                    serf.reload(&kernel)
+                };
+                let _ = result.send(res).map_err(|e| {
+                    debug!("Failed to send reload result from serf thread");
+                    e
+                });
+            }
             SerfAction::ProvideMetrics { metrics, result } => {
                 serf.metrics = Some(metrics);
                 let _ = result.send(()).map_err(|e| {
@@ -499,21 +673,51 @@ fn serf_loop(
     }
 }
 
+/// Record the NockStack high-water mark and effects slab size of the poke that just ran
+fn record_poke_memory(
+    serf: &mut Serf,
+    memory_stats: &Mutex<MemoryStats>,
+    effects: Option<&NounSlab>,
+) {
+    let stack = serf.stack();
+    let high_water_bytes = ((stack.size() - stack.least_space()) * 8) as u64;
+    let effects_bytes = effects.map_or(0, |slab| slab.allocated_bytes() as u64);
+    if let Ok(mut stats) = memory_stats.lock() {
+        stats.stack_high_water_bytes = high_water_bytes;
+        stats.effects_slab_bytes = effects_bytes;
+    }
+    if let Some(nockapp_metrics) = &serf.metrics {
+        nockapp_metrics
+            .poke_stack_high_water
+            .swap(high_water_bytes as f64);
+        nockapp_metrics
+            .poke_effects_slab_bytes
+            .swap(effects_bytes as f64);
+    };
+}
+
 /// Extracts the kernel state from a jammed checkpoint or exported state
-fn extract_state_from_bytes(stack: &mut NockStack, state_bytes: &[u8]) -> Result<Noun> {
+fn extract_state_from_bytes(
+    stack: &mut NockStack,
+    state_bytes: &[u8],
+    ker_hash: &Hash,
+) -> Result<Noun> {
     // First try to decode as JammedCheckpoint
     match extract_from_checkpoint(stack, state_bytes) {
         Ok(noun) => {
             debug!("Successfully loaded state from JammedCheckpoint format");
             Ok(noun)
         }
+        // Recognized, but written by a build we cannot migrate from
+        Err(e1 @ CrownError::Migration(_)) => Err(e1),
         Err(e1) => {
             // Then try to decode as ExportedState
-            match extract_from_exported_state(stack, state_bytes) {
+            match extract_from_exported_state(stack, state_bytes, ker_hash) {
                 Ok(noun) => {
                     debug!("Successfully loaded state from ExportedState format");
                     Ok(noun)
                 }
+                Err(e2 @ CrownError::Migration(_)) => Err(e2),
                 Err(e2) => {
                     warn!("Failed to load as JammedCheckpoint: {}", e1);
                     warn!("Failed to load as ExportedState: {}", e2);
@@ -526,7 +730,11 @@ fn extract_state_from_bytes(stack: &mut NockStack, state_bytes: &[u8]) -> Result
 }
 
 /// Extracts the kernel state from an ExportedState
-fn extract_from_exported_state(stack: &mut NockStack, state_jam: &[u8]) -> Result<Noun> {
+fn extract_from_exported_state(
+    stack: &mut NockStack,
+    state_jam: &[u8],
+    ker_hash: &Hash,
+) -> Result<Noun> {
     let config = bincode::config::standard();
 
     // Try to decode as ExportedState
@@ -544,6 +752,17 @@ fn extract_from_exported_state(stack: &mut NockStack, state_jam: &[u8]) -> Resul
         return Err(CrownError::StateJamFormatError);
     }
 
+    // State exported from another kernel is still loaded, since the kernel's +load arm
+    // is responsible for upgrading it, but it is worth calling out.
+    if exported.ker_hash != *ker_hash {
+        warn!(
+            "Importing state exported by a different kernel (exported: {}, running: {})",
+            exported.ker_hash, ker_hash
+        );
+    }
+
+    migration::plan(exported.version)?;
+
     // Extract the kernel state from the jammed noun
     let noun = <Noun as NounExt>::cue_bytes(stack, &exported.jam.0).map_err(|e| {
         warn!("Failed to cue bytes from exported state jam: {:?}", e);
@@ -551,7 +770,7 @@ fn extract_from_exported_state(stack: &mut NockStack, state_jam: &[u8]) -> Resul
     })?;
 
     debug!("Successfully extracted kernel state from ExportedState");
-    Ok(noun)
+    Ok(migration::migrate(stack, exported.version, noun)?)
 }
 
 /// Extracts the kernel state from a JammedCheckpoint
@@ -578,6 +797,7 @@ fn extract_from_checkpoint(stack: &mut NockStack, state_jam: &[u8]) -> Result<No
         warn!("Checkpoint validation failed");
         return Err(CrownError::StateJamFormatError);
     }
+    migration::plan(checkpoint.version)?;
 
     // Extract the kernel state from the jammed noun
     let cell = <Noun as NounExt>::cue_bytes(stack, &checkpoint.jam.0)
@@ -593,7 +813,7 @@ fn extract_from_checkpoint(stack: &mut NockStack, state_jam: &[u8]) -> Result<No
 
     // The kernel state is the head of the cell
     debug!("Successfully extracted kernel state from JammedCheckpoint");
-    Ok(cell.head())
+    Ok(migration::migrate(stack, checkpoint.version, cell.head())?)
 }
 
 /// Creates a serialized byte array from the current kernel state
@@ -672,18 +892,10 @@ impl Kernel {
         hot_state: &[HotEntry],
         trace: bool,
     ) -> Result<Self> {
-        let jam_paths_arc = Arc::new(jam_paths);
-        let kernel_vec = Vec::from(kernel);
-        let hot_state_vec = Vec::from(hot_state);
-        let pma_dir_arc = Arc::new(pma_dir);
-        let serf = SerfThread::new(
-            NOCK_STACK_SIZE, jam_paths_arc, kernel_vec, hot_state_vec, trace,
+        Self::load_with_stack_size(
+            pma_dir, jam_paths, kernel, hot_state, NOCK_STACK_SIZE, trace,
         )
-        .await?;
-        Ok(Self {
-            serf,
-            pma_dir: pma_dir_arc,
-        })
+        .await
     }
 
     pub async fn load_with_hot_state_huge(
@@ -692,13 +904,40 @@ impl Kernel {
         kernel: &[u8],
         hot_state: &[HotEntry],
         trace: bool,
+    ) -> Result<Self> {
+        Self::load_with_stack_size(
+            pma_dir, jam_paths, kernel, hot_state, NOCK_STACK_SIZE_HUGE, trace,
+        )
+        .await
+    }
+
+    /// Loads a kernel with a custom hot state and NockStack size.
+    ///
+    /// # Arguments
+    ///
+    /// * `snap_dir` - Directory for storing snapshots.
+    /// * `kernel` - Byte slice containing the kernel as a jammed noun.
+    /// * `hot_state` - Custom hot state entries.
+    /// * `nock_stack_size` - Size of the NockStack in 8-byte words.
+    /// * `trace` - Whether to enable tracing.
+    ///
+    /// # Returns
+    ///
+    /// A new `Kernel` instance.
+    pub async fn load_with_stack_size(
+        pma_dir: PathBuf,
+        jam_paths: JamPaths,
+        kernel: &[u8],
+        hot_state: &[HotEntry],
+        nock_stack_size: usize,
+        trace: bool,
     ) -> Result<Self> {
         let jam_paths_arc = Arc::new(jam_paths);
         let kernel_vec = Vec::from(kernel);
         let hot_state_vec = Vec::from(hot_state);
         let pma_dir_arc = Arc::new(pma_dir);
         let serf = SerfThread::new(
-            NOCK_STACK_SIZE_HUGE, jam_paths_arc, kernel_vec, hot_state_vec, trace,
+            nock_stack_size, jam_paths_arc, kernel_vec, hot_state_vec, trace,
         )
         .await?;
         Ok(Self {
@@ -756,6 +995,23 @@ impl Kernel {
         }
     }
 
+    /// Memory usage recorded after the most recent poke and checkpoint
+    pub fn memory_stats(&self) -> MemoryStats {
+        *self
+            .serf
+            .memory_stats
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Token that interrupts whatever computation the kernel is currently running.
+    ///
+    /// Cancelling makes the in-flight poke or peek fail; the kernel state is left as it
+    /// was before that event.
+    pub fn cancel_token(&self) -> NockCancelToken {
+        self.serf.cancel_token.clone()
+    }
+
     /// Produces a checkpoint of the kernel state.
     pub fn checkpoint(&self) -> impl Future<Output = Result<JammedCheckpoint>> {
         self.serf.checkpoint()
@@ -763,7 +1019,17 @@ impl Kernel {
 
     // We are very carefully ensuring the future does not contain the "self" reference to ensure no lifetime issues when spawning tasks
     pub fn poke(&self, wire: WireRepr, cause: NounSlab) -> impl Future<Output = Result<NounSlab>> {
-        self.serf.poke(wire, cause)
+        self.serf.poke(wire, cause, Entropy::Random)
+    }
+
+    /// Poke with explicit entropy, e.g. [`Entropy::Fixed`] for reproducible runs
+    pub fn poke_with_entropy(
+        &self,
+        wire: WireRepr,
+        cause: NounSlab,
+        entropy: Entropy,
+    ) -> impl Future<Output = Result<NounSlab>> {
+        self.serf.poke(wire, cause, entropy)
     }
 
     pub fn poke_sync(&self, wire: WireRepr, cause: NounSlab) -> Result<NounSlab> {
@@ -787,8 +1053,81 @@ impl Kernel {
         self.serf.provide_metrics(metrics)
     }
 
-    pub async fn create_state_bytes(&self) -> Result<Vec<u8>> {
-        self.serf.create_state_bytes().await
+    /// Jams the kernel state as an `ExportedState`, see [`Self::export_state`].
+    ///
+    /// The future does not borrow the kernel, so the state of a kernel that is about to
+    /// be dropped can still be collected, e.g. after a failed poke.
+    pub fn create_state_bytes(&self) -> impl Future<Output = Result<Vec<u8>>> {
+        self.serf.create_state_bytes()
+    }
+
+    /// Exports the kernel state to a portable jam archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - File to write the `ExportedState` to. It is written atomically.
+    ///
+    /// # Returns
+    ///
+    /// The event number the exported state was taken at.
+    pub async fn export_state(&self, path: &Path) -> Result<u64> {
+        let state_bytes = self.create_state_bytes().await?;
+        let event_num = self.serf.event_number.load(Ordering::SeqCst);
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, &state_bytes).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        info!(
+            "Exported kernel state at event {} to {:?} ({} bytes)",
+            event_num,
+            path,
+            state_bytes.len()
+        );
+        Ok(event_num)
+    }
+
+    /// Runs `cause` as a poke whose effects and new state are thrown away, so that the
+    /// first real poke does not pay for jet registration and faulting in the NockStack.
+    ///
+    /// The kernel state and event number are left as they were. Pick a cause the kernel
+    /// handles cheaply; a poke that crashes still warms what it ran and fails with
+    /// [`CrownError::WorkBail`].
+    ///
+    /// # Returns
+    ///
+    /// How long the warm-up poke took on the serf.
+    pub fn warm_up(
+        &self,
+        wire: WireRepr,
+        cause: NounSlab,
+    ) -> impl Future<Output = Result<Duration>> {
+        self.serf.warm_up(wire, cause)
+    }
+
+    /// Swaps in a new kernel without restarting, e.g. a rebuilt jam during development.
+    ///
+    /// The new kernel is booted on the running serf and its `+load` arm is handed the
+    /// current kernel state, exactly as when booting a new kernel from a checkpoint.
+    /// Pokes and peeks sent before the reload finish against the old kernel, those sent
+    /// after run against the new one. If the new kernel fails to boot or to load the
+    /// state, the old kernel keeps running.
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel` - The new kernel as a jammed noun.
+    pub fn reload(&self, kernel: &[u8]) -> impl Future<Output = Result<KernelReload>> {
+        self.serf.reload(Vec::from(kernel))
+    }
+
+    /// Replaces the running kernel state with an exported state or checkpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `state_bytes` - A `JammedCheckpoint` or `ExportedState`, possibly produced on
+    ///   another machine.
+    pub async fn import_state(&self, state_bytes: Vec<u8>) -> Result<()> {
+        self.serf.load_state_from_bytes(state_bytes).await?;
+        info!("Imported kernel state");
+        Ok(())
     }
 }
 
@@ -831,7 +1170,11 @@ impl Serf {
         kernel_bytes: &[u8],
         constant_hot_state: &[HotEntry],
         trace: bool,
-    ) -> Self {
+    ) -> std::result::Result<Self, MigrationError> {
+        // Refuse before paying for a kernel boot
+        if let Some(checkpoint) = &checkpoint {
+            migration::plan(checkpoint.version)?;
+        }
         let hot_state = [URBIT_HOT_STATE, constant_hot_state].concat();
 
         let (cold, event_num_raw) = checkpoint.as_ref().map_or_else(
@@ -857,9 +1200,8 @@ impl Serf {
         let mut context = create_context(stack, &hot_state, cold, trace_info);
         let cancel_token = context.cancel_token();
 
-        let version = checkpoint
-            .as_ref()
-            .map_or_else(|| SNAPSHOT_VERSION, |snapshot| snapshot.version);
+        // Checkpointed state is migrated on load, so it is always written back at this version
+        let version = SNAPSHOT_VERSION;
 
         let mut arvo = {
             let kernel_trap = Noun::cue_bytes_slice(&mut context.stack, kernel_bytes)
@@ -911,14 +1253,18 @@ impl Serf {
                     checkpoint.ker_hash, ker_hash
                 );
             }
-            arvo = serf.load(checkpoint.ker_state).expect("serf: load failed");
+            let state = migration::migrate(serf.stack(), checkpoint.version, checkpoint.ker_state)?;
+            arvo = serf.load(state).map_err(|e| MigrationError::LoadRejected {
+                version: checkpoint.version,
+                reason: e.to_string(),
+            })?;
         }
 
         unsafe {
             serf.event_update(event_num_raw, arvo);
             serf.preserve_event_update_leftovers();
         }
-        serf
+        Ok(serf)
     }
 
     /// Performs a peek operation on the Arvo state.
@@ -981,6 +1327,55 @@ impl Serf {
         }
     }
 
+    /// Boots `kernel_bytes` and loads the current state into it, keeping the running
+    /// kernel if either fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel_bytes` - Byte slice containing the new kernel as a jammed noun.
+    ///
+    /// # Returns
+    ///
+    /// Result containing the hashes of the old and new kernel or an error.
+    pub fn reload(&mut self, kernel_bytes: &[u8]) -> Result<KernelReload> {
+        let kernel_trap = Noun::cue_bytes_slice(&mut self.context.stack, kernel_bytes)
+            .map_err(|_| CrownError::BootError)?;
+        let fol = T(&mut self.context.stack, &[D(9), D(2), D(0), D(1)]);
+        let new_arvo = interpret(&mut self.context, kernel_trap, fol).map_err(|err| {
+            warn!("New kernel failed to boot: {:?}", err);
+            CrownError::BootError
+        })?;
+
+        let old_arvo = self.arvo;
+        let ker_state = old_arvo.slot(STATE_AXIS)?;
+        self.arvo = new_arvo;
+        let arvo = match self.load(ker_state) {
+            Ok(arvo) => arvo,
+            Err(e) => {
+                self.arvo = old_arvo;
+                return Err(e);
+            }
+        };
+
+        let mut hasher = Hasher::new();
+        hasher.update(kernel_bytes);
+        let reload = KernelReload {
+            old_hash: self.ker_hash,
+            new_hash: hasher.finalize(),
+            event_num: self.event_num.load(Ordering::SeqCst),
+        };
+        self.ker_hash = reload.new_hash;
+        unsafe {
+            self.event_update(reload.event_num, arvo);
+            self.preserve_event_update_leftovers();
+        }
+        info!(
+            "Kernel reloaded: {:?} -> {:?}",
+            reload.old_hash, reload.new_hash
+        );
+        Ok(reload)
+    }
+
     pub fn print_goof(&mut self, goof: Noun) {
         let tang = goof
             .as_cell()
@@ -1234,7 +1629,49 @@ impl Serf {
         src = wire.source
     ))]
     pub fn poke(&mut self, wire: WireRepr, cause: Noun) -> Result<Noun> {
-        let random_bytes = rand::random::<u64>();
+        self.poke_with_entropy(wire, cause, Entropy::Random)
+    }
+
+    /// Like [`Serf::poke`], with `eny` taken from `entropy`
+    pub fn poke_with_entropy(
+        &mut self,
+        wire: WireRepr,
+        cause: Noun,
+        entropy: Entropy,
+    ) -> Result<Noun> {
+        let poke = self.poke_job(wire, cause, entropy)?;
+        self.do_poke(poke)
+    }
+
+    /// Runs a poke and throws its effects and new state away, leaving the event number
+    /// where it was. Jets registered during the poke are kept, so the next real poke
+    /// does not pay for registering them.
+    ///
+    /// # Arguments
+    ///
+    /// * `wire` - The wire noun.
+    /// * `cause` - The cause noun, best one the kernel handles cheaply.
+    pub fn warm_up(&mut self, wire: WireRepr, cause: Noun) -> Result<()> {
+        let poke = self.poke_job(wire, cause, Entropy::Fixed(0))?;
+        let poked = self
+            .soft(poke, POKE_AXIS, Some("warm-up".to_string()))
+            .is_ok();
+        let (event_num, arvo) = (self.event_num.load(Ordering::SeqCst), self.arvo);
+        unsafe {
+            // keeps the warm, hot and cold state the poke filled in, drops the rest
+            self.event_update(event_num, arvo);
+            self.preserve_event_update_leftovers();
+        }
+        if poked {
+            Ok(())
+        } else {
+            Err(CrownError::WorkBail)
+        }
+    }
+
+    /// The `[eve wire eny our now cause]` sample of the kernel's poke arm
+    fn poke_job(&mut self, wire: WireRepr, cause: Noun, entropy: Entropy) -> Result<Noun> {
+        let random_bytes = entropy.value();
         let bytes = random_bytes.as_bytes()?;
         let eny: Atom = Atom::from_bytes(&mut self.context.stack, &bytes);
         let our = <nockvm::noun::Atom as AtomExt>::from_value(&mut self.context.stack, 0)?; // Using 0 as default value
@@ -1252,8 +1689,7 @@ impl Serf {
             &mut self.context.stack,
             &[event_num, wire, eny.as_noun(), our.as_noun(), now.as_noun(), cause],
         );
-
-        self.do_poke(poke)
+        Ok(poke)
     }
 
     /// Updates the Serf's state after an event.
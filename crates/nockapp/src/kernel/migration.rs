@@ -0,0 +1,175 @@
+//! Bringing checkpoints written by older builds up to the current format.
+//!
+//! A checkpoint records the [`SNAPSHOT_VERSION`] of the build that wrote it.
+//! On load, a checkpoint from an older version is passed through every
+//! [`Migration`] from its version up to the current one before the kernel's
+//! `+load` arm sees it; a changed kernel hash alone needs no migration, since
+//! `+load` is what upgrades kernel state. A checkpoint that cannot be brought
+//! up, because it was written by a newer build or no chain of migrations
+//! reaches the current version, is refused with a [`MigrationError`] rather
+//! than handed to a kernel that would misread it.
+
+use nockvm::mem::NockStack;
+use nockvm::noun::Noun;
+use thiserror::Error;
+use tracing::info;
+
+/// Version of the checkpoint and exported state formats written by this build
+pub const SNAPSHOT_VERSION: u32 = 0;
+
+/// Rewrites kernel state from one snapshot version to the next
+pub struct Migration {
+    /// Version this migration reads; it writes `from + 1`
+    pub from: u32,
+    /// What changed, for the log
+    pub describe: &'static str,
+    pub migrate: fn(&mut NockStack, Noun) -> Result<Noun, MigrationError>,
+}
+
+/// Every migration this build knows, one per version bump. Bumping
+/// [`SNAPSHOT_VERSION`] means adding the migration from the previous version
+/// here.
+static MIGRATIONS: &[Migration] = &[];
+
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error(
+        "checkpoint version {found} was written by a newer build, this build reads up to version {current}"
+    )]
+    Newer { found: u32, current: u32 },
+    #[error("no migration from checkpoint version {from} to version {current}")]
+    NoPath { from: u32, current: u32 },
+    #[error("migrating checkpoint from version {from} failed: {reason}")]
+    Failed { from: u32, reason: String },
+    #[error("kernel rejected the checkpointed state (version {version}): {reason}")]
+    LoadRejected { version: u32, reason: String },
+}
+
+/// The migrations that take state at `version` to [`SNAPSHOT_VERSION`], in order
+pub fn plan(version: u32) -> Result<Vec<&'static Migration>, MigrationError> {
+    plan_in(MIGRATIONS, version, SNAPSHOT_VERSION)
+}
+
+/// Migrate `state`, written at `version`, to [`SNAPSHOT_VERSION`]
+pub fn migrate(stack: &mut NockStack, version: u32, state: Noun) -> Result<Noun, MigrationError> {
+    run(plan(version)?, stack, state)
+}
+
+fn plan_in(
+    migrations: &'static [Migration],
+    version: u32,
+    current: u32,
+) -> Result<Vec<&'static Migration>, MigrationError> {
+    if version > current {
+        return Err(MigrationError::Newer {
+            found: version,
+            current,
+        });
+    }
+    (version..current)
+        .map(|from| {
+            migrations
+                .iter()
+                .find(|migration| migration.from == from)
+                .ok_or(MigrationError::NoPath {
+                    from: version,
+                    current,
+                })
+        })
+        .collect()
+}
+
+fn run(
+    steps: Vec<&'static Migration>,
+    stack: &mut NockStack,
+    mut state: Noun,
+) -> Result<Noun, MigrationError> {
+    for step in steps {
+        info!(
+            "Migrating checkpoint from version {} to {}: {}",
+            step.from,
+            step.from + 1,
+            step.describe
+        );
+        state = (step.migrate)(stack, state)?;
+    }
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nockvm::noun::{D, T};
+
+    fn wrap(stack: &mut NockStack, state: Noun) -> Result<Noun, MigrationError> {
+        Ok(T(stack, &[D(1), state]))
+    }
+
+    fn refuse(_stack: &mut NockStack, _state: Noun) -> Result<Noun, MigrationError> {
+        Err(MigrationError::Failed {
+            from: 1,
+            reason: "unsupported".to_string(),
+        })
+    }
+
+    static TABLE: &[Migration] = &[
+        Migration {
+            from: 0,
+            describe: "wrap state",
+            migrate: wrap,
+        },
+        Migration {
+            from: 1,
+            describe: "wrap state again",
+            migrate: wrap,
+        },
+    ];
+
+    #[test]
+    fn test_plan_chains_migrations() {
+        let steps = plan_in(TABLE, 0, 2).unwrap();
+        let froms: Vec<u32> = steps.iter().map(|step| step.from).collect();
+        assert_eq!(froms, vec![0, 1]);
+        assert!(plan_in(TABLE, 2, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_plan_refuses_newer_and_gaps() {
+        assert!(matches!(
+            plan_in(TABLE, 3, 2),
+            Err(MigrationError::Newer {
+                found: 3,
+                current: 2
+            })
+        ));
+        assert!(matches!(
+            plan_in(TABLE, 0, 3),
+            Err(MigrationError::NoPath {
+                from: 0,
+                current: 3
+            })
+        ));
+        assert!(plan(SNAPSHOT_VERSION).unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_run_applies_steps_in_order() {
+        let mut stack = NockStack::new(1 << 16, 0);
+        let state = run(plan_in(TABLE, 0, 2).unwrap(), &mut stack, D(7)).unwrap();
+        let outer = state.as_cell().unwrap();
+        assert_eq!(outer.head().as_direct().unwrap().data(), 1);
+        let inner = outer.tail().as_cell().unwrap();
+        assert_eq!(inner.tail().as_direct().unwrap().data(), 7);
+
+        static FAILING: &[Migration] = &[Migration {
+            from: 0,
+            describe: "refuse",
+            migrate: refuse,
+        }];
+        assert!(matches!(
+            run(plan_in(FAILING, 0, 1).unwrap(), &mut stack, D(7)),
+            Err(MigrationError::Failed { from: 1, .. })
+        ));
+    }
+}
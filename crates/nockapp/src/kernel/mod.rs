@@ -1,3 +1,5 @@
 pub mod boot;
 pub mod checkpoint;
 pub mod form;
+pub mod migration;
+pub mod retention;
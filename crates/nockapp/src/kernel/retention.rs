@@ -0,0 +1,203 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{fs, io};
+
+use tracing::{debug, info};
+
+/// Extension used for archived checkpoints
+const ARCHIVE_EXTENSION: &str = "chkjam";
+
+/// Limits on how many archived checkpoints are kept around.
+///
+/// Every limit that is set is enforced; the newest checkpoint is never removed
+/// so there is always something to restore from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Keep at most this many checkpoints
+    pub keep_last: Option<usize>,
+    /// Keep at most this many bytes of checkpoints in total
+    pub max_total_bytes: Option<u64>,
+    /// Remove checkpoints older than this
+    pub max_age: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    /// A policy with no limits keeps every checkpoint and disables archiving entirely
+    pub fn is_unbounded(&self) -> bool {
+        self.keep_last.is_none() && self.max_total_bytes.is_none() && self.max_age.is_none()
+    }
+}
+
+/// What a call to [`CheckpointArchive::prune`] removed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    pub removed: usize,
+    pub freed_bytes: u64,
+    pub retained: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchivedCheckpoint {
+    pub path: PathBuf,
+    pub event_num: u64,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// Directory of checkpoints copied out of the double buffer, named by event number
+#[derive(Debug, Clone)]
+pub struct CheckpointArchive {
+    dir: PathBuf,
+}
+
+impl CheckpointArchive {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path an archived checkpoint for `event_num` is written to
+    pub fn path_for(&self, event_num: u64) -> PathBuf {
+        self.dir
+            .join(format!("{:020}", event_num))
+            .with_extension(ARCHIVE_EXTENSION)
+    }
+
+    /// Copy an encoded checkpoint into the archive
+    pub fn archive(&self, event_num: u64, bytes: &[u8]) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path_for(event_num);
+        fs::write(&path, bytes)?;
+        debug!("Archived checkpoint for event {} at {:?}", event_num, path);
+        Ok(path)
+    }
+
+    /// Archived checkpoints, newest first
+    pub fn list(&self) -> io::Result<Vec<ArchivedCheckpoint>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut checkpoints = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(ARCHIVE_EXTENSION) {
+                continue;
+            }
+            let Some(event_num) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            let metadata = entry.metadata()?;
+            checkpoints.push(ArchivedCheckpoint {
+                path,
+                event_num,
+                size: metadata.len(),
+                modified: metadata.modified()?,
+            });
+        }
+        checkpoints.sort_by(|a, b| b.event_num.cmp(&a.event_num));
+        Ok(checkpoints)
+    }
+
+    /// Remove archived checkpoints that fall outside of `policy`
+    pub fn prune(&self, policy: &RetentionPolicy) -> io::Result<PruneReport> {
+        let checkpoints = self.list()?;
+        let now = SystemTime::now();
+        let mut report = PruneReport::default();
+        let mut kept_bytes = 0u64;
+
+        for (i, checkpoint) in checkpoints.iter().enumerate() {
+            // never remove the newest checkpoint
+            let expired = i > 0
+                && (policy.keep_last.is_some_and(|n| i >= n)
+                    || policy
+                        .max_total_bytes
+                        .is_some_and(|max| kept_bytes + checkpoint.size > max)
+                    || policy.max_age.is_some_and(|age| {
+                        now.duration_since(checkpoint.modified)
+                            .is_ok_and(|elapsed| elapsed > age)
+                    }));
+            if expired {
+                fs::remove_file(&checkpoint.path)?;
+                report.removed += 1;
+                report.freed_bytes += checkpoint.size;
+            } else {
+                kept_bytes += checkpoint.size;
+                report.retained += 1;
+            }
+        }
+        if report.removed > 0 {
+            info!(
+                "Pruned {} checkpoints ({} bytes), {} retained",
+                report.removed, report.freed_bytes, report.retained
+            );
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prune_keep_last_and_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = CheckpointArchive::new(dir.path());
+        for event in 1..=5u64 {
+            archive.archive(event, &[0u8; 10]).unwrap();
+        }
+
+        let report = archive
+            .prune(&RetentionPolicy {
+                keep_last: Some(3),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(report.removed, 2);
+        let events: Vec<u64> = archive
+            .list()
+            .unwrap()
+            .iter()
+            .map(|c| c.event_num)
+            .collect();
+        assert_eq!(events, vec![5, 4, 3]);
+
+        let report = archive
+            .prune(&RetentionPolicy {
+                max_total_bytes: Some(15),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(report.removed, 2);
+        assert_eq!(report.retained, 1);
+    }
+
+    #[test]
+    fn test_prune_keeps_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = CheckpointArchive::new(dir.path());
+        archive.archive(7, &[0u8; 100]).unwrap();
+
+        let report = archive
+            .prune(&RetentionPolicy {
+                keep_last: Some(0),
+                max_total_bytes: Some(1),
+                max_age: Some(Duration::ZERO),
+            })
+            .unwrap();
+        assert_eq!(report.removed, 0);
+        assert_eq!(report.retained, 1);
+    }
+}
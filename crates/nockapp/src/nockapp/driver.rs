@@ -1,3 +1,4 @@
+use crate::kernel::form::KernelReload;
 use crate::noun::slab::NounSlab;
 use futures::future::Future;
 use std::pin::Pin;
@@ -65,6 +66,11 @@ pub enum IOAction {
         path: NounSlab,
         result_channel: oneshot::Sender<Option<NounSlab>>,
     },
+    /// Kernel swap request to [`crate::NockApp`], see [`crate::NockApp::reload_kernel`]
+    ReloadKernel {
+        kernel: Vec<u8>,
+        result_channel: oneshot::Sender<Result<KernelReload, NockAppError>>,
+    },
 }
 
 impl NockAppHandle {
@@ -161,6 +167,19 @@ impl NockAppHandle {
         Ok(result_future.await?)
     }
 
+    /// Swap in a new kernel jam, see [`crate::NockApp::reload_kernel`]
+    #[tracing::instrument(name = "nockapp::NockAppHandle::reload_kernel", skip_all)]
+    pub async fn reload_kernel(&self, kernel: Vec<u8>) -> Result<KernelReload, NockAppError> {
+        let (result_channel, result_future) = oneshot::channel();
+        self.io_sender
+            .send(IOAction::ReloadKernel {
+                kernel,
+                result_channel,
+            })
+            .await?;
+        result_future.await?
+    }
+
     #[instrument(skip(self))]
     pub async fn next_effect(&self) -> Result<NounSlab, NockAppError> {
         let mut effect_receiver = self.effect_receiver.lock().await;
@@ -9,6 +9,9 @@ metrics_struct![
     (poke_during_exit, "nockapp.poke_during_exit", Count),
     (peek_during_exit, "nockapp.peek_during_exit", Count),
     (least_free_space_seen_in_slam, "nockapp.least_free_space_seen_in_slam", Gauge),
+    (poke_stack_high_water, "nockapp.poke.stack_high_water", Gauge),
+    (poke_effects_slab_bytes, "nockapp.poke.effects_slab_bytes", Gauge),
+    (checkpoint_bytes, "nockapp.checkpoint_bytes", Gauge),
     (serf_loop_blocking_recv, "nockapp.serf_loop.blocking_recv", TimingCount),
     (serf_loop_all, "nockapp.serf_loop.all", TimingCount),
     (serf_loop_load_state, "nockapp.serf_loop.load_state", TimingCount),
@@ -14,13 +14,15 @@ use std::sync::Arc;
 
 use futures::FutureExt;
 use tokio::io::AsyncWriteExt;
-use tokio::sync::{broadcast, mpsc, Mutex, OwnedMutexGuard};
+use tokio::sync::{broadcast, mpsc, Mutex, OwnedMutexGuard, RwLock};
 use tokio::time::{interval, Duration, Interval};
 use tokio::{fs, select};
+use tokio_util::sync::{CancellationToken, WaitForCancellationFuture};
 use tokio_util::task::TaskTracker;
 use tracing::{debug, error, info, instrument, trace, warn};
 
-use crate::kernel::form::Kernel;
+use crate::kernel::form::{Kernel, KernelReload};
+use crate::kernel::retention::{CheckpointArchive, PruneReport, RetentionPolicy};
 use crate::noun::slab::NounSlab;
 
 use driver::{IOAction, IODriverFn, NockAppHandle, PokeResult};
@@ -77,6 +79,11 @@ pub struct NockApp {
     save_interval: Interval,
     /// Mutex to ensure only one save at a time
     pub(crate) save_mutex: Arc<Mutex<()>>,
+    /// Held shared by every poke in flight and exclusively by a kernel reload, so a
+    /// reload waits for running pokes and holds back new ones until it is done
+    poke_gate: Arc<RwLock<()>>,
+    /// Archive of saved checkpoints and the policy for pruning it, if enabled
+    checkpoint_retention: Option<(CheckpointArchive, RetentionPolicy)>,
     /// Shutdown oneshot sender
     pub npc_socket_path: Option<PathBuf>,
     metrics: Arc<NockAppMetrics>,
@@ -98,12 +105,35 @@ pub enum NockAppExitStatus {
 #[derive(Clone)]
 pub struct NockAppExit {
     sender: tokio::sync::mpsc::Sender<NockAppExitStatus>,
+    /// Cancelled as soon as the app starts exiting, so drivers can wind down
+    shutdown_token: CancellationToken,
 }
 
 impl NockAppExit {
     pub fn new() -> (Self, tokio::sync::mpsc::Receiver<NockAppExitStatus>) {
         let (sender, receiver) = tokio::sync::mpsc::channel(1);
-        (NockAppExit { sender }, receiver)
+        let shutdown_token = CancellationToken::new();
+        (
+            NockAppExit {
+                sender,
+                shutdown_token,
+            },
+            receiver,
+        )
+    }
+
+    /// Resolves once the app has started exiting. Drivers holding work that would be
+    /// lost on exit should persist it when this fires.
+    pub fn shutdown_requested(&self) -> WaitForCancellationFuture<'_> {
+        self.shutdown_token.cancelled()
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown_token.is_cancelled()
+    }
+
+    fn request_shutdown(&self) {
+        self.shutdown_token.cancel();
     }
 
     pub fn exit(&self, code: usize) -> impl std::future::Future<Output = NockAppResult> {
@@ -193,6 +223,8 @@ impl NockApp {
             effect_broadcast,
             save_interval,
             save_mutex,
+            poke_gate: Arc::new(RwLock::new(())),
+            checkpoint_retention: None,
             // cancel_token,
             npc_socket_path: None,
             metrics,
@@ -257,6 +289,42 @@ impl NockApp {
         io_sender
     }
 
+    /// Keep a copy of every saved checkpoint in `checkpoints/archive`, pruned according to `policy`.
+    ///
+    /// An unbounded policy disables archiving, leaving only the double-buffered checkpoints.
+    pub fn set_checkpoint_retention(&mut self, policy: RetentionPolicy) {
+        if policy.is_unbounded() {
+            self.checkpoint_retention = None;
+            return;
+        }
+        let jams_dir = self
+            .kernel
+            .serf
+            .jam_paths
+            .0
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default();
+        let archive = CheckpointArchive::new(&jams_dir.join("archive"));
+        info!(
+            "Archiving checkpoints to {:?} with retention {:?}",
+            archive.dir(),
+            policy
+        );
+        self.checkpoint_retention = Some((archive, policy));
+    }
+
+    /// Prune archived checkpoints now instead of waiting for the next save
+    pub async fn compact_checkpoints(&self) -> Result<PruneReport, NockAppError> {
+        let Some((archive, policy)) = self.checkpoint_retention.clone() else {
+            return Ok(PruneReport::default());
+        };
+        let _guard = self.save_mutex.lock().await;
+        tokio::task::spawn_blocking(move || archive.prune(&policy))
+            .await?
+            .map_err(NockAppError::IoError)
+    }
+
     /// Purely for testing purposes (injecting delays) for now.
     #[instrument(skip(self, f, save_permit))]
     pub(crate) async fn save_f(
@@ -264,12 +332,30 @@ impl NockApp {
         f: impl std::future::Future<Output = ()> + Send + 'static,
         save_permit: OwnedMutexGuard<()>,
     ) -> Result<tokio::task::JoinHandle<NockAppResult>, NockAppError> {
+        let save_fut = self.save_future(f);
+        let join_handle = self.tasks.spawn(async move {
+            save_fut.await?;
+            drop(save_permit);
+            Ok::<(), NockAppError>(())
+        });
+        // We don't want to close and re-open the tasktracker from multiple places
+        // so we're just returning the join_handle to let the caller decide.
+        Ok(join_handle)
+    }
+
+    /// Checkpoint the kernel and write it out, without taking the save mutex. The
+    /// checkpoint is taken when the future is first polled, not when it is created.
+    fn save_future(
+        &self,
+        f: impl std::future::Future<Output = ()> + Send + 'static,
+    ) -> impl Future<Output = NockAppResult> + Send + 'static {
         let toggle = self.kernel.serf.buffer_toggle.clone();
         let jam_paths = self.kernel.serf.jam_paths.clone();
         let send_lock = self.watch_send.clone();
         let checkpoint_fut = self.kernel.checkpoint();
+        let retention = self.checkpoint_retention.clone();
 
-        let join_handle = self.tasks.spawn(async move {
+        async move {
             let checkpoint = checkpoint_fut.await?;
             let bytes = checkpoint.encode()?;
             f.await;
@@ -296,14 +382,20 @@ impl NockApp {
 
             // Flip toggle after successful write
             toggle.store(!toggle.load(Ordering::SeqCst), Ordering::SeqCst);
+
+            if let Some((archive, policy)) = retention {
+                let event_num = checkpoint.event_num;
+                tokio::task::spawn_blocking(move || {
+                    archive.archive(event_num, &bytes)?;
+                    archive.prune(&policy)
+                })
+                .await?
+                .map_err(NockAppError::SaveError)?;
+            }
             let send = send_lock.lock().await;
             send.send(checkpoint.event_num)?;
-            drop(save_permit);
-            Ok::<(), NockAppError>(())
-        });
-        // We don't want to close and re-open the tasktracker from multiple places
-        // so we're just returning the join_handle to let the caller decide.
-        Ok(join_handle)
+            Ok(())
+        }
     }
 
     /// Except in tests, save should only be called by the permit handler.
@@ -321,6 +413,51 @@ impl NockApp {
         Ok(())
     }
 
+    /// Export the kernel state to a portable jam archive, see [`Kernel::export_state`]
+    pub async fn export_state(&self, path: &std::path::Path) -> Result<u64, NockAppError> {
+        Ok(self.kernel.export_state(path).await?)
+    }
+
+    /// Replace the kernel state with one exported from another node and checkpoint it
+    /// immediately, so a restart doesn't fall back to the previous state.
+    pub async fn import_state(&mut self, path: &std::path::Path) -> NockAppResult {
+        let state_bytes = fs::read(path).await.map_err(NockAppError::IoError)?;
+        self.kernel.import_state(state_bytes).await?;
+        self.save_locked().await
+    }
+
+    /// Swap in a new kernel without restarting, see [`Kernel::reload`].
+    ///
+    /// Waits for pokes in flight to finish and holds back new ones, checkpoints the
+    /// state, loads `kernel_jam` and checkpoints again so a restart resumes from the
+    /// state the new kernel produced. On failure the old kernel keeps running.
+    /// Drivers reload through [`NockAppHandle::reload_kernel`] instead.
+    pub async fn reload_kernel(
+        &mut self,
+        kernel_jam: Vec<u8>,
+    ) -> Result<KernelReload, NockAppError> {
+        self.reload_future(kernel_jam).await
+    }
+
+    fn reload_future(
+        &self,
+        kernel_jam: Vec<u8>,
+    ) -> impl Future<Output = Result<KernelReload, NockAppError>> + Send + 'static {
+        let poke_gate = self.poke_gate.clone();
+        let save_mutex = self.save_mutex.clone();
+        let save_before = self.save_future(async {});
+        let reload = self.kernel.reload(&kernel_jam);
+        let save_after = self.save_future(async {});
+        async move {
+            let _drained = poke_gate.write_owned().await;
+            let _save_permit = save_mutex.lock_owned().await;
+            save_before.await?;
+            let reload = reload.await?;
+            save_after.await?;
+            Ok(reload)
+        }
+    }
+
     /// Peek at a noun in the kernel, blocking operation
     #[tracing::instrument(skip(self, path))]
     pub fn peek_sync(&mut self, path: NounSlab) -> Result<NounSlab, NockAppError> {
@@ -533,12 +670,18 @@ impl NockApp {
     async fn handle_action(&self, action: IOAction) {
         // Stop processing events if we are exiting
         if self.exit_status.load(Ordering::SeqCst) {
-            if let IOAction::Poke { .. } = action {
-                self.metrics.poke_during_exit.increment();
-                debug!("Poked during exit. Ignoring.")
-            } else {
-                self.metrics.peek_during_exit.increment();
-                debug!("Peeked during exit. Ignoring.")
+            match action {
+                IOAction::Poke { .. } => {
+                    self.metrics.poke_during_exit.increment();
+                    debug!("Poked during exit. Ignoring.")
+                }
+                IOAction::Peek { .. } => {
+                    self.metrics.peek_during_exit.increment();
+                    debug!("Peeked during exit. Ignoring.")
+                }
+                IOAction::ReloadKernel { .. } => {
+                    debug!("Kernel reload requested during exit. Ignoring.")
+                }
             }
             return;
         }
@@ -552,6 +695,10 @@ impl NockApp {
                 path,
                 result_channel,
             } => self.handle_peek(path, result_channel).await,
+            IOAction::ReloadKernel {
+                kernel,
+                result_channel,
+            } => self.handle_reload(kernel, result_channel).await,
         }
     }
 
@@ -564,7 +711,9 @@ impl NockApp {
     ) {
         let poke_future = self.kernel.poke(wire, cause);
         let effect_broadcast = self.effect_broadcast.clone();
+        let poke_gate = self.poke_gate.clone();
         let _ = self.tasks.spawn(async move {
+            let _in_flight = poke_gate.read_owned().await;
             let poke_result = poke_future.await;
             match poke_result {
                 Ok(effects) => {
@@ -602,6 +751,22 @@ impl NockApp {
         });
     }
 
+    #[instrument(skip_all)]
+    async fn handle_reload(
+        &self,
+        kernel: Vec<u8>,
+        result_channel: tokio::sync::oneshot::Sender<Result<KernelReload, NockAppError>>,
+    ) {
+        let reload_future = self.reload_future(kernel);
+        let _ = self.tasks.spawn(async move {
+            let reload_res = reload_future.await;
+            if let Err(e) = &reload_res {
+                error!("Kernel reload failed, keeping the running kernel: {:?}", e);
+            }
+            let _ = result_channel.send(reload_res);
+        });
+    }
+
     async fn handle_signal(&mut self, code: usize) -> Result<NockAppRun, NockAppError> {
         self.kernel.serf.cancel_token.cancel();
         self.handle_exit(code).await
@@ -624,6 +789,9 @@ impl NockApp {
             }
         }
 
+        // Let drivers persist in-flight work before we take the final checkpoint
+        self.exit.request_shutdown();
+
         // Force an immediate save to ensure we have the latest state
         info!(
             "Exit signal received with code {}, forcing immediate save",
@@ -37,10 +37,13 @@ pub async fn setup_nockapp(jam: &str) -> (TempDir, NockApp) {
 #[cfg(test)]
 pub mod tests {
     use super::setup_nockapp;
+    use crate::kernel::checkpoint::JamPaths;
+    use crate::kernel::form::Kernel;
+    use crate::kernel::migration::{MigrationError, SNAPSHOT_VERSION};
     use crate::nockapp::wire::{SystemWire, Wire};
     use crate::noun::slab::{slab_equality, slab_noun_equality, NounSlab};
     use crate::utils::NOCK_STACK_SIZE;
-    use crate::{NockApp, NounExt};
+    use crate::{CrownError, NockApp, NounExt};
     use bytes::Bytes;
     use nockvm::mem::NockStack;
     use tracing::info;
@@ -51,6 +54,7 @@ pub mod tests {
     use nockvm::serialization::{cue, jam};
     use nockvm::unifying_equality::unifying_equality;
     use nockvm_macros::tas;
+    use std::fs;
     use std::sync::atomic::Ordering;
     use std::time::Duration;
 
@@ -437,6 +441,155 @@ pub mod tests {
         assert!(chk.event_num == valid.event_num);
     }
 
+    // Checkpoints this build cannot migrate are refused instead of booting fresh over them
+    #[tokio::test]
+    #[traced_test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_nockapp_refuses_unmigratable_checkpoint() {
+        let (temp, mut nockapp) = setup_nockapp("test-ker.jam").await;
+        let jam_paths = nockapp.kernel.serf.jam_paths.clone();
+        save_nockapp(&mut nockapp).await;
+        let mut newer = nockapp
+            .kernel
+            .checkpoint()
+            .await
+            .expect("Could not get kernel checkpoint");
+        drop(nockapp);
+
+        let jam = include_bytes!("../../test-jams/test-ker.jam");
+        let boot = || {
+            Kernel::load(
+                temp.path().to_path_buf(),
+                JamPaths::new(temp.path()),
+                jam,
+                false,
+            )
+        };
+        // The checkpoint written at the current version boots
+        boot().await.expect("Could not boot from checkpoint");
+
+        // One written by a newer build does not
+        newer.version = SNAPSHOT_VERSION + 1;
+        assert!(newer.validate());
+        fs::write(
+            &jam_paths.0,
+            newer.encode().expect("Could not encode checkpoint"),
+        )
+        .expect("Could not write checkpoint");
+        match boot().await {
+            Err(CrownError::CheckpointRefused {
+                source: MigrationError::Newer { found, .. },
+                ..
+            }) => assert_eq!(found, SNAPSHOT_VERSION + 1),
+            Err(e) => panic!("Unexpected error: {e}"),
+            Ok(_) => panic!("Booted from a checkpoint of a newer build"),
+        }
+
+        // Checkpoints that cannot be read at all hold nothing to refuse, so
+        // the kernel starts fresh as it always has
+        fs::write(&jam_paths.0, b"not a checkpoint").expect("Could not write checkpoint");
+        fs::write(&jam_paths.1, b"not a checkpoint").expect("Could not write checkpoint");
+        let fresh = boot().await.expect("Could not boot over unreadable checkpoints");
+        assert_eq!(fresh.serf.event_number.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_nockapp_reload_kernel() {
+        let (_temp, mut nockapp) = setup_nockapp("test-ker.jam").await;
+        let poke = || {
+            let mut slab = NounSlab::new();
+            slab.copy_into(D(tas!(b"inc")));
+            slab
+        };
+        nockapp
+            .poke(SystemWire.to_wire(), poke())
+            .await
+            .expect("Poke failed");
+        let state_before = nockapp
+            .kernel
+            .serf
+            .get_kernel_state_slab()
+            .await
+            .expect("Failed to get kernel state slab");
+
+        // Reloading the same kernel carries the state over untouched
+        let jam = include_bytes!("../../test-jams/test-ker.jam").to_vec();
+        let reload = nockapp.reload_kernel(jam).await.expect("Reload failed");
+        assert_eq!(reload.old_hash, reload.new_hash);
+        assert_eq!(reload.event_num, 1);
+        let state_after = nockapp
+            .kernel
+            .serf
+            .get_kernel_state_slab()
+            .await
+            .expect("Failed to get kernel state slab");
+        assert!(slab_equality(&state_before, &state_after));
+
+        // and leaves a checkpoint of it behind
+        let mut checkpoint_stack = NockStack::new(NOCK_STACK_SIZE, 0);
+        let checkpoint = nockapp
+            .kernel
+            .serf
+            .jam_paths
+            .load_checkpoint(&mut checkpoint_stack)
+            .expect("No checkpoint after reload");
+        assert_eq!(checkpoint.event_num, 1);
+        assert_eq!(checkpoint.ker_hash, reload.new_hash);
+
+        // A kernel that does not boot leaves the running one in place
+        assert!(nockapp
+            .reload_kernel(b"not a kernel".to_vec())
+            .await
+            .is_err());
+        nockapp
+            .poke(SystemWire.to_wire(), poke())
+            .await
+            .expect("Poke after failed reload failed");
+        assert_eq!(nockapp.kernel.serf.event_number.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_kernel_warm_up_leaves_state() {
+        let (_temp, nockapp) = setup_nockapp("test-ker.jam").await;
+        let inc = || {
+            let mut slab = NounSlab::new();
+            slab.copy_into(D(tas!(b"inc")));
+            slab
+        };
+        let state_before = nockapp
+            .kernel
+            .serf
+            .get_kernel_state_slab()
+            .await
+            .expect("Failed to get kernel state slab");
+
+        nockapp
+            .kernel
+            .warm_up(SystemWire.to_wire(), inc())
+            .await
+            .expect("Warm-up failed");
+        let state_after = nockapp
+            .kernel
+            .serf
+            .get_kernel_state_slab()
+            .await
+            .expect("Failed to get kernel state slab");
+        assert!(slab_equality(&state_before, &state_after));
+        assert_eq!(nockapp.kernel.serf.event_number.load(Ordering::SeqCst), 0);
+
+        // the kernel pokes as usual afterwards
+        nockapp
+            .kernel
+            .poke(SystemWire.to_wire(), inc())
+            .await
+            .expect("Poke after warm-up failed");
+        assert_eq!(nockapp.kernel.serf.event_number.load(Ordering::SeqCst), 1);
+    }
+
     #[tokio::test]
     #[cfg_attr(miri, ignore)]
     async fn test_jam_equality_stack() {
@@ -1,5 +1,8 @@
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
 mod extensions;
 mod ops;
+pub mod pool;
 pub mod slab;
 pub use extensions::*;
 pub use ops::*;
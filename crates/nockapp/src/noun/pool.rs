@@ -0,0 +1,76 @@
+//! Reuse of [`NounSlab`] allocations.
+//!
+//! Hot loops that build a fresh slab per iteration spend much of their time in
+//! the allocator. A [`SlabPool`] hands out slabs that have been
+//! [reset](NounSlab::reset), so their largest allocation is reused.
+
+use std::sync::Mutex;
+
+use crate::noun::slab::NounSlab;
+
+/// A bounded pool of empty slabs
+#[derive(Debug)]
+pub struct SlabPool {
+    free: Mutex<Vec<NounSlab>>,
+    capacity: usize,
+}
+
+impl SlabPool {
+    /// A pool holding at most `capacity` idle slabs
+    pub fn new(capacity: usize) -> Self {
+        SlabPool {
+            free: Mutex::new(Vec::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// An empty slab, reused from the pool if one is idle
+    pub fn take(&self) -> NounSlab {
+        self.lock().pop().unwrap_or_default()
+    }
+
+    /// Reset `slab` and keep it for a later [`take`](Self::take), or drop it
+    /// if the pool is full
+    pub fn give(&self, mut slab: NounSlab) {
+        let mut free = self.lock();
+        if free.len() < self.capacity {
+            slab.reset();
+            free.push(slab);
+        }
+    }
+
+    /// Number of idle slabs
+    pub fn idle(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<NounSlab>> {
+        self.free
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nockvm::noun::{D, T};
+
+    #[test]
+    fn test_slab_pool_reuses_slabs() {
+        let pool = SlabPool::new(1);
+        let mut slab = pool.take();
+        let noun = T(&mut slab, &[D(1), D(2), D(3)]);
+        slab.set_root(noun);
+        let allocated = slab.allocated_bytes();
+
+        pool.give(slab);
+        pool.give(NounSlab::new());
+        assert_eq!(pool.idle(), 1);
+
+        let reused = pool.take();
+        assert_eq!(reused.allocated_bytes(), allocated);
+        assert!(unsafe { reused.root().raw_equals(&D(0)) });
+        assert_eq!(pool.idle(), 0);
+    }
+}
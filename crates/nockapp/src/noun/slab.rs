@@ -329,6 +329,34 @@ impl NounSlab {
         res
     }
 
+    /// Total bytes allocated for this slab's nouns
+    pub fn allocated_bytes(&self) -> usize {
+        self.slabs
+            .iter()
+            .filter(|(ptr, _)| !ptr.is_null())
+            .map(|(_, layout)| layout.size())
+            .sum()
+    }
+
+    /// Drop every noun in the slab, keeping its largest allocation for reuse.
+    ///
+    /// Any noun previously taken from this slab is invalid afterwards.
+    pub fn reset(&mut self) {
+        self.root = D(0);
+        let Some(largest) = self.slabs.iter().rposition(|(ptr, _)| !ptr.is_null()) else {
+            return;
+        };
+        for (idx, slab) in self.slabs.iter_mut().enumerate() {
+            if idx != largest && !slab.0.is_null() {
+                unsafe { std::alloc::dealloc(slab.0, slab.1) };
+                *slab = (std::ptr::null_mut(), Layout::new::<u8>());
+            }
+        }
+        let (ptr, layout) = self.slabs[largest];
+        self.allocation_start = ptr as *mut u64;
+        self.allocation_stop = unsafe { self.allocation_start.add(layout.size() >> 3) };
+    }
+
     /// Set the root of the noun slab.
     ///
     /// Panics if the given root is not in the noun slab or PMA.
@@ -961,6 +989,34 @@ mod tests {
         copy_slab.copy_into(test_noun);
     }
 
+    #[test]
+    fn test_noun_slab_reset_reuses_largest_allocation() {
+        let mut slab = NounSlab::new();
+        let mut list = D(0);
+        for i in 0..10_000 {
+            list = T(&mut slab, &[D(i), list]);
+        }
+        slab.set_root(list);
+        let largest = slab
+            .slabs
+            .iter()
+            .map(|(_, layout)| layout.size())
+            .max()
+            .unwrap();
+
+        slab.reset();
+        assert_eq!(slab.allocated_bytes(), largest);
+        assert!(unsafe { slab.root().raw_equals(&D(0)) });
+
+        let test_noun = T(&mut slab, &[D(5), D(23)]);
+        slab.set_root(test_noun);
+        assert_eq!(slab.allocated_bytes(), largest);
+        let mut expected = NounSlab::new();
+        let expected_noun = T(&mut expected, &[D(5), D(23)]);
+        expected.set_root(expected_noun);
+        assert!(slab_equality(&slab, &expected));
+    }
+
     // Fails in Miri
     // #[test]
     // fn test_alloc_cell_for_noun_slab_uninit() {
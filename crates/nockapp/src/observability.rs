@@ -1,7 +1,4 @@
 pub fn init_tracing() -> Result<impl tracing::Subscriber, opentelemetry::trace::TraceError> {
-    use opentelemetry::trace::TracerProvider;
-    use opentelemetry_otlp::WithExportConfig;
-    use opentelemetry_sdk::trace::Sampler;
     use tracing_subscriber::layer::SubscriberExt;
 
     // Datadog agent OTLP endpoint configuration
@@ -23,20 +20,48 @@ pub fn init_tracing() -> Result<impl tracing::Subscriber, opentelemetry::trace::
         opentelemetry::KeyValue::new("deployment.environment", environment),
     ]);
 
-    // Create OTLP exporter configured for Datadog
+    let use_ansi = std::env::var("DD_ENV").is_err();
+    let telemetry = otlp_layer(&endpoint, &service_name, resource)?;
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_target(true)
+        .with_thread_ids(true)
+        .with_line_number(true)
+        // .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .with_ansi(use_ansi);
+
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(tracing_subscriber::EnvFilter::from_default_env())
+        .with(fmt_layer)
+        .with(telemetry);
+    Ok(subscriber)
+}
+
+/// A layer exporting spans over OTLP gRPC to `endpoint`, as `service_name`
+/// described by `resource`. `OTEL_TRACES_SAMPLE_RATE` sets the share of
+/// traces sampled, all of them by default. Must be called within a Tokio
+/// runtime, which batches and sends the spans.
+pub fn otlp_layer<S>(
+    endpoint: &str,
+    service_name: &str,
+    resource: opentelemetry_sdk::Resource,
+) -> Result<
+    tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>,
+    opentelemetry::trace::TraceError,
+>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::trace::Sampler;
+
+    // Create OTLP exporter, e.g. for the Datadog agent
     let otlp_exporter = opentelemetry_otlp::SpanExporter::builder()
         .with_tonic() // use gRPC
         .with_endpoint(endpoint)
         .with_timeout(std::time::Duration::from_secs(30))
         .build()
-        .unwrap_or_else(|_| {
-            panic!(
-                "Panicked at {}:{} (git sha: {:?})",
-                file!(),
-                line!(),
-                option_env!("GIT_SHA")
-            )
-        });
+        .map_err(|e| opentelemetry::trace::TraceError::Other(Box::new(e)))?;
 
     // Configure sampling rate - 0.1 means sample ~10% of traces
     let sampling_ratio = std::env::var("OTEL_TRACES_SAMPLE_RATE")
@@ -51,21 +76,8 @@ pub fn init_tracing() -> Result<impl tracing::Subscriber, opentelemetry::trace::
         .with_sampler(Sampler::TraceIdRatioBased(sampling_ratio))
         .build();
 
-    let use_ansi = std::env::var("DD_ENV").is_err();
-    let tracer = provider.tracer(service_name);
-    let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
-    let fmt_layer = tracing_subscriber::fmt::layer()
-        .with_target(true)
-        .with_thread_ids(true)
-        .with_line_number(true)
-        // .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
-        .with_ansi(use_ansi);
-
-    let subscriber = tracing_subscriber::Registry::default()
-        .with(tracing_subscriber::EnvFilter::from_default_env())
-        .with(fmt_layer)
-        .with(telemetry);
-    Ok(subscriber)
+    let tracer = provider.tracer(service_name.to_owned());
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
 }
 
 // pub fn init_tracing() -> Result<impl tracing::Subscriber, opentelemetry::trace::TraceError> {
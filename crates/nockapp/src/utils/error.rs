@@ -63,6 +63,13 @@ pub enum CrownError<T = ExternalError> {
     EncodeError(#[from] bincode::error::EncodeError),
     #[error("state jam format error: the state jam file format is not recognized")]
     StateJamFormatError,
+    #[error("{0}")]
+    Migration(#[from] crate::kernel::migration::MigrationError),
+    #[error("refusing to boot from checkpoints {paths}: {source}; move them aside to start from fresh state")]
+    CheckpointRefused {
+        paths: String,
+        source: crate::kernel::migration::MigrationError,
+    },
     #[error("unknown error: {0}")]
     Unknown(String),
     #[error("conversion error: {0}")]
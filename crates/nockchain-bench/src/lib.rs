@@ -0,0 +1,407 @@
+//! Proving and verification benchmarks for nockchain.
+//!
+//! A [`Suite`] names a set of prove inputs. [`BenchRunner`] proves each of
+//! them with a [`ProvingBackend`], optionally checks the proof with a
+//! [`VerificationService`], and turns every proof into a [`BenchRecord`].
+//! The `nockchain-bench` binary runs suites from the command line and
+//! writes the records as JSON, see [`report`], and [`render`] turns a
+//! directory of results into a report to share. A [`Matrix`] sweeps
+//! lengths, nonce counts and thread counts for a side-by-side comparison.
+//! Every run also records its peak memory, see [`memory`].
+
+pub mod matrix;
+pub mod memory;
+pub mod render;
+pub mod report;
+pub mod suite;
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use futures::stream::{self, StreamExt};
+pub use matrix::{Matrix, MatrixCell, MatrixRun};
+use memory::{RssSampler, DEFAULT_SAMPLE_INTERVAL};
+use nockapp::kernel::form::Entropy;
+use nockapp::CrownError;
+use nockchain::backend::{Prover, ProvingBackend, INSECURE_DEV_PROVING};
+use nockchain::effect::{MiningEffect, MiningEffectError};
+use nockchain::mining::{mined_commands, proof_fingerprint};
+use nockchain::prove_input::{ProveBlockInput, ProveInputError};
+use nockchain::regression::current_git_branch;
+use nockchain::stack::StackSize;
+use nockchain::trend::TrendError;
+use nockchain::verifier::{VerificationService, VerifyError};
+pub use report::{summarize, BenchRecord, InputRecord, InputSummary, MachineInfo};
+pub use suite::Suite;
+use thiserror::Error;
+use tracing::info;
+
+#[derive(Debug, Error)]
+pub enum BenchError {
+    #[error("bad prove input: {0}")]
+    Input(#[from] ProveInputError),
+    #[error("prove failed: {0}")]
+    Prove(#[from] CrownError),
+    #[error("prover produced no %pow effect")]
+    NoProof,
+    #[error("prover produced a bad %pow effect: {0}")]
+    Effect(#[from] MiningEffectError),
+    #[error("verification failed: {0}")]
+    Verify(#[from] VerifyError),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("bad benchmark record: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("trend store: {0}")]
+    Trend(#[from] TrendError),
+}
+
+/// Proves suites and records how long it took
+pub struct BenchRunner {
+    backend: Arc<dyn ProvingBackend>,
+    verifier: Option<Arc<VerificationService>>,
+    stack_size: StackSize,
+    entropy: Entropy,
+    keep_proofs: bool,
+    warm_up: bool,
+    git_branch: Option<String>,
+    machine: MachineInfo,
+}
+
+impl BenchRunner {
+    /// Prove with `backend` using fixed entropy, so reruns of the same
+    /// input produce the same proof
+    pub fn new(backend: Arc<dyn ProvingBackend>) -> Self {
+        Self {
+            backend,
+            verifier: None,
+            stack_size: StackSize::Auto,
+            entropy: Entropy::Fixed(0),
+            keep_proofs: false,
+            warm_up: false,
+            git_branch: current_git_branch(),
+            machine: MachineInfo::detect(),
+        }
+    }
+
+    /// Also verify every proof, timing it separately
+    pub fn with_verifier(mut self, verifier: Arc<VerificationService>) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    pub fn with_stack_size(mut self, stack_size: StackSize) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    pub fn with_entropy(mut self, entropy: Entropy) -> Self {
+        self.entropy = entropy;
+        self
+    }
+
+    /// Keep the jammed proof in each record, for the proof archive
+    pub fn keep_proofs(mut self, keep: bool) -> Self {
+        self.keep_proofs = keep;
+        self
+    }
+
+    /// Warm every kernel up before proving, see [`Prover::warm_up`], so
+    /// the prove time leaves out first-poke costs. The warm-up is timed on
+    /// its own and counts towards the total duration.
+    pub fn with_warm_up(mut self, warm_up: bool) -> Self {
+        self.warm_up = warm_up;
+        self
+    }
+
+    pub fn machine(&self) -> &MachineInfo {
+        &self.machine
+    }
+
+    /// Prove `input` once, as part of the suite named `test_name`
+    pub async fn run_input(
+        &self,
+        test_name: &str,
+        input: &ProveBlockInput,
+    ) -> Result<BenchRecord, BenchError> {
+        let sampler = RssSampler::start(DEFAULT_SAMPLE_INTERVAL);
+        let started = Instant::now();
+        let prover = self
+            .backend
+            .load(self.stack_size.words_for(input.length()))
+            .await?;
+        let load_secs = started.elapsed().as_secs_f64();
+        let warm_up_secs = if self.warm_up {
+            Some(prover.warm_up().await?.as_secs_f64())
+        } else {
+            None
+        };
+        let proving = Instant::now();
+        let effects = prover.prove(input.to_noun_slab(), self.entropy).await?;
+        let prove_secs = proving.elapsed().as_secs_f64();
+        let duration_secs = started.elapsed().as_secs_f64();
+        let peak_rss_bytes = sampler.stop();
+        let stack_high_water_bytes = prover
+            .memory_stats()
+            .map(|stats| stats.stack_high_water_bytes);
+
+        let extracting = Instant::now();
+        let effect = mined_commands(&effects)
+            .into_iter()
+            .next()
+            .ok_or(BenchError::NoProof)?;
+        let effect = MiningEffect::try_from(effect)?;
+        effect.verify(input)?;
+        let proof_hash = proof_fingerprint(effect.slab()).to_hex().to_string();
+        let extract_secs = extracting.elapsed().as_secs_f64();
+
+        let serializing = Instant::now();
+        let proof = effect.proof_slab();
+        let jam = proof.jam();
+        let serialize_secs = serializing.elapsed().as_secs_f64();
+
+        let (verify_secs, valid) = match &self.verifier {
+            Some(verifier) => {
+                let verdict = verifier.verify(proof, None).await?;
+                (Some(verdict.verified_in.as_secs_f64()), Some(verdict.valid))
+            }
+            None => (None, None),
+        };
+        let proof_data = if self.keep_proofs {
+            jam.to_vec()
+        } else {
+            Vec::new()
+        };
+        Ok(BenchRecord {
+            test_name: test_name.to_string(),
+            input: input.into(),
+            duration_secs,
+            load_secs,
+            warm_up_secs,
+            prove_secs,
+            extract_secs,
+            serialize_secs,
+            verify_secs,
+            valid,
+            peak_rss_bytes,
+            stack_high_water_bytes,
+            proof_hash,
+            proof_data,
+            recorded_at: report::unix_now(),
+            git_branch: self.git_branch.clone(),
+            dev_proving: INSECURE_DEV_PROVING,
+            machine: self.machine.clone(),
+        })
+    }
+
+    /// Prove every input of `suite` `repeats` times, handing each record to
+    /// `on_record` with its repeat number as soon as it is done
+    pub async fn run_suite(
+        &self,
+        suite: &Suite,
+        repeats: usize,
+        mut on_record: impl FnMut(&BenchRecord, usize) -> Result<(), BenchError>,
+    ) -> Result<Vec<BenchRecord>, BenchError> {
+        let mut records = Vec::new();
+        for input in suite.inputs()? {
+            for repeat in 0..repeats {
+                info!(
+                    "{}: proving length {} ({}/{repeats})",
+                    suite.name,
+                    input.length(),
+                    repeat + 1
+                );
+                let record = self.run_input(&suite.name, &input).await?;
+                on_record(&record, repeat)?;
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Prove every cell of `matrix` over the commitment and nonce of
+    /// `suite`, handing each record to `on_record` with its nonce index as
+    /// soon as it is done. Records are named after their cell, see
+    /// [`matrix::cell_test_name`].
+    pub async fn run_matrix(
+        &self,
+        suite: &Suite,
+        matrix: &Matrix,
+        mut on_record: impl FnMut(&BenchRecord, usize) -> Result<(), BenchError>,
+    ) -> Result<MatrixRun, BenchError> {
+        let mut cells = Vec::new();
+        let mut too_slow = None;
+        for (length, nonces, threads) in matrix.cells() {
+            if too_slow.is_some_and(|slow| length > slow) {
+                info!("{}: skipping length {length} and up", suite.name);
+                break;
+            }
+            info!(
+                "{}: proving {nonces} inputs of length {length} on {threads} threads",
+                suite.name
+            );
+            let test_name = matrix::cell_test_name(&suite.name, nonces, threads);
+            let inputs = suite.inputs_with_nonces(length, nonces)?;
+            let started = Instant::now();
+            // buffered keeps the proofs in input order while up to `threads` run
+            let mut running = stream::iter(&inputs)
+                .map(|input| self.run_input(&test_name, input))
+                .buffered(threads);
+            let mut records = Vec::with_capacity(nonces);
+            while let Some(record) = running.next().await {
+                let record = record?;
+                on_record(&record, records.len())?;
+                records.push(record);
+            }
+            let wall_secs = started.elapsed().as_secs_f64();
+            let Some(cell) = MatrixCell::new(threads, wall_secs, &records) else {
+                continue;
+            };
+            if matrix
+                .give_up_after
+                .is_some_and(|limit| cell.prove.median > limit.as_secs_f64())
+            {
+                too_slow = Some(length);
+            }
+            cells.push(cell);
+        }
+        Ok(MatrixRun {
+            suite: suite.name.clone(),
+            cells,
+            recorded_at: report::unix_now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::future::BoxFuture;
+    use ibig::UBig;
+    use nockapp::noun::slab::NounSlab;
+    use nockchain::backend::SimulatedBackend;
+    use nockchain::verifier::{ProofCheck, Verifier, VerifierBackend, VerifierConfig};
+
+    use super::*;
+
+    /// Finds every proof valid
+    struct AcceptAll;
+
+    impl VerifierBackend for AcceptAll {
+        fn load(
+            &self,
+            _stack_words: usize,
+        ) -> BoxFuture<'_, Result<Box<dyn Verifier>, CrownError>> {
+            Box::pin(async { Ok(Box::new(AcceptAll) as Box<dyn Verifier>) })
+        }
+    }
+
+    impl Verifier for AcceptAll {
+        fn verify(
+            &self,
+            _proof: NounSlab,
+            _entropy: Entropy,
+        ) -> BoxFuture<'static, Result<ProofCheck, VerifyError>> {
+            Box::pin(async { Ok(ProofCheck::valid(UBig::from(42u8))) })
+        }
+
+        fn cancel(&self) -> bool {
+            false
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_suite_writes_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let runner = BenchRunner::new(Arc::new(SimulatedBackend::new(Duration::ZERO)))
+            .with_verifier(Arc::new(VerificationService::new(
+                Arc::new(AcceptAll),
+                VerifierConfig::default(),
+            )))
+            .keep_proofs(true);
+        let suite = Suite::preset("progressive")
+            .unwrap()
+            .with_lengths(vec![2, 4]);
+        let mut paths = Vec::new();
+        let records = runner
+            .run_suite(&suite, 2, |record, repeat| {
+                paths.push(record.write(dir.path(), repeat)?);
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(records.len(), 4);
+        assert_eq!(paths.len(), 4);
+        // the simulated prover runs no kernel
+        assert!(records.iter().all(|r| r.stack_high_water_bytes.is_none()));
+        for (record, path) in records.iter().zip(&paths) {
+            assert_eq!(&BenchRecord::read(path).unwrap(), record);
+            assert_eq!(record.valid, Some(true));
+            assert!(!record.proof_data.is_empty());
+        }
+        assert_eq!(records[0].input.length, 2);
+        assert_eq!(records[2].input.length, 4);
+        // fixed entropy, so repeats prove the same thing
+        assert_eq!(records[0].proof_hash, records[1].proof_hash);
+        assert_ne!(records[0].proof_hash, records[2].proof_hash);
+
+        let summaries = summarize(&records);
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[1].input.length, 4);
+        assert_eq!(summaries[1].prove.runs, 2);
+        assert_eq!(summaries[1].verify.as_ref().map(|v| v.runs), Some(2));
+        assert!(summaries.iter().all(|summary| summary.proofs_match));
+    }
+
+    #[tokio::test]
+    async fn test_run_matrix_proves_every_cell() {
+        let runner = BenchRunner::new(Arc::new(SimulatedBackend::new(Duration::ZERO)));
+        let suite = Suite::preset("progressive").unwrap();
+        let matrix = Matrix::new(vec![2, 4], vec![1, 3], vec![1, 2]);
+        let mut records = Vec::new();
+        let run = runner
+            .run_matrix(&suite, &matrix, |record, nonce| {
+                records.push((record.clone(), nonce));
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(run.cells.len(), matrix.len());
+        assert_eq!(records.len(), (1 + 3) * 2 * 2);
+        for (cell, (length, nonces, threads)) in run.cells.iter().zip(matrix.cells()) {
+            assert_eq!(
+                (cell.length, cell.nonces, cell.threads),
+                (length, nonces, threads)
+            );
+            assert_eq!(cell.prove.runs, nonces);
+        }
+        // the same input proves the same whatever the thread count
+        let proofs = |test_name: &str| -> Vec<String> {
+            records
+                .iter()
+                .filter(|(record, _)| record.test_name == test_name && record.input.length == 4)
+                .map(|(record, _)| record.proof_hash.clone())
+                .collect()
+        };
+        assert_eq!(proofs("progressive-n3-t1"), proofs("progressive-n3-t2"));
+        assert_eq!(proofs("progressive-n3-t1").len(), 3);
+        assert_eq!(
+            records
+                .iter()
+                .filter(|(record, _)| record.test_name == "progressive-n3-t2")
+                .map(|(_, nonce)| *nonce)
+                .collect::<Vec<_>>(),
+            [0, 1, 2, 0, 1, 2]
+        );
+
+        let give_up = matrix.give_up_after(Duration::ZERO);
+        let run = runner
+            .run_matrix(&suite, &give_up, |_, _| Ok(()))
+            .await
+            .unwrap();
+        assert!(run.cells.iter().all(|cell| cell.length == 2));
+    }
+}
@@ -0,0 +1,407 @@
+//! Run proving benchmarks and write their results.
+//!
+//! Each proof is written to `--out` as a JSON [`BenchRecord`] and added to
+//! the trend store there, and a summary line with its trend is printed to
+//! stdout. Logs go to stderr. `nockchain-bench report --to <file>` renders
+//! what is in `--out` as a markdown or HTML report instead, and
+//! `nockchain-bench matrix` sweeps nonce and thread counts over the suites'
+//! lengths and prints a comparison table.
+//!
+//! `nockchain-bench save-baseline <name>` freezes the current trends as a
+//! named baseline, and `--baseline <name>` judges a run against one. The
+//! exit status then tells how the run did: 0 when every proof passed,
+//! [`EXIT_WARN`] when one came within the warning band or changed its
+//! proof, and [`EXIT_FAIL`] when one was slower than `--tolerance` allows.
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::{value_parser, Parser, Subcommand};
+use nockapp::kernel::form::Entropy;
+use nockchain::backend::{
+    KernelBackend, KernelJam, ProvingBackend, SimulatedBackend, INSECURE_DEV_PROVING,
+};
+use nockchain::regression::current_git_branch;
+use nockchain::stack::StackSize;
+use nockchain::trend::{NamedBaseline, Outcome, Tolerance, TrendConfig, TrendStore};
+use nockchain::verifier::{KernelVerifierBackend, VerificationService, VerifierConfig};
+use nockchain_bench::matrix::comparison_table;
+use nockchain_bench::memory::format_bytes;
+use nockchain_bench::render::Report;
+use nockchain_bench::suite::PRESETS;
+use nockchain_bench::{
+    summarize, BenchError, BenchRecord, BenchRunner, InputSummary, Matrix, Suite,
+};
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
+
+/// Exit status of a run with a proof in the warning band of its baseline
+const EXIT_WARN: u8 = 3;
+/// Exit status of a run with a proof slower than its baseline allows
+const EXIT_FAIL: u8 = 4;
+
+#[derive(Parser, Debug)]
+#[command(name = "nockchain-bench")]
+struct BenchCli {
+    #[command(subcommand)]
+    command: Option<BenchCommand>,
+    #[arg(
+        long,
+        help = "Comma separated suites to run",
+        long_help = format!("Comma separated suites to run, of: {}", PRESETS.join(", ")),
+        value_delimiter = ',',
+        default_value = "minimal"
+    )]
+    suite: Vec<Suite>,
+    #[arg(
+        long,
+        help = "Comma separated proof lengths, replacing those of every suite",
+        value_delimiter = ','
+    )]
+    lengths: Option<Vec<u64>>,
+    #[arg(long, help = "Times to prove each input", default_value = "1")]
+    repeats: usize,
+    #[arg(long, help = "Also verify every proof with the verifier kernel")]
+    verify: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Directory to write results to",
+        default_value = "benchmark_results"
+    )]
+    out: PathBuf,
+    #[arg(
+        long,
+        help = "Keep each jammed proof in its result, for the proof archive"
+    )]
+    keep_proofs: bool,
+    #[arg(
+        long,
+        help = "Warm each kernel up with a throwaway poke before proving, timed apart from the prove"
+    )]
+    warm_up: bool,
+    #[arg(
+        long,
+        help = "Prove with the kernel jam at this path instead of the one built in"
+    )]
+    kernel_path: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "NockStack size per kernel, 'auto' to size it from the proof length, or a size such as 16GB",
+        value_parser = value_parser!(StackSize),
+        default_value = "auto"
+    )]
+    stack_size: StackSize,
+    #[arg(
+        long,
+        help = "Entropy to prove with, fixed so reruns produce the same proofs",
+        default_value = "0"
+    )]
+    entropy_seed: u64,
+    #[arg(
+        long,
+        value_name = "MILLIS",
+        help = "Fake every proof after this many milliseconds instead of proving, to check a setup",
+        conflicts_with = "verify"
+    )]
+    dry_run: Option<u64>,
+    #[arg(
+        long,
+        global = true,
+        help = "Earlier runs the rolling baseline is the median of",
+        default_value = "5"
+    )]
+    baseline_window: usize,
+    #[arg(
+        long,
+        global = true,
+        value_name = "PERCENT",
+        help = "Slowdown past the rolling baseline that counts as a regression",
+        default_value = "10"
+    )]
+    regression_threshold: f64,
+    #[arg(long, help = "Exit with an error if any proof regressed")]
+    fail_on_regression: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Also write a report of all results after the run, see the report command"
+    )]
+    report: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "NAME|FILE",
+        help = "Judge every proof against a named baseline, see save-baseline, or a baseline JSON file"
+    )]
+    baseline: Option<String>,
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Slowdown past --baseline that fails the run",
+        default_value = "5"
+    )]
+    tolerance: f64,
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Slowdown past --baseline that warns, half of --tolerance by default"
+    )]
+    warn_tolerance: Option<f64>,
+}
+
+#[derive(Subcommand, Debug)]
+enum BenchCommand {
+    /// Render the results in --out as a report, without proving anything
+    Report {
+        #[arg(long, help = "File to write, HTML for .html, markdown otherwise")]
+        to: PathBuf,
+    },
+    /// Prove every combination of length, nonce count and thread count
+    /// over each suite's inputs and print a comparison table
+    Matrix {
+        #[arg(
+            long,
+            help = "Comma separated numbers of inputs to prove per length, each with its own nonce",
+            value_delimiter = ',',
+            default_value = "1"
+        )]
+        nonces: Vec<usize>,
+        #[arg(
+            long,
+            help = "Comma separated numbers of kernels proving at once",
+            value_delimiter = ',',
+            default_value = "1"
+        )]
+        threads: Vec<usize>,
+        #[arg(
+            long,
+            value_name = "SECS",
+            help = "Skip longer lengths once a median proof takes longer than this"
+        )]
+        give_up_after: Option<u64>,
+    },
+    /// Freeze the rolling baseline of every series in --out under a name,
+    /// to judge later runs against with --baseline
+    SaveBaseline { name: String },
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode, Box<dyn Error>> {
+    nockvm::check_endian();
+    let cli = BenchCli::parse();
+    // stdout carries the summary, keep logs off it
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(EnvFilter::from_default_env())
+        .init();
+
+    let trend_config = TrendConfig {
+        window: cli.baseline_window,
+        threshold: cli.regression_threshold / 100.0,
+    };
+    if let Some(BenchCommand::Report { to }) = &cli.command {
+        let report = Report::load(&cli.out, trend_config)?;
+        report.write(to)?;
+        println!(
+            "wrote {} ({} regressions)",
+            to.display(),
+            report.regressions()
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+    if let Some(BenchCommand::SaveBaseline { name }) = &cli.command {
+        let baseline = TrendStore::open(&cli.out)?
+            .with_config(trend_config)
+            .snapshot(name, current_git_branch());
+        let path = baseline.save(&cli.out)?;
+        println!(
+            "saved {} series as baseline {name} to {}",
+            baseline.entries.len(),
+            path.display()
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+    let baseline = cli
+        .baseline
+        .as_deref()
+        .map(|name| NamedBaseline::load(&cli.out, name))
+        .transpose()?;
+    let mut tolerance = Tolerance::percent(cli.tolerance);
+    if let Some(percent) = cli.warn_tolerance {
+        tolerance.warn = percent / 100.0;
+    }
+
+    let backend: Arc<dyn ProvingBackend> = match cli.dry_run {
+        Some(millis) => Arc::new(SimulatedBackend::new(Duration::from_millis(millis))),
+        None => {
+            let kernel = match &cli.kernel_path {
+                Some(path) => KernelJam::load(path)?,
+                None => KernelJam::embedded(),
+            };
+            info!("benchmarking {kernel:?}");
+            Arc::new(KernelBackend::new(kernel))
+        }
+    };
+    if INSECURE_DEV_PROVING && cli.dry_run.is_none() {
+        warn!("benchmarking the INSECURE dev-proving kernel, results are not comparable to release builds");
+    }
+    let mut runner = BenchRunner::new(backend)
+        .with_stack_size(cli.stack_size)
+        .with_entropy(Entropy::Fixed(cli.entropy_seed))
+        .keep_proofs(cli.keep_proofs)
+        .with_warm_up(cli.warm_up);
+    if cli.verify {
+        let config = VerifierConfig {
+            max_concurrent: 1,
+            stack_size: cli.stack_size,
+            // a length 64 proof can take a long while, only stop a stuck one
+            default_deadline: Duration::from_secs(24 * 60 * 60),
+            entropy: Entropy::Fixed(cli.entropy_seed),
+            ..Default::default()
+        };
+        runner = runner.with_verifier(Arc::new(VerificationService::new(
+            Arc::new(KernelVerifierBackend),
+            config,
+        )));
+    }
+    info!("benchmarking on {}", runner.machine().id());
+
+    let mut trends = TrendStore::open(&cli.out)?.with_config(trend_config);
+    let mut regressions = 0;
+    let mut outcome = Outcome::Pass;
+    let mut record_trend = |record: &BenchRecord| -> Result<(), BenchError> {
+        // fake proofs say nothing about performance
+        if cli.dry_run.is_none() {
+            let check = trends.record(record.series_key(), record.sample());
+            trends.save()?;
+            if check.is_regression() {
+                regressions += 1;
+            }
+            println!("  {check}");
+            if let Some(baseline) = &baseline {
+                let check = baseline.compare(&record.series_key(), &record.sample(), tolerance);
+                outcome = outcome.max(check.outcome);
+                println!("  {check}");
+            }
+        }
+        Ok(())
+    };
+    for suite in cli.suite {
+        let suite = match &cli.lengths {
+            Some(lengths) => suite.with_lengths(lengths.clone()),
+            None => suite,
+        };
+        if let Some(BenchCommand::Matrix {
+            nonces,
+            threads,
+            give_up_after,
+        }) = &cli.command
+        {
+            let mut matrix = Matrix::new(suite.lengths.clone(), nonces.clone(), threads.clone());
+            if let Some(secs) = give_up_after {
+                matrix = matrix.give_up_after(Duration::from_secs(*secs));
+            }
+            let run = runner
+                .run_matrix(&suite, &matrix, |record, nonce| {
+                    let path = record.write(&cli.out, nonce)?;
+                    info!("wrote {}", path.display());
+                    println!("{}", summary(record));
+                    record_trend(record)
+                })
+                .await?;
+            let path = run.write(&cli.out)?;
+            info!("wrote {}", path.display());
+            println!("\n{}\n{}", suite.name, comparison_table(&run.cells));
+            continue;
+        }
+        let records = runner
+            .run_suite(&suite, cli.repeats, |record, repeat| {
+                let path = record.write(&cli.out, repeat)?;
+                info!("wrote {}", path.display());
+                println!("{}", summary(record));
+                record_trend(record)
+            })
+            .await?;
+        if cli.repeats > 1 {
+            for summary in summarize(&records) {
+                summary.write(&cli.out)?;
+                println!("{}", summary_stats(&summary));
+            }
+        }
+    }
+    if let Some(path) = &cli.report {
+        Report::load(&cli.out, trend_config)?.write(path)?;
+        info!("wrote report to {}", path.display());
+    }
+    if regressions > 0 {
+        warn!("{regressions} proofs regressed past the rolling baseline");
+        if cli.fail_on_regression {
+            return Err(format!("{regressions} proofs regressed").into());
+        }
+    }
+    if let Some(baseline) = &baseline {
+        println!("{outcome} against baseline {}", baseline.name);
+    }
+    Ok(match outcome {
+        Outcome::Pass => ExitCode::SUCCESS,
+        Outcome::Warn => ExitCode::from(EXIT_WARN),
+        Outcome::Fail => ExitCode::from(EXIT_FAIL),
+    })
+}
+
+fn summary(record: &BenchRecord) -> String {
+    let mut line = format!(
+        "{} length {}: load {:.2}s, prove {:.2}s, extract {:.3}s, serialize {:.3}s, proof {}",
+        record.test_name,
+        record.input.length,
+        record.load_secs,
+        record.prove_secs,
+        record.extract_secs,
+        record.serialize_secs,
+        &record.proof_hash[..16]
+    );
+    if let Some(secs) = record.warm_up_secs {
+        line.push_str(&format!(", warm-up {secs:.2}s"));
+    }
+    if let (Some(secs), Some(valid)) = (record.verify_secs, record.valid) {
+        let verdict = if valid { "valid" } else { "INVALID" };
+        line.push_str(&format!(", verified {verdict} in {secs:.2}s"));
+    }
+    line.push_str(&memory(
+        record.peak_rss_bytes,
+        record.stack_high_water_bytes,
+    ));
+    line
+}
+
+fn memory(peak_rss: Option<u64>, stack_high_water: Option<u64>) -> String {
+    let mut part = String::new();
+    if let Some(bytes) = peak_rss {
+        part.push_str(&format!(", peak rss {}", format_bytes(bytes)));
+    }
+    if let Some(bytes) = stack_high_water {
+        part.push_str(&format!(", stack {}", format_bytes(bytes)));
+    }
+    part
+}
+
+fn summary_stats(summary: &InputSummary) -> String {
+    let mut line = format!(
+        "{} length {}: prove {}",
+        summary.test_name, summary.input.length, summary.prove
+    );
+    if let Some(verify) = &summary.verify {
+        line.push_str(&format!("; verify {verify}"));
+    }
+    line.push_str(&memory(
+        summary.peak_rss_bytes,
+        summary.stack_high_water_bytes,
+    ));
+    if !summary.proofs_match {
+        line.push_str("; proofs DIFFER between repeats");
+    }
+    line
+}
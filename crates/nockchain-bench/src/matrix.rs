@@ -0,0 +1,216 @@
+//! Sweeps over proof length, nonce count and thread count.
+//!
+//! A [`Matrix`] proves every combination of its lengths, nonce counts and
+//! thread counts: `nonces` inputs of one length, differing only in their
+//! nonce, proven by `threads` kernels at once (each kernel runs on a thread
+//! of its own). Every combination becomes a [`MatrixCell`], and
+//! [`comparison_table`] lines the cells up against the fewest-threads run
+//! of the same length and nonce count.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use nockchain::trend::TimingStats;
+use serde::{Deserialize, Serialize};
+
+use crate::report::{BenchRecord, RECORD_EXTENSION};
+use crate::BenchError;
+
+/// Subdirectory matrix runs are written to
+pub const MATRIX_DIR: &str = "matrix";
+
+/// The combinations to prove
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix {
+    pub lengths: Vec<u64>,
+    /// How many inputs of each length to prove
+    pub nonce_counts: Vec<usize>,
+    /// How many kernels prove at once
+    pub threads: Vec<usize>,
+    /// Skip longer lengths once a length's median proof takes longer than this
+    pub give_up_after: Option<Duration>,
+}
+
+impl Matrix {
+    /// Every combination of the given values, each list sorted and deduped.
+    /// Counts of 0 are dropped.
+    pub fn new(
+        mut lengths: Vec<u64>,
+        mut nonce_counts: Vec<usize>,
+        mut threads: Vec<usize>,
+    ) -> Self {
+        for list in [&mut nonce_counts, &mut threads] {
+            list.retain(|&count| count > 0);
+            list.sort_unstable();
+            list.dedup();
+        }
+        lengths.sort_unstable();
+        lengths.dedup();
+        Self {
+            lengths,
+            nonce_counts,
+            threads,
+            give_up_after: None,
+        }
+    }
+
+    pub fn give_up_after(self, limit: Duration) -> Self {
+        Self {
+            give_up_after: Some(limit),
+            ..self
+        }
+    }
+
+    /// The `(length, nonces, threads)` combinations, shortest length first
+    pub fn cells(&self) -> impl Iterator<Item = (u64, usize, usize)> + '_ {
+        self.lengths.iter().flat_map(move |&length| {
+            self.nonce_counts.iter().flat_map(move |&nonces| {
+                self.threads
+                    .iter()
+                    .map(move |&threads| (length, nonces, threads))
+            })
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.lengths.len() * self.nonce_counts.len() * self.threads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Test name of the records of one cell, so each cell is a trend series
+/// of its own
+pub fn cell_test_name(suite: &str, nonces: usize, threads: usize) -> String {
+    format!("{suite}-n{nonces}-t{threads}")
+}
+
+/// One combination of a [`Matrix`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixCell {
+    pub length: u64,
+    pub nonces: usize,
+    pub threads: usize,
+    /// From the first kernel loading to the last proof finishing
+    pub wall_secs: f64,
+    pub proofs_per_sec: f64,
+    /// Prove poke times of the cell's proofs
+    pub prove: TimingStats,
+}
+
+impl MatrixCell {
+    /// The cell for `records`, proven in `wall_secs`. `None` if there are no records.
+    pub fn new(threads: usize, wall_secs: f64, records: &[BenchRecord]) -> Option<Self> {
+        let first = records.first()?;
+        let prove: Vec<f64> = records.iter().map(|r| r.prove_secs).collect();
+        Some(Self {
+            length: first.input.length,
+            nonces: records.len(),
+            threads,
+            wall_secs,
+            proofs_per_sec: if wall_secs > 0.0 {
+                records.len() as f64 / wall_secs
+            } else {
+                f64::INFINITY
+            },
+            prove: TimingStats::new(&prove)?,
+        })
+    }
+}
+
+/// The cells of one matrix run of a suite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixRun {
+    pub suite: String,
+    pub cells: Vec<MatrixCell>,
+    pub recorded_at: u64,
+}
+
+impl MatrixRun {
+    /// Write the run into the [`MATRIX_DIR`] of `dir`
+    pub fn write(&self, dir: impl AsRef<Path>) -> Result<PathBuf, BenchError> {
+        let dir = dir.as_ref().join(MATRIX_DIR);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!(
+            "{}_{}.{RECORD_EXTENSION}",
+            self.suite, self.recorded_at
+        ));
+        fs::write(&path, serde_json::to_vec_pretty(self)?)?;
+        Ok(path)
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, BenchError> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+}
+
+/// Markdown table of `cells`. Speedup is throughput against the cell of
+/// the same length and nonce count with the fewest threads.
+pub fn comparison_table(cells: &[MatrixCell]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "| Length | Nonces | Threads | Wall | Median prove | Proofs/s | Speedup |"
+    );
+    let _ = writeln!(out, "|---:|---:|---:|---:|---:|---:|---:|");
+    for cell in cells {
+        let base = cells
+            .iter()
+            .filter(|other| other.length == cell.length && other.nonces == cell.nonces)
+            .min_by_key(|other| other.threads)
+            .unwrap_or(cell);
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {:.2}s | {:.2}s | {:.3} | {:.2}x |",
+            cell.length,
+            cell.nonces,
+            cell.threads,
+            cell.wall_secs,
+            cell.prove.median,
+            cell.proofs_per_sec,
+            cell.proofs_per_sec / base.proofs_per_sec,
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix_cells() {
+        let matrix = Matrix::new(vec![8, 4, 8], vec![2, 0, 1], vec![1, 2]);
+        assert_eq!(matrix.lengths, [4, 8]);
+        assert_eq!(matrix.nonce_counts, [1, 2]);
+        assert_eq!(matrix.len(), 8);
+        let cells: Vec<_> = matrix.cells().collect();
+        assert_eq!(cells.len(), matrix.len());
+        assert_eq!(cells[0], (4, 1, 1));
+        assert_eq!(cells[1], (4, 1, 2));
+        assert_eq!(cells[2], (4, 2, 1));
+        assert_eq!(cells[7], (8, 2, 2));
+        assert!(Matrix::new(vec![4], vec![1], vec![0]).is_empty());
+    }
+
+    #[test]
+    fn test_comparison_table() {
+        let cell = |threads, wall_secs: f64| MatrixCell {
+            length: 4,
+            nonces: 4,
+            threads,
+            wall_secs,
+            proofs_per_sec: 4.0 / wall_secs,
+            prove: TimingStats::new(&[1.0]).unwrap(),
+        };
+        let table = comparison_table(&[cell(1, 4.0), cell(2, 2.0)]);
+        let rows: Vec<&str> = table.lines().collect();
+        assert_eq!(rows.len(), 4);
+        assert!(rows[2].ends_with("| 1.000 | 1.00x |"), "{}", rows[2]);
+        assert!(rows[3].ends_with("| 2.000 | 2.00x |"), "{}", rows[3]);
+    }
+}
@@ -0,0 +1,114 @@
+//! Peak memory of a benchmark run.
+//!
+//! The kernel reports its own NockStack high-water mark after every poke,
+//! see `nockchain::backend::Prover::memory_stats`. Resident memory has no
+//! such counter per run (`VmHWM` covers the whole process), so an
+//! [`RssSampler`] polls it from a thread of its own while a run is going.
+//! RSS is per process: runs proving at once share one peak.
+
+use std::fs;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How often an [`RssSampler`] reads the resident set by default
+pub const DEFAULT_SAMPLE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Resident set size of this process in bytes, `None` where `/proc` is
+/// not available
+pub fn current_rss() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let kb = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+/// Tracks the largest resident set seen between [`Self::start`] and
+/// [`Self::stop`]
+pub struct RssSampler {
+    peak: Arc<AtomicU64>,
+    done: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RssSampler {
+    /// Start sampling every `interval`. Does nothing where RSS cannot be read.
+    pub fn start(interval: Duration) -> Self {
+        let peak = Arc::new(AtomicU64::new(current_rss().unwrap_or(0)));
+        let done = Arc::new(AtomicBool::new(false));
+        let thread = (peak.load(Ordering::Relaxed) > 0).then(|| {
+            let (peak, done) = (peak.clone(), done.clone());
+            std::thread::spawn(move || {
+                while !done.load(Ordering::Relaxed) {
+                    if let Some(rss) = current_rss() {
+                        peak.fetch_max(rss, Ordering::Relaxed);
+                    }
+                    std::thread::park_timeout(interval);
+                }
+            })
+        });
+        Self { peak, done, thread }
+    }
+
+    /// Stop sampling, returning the peak in bytes, `None` if RSS could not
+    /// be read
+    pub fn stop(mut self) -> Option<u64> {
+        self.finish();
+        if let Some(rss) = current_rss() {
+            self.peak.fetch_max(rss, Ordering::Relaxed);
+        }
+        Some(self.peak.load(Ordering::Relaxed)).filter(|&peak| peak > 0)
+    }
+
+    fn finish(&mut self) {
+        self.done.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for RssSampler {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Human readable size of `bytes`, in the megabytes memory is compared in
+pub fn format_bytes(bytes: u64) -> String {
+    format!("{:.1}MB", bytes as f64 / (1u64 << 20) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512 << 10), "0.5MB");
+        assert_eq!(format_bytes(16 << 30), "16384.0MB");
+    }
+
+    #[test]
+    fn test_sampler_sees_allocations() {
+        let Some(before) = current_rss() else {
+            // no /proc here
+            assert_eq!(RssSampler::start(DEFAULT_SAMPLE_INTERVAL).stop(), None);
+            return;
+        };
+        let sampler = RssSampler::start(Duration::from_millis(1));
+        let buffer = vec![1u8; 64 << 20];
+        std::thread::sleep(Duration::from_millis(20));
+        drop(std::hint::black_box(buffer));
+        let peak = sampler.stop().unwrap();
+        assert!(peak >= before + (32 << 20), "peak {peak} before {before}");
+    }
+}
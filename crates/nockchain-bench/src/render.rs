@@ -0,0 +1,414 @@
+//! Shareable reports of benchmark results.
+//!
+//! A [`Report`] gathers what a results directory holds, the trend store and
+//! the summaries of repeated runs, and renders it as a markdown or a
+//! self-contained HTML file: a table of every series with a sparkline of its
+//! recent timings, how the newest timing compares with the rolling baseline
+//! and whether its proof matches the run before, then a table of the
+//! repeated-run statistics.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use nockchain::trend::{TrendCheck, TrendConfig, TrendStatus, TrendStore};
+
+use crate::memory::format_bytes;
+use crate::report::{InputSummary, SUMMARY_DIR};
+use crate::BenchError;
+
+/// Timings a sparkline shows, the most recent ones
+pub const SPARKLINE_POINTS: usize = 20;
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+const SVG_WIDTH: f64 = 120.0;
+const SVG_HEIGHT: f64 = 24.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// HTML for `.html` and `.htm` files, markdown for anything else
+    pub fn for_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("html" | "htm") => ReportFormat::Html,
+            _ => ReportFormat::Markdown,
+        }
+    }
+}
+
+/// `values` as a line of unicode block characters, lowest to highest
+pub fn sparkline(values: &[f64]) -> String {
+    let (min, max) = bounds(values);
+    values
+        .iter()
+        .map(|&value| {
+            if max > min {
+                SPARKS[((value - min) / (max - min) * 7.0).round() as usize]
+            } else {
+                SPARKS[3]
+            }
+        })
+        .collect()
+}
+
+/// `values` as an inline SVG polyline
+fn svg_sparkline(values: &[f64]) -> String {
+    let (min, max) = bounds(values);
+    let step = SVG_WIDTH / values.len().saturating_sub(1).max(1) as f64;
+    let points: Vec<String> = values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            let height = if max > min {
+                (value - min) / (max - min)
+            } else {
+                0.5
+            };
+            let y = 2.0 + (1.0 - height) * (SVG_HEIGHT - 4.0);
+            format!("{:.1},{:.1}", i as f64 * step, y)
+        })
+        .collect();
+    let last = points.last().cloned().unwrap_or_default();
+    let (x, y) = last.split_once(',').unwrap_or(("0", "0"));
+    format!(
+        "<svg width=\"{SVG_WIDTH}\" height=\"{SVG_HEIGHT}\" viewBox=\"0 0 {SVG_WIDTH} {SVG_HEIGHT}\">\
+         <polyline fill=\"none\" stroke=\"#36c\" stroke-width=\"1.5\" points=\"{}\"/>\
+         <circle cx=\"{x}\" cy=\"{y}\" r=\"2\" fill=\"#36c\"/></svg>",
+        points.join(" ")
+    )
+}
+
+fn bounds(values: &[f64]) -> (f64, f64) {
+    values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn status(check: &TrendCheck) -> &'static str {
+    match check.status {
+        TrendStatus::NoBaseline => "new",
+        TrendStatus::Steady => "steady",
+        TrendStatus::Improvement => "improvement",
+        TrendStatus::Regression => "REGRESSION",
+    }
+}
+
+fn proof_match(matched: Option<bool>) -> &'static str {
+    match matched {
+        Some(true) => "match",
+        Some(false) => "DIFFERS",
+        None => "-",
+    }
+}
+
+/// Peak RSS and NockStack high-water mark of a summary's repeats
+fn memory(summary: &InputSummary) -> String {
+    let size = |bytes: Option<u64>| bytes.map_or("-".to_string(), format_bytes);
+    format!(
+        "{} / {}",
+        size(summary.peak_rss_bytes),
+        size(summary.stack_high_water_bytes)
+    )
+}
+
+fn change(check: &TrendCheck) -> String {
+    match (check.baseline, check.time_delta()) {
+        (Some((baseline, _)), Some(delta)) => {
+            format!("{baseline:.2}s ({:+.1}%)", delta * 100.0)
+        }
+        _ => "-".to_string(),
+    }
+}
+
+/// One row of the trend table
+struct TrendRow {
+    check: TrendCheck,
+    runs: usize,
+    recent: Vec<f64>,
+}
+
+/// Everything in a results directory, ready to render
+pub struct Report {
+    pub title: String,
+    config: TrendConfig,
+    trends: Vec<TrendRow>,
+    summaries: Vec<InputSummary>,
+}
+
+impl Report {
+    /// Read the trend store and summaries in `dir`, judging trends by `config`
+    pub fn load(dir: impl AsRef<Path>, config: TrendConfig) -> Result<Self, BenchError> {
+        let dir = dir.as_ref();
+        let store = TrendStore::open(dir)?.with_config(config);
+        let trends = store
+            .keys()
+            .filter_map(|key| {
+                let series = store.series(key);
+                let recent = &series[series.len().saturating_sub(SPARKLINE_POINTS)..];
+                Some(TrendRow {
+                    check: store.latest(key)?,
+                    runs: series.len(),
+                    recent: recent.iter().map(|sample| sample.secs).collect(),
+                })
+            })
+            .collect();
+
+        // only the newest summary of each input
+        let mut summaries: Vec<InputSummary> = Vec::new();
+        let summary_dir = dir.join(SUMMARY_DIR);
+        if summary_dir.is_dir() {
+            for entry in fs::read_dir(summary_dir)? {
+                let summary: InputSummary = serde_json::from_slice(&fs::read(entry?.path())?)?;
+                match summaries.iter_mut().find(|s| {
+                    s.test_name == summary.test_name
+                        && s.input == summary.input
+                        && s.machine == summary.machine
+                }) {
+                    Some(s) if s.recorded_at < summary.recorded_at => *s = summary,
+                    Some(_) => {}
+                    None => summaries.push(summary),
+                }
+            }
+        }
+        summaries
+            .sort_by(|a, b| (&a.test_name, a.input.length).cmp(&(&b.test_name, b.input.length)));
+
+        Ok(Self {
+            title: format!("Benchmarks in {}", dir.display()),
+            config,
+            trends,
+            summaries,
+        })
+    }
+
+    pub fn regressions(&self) -> usize {
+        self.trends
+            .iter()
+            .filter(|row| row.check.is_regression())
+            .count()
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => self.markdown(),
+            ReportFormat::Html => self.html(),
+        }
+    }
+
+    /// Render and write to `path`, in the format its extension names
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), BenchError> {
+        let path = path.as_ref();
+        fs::write(path, self.render(ReportFormat::for_path(path)))?;
+        Ok(())
+    }
+
+    fn baseline_note(&self) -> String {
+        format!(
+            "Newest timing of each series against the median of the {} before it; \
+             more than {:.0}% slower is a regression. {} of {} series regressed.",
+            self.config.window,
+            self.config.threshold * 100.0,
+            self.regressions(),
+            self.trends.len()
+        )
+    }
+
+    pub fn markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {}\n", self.title);
+        let _ = writeln!(out, "## Trends\n\n{}\n", self.baseline_note());
+        let _ = writeln!(
+            out,
+            "| Test | Length | Machine | Runs | Trend | Latest | Baseline | Status | Proof |"
+        );
+        let _ = writeln!(out, "|---|---:|---|---:|---|---:|---:|---|---|");
+        for row in &self.trends {
+            let key = &row.check.key;
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {:.2}s | {} | {} | {} |",
+                key.test,
+                key.length,
+                key.machine,
+                row.runs,
+                sparkline(&row.recent),
+                row.check.sample.secs,
+                change(&row.check),
+                status(&row.check),
+                proof_match(row.check.proof_match),
+            );
+        }
+        if !self.summaries.is_empty() {
+            let _ = writeln!(out, "\n## Repeated runs\n");
+            let _ = writeln!(
+                out,
+                "| Test | Length | Machine | Runs | Median | Mean ± stddev | Without outliers | Verify median | Peak RSS / stack | Proofs |"
+            );
+            let _ = writeln!(out, "|---|---:|---|---:|---:|---:|---:|---:|---:|---|");
+            for summary in &self.summaries {
+                let prove = &summary.prove;
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} | {} | {:.2}s | {:.2}s ± {:.2}s | {:.2}s ({} dropped) | {} | {} | {} |",
+                    summary.test_name,
+                    summary.input.length,
+                    summary.machine.id(),
+                    prove.runs,
+                    prove.median,
+                    prove.mean,
+                    prove.stddev,
+                    prove.trimmed_mean,
+                    prove.outliers,
+                    summary
+                        .verify
+                        .as_ref()
+                        .map_or("-".to_string(), |v| format!("{:.2}s", v.median)),
+                    memory(summary),
+                    proof_match(Some(summary.proofs_match)),
+                );
+            }
+        }
+        out
+    }
+
+    pub fn html(&self) -> String {
+        let mut out = String::new();
+        let title = escape(&self.title);
+        let _ = writeln!(
+            out,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title>\n<style>\
+             body{{font-family:sans-serif;margin:2em}}\
+             table{{border-collapse:collapse}}\
+             th,td{{border:1px solid #ccc;padding:4px 8px;text-align:left}}\
+             td.num{{text-align:right}}\
+             .bad{{color:#c00;font-weight:bold}}\
+             .good{{color:#080}}\
+             </style></head><body>\n<h1>{title}</h1>"
+        );
+        let _ = writeln!(
+            out,
+            "<h2>Trends</h2>\n<p>{}</p>\n<table>\n<tr><th>Test</th><th>Length</th><th>Machine</th>\
+             <th>Runs</th><th>Trend</th><th>Latest</th><th>Baseline</th><th>Status</th><th>Proof</th></tr>",
+            escape(&self.baseline_note())
+        );
+        for row in &self.trends {
+            let key = &row.check.key;
+            let status_class = match row.check.status {
+                TrendStatus::Regression => "bad",
+                TrendStatus::Improvement => "good",
+                TrendStatus::Steady | TrendStatus::NoBaseline => "",
+            };
+            let proof_class = match row.check.proof_match {
+                Some(false) => "bad",
+                _ => "",
+            };
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td class=\"num\">{}</td><td>{}</td><td class=\"num\">{}</td>\
+                 <td>{}</td><td class=\"num\">{:.2}s</td><td class=\"num\">{}</td>\
+                 <td class=\"{status_class}\">{}</td><td class=\"{proof_class}\">{}</td></tr>",
+                escape(&key.test),
+                key.length,
+                escape(&key.machine),
+                row.runs,
+                svg_sparkline(&row.recent),
+                row.check.sample.secs,
+                change(&row.check),
+                status(&row.check),
+                proof_match(row.check.proof_match),
+            );
+        }
+        let _ = writeln!(out, "</table>");
+        if !self.summaries.is_empty() {
+            let _ = writeln!(
+                out,
+                "<h2>Repeated runs</h2>\n<table>\n<tr><th>Test</th><th>Length</th><th>Machine</th>\
+                 <th>Runs</th><th>Median</th><th>Mean ± stddev</th><th>Without outliers</th>\
+                 <th>Verify median</th><th>Peak RSS / stack</th><th>Proofs</th></tr>"
+            );
+            for summary in &self.summaries {
+                let prove = &summary.prove;
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}</td><td class=\"num\">{}</td><td>{}</td><td class=\"num\">{}</td>\
+                     <td class=\"num\">{:.2}s</td><td class=\"num\">{:.2}s ± {:.2}s</td>\
+                     <td class=\"num\">{:.2}s ({} dropped)</td><td class=\"num\">{}</td>\
+                     <td class=\"num\">{}</td><td class=\"{}\">{}</td></tr>",
+                    escape(&summary.test_name),
+                    summary.input.length,
+                    escape(&summary.machine.id()),
+                    prove.runs,
+                    prove.median,
+                    prove.mean,
+                    prove.stddev,
+                    prove.trimmed_mean,
+                    prove.outliers,
+                    summary
+                        .verify
+                        .as_ref()
+                        .map_or("-".to_string(), |v| format!("{:.2}s", v.median)),
+                    memory(summary),
+                    if summary.proofs_match { "" } else { "bad" },
+                    proof_match(Some(summary.proofs_match)),
+                );
+            }
+            let _ = writeln!(out, "</table>");
+        }
+        let _ = writeln!(out, "</body></html>");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nockchain::trend::{Sample, SeriesKey};
+
+    use super::*;
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[1.0, 8.0, 4.5]), "▁█▅");
+        assert_eq!(sparkline(&[3.0, 3.0]), "▄▄");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_report_renders_trends() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = TrendStore::open(dir.path()).unwrap();
+        let key = SeriesKey::new("minimal<b>", 2, "host-x86_64-8");
+        for (at, secs, hash) in [(1, 10.0, "a"), (2, 10.0, "a"), (3, 13.0, "b")] {
+            let sample = Sample {
+                recorded_at: at,
+                secs,
+                proof_hash: hash.to_string(),
+                git_branch: None,
+            };
+            store.record(key.clone(), sample);
+        }
+        store.save().unwrap();
+
+        let report = Report::load(dir.path(), TrendConfig::default()).unwrap();
+        assert_eq!(report.regressions(), 1);
+        let markdown = report.markdown();
+        assert!(markdown.contains("| minimal<b> | 2 | host-x86_64-8 | 3 | ▁▁█ | 13.00s | 10.00s (+30.0%) | REGRESSION | DIFFERS |"));
+        let html = report.html();
+        assert!(html.contains("minimal&lt;b&gt;"));
+        assert!(html.contains("<svg"));
+        assert!(!html.contains("minimal<b>"));
+    }
+}
@@ -0,0 +1,242 @@
+//! Benchmark results as they are written to disk.
+//!
+//! Each proof gets its own JSON file. The `input`, `duration_secs` and
+//! `proof_data` fields keep the layout of the old test benchmarks, so
+//! `ProofArchive::migrate_benchmark_results` imports records that kept
+//! their proofs. Each record is also a point in a `nockchain::trend`
+//! series, see [`BenchRecord::series_key`].
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use nockchain::prove_input::{ProveBlockInput, DIGEST_BELTS};
+use nockchain::trend::{hostname, machine_id, Sample, SeriesKey, TimingStats};
+use serde::{Deserialize, Serialize};
+use zkvm_jetpack::form::math::tip5::tip5_backend;
+
+use crate::BenchError;
+
+/// Extension of record files
+pub const RECORD_EXTENSION: &str = "json";
+
+/// Subdirectory [`InputSummary`]s are written to, out of the way of tools
+/// that read every record
+pub const SUMMARY_DIR: &str = "summaries";
+
+/// The prove input of a record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputRecord {
+    pub length: u64,
+    pub block_commitment: [u64; DIGEST_BELTS],
+    pub nonce: [u64; DIGEST_BELTS],
+}
+
+impl From<&ProveBlockInput> for InputRecord {
+    fn from(input: &ProveBlockInput) -> Self {
+        Self {
+            length: input.length(),
+            block_commitment: *input.block_commitment(),
+            nonce: *input.nonce(),
+        }
+    }
+}
+
+/// The machine a benchmark ran on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineInfo {
+    pub host: String,
+    /// CPU model name, when the OS reports one
+    pub cpu: Option<String>,
+    pub cores: usize,
+    pub arch: String,
+    /// The tip5 permutation in use, see `zkvm_jetpack::form::math::tip5::Tip5Backend`
+    pub tip5_backend: String,
+}
+
+impl MachineInfo {
+    pub fn detect() -> Self {
+        let cpu = fs::read_to_string("/proc/cpuinfo").ok().and_then(|info| {
+            info.lines()
+                .find_map(|line| line.strip_prefix("model name")?.split_once(':'))
+                .map(|(_, model)| model.trim().to_string())
+        });
+        Self {
+            host: hostname(),
+            cpu,
+            cores: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            arch: std::env::consts::ARCH.to_string(),
+            tip5_backend: tip5_backend().name().to_string(),
+        }
+    }
+
+    /// The machine's name in trend series, see [`machine_id`]
+    pub fn id(&self) -> String {
+        machine_id(&self.host, &self.arch, self.cores)
+    }
+}
+
+/// One proof of one input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchRecord {
+    pub test_name: String,
+    pub input: InputRecord,
+    /// Loading the kernel and proving, what the old benchmarks timed
+    pub duration_secs: f64,
+    /// Booting the prover kernel
+    #[serde(default)]
+    pub load_secs: f64,
+    /// The throwaway poke run after loading, when the kernel was warmed up
+    #[serde(default)]
+    pub warm_up_secs: Option<f64>,
+    /// The prove poke
+    pub prove_secs: f64,
+    /// Decoding and checking the `%pow` effect and hashing it
+    #[serde(default)]
+    pub extract_secs: f64,
+    /// Jamming the proof, as it is stored or sent to a verifier
+    #[serde(default)]
+    pub serialize_secs: f64,
+    /// Verifier time, when the proof was verified
+    pub verify_secs: Option<f64>,
+    /// The verifier's verdict, when the proof was verified
+    pub valid: Option<bool>,
+    /// Largest resident set of the process while loading and proving, see
+    /// [`crate::memory::RssSampler`]
+    #[serde(default)]
+    pub peak_rss_bytes: Option<u64>,
+    /// Most NockStack the prove poke used, when the prover runs a kernel
+    #[serde(default)]
+    pub stack_high_water_bytes: Option<u64>,
+    /// blake3 of the jammed `%pow` effect, equal across runs exactly when
+    /// the proofs are identical
+    pub proof_hash: String,
+    /// The jammed proof, only kept when asked for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proof_data: Vec<u8>,
+    /// Seconds since the unix epoch
+    pub recorded_at: u64,
+    pub git_branch: Option<String>,
+    /// Proven with the INSECURE dev-proving kernel
+    pub dev_proving: bool,
+    pub machine: MachineInfo,
+}
+
+impl BenchRecord {
+    /// File name of the record, unique per suite, length, repeat and time
+    pub fn file_name(&self, repeat: usize) -> String {
+        format!(
+            "{}_len{}_{}_{}.{RECORD_EXTENSION}",
+            self.test_name, self.input.length, self.recorded_at, repeat
+        )
+    }
+
+    /// Write the record into `dir`, creating it if needed
+    pub fn write(&self, dir: impl AsRef<Path>, repeat: usize) -> Result<PathBuf, BenchError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let path = dir.join(self.file_name(repeat));
+        fs::write(&path, serde_json::to_vec_pretty(self)?)?;
+        Ok(path)
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, BenchError> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// The series the record belongs to. Dev-proving timings get a series
+    /// of their own, they are not comparable to real ones.
+    pub fn series_key(&self) -> SeriesKey {
+        let test = if self.dev_proving {
+            format!("{}-dev-proving", self.test_name)
+        } else {
+            self.test_name.clone()
+        };
+        SeriesKey::new(test, self.input.length, self.machine.id())
+    }
+
+    /// The record as a point in its series
+    pub fn sample(&self) -> Sample {
+        Sample {
+            recorded_at: self.recorded_at,
+            secs: self.duration_secs,
+            proof_hash: self.proof_hash.clone(),
+            git_branch: self.git_branch.clone(),
+        }
+    }
+}
+
+/// Timings of every repeat of one input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputSummary {
+    pub test_name: String,
+    pub input: InputRecord,
+    pub duration: TimingStats,
+    pub prove: TimingStats,
+    /// Verifier timings, when every repeat was verified
+    pub verify: Option<TimingStats>,
+    /// Whether every repeat produced the same proof
+    pub proofs_match: bool,
+    /// Largest [`BenchRecord::peak_rss_bytes`] of any repeat
+    #[serde(default)]
+    pub peak_rss_bytes: Option<u64>,
+    /// Largest [`BenchRecord::stack_high_water_bytes`] of any repeat
+    #[serde(default)]
+    pub stack_high_water_bytes: Option<u64>,
+    pub recorded_at: u64,
+    pub machine: MachineInfo,
+}
+
+impl InputSummary {
+    /// Write the summary into the [`SUMMARY_DIR`] of `dir`
+    pub fn write(&self, dir: impl AsRef<Path>) -> Result<PathBuf, BenchError> {
+        let dir = dir.as_ref().join(SUMMARY_DIR);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!(
+            "{}_len{}_{}.{RECORD_EXTENSION}",
+            self.test_name, self.input.length, self.recorded_at
+        ));
+        fs::write(&path, serde_json::to_vec_pretty(self)?)?;
+        Ok(path)
+    }
+}
+
+/// Summarize `records` by test and input, in the order each first appears
+pub fn summarize(records: &[BenchRecord]) -> Vec<InputSummary> {
+    let mut groups: Vec<Vec<&BenchRecord>> = Vec::new();
+    for record in records {
+        match groups
+            .iter_mut()
+            .find(|group| group[0].test_name == record.test_name && group[0].input == record.input)
+        {
+            Some(group) => group.push(record),
+            None => groups.push(vec![record]),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|group| {
+            let stats = |secs: Vec<f64>| TimingStats::new(&secs).expect("groups are not empty");
+            let verify: Option<Vec<f64>> = group.iter().map(|r| r.verify_secs).collect();
+            let first = group[0];
+            InputSummary {
+                test_name: first.test_name.clone(),
+                input: first.input.clone(),
+                duration: stats(group.iter().map(|r| r.duration_secs).collect()),
+                prove: stats(group.iter().map(|r| r.prove_secs).collect()),
+                verify: verify.map(stats),
+                proofs_match: group.iter().all(|r| r.proof_hash == first.proof_hash),
+                peak_rss_bytes: group.iter().filter_map(|r| r.peak_rss_bytes).max(),
+                stack_high_water_bytes: group.iter().filter_map(|r| r.stack_high_water_bytes).max(),
+                recorded_at: group[group.len() - 1].recorded_at,
+                machine: first.machine.clone(),
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
@@ -0,0 +1,130 @@
+//! Named sets of prove inputs.
+//!
+//! The presets are the inputs the old `prove_block_*` test benchmarks
+//! proved, so numbers from either can be compared.
+
+use std::str::FromStr;
+
+use nockchain::prove_input::{ProveBlockInput, ProveInputError, DIGEST_BELTS};
+
+const COMMITMENT: [u64; DIGEST_BELTS] = [0x1, 0x2, 0x3, 0x4, 0x5];
+const NONCE: [u64; DIGEST_BELTS] = [0x10, 0x20, 0x30, 0x40, 0x1];
+
+/// Names accepted by [`Suite::preset`]
+pub const PRESETS: &[&str] = &["minimal", "length-4", "very-fast", "progressive", "single"];
+
+/// Prove one candidate of each length, all over the same commitment and nonce
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suite {
+    pub name: String,
+    pub lengths: Vec<u64>,
+    pub block_commitment: [u64; DIGEST_BELTS],
+    pub nonce: [u64; DIGEST_BELTS],
+}
+
+impl Suite {
+    pub fn new(name: impl Into<String>, lengths: Vec<u64>) -> Self {
+        Self {
+            name: name.into(),
+            lengths,
+            block_commitment: COMMITMENT,
+            nonce: NONCE,
+        }
+    }
+
+    /// One of the [`PRESETS`]
+    pub fn preset(name: &str) -> Option<Self> {
+        let suite = match name {
+            "minimal" => Suite {
+                block_commitment: [0x1; DIGEST_BELTS],
+                nonce: [0x1; DIGEST_BELTS],
+                ..Suite::new(name, vec![2])
+            },
+            "length-4" => Suite::new(name, vec![4]),
+            "very-fast" => Suite::new(name, vec![8]),
+            "progressive" => Suite::new(name, vec![4, 8, 16, 32]),
+            "single" => Suite {
+                nonce: [0x100, 0x200, 0x300, 0x400, 0x1],
+                ..Suite::new(name, vec![64])
+            },
+            _ => return None,
+        };
+        Some(suite)
+    }
+
+    /// The same inputs at `lengths` instead
+    pub fn with_lengths(self, lengths: Vec<u64>) -> Self {
+        Self { lengths, ..self }
+    }
+
+    /// The checked prove inputs, in order
+    pub fn inputs(&self) -> Result<Vec<ProveBlockInput>, ProveInputError> {
+        self.lengths
+            .iter()
+            .map(|&length| {
+                ProveBlockInput::builder()
+                    .length(length)
+                    .block_commitment(&self.block_commitment)
+                    .nonce(&self.nonce)
+                    .build()
+            })
+            .collect()
+    }
+
+    /// `count` inputs of `length`, the first with the suite's nonce and
+    /// each next one counting up its first belt
+    pub fn inputs_with_nonces(
+        &self,
+        length: u64,
+        count: usize,
+    ) -> Result<Vec<ProveBlockInput>, ProveInputError> {
+        (0..count as u64)
+            .map(|i| {
+                let mut nonce = self.nonce;
+                nonce[0] += i;
+                ProveBlockInput::builder()
+                    .length(length)
+                    .block_commitment(&self.block_commitment)
+                    .nonce(&nonce)
+                    .build()
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Suite {
+    type Err = String;
+
+    /// Parses one of the [`PRESETS`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Suite::preset(s.trim()).ok_or_else(|| {
+            format!(
+                "unknown suite {s:?}, expected one of {}",
+                PRESETS.join(", ")
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_are_valid() {
+        for name in PRESETS {
+            let suite: Suite = name.parse().unwrap();
+            assert_eq!(suite.name, *name);
+            assert_eq!(suite.inputs().unwrap().len(), suite.lengths.len());
+        }
+        assert!("huge".parse::<Suite>().is_err());
+        let odd = Suite::preset("very-fast").unwrap().with_lengths(vec![3]);
+        assert_eq!(odd.inputs(), Err(ProveInputError::BadLength(3)));
+
+        let minimal = Suite::preset("minimal").unwrap();
+        let inputs = minimal.inputs_with_nonces(4, 3).unwrap();
+        assert_eq!(inputs.len(), 3);
+        assert_eq!(inputs[0].nonce(), &minimal.nonce);
+        assert_eq!(inputs[2].nonce(), &[0x3, 0x1, 0x1, 0x1, 0x1]);
+    }
+}
@@ -163,7 +163,18 @@ impl<T: 'static> TrackedJoinSet<T> {
 
 const POKE_VERSION: u64 = 0;
 
-#[instrument(skip(keypair, bind, allowed, limits, memory_limits, equix_builder))]
+/// Called with each peer a connection is established to, and its address
+pub type OnPeerConnected = Arc<dyn Fn(PeerId, &Multiaddr) + Send + Sync>;
+
+#[instrument(skip(
+    keypair,
+    bind,
+    allowed,
+    limits,
+    memory_limits,
+    equix_builder,
+    on_connected
+))]
 pub fn make_libp2p_driver(
     keypair: Keypair,
     bind: Vec<Multiaddr>,
@@ -174,6 +185,7 @@ pub fn make_libp2p_driver(
     force_peers: &[Multiaddr],
     equix_builder: equix::EquiXBuilder,
     init_complete_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    on_connected: Option<OnPeerConnected>,
 ) -> IODriverFn {
     let initial_peers = Vec::from(initial_peers);
     let force_peers = Vec::from(force_peers);
@@ -267,6 +279,9 @@ pub fn make_libp2p_driver(
                             SwarmEvent::ConnectionEstablished { connection_id, peer_id, endpoint, .. } => {
                                 message_tracker.lock().await.track_connection(connection_id, peer_id, endpoint.get_remote_address());
                                 debug!("SEvent: {peer_id} is new friend via: {endpoint:?}");
+                                if let Some(on_connected) = &on_connected {
+                                    on_connected(peer_id, endpoint.get_remote_address());
+                                }
                             },
                             SwarmEvent::ConnectionClosed { connection_id, peer_id, endpoint, cause, .. } => {
                                 message_tracker.lock().await.lost_connection(connection_id);
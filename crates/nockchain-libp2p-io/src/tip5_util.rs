@@ -6,8 +6,9 @@ use nockvm::noun::Noun;
 //using the Atom::as_ubig with the NockStack instead of ibig's heap version
 // which we use to avoid having a NockStack sitting around.
 
-// Goldilocks prime
+// Goldilocks prime, the same as zkvm-jetpack's PRIME and the kernel's +p
 const P: u64 = 0xffffffff00000001;
+const _: () = assert!(P as u128 == (1 << 64) - (1 << 32) + 1);
 
 /// Tries to convert a Noun to a Base58 string by extracting a 5-tuple, converting it to a decimal, and then to Base58.
 ///
@@ -0,0 +1,175 @@
+//! Transaction history for accounting.
+//!
+//! The history is built from a [`NoteDb`]: every note the wallet received is
+//! an incoming entry and every transaction it recorded sending is an
+//! outgoing one. [`export`] writes it as CSV or JSON.
+
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use serde::Serialize;
+use zkvm_jetpack::form::address;
+
+use crate::scan::{NoteDb, OwnedNote, SentTx};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Received,
+    Sent,
+}
+
+impl Direction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Received => "received",
+            Direction::Sent => "sent",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistoryEntry {
+    /// Height of the including block, none while a send is pending
+    pub height: Option<u64>,
+    /// Address of the block a note was received in, or of a sent transaction
+    pub digest: String,
+    pub amount: u64,
+    pub direction: Direction,
+    /// Fee paid, zero for received notes
+    pub fee: u64,
+}
+
+/// The wallet's history, oldest first with pending sends last
+pub fn history(db: &NoteDb) -> Vec<HistoryEntry> {
+    entries(db.notes(), db.sent())
+}
+
+fn entries(notes: &[OwnedNote], sent: &[SentTx]) -> Vec<HistoryEntry> {
+    let received = notes.iter().map(|note| HistoryEntry {
+        height: Some(note.id.height),
+        digest: address::encode(&note.block),
+        amount: note.amount,
+        direction: Direction::Received,
+        fee: 0,
+    });
+    let sent = sent.iter().map(|tx| HistoryEntry {
+        height: tx.height,
+        digest: address::encode(&tx.tx),
+        amount: tx.amount,
+        direction: Direction::Sent,
+        fee: tx.fee,
+    });
+    let mut entries: Vec<_> = received.chain(sent).collect();
+    // stable, so entries at one height keep receives before sends
+    entries.sort_by_key(|entry| entry.height.unwrap_or(u64::MAX));
+    entries
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            other => Err(format!(
+                "unknown export format {other}, expected csv or json"
+            )),
+        }
+    }
+}
+
+/// Write `entries` in `format`, pending sends have an empty height
+pub fn export(entries: &[HistoryEntry], format: ExportFormat) -> Result<String, serde_json::Error> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(entries),
+        ExportFormat::Csv => {
+            let mut csv = String::from("height,digest,amount,direction,fee\n");
+            for entry in entries {
+                let height = entry.height.map(|h| h.to_string()).unwrap_or_default();
+                writeln!(
+                    csv,
+                    "{height},{},{},{},{}",
+                    entry.digest,
+                    entry.amount,
+                    entry.direction.as_str(),
+                    entry.fee
+                )
+                .expect("writing to a string cannot fail");
+            }
+            Ok(csv)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::NoteId;
+
+    fn note(height: u64, amount: u64) -> OwnedNote {
+        OwnedNote {
+            id: NoteId { height, index: 0 },
+            block: [height, 0, 0, 0, 0],
+            amount,
+            m: 1,
+            pubkeys: vec![],
+            coinbase: true,
+            spent: false,
+        }
+    }
+
+    fn sent(height: Option<u64>, amount: u64, fee: u64) -> SentTx {
+        SentTx {
+            tx: [amount, fee, 0, 0, 0],
+            notes: vec![],
+            amount,
+            fee,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_history_export() {
+        let entries = entries(
+            &[note(3, 50), note(7, 20)],
+            &[sent(None, 5, 1), sent(Some(3), 30, 2)],
+        );
+        let order: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.height, entry.direction))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                (Some(3), Direction::Received),
+                (Some(3), Direction::Sent),
+                (Some(7), Direction::Received),
+                (None, Direction::Sent),
+            ]
+        );
+
+        let csv = export(&entries, ExportFormat::Csv).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "height,digest,amount,direction,fee");
+        assert_eq!(
+            lines[2],
+            format!("3,{},30,sent,2", address::encode(&[30, 2, 0, 0, 0]))
+        );
+        assert!(lines[4].starts_with(',') && lines[4].ends_with(",5,sent,1"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&export(&entries, ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["direction"], "received");
+        assert_eq!(json[3]["height"], serde_json::Value::Null);
+        assert_eq!("JSON".parse(), Ok(ExportFormat::Json));
+        assert!("xml".parse::<ExportFormat>().is_err());
+    }
+}
@@ -0,0 +1,557 @@
+//! Seed phrases and the signing keys derived from them.
+//!
+//! This follows the wallet kernel's `bip39` and `slip10` cores, so a seed
+//! phrase made here restores the same keys with `gen-master-privkey`:
+//!
+//! - [`Mnemonic`] is a BIP39 seed phrase over the English word list, and
+//!   [`Mnemonic::to_seed`] stretches it into a 64 byte [`Seed`] with
+//!   PBKDF2-HMAC-SHA512.
+//! - [`ExtendedPrivateKey::from_seed`] and the `derive_child` methods are
+//!   SLIP-10 over the cheetah curve, keyed with `"Nockchain seed"`, keys
+//!   being scalars below the order of the cheetah generator.
+//! - [`PublicKey::to_bytes`] is the kernel's `ser-a-pt` serialization.
+//!
+//! Seed phrases, seeds, private keys and chain codes are wiped from memory
+//! when dropped. Scalar arithmetic goes through `ibig`, whose temporaries are
+//! not wiped.
+
+use std::fmt;
+use std::sync::LazyLock;
+
+use hmac::{Hmac, Mac};
+use ibig::UBig;
+use sha2::{Digest, Sha256, Sha512};
+use thiserror::Error;
+use zeroize::Zeroizing;
+use zkvm_jetpack::form::math::base::PRIME;
+use zkvm_jetpack::form::Belt;
+use zkvm_jetpack::jets::cheetah_jets::{
+    ch_add, ch_scal_big, CheetahPoint, F6lt, A_GEN, A_ID, G_ORDER_HEX,
+};
+
+/// The kernel's word list, `/common/bip39-english`
+const WORDLIST_HOON: &str = include_str!("../../../hoon/common/bip39-english.hoon");
+/// SLIP-10 key for master keys, `'dees niahckcoN'` as a hoon cord
+const DOMAIN_SEPARATOR: &[u8] = b"Nockchain seed";
+const PBKDF2_ROUNDS: u32 = 2048;
+/// Child indices from here on are hardened
+pub const HARDENED: u32 = 1 << 31;
+
+static WORDLIST: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
+    WORDLIST_HOON
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix('"')?.strip_suffix('"'))
+        .collect()
+});
+
+static G_ORDER: LazyLock<UBig> =
+    LazyLock::new(|| UBig::from_str_radix(G_ORDER_HEX, 16).expect("g-order is hex"));
+
+#[derive(Debug, Error)]
+pub enum KeyError {
+    #[error("entropy must be 16 to 32 bytes in steps of 4, got {0}")]
+    EntropyLength(usize),
+    #[error("seed phrases have 12, 15, 18, 21 or 24 words, got {0}")]
+    WordCount(usize),
+    #[error("word {0} of the seed phrase is not in the BIP39 English word list")]
+    UnknownWord(usize),
+    #[error("seed phrase checksum does not match")]
+    Checksum,
+    #[error("cannot derive hardened child {0} from a public key")]
+    HardenedFromPublic(u32),
+    #[error("could not get entropy from the OS: {0}")]
+    Entropy(getrandom::Error),
+    #[error("cheetah curve arithmetic failed")]
+    Curve,
+    #[error("not a public key: {0}")]
+    InvalidPublicKey(&'static str),
+}
+
+/// A BIP39 seed phrase
+#[derive(Clone)]
+pub struct Mnemonic(Zeroizing<String>);
+
+impl Mnemonic {
+    /// A new 24 word seed phrase from OS entropy
+    pub fn generate() -> Result<Self, KeyError> {
+        let mut entropy = Zeroizing::new([0u8; 32]);
+        getrandom::getrandom(&mut entropy[..]).map_err(KeyError::Entropy)?;
+        Self::from_entropy(&entropy[..])
+    }
+
+    /// The seed phrase encoding `entropy`, followed by its checksum
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, KeyError> {
+        if !(16..=32).contains(&entropy.len()) || entropy.len() % 4 != 0 {
+            return Err(KeyError::EntropyLength(entropy.len()));
+        }
+        let entropy_bits = entropy.len() * 8;
+        // one bit of sha-256 for every 32 bits of entropy
+        let checksum = Sha256::digest(entropy)[0];
+        let bit = |i: usize| -> usize {
+            let byte = if i < entropy_bits {
+                entropy[i / 8] << (i % 8)
+            } else {
+                checksum << (i - entropy_bits)
+            };
+            usize::from(byte >> 7)
+        };
+        let words = (entropy_bits + entropy_bits / 32) / 11;
+        let phrase = (0..words)
+            .map(|word| {
+                let index = (word * 11..(word + 1) * 11).fold(0, |acc, i| acc << 1 | bit(i));
+                WORDLIST[index]
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        Ok(Mnemonic(Zeroizing::new(phrase)))
+    }
+
+    /// Check a seed phrase's words and checksum
+    pub fn parse(phrase: &str) -> Result<Self, KeyError> {
+        let words: Vec<&str> = phrase.split_whitespace().collect();
+        if !matches!(words.len(), 12 | 15 | 18 | 21 | 24) {
+            return Err(KeyError::WordCount(words.len()));
+        }
+        let entropy_bits = words.len() * 11 * 32 / 33;
+        let mut entropy = Zeroizing::new(vec![0u8; entropy_bits / 8]);
+        for (position, word) in words.iter().enumerate() {
+            let index = WORDLIST
+                .binary_search(word)
+                .map_err(|_| KeyError::UnknownWord(position + 1))?;
+            for bit in 0..11 {
+                let i = position * 11 + bit;
+                if i < entropy_bits && (index >> (10 - bit)) & 1 == 1 {
+                    entropy[i / 8] |= 0x80 >> (i % 8);
+                }
+            }
+        }
+        let mnemonic = Self::from_entropy(&entropy)?;
+        if mnemonic.phrase().split(' ').ne(words.iter().copied()) {
+            return Err(KeyError::Checksum);
+        }
+        Ok(mnemonic)
+    }
+
+    pub fn phrase(&self) -> &str {
+        &self.0
+    }
+
+    /// The BIP39 seed, salted with `passphrase`. The kernel uses an empty one.
+    pub fn to_seed(&self, passphrase: &str) -> Seed {
+        let salt = Zeroizing::new(format!("mnemonic{passphrase}"));
+        let mut seed = Zeroizing::new([0u8; 64]);
+        pbkdf2::pbkdf2_hmac::<Sha512>(
+            self.0.as_bytes(),
+            salt.as_bytes(),
+            PBKDF2_ROUNDS,
+            &mut seed[..],
+        );
+        Seed(seed)
+    }
+}
+
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Mnemonic(..)")
+    }
+}
+
+/// The seed master keys are made from
+#[derive(Clone)]
+pub struct Seed(Zeroizing<[u8; 64]>);
+
+impl Seed {
+    pub fn as_bytes(&self) -> &[u8; 64] {
+        &self.0
+    }
+}
+
+impl From<[u8; 64]> for Seed {
+    fn from(bytes: [u8; 64]) -> Self {
+        Seed(Zeroizing::new(bytes))
+    }
+}
+
+impl fmt::Debug for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Seed(..)")
+    }
+}
+
+/// A point on the cheetah curve other than the identity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey(CheetahPoint);
+
+impl PublicKey {
+    /// The public key at `point`, or `None` for the identity
+    pub fn from_point(point: CheetahPoint) -> Option<Self> {
+        (!point.inf).then_some(PublicKey(point))
+    }
+
+    pub fn point(&self) -> &CheetahPoint {
+        &self.0
+    }
+
+    /// `ser-a-pt`: a leading 1, then y and x, most significant belt first
+    pub fn to_bytes(&self) -> [u8; 97] {
+        let mut bytes = [0u8; 97];
+        bytes[0] = 1;
+        let belts = self.0.y.0.iter().rev().chain(self.0.x.0.iter().rev());
+        for (chunk, belt) in bytes[1..].chunks_exact_mut(8).zip(belts) {
+            chunk.copy_from_slice(&belt.0.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Base58, as the wallet shows public keys
+    pub fn to_base58(&self) -> String {
+        bs58::encode(self.to_bytes()).into_string()
+    }
+
+    /// Parse [`PublicKey::to_bytes`], checking the point is in the group
+    /// the generator spans
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, KeyError> {
+        if bytes.len() != 97 || bytes[0] != 1 {
+            return Err(KeyError::InvalidPublicKey(
+                "expected 97 bytes with a leading 1",
+            ));
+        }
+        // y then x, most significant belt first
+        let mut belts = [0u64; 12];
+        for (belt, chunk) in belts.iter_mut().zip(bytes[1..].chunks_exact(8)) {
+            *belt = u64::from_be_bytes(chunk.try_into().expect("8 bytes"));
+            if *belt >= PRIME {
+                return Err(KeyError::InvalidPublicKey("coordinate out of range"));
+            }
+        }
+        let coordinate = |at: usize| F6lt(std::array::from_fn(|i| Belt(belts[at + 5 - i])));
+        let point = CheetahPoint {
+            x: coordinate(6),
+            y: coordinate(0),
+            inf: false,
+        };
+        if ch_scal_big(&G_ORDER, &point).ok() != Some(A_ID) {
+            return Err(KeyError::InvalidPublicKey("not in the group"));
+        }
+        Ok(PublicKey(point))
+    }
+
+    pub fn from_base58(s: &str) -> Result<Self, KeyError> {
+        let bytes = bs58::decode(s.trim())
+            .into_vec()
+            .map_err(|_| KeyError::InvalidPublicKey("not base58"))?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// A SLIP-10 private key and chain code
+#[derive(Clone)]
+pub struct ExtendedPrivateKey {
+    key: Zeroizing<[u8; 32]>,
+    chain_code: Zeroizing<[u8; 32]>,
+    depth: u32,
+    index: u32,
+}
+
+impl ExtendedPrivateKey {
+    /// The master key for `seed`
+    pub fn from_seed(seed: &Seed) -> Self {
+        let mut digest = hmac_sha512(DOMAIN_SEPARATOR, seed.as_bytes());
+        loop {
+            let (left, right) = digest.split_at(32);
+            // rehash rather than reduce so keys stay uniform below the order
+            if UBig::from_be_bytes(left) < *G_ORDER {
+                return ExtendedPrivateKey {
+                    key: Zeroizing::new(left.try_into().expect("32 bytes")),
+                    chain_code: Zeroizing::new(right.try_into().expect("32 bytes")),
+                    depth: 0,
+                    index: 0,
+                };
+            }
+            digest = hmac_sha512(DOMAIN_SEPARATOR, &digest[..]);
+        }
+    }
+
+    /// A key and chain code exported from the wallet
+    pub fn from_parts(key: [u8; 32], chain_code: [u8; 32]) -> Self {
+        ExtendedPrivateKey {
+            key: Zeroizing::new(key),
+            chain_code: Zeroizing::new(chain_code),
+            depth: 0,
+            index: 0,
+        }
+    }
+
+    /// The private key, a big-endian scalar
+    pub fn secret(&self) -> &[u8; 32] {
+        &self.key
+    }
+
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn public_key(&self) -> Result<PublicKey, KeyError> {
+        point(&UBig::from_be_bytes(&self.key[..])).map(PublicKey)
+    }
+
+    /// The public half, which can derive the same non-hardened children
+    pub fn public(&self) -> Result<ExtendedPublicKey, KeyError> {
+        Ok(ExtendedPublicKey {
+            key: self.public_key()?,
+            chain_code: *self.chain_code,
+            depth: self.depth,
+            index: self.index,
+        })
+    }
+
+    /// Child `index`, hardened from [`HARDENED`] up
+    pub fn derive_child(&self, index: u32) -> Result<Self, KeyError> {
+        let mut data = Zeroizing::new(Vec::with_capacity(101));
+        if index >= HARDENED {
+            data.push(0);
+            data.extend_from_slice(&self.key[..]);
+        } else {
+            data.extend_from_slice(&self.public_key()?.to_bytes());
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+        let parent = UBig::from_be_bytes(&self.key[..]);
+        let mut digest = hmac_sha512(&self.chain_code[..], &data);
+        loop {
+            let (left, right) = digest.split_at(32);
+            let left = UBig::from_be_bytes(left);
+            let key = (&left + &parent) % &*G_ORDER;
+            if left < *G_ORDER && key != UBig::from(0u8) {
+                return Ok(ExtendedPrivateKey {
+                    key: Zeroizing::new(scalar_bytes(&key)),
+                    chain_code: Zeroizing::new(right.try_into().expect("32 bytes")),
+                    depth: self.depth + 1,
+                    index,
+                });
+            }
+            digest = hmac_sha512(&self.chain_code[..], &retry_data(right, index));
+        }
+    }
+
+    /// Derive along `path` in order
+    pub fn derive_path(&self, path: &[u32]) -> Result<Self, KeyError> {
+        path.iter()
+            .try_fold(self.clone(), |key, &index| key.derive_child(index))
+    }
+}
+
+impl fmt::Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtendedPrivateKey")
+            .field("depth", &self.depth)
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A SLIP-10 public key and chain code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedPublicKey {
+    key: PublicKey,
+    chain_code: [u8; 32],
+    depth: u32,
+    index: u32,
+}
+
+impl ExtendedPublicKey {
+    pub fn public_key(&self) -> &PublicKey {
+        &self.key
+    }
+
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Non-hardened child `index`
+    pub fn derive_child(&self, index: u32) -> Result<Self, KeyError> {
+        if index >= HARDENED {
+            return Err(KeyError::HardenedFromPublic(index));
+        }
+        let mut data = self.key.to_bytes().to_vec();
+        data.extend_from_slice(&index.to_be_bytes());
+        let mut digest = hmac_sha512(&self.chain_code, &data);
+        loop {
+            let (left, right) = digest.split_at(32);
+            let left = UBig::from_be_bytes(left);
+            if left < *G_ORDER {
+                let key = ch_add(&point(&left)?, self.key.point()).map_err(|_| KeyError::Curve)?;
+                if key != A_ID {
+                    return Ok(ExtendedPublicKey {
+                        key: PublicKey(key),
+                        chain_code: right.try_into().expect("32 bytes"),
+                        depth: self.depth + 1,
+                        index,
+                    });
+                }
+            }
+            digest = hmac_sha512(&self.chain_code, &retry_data(right, index));
+        }
+    }
+}
+
+/// `scalar` times the generator
+fn point(scalar: &UBig) -> Result<CheetahPoint, KeyError> {
+    ch_scal_big(scalar, &A_GEN).map_err(|_| KeyError::Curve)
+}
+
+fn scalar_bytes(scalar: &UBig) -> [u8; 32] {
+    let be = Zeroizing::new(scalar.to_be_bytes());
+    let mut bytes = [0u8; 32];
+    bytes[32 - be.len()..].copy_from_slice(&be);
+    bytes
+}
+
+/// What is hashed instead when a child key comes out invalid
+fn retry_data(right: &[u8], index: u32) -> Zeroizing<Vec<u8>> {
+    let mut data = Zeroizing::new(Vec::with_capacity(37));
+    data.push(1);
+    data.extend_from_slice(right);
+    data.extend_from_slice(&index.to_be_bytes());
+    data
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> Zeroizing<[u8; 64]> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("hmac takes keys of any length");
+    mac.update(data);
+    let mut digest = Zeroizing::new([0u8; 64]);
+    digest.copy_from_slice(&mac.finalize().into_bytes());
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn trezor_seed() -> Seed {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        Mnemonic::parse(phrase).unwrap().to_seed("TREZOR")
+    }
+
+    #[test]
+    fn test_bip39_vectors() {
+        assert_eq!(WORDLIST.len(), 2048);
+        let mnemonic = Mnemonic::from_entropy(&[0; 16]).unwrap();
+        assert_eq!(
+            mnemonic.phrase(),
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"
+        );
+        assert_eq!(
+            Mnemonic::from_entropy(&[0x7f; 16]).unwrap().phrase(),
+            "legal winner thank year wave sausage worth useful legal winner thank yellow"
+        );
+        assert_eq!(
+            trezor_seed().as_bytes().to_vec(),
+            hex("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04")
+        );
+    }
+
+    #[test]
+    fn test_parse_checks_words_and_checksum() {
+        let generated = Mnemonic::generate().unwrap();
+        assert_eq!(generated.phrase().split(' ').count(), 24);
+        let parsed = Mnemonic::parse(&format!("  {}\n", generated.phrase())).unwrap();
+        assert_eq!(parsed.phrase(), generated.phrase());
+
+        assert!(matches!(
+            Mnemonic::parse(&["abandon"; 12].join(" ")),
+            Err(KeyError::Checksum)
+        ));
+        assert!(matches!(
+            Mnemonic::parse("correct horse battery staple"),
+            Err(KeyError::WordCount(4))
+        ));
+        let mut words = ["abandon"; 12];
+        words[3] = "nockchain";
+        assert!(matches!(
+            Mnemonic::parse(&words.join(" ")),
+            Err(KeyError::UnknownWord(4))
+        ));
+        assert!(matches!(
+            Mnemonic::from_entropy(&[0; 21]),
+            Err(KeyError::EntropyLength(21))
+        ));
+    }
+
+    #[test]
+    fn test_slip10_master_and_hardened_child() {
+        let master = ExtendedPrivateKey::from_seed(&trezor_seed());
+        assert_eq!(
+            master.secret().to_vec(),
+            hex("71213cc263e29527c43e97ae6ea7f042805b376e25021f5ae27babcd59ffeca9")
+        );
+        assert_eq!(
+            master.chain_code().to_vec(),
+            hex("12d91987bbbc933579c13b1a6b21d40602cbfe9b433d8a6a86653dd1eb290639")
+        );
+        // the first hash of this child is above the order and gets rehashed
+        let child = master.derive_child(HARDENED).unwrap();
+        assert_eq!(
+            child.secret().to_vec(),
+            hex("12e4e152ade97a1ef6fbc40d99aaba841a0eb769ecef6ee617c182c1e9169b0a")
+        );
+        assert_eq!(
+            child.chain_code().to_vec(),
+            hex("4665f4b8bae8f644b84b957d83d663de8c70be6184024bc42f93d18b16d751c2")
+        );
+        assert_eq!((child.depth(), child.index()), (1, HARDENED));
+    }
+
+    #[test]
+    fn test_public_derivation_matches_private() {
+        let master = ExtendedPrivateKey::from_seed(&trezor_seed());
+        let from_private = master.derive_path(&[HARDENED, 7]).unwrap();
+        let from_public = master
+            .derive_child(HARDENED)
+            .and_then(|key| key.public())
+            .and_then(|key| key.derive_child(7))
+            .unwrap();
+        assert_eq!(from_private.public().unwrap(), from_public);
+        assert_eq!(from_public.public_key().to_bytes()[0], 1);
+
+        assert!(matches!(
+            from_public.derive_child(HARDENED + 1),
+            Err(KeyError::HardenedFromPublic(_))
+        ));
+    }
+
+    #[test]
+    fn test_public_key_bytes_roundtrip() {
+        let key = ExtendedPrivateKey::from_seed(&trezor_seed())
+            .public_key()
+            .unwrap();
+        assert_eq!(PublicKey::from_base58(&key.to_base58()).unwrap(), key);
+
+        let mut off_curve = key.to_bytes();
+        off_curve[96] ^= 1;
+        assert!(PublicKey::from_bytes(&off_curve).is_err());
+        assert!(PublicKey::from_bytes(&off_curve[1..]).is_err());
+    }
+}
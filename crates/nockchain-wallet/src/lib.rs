@@ -0,0 +1,5 @@
+pub mod history;
+pub mod keys;
+pub mod scan;
+pub mod signer;
+pub mod tx;
@@ -1,17 +1,21 @@
 #![allow(clippy::doc_overindented_list_items)]
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::{Parser, Subcommand};
 use getrandom::getrandom;
 use nockapp::utils::bytes::Byts;
 use nockapp::{system_data_dir, CrownError, NockApp, NockAppError, ToBytesExt};
+use nockchain_wallet::history::{self, ExportFormat};
+use nockchain_wallet::scan::{NoteDb, NOTES_DB_FILE};
+use nockchain_wallet::tx;
 use nockvm::jets::cold::Nounable;
 use nockvm::noun::{Atom, Cell, IndirectAtom, Noun, D, SIG, T};
 use tokio::fs as tokio_fs;
 use tokio::net::UnixStream;
 use tracing::{error, info};
+use zkvm_jetpack::form::address;
 use zkvm_jetpack::hot::produce_prover_hot_state;
 
 mod error;
@@ -191,6 +195,23 @@ pub enum Commands {
 
     /// Show the master private key
     ShowMasterPrivkey,
+
+    /// Merge the signatures of copies of a draft signed by different keys
+    AggregateSignatures {
+        /// Paths to the signed copies of the draft (comma-separated)
+        #[arg(short, long)]
+        drafts: String,
+    },
+
+    /// Export the transaction history of the wallet
+    ExportHistory {
+        /// Output format, csv or json
+        #[arg(short, long, default_value = "csv")]
+        format: ExportFormat,
+        /// File to write the history to, printed if not given
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
 }
 
 impl Commands {
@@ -215,6 +236,8 @@ impl Commands {
             Commands::ShowSeedphrase => "show-seedphrase",
             Commands::ShowMasterPubkey => "show-master-pubkey",
             Commands::ShowMasterPrivkey => "show-master-privkey",
+            Commands::AggregateSignatures { .. } => "aggregate-signatures",
+            Commands::ExportHistory { .. } => "export-history",
         }
     }
 }
@@ -388,6 +411,36 @@ impl Wallet {
         )
     }
 
+    /// Merges the signatures of copies of a draft signed by different keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `draft_paths` - Paths to the signed copies of the draft
+    fn aggregate_signatures(draft_paths: &[String]) -> CommandNoun<NounSlab> {
+        let mut slab = NounSlab::new();
+
+        let mut drafts = Vec::with_capacity(draft_paths.len());
+        for path in draft_paths {
+            let draft_data = fs::read(path).map_err(|e| {
+                CrownError::Unknown(format!("Failed to read draft {}: {}", path, e))
+            })?;
+            let draft_noun = slab.cue_into(draft_data.as_bytes()?).map_err(|e| {
+                CrownError::Unknown(format!("Failed to decode draft {}: {}", path, e))
+            })?;
+            drafts.push(draft_noun);
+        }
+        let drafts_noun = drafts.into_iter().rev().fold(D(0), |acc, draft| {
+            Cell::new(&mut slab, draft, acc).as_noun()
+        });
+
+        Self::wallet(
+            "aggregate-signatures",
+            &[drafts_noun],
+            Operation::Poke,
+            &mut slab,
+        )
+    }
+
     /// Generates a master private key from a seed phrase.
     ///
     /// # Arguments
@@ -485,12 +538,12 @@ impl Wallet {
     ///
     /// * `names` - Comma-separated list of note name pairs in format "[first last]"
     ///             Example: "[first1 last1],[first2 last2]"
+    ///             Each half may be given as an address or as the kernel's base58
     ///
     /// * `recipients` - Comma-separated list of recipient $locks
     ///                 Example: "[1 pk1],[2 pk2,pk3,pk4]"
-    ///                 A simple comma-separated list is also supported: "pk1,pk2,pk3",
-    ///                 where it is presumed that all recipients are single-signature,
-    ///                 that is to say, it is the same as "[1 pk1],[1 pk2],[1 pk3]"
+    ///                 A bare public key is a single-signature lock, so "pk1,pk2,pk3"
+    ///                 is the same as "[1 pk1],[1 pk2],[1 pk3]", and the two forms mix
     ///
     /// * `gifts` - Comma-separated list of amounts to send to each recipient
     ///             Example: "100,200"
@@ -507,6 +560,7 @@ impl Wallet {
     ///
     /// Returns `NockAppError` if:
     /// - Name pairs are not properly formatted as "[first last]"
+    /// - A recipient is not a valid public key or m-of-n lock
     /// - Number of names, recipients, and gifts don't match
     /// - Any input parsing fails
     ///
@@ -537,7 +591,7 @@ impl Wallet {
                     let inner = &pair[1..pair.len() - 1];
                     let parts: Vec<&str> = inner.split_whitespace().collect();
                     if parts.len() == 2 {
-                        Some((parts[0].to_string(), parts[1].to_string()))
+                        Some((kernel_digest(parts[0]), kernel_digest(parts[1])))
                     } else {
                         None
                     }
@@ -547,40 +601,9 @@ impl Wallet {
             })
             .collect();
 
-        // Convert recipients to list of [number pubkeys] pairs
-        let recipients_vec: Vec<(u64, Vec<String>)> = if recipients.contains('[') {
-            // Parse complex format: "[1 pk1],[2 pk2,pk3,pk4]"
-            recipients
-                .split(',')
-                .filter_map(|pair| {
-                    let pair = pair.trim();
-                    if pair.starts_with('[') && pair.ends_with(']') {
-                        let inner = &pair[1..pair.len() - 1];
-                        let mut parts = inner.splitn(2, ' ');
-
-                        // Parse the number
-                        let number = parts.next()?.parse().ok()?;
-
-                        // Parse the pubkeys
-                        let pubkeys = parts
-                            .next()?
-                            .split(',')
-                            .map(|s| s.trim().to_string())
-                            .collect();
-
-                        Some((number, pubkeys))
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-        } else {
-            // Parse simple format: "pk1,pk2,pk3"
-            recipients
-                .split(',')
-                .map(|addr| (1, vec![addr.trim().to_string()]))
-                .collect()
-        };
+        // Recipients are public keys or m-of-n locks: "pk1,[2 pk2,pk3,pk4]"
+        let recipients_vec = tx::parse_locks(&recipients)
+            .map_err(|e| CrownError::Unknown(format!("Invalid recipients: {}", e)))?;
 
         let gifts_vec: Vec<u64> = gifts.split(',').filter_map(|s| s.parse().ok()).collect();
 
@@ -605,21 +628,11 @@ impl Wallet {
                 Cell::new(&mut slab, name_pair, acc).as_noun()
             });
 
-        // Convert recipients to list
-        let recipients_noun = recipients_vec
-            .into_iter()
-            .rev()
-            .fold(D(0), |acc, (num, pubkeys)| {
-                // Create the inner list of pubkeys
-                let pubkeys_noun = pubkeys.into_iter().rev().fold(D(0), |acc, pubkey| {
-                    let pubkey_noun = make_tas(&mut slab, &pubkey).as_noun();
-                    Cell::new(&mut slab, pubkey_noun, acc).as_noun()
-                });
-
-                // Create the pair of [number pubkeys_list]
-                let pair = T(&mut slab, &[D(num), pubkeys_noun]);
-                Cell::new(&mut slab, pair, acc).as_noun()
-            });
+        // Convert recipients to list of [m pubkeys] locks
+        let recipients_noun = recipients_vec.iter().rev().fold(D(0), |acc, lock| {
+            let lock_noun = lock.to_noun(&mut slab);
+            Cell::new(&mut slab, lock_noun, acc).as_noun()
+        });
 
         // Convert gifts to list
         let gifts_noun = gifts_vec.into_iter().rev().fold(D(0), |acc, amount| {
@@ -750,11 +763,39 @@ pub async fn wallet_data_dir() -> Result<PathBuf, NockAppError> {
     Ok(wallet_data_dir)
 }
 
+async fn export_history(format: ExportFormat, output: Option<&Path>) -> Result<(), NockAppError> {
+    let path = wallet_data_dir().await?.join(NOTES_DB_FILE);
+    let db = NoteDb::open(&path)
+        .map_err(|e| CrownError::Unknown(format!("Failed to open notes database: {}", e)))?;
+    let entries = history::history(&db);
+    let exported = history::export(&entries, format)
+        .map_err(|e| CrownError::Unknown(format!("Failed to export history: {}", e)))?;
+    match output {
+        Some(output) => {
+            tokio_fs::write(output, exported).await.map_err(|e| {
+                CrownError::Unknown(format!("Failed to write {}: {}", output.display(), e))
+            })?;
+            info!(
+                "exported {} history entries to {}",
+                entries.len(),
+                output.display()
+            );
+        }
+        None => print!("{exported}"),
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), NockAppError> {
     let cli = WalletCli::parse();
     boot::init_default_tracing(&cli.boot.clone()); // Init tracing early
 
+    // Reads the notes database only, no kernel needed
+    if let Commands::ExportHistory { format, output } = &cli.command {
+        return export_history(*format, output.as_deref()).await;
+    }
+
     let prover_hot_state = produce_prover_hot_state();
     let data_dir = wallet_data_dir().await?;
 
@@ -778,6 +819,7 @@ async fn main() -> Result<(), NockAppError> {
         | Commands::ImportKeys { .. }
         | Commands::ExportKeys
         | Commands::SignTx { .. }
+        | Commands::AggregateSignatures { .. }
         | Commands::MakeTx { .. }
         | Commands::GenMasterPrivkey { .. }
         | Commands::GenMasterPubkey { .. }
@@ -787,7 +829,8 @@ async fn main() -> Result<(), NockAppError> {
         | Commands::ShowSeedphrase
         | Commands::ShowMasterPubkey
         | Commands::ShowMasterPrivkey
-        | Commands::SimpleSpend { .. } => false,
+        | Commands::SimpleSpend { .. }
+        | Commands::ExportHistory { .. } => false,
 
         // All other commands DO need sync
         _ => true,
@@ -825,6 +868,10 @@ async fn main() -> Result<(), NockAppError> {
             Wallet::derive_child(key_type, *index)
         }
         Commands::SignTx { draft, index } => Wallet::sign_tx(draft, *index),
+        Commands::AggregateSignatures { drafts } => {
+            let paths: Vec<String> = drafts.split(',').map(|s| s.trim().to_string()).collect();
+            Wallet::aggregate_signatures(&paths)
+        }
         Commands::ImportKeys { input } => Wallet::import_keys(input),
         Commands::ExportKeys => Wallet::export_keys(),
         Commands::GenMasterPrivkey { seedphrase } => Wallet::gen_master_privkey(seedphrase),
@@ -862,6 +909,7 @@ async fn main() -> Result<(), NockAppError> {
         Commands::ShowSeedphrase => Wallet::show_seedphrase(),
         Commands::ShowMasterPubkey => Wallet::show_master_pubkey(),
         Commands::ShowMasterPrivkey => Wallet::show_master_privkey(),
+        Commands::ExportHistory { .. } => unreachable!("handled before boot"),
     }?;
 
     // If this command requires sync and we have a socket, wrap it with sync-run
@@ -909,6 +957,14 @@ async fn main() -> Result<(), NockAppError> {
     }
 }
 
+/// The kernel's base58 name for a digest given as an address. Anything that
+/// is not an address is passed through as is.
+fn kernel_digest(name: &str) -> String {
+    address::decode(name)
+        .map(|digest| address::to_kernel_base58(&digest))
+        .unwrap_or_else(|_| name.to_string())
+}
+
 pub fn from_bytes(stack: &mut NounSlab, bytes: &[u8]) -> Atom {
     unsafe {
         let mut tas_atom = IndirectAtom::new_raw_bytes(stack, bytes.len(), bytes.as_ptr());
@@ -923,10 +979,13 @@ pub fn from_bytes(stack: &mut NounSlab, bytes: &[u8]) -> Atom {
 mod tests {
     use std::sync::Once;
 
+    use ibig::UBig;
     use nockapp::kernel::boot::{self, Cli as BootCli};
     use nockapp::wire::SystemWire;
     use nockapp::{exit_driver, Bytes};
+    use nockchain_wallet::keys::PublicKey;
     use tokio::sync::mpsc;
+    use zkvm_jetpack::jets::cheetah_jets::{ch_scal_big, A_GEN};
 
     use super::*;
 
@@ -939,6 +998,14 @@ mod tests {
         });
     }
 
+    /// Base58 public key for the secret scalar `k`
+    fn test_pubkey(k: u64) -> String {
+        let point = ch_scal_big(&UBig::from(k), &A_GEN).expect("scalar multiplication");
+        PublicKey::from_point(point)
+            .expect("not the identity")
+            .to_base58()
+    }
+
     #[tokio::test]
     #[cfg_attr(miri, ignore)]
     async fn test_keygen() -> Result<(), NockAppError> {
@@ -1202,7 +1269,13 @@ mod tests {
         let mut wallet = Wallet::new(nockapp);
 
         let names = "[first1 last1],[first2 last2]".to_string();
-        let recipients = "[1 pk1],[2 pk2,pk3,pk4]".to_string();
+        let recipients = format!(
+            "[1 {}],[2 {},{},{}]",
+            test_pubkey(1),
+            test_pubkey(2),
+            test_pubkey(3),
+            test_pubkey(4)
+        );
         let gifts = "1,2".to_string();
         let fee = 1;
 
@@ -1233,7 +1306,7 @@ mod tests {
 
         // these should be valid names of notes in the wallet balance
         let names = "[Amt4GcpYievY4PXHfffiWriJ1sYfTXFkyQsGzbzwMVzewECWDV3Ad8Q BJnaDB3koU7ruYVdWCQqkFYQ9e3GXhFsDYjJ1vSmKFdxzf6Y87DzP4n]".to_string();
-        let recipients = "EHmKL2U3vXfS5GYAY5aVnGdukfDWwvkQPCZXnjvZVShsSQi3UAuA4tQ".to_string();
+        let recipients = test_pubkey(5);
         let gifts = "0".to_string();
         let fee = 0;
 
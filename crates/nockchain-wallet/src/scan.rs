@@ -0,0 +1,602 @@
+//! Balance and note scanning.
+//!
+//! A [`Scanner`] walks the blocks of a [`BlockSource`], normally a node's
+//! [`BlockStore`], and keeps every output the wallet can spend in a
+//! [`NoteDb`] on disk. An output belongs to the wallet when the wallet holds
+//! at least `m` of the keys of its `m`-of-`n` lock. Scanning resumes from the
+//! last block scanned, follows reorgs of up to [`REORG_WINDOW`] blocks and can
+//! be started over from any height with [`Scanner::rescan_from`].
+//!
+//! Pages carry the coinbase split of their block but only the ids of its
+//! transactions, so the outputs found in stored blocks are coinbase outputs.
+//! Spends are not visible in pages either: the wallet records what it sends
+//! with [`Scanner::record_sent`], and the scan notes the height at which the
+//! transaction's id shows up in a block.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bincode::config;
+use nockapp::noun::slab::NounSlab;
+use nockchain::block_store::{BlockStore, BlockStoreError};
+use nockchain::light_client::BlockId;
+use nockvm::jets::cold::Nounable;
+use nockvm::noun::{Noun, Slots};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, info, warn};
+use zkvm_jetpack::form::address::Digest;
+use zkvm_jetpack::jets::cheetah_jets::CheetahPoint;
+
+use crate::keys::PublicKey;
+
+/// Scanned blocks remembered to detect reorgs
+pub const REORG_WINDOW: usize = 100;
+
+/// Where the wallet keeps its notes database, under its data directory
+pub const NOTES_DB_FILE: &str = "notes.db";
+
+/// Axis of `tx-ids` in a page noun
+const TX_IDS_AXIS: u64 = 30;
+/// Axis of `coinbase` in a page noun
+const COINBASE_AXIS: u64 = 62;
+
+#[derive(Debug, Error)]
+pub enum ScanError {
+    #[error("block source error: {0}")]
+    Source(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("no block at height {0}")]
+    MissingBlock(u64),
+    #[error("malformed coinbase in page at height {0}")]
+    MalformedPage(u64),
+    #[error("chain reorganized below the scanned blocks kept, rescan from height {0} or lower")]
+    ReorgTooDeep(u64),
+    #[error("no note at height {} index {}", .0.height, .0.index)]
+    UnknownNote(NoteId),
+    #[error("failed to encode note database: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+    #[error("failed to decode note database: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Where the scanner reads blocks from
+pub trait BlockSource {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Height of the best block held
+    fn tip_height(&self) -> Option<u64>;
+    /// Digest of the block held at `height`
+    fn digest_at(&self, height: u64) -> Option<BlockId>;
+    /// Page of the block held at `height`
+    fn page(&self, height: u64) -> Result<Option<NounSlab>, Self::Error>;
+}
+
+impl BlockSource for BlockStore {
+    type Error = BlockStoreError;
+
+    fn tip_height(&self) -> Option<u64> {
+        BlockStore::tip_height(self)
+    }
+
+    fn digest_at(&self, height: u64) -> Option<BlockId> {
+        BlockStore::digest_at(self, height)
+    }
+
+    fn page(&self, height: u64) -> Result<Option<NounSlab>, BlockStoreError> {
+        self.get_slab(height)
+    }
+}
+
+/// An output, by the height of its block and its place in the block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct NoteId {
+    pub height: u64,
+    pub index: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnedNote {
+    pub id: NoteId,
+    pub block: BlockId,
+    pub amount: u64,
+    /// Signatures needed to spend the note
+    pub m: u64,
+    /// Base58 public keys of the lock, the wallet's and any others
+    pub pubkeys: Vec<String>,
+    pub coinbase: bool,
+    pub spent: bool,
+}
+
+/// A transaction the wallet sent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SentTx {
+    pub tx: Digest,
+    /// Notes it spends
+    pub notes: Vec<NoteId>,
+    /// Sent to others, not counting change or the fee
+    pub amount: u64,
+    pub fee: u64,
+    /// Height of the block that included it, once one has
+    pub height: Option<u64>,
+}
+
+/// Notes owned by the wallet and how far the chain has been scanned
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteDb {
+    /// Height the next scan starts at
+    next_height: u64,
+    /// The most recent blocks scanned, oldest first
+    recent: VecDeque<(u64, BlockId)>,
+    /// Notes in [`NoteId`] order
+    notes: Vec<OwnedNote>,
+    /// Transactions sent, oldest first
+    sent: Vec<SentTx>,
+}
+
+impl NoteDb {
+    /// Load the database at `path`, or an empty one if there is none
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ScanError> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(bincode::serde::decode_from_slice(&bytes, config::standard())?.0),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(NoteDb::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn notes(&self) -> &[OwnedNote] {
+        &self.notes
+    }
+
+    pub fn sent(&self) -> &[SentTx] {
+        &self.sent
+    }
+}
+
+/// What a call to [`Scanner::scan`] did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanReport {
+    pub blocks: u64,
+    pub found: usize,
+    /// Height the scan went back to after a reorg
+    pub reorg: Option<u64>,
+}
+
+pub struct Scanner {
+    keys: Vec<PublicKey>,
+    db: NoteDb,
+    path: PathBuf,
+}
+
+impl Scanner {
+    /// Scan for outputs to `keys`, keeping the notes database at `path`
+    pub fn open(keys: Vec<PublicKey>, path: impl AsRef<Path>) -> Result<Self, ScanError> {
+        let path = path.as_ref().to_path_buf();
+        let db = NoteDb::open(&path)?;
+        Ok(Self { keys, db, path })
+    }
+
+    pub fn db(&self) -> &NoteDb {
+        &self.db
+    }
+
+    /// Height of the last block scanned
+    pub fn scanned_height(&self) -> Option<u64> {
+        self.db.next_height.checked_sub(1)
+    }
+
+    pub fn notes(&self) -> &[OwnedNote] {
+        &self.db.notes
+    }
+
+    /// Sum of the unspent notes
+    pub fn balance(&self) -> u64 {
+        self.unspent().map(|note| note.amount).sum()
+    }
+
+    pub fn unspent(&self) -> impl Iterator<Item = &OwnedNote> {
+        self.db.notes.iter().filter(|note| !note.spent)
+    }
+
+    /// Scan the blocks of `source` above the last block scanned, first
+    /// going back to where `source` forked off the blocks scanned before
+    pub fn scan<B: BlockSource>(&mut self, source: &B) -> Result<ScanReport, ScanError> {
+        let mut report = ScanReport::default();
+        if let Some(fork) = self.find_fork(source)? {
+            warn!("chain reorganized, rescanning from height {fork}");
+            self.db.recent.retain(|(height, _)| *height < fork);
+            self.drop_from(fork);
+            report.reorg = Some(fork);
+        }
+
+        if let Some(tip) = source.tip_height() {
+            for height in self.db.next_height..=tip {
+                let digest = source
+                    .digest_at(height)
+                    .ok_or(ScanError::MissingBlock(height))?;
+                let page = source
+                    .page(height)
+                    .map_err(|e| ScanError::Source(Box::new(e)))?
+                    .ok_or(ScanError::MissingBlock(height))?;
+                let found = self.scan_page(height, digest, &page)?;
+                if found > 0 {
+                    debug!("found {found} notes at height {height}");
+                }
+                report.found += found;
+                report.blocks += 1;
+
+                self.db.next_height = height + 1;
+                self.db.recent.push_back((height, digest));
+                if self.db.recent.len() > REORG_WINDOW {
+                    self.db.recent.pop_front();
+                }
+            }
+            if report.blocks > 0 {
+                info!(
+                    "scanned {} blocks up to height {tip}, found {} notes",
+                    report.blocks, report.found
+                );
+            }
+        }
+        self.save()?;
+        Ok(report)
+    }
+
+    /// Forget the notes at or above `height`, the next scan starts there
+    pub fn rescan_from(&mut self, height: u64) -> Result<(), ScanError> {
+        if height < self.db.next_height {
+            self.db.recent.retain(|(scanned, _)| *scanned < height);
+            self.drop_from(height);
+        }
+        self.save()
+    }
+
+    /// Record that the wallet spent a note
+    pub fn mark_spent(&mut self, id: NoteId) -> Result<(), ScanError> {
+        self.note_mut(id)?.spent = true;
+        self.save()
+    }
+
+    /// Record that the wallet sent transaction `tx`, spending `notes`
+    pub fn record_sent(
+        &mut self,
+        tx: Digest,
+        notes: Vec<NoteId>,
+        amount: u64,
+        fee: u64,
+    ) -> Result<(), ScanError> {
+        if let Some(id) = notes
+            .iter()
+            .find(|id| !self.db.notes.iter().any(|note| note.id == **id))
+        {
+            return Err(ScanError::UnknownNote(*id));
+        }
+        for id in &notes {
+            self.note_mut(*id)?.spent = true;
+        }
+        self.db.sent.push(SentTx {
+            tx,
+            notes,
+            amount,
+            fee,
+            height: None,
+        });
+        self.save()
+    }
+
+    fn note_mut(&mut self, id: NoteId) -> Result<&mut OwnedNote, ScanError> {
+        self.db
+            .notes
+            .iter_mut()
+            .find(|note| note.id == id)
+            .ok_or(ScanError::UnknownNote(id))
+    }
+
+    /// Lowest height at which `source` no longer holds the block scanned
+    fn find_fork<B: BlockSource>(&self, source: &B) -> Result<Option<u64>, ScanError> {
+        let recent = &self.db.recent;
+        let Some(&(oldest, _)) = recent.front() else {
+            return Ok(None);
+        };
+        match recent
+            .iter()
+            .rposition(|(height, digest)| source.digest_at(*height) == Some(*digest))
+        {
+            Some(kept) => Ok(recent.get(kept + 1).map(|(height, _)| *height)),
+            None if oldest == 0 => Ok(Some(0)),
+            None => Err(ScanError::ReorgTooDeep(oldest)),
+        }
+    }
+
+    fn drop_from(&mut self, height: u64) {
+        self.db.notes.retain(|note| note.id.height < height);
+        for sent in &mut self.db.sent {
+            if sent.height.is_some_and(|included| included >= height) {
+                sent.height = None;
+            }
+        }
+        self.db.next_height = self.db.next_height.min(height);
+    }
+
+    fn scan_page(
+        &mut self,
+        height: u64,
+        block: BlockId,
+        page: &NounSlab,
+    ) -> Result<usize, ScanError> {
+        let root = unsafe { page.root() };
+        let mut outputs = Vec::new();
+        let mut scratch = NounSlab::new();
+        root.slot(COINBASE_AXIS)
+            .ok()
+            .and_then(|coinbase| coinbase_outputs(&mut scratch, coinbase, &mut outputs))
+            .ok_or(ScanError::MalformedPage(height))?;
+        let mut tx_ids = Vec::new();
+        root.slot(TX_IDS_AXIS)
+            .ok()
+            .and_then(|ids| digests(ids, &mut tx_ids))
+            .ok_or(ScanError::MalformedPage(height))?;
+
+        for sent in &mut self.db.sent {
+            if sent.height.is_none() && tx_ids.contains(&sent.tx) {
+                debug!("sent transaction included at height {height}");
+                sent.height = Some(height);
+            }
+        }
+
+        let mut found = 0;
+        for (index, (m, pubkeys, amount)) in outputs.into_iter().enumerate() {
+            let held = pubkeys.iter().filter(|key| self.keys.contains(key)).count();
+            if m == 0 || (held as u64) < m {
+                continue;
+            }
+            self.db.notes.push(OwnedNote {
+                id: NoteId {
+                    height,
+                    index: index as u32,
+                },
+                block,
+                amount,
+                m,
+                pubkeys: pubkeys.iter().map(PublicKey::to_base58).collect(),
+                coinbase: true,
+                spent: false,
+            });
+            found += 1;
+        }
+        Ok(found)
+    }
+
+    fn save(&self) -> Result<(), ScanError> {
+        let bytes = bincode::serde::encode_to_vec(&self.db, config::standard())?;
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Walk a coinbase split, `(z-map lock coins)`, collecting `[m pubkeys
+/// amount]` for each lock
+fn coinbase_outputs(
+    scratch: &mut NounSlab,
+    tree: Noun,
+    outputs: &mut Vec<(u64, Vec<PublicKey>, u64)>,
+) -> Option<()> {
+    let Ok(node) = tree.as_cell() else {
+        return (tree.as_atom().ok()?.as_u64().ok()? == 0).then_some(());
+    };
+    let entry = node.head();
+    let lock = entry.slot(2).ok()?;
+    let m = lock.slot(2).ok()?.as_atom().ok()?.as_u64().ok()?;
+    let mut pubkeys = Vec::new();
+    lock_pubkeys(scratch, lock.slot(3).ok()?, &mut pubkeys)?;
+    let amount = entry.slot(3).ok()?.as_atom().ok()?.as_u64().ok()?;
+    outputs.push((m, pubkeys, amount));
+
+    coinbase_outputs(scratch, node.tail().slot(2).ok()?, outputs)?;
+    coinbase_outputs(scratch, node.tail().slot(3).ok()?, outputs)
+}
+
+/// Walk a `(z-set tx-id)`
+fn digests(tree: Noun, out: &mut Vec<Digest>) -> Option<()> {
+    let Ok(node) = tree.as_cell() else {
+        return (tree.as_atom().ok()?.as_u64().ok()? == 0).then_some(());
+    };
+    let mut digest = [0u64; 5];
+    let mut rest = node.head();
+    for (i, belt) in digest.iter_mut().enumerate() {
+        let elem = if i == 4 {
+            rest
+        } else {
+            let cell = rest.as_cell().ok()?;
+            rest = cell.tail();
+            cell.head()
+        };
+        *belt = elem.as_atom().ok()?.as_u64().ok()?;
+    }
+    out.push(digest);
+    digests(node.tail().slot(2).ok()?, out)?;
+    digests(node.tail().slot(3).ok()?, out)
+}
+
+/// Walk a `(z-set schnorr-pubkey)`
+fn lock_pubkeys(scratch: &mut NounSlab, tree: Noun, pubkeys: &mut Vec<PublicKey>) -> Option<()> {
+    let Ok(node) = tree.as_cell() else {
+        return (tree.as_atom().ok()?.as_u64().ok()? == 0).then_some(());
+    };
+    let point = CheetahPoint::from_noun(scratch, &node.head()).ok()?;
+    pubkeys.extend(PublicKey::from_point(point));
+    lock_pubkeys(scratch, node.tail().slot(2).ok()?, pubkeys)?;
+    lock_pubkeys(scratch, node.tail().slot(3).ok()?, pubkeys)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::convert::Infallible;
+
+    use ibig::UBig;
+    use nockvm::noun::{D, T};
+    use zkvm_jetpack::jets::cheetah_jets::{ch_scal_big, A_GEN};
+
+    use super::*;
+
+    fn key(k: u64) -> PublicKey {
+        PublicKey::from_point(ch_scal_big(&UBig::from(k), &A_GEN).unwrap()).unwrap()
+    }
+
+    /// Blocks by height, each a digest and the `(m, keys, amount)` of its
+    /// coinbase outputs
+    #[derive(Default)]
+    struct Chain {
+        blocks: BTreeMap<u64, (BlockId, Vec<(u64, Vec<PublicKey>, u64)>)>,
+        txs: BTreeMap<u64, Vec<Digest>>,
+    }
+
+    impl Chain {
+        fn put(&mut self, height: u64, fork: u64, outputs: Vec<(u64, Vec<PublicKey>, u64)>) {
+            self.blocks
+                .insert(height, ([height, fork, 0, 0, 0], outputs));
+        }
+    }
+
+    /// A z-tree of `items` in a line down the left
+    fn tree(slab: &mut NounSlab, items: Vec<Noun>) -> Noun {
+        items
+            .into_iter()
+            .fold(D(0), |left, item| T(slab, &[item, left, D(0)]))
+    }
+
+    impl BlockSource for Chain {
+        type Error = Infallible;
+
+        fn tip_height(&self) -> Option<u64> {
+            self.blocks.keys().next_back().copied()
+        }
+
+        fn digest_at(&self, height: u64) -> Option<BlockId> {
+            self.blocks.get(&height).map(|(digest, _)| *digest)
+        }
+
+        fn page(&self, height: u64) -> Result<Option<NounSlab>, Infallible> {
+            let Some((digest, outputs)) = self.blocks.get(&height) else {
+                return Ok(None);
+            };
+            let mut slab = NounSlab::new();
+            let entries = outputs
+                .iter()
+                .map(|(m, keys, amount)| {
+                    let points = keys
+                        .iter()
+                        .map(|k| k.point().into_noun(&mut slab))
+                        .collect();
+                    let pubkeys = tree(&mut slab, points);
+                    T(&mut slab, &[D(*m), pubkeys, D(*amount)])
+                })
+                .collect();
+            let coinbase = tree(&mut slab, entries);
+            let mut digest_noun = |digest: &Digest| {
+                let [a, b, c, d, e] = digest.map(D);
+                T(&mut slab, &[a, b, c, d, e])
+            };
+            let id = digest_noun(digest);
+            let tx_ids = self
+                .txs
+                .get(&height)
+                .into_iter()
+                .flatten()
+                .map(&mut digest_noun)
+                .collect::<Vec<_>>();
+            let tx_ids = tree(&mut slab, tx_ids);
+            let page = T(&mut slab, &[id, D(0), D(0), tx_ids, coinbase, D(0)]);
+            slab.set_root(page);
+            Ok(Some(slab))
+        }
+    }
+
+    #[test]
+    fn test_scan_finds_owned_outputs_and_follows_reorgs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.db");
+        let (ours, theirs, cosigner) = (key(11), key(22), key(33));
+
+        let mut chain = Chain::default();
+        chain.put(0, 0, vec![]);
+        chain.put(1, 0, vec![(1, vec![ours], 50), (1, vec![theirs], 60)]);
+        chain.put(2, 0, vec![(2, vec![ours, cosigner], 70)]);
+        chain.put(3, 0, vec![(1, vec![cosigner, ours], 7)]);
+
+        let mut scanner = Scanner::open(vec![ours], &path).unwrap();
+        let report = scanner.scan(&chain).unwrap();
+        assert_eq!((report.blocks, report.found, report.reorg), (4, 2, None));
+        assert_eq!(scanner.balance(), 57);
+        assert_eq!(scanner.notes()[1].pubkeys.len(), 2);
+
+        // reopened, it picks up where it left off
+        let mut scanner = Scanner::open(vec![ours], &path).unwrap();
+        assert_eq!(scanner.scanned_height(), Some(3));
+        assert_eq!(scanner.scan(&chain).unwrap().blocks, 0);
+
+        chain.put(3, 1, vec![]);
+        chain.put(4, 1, vec![(1, vec![ours], 5)]);
+        let report = scanner.scan(&chain).unwrap();
+        assert_eq!((report.blocks, report.reorg), (2, Some(3)));
+        assert_eq!(scanner.balance(), 55);
+
+        let first = scanner.notes()[0].id;
+        assert_eq!(
+            first,
+            NoteId {
+                height: 1,
+                index: 1
+            }
+        );
+        scanner.mark_spent(first).unwrap();
+        assert_eq!(scanner.balance(), 5);
+
+        scanner.rescan_from(2).unwrap();
+        assert_eq!(scanner.scanned_height(), Some(1));
+        assert_eq!(scanner.scan(&chain).unwrap().blocks, 3);
+        assert_eq!(scanner.balance(), 5);
+    }
+
+    #[test]
+    fn test_sent_transactions_track_inclusion() {
+        let dir = tempfile::tempdir().unwrap();
+        let ours = key(11);
+        let mut chain = Chain::default();
+        chain.put(0, 0, vec![(1, vec![ours], 40)]);
+
+        let mut scanner = Scanner::open(vec![ours], dir.path().join("notes.db")).unwrap();
+        scanner.scan(&chain).unwrap();
+        let note = scanner.notes()[0].id;
+        let tx = [9, 8, 7, 6, 5];
+        let missing = NoteId {
+            height: 5,
+            index: 0,
+        };
+        assert!(matches!(
+            scanner.record_sent(tx, vec![note, missing], 30, 2),
+            Err(ScanError::UnknownNote(id)) if id == missing
+        ));
+        assert_eq!(scanner.balance(), 40);
+        scanner.record_sent(tx, vec![note], 30, 2).unwrap();
+        assert_eq!(scanner.balance(), 0);
+        assert_eq!(scanner.db().sent()[0].height, None);
+
+        chain.put(1, 0, vec![]);
+        chain.txs.insert(1, vec![[1, 1, 1, 1, 1], tx]);
+        scanner.scan(&chain).unwrap();
+        assert_eq!(scanner.db().sent()[0].height, Some(1));
+
+        // reorged out, it is pending again until a block includes it
+        chain.put(1, 1, vec![]);
+        chain.txs.clear();
+        scanner.scan(&chain).unwrap();
+        assert_eq!(scanner.db().sent()[0].height, None);
+    }
+}
@@ -0,0 +1,394 @@
+//! Transaction signing behind a [`Signer`].
+//!
+//! Whatever builds a transaction only needs the public keys it spends from
+//! and a way to get a schnorr signature over a tip5 digest, so raw keys can
+//! live in another process or on a hardware device. [`LocalSigner`] holds
+//! keys in memory. [`ExternalSigner`] asks another process over a line
+//! based protocol, and [`serve`] answers that protocol for any signer, which
+//! is all a device bridge has to implement.
+//!
+//! Each line is a JSON object with an `id` the answer repeats:
+//!
+//! ```text
+//! > {"id":1,"method":"public-keys"}
+//! < {"id":1,"public-keys":["<base58>", ...]}
+//! > {"id":2,"method":"sign","pubkey":"<base58>","message":[1,2,3,4,5]}
+//! < {"id":2,"signature":{"chal":"<hex>","sig":"<hex>"}}
+//! < {"id":2,"error":"<reason>"}
+//! ```
+//!
+//! Signatures that come back are checked before they are handed on.
+
+use std::io;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use futures::future::BoxFuture;
+use ibig::UBig;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+use zkvm_jetpack::jets::cheetah_jets::{schnorr_sign, schnorr_verify};
+
+use crate::keys::{ExtendedPrivateKey, KeyError, PublicKey};
+
+/// What gets signed, a tip5 digest
+pub type Message = [u64; 5];
+
+#[derive(Debug, Error)]
+pub enum SignerError {
+    #[error("signer has no key {0}")]
+    UnknownKey(String),
+    #[error("signer refused: {0}")]
+    Refused(String),
+    #[error("signer returned an invalid signature")]
+    BadSignature,
+    #[error("signer protocol error: {0}")]
+    Protocol(String),
+    #[error("signer went away")]
+    Closed,
+    #[error(transparent)]
+    Key(#[from] KeyError),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// A schnorr signature, as `+sign:affine:schnorr` returns it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub chal: UBig,
+    pub sig: UBig,
+}
+
+impl Signature {
+    /// Whether this is `pubkey`'s signature over `message`
+    pub fn verify(&self, pubkey: &PublicKey, message: &Message) -> bool {
+        schnorr_verify(pubkey.point(), message, &self.chal, &self.sig).unwrap_or(false)
+    }
+}
+
+pub trait Signer: Send + Sync {
+    /// Keys this signer can sign with
+    fn public_keys(&self) -> BoxFuture<'_, Result<Vec<PublicKey>, SignerError>>;
+
+    /// Sign `message` with the key behind `pubkey`
+    fn sign<'a>(
+        &'a self,
+        pubkey: &'a PublicKey,
+        message: &'a Message,
+    ) -> BoxFuture<'a, Result<Signature, SignerError>>;
+}
+
+/// Signs with keys held in this process
+pub struct LocalSigner {
+    keys: Vec<(PublicKey, ExtendedPrivateKey)>,
+}
+
+impl LocalSigner {
+    pub fn new(keys: Vec<ExtendedPrivateKey>) -> Result<Self, SignerError> {
+        let keys = keys
+            .into_iter()
+            .map(|key| Ok((key.public_key()?, key)))
+            .collect::<Result<_, KeyError>>()?;
+        Ok(Self { keys })
+    }
+}
+
+impl Signer for LocalSigner {
+    fn public_keys(&self) -> BoxFuture<'_, Result<Vec<PublicKey>, SignerError>> {
+        let keys = self.keys.iter().map(|(public, _)| *public).collect();
+        Box::pin(async move { Ok(keys) })
+    }
+
+    fn sign<'a>(
+        &'a self,
+        pubkey: &'a PublicKey,
+        message: &'a Message,
+    ) -> BoxFuture<'a, Result<Signature, SignerError>> {
+        Box::pin(async move {
+            let (_, key) = self
+                .keys
+                .iter()
+                .find(|(public, _)| public == pubkey)
+                .ok_or_else(|| SignerError::UnknownKey(pubkey.to_base58()))?;
+            let secret = UBig::from_be_bytes(&key.secret()[..]);
+            let (chal, sig) = schnorr_sign(&secret, message)
+                .map_err(|_| SignerError::Refused("key cannot sign".into()))?;
+            Ok(Signature { chal, sig })
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "kebab-case")]
+enum Request {
+    PublicKeys,
+    Sign { pubkey: String, message: Message },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Response {
+    PublicKeys(Vec<String>),
+    Signature { chal: String, sig: String },
+    Error(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope<T> {
+    id: u64,
+    #[serde(flatten)]
+    body: T,
+}
+
+type Reader = Box<dyn AsyncRead + Send + Unpin>;
+type Writer = Box<dyn AsyncWrite + Send + Unpin>;
+
+struct Connection {
+    lines: Lines<BufReader<Reader>>,
+    writer: Writer,
+}
+
+/// Signs by asking another process, see the [module docs](self)
+pub struct ExternalSigner {
+    connection: Mutex<Connection>,
+    next_id: AtomicU64,
+    /// The signer process, if we started it
+    _child: Option<Child>,
+}
+
+impl ExternalSigner {
+    /// Talk to a signer that reads requests from `writer` and answers on
+    /// `reader`
+    pub fn new(
+        reader: impl AsyncRead + Send + Unpin + 'static,
+        writer: impl AsyncWrite + Send + Unpin + 'static,
+    ) -> Self {
+        let reader: Reader = Box::new(reader);
+        Self {
+            connection: Mutex::new(Connection {
+                lines: BufReader::new(reader).lines(),
+                writer: Box::new(writer),
+            }),
+            next_id: AtomicU64::new(1),
+            _child: None,
+        }
+    }
+
+    /// Start `command` and talk to it over its stdin and stdout. It is
+    /// killed when the signer is dropped.
+    pub fn spawn(mut command: Command) -> Result<Self, SignerError> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or(SignerError::Closed)?;
+        let stdout = child.stdout.take().ok_or(SignerError::Closed)?;
+        Ok(Self {
+            _child: Some(child),
+            ..Self::new(stdout, stdin)
+        })
+    }
+
+    async fn call(&self, request: Request) -> Result<Response, SignerError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut line = serde_json::to_string(&Envelope { id, body: request })?;
+        line.push('\n');
+
+        let mut connection = self.connection.lock().await;
+        connection.writer.write_all(line.as_bytes()).await?;
+        connection.writer.flush().await?;
+        loop {
+            let line = connection
+                .lines
+                .next_line()
+                .await?
+                .ok_or(SignerError::Closed)?;
+            let answer: Envelope<Response> = serde_json::from_str(&line)?;
+            if answer.id == id {
+                return match answer.body {
+                    Response::Error(reason) => Err(SignerError::Refused(reason)),
+                    body => Ok(body),
+                };
+            }
+            // an answer to a call whose future was dropped
+            debug!("skipping signer answer to request {}", answer.id);
+        }
+    }
+}
+
+impl Signer for ExternalSigner {
+    fn public_keys(&self) -> BoxFuture<'_, Result<Vec<PublicKey>, SignerError>> {
+        Box::pin(async move {
+            match self.call(Request::PublicKeys).await? {
+                Response::PublicKeys(keys) => keys
+                    .iter()
+                    .map(|key| PublicKey::from_base58(key).map_err(Into::into))
+                    .collect(),
+                other => Err(unexpected(other)),
+            }
+        })
+    }
+
+    fn sign<'a>(
+        &'a self,
+        pubkey: &'a PublicKey,
+        message: &'a Message,
+    ) -> BoxFuture<'a, Result<Signature, SignerError>> {
+        Box::pin(async move {
+            let request = Request::Sign {
+                pubkey: pubkey.to_base58(),
+                message: *message,
+            };
+            let (chal, sig) = match self.call(request).await? {
+                Response::Signature { chal, sig } => (chal, sig),
+                other => return Err(unexpected(other)),
+            };
+            let parse =
+                |hex: &str| UBig::from_str_radix(hex, 16).map_err(|_| SignerError::BadSignature);
+            let signature = Signature {
+                chal: parse(&chal)?,
+                sig: parse(&sig)?,
+            };
+            if !signature.verify(pubkey, message) {
+                return Err(SignerError::BadSignature);
+            }
+            Ok(signature)
+        })
+    }
+}
+
+fn unexpected(response: Response) -> SignerError {
+    SignerError::Protocol(format!("unexpected answer {response:?}"))
+}
+
+/// Answer the signer protocol on `reader` and `writer` with `signer`
+/// until `reader` is closed
+pub async fn serve<S: Signer + ?Sized>(
+    signer: &S,
+    reader: impl AsyncRead + Unpin,
+    mut writer: impl AsyncWrite + Unpin,
+) -> Result<(), SignerError> {
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let request: Envelope<Request> = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                warn!("ignoring malformed signer request: {e}");
+                continue;
+            }
+        };
+        let body = match answer(signer, request.body).await {
+            Ok(body) => body,
+            Err(e) => Response::Error(e.to_string()),
+        };
+        let mut line = serde_json::to_string(&Envelope {
+            id: request.id,
+            body,
+        })?;
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await?;
+        writer.flush().await?;
+    }
+    Ok(())
+}
+
+async fn answer<S: Signer + ?Sized>(signer: &S, request: Request) -> Result<Response, SignerError> {
+    match request {
+        Request::PublicKeys => {
+            let keys = signer.public_keys().await?;
+            Ok(Response::PublicKeys(
+                keys.iter().map(PublicKey::to_base58).collect(),
+            ))
+        }
+        Request::Sign { pubkey, message } => {
+            let pubkey = PublicKey::from_base58(&pubkey)?;
+            let signature = signer.sign(&pubkey, &message).await?;
+            Ok(Response::Signature {
+                chal: format!("{:x}", signature.chal),
+                sig: format!("{:x}", signature.sig),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::duplex;
+
+    use super::*;
+    use crate::keys::HARDENED;
+
+    fn local() -> LocalSigner {
+        let master = ExtendedPrivateKey::from_parts([7; 32], [9; 32]);
+        let keys = (0..2)
+            .map(|i| master.derive_child(HARDENED + i).unwrap())
+            .collect();
+        LocalSigner::new(keys).unwrap()
+    }
+
+    /// An external signer talking to `local()` in a task
+    fn external() -> ExternalSigner {
+        let (ours, theirs) = duplex(4096);
+        let (their_reader, their_writer) = tokio::io::split(theirs);
+        tokio::spawn(async move { serve(&local(), their_reader, their_writer).await });
+        let (reader, writer) = tokio::io::split(ours);
+        ExternalSigner::new(reader, writer)
+    }
+
+    #[tokio::test]
+    async fn test_external_signer_signs_like_local() {
+        let signer = external();
+        let keys = signer.public_keys().await.unwrap();
+        assert_eq!(keys, local().public_keys().await.unwrap());
+
+        let message = [1, 2, 3, 4, 5];
+        let signature = signer.sign(&keys[1], &message).await.unwrap();
+        assert!(signature.verify(&keys[1], &message));
+        assert!(!signature.verify(&keys[0], &message));
+        assert!(!signature.verify(&keys[1], &[1, 2, 3, 4, 6]));
+
+        let stranger = ExtendedPrivateKey::from_parts([3; 32], [9; 32])
+            .public_key()
+            .unwrap();
+        assert!(matches!(
+            signer.sign(&stranger, &message).await,
+            Err(SignerError::Refused(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_external_signatures_are_checked() {
+        let (ours, theirs) = duplex(4096);
+        let (reader, mut writer) = tokio::io::split(theirs);
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(reader).lines();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let request: Envelope<Request> = serde_json::from_str(&line).unwrap();
+                let forged = Envelope {
+                    id: request.id,
+                    body: Response::Signature {
+                        chal: "1".into(),
+                        sig: "2".into(),
+                    },
+                };
+                let line = serde_json::to_string(&forged).unwrap() + "\n";
+                writer.write_all(line.as_bytes()).await.unwrap();
+            }
+        });
+        let (reader, writer) = tokio::io::split(ours);
+        let signer = ExternalSigner::new(reader, writer);
+
+        let key = local().public_keys().await.unwrap()[0];
+        assert!(matches!(
+            signer.sign(&key, &[1, 2, 3, 4, 5]).await,
+            Err(SignerError::BadSignature)
+        ));
+    }
+}
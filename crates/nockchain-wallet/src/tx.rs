@@ -0,0 +1,213 @@
+//! Locks for transaction outputs, as the wallet kernel takes them.
+//!
+//! An output is locked to `m` of `n` public keys: spending it takes
+//! signatures from at least `m` of them. A single key is the 1-of-1 case.
+//! Recipients are written either as a bare base58 public key or as
+//! `[m pk1,pk2,...]`, and a list of recipients separates them with commas
+//! outside brackets, so `pk1,[2 pk2,pk3,pk4]` is two recipients.
+//!
+//! Notes locked to several keys are spent by passing a draft around: each
+//! cosigner runs `sign-tx` on it with their key, then `aggregate-signatures`
+//! merges the signed copies into one draft for `make-tx`.
+
+use std::str::FromStr;
+
+use nockapp::noun::slab::NounSlab;
+use nockapp::utils::make_tas;
+use nockvm::noun::{Noun, D, T};
+use thiserror::Error;
+
+use crate::keys::{KeyError, PublicKey};
+
+/// Most keys, and so most signatures required, a lock can have
+pub const MAX_LOCK_KEYS: usize = 255;
+
+#[derive(Debug, Error)]
+pub enum LockError {
+    #[error("malformed recipient {0:?}, expected a public key or [m pk1,pk2,...]")]
+    Syntax(String),
+    #[error("lock requires {m} signatures of {n} keys")]
+    Threshold { m: u64, n: usize },
+    #[error("lock has {0} keys, at most {} are allowed", MAX_LOCK_KEYS)]
+    TooManyKeys(usize),
+    #[error("public key {0} appears twice in the lock")]
+    DuplicateKey(String),
+    #[error("invalid public key {key:?}: {source}")]
+    Key {
+        key: String,
+        #[source]
+        source: KeyError,
+    },
+}
+
+/// `m` of the public keys `pubkeys`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lock {
+    m: u64,
+    pubkeys: Vec<PublicKey>,
+}
+
+impl Lock {
+    pub fn single(pubkey: PublicKey) -> Self {
+        Lock {
+            m: 1,
+            pubkeys: vec![pubkey],
+        }
+    }
+
+    /// Checks the same bounds as `m-of-n:new:lock`, and that there are at
+    /// least `m` keys to sign with
+    pub fn m_of_n(m: u64, pubkeys: Vec<PublicKey>) -> Result<Self, LockError> {
+        let n = pubkeys.len();
+        if n > MAX_LOCK_KEYS {
+            return Err(LockError::TooManyKeys(n));
+        }
+        if m == 0 || n == 0 || m as usize > n {
+            return Err(LockError::Threshold { m, n });
+        }
+        for (i, key) in pubkeys.iter().enumerate() {
+            if pubkeys[..i].contains(key) {
+                return Err(LockError::DuplicateKey(key.to_base58()));
+            }
+        }
+        Ok(Lock { m, pubkeys })
+    }
+
+    /// Signatures needed to spend
+    pub fn m(&self) -> u64 {
+        self.m
+    }
+
+    pub fn pubkeys(&self) -> &[PublicKey] {
+        &self.pubkeys
+    }
+
+    /// `[m pks=(list @t)]`, the recipient of `simple-spend` and
+    /// `set-recipient`
+    pub fn to_noun(&self, slab: &mut NounSlab) -> Noun {
+        let pubkeys = self.pubkeys.iter().rev().fold(D(0), |list, key| {
+            let key = make_tas(slab, &key.to_base58()).as_noun();
+            T(slab, &[key, list])
+        });
+        T(slab, &[D(self.m), pubkeys])
+    }
+}
+
+impl FromStr for Lock {
+    type Err = LockError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let parse_key = |key: &str| {
+            PublicKey::from_base58(key).map_err(|source| LockError::Key {
+                key: key.to_string(),
+                source,
+            })
+        };
+        let Some(inner) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) else {
+            if s.is_empty() || s.contains([',', ' ', '[', ']']) {
+                return Err(LockError::Syntax(s.to_string()));
+            }
+            return Ok(Lock::single(parse_key(s)?));
+        };
+        let (m, keys) = inner
+            .trim()
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| LockError::Syntax(s.to_string()))?;
+        let m = m.parse().map_err(|_| LockError::Syntax(s.to_string()))?;
+        let pubkeys = keys
+            .split(',')
+            .map(|key| parse_key(key.trim()))
+            .collect::<Result<_, _>>()?;
+        Lock::m_of_n(m, pubkeys)
+    }
+}
+
+/// Parse a comma separated list of recipients
+pub fn parse_locks(s: &str) -> Result<Vec<Lock>, LockError> {
+    let mut locks = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| LockError::Syntax(s.to_string()))?
+            }
+            ',' if depth == 0 => {
+                locks.push(s[start..i].parse()?);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err(LockError::Syntax(s.to_string()));
+    }
+    locks.push(s[start..].parse()?);
+    Ok(locks)
+}
+
+#[cfg(test)]
+mod tests {
+    use ibig::UBig;
+    use nockapp::AtomExt;
+    use nockvm::noun::Slots;
+    use zkvm_jetpack::jets::cheetah_jets::{ch_scal_big, A_GEN};
+
+    use super::*;
+
+    fn key(k: u64) -> PublicKey {
+        PublicKey::from_point(ch_scal_big(&UBig::from(k), &A_GEN).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_multisig_recipients() {
+        let [a, b, c] = [key(3), key(5), key(7)].map(|k| k.to_base58());
+        let locks = parse_locks(&format!("{a}, [2 {b},{c}, {a}],{c}")).unwrap();
+        assert_eq!(locks.len(), 3);
+        assert_eq!(locks[0], Lock::single(key(3)));
+        assert_eq!(locks[1].m(), 2);
+        assert_eq!(locks[1].pubkeys(), &[key(5), key(7), key(3)]);
+        assert_eq!(locks[2], Lock::single(key(7)));
+
+        assert!(matches!(
+            parse_locks(&format!("[3 {a},{b}]")),
+            Err(LockError::Threshold { m: 3, n: 2 })
+        ));
+        assert!(matches!(
+            parse_locks(&format!("[0 {a}]")),
+            Err(LockError::Threshold { m: 0, n: 1 })
+        ));
+        assert!(matches!(
+            parse_locks(&format!("[1 {a},{a}]")),
+            Err(LockError::DuplicateKey(_))
+        ));
+        assert!(matches!(
+            parse_locks(&format!("[1 {a}")),
+            Err(LockError::Syntax(_))
+        ));
+        assert!(matches!(
+            parse_locks("[1 notakey]"),
+            Err(LockError::Key { .. })
+        ));
+    }
+
+    #[test]
+    fn test_lock_noun() {
+        let lock = Lock::m_of_n(2, vec![key(3), key(5)]).unwrap();
+        let mut slab = NounSlab::new();
+        let noun = lock.to_noun(&mut slab);
+        let m = noun.slot(2).unwrap().as_atom().unwrap().as_u64().unwrap();
+        assert_eq!(m, 2);
+        let first = noun.slot(6).unwrap().as_atom().unwrap();
+        assert_eq!(first.into_string().unwrap(), key(3).to_base58());
+        assert!(noun
+            .slot(15)
+            .unwrap()
+            .as_atom()
+            .is_ok_and(|tail| tail.as_u64() == Ok(0)));
+    }
+}
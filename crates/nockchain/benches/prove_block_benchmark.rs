@@ -1,120 +1,90 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use kernels::miner::KERNEL;
-use nockapp::kernel::checkpoint::JamPaths;
-use nockapp::kernel::form::Kernel;
-use nockapp::noun::slab::NounSlab;
-use nockapp::wire::Wire;
-use nockvm::noun::{D, T};
-use nockvm_macros::tas;
-use std::time::Duration;
-use tempfile::tempdir;
-use zkvm_jetpack::hot::produce_prover_hot_state;
+//! Criterion benchmark of the prove poke.
+//!
+//! The miner kernel is booted once per length and reused for every sample,
+//! so criterion measures proving rather than kernel setup. Built with
+//! `--features dev-proving` the benchmark proves with the INSECURE dev
+//! kernel at short lengths, which keeps a full run to minutes.
 
-/// Wire type for mining operations
-pub enum MiningWire {
-    Candidate,
-}
+use std::time::Duration;
 
-impl Wire for MiningWire {
-    const VERSION: u64 = 1;
-    const SOURCE: &'static str = "miner";
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use nockapp::kernel::form::Entropy;
+use nockchain::backend::{KernelBackend, Prover, ProvingBackend, INSECURE_DEV_PROVING};
+use nockchain::prove_input::ProveBlockInput;
+use nockchain::stack::StackSize;
+use tokio::runtime::Runtime;
 
-    fn to_wire(&self) -> nockapp::wire::WireRepr {
-        let tags = vec!["candidate".into()];
-        nockapp::wire::WireRepr::new(MiningWire::SOURCE, MiningWire::VERSION, tags)
-    }
+/// Lengths, sample counts and time budget of one benchmark run
+struct BenchProfile {
+    lengths: &'static [u64],
+    sample_size: usize,
+    measurement_time: Duration,
 }
 
-/// Create test input for prove-block-inner function
-fn create_test_input(nonce_variant: u64) -> NounSlab {
-    let mut slab = NounSlab::new();
-    
-    // Create test parameters: [length block-commitment nonce]
-    let length = 64u64; // Standard pow-len
-    
-    // Test block commitment (5 belt values)
-    let block_commitment = T(
-        &mut slab,
-        &[D(0x1), D(0x2), D(0x3), D(0x4), D(0x5)],
-    );
-    
-    // Test nonce with variant (5 belt values)
-    let nonce = T(
-        &mut slab,
-        &[D(0x100), D(0x200), D(0x300), D(0x400), D(nonce_variant)],
-    );
-    
-    // Create the full input: [length block-commitment nonce]
-    let input = T(
-        &mut slab,
-        &[D(length), block_commitment, nonce],
-    );
-    
-    slab.set_root(input);
-    slab
-}
+/// Full-size proofs with the real kernel
+const RELEASE_PROFILE: BenchProfile = BenchProfile {
+    lengths: &[64],
+    sample_size: 10,
+    measurement_time: Duration::from_secs(60),
+};
 
-/// Benchmark the prove-block-inner function
-async fn benchmark_prove_block_inner(nonce_variant: u64) -> Result<(), Box<dyn std::error::Error>> {
-    // Create temporary directory for kernel
-    let snapshot_dir = tempdir()?;
-    let hot_state = produce_prover_hot_state();
-    let snapshot_path_buf = snapshot_dir.path().to_path_buf();
-    let jam_paths = JamPaths::new(snapshot_dir.path());
-    
-    // Load the mining kernel
-    let kernel = Kernel::load_with_hot_state_huge(
-        snapshot_path_buf,
-        jam_paths,
-        KERNEL,
-        &hot_state,
-        false,
-    )
-    .await?;
-    
-    // Create test input
-    let candidate_slab = create_test_input(nonce_variant);
-    
-    // Call the kernel with the candidate (this will execute prove-block-inner)
-    let _effects_slab = kernel
-        .poke(MiningWire::Candidate.to_wire(), candidate_slab)
-        .await?;
-    
-    Ok(())
-}
+/// Short proofs with the dev kernel
+const DEV_PROFILE: BenchProfile = BenchProfile {
+    lengths: &[4, 8, 16],
+    sample_size: 10,
+    measurement_time: Duration::from_secs(20),
+};
+
+const NONCE_VARIANTS: [u64; 3] = [1, 2, 3];
 
-/// Synchronous wrapper for the async benchmark
-fn sync_benchmark_prove_block_inner(nonce_variant: u64) {
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    rt.block_on(async {
-        benchmark_prove_block_inner(nonce_variant)
-            .await
-            .expect("Benchmark failed")
-    });
+fn input(length: u64, nonce_variant: u64) -> ProveBlockInput {
+    ProveBlockInput::builder()
+        .length(length)
+        .block_commitment(&[0x1, 0x2, 0x3, 0x4, 0x5])
+        .nonce(&[0x100, 0x200, 0x300, 0x400, nonce_variant])
+        .build()
+        .expect("benchmark inputs are valid")
 }
 
-/// Criterion benchmark function
 fn prove_block_benchmark(c: &mut Criterion) {
-    let mut group = c.benchmark_group("prove_block_inner");
-    
-    // Set longer measurement time since STARK proving is slow
-    group.measurement_time(Duration::from_secs(60));
-    group.sample_size(10); // Fewer samples due to long execution time
-    
-    // Benchmark with different nonce values
-    for nonce_variant in [1u64, 2u64, 3u64] {
-        group.bench_with_input(
-            format!("nonce_{}", nonce_variant),
-            &nonce_variant,
-            |b, &nonce| {
-                b.iter(|| {
-                    sync_benchmark_prove_block_inner(black_box(nonce));
-                });
-            },
-        );
+    let profile = if INSECURE_DEV_PROVING {
+        DEV_PROFILE
+    } else {
+        RELEASE_PROFILE
+    };
+    let rt = Runtime::new().expect("could not start tokio runtime");
+
+    for &length in profile.lengths {
+        let prover: Box<dyn Prover> = rt
+            .block_on(KernelBackend::default().load(StackSize::Auto.words_for(length)))
+            .expect("could not load the miner kernel");
+        let mut group = c.benchmark_group(format!("prove_block_inner/len_{length}"));
+        group.measurement_time(profile.measurement_time);
+        group.sample_size(profile.sample_size);
+
+        for nonce_variant in NONCE_VARIANTS {
+            let input = input(length, nonce_variant);
+            group.bench_function(format!("nonce_{nonce_variant}"), |b| {
+                b.iter_batched(
+                    || input.to_noun_slab(),
+                    |candidate| {
+                        rt.block_on(prover.prove(black_box(candidate), Entropy::Fixed(0)))
+                            .expect("prove poke failed")
+                    },
+                    BatchSize::PerIteration,
+                );
+            });
+
+            // memory next to time, so memory regressions show up in the same run
+            if let Some(memory) = prover.memory_stats() {
+                eprintln!(
+                    "len_{length}/nonce_{nonce_variant}: stack high-water {} of {} bytes, effects slab {} bytes",
+                    memory.stack_high_water_bytes, memory.stack_bytes, memory.effects_slab_bytes
+                );
+            }
+        }
+        group.finish();
     }
-    
-    group.finish();
 }
 
 criterion_group!(benches, prove_block_benchmark);
@@ -14,4 +14,19 @@ fn main() {
         let value = std::env::var(var).unwrap_or_else(|_| "unknown".to_string());
         println!("cargo:rustc-env={var}={value}");
     }
+
+    #[cfg(feature = "proto")]
+    compile_protos();
+}
+
+/// Generate the proof exchange types from proto/ with a pure-Rust compiler,
+/// so building does not need protoc installed
+#[cfg(feature = "proto")]
+fn compile_protos() {
+    let file_descriptors = protox::compile(["nockchain/proof/v1/proof.proto"], ["proto"])
+        .expect("failed to parse proto/nockchain/proof/v1/proof.proto");
+    prost_build::Config::new()
+        .compile_fds(file_descriptors)
+        .expect("failed to generate protobuf types");
+    println!("cargo:rerun-if-changed=proto");
 }
@@ -0,0 +1,10 @@
+//! Random nouns through the light client's page header decoder.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nockapp::noun::arbitrary::ArbitraryNoun;
+use nockchain::light_client::BlockHeader;
+
+fuzz_target!(|noun: ArbitraryNoun| {
+    let _ = BlockHeader::from_noun(noun.noun());
+});
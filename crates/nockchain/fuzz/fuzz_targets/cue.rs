@@ -0,0 +1,21 @@
+//! Random bytes through the jam decoder. Anything that cues must jam back to
+//! a noun that cues to the same thing.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nockapp::noun::slab::{slab_equality, NounSlab};
+
+fuzz_target!(|data: &[u8]| {
+    let mut slab = NounSlab::new();
+    let Ok(root) = slab.cue_into(data.to_vec().into()) else {
+        return;
+    };
+    slab.set_root(root);
+
+    let mut again = NounSlab::new();
+    let root = again
+        .cue_into(slab.jam())
+        .expect("re-jammed noun failed to cue");
+    again.set_root(root);
+    assert!(slab_equality(&slab, &again));
+});
@@ -0,0 +1,29 @@
+//! Random nouns, and random jams, through the `%pow` effect decoder and
+//! schema check. Both must return an error for garbage, never panic.
+#![no_main]
+
+use libfuzzer_sys::arbitrary::{Arbitrary, Unstructured};
+use libfuzzer_sys::fuzz_target;
+use nockapp::noun::arbitrary::ArbitraryNoun;
+use nockapp::noun::slab::NounSlab;
+use nockchain::effect::{validate_effect_schema, ExpectedSchema, MiningEffect};
+
+fn check(slab: NounSlab) {
+    let schema = validate_effect_schema(unsafe { *slab.root() }, &ExpectedSchema::pow_effect());
+    let decoded = MiningEffect::try_from(slab);
+    // anything the decoder accepts has the shape the schema describes
+    if decoded.is_ok() {
+        assert!(schema.is_ok(), "decoded an effect that fails the schema");
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut jammed = NounSlab::new();
+    if let Ok(root) = jammed.cue_into(data.to_vec().into()) {
+        jammed.set_root(root);
+        check(jammed);
+    }
+    if let Ok(noun) = ArbitraryNoun::arbitrary(&mut Unstructured::new(data)) {
+        check(noun.into_slab());
+    }
+});
@@ -0,0 +1,592 @@
+//! Backends that turn a mining candidate into proof effects.
+//!
+//! [`KernelBackend`] boots the real prover kernel for every attempt. It is a
+//! [`VerifierBackend`] as well, checking proofs over the miner kernel's
+//! `%verify` wire, so a node that mines needs no second kernel to verify.
+//! [`SharedKernelBackend`] keeps one such kernel for both, its pokes taking
+//! turns in a [`PokeQueue`] that lets verifications ahead of queued proves.
+//! [`MockBackend`] hands back canned effects immediately, so the code around
+//! proving can be exercised without running the STARK prover.
+//! [`SimulatedBackend`] goes one step further for dry runs of the whole
+//! mining pipeline: it answers every candidate with a well-formed `%pow`
+//! effect for that candidate, carrying a fake proof the network rejects.
+//!
+//! Building with the `dev-proving` feature swaps [`MINER_KERNEL`] for a
+//! reduced-parameter kernel. Its proofs are INSECURE and rejected by the
+//! network; it exists only to make development and CI runs tolerable.
+//! To try a freshly built kernel without rebuilding at all, give the kernel
+//! backends a [`KernelJam`] loaded from its file.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use nockapp::kernel::checkpoint::JamPaths;
+use nockapp::kernel::form::{Entropy, Kernel, MemoryStats};
+use nockapp::noun::slab::NounSlab;
+use nockapp::wire::Wire;
+use nockapp::CrownError;
+use nockvm::noun::{Atom, Noun, D, T};
+use nockvm_macros::tas;
+use tempfile::{tempdir, TempDir};
+use thiserror::Error;
+use tokio::sync::OnceCell;
+
+use crate::effect::PROOF_VERSION;
+use crate::mining::{status_cause, MiningWire};
+use crate::poke::{PokePriority, PokeQueue};
+use crate::prove_input::{ProveBlockInput, DIGEST_BELTS};
+use crate::verifier::{verdict, verify_cause, ProofCheck, Verifier, VerifierBackend, VerifyError};
+
+/// The miner kernel booted by [`KernelBackend`]
+#[cfg(not(feature = "dev-proving"))]
+pub use kernels::miner::KERNEL as MINER_KERNEL;
+/// The miner kernel booted by [`KernelBackend`], INSECURE dev parameters
+#[cfg(feature = "dev-proving")]
+pub use kernels::miner_dev::KERNEL as MINER_KERNEL;
+
+/// Whether this build proves with the insecure dev-proving kernel
+pub const INSECURE_DEV_PROVING: bool = cfg!(feature = "dev-proving");
+
+#[derive(Debug, Error)]
+pub enum KernelJamError {
+    #[error("could not read kernel jam {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("kernel jam {path} is not a kernel: {reason}")]
+    NotAKernel { path: PathBuf, reason: String },
+}
+
+/// The jam the kernel backends boot, [`MINER_KERNEL`] unless loaded from a
+/// file with [`Self::load`]
+#[derive(Clone, Default)]
+pub struct KernelJam {
+    // `None` for the embedded kernel
+    loaded: Option<(PathBuf, Arc<[u8]>)>,
+}
+
+impl KernelJam {
+    /// The kernel built into this binary
+    pub fn embedded() -> Self {
+        Self::default()
+    }
+
+    /// Read the kernel jam at `path`, checking that it cues to a kernel
+    pub fn load(path: &Path) -> Result<Self, KernelJamError> {
+        let jam = std::fs::read(path).map_err(|source| KernelJamError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let not_a_kernel = |reason: String| KernelJamError::NotAKernel {
+            path: path.to_path_buf(),
+            reason,
+        };
+        if jam.is_empty() {
+            return Err(not_a_kernel("jam is empty".to_string()));
+        }
+        let mut slab = NounSlab::new();
+        let trap = slab
+            .cue_into(jam.clone().into())
+            .map_err(|e| not_a_kernel(e.to_string()))?;
+        // a kernel is a trap, anything else fails to boot much later
+        if !trap.is_cell() {
+            return Err(not_a_kernel("jam is an atom".to_string()));
+        }
+        Ok(Self {
+            loaded: Some((path.to_path_buf(), Arc::from(jam))),
+        })
+    }
+
+    /// [`Self::load`] the jam at `path` if there is one, falling back to the
+    /// embedded kernel if there is not or it is no kernel
+    pub fn load_or_embedded(path: Option<&Path>) -> Self {
+        let Some(path) = path else {
+            return Self::embedded();
+        };
+        match Self::load(path) {
+            Ok(kernel) => {
+                tracing::info!("Booting kernels from {kernel:?}");
+                kernel
+            }
+            Err(e) => {
+                tracing::warn!("{e}, booting the embedded kernel instead");
+                Self::embedded()
+            }
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        match &self.loaded {
+            Some((_, jam)) => &jam[..],
+            None => MINER_KERNEL,
+        }
+    }
+
+    /// Where the jam was loaded from, `None` for the embedded kernel
+    pub fn path(&self) -> Option<&Path> {
+        self.loaded.as_ref().map(|(path, _)| path.as_path())
+    }
+
+    /// Hash of the jam, as the kernel's checkpoints record it
+    pub fn hash(&self) -> blake3::Hash {
+        blake3::hash(self.bytes())
+    }
+}
+
+impl std::fmt::Debug for KernelJam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.path() {
+            Some(path) => write!(f, "{} ({})", path.display(), self.hash()),
+            None => write!(f, "embedded kernel ({})", self.hash()),
+        }
+    }
+}
+
+/// Something that can prove mining candidates
+pub trait ProvingBackend: Send + Sync {
+    /// Start a prover with a NockStack of `stack_words`
+    fn load(&self, stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Prover>, CrownError>>;
+}
+
+/// A loaded prover, good for one or more prove calls
+pub trait Prover: Send + Sync {
+    /// Prove `candidate`, producing the effects of the prove poke
+    fn prove(
+        &self,
+        candidate: NounSlab,
+        entropy: Entropy,
+    ) -> BoxFuture<'static, Result<NounSlab, CrownError>>;
+
+    /// Interrupt an in-flight prove. Returns false if nothing was running.
+    fn cancel(&self) -> bool;
+
+    /// Run a cheap poke whose effects and state are thrown away, so the
+    /// first prove does not pay for registering jets, see
+    /// [`Kernel::warm_up`]. Returns how long it took, nothing for provers
+    /// without a kernel.
+    fn warm_up(&self) -> BoxFuture<'static, Result<Duration, CrownError>> {
+        Box::pin(async { Ok(Duration::ZERO) })
+    }
+
+    /// The kernel state jammed as an `ExportedState`, see
+    /// [`Kernel::create_state_bytes`]. `None` for provers that do not run a
+    /// kernel.
+    fn state(&self) -> Option<BoxFuture<'static, Result<Vec<u8>, CrownError>>> {
+        None
+    }
+
+    /// Memory use of the kernel as of its last poke, `None` for provers
+    /// that do not run one
+    fn memory_stats(&self) -> Option<MemoryStats> {
+        None
+    }
+}
+
+/// Proves with the miner kernel, booted fresh for every attempt
+#[derive(Debug, Clone, Default)]
+pub struct KernelBackend {
+    kernel: KernelJam,
+}
+
+/// One miner kernel shared by mining and verification.
+///
+/// The kernel is booted on first use with the NockStack given to
+/// [`Self::new`], whatever size `load` asks for, and every prover and
+/// verifier loaded from this backend pokes it in turn through
+/// [`Self::queue`]. A verification waits for at most the prove already
+/// running. Waiting for a turn counts against the watchdog's stall timeout,
+/// and a watchdog restart gets the same kernel back.
+#[derive(Clone)]
+pub struct SharedKernelBackend {
+    backend: KernelBackend,
+    stack_words: usize,
+    kernel: Arc<OnceCell<Arc<KernelProver>>>,
+    queue: PokeQueue,
+}
+
+/// A prover or verifier on a [`SharedKernelBackend`]'s kernel
+struct QueuedKernel {
+    kernel: Arc<KernelProver>,
+    queue: PokeQueue,
+    // whether a poke of this handle holds the kernel, so cancelling one
+    // handle cannot interrupt another's poke
+    poking: Arc<AtomicBool>,
+}
+
+struct KernelProver {
+    kernel: Kernel,
+    // the kernel's snapshot directory, removed when the prover is dropped
+    _snapshot_dir: TempDir,
+}
+
+impl KernelBackend {
+    /// Boot `kernel` instead of the embedded miner kernel
+    pub fn new(kernel: KernelJam) -> Self {
+        Self { kernel }
+    }
+
+    pub fn kernel(&self) -> &KernelJam {
+        &self.kernel
+    }
+
+    async fn load_kernel(&self, stack_words: usize) -> Result<KernelProver, CrownError> {
+        if INSECURE_DEV_PROVING {
+            tracing::warn!("proving with INSECURE dev-proving parameters, proofs will not be accepted by the network");
+        }
+        let snapshot_dir = tokio::task::spawn_blocking(tempdir).await??;
+        let hot_state = zkvm_jetpack::hot::produce_prover_hot_state();
+        let jam_paths = JamPaths::new(snapshot_dir.path());
+        // Spawns a new std::thread for this prover
+        let kernel = Kernel::load_with_stack_size(
+            snapshot_dir.path().to_path_buf(),
+            jam_paths,
+            self.kernel.bytes(),
+            &hot_state,
+            stack_words,
+            false,
+        )
+        .await?;
+        Ok(KernelProver {
+            kernel,
+            _snapshot_dir: snapshot_dir,
+        })
+    }
+}
+
+impl ProvingBackend for KernelBackend {
+    fn load(&self, stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Prover>, CrownError>> {
+        Box::pin(
+            async move { Ok(Box::new(self.load_kernel(stack_words).await?) as Box<dyn Prover>) },
+        )
+    }
+}
+
+impl VerifierBackend for KernelBackend {
+    fn load(&self, stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Verifier>, CrownError>> {
+        Box::pin(
+            async move { Ok(Box::new(self.load_kernel(stack_words).await?) as Box<dyn Verifier>) },
+        )
+    }
+}
+
+impl SharedKernelBackend {
+    pub fn new(stack_words: usize, queue: PokeQueue) -> Self {
+        Self {
+            backend: KernelBackend::default(),
+            stack_words,
+            kernel: Arc::new(OnceCell::new()),
+            queue,
+        }
+    }
+
+    /// Boot `kernel` instead of the embedded miner kernel
+    pub fn with_kernel(mut self, kernel: KernelJam) -> Self {
+        self.backend = KernelBackend::new(kernel);
+        self
+    }
+
+    /// The queue the kernel's pokes wait in, e.g. to watch its depth
+    pub fn queue(&self) -> &PokeQueue {
+        &self.queue
+    }
+
+    /// Boot and warm up the kernel now rather than on the first candidate,
+    /// returning how long the warm-up poke took
+    pub async fn preload(&self) -> Result<Duration, CrownError> {
+        Prover::warm_up(&self.handle().await?).await
+    }
+
+    async fn handle(&self) -> Result<QueuedKernel, CrownError> {
+        let kernel = self
+            .kernel
+            .get_or_try_init(|| async {
+                Ok::<_, CrownError>(Arc::new(self.backend.load_kernel(self.stack_words).await?))
+            })
+            .await?;
+        Ok(QueuedKernel {
+            kernel: kernel.clone(),
+            queue: self.queue.clone(),
+            poking: Arc::new(AtomicBool::new(false)),
+        })
+    }
+}
+
+impl ProvingBackend for SharedKernelBackend {
+    fn load(&self, _stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Prover>, CrownError>> {
+        Box::pin(async move { Ok(Box::new(self.handle().await?) as Box<dyn Prover>) })
+    }
+}
+
+impl VerifierBackend for SharedKernelBackend {
+    fn load(&self, _stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Verifier>, CrownError>> {
+        Box::pin(async move { Ok(Box::new(self.handle().await?) as Box<dyn Verifier>) })
+    }
+}
+
+impl QueuedKernel {
+    /// Wait for a turn at `priority`, then run `poke` holding it
+    fn in_turn<T: Send + 'static>(
+        &self,
+        priority: PokePriority,
+        poke: impl FnOnce(&KernelProver) -> BoxFuture<'static, T> + Send + 'static,
+    ) -> BoxFuture<'static, T> {
+        let kernel = self.kernel.clone();
+        let queue = self.queue.clone();
+        let poking = self.poking.clone();
+        Box::pin(async move {
+            let _turn = queue.acquire(priority).await;
+            poking.store(true, Ordering::Release);
+            let res = poke(&kernel).await;
+            poking.store(false, Ordering::Release);
+            res
+        })
+    }
+
+    /// Interrupt this handle's poke, leaving pokes of other handles be
+    fn cancel_poke(&self) -> bool {
+        self.poking.load(Ordering::Acquire) && self.kernel.kernel.cancel_token().cancel()
+    }
+}
+
+impl Prover for QueuedKernel {
+    fn prove(
+        &self,
+        candidate: NounSlab,
+        entropy: Entropy,
+    ) -> BoxFuture<'static, Result<NounSlab, CrownError>> {
+        self.in_turn(PokePriority::Mine, move |kernel| {
+            kernel.prove(candidate, entropy)
+        })
+    }
+
+    fn cancel(&self) -> bool {
+        self.cancel_poke()
+    }
+
+    fn warm_up(&self) -> BoxFuture<'static, Result<Duration, CrownError>> {
+        self.in_turn(PokePriority::Mine, |kernel| kernel.warm_up())
+    }
+
+    fn state(&self) -> Option<BoxFuture<'static, Result<Vec<u8>, CrownError>>> {
+        Some(self.in_turn(PokePriority::Mine, |kernel| {
+            Box::pin(kernel.kernel.create_state_bytes())
+        }))
+    }
+
+    fn memory_stats(&self) -> Option<MemoryStats> {
+        Some(self.kernel.kernel.memory_stats())
+    }
+}
+
+impl Verifier for QueuedKernel {
+    fn verify(
+        &self,
+        proof: NounSlab,
+        entropy: Entropy,
+    ) -> BoxFuture<'static, Result<ProofCheck, VerifyError>> {
+        self.in_turn(PokePriority::Verify, move |kernel| {
+            kernel.verify(proof, entropy)
+        })
+    }
+
+    fn cancel(&self) -> bool {
+        self.cancel_poke()
+    }
+}
+
+impl Prover for KernelProver {
+    fn prove(
+        &self,
+        candidate: NounSlab,
+        entropy: Entropy,
+    ) -> BoxFuture<'static, Result<NounSlab, CrownError>> {
+        Box::pin(
+            self.kernel
+                .poke_with_entropy(MiningWire::Candidate.to_wire(), candidate, entropy),
+        )
+    }
+
+    fn cancel(&self) -> bool {
+        self.kernel.cancel_token().cancel()
+    }
+
+    /// Pokes `%status`, which runs the kernel without proving anything
+    fn warm_up(&self) -> BoxFuture<'static, Result<Duration, CrownError>> {
+        Box::pin(
+            self.kernel
+                .warm_up(MiningWire::Status.to_wire(), status_cause()),
+        )
+    }
+
+    fn state(&self) -> Option<BoxFuture<'static, Result<Vec<u8>, CrownError>>> {
+        Some(Box::pin(self.kernel.create_state_bytes()))
+    }
+
+    fn memory_stats(&self) -> Option<MemoryStats> {
+        Some(self.kernel.memory_stats())
+    }
+}
+
+impl Verifier for KernelProver {
+    fn verify(
+        &self,
+        proof: NounSlab,
+        entropy: Entropy,
+    ) -> BoxFuture<'static, Result<ProofCheck, VerifyError>> {
+        let poke = self.kernel.poke_with_entropy(
+            MiningWire::Verify.to_wire(),
+            verify_cause(&proof),
+            entropy,
+        );
+        Box::pin(async move { verdict(&poke.await?) })
+    }
+
+    fn cancel(&self) -> bool {
+        self.kernel.cancel_token().cancel()
+    }
+}
+
+/// Returns the same canned effects for every candidate, without proving anything
+#[derive(Clone)]
+pub struct MockBackend {
+    effects: Arc<Mutex<NounSlab>>,
+    candidates: Arc<Mutex<Vec<NounSlab>>>,
+}
+
+impl MockBackend {
+    pub fn new(effects: NounSlab) -> Self {
+        Self {
+            effects: Arc::new(Mutex::new(effects)),
+            candidates: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Every candidate proven so far, oldest first
+    pub fn candidates(&self) -> Vec<NounSlab> {
+        self.candidates
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+impl ProvingBackend for MockBackend {
+    fn load(&self, _stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Prover>, CrownError>> {
+        let prover = self.clone();
+        Box::pin(async move { Ok(Box::new(prover) as Box<dyn Prover>) })
+    }
+}
+
+impl Prover for MockBackend {
+    fn prove(
+        &self,
+        candidate: NounSlab,
+        _entropy: Entropy,
+    ) -> BoxFuture<'static, Result<NounSlab, CrownError>> {
+        self.candidates
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(candidate);
+        let effects = self
+            .effects
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        Box::pin(async move { Ok(effects) })
+    }
+
+    fn cancel(&self) -> bool {
+        false
+    }
+}
+
+/// Proves every candidate instantly, or after a fixed delay, with a fake
+/// proof that only has the right shape
+#[derive(Debug, Clone, Default)]
+pub struct SimulatedBackend {
+    delay: Duration,
+    proven: Arc<AtomicUsize>,
+}
+
+impl SimulatedBackend {
+    /// Take `delay` per candidate, standing in for proving time
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            proven: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Candidates proven so far
+    pub fn proven(&self) -> usize {
+        self.proven.load(Ordering::Relaxed)
+    }
+}
+
+impl ProvingBackend for SimulatedBackend {
+    fn load(&self, _stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Prover>, CrownError>> {
+        let prover = self.clone();
+        Box::pin(async move { Ok(Box::new(prover) as Box<dyn Prover>) })
+    }
+}
+
+impl Prover for SimulatedBackend {
+    fn prove(
+        &self,
+        candidate: NounSlab,
+        _entropy: Entropy,
+    ) -> BoxFuture<'static, Result<NounSlab, CrownError>> {
+        let effects = ProveBlockInput::try_from(&candidate).map(|input| simulated_effects(&input));
+        let delay = self.delay;
+        let proven = self.proven.clone();
+        Box::pin(async move {
+            // the kernel crashes on a malformed cause, fail the same way
+            let effects = effects.map_err(|_| CrownError::InvalidKernelInput)?;
+            tokio::time::sleep(delay).await;
+            proven.fetch_add(1, Ordering::Relaxed);
+            Ok(effects)
+        })
+    }
+
+    fn cancel(&self) -> bool {
+        false
+    }
+}
+
+/// `[[%command %pow prf 0 commitment nonce] ~]` with the fake proof
+/// `[version [[%puzzle commitment nonce length 0] ~] ~ 0]`
+fn simulated_effects(input: &ProveBlockInput) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let commitment = digest_noun(&mut slab, &input.mined_commitment());
+    let nonce = digest_noun(&mut slab, input.nonce());
+    let length = Atom::new(&mut slab, input.length()).as_noun();
+    let puzzle = T(
+        &mut slab,
+        &[D(tas!(b"puzzle")), commitment, nonce, length, D(0)],
+    );
+    let objects = T(&mut slab, &[puzzle, D(0)]);
+    let proof = T(&mut slab, &[D(PROOF_VERSION), objects, D(0), D(0)]);
+    let pow = T(
+        &mut slab,
+        &[
+            D(tas!(b"command")),
+            D(tas!(b"pow")),
+            proof,
+            D(0),
+            commitment,
+            nonce,
+        ],
+    );
+    let effects = T(&mut slab, &[pow, D(0)]);
+    slab.set_root(effects);
+    slab
+}
+
+fn digest_noun(slab: &mut NounSlab, belts: &[u64; DIGEST_BELTS]) -> Noun {
+    let belts = belts.map(|belt| Atom::new(slab, belt).as_noun());
+    T(slab, &belts)
+}
@@ -0,0 +1,163 @@
+//! Keep miner kernels loaded and prove or verify for other processes.
+//!
+//! `nockchain-daemon serve` loads the kernels once and listens on a unix
+//! socket, see `nockchain::daemon`. The `prove`, `verify` and `status`
+//! subcommands are a thin client for it, so scripts can use the loaded
+//! kernels without paying for a load of their own. Logs go to stderr, as JSON
+//! lines with `--log-json`.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::{value_parser, Parser, Subcommand};
+use nockapp::kernel::boot::JsonFormatter;
+use nockapp::kernel::form::Entropy;
+use nockapp::noun::slab::NounSlab;
+use nockchain::backend::KernelJam;
+use nockchain::daemon::{bind, DaemonClient, DaemonConfig, KernelDaemon};
+use nockchain::frame::DEFAULT_MAX_FRAME_BYTES;
+use nockchain::poke::DEFAULT_VERIFY_BURST;
+use nockchain::stack::StackSize;
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Parser, Debug)]
+#[command(name = "nockchain-daemon")]
+struct DaemonCli {
+    #[arg(
+        long,
+        help = "Unix socket the daemon listens on",
+        default_value = ".socket/nockchain_daemon.sock"
+    )]
+    socket: PathBuf,
+    #[arg(long, help = "Log one JSON object per line, for log pipelines")]
+    log_json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Load the kernels and serve requests until killed
+    Serve {
+        #[arg(long, help = "Miner kernels to keep loaded", default_value = "1")]
+        kernels: usize,
+        #[arg(
+            long,
+            help = "NockStack size per kernel, or 'auto' for the default size",
+            value_parser = value_parser!(StackSize),
+            default_value = "auto"
+        )]
+        stack_size: StackSize,
+        #[arg(
+            long,
+            help = "Verify requests let past a waiting prove before it runs",
+            default_value_t = DEFAULT_VERIFY_BURST
+        )]
+        verify_burst: usize,
+        #[arg(
+            long,
+            help = "Largest request accepted, in bytes",
+            default_value_t = DEFAULT_MAX_FRAME_BYTES
+        )]
+        max_request_bytes: usize,
+        #[arg(
+            long,
+            help = "Boot the kernels from this jam instead of the one built in"
+        )]
+        kernel_path: Option<PathBuf>,
+    },
+    /// Prove a jammed candidate, writing the jammed effects
+    Prove {
+        candidate: PathBuf,
+        #[arg(long, help = "Where to write the jammed effects")]
+        out: PathBuf,
+        #[arg(long, help = "Fixed entropy, fresh entropy if not given")]
+        entropy_seed: Option<u64>,
+    },
+    /// Verify a jammed proof, printing the verdict as JSON
+    Verify { proof: PathBuf },
+    /// Print the daemon's queue depths as JSON
+    Status,
+}
+
+fn read_jam(path: &Path) -> Result<NounSlab, Box<dyn Error>> {
+    let mut slab = NounSlab::new();
+    let root = slab.cue_into(std::fs::read(path)?.into())?;
+    slab.set_root(root);
+    Ok(slab)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    nockvm::check_endian();
+    let cli = DaemonCli::parse();
+    // stdout carries the client's output, keep logs off it
+    let logs = tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(EnvFilter::from_default_env());
+    if cli.log_json {
+        logs.with_ansi(false).event_format(JsonFormatter).init();
+    } else {
+        logs.init();
+    }
+
+    match cli.command {
+        Command::Serve {
+            kernels,
+            stack_size,
+            verify_burst,
+            max_request_bytes,
+            kernel_path,
+        } => {
+            let kernel = match kernel_path {
+                Some(path) => KernelJam::load(&path)?,
+                None => KernelJam::embedded(),
+            };
+            let config = DaemonConfig {
+                kernels,
+                stack_size,
+                verify_burst,
+                max_frame_bytes: max_request_bytes,
+                kernel,
+            };
+            let daemon = Arc::new(KernelDaemon::new(&config));
+            let listener = bind(&cli.socket).await?;
+            let warm_up = daemon.preload().await?;
+            info!(
+                "{kernels} kernels of {:?} loaded, warm-up took {warm_up:?}, serving on {}",
+                config.kernel,
+                cli.socket.display()
+            );
+            daemon.serve(listener).await?;
+        }
+        Command::Prove {
+            candidate,
+            out,
+            entropy_seed,
+        } => {
+            let candidate = read_jam(&candidate)?;
+            let entropy = entropy_seed.map_or(Entropy::Random, Entropy::Fixed);
+            let mut client = DaemonClient::connect(&cli.socket).await?;
+            let effects = client.prove(&candidate, entropy).await?;
+            std::fs::write(&out, effects.jam())?;
+            info!("wrote effects to {}", out.display());
+        }
+        Command::Verify { proof } => {
+            let proof = read_jam(&proof)?;
+            let mut client = DaemonClient::connect(&cli.socket).await?;
+            let check = client.verify(&proof).await?;
+            let pow = check.pow.map(|pow| format!("{pow:x}"));
+            println!(
+                "{}",
+                serde_json::json!({ "valid": check.valid, "pow": pow })
+            );
+        }
+        Command::Status => {
+            let mut client = DaemonClient::connect(&cli.socket).await?;
+            println!("{}", serde_json::to_string(&client.status().await?)?);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,149 @@
+//! Verify jammed proofs streamed on stdin or a unix socket.
+//!
+//! Each proof is framed by its length as 8 little-endian bytes. A JSON line
+//! is written to stdout (or back to the socket client) for every proof, see
+//! `nockchain::verifier::StreamVerdict`. Logs go to stderr, as JSON lines
+//! with `--log-json`.
+//!
+//! With `--headers`, the file is instead read as a stream of headers, each
+//! a length-prefixed jam of `[page-summary tx-ids coinbase msg proof]` as
+//! the node's `%heavy-header` peek answers, parents first. They are
+//! checked by a light client under `--network`'s retargeting rule and a
+//! JSON line is written for each, see `nockchain::header_sync::HeaderVerdict`.
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::{value_parser, Parser};
+use nockapp::kernel::boot::JsonFormatter;
+use nockchain::header_sync::follow_stream;
+use nockchain::light_client::{LightClient, ServiceVerifier};
+use nockchain::network::NetworkMode;
+use nockchain::stack::StackSize;
+use nockchain::verifier::{
+    serve_stream, serve_unix, KernelVerifierBackend, VerificationService, VerifierConfig,
+    DEFAULT_MAX_PROOF_BYTES,
+};
+use tokio::net::UnixListener;
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Parser, Debug)]
+#[command(name = "nockchain-verify")]
+struct VerifyCli {
+    #[arg(long, help = "Listen on this unix socket instead of reading stdin")]
+    socket: Option<PathBuf>,
+    #[arg(long, help = "Verifier kernels to run at once", default_value = "2")]
+    max_concurrent: usize,
+    #[arg(
+        long,
+        help = "Proofs allowed to wait for a kernel before new ones are refused",
+        default_value = "16"
+    )]
+    max_queued: usize,
+    #[arg(
+        long,
+        help = "Seconds allowed per proof, queueing included",
+        default_value = "300"
+    )]
+    timeout: u64,
+    #[arg(
+        long,
+        help = "NockStack size per kernel, 'auto' to size it from the proof length, or a size such as 16GB",
+        value_parser = value_parser!(StackSize),
+        default_value = "auto"
+    )]
+    stack_size: StackSize,
+    #[arg(
+        long,
+        help = "Largest NockStack any kernel may get, such as 32GB",
+        value_parser = value_parser!(StackSize)
+    )]
+    max_stack_size: Option<StackSize>,
+    #[arg(
+        long,
+        help = "Largest jammed proof accepted, in bytes",
+        default_value_t = DEFAULT_MAX_PROOF_BYTES
+    )]
+    max_proof_bytes: usize,
+    #[arg(long, help = "Log one JSON object per line, for log pipelines")]
+    log_json: bool,
+    #[arg(
+        long,
+        help = "Check the stream of headers in this file, '-' for stdin, instead of bare proofs"
+    )]
+    headers: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Network whose retargeting rule --headers checks targets against: mainnet, testnet or regtest",
+        value_parser = value_parser!(NetworkMode),
+        default_value = "mainnet"
+    )]
+    network: NetworkMode,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    nockvm::check_endian();
+    let cli = VerifyCli::parse();
+    // stdout carries the verdicts, keep logs off it
+    let logs = tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(EnvFilter::from_default_env());
+    if cli.log_json {
+        logs.with_ansi(false).event_format(JsonFormatter).init();
+    } else {
+        logs.init();
+    }
+
+    let config = VerifierConfig {
+        max_concurrent: cli.max_concurrent,
+        max_queued: cli.max_queued,
+        default_deadline: Duration::from_secs(cli.timeout),
+        stack_size: cli.stack_size,
+        max_stack_words: cli.max_stack_size.map(|max| max.words_for(0)),
+        max_proof_bytes: cli.max_proof_bytes,
+        ..Default::default()
+    };
+    let service = Arc::new(VerificationService::new(
+        Arc::new(KernelVerifierBackend),
+        config,
+    ));
+
+    if let Some(path) = cli.headers {
+        let verifier =
+            ServiceVerifier::new(service).with_deadline(Duration::from_secs(cli.timeout));
+        let mut client = LightClient::new(verifier).with_difficulty(cli.network.difficulty());
+        let stdout = tokio::io::stdout();
+        let headers = if path.as_os_str() == "-" {
+            follow_stream(&mut client, tokio::io::stdin(), stdout, cli.max_proof_bytes).await?
+        } else {
+            let file = tokio::fs::File::open(&path).await?;
+            follow_stream(&mut client, file, stdout, cli.max_proof_bytes).await?
+        };
+        info!(
+            "read {headers} headers, best at height {:?}",
+            client.best_header().map(|h| h.height)
+        );
+        return Ok(());
+    }
+
+    match cli.socket {
+        Some(path) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let listener = UnixListener::bind(&path)?;
+            info!("verifying proofs sent to {}", path.display());
+            serve_unix(service, listener, None).await?;
+        }
+        None => {
+            let proofs =
+                serve_stream(&service, tokio::io::stdin(), tokio::io::stdout(), None).await?;
+            info!("verified {proofs} proofs");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,230 @@
+//! Persistent block storage.
+//!
+//! Blocks are stored as jammed page nouns, one file per height, alongside a
+//! `digest -> height` index that is rewritten on every change. Only the
+//! heaviest chain is stored: writing a block at an existing height replaces
+//! the old one, which is how reorgs are reflected.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bincode::config;
+use nockapp::noun::slab::NounSlab;
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::light_client::BlockId;
+
+const BLOCKS_DIR: &str = "blocks";
+const INDEX_FILE: &str = "index.bin";
+const BLOCK_EXTENSION: &str = "jam";
+
+#[derive(Debug, Error)]
+pub enum BlockStoreError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("failed to encode block index: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+    #[error("failed to decode block index: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+    #[error("failed to cue stored block at height {0}")]
+    Cue(u64),
+    #[error("block at height {0} has been pruned")]
+    Pruned(u64),
+}
+
+/// How much history the store keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PruneMode {
+    /// Keep every block
+    #[default]
+    Archive,
+    /// Keep only the most recent `n` blocks below the tip
+    KeepRecent(u64),
+}
+
+pub struct BlockStore {
+    root: PathBuf,
+    prune_mode: PruneMode,
+    by_height: BTreeMap<u64, BlockId>,
+    by_digest: HashMap<BlockId, u64>,
+    /// Lowest height still on disk, everything below has been pruned
+    pruned_below: u64,
+}
+
+impl BlockStore {
+    /// Open a block store, loading its index if one exists
+    pub fn open(root: impl AsRef<Path>, prune_mode: PruneMode) -> Result<Self, BlockStoreError> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(root.join(BLOCKS_DIR))?;
+
+        let mut store = BlockStore {
+            root,
+            prune_mode,
+            by_height: BTreeMap::new(),
+            by_digest: HashMap::new(),
+            pruned_below: 0,
+        };
+        match fs::read(store.root.join(INDEX_FILE)) {
+            Ok(bytes) => {
+                let ((pruned_below, entries), _): ((u64, Vec<(u64, BlockId)>), usize) =
+                    bincode::decode_from_slice(&bytes, config::standard())?;
+                store.pruned_below = pruned_below;
+                for (height, digest) in entries {
+                    store.by_height.insert(height, digest);
+                    store.by_digest.insert(digest, height);
+                }
+                info!(
+                    "opened block store with {} blocks, tip {:?}",
+                    store.by_height.len(),
+                    store.tip_height()
+                );
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        Ok(store)
+    }
+
+    /// Store the jammed page at `height`, replacing whatever was there
+    pub fn put(&mut self, height: u64, digest: BlockId, jam: &[u8]) -> Result<(), BlockStoreError> {
+        let path = self.block_path(height);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, jam)?;
+        fs::rename(&tmp, &path)?;
+
+        if let Some(old) = self.by_height.insert(height, digest) {
+            self.by_digest.remove(&old);
+        }
+        self.by_digest.insert(digest, height);
+        debug!("stored block at height {}", height);
+
+        self.apply_prune_mode()?;
+        self.write_index()
+    }
+
+    /// Jam and store a page noun
+    pub fn put_slab(
+        &mut self,
+        height: u64,
+        digest: BlockId,
+        page: &NounSlab,
+    ) -> Result<(), BlockStoreError> {
+        self.put(height, digest, &page.jam())
+    }
+
+    /// Jammed page at `height`
+    pub fn get_by_height(&self, height: u64) -> Result<Option<Vec<u8>>, BlockStoreError> {
+        if height < self.pruned_below {
+            return Err(BlockStoreError::Pruned(height));
+        }
+        if !self.by_height.contains_key(&height) {
+            return Ok(None);
+        }
+        Ok(Some(fs::read(self.block_path(height))?))
+    }
+
+    pub fn get_by_digest(&self, digest: &BlockId) -> Result<Option<Vec<u8>>, BlockStoreError> {
+        match self.height_of(digest) {
+            Some(height) => self.get_by_height(height),
+            None => Ok(None),
+        }
+    }
+
+    /// Page at `height`, cued into a fresh slab
+    pub fn get_slab(&self, height: u64) -> Result<Option<NounSlab>, BlockStoreError> {
+        let Some(jam) = self.get_by_height(height)? else {
+            return Ok(None);
+        };
+        let mut slab = NounSlab::new();
+        let noun = slab
+            .cue_into(jam.into())
+            .map_err(|_| BlockStoreError::Cue(height))?;
+        slab.set_root(noun);
+        Ok(Some(slab))
+    }
+
+    pub fn height_of(&self, digest: &BlockId) -> Option<u64> {
+        self.by_digest.get(digest).copied()
+    }
+
+    pub fn digest_at(&self, height: u64) -> Option<BlockId> {
+        self.by_height.get(&height).copied()
+    }
+
+    pub fn tip_height(&self) -> Option<u64> {
+        self.by_height.keys().next_back().copied()
+    }
+
+    /// Remove every block above `height`, e.g. when the heaviest chain switches forks
+    pub fn truncate_above(&mut self, height: u64) -> Result<(), BlockStoreError> {
+        let removed: Vec<u64> = self
+            .by_height
+            .range(height + 1..)
+            .map(|(h, _)| *h)
+            .collect();
+        for h in removed {
+            self.remove_height(h)?;
+        }
+        self.write_index()
+    }
+
+    /// Delete every block below `height`
+    pub fn prune_below(&mut self, height: u64) -> Result<usize, BlockStoreError> {
+        let removed: Vec<u64> = self.by_height.range(..height).map(|(h, _)| *h).collect();
+        let count = removed.len();
+        for h in removed {
+            self.remove_height(h)?;
+        }
+        self.pruned_below = self.pruned_below.max(height);
+        self.write_index()?;
+        if count > 0 {
+            info!("pruned {} blocks below height {}", count, height);
+        }
+        Ok(count)
+    }
+
+    fn apply_prune_mode(&mut self) -> Result<(), BlockStoreError> {
+        let PruneMode::KeepRecent(keep) = self.prune_mode else {
+            return Ok(());
+        };
+        let Some(tip) = self.tip_height() else {
+            return Ok(());
+        };
+        let floor = tip.saturating_sub(keep);
+        if floor > self.pruned_below {
+            self.prune_below(floor)?;
+        }
+        Ok(())
+    }
+
+    fn remove_height(&mut self, height: u64) -> Result<(), BlockStoreError> {
+        if let Some(digest) = self.by_height.remove(&height) {
+            self.by_digest.remove(&digest);
+        }
+        match fs::remove_file(self.block_path(height)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write_index(&self) -> Result<(), BlockStoreError> {
+        let entries: Vec<(u64, BlockId)> = self.by_height.iter().map(|(h, d)| (*h, *d)).collect();
+        let encoded = bincode::encode_to_vec((self.pruned_below, entries), config::standard())?;
+        let path = self.root.join(INDEX_FILE);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, encoded)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn block_path(&self, height: u64) -> PathBuf {
+        self.root
+            .join(BLOCKS_DIR)
+            .join(format!("{:012}", height))
+            .with_extension(BLOCK_EXTENSION)
+    }
+}
@@ -0,0 +1,182 @@
+//! A bounded queue of mining candidates, best first.
+//!
+//! The node hands candidates to mining workers through a [`CandidateQueue`]
+//! instead of poking a prover for each one as it arrives. Candidates come
+//! out highest [`CandidatePriority`] first, in arrival order among equals.
+//! The queue holds at most `capacity` candidates: [`CandidateQueue::push`]
+//! waits for a worker to make room, and [`CandidateQueue::try_push`] hands
+//! the candidate back so the producer decides what to drop.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Mutex;
+
+use thiserror::Error;
+use tokio::sync::Semaphore;
+
+use crate::prove_input::ProveBlockInput;
+
+/// How much a candidate is worth mining, compared height first, then fees
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CandidatePriority {
+    pub height: u64,
+    pub fee: u64,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QueueError {
+    /// The queue is at capacity, the candidate is handed back
+    #[error("candidate queue is full")]
+    Full(CandidatePriority, ProveBlockInput),
+    #[error("candidate queue is closed")]
+    Closed,
+}
+
+#[derive(Debug)]
+struct Queued {
+    priority: CandidatePriority,
+    /// Arrival order, so equal priorities come out first in first out
+    seq: u64,
+    input: ProveBlockInput,
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Heap {
+    queued: BinaryHeap<Queued>,
+    next_seq: u64,
+}
+
+#[derive(Debug)]
+pub struct CandidateQueue {
+    heap: Mutex<Heap>,
+    /// Free slots, taken by pushes and returned by pops
+    slots: Semaphore,
+    /// Queued candidates, taken by pops
+    ready: Semaphore,
+    capacity: usize,
+}
+
+impl CandidateQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            heap: Mutex::new(Heap::default()),
+            slots: Semaphore::new(capacity),
+            ready: Semaphore::new(0),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap().queued.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Queue a candidate, waiting for room if the queue is full
+    pub async fn push(
+        &self,
+        priority: CandidatePriority,
+        input: ProveBlockInput,
+    ) -> Result<(), QueueError> {
+        let slot = self.slots.acquire().await.map_err(|_| QueueError::Closed)?;
+        slot.forget();
+        self.insert(priority, input);
+        Ok(())
+    }
+
+    /// Queue a candidate if there is room right now
+    pub fn try_push(
+        &self,
+        priority: CandidatePriority,
+        input: ProveBlockInput,
+    ) -> Result<(), QueueError> {
+        match self.slots.try_acquire() {
+            Ok(slot) => slot.forget(),
+            Err(tokio::sync::TryAcquireError::NoPermits) => {
+                return Err(QueueError::Full(priority, input))
+            }
+            Err(tokio::sync::TryAcquireError::Closed) => return Err(QueueError::Closed),
+        }
+        self.insert(priority, input);
+        Ok(())
+    }
+
+    /// Take the best candidate, waiting for one if the queue is empty.
+    /// Returns `None` once the queue is closed.
+    pub async fn pop(&self) -> Option<(CandidatePriority, ProveBlockInput)> {
+        self.ready.acquire().await.ok()?.forget();
+        self.remove()
+    }
+
+    /// Take the best candidate if there is one, even after closing
+    pub fn try_pop(&self) -> Option<(CandidatePriority, ProveBlockInput)> {
+        match self.ready.try_acquire() {
+            Ok(ready) => ready.forget(),
+            Err(tokio::sync::TryAcquireError::NoPermits) => return None,
+            // ready permits are gone once closed, drain whatever is left
+            Err(tokio::sync::TryAcquireError::Closed) => {}
+        }
+        self.remove()
+    }
+
+    /// Stop accepting candidates and wake every waiting push and pop.
+    /// Candidates still queued can be drained with [`Self::try_pop`].
+    pub fn close(&self) {
+        self.slots.close();
+        self.ready.close();
+    }
+
+    fn insert(&self, priority: CandidatePriority, input: ProveBlockInput) {
+        let mut heap = self.heap();
+        let seq = heap.next_seq;
+        heap.next_seq += 1;
+        heap.queued.push(Queued {
+            priority,
+            seq,
+            input,
+        });
+        drop(heap);
+        self.ready.add_permits(1);
+    }
+
+    /// Pop the heap, `None` only if a drain after closing got there first
+    fn remove(&self) -> Option<(CandidatePriority, ProveBlockInput)> {
+        let queued = self.heap().queued.pop()?;
+        self.slots.add_permits(1);
+        Some((queued.priority, queued.input))
+    }
+
+    fn heap(&self) -> std::sync::MutexGuard<'_, Heap> {
+        self.heap
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,185 @@
+//! Serde encodings for proof and benchmark records.
+//!
+//! JSON is convenient for results people read, but proofs are large and
+//! mostly polynomials. [`Codec::Bincode`] and [`Codec::Cbor`] give compact
+//! encodings of the same serde types for storage and network transport.
+//!
+//! Polynomial fields go through [`poly`] and [`fpoly`]: in human-readable
+//! formats they are one hex string of 16 digits per word, which stays exact
+//! where JSON numbers would lose precision above 2^53, and in binary formats
+//! they are plain sequences of integers.
+//!
+//! Mining targets and accumulated work go through [`target`], a hex string
+//! in human-readable formats and little-endian bytes otherwise.
+//!
+//! Stored proofs and baselines compress very well, so they are written
+//! through [`compress`] and read back through [`decompress`], which passes
+//! data that was never compressed through untouched.
+
+use std::io;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Json,
+    Bincode,
+    Cbor,
+}
+
+#[derive(Debug, Error)]
+pub enum CodecError {
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("bincode encode: {0}")]
+    BincodeEncode(#[from] bincode::error::EncodeError),
+    #[error("bincode decode: {0}")]
+    BincodeDecode(#[from] bincode::error::DecodeError),
+    #[error("cbor encode: {0}")]
+    CborEncode(#[from] ciborium::ser::Error<std::io::Error>),
+    #[error("cbor decode: {0}")]
+    CborDecode(#[from] ciborium::de::Error<std::io::Error>),
+    #[error("{0} trailing bytes after value")]
+    TrailingBytes(usize),
+}
+
+impl Codec {
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        match self {
+            Codec::Json => Ok(serde_json::to_vec(value)?),
+            Codec::Bincode => Ok(bincode::serde::encode_to_vec(
+                value,
+                bincode::config::standard(),
+            )?),
+            Codec::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes)?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError> {
+        match self {
+            Codec::Json => Ok(serde_json::from_slice(bytes)?),
+            Codec::Bincode => {
+                let (value, read) =
+                    bincode::serde::decode_from_slice(bytes, bincode::config::standard())?;
+                if read != bytes.len() {
+                    return Err(CodecError::TrailingBytes(bytes.len() - read));
+                }
+                Ok(value)
+            }
+            Codec::Cbor => Ok(ciborium::from_reader(bytes)?),
+        }
+    }
+}
+
+/// zstd level used unless configured otherwise
+pub const DEFAULT_ZSTD_LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
+
+/// Little-endian magic number opening every zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compress `bytes` into a single zstd frame at `level`
+pub fn compress(bytes: &[u8], level: i32) -> io::Result<Vec<u8>> {
+    zstd::bulk::compress(bytes, level)
+}
+
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ZSTD_MAGIC)
+}
+
+/// Decompress `bytes` if they are a zstd frame, otherwise hand them back as is
+pub fn decompress(bytes: Vec<u8>) -> io::Result<Vec<u8>> {
+    if is_compressed(&bytes) {
+        zstd::stream::decode_all(&bytes[..])
+    } else {
+        Ok(bytes)
+    }
+}
+
+/// Serde for a polynomial of belts, see the module docs
+pub mod poly {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(words: &[u64], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let mut hex = String::with_capacity(words.len() * 16);
+            for word in words {
+                hex.push_str(&format!("{word:016x}"));
+            }
+            hex.serialize(serializer)
+        } else {
+            words.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+        if !deserializer.is_human_readable() {
+            return Vec::<u64>::deserialize(deserializer);
+        }
+        let hex = String::deserialize(deserializer)?;
+        if hex.len() % 16 != 0 || !hex.is_ascii() {
+            return Err(D::Error::custom(
+                "polynomial is not a run of 16-digit hex words",
+            ));
+        }
+        (0..hex.len())
+            .step_by(16)
+            .map(|start| u64::from_str_radix(&hex[start..start + 16], 16).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+/// Serde for a polynomial of felts, three words each, see the module docs
+pub mod fpoly {
+    use serde::de::Error;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(felts: &[[u64; 3]], serializer: S) -> Result<S::Ok, S::Error> {
+        super::poly::serialize(felts.as_flattened(), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<[u64; 3]>, D::Error> {
+        let words = super::poly::deserialize(deserializer)?;
+        if words.len() % 3 != 0 {
+            return Err(D::Error::custom(
+                "felt polynomial is not a multiple of 3 words",
+            ));
+        }
+        Ok(words
+            .chunks_exact(3)
+            .map(|felt| [felt[0], felt[1], felt[2]])
+            .collect())
+    }
+}
+
+/// Serde for a mining target, see the module docs
+pub mod target {
+    use ibig::UBig;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(target: &UBig, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            format!("{target:x}").serialize(serializer)
+        } else {
+            serde_bytes::Bytes::new(&target.to_le_bytes()).serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<UBig, D::Error> {
+        if !deserializer.is_human_readable() {
+            let bytes = serde_bytes::ByteBuf::deserialize(deserializer)?;
+            return Ok(UBig::from_le_bytes(&bytes));
+        }
+        let hex = String::deserialize(deserializer)?;
+        UBig::from_str_radix(&hex, 16).map_err(D::Error::custom)
+    }
+}
@@ -2,8 +2,13 @@ use std::path::PathBuf;
 
 use clap::{arg, command, value_parser, ArgAction, Parser};
 use nockchain_bitcoin_sync::BitcoinRPCConnection;
+use zkvm_jetpack::form::math::tip5::Tip5Backend;
 
+use crate::crash_dump::DEFAULT_MAX_DUMPS;
 use crate::mining::MiningKeyConfig;
+use crate::network::NetworkMode;
+use crate::nonce::NonceSource;
+use crate::stack::StackSize;
 
 // TODO: command-line/configure
 /** Path to read current node's identity from */
@@ -21,9 +26,7 @@ pub const PEER_ID_FILE_EXTENSION: &str = "peerid";
 pub const TESTNET_BACKBONE_NODES: &[&str] = &[];
 
 // Libp2p multiaddrs don't support const construction, so we have to put strings literals and parse them at startup
-// TODO: feature flag testnet/realnet
 /** Backbone nodes for our realnet */
-#[allow(dead_code)]
 pub const REALNET_BACKBONE_NODES: &[&str] = &["/dnsaddr/nockchain-backbone.zorp.io"];
 
 /** How often we should affirmatively ask other nodes for their heaviest chain */
@@ -61,11 +64,85 @@ pub struct NockchainCli {
         value_delimiter = ',',
     )]
     pub mining_key_adv: Option<Vec<MiningKeyConfig>>,
+    #[arg(
+        long,
+        help = "NockStack size for the mining kernel, 'auto' to size it from the proof length, or a size such as 16GB",
+        value_parser = value_parser!(StackSize),
+        default_value = "auto"
+    )]
+    pub mining_stack_size: StackSize,
+    #[arg(
+        long,
+        help = "Extra nonce mixed into every mining candidate, for pool workers sharing candidates",
+        default_value = "0"
+    )]
+    pub mining_extra_nonce: u64,
+    #[arg(
+        long,
+        help = "Nonces to mine with: 'candidate' for the node's, 'random' from the OS RNG, 'sequential[:start]', or 'seeded:<seed>' from a tip5 RNG",
+        value_parser = value_parser!(NonceSource),
+        default_value = "candidate"
+    )]
+    pub mining_nonces: NonceSource,
+    #[arg(
+        long,
+        help = "Mine with a fake prover that answers after this many milliseconds, to exercise the mining pipeline without proving. The proofs are rejected."
+    )]
+    pub mining_dry_run: Option<u64>,
+    #[arg(
+        long,
+        help = "Restart the mining kernel if a proof takes longer than this many seconds"
+    )]
+    pub mining_stall_timeout: Option<u64>,
+    #[arg(
+        long,
+        help = "Times the mining kernel is restarted on one candidate before giving up",
+        default_value = "3"
+    )]
+    pub mining_max_restarts: u32,
+    #[arg(
+        long,
+        help = "Write the candidate and kernel state of every failed prove poke to this directory, for reproducing the failure"
+    )]
+    pub mining_crash_dump_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Crash dumps kept in --mining-crash-dump-dir, the oldest are removed",
+        default_value_t = DEFAULT_MAX_DUMPS
+    )]
+    pub mining_max_crash_dumps: usize,
+    #[arg(
+        long,
+        help = "Mine with the miner kernel jam at this path instead of the one built in, falling back to the built-in kernel if it is not a kernel"
+    )]
+    pub kernel_path: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Mine reproducibly with this entropy seed, so repeated runs on a candidate produce identical proofs"
+    )]
+    pub mining_deterministic: Option<u64>,
+    #[arg(
+        long,
+        help = "Run tip5 on this backend instead of the fastest the CPU supports: portable, avx2, avx512 or neon",
+        value_parser = value_parser!(Tip5Backend)
+    )]
+    pub tip5_backend: Option<Tip5Backend>,
     #[arg(long, help = "Watch for genesis block", default_value = "false")]
     pub genesis_watcher: bool,
     #[arg(long, help = "Mine genesis block", default_value = "false")]
     pub genesis_leader: bool,
-    #[arg(long, help = "use fake genesis block", default_value = "false")]
+    #[arg(
+        long,
+        help = "Network to join: mainnet, testnet, or regtest for local networks that mine a block every few seconds with a dev-proving build",
+        value_parser = value_parser!(NetworkMode),
+        default_value = "mainnet"
+    )]
+    pub network: NetworkMode,
+    #[arg(
+        long,
+        help = "use fake genesis block, keeping the network's consensus constants",
+        default_value = "false"
+    )]
     pub fakenet: bool,
     #[arg(long, help = "Genesis block message", default_value = "Hail Zorp")]
     pub genesis_message: String,
@@ -113,9 +190,37 @@ pub struct NockchainCli {
     pub max_system_memory_fraction: Option<f64>,
     #[arg(long, help = "Maximum process memory for connection limits (bytes)")]
     pub max_system_memory_bytes: Option<usize>,
+    #[arg(
+        long,
+        help = "Serve the transaction submission/status API on this address (e.g. 127.0.0.1:3030)"
+    )]
+    pub tx_api_bind: Option<std::net::SocketAddr>,
 }
 
 impl NockchainCli {
+    /// The network whose consensus constants apply. `--fakenet` does not
+    /// change them, only `--network` does.
+    pub fn network(&self) -> NetworkMode {
+        self.network
+    }
+
+    /// Whether genesis is made up locally, with `--fakenet` or on a network
+    /// that always does
+    pub fn fake_genesis(&self) -> bool {
+        self.fakenet || self.network.fake_genesis()
+    }
+
+    /// Peers dialed unless `--no-default-peers` is given. A fakenet on
+    /// mainnet's constants has its own genesis, so it skips mainnet's
+    /// backbone.
+    pub fn backbone_nodes(&self) -> &'static [&'static str] {
+        if self.fakenet && self.network == NetworkMode::Mainnet {
+            TESTNET_BACKBONE_NODES
+        } else {
+            self.network.backbone_nodes()
+        }
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         if self.mine && !(self.mining_pubkey.is_some() || self.mining_key_adv.is_some()) {
             return Err(
@@ -136,7 +241,11 @@ impl NockchainCli {
             );
         }
 
-        if !self.fakenet && (self.genesis_watcher || self.genesis_leader) {
+        if self.mine {
+            self.network().check_build()?;
+        }
+
+        if !self.fake_genesis() && (self.genesis_watcher || self.genesis_leader) {
             if self.btc_node_url.is_empty() {
                 return Err(
                     "Must specify --btc-node-url when using genesis_watcher or genesis_leader"
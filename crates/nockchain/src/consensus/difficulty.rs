@@ -0,0 +1,257 @@
+//! Working out the target of the next block from the blocks before it.
+//!
+//! A [`DifficultyAlgorithm`] looks at the most recent blocks, oldest first
+//! and ending with the parent, and returns the target the next block's proof
+//! has to hash at or below. Larger targets are easier, so blocks coming too
+//! slowly raise the target.
+//!
+//! [`EpochRetarget`] is the rule the kernel enforces in `+compute-target`:
+//! the target only moves at the end of an epoch, by the ratio of how long
+//! the epoch took to how long it should have taken. Like the kernel's
+//! `+compute-epoch-duration`, an epoch is measured between the median
+//! timestamps of the blocks closing it and the epoch before, so one block
+//! lying about its time barely moves it. [`MovingAverage`] retargets every block from the mean target
+//! and block interval over a window, which settles in a handful of blocks
+//! and suits test networks.
+//!
+//! Miners compute the target to mine at with [`next_target`], validators
+//! compare a block's target against it with [`check_target`].
+
+use ibig::UBig;
+use thiserror::Error;
+use zkvm_jetpack::form::math::base::PRIME;
+
+use crate::light_client::BlockHeader;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DifficultyError {
+    #[error("retargeting block {height} needs the {needed} blocks before it, have {have}")]
+    NotEnoughBlocks {
+        height: u64,
+        needed: usize,
+        have: usize,
+    },
+    #[error("block at height {0} does not follow the block before it")]
+    NotConsecutive(u64),
+    #[error("block target {actual:x} should be {expected:x}")]
+    WrongTarget { expected: UBig, actual: UBig },
+}
+
+/// What difficulty algorithms need to know about a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockTime {
+    pub height: u64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub target: UBig,
+}
+
+impl From<&BlockHeader> for BlockTime {
+    fn from(header: &BlockHeader) -> Self {
+        Self {
+            height: header.height,
+            timestamp: header.timestamp,
+            target: header.target.clone(),
+        }
+    }
+}
+
+/// Computes the target of the next block
+pub trait DifficultyAlgorithm: Send + Sync {
+    /// How many blocks before height `height` [`Self::next_target`] needs,
+    /// at least 1. Fewer are passed near genesis.
+    fn window(&self, height: u64) -> usize;
+
+    /// The target of the block after `recent`, which is consecutive, ends
+    /// with the parent and holds [`Self::window`] blocks, or every block
+    /// since genesis if there are fewer
+    fn next_target(&self, recent: &[BlockTime]) -> UBig;
+}
+
+/// The easiest target there is, every TIP5 digest meets it
+pub fn max_target() -> UBig {
+    UBig::from(PRIME).pow(5) - UBig::from(1u8)
+}
+
+/// Work a block at `target` adds to the chain's accumulated work, the
+/// kernel's `+compute-work`. Every network shares the max target it is
+/// measured against.
+pub fn block_work(target: &UBig) -> UBig {
+    max_target() / (target + UBig::from(1u8))
+}
+
+/// The target of the block after `recent`, which must end with the parent.
+/// Only the last [`DifficultyAlgorithm::window`] blocks are looked at.
+pub fn next_target(
+    algorithm: &dyn DifficultyAlgorithm,
+    recent: &[BlockTime],
+) -> Result<UBig, DifficultyError> {
+    let Some(parent) = recent.last() else {
+        return Err(DifficultyError::NotEnoughBlocks {
+            height: 0,
+            needed: 1,
+            have: 0,
+        });
+    };
+    let height = parent.height + 1;
+    let needed = algorithm.window(height).max(1);
+    let recent = &recent[recent.len().saturating_sub(needed)..];
+    // close to genesis every block there is will have to do
+    if recent.len() < needed && recent[0].height != 0 {
+        return Err(DifficultyError::NotEnoughBlocks {
+            height,
+            needed,
+            have: recent.len(),
+        });
+    }
+    if let Some(pair) = recent
+        .windows(2)
+        .find(|pair| pair[1].height != pair[0].height + 1)
+    {
+        return Err(DifficultyError::NotConsecutive(pair[1].height));
+    }
+    Ok(algorithm.next_target(recent))
+}
+
+/// Check that a block following `recent` carries the target it should
+pub fn check_target(
+    algorithm: &dyn DifficultyAlgorithm,
+    recent: &[BlockTime],
+    target: &UBig,
+) -> Result<(), DifficultyError> {
+    let expected = next_target(algorithm, recent)?;
+    if &expected != target {
+        return Err(DifficultyError::WrongTarget {
+            expected,
+            actual: target.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// The kernel's retarget: fixed for an epoch, then scaled by how long the
+/// epoch took, by at most a factor of 4 either way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochRetarget {
+    pub blocks_per_epoch: u64,
+    /// Seconds an epoch should take
+    pub target_epoch_duration: u64,
+    /// Blocks the median past timestamp closing an epoch is taken over
+    pub min_past_blocks: u64,
+    pub max_target: UBig,
+}
+
+/// The kernel's `+median`, rounding down between the middle two
+fn median(timestamps: &[BlockTime]) -> u64 {
+    let mut sorted: Vec<u64> = timestamps.iter().map(|block| block.timestamp).collect();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2
+    } else {
+        sorted[mid]
+    }
+}
+
+impl Default for EpochRetarget {
+    /// The mainnet `blockchain-constants`
+    fn default() -> Self {
+        Self {
+            blocks_per_epoch: 2016,
+            target_epoch_duration: 14 * 24 * 60 * 60,
+            min_past_blocks: 11,
+            max_target: max_target(),
+        }
+    }
+}
+
+impl EpochRetarget {
+    /// The kernel's `min-timestamps` entry for `recent`'s last block: the
+    /// median timestamp of it and the blocks before it, back to genesis
+    fn median_past_time(&self, recent: &[BlockTime]) -> u64 {
+        let past = (self.min_past_blocks as usize).max(1);
+        median(&recent[recent.len().saturating_sub(past)..])
+    }
+}
+
+impl DifficultyAlgorithm for EpochRetarget {
+    /// An epoch, and the blocks the median past time of the block before it
+    /// is taken over
+    fn window(&self, height: u64) -> usize {
+        if height % self.blocks_per_epoch == 0 {
+            (self.blocks_per_epoch + self.min_past_blocks.max(1)) as usize
+        } else {
+            1
+        }
+    }
+
+    fn next_target(&self, recent: &[BlockTime]) -> UBig {
+        let parent = &recent[recent.len() - 1];
+        if (parent.height + 1) % self.blocks_per_epoch != 0 {
+            return parent.target.clone();
+        }
+        // the block closing the epoch before, genesis closing the first
+        let start = parent
+            .height
+            .saturating_sub(self.blocks_per_epoch)
+            .saturating_sub(recent[0].height);
+        let start = self.median_past_time(&recent[..=start as usize]);
+        let end = self.median_past_time(recent);
+        let duration = end.saturating_sub(start).clamp(
+            self.target_epoch_duration / 4,
+            self.target_epoch_duration * 4,
+        );
+        let next = &parent.target * UBig::from(duration) / UBig::from(self.target_epoch_duration);
+        next.min(self.max_target.clone())
+    }
+}
+
+/// Retargets every block to the mean target over the last `intervals`
+/// blocks, scaled by how far their mean interval is from `block_time`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovingAverage {
+    /// Block intervals averaged over
+    pub intervals: usize,
+    /// Seconds a block should take
+    pub block_time: u64,
+    /// Most the target moves per block, as a factor either way
+    pub max_step: u64,
+    pub max_target: UBig,
+}
+
+impl MovingAverage {
+    /// Panics if `block_time` is 0
+    pub fn new(intervals: usize, block_time: u64) -> Self {
+        assert!(block_time > 0, "block time must be at least a second");
+        Self {
+            intervals,
+            block_time,
+            max_step: 4,
+            max_target: max_target(),
+        }
+    }
+}
+
+impl DifficultyAlgorithm for MovingAverage {
+    fn window(&self, _height: u64) -> usize {
+        self.intervals + 1
+    }
+
+    fn next_target(&self, recent: &[BlockTime]) -> UBig {
+        let intervals = recent.len() - 1;
+        if intervals == 0 {
+            return recent[0].target.clone();
+        }
+        let expected = intervals as u64 * self.block_time;
+        let span = recent[intervals]
+            .timestamp
+            .saturating_sub(recent[0].timestamp)
+            .clamp(expected / self.max_step, expected * self.max_step);
+        let mean = recent[1..]
+            .iter()
+            .fold(UBig::from(0u8), |sum, block| sum + &block.target)
+            / UBig::from(intervals);
+        let next = mean * UBig::from(span) / UBig::from(expected);
+        next.clamp(UBig::from(1u8), self.max_target.clone())
+    }
+}
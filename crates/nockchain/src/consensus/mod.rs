@@ -0,0 +1,7 @@
+//! Consensus rules shared by the miner and by anything validating blocks.
+//!
+//! The kernel remains the authority on consensus. These are Rust versions of
+//! rules the driver needs without a kernel at hand, such as a light client
+//! checking headers or a miner working out the target of the next block.
+
+pub mod difficulty;
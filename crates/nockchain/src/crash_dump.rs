@@ -0,0 +1,312 @@
+//! Keeping what it takes to reproduce a failed prove poke.
+//!
+//! [`CrashDumpBackend`] wraps another [`ProvingBackend`]. When a prove poke
+//! fails, whether the kernel crashed on the candidate, ran out of NockStack
+//! or its serf thread panicked, the candidate and the kernel's state are
+//! written to a [`CrashDumps`] directory before the error is passed on:
+//!
+//! ```text
+//! <dir>/index.jsonl
+//! <dir>/<id>/cause.jam
+//! <dir>/<id>/state.jam
+//! ```
+//!
+//! Every line of `index.jsonl` is a [`CrashDump`] naming its files. The
+//! state is the kernel's `ExportedState` as of before the failed poke, which
+//! [`nockapp::kernel::form::Kernel::import_state`] loads into a fresh
+//! kernel; poking that kernel with the cause on [`CrashDump::wire`] replays
+//! the failure. A kernel whose serf died with the poke has no state to give,
+//! and its dump says why instead.
+//!
+//! Pokes that fail because they were cancelled, e.g. by a newer template or
+//! the watchdog, are not dumped. Only the newest [`CrashDumps::max_dumps`]
+//! dumps are kept.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures::future::BoxFuture;
+use nockapp::kernel::form::{Entropy, MemoryStats};
+use nockapp::noun::slab::{CueError, NounSlab};
+use nockapp::wire::{Wire, WireRepr};
+use nockapp::CrownError;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{error, warn};
+
+use crate::backend::{Prover, ProvingBackend};
+use crate::mining::MiningWire;
+use crate::stack::is_out_of_memory;
+
+/// Dumps kept unless configured otherwise
+pub const DEFAULT_MAX_DUMPS: usize = 16;
+
+const INDEX_FILE: &str = "index.jsonl";
+const CAUSE_FILE: &str = "cause.jam";
+const STATE_FILE: &str = "state.jam";
+
+#[derive(Debug, Error)]
+pub enum CrashDumpError {
+    #[error("crash dump: {0}")]
+    Io(#[from] io::Error),
+    #[error("crash dump index: {0}")]
+    Index(#[from] serde_json::Error),
+    #[error("crash dump cause is not a jammed noun: {0}")]
+    Cue(#[from] CueError),
+}
+
+/// One failed poke, a line of the index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashDump {
+    /// Names the dump's directory, ordered by when the poke failed
+    pub id: String,
+    /// Unix time the poke failed at, in seconds
+    pub failed_at: u64,
+    /// The wire the cause was poked on, as comma separated tags
+    pub wire: String,
+    /// The poke's entropy, if it was fixed
+    pub entropy: Option<u64>,
+    pub error: String,
+    pub out_of_memory: bool,
+    /// The jammed cause, relative to the dump directory
+    pub cause: PathBuf,
+    /// The jammed kernel state, relative to the dump directory, if the kernel
+    /// could still give it
+    pub state: Option<PathBuf>,
+    /// Why there is no state
+    pub state_error: Option<String>,
+}
+
+/// A crash-dump directory, see the module docs
+#[derive(Debug, Clone)]
+pub struct CrashDumps {
+    dir: PathBuf,
+    max_dumps: usize,
+    // one writer at a time for the index
+    index: Arc<Mutex<()>>,
+    seq: Arc<AtomicU64>,
+}
+
+impl CrashDumps {
+    /// Dump into `dir`, created on the first dump, keeping the newest
+    /// `max_dumps`
+    pub fn new(dir: impl Into<PathBuf>, max_dumps: usize) -> Self {
+        Self {
+            dir: dir.into(),
+            max_dumps: max_dumps.max(1),
+            index: Arc::new(Mutex::new(())),
+            seq: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn max_dumps(&self) -> usize {
+        self.max_dumps
+    }
+
+    /// Every dump in the index, oldest first
+    pub fn list(&self) -> Result<Vec<CrashDump>, CrashDumpError> {
+        let _index = self.lock_index();
+        self.read_index()
+    }
+
+    /// The cause of `dump`, ready to poke
+    pub fn cause(&self, dump: &CrashDump) -> Result<NounSlab, CrashDumpError> {
+        let jam = fs::read(self.dir.join(&dump.cause))?;
+        let mut cause = NounSlab::new();
+        let root = cause.cue_into(jam.into())?;
+        cause.set_root(root);
+        Ok(cause)
+    }
+
+    /// The kernel state of `dump`, for [`nockapp::kernel::form::Kernel::import_state`]
+    pub fn state(&self, dump: &CrashDump) -> Result<Option<Vec<u8>>, CrashDumpError> {
+        match &dump.state {
+            Some(state) => Ok(Some(fs::read(self.dir.join(state))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Write a dump of `cause` failing with `error` on `wire`, and add it to
+    /// the index, dropping the oldest dumps beyond [`Self::max_dumps`]
+    pub fn record(
+        &self,
+        wire: &WireRepr,
+        cause: &NounSlab,
+        entropy: Entropy,
+        error: &CrownError,
+        state: Result<Vec<u8>, CrownError>,
+    ) -> Result<CrashDump, CrashDumpError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let id = format!(
+            "{}-{}",
+            now.as_millis(),
+            self.seq.fetch_add(1, Ordering::Relaxed)
+        );
+        let dump_dir = self.dir.join(&id);
+        fs::create_dir_all(&dump_dir)?;
+        let cause_path = Path::new(&id).join(CAUSE_FILE);
+        fs::write(self.dir.join(&cause_path), cause.jam())?;
+        let (state, state_error) = match state {
+            Ok(bytes) => {
+                let state_path = Path::new(&id).join(STATE_FILE);
+                fs::write(self.dir.join(&state_path), bytes)?;
+                (Some(state_path), None)
+            }
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let dump = CrashDump {
+            id,
+            failed_at: now.as_secs(),
+            wire: wire.tags_as_csv(),
+            entropy: match entropy {
+                Entropy::Random => None,
+                Entropy::Fixed(value) => Some(value),
+            },
+            error: error.to_string(),
+            out_of_memory: is_out_of_memory(error),
+            cause: cause_path,
+            state,
+            state_error,
+        };
+
+        let _index = self.lock_index();
+        let mut dumps = self.read_index()?;
+        dumps.push(dump.clone());
+        let expired = dumps.len().saturating_sub(self.max_dumps);
+        for old in dumps.drain(..expired) {
+            if let Err(e) = fs::remove_dir_all(self.dir.join(&old.id)) {
+                warn!("could not remove crash dump {}: {e}", old.id);
+            }
+        }
+        self.write_index(&dumps)?;
+        Ok(dump)
+    }
+
+    fn lock_index(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.index
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn read_index(&self) -> Result<Vec<CrashDump>, CrashDumpError> {
+        let index = match fs::read_to_string(self.dir.join(INDEX_FILE)) {
+            Ok(index) => index,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        index
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Ok(serde_json::from_str(line)?))
+            .collect()
+    }
+
+    /// Replace the index atomically
+    fn write_index(&self, dumps: &[CrashDump]) -> Result<(), CrashDumpError> {
+        let tmp = self.dir.join(format!("{INDEX_FILE}.tmp"));
+        let mut file = fs::File::create(&tmp)?;
+        for dump in dumps {
+            serde_json::to_writer(&mut file, dump)?;
+            file.write_all(b"\n")?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, self.dir.join(INDEX_FILE))?;
+        Ok(())
+    }
+}
+
+/// A [`ProvingBackend`] whose failed prove pokes are dumped
+pub struct CrashDumpBackend {
+    inner: Arc<dyn ProvingBackend>,
+    dumps: CrashDumps,
+}
+
+impl CrashDumpBackend {
+    pub fn new(inner: Arc<dyn ProvingBackend>, dumps: CrashDumps) -> Self {
+        Self { inner, dumps }
+    }
+}
+
+impl ProvingBackend for CrashDumpBackend {
+    fn load(&self, stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Prover>, CrownError>> {
+        Box::pin(async move {
+            let prover = self.inner.load(stack_words).await?;
+            Ok(Box::new(DumpingProver {
+                inner: Arc::from(prover),
+                dumps: self.dumps.clone(),
+                cancelled: Arc::new(AtomicBool::new(false)),
+            }) as Box<dyn Prover>)
+        })
+    }
+}
+
+struct DumpingProver {
+    inner: Arc<dyn Prover>,
+    dumps: CrashDumps,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Prover for DumpingProver {
+    fn prove(
+        &self,
+        candidate: NounSlab,
+        entropy: Entropy,
+    ) -> BoxFuture<'static, Result<NounSlab, CrownError>> {
+        self.cancelled.store(false, Ordering::SeqCst);
+        let poke = self.inner.prove(candidate.clone(), entropy);
+        let inner = self.inner.clone();
+        let dumps = self.dumps.clone();
+        let cancelled = self.cancelled.clone();
+        Box::pin(async move {
+            let failure = match poke.await {
+                Ok(effects) => return Ok(effects),
+                Err(e) if cancelled.load(Ordering::SeqCst) => return Err(e),
+                Err(e) => e,
+            };
+            let state = match inner.state() {
+                Some(state) => state.await,
+                None => Err(CrownError::Unknown("prover runs no kernel".to_string())),
+            };
+            let wire = MiningWire::Candidate.to_wire();
+            let (dumped, failure) = tokio::task::spawn_blocking(move || {
+                let dumped = dumps
+                    .record(&wire, &candidate, entropy, &failure, state)
+                    .map(|dump| dumps.dir().join(dump.id));
+                (dumped, failure)
+            })
+            .await?;
+            match dumped {
+                Ok(dir) => error!("Prove poke failed, crash dump written to {}", dir.display()),
+                Err(e) => warn!("Prove poke failed, could not write a crash dump: {e}"),
+            }
+            Err(failure)
+        })
+    }
+
+    fn cancel(&self) -> bool {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.inner.cancel()
+    }
+
+    fn warm_up(&self) -> BoxFuture<'static, Result<std::time::Duration, CrownError>> {
+        self.inner.warm_up()
+    }
+
+    fn state(&self) -> Option<BoxFuture<'static, Result<Vec<u8>, CrownError>>> {
+        self.inner.state()
+    }
+
+    fn memory_stats(&self) -> Option<MemoryStats> {
+        self.inner.memory_stats()
+    }
+}
@@ -0,0 +1,441 @@
+//! A long-lived process that keeps miner kernels loaded for other processes.
+//!
+//! Booting the miner kernel takes seconds, which every CLI invocation and
+//! test pays again when it loads its own. [`KernelDaemon`] loads its kernels
+//! once and serves prove and verify requests from [`DaemonClient`]s over a
+//! unix socket, so only the daemon pays for the load.
+//!
+//! Each request and response is a [`Codec::Bincode`] encoded
+//! [`DaemonRequest`] or [`DaemonResponse`] in a [`crate::frame`], preceded
+//! by its length as 8 little-endian bytes. A connection carries
+//! one request at a time; clients wanting more in flight open more
+//! connections. Requests go to the hosted kernel with the fewest pokes
+//! queued, each kernel taking turns through its own [`PokeQueue`], so
+//! verifications get ahead of queued proves as they do in the node.
+
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::try_join_all;
+use ibig::UBig;
+use nockapp::kernel::form::Entropy;
+use nockapp::noun::slab::{CueError, NounSlab};
+use nockapp::CrownError;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{UnixListener, UnixStream};
+use tracing::{debug, info, warn};
+
+use crate::backend::{KernelJam, ProvingBackend, SharedKernelBackend};
+use crate::codec::{Codec, CodecError};
+use crate::frame::{read_frame, write_frame, FrameTooLarge, DEFAULT_MAX_FRAME_BYTES};
+use crate::poke::{PokeQueue, QueueDepth, DEFAULT_VERIFY_BURST};
+use crate::stack::StackSize;
+use crate::verifier::{ProofCheck, VerifierBackend, VerifyError};
+
+#[derive(Debug, Error)]
+pub enum DaemonError {
+    #[error("daemon socket: {0}")]
+    Io(#[from] io::Error),
+    #[error("daemon message: {0}")]
+    Codec(#[from] CodecError),
+    #[error("not a jammed noun: {0}")]
+    Cue(#[from] CueError),
+    #[error("kernel failed: {0}")]
+    Kernel(#[from] CrownError),
+    #[error("verify failed: {0}")]
+    Verify(#[from] VerifyError),
+    #[error("daemon message: {0}")]
+    TooLarge(#[from] FrameTooLarge),
+    #[error("daemon hosts no verifier")]
+    NoVerifier,
+    #[error("daemon refused the request: {0}")]
+    Refused(String),
+    #[error("daemon answered with {0}")]
+    UnexpectedResponse(&'static str),
+}
+
+/// What a client asks the daemon for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DaemonRequest {
+    /// Prove a jammed candidate, the cause of a prove poke
+    Prove {
+        #[serde(with = "serde_bytes")]
+        candidate: Vec<u8>,
+        /// Fixed entropy, fresh entropy for every poke if absent
+        entropy: Option<u64>,
+    },
+    /// Verify a jammed proof
+    Verify {
+        #[serde(with = "serde_bytes")]
+        proof: Vec<u8>,
+    },
+    Status,
+}
+
+/// The daemon's answer to a [`DaemonRequest`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DaemonResponse {
+    Proved {
+        /// The jammed effects of the prove poke
+        #[serde(with = "serde_bytes")]
+        effects: Vec<u8>,
+        micros: u64,
+    },
+    Verified {
+        valid: bool,
+        /// Hash of a valid proof in hex, see [`ProofCheck::pow`]
+        pow: Option<String>,
+        micros: u64,
+    },
+    Status(DaemonStatus),
+    /// The request failed, with the reason
+    Error(String),
+}
+
+impl DaemonResponse {
+    fn kind(&self) -> &'static str {
+        match self {
+            DaemonResponse::Proved { .. } => "a proof",
+            DaemonResponse::Verified { .. } => "a verdict",
+            DaemonResponse::Status(_) => "its status",
+            DaemonResponse::Error(_) => "an error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    /// Queue depth of every hosted kernel, in the order they were added
+    pub kernels: Vec<QueueDepth>,
+    pub uptime_secs: u64,
+    /// Requests answered so far, failed ones included
+    pub served: u64,
+}
+
+/// One kernel hosted by a [`KernelDaemon`]
+#[derive(Clone)]
+pub struct HostedKernel {
+    prover: Arc<dyn ProvingBackend>,
+    verifier: Option<Arc<dyn VerifierBackend>>,
+    queue: PokeQueue,
+}
+
+impl HostedKernel {
+    /// Host any backend. Its provers and verifiers should take turns
+    /// through `queue`, which is only read to balance requests.
+    pub fn new(
+        prover: Arc<dyn ProvingBackend>,
+        verifier: Option<Arc<dyn VerifierBackend>>,
+        queue: PokeQueue,
+    ) -> Self {
+        Self {
+            prover,
+            verifier,
+            queue,
+        }
+    }
+
+    /// Load the prover and run its warm-up poke
+    async fn preload(&self, stack_words: usize) -> Result<Duration, CrownError> {
+        self.prover.load(stack_words).await?.warm_up().await
+    }
+
+    fn load(&self) -> usize {
+        let depth = self.queue.depth();
+        depth.verify + depth.mine + usize::from(depth.running.is_some())
+    }
+}
+
+impl From<SharedKernelBackend> for HostedKernel {
+    fn from(backend: SharedKernelBackend) -> Self {
+        let queue = backend.queue().clone();
+        let backend = Arc::new(backend);
+        Self::new(backend.clone(), Some(backend), queue)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DaemonConfig {
+    /// Miner kernels to host
+    pub kernels: usize,
+    /// NockStack of every kernel, sized for proofs of length 0 if `Auto`
+    pub stack_size: StackSize,
+    /// See [`PokeQueue::new`]
+    pub verify_burst: usize,
+    /// Largest request accepted, in bytes
+    pub max_frame_bytes: usize,
+    /// The jam every kernel boots
+    pub kernel: KernelJam,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            kernels: 1,
+            stack_size: StackSize::default(),
+            verify_burst: DEFAULT_VERIFY_BURST,
+            max_frame_bytes: DEFAULT_MAX_FRAME_BYTES,
+            kernel: KernelJam::embedded(),
+        }
+    }
+}
+
+/// Serves prove and verify requests with kernels it keeps loaded
+pub struct KernelDaemon {
+    kernels: Vec<HostedKernel>,
+    stack_words: usize,
+    max_frame_bytes: usize,
+    started: Instant,
+    served: AtomicU64,
+}
+
+impl KernelDaemon {
+    /// Host `config.kernels` miner kernels, each shared by proves and
+    /// verifies, see [`SharedKernelBackend`]
+    pub fn new(config: &DaemonConfig) -> Self {
+        let stack_words = config.stack_size.words_for(0);
+        let kernels = (0..config.kernels.max(1))
+            .map(|_| {
+                let queue = PokeQueue::new(config.verify_burst);
+                HostedKernel::from(
+                    SharedKernelBackend::new(stack_words, queue).with_kernel(config.kernel.clone()),
+                )
+            })
+            .collect();
+        Self::with_kernels(kernels, config)
+    }
+
+    /// Host the given kernels instead of booting miner kernels
+    pub fn with_kernels(kernels: Vec<HostedKernel>, config: &DaemonConfig) -> Self {
+        assert!(!kernels.is_empty(), "a daemon needs a kernel to serve with");
+        Self {
+            kernels,
+            stack_words: config.stack_size.words_for(0),
+            max_frame_bytes: config.max_frame_bytes,
+            started: Instant::now(),
+            served: AtomicU64::new(0),
+        }
+    }
+
+    /// Boot and warm up every kernel now rather than on the first request.
+    /// Returns the slowest warm-up.
+    pub async fn preload(&self) -> Result<Duration, CrownError> {
+        let warm_ups = try_join_all(
+            self.kernels
+                .iter()
+                .map(|kernel| kernel.preload(self.stack_words)),
+        )
+        .await?;
+        Ok(warm_ups.into_iter().max().unwrap_or_default())
+    }
+
+    pub fn status(&self) -> DaemonStatus {
+        DaemonStatus {
+            kernels: self.kernels.iter().map(|k| k.queue.depth()).collect(),
+            uptime_secs: self.started.elapsed().as_secs(),
+            served: self.served.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Answer one request. Failures are answered with
+    /// [`DaemonResponse::Error`] rather than returned.
+    pub async fn handle(&self, request: DaemonRequest) -> DaemonResponse {
+        let response = match request {
+            DaemonRequest::Prove { candidate, entropy } => self
+                .prove(candidate, entropy.map_or(Entropy::Random, Entropy::Fixed))
+                .await
+                .unwrap_or_else(|e| DaemonResponse::Error(e.to_string())),
+            DaemonRequest::Verify { proof } => self
+                .verify(proof)
+                .await
+                .unwrap_or_else(|e| DaemonResponse::Error(e.to_string())),
+            DaemonRequest::Status => DaemonResponse::Status(self.status()),
+        };
+        self.served.fetch_add(1, Ordering::Relaxed);
+        response
+    }
+
+    async fn prove(
+        &self,
+        candidate: Vec<u8>,
+        entropy: Entropy,
+    ) -> Result<DaemonResponse, DaemonError> {
+        let candidate = cue(candidate)?;
+        let started = Instant::now();
+        let prover = self.least_busy().prover.load(self.stack_words).await?;
+        let effects = prover.prove(candidate, entropy).await?;
+        Ok(DaemonResponse::Proved {
+            effects: effects.jam().to_vec(),
+            micros: started.elapsed().as_micros() as u64,
+        })
+    }
+
+    async fn verify(&self, proof: Vec<u8>) -> Result<DaemonResponse, DaemonError> {
+        let proof = cue(proof)?;
+        let started = Instant::now();
+        let backend = self
+            .kernels
+            .iter()
+            .filter(|kernel| kernel.verifier.is_some())
+            .min_by_key(|kernel| kernel.load())
+            .and_then(|kernel| kernel.verifier.as_ref())
+            .ok_or(DaemonError::NoVerifier)?;
+        let verifier = backend.load(self.stack_words).await?;
+        let check = verifier.verify(proof, Entropy::Random).await?;
+        Ok(DaemonResponse::Verified {
+            valid: check.valid,
+            pow: check.pow.map(|pow| format!("{pow:x}")),
+            micros: started.elapsed().as_micros() as u64,
+        })
+    }
+
+    fn least_busy(&self) -> &HostedKernel {
+        self.kernels
+            .iter()
+            .min_by_key(|kernel| kernel.load())
+            .expect("daemon has a kernel")
+    }
+
+    /// Answer requests on `stream` until the client hangs up
+    pub async fn serve_connection<S>(&self, mut stream: S) -> Result<u64, DaemonError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut requests = 0;
+        while let Some(frame) = read_frame(&mut stream, self.max_frame_bytes).await? {
+            let response = match frame.map(|frame| Codec::Bincode.decode::<DaemonRequest>(&frame)) {
+                Ok(Ok(request)) => self.handle(request).await,
+                Ok(Err(e)) => DaemonResponse::Error(format!("malformed request: {e}")),
+                Err(e) => DaemonResponse::Error(format!("request refused: {e}")),
+            };
+            write_frame(&mut stream, &Codec::Bincode.encode(&response)?).await?;
+            requests += 1;
+        }
+        Ok(requests)
+    }
+
+    /// Accept connections on `listener` forever, serving each concurrently
+    pub async fn serve(self: Arc<Self>, listener: UnixListener) -> io::Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let daemon = self.clone();
+            tokio::spawn(async move {
+                match daemon.serve_connection(stream).await {
+                    Ok(requests) => debug!("daemon client left after {requests} requests"),
+                    Err(e) => warn!("daemon client failed: {e}"),
+                }
+            });
+        }
+    }
+}
+
+/// Bind `path` for a daemon, replacing the socket of a daemon that is gone.
+/// Fails if another daemon still answers on it.
+pub async fn bind(path: impl AsRef<Path>) -> io::Result<UnixListener> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    if tokio::fs::try_exists(path).await? {
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("a daemon is already listening on {}", path.display()),
+            ));
+        }
+        info!("removing stale daemon socket {}", path.display());
+        tokio::fs::remove_file(path).await?;
+    }
+    UnixListener::bind(path)
+}
+
+/// A connection to a [`KernelDaemon`]
+pub struct DaemonClient {
+    stream: UnixStream,
+}
+
+impl DaemonClient {
+    pub async fn connect(path: impl AsRef<Path>) -> Result<Self, DaemonError> {
+        Ok(Self::new(UnixStream::connect(path).await?))
+    }
+
+    pub fn new(stream: UnixStream) -> Self {
+        Self { stream }
+    }
+
+    /// Prove the candidate at the root of `candidate`, returning the effects
+    /// of the prove poke
+    pub async fn prove(
+        &mut self,
+        candidate: &NounSlab,
+        entropy: Entropy,
+    ) -> Result<NounSlab, DaemonError> {
+        let entropy = match entropy {
+            Entropy::Random => None,
+            Entropy::Fixed(value) => Some(value),
+        };
+        let request = DaemonRequest::Prove {
+            candidate: candidate.jam().to_vec(),
+            entropy,
+        };
+        match self.request(&request).await? {
+            DaemonResponse::Proved { effects, .. } => cue(effects),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Verify the proof at the root of `proof`
+    pub async fn verify(&mut self, proof: &NounSlab) -> Result<ProofCheck, DaemonError> {
+        let request = DaemonRequest::Verify {
+            proof: proof.jam().to_vec(),
+        };
+        match self.request(&request).await? {
+            DaemonResponse::Verified { valid, pow, .. } => Ok(ProofCheck {
+                valid,
+                pow: pow
+                    .map(|pow| UBig::from_str_radix(&pow, 16))
+                    .transpose()
+                    .map_err(|_| DaemonError::UnexpectedResponse("a malformed proof hash"))?,
+            }),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn status(&mut self) -> Result<DaemonStatus, DaemonError> {
+        match self.request(&DaemonRequest::Status).await? {
+            DaemonResponse::Status(status) => Ok(status),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Send `request` and wait for the daemon's answer
+    pub async fn request(
+        &mut self,
+        request: &DaemonRequest,
+    ) -> Result<DaemonResponse, DaemonError> {
+        write_frame(&mut self.stream, &Codec::Bincode.encode(request)?).await?;
+        let frame = read_frame(&mut self.stream, usize::MAX)
+            .await?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))??;
+        Ok(Codec::Bincode.decode(&frame)?)
+    }
+}
+
+fn unexpected(response: DaemonResponse) -> DaemonError {
+    match response {
+        DaemonResponse::Error(reason) => DaemonError::Refused(reason),
+        other => DaemonError::UnexpectedResponse(other.kind()),
+    }
+}
+
+fn cue(jam: Vec<u8>) -> Result<NounSlab, DaemonError> {
+    let mut slab = NounSlab::new();
+    let root = slab.cue_into(jam.into())?;
+    slab.set_root(root);
+    Ok(slab)
+}
@@ -0,0 +1,829 @@
+//! Decoding and checking the effects the miner kernel hands back.
+//!
+//! A successful prove poke emits
+//! `[%command %pow prf=proof dig=@ux block-commitment=digest nonce=digest]`.
+//! [`MiningEffect`] decodes that shape with a specific error for each way it
+//! can be malformed, and [`MiningEffect::verify`] checks that it actually
+//! answers the candidate we asked to prove.
+//!
+//! The kernel's other pokes answer with [`MinerStatus`] for `%status` and
+//! [`CancelledEffect`] for `%cancel`, and with the verifier kernel's
+//! `%verified` verdict for `%verify`; [`find_effect`] picks one out of a
+//! poke's effects.
+//!
+//! [`validate_effect_schema`] checks any effect noun against a declarative
+//! [`ExpectedSchema`] and names the path to the first field that differs, so
+//! a kernel change to an effect's shape shows up as a precise error.
+
+use std::borrow::Cow;
+use std::marker::PhantomData;
+
+use ibig::UBig;
+use nockapp::noun::slab::NounSlab;
+use nockapp::noun::{AtomExt, NounExt};
+use nockvm::noun::{Noun, Slots, T};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zkvm_jetpack::form::math::base::PRIME;
+use zkvm_jetpack::form::math::merkle::hash_pair;
+use zkvm_jetpack::form::math::tip5::{hash_varlen, tip5_noun_digest, Sponge, Tog};
+
+use crate::prove_input::{
+    check_belts, noun_to_belts, ProveBlockInput, ProveInputError, DIGEST_BELTS,
+};
+
+/// Only proof version the miner kernel produces
+pub const PROOF_VERSION: u64 = 0;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MiningEffectError {
+    #[error("effect is missing {0}")]
+    Missing(&'static str),
+    #[error("expected tag %{0}")]
+    WrongTag(&'static str),
+    #[error("{0} is not an atom")]
+    NotAnAtom(&'static str),
+    #[error("unsupported proof version {0}")]
+    ProofVersion(u64),
+    #[error("proof is truncated at {0}")]
+    TruncatedProof(&'static str),
+    #[error("proof has no %puzzle object")]
+    MissingPuzzle,
+    #[error("malformed {field}: {source}")]
+    Digest {
+        field: &'static str,
+        source: ProveInputError,
+    },
+    #[error("{0} does not match the candidate")]
+    Mismatch(&'static str),
+    #[error("{0} has a word that is not a base field element")]
+    NotBelt(&'static str),
+    #[error("cannot hash proof object %{0}")]
+    UnknownObject(String),
+}
+
+/// The `%puzzle` object a proof commits to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub block_commitment: [u64; DIGEST_BELTS],
+    pub nonce: [u64; DIGEST_BELTS],
+    pub length: u64,
+}
+
+/// A decoded `%pow` command effect
+#[derive(Debug, Clone)]
+pub struct MiningEffect {
+    slab: NounSlab,
+    /// The proof hash (`dig`) the kernel reports, compared against targets
+    pub pow: UBig,
+    pub block_commitment: [u64; DIGEST_BELTS],
+    pub nonce: [u64; DIGEST_BELTS],
+    pub puzzle: Puzzle,
+}
+
+impl MiningEffect {
+    /// The effect noun, as it is poked back into the node
+    pub fn slab(&self) -> &NounSlab {
+        &self.slab
+    }
+
+    pub fn into_slab(self) -> NounSlab {
+        self.slab
+    }
+
+    /// The proof, read in place from the effect slab
+    pub fn proof(&self) -> ProofView<'_> {
+        // [%command %pow prf ...], so the proof sits at axis 14
+        let proof = unsafe { self.slab.root() }
+            .slot(14)
+            .expect("effect shape is checked when it is decoded");
+        ProofView::new(&self.slab, proof).expect("proof is checked when the effect is decoded")
+    }
+
+    /// The proof copied into a slab of its own, e.g. to hand to a verifier
+    pub fn proof_slab(&self) -> NounSlab {
+        let mut slab = NounSlab::new();
+        slab.copy_into(
+            unsafe { self.slab.root() }
+                .slot(14)
+                .expect("effect shape is checked when it is decoded"),
+        );
+        slab
+    }
+
+    /// Check that this effect proves `input` and agrees with its own proof
+    pub fn verify(&self, input: &ProveBlockInput) -> Result<(), MiningEffectError> {
+        if self.puzzle.block_commitment != self.block_commitment
+            || self.block_commitment != input.mined_commitment()
+        {
+            return Err(MiningEffectError::Mismatch("block commitment"));
+        }
+        if self.puzzle.nonce != self.nonce || self.nonce != *input.nonce() {
+            return Err(MiningEffectError::Mismatch("nonce"));
+        }
+        if self.puzzle.length != input.length() {
+            return Err(MiningEffectError::Mismatch("length"));
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<NounSlab> for MiningEffect {
+    type Error = MiningEffectError;
+
+    fn try_from(slab: NounSlab) -> Result<Self, Self::Error> {
+        let root = unsafe { *slab.root() };
+        let (tag, rest) = split(root, "command tag")?;
+        expect_tag(tag, "command")?;
+        let (tag, rest) = split(rest, "pow tag")?;
+        expect_tag(tag, "pow")?;
+        let (proof, rest) = split(rest, "proof")?;
+        let (dig, rest) = split(rest, "dig")?;
+        let pow = dig
+            .as_atom()
+            .map(|dig| UBig::from_le_bytes(&dig.to_le_bytes()))
+            .map_err(|_| MiningEffectError::NotAnAtom("dig"))?;
+        let (block_commitment, nonce) = split(rest, "nonce")?;
+        let block_commitment = digest(block_commitment, "block commitment")?;
+        let nonce = digest(nonce, "nonce")?;
+        let puzzle = decode_proof(&slab, proof)?;
+        Ok(MiningEffect {
+            slab,
+            pow,
+            block_commitment,
+            nonce,
+            puzzle,
+        })
+    }
+}
+
+/// The miner kernel's answer to a `%status` poke,
+/// `[%status proofs=@ud verifies=@ud cancelled=@ud]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MinerStatus {
+    /// Candidates the kernel has proven
+    pub proofs: u64,
+    /// Proofs the kernel has checked
+    pub verifies: u64,
+    /// Block commitments cancelled with a `%cancel` poke
+    pub cancelled: u64,
+}
+
+impl TryFrom<&NounSlab> for MinerStatus {
+    type Error = MiningEffectError;
+
+    fn try_from(slab: &NounSlab) -> Result<Self, Self::Error> {
+        let root = unsafe { *slab.root() };
+        let (tag, rest) = split(root, "status tag")?;
+        expect_tag(tag, "status")?;
+        let (proofs, rest) = split(rest, "proofs")?;
+        let (verifies, cancelled) = split(rest, "cancelled")?;
+        Ok(MinerStatus {
+            proofs: atom(proofs, "proofs")?,
+            verifies: atom(verifies, "verifies")?,
+            cancelled: atom(cancelled, "cancelled")?,
+        })
+    }
+}
+
+/// `[%cancelled block-commitment=digest]`, the miner kernel's answer to a
+/// `%cancel` poke and to every candidate it skipped because of one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancelledEffect {
+    pub block_commitment: [u64; DIGEST_BELTS],
+}
+
+impl TryFrom<&NounSlab> for CancelledEffect {
+    type Error = MiningEffectError;
+
+    fn try_from(slab: &NounSlab) -> Result<Self, Self::Error> {
+        let root = unsafe { *slab.root() };
+        let (tag, block_commitment) = split(root, "cancelled tag")?;
+        expect_tag(tag, "cancelled")?;
+        Ok(CancelledEffect {
+            block_commitment: digest(block_commitment, "block commitment")?,
+        })
+    }
+}
+
+/// The first of a poke's `effects` that decodes as `T`
+pub fn find_effect<T>(effects: &NounSlab) -> Option<T>
+where
+    T: for<'a> TryFrom<&'a NounSlab>,
+{
+    effects
+        .to_vec()
+        .iter()
+        .find_map(|effect| T::try_from(effect).ok())
+}
+
+/// Decode `[version objects hashes read-index]`, returning its `%puzzle`
+fn decode_proof(slab: &NounSlab, proof: Noun) -> Result<Puzzle, MiningEffectError> {
+    let view = ProofView::new(slab, proof)?;
+    for hash in view.hashes() {
+        hash?;
+    }
+    view.puzzle()
+}
+
+/// A proof read in place from the slab that holds it.
+///
+/// Nothing is copied out of the slab until asked for: objects and hashes are
+/// walked lazily, and indirect atoms are borrowed as bytes by [`atom_bytes`].
+#[derive(Debug, Clone, Copy)]
+pub struct ProofView<'slab> {
+    version: u64,
+    objects: Noun,
+    hashes: Noun,
+    read_index: Noun,
+    _slab: PhantomData<&'slab NounSlab>,
+}
+
+impl<'slab> ProofView<'slab> {
+    /// View `proof`, a noun in `slab`, checking only the shape of its spine
+    pub fn new(_slab: &'slab NounSlab, proof: Noun) -> Result<Self, MiningEffectError> {
+        let truncated = |_| MiningEffectError::TruncatedProof("version");
+        let cell = proof.as_cell().map_err(truncated)?;
+        let version = atom(cell.head(), "proof version")?;
+        if version != PROOF_VERSION {
+            return Err(MiningEffectError::ProofVersion(version));
+        }
+        let (objects, rest) = cell
+            .tail()
+            .as_cell()
+            .map(|cell| (cell.head(), cell.tail()))
+            .map_err(|_| MiningEffectError::TruncatedProof("objects"))?;
+        let (hashes, read_index) = rest
+            .as_cell()
+            .map(|cell| (cell.head(), cell.tail()))
+            .map_err(|_| MiningEffectError::TruncatedProof("hashes"))?;
+        if !read_index.is_atom() {
+            return Err(MiningEffectError::TruncatedProof("read index"));
+        }
+        check_list(hashes, "hashes")?;
+        check_list(objects, "objects")?;
+        if list_items(objects).any(|object| !object.is_cell()) {
+            return Err(MiningEffectError::TruncatedProof("objects"));
+        }
+        Ok(ProofView {
+            version,
+            objects,
+            hashes,
+            read_index,
+            _slab: PhantomData,
+        })
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// The read index, `None` if it does not fit in a u64
+    pub fn read_index(&self) -> Option<u64> {
+        atom(self.read_index, "read index").ok()
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = ProofObjectView<'slab>> {
+        list_items(self.objects).map(|object| {
+            let cell = object
+                .as_cell()
+                .expect("proof objects are checked in ProofView::new");
+            ProofObjectView {
+                tag: cell.head(),
+                body: cell.tail(),
+                _slab: PhantomData,
+            }
+        })
+    }
+
+    /// The proof's hashes, decoded one at a time
+    pub fn hashes(
+        &self,
+    ) -> impl Iterator<Item = Result<[u64; DIGEST_BELTS], MiningEffectError>> + 'slab {
+        list_items(self.hashes).map(|hash| digest(hash, "proof hash"))
+    }
+
+    /// Copy every object out of the slab, parsing them in parallel. The
+    /// result is in the same order as the proof's object list.
+    pub fn parse_objects(&self) -> Result<Vec<ProofObject>, MiningEffectError> {
+        let objects: Vec<Noun> = list_items(self.objects).collect();
+        objects
+            .par_iter()
+            .map(|&object| ProofObject::parse(object))
+            .collect()
+    }
+
+    /// The first `%puzzle` object
+    pub fn puzzle(&self) -> Result<Puzzle, MiningEffectError> {
+        self.objects()
+            .find(|object| object.is("puzzle"))
+            .ok_or(MiningEffectError::MissingPuzzle)
+            .and_then(|object| decode_puzzle(object.body()))
+    }
+
+    /// `+hash-proof`, the digest of the proof a page's digest commits to:
+    /// every object's [`ProofObjectView::hash`] absorbed into a sponge one
+    /// at a time, then five belts squeezed out
+    pub fn hash(&self) -> Result<[u64; DIGEST_BELTS], MiningEffectError> {
+        let objects: Vec<Noun> = list_items(self.objects).collect();
+        let digests = objects
+            .par_iter()
+            .map(|&object| {
+                let cell = object
+                    .as_cell()
+                    .expect("proof objects are checked in ProofView::new");
+                hash_object(cell.head(), cell.tail())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut sponge = Sponge::new();
+        for digest in &digests {
+            sponge.absorb(digest);
+        }
+        let belts = Tog::new(sponge).belts(DIGEST_BELTS);
+        Ok(belts.try_into().expect("squeezed five belts"))
+    }
+}
+
+/// One `[tag body]` entry of a proof's object list, still in its slab
+#[derive(Debug, Clone, Copy)]
+pub struct ProofObjectView<'slab> {
+    tag: Noun,
+    body: Noun,
+    _slab: PhantomData<&'slab NounSlab>,
+}
+
+impl<'slab> ProofObjectView<'slab> {
+    /// Whether this is a `%tag` object
+    pub fn is(&self, tag: &str) -> bool {
+        self.tag.eq_bytes(tag)
+    }
+
+    /// The object's tag, e.g. `m-root` or `codeword`
+    pub fn tag(&self) -> Option<String> {
+        self.tag.as_atom().ok()?.into_string().ok()
+    }
+
+    pub fn body(&self) -> Noun {
+        self.body
+    }
+
+    /// `+hash-proof-data`. Tags the kernel does not hash are an error.
+    pub fn hash(&self) -> Result<[u64; DIGEST_BELTS], MiningEffectError> {
+        hash_object(self.tag, self.body)
+    }
+
+    /// Copy the whole `[tag body]` object into its own slab
+    pub fn to_slab(&self) -> NounSlab {
+        let mut slab = NounSlab::new();
+        let object = T(&mut slab, &[self.tag, self.body]);
+        let mut owned = NounSlab::new();
+        owned.copy_into(object);
+        owned
+    }
+}
+
+/// A proof object copied out of its slab
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProofObject {
+    MRoot([u64; DIGEST_BELTS]),
+    Puzzle(Puzzle),
+    /// Felts of an fpoly
+    Codeword(#[serde(with = "crate::codec::fpoly")] Vec<[u64; 3]>),
+    /// Belts of a bpoly
+    Terms(#[serde(with = "crate::codec::poly")] Vec<u64>),
+    CompM {
+        root: [u64; DIGEST_BELTS],
+        num: u64,
+    },
+    Evals(#[serde(with = "crate::codec::fpoly")] Vec<[u64; 3]>),
+    Heights(Vec<u64>),
+    Poly(#[serde(with = "crate::codec::poly")] Vec<u64>),
+    /// Merkle paths and anything newer, kept as the jam of the whole object
+    Other {
+        tag: Option<String>,
+        #[serde(with = "serde_bytes")]
+        jam: Vec<u8>,
+    },
+}
+
+impl ProofObject {
+    fn parse(object: Noun) -> Result<Self, MiningEffectError> {
+        let cell = object
+            .as_cell()
+            .map_err(|_| MiningEffectError::TruncatedProof("objects"))?;
+        let (tag, body) = (cell.head(), cell.tail());
+        let parsed = if tag.eq_bytes("m-root") {
+            ProofObject::MRoot(digest(body, "m-root")?)
+        } else if tag.eq_bytes("puzzle") {
+            ProofObject::Puzzle(decode_puzzle(body)?)
+        } else if tag.eq_bytes("codeword") {
+            ProofObject::Codeword(felts(poly_words(body, 3, "codeword")?))
+        } else if tag.eq_bytes("terms") {
+            ProofObject::Terms(poly_words(body, 1, "terms")?)
+        } else if tag.eq_bytes("comp-m") {
+            let (root, num) = split(body, "comp-m")?;
+            ProofObject::CompM {
+                root: digest(root, "comp-m")?,
+                num: atom(num, "comp-m")?,
+            }
+        } else if tag.eq_bytes("evals") {
+            ProofObject::Evals(felts(poly_words(body, 3, "evals")?))
+        } else if tag.eq_bytes("heights") {
+            check_list(body, "heights")?;
+            ProofObject::Heights(
+                list_items(body)
+                    .map(|height| atom(height, "heights"))
+                    .collect::<Result<_, _>>()?,
+            )
+        } else if tag.eq_bytes("poly") {
+            ProofObject::Poly(poly_words(body, 1, "poly")?)
+        } else {
+            let mut slab = NounSlab::new();
+            slab.copy_into(object);
+            ProofObject::Other {
+                tag: tag.as_atom().ok().and_then(|tag| tag.into_string().ok()),
+                jam: slab.jam().to_vec(),
+            }
+        };
+        Ok(parsed)
+    }
+}
+
+/// Words of a `[len dat]` poly with `width` words per element
+fn poly_words(
+    poly: Noun,
+    width: usize,
+    field: &'static str,
+) -> Result<Vec<u64>, MiningEffectError> {
+    let (len, dat) = split(poly, field)?;
+    let len = atom(len, field)? as usize;
+    let dat = dat
+        .as_atom()
+        .map_err(|_| MiningEffectError::NotAnAtom(field))?;
+    let mut words = match dat.as_indirect() {
+        Ok(indirect) => indirect.as_slice().to_vec(),
+        Err(_) => vec![dat
+            .as_u64()
+            .map_err(|_| MiningEffectError::NotAnAtom(field))?],
+    };
+    // dat carries a high marker bit past the last element, so it has at
+    // least len * width words
+    let wanted = len
+        .checked_mul(width)
+        .filter(|&wanted| wanted <= words.len())
+        .ok_or(MiningEffectError::TruncatedProof(field))?;
+    words.truncate(wanted);
+    Ok(words)
+}
+
+/// `+hash-proof-data` of a `[tag body]` proof object
+fn hash_object(tag: Noun, body: Noun) -> Result<[u64; DIGEST_BELTS], MiningEffectError> {
+    let hashed = if tag.eq_bytes("m-root") {
+        digest(body, "m-root")?
+    } else if tag.eq_bytes("puzzle") {
+        let (commitment, rest) = split(body, "puzzle")?;
+        let (nonce, rest) = split(rest, "puzzle")?;
+        let (len, p) = split(rest, "puzzle")?;
+        hash_pair(
+            &digest(commitment, "puzzle commitment")?,
+            &hash_pair(
+                &digest(nonce, "puzzle nonce")?,
+                &hash_pair(&leaf(len, "puzzle")?, &leaf(p, "puzzle")?),
+            ),
+        )
+    } else if tag.eq_bytes("comp-m") {
+        let (root, num) = split(body, "comp-m")?;
+        hash_pair(&digest(root, "comp-m")?, &leaf(num, "comp-m")?)
+    } else if tag.eq_bytes("heights") {
+        leaf(body, "heights")?
+    } else if tag.eq_bytes("codeword") || tag.eq_bytes("evals") {
+        mary(body, 3, "fpoly")?
+    } else if tag.eq_bytes("terms") || tag.eq_bytes("poly") {
+        mary(body, 1, "bpoly")?
+    } else if tag.eq_bytes("m-pathbf") {
+        proof_path(body, 1)?
+    } else if tag.eq_bytes("m-path") {
+        proof_path(body, 3)?
+    } else if tag.eq_bytes("m-paths") {
+        let (a, rest) = split(body, "m-paths")?;
+        let (b, c) = split(rest, "m-paths")?;
+        hash_pair(
+            &proof_path(a, 3)?,
+            &hash_pair(&proof_path(b, 3)?, &proof_path(c, 3)?),
+        )
+    } else {
+        return Err(MiningEffectError::UnknownObject(
+            tag.as_atom()
+                .ok()
+                .and_then(|tag| tag.into_string().ok())
+                .unwrap_or_default(),
+        ));
+    };
+    // the kernel tags merkle paths %m-mpath and %m-mpaths in the hash
+    let tag = if tag.eq_bytes("m-path") {
+        tag_leaf("m-mpath")
+    } else if tag.eq_bytes("m-paths") {
+        tag_leaf("m-mpaths")
+    } else {
+        leaf(tag, "object tag")?
+    };
+    Ok(hash_pair(&tag, &hashed))
+}
+
+/// `+hash-noun-varlen` of a noun whose leaves are all belts, a `leaf+`
+/// hashable
+fn leaf(noun: Noun, field: &'static str) -> Result<[u64; DIGEST_BELTS], MiningEffectError> {
+    tip5_noun_digest(noun).map_err(|_| MiningEffectError::NotBelt(field))
+}
+
+/// `leaf+%tag`, for a tag of at most eight bytes that is a belt
+fn tag_leaf(tag: &str) -> [u64; DIGEST_BELTS] {
+    let mut bytes = [0; 8];
+    bytes[..tag.len()].copy_from_slice(tag.as_bytes());
+    hash_varlen(&[1, u64::from_le_bytes(bytes)])
+}
+
+/// A `mary+[step poly]` hashable: the step, the length and the hash of the
+/// poly's belts
+fn mary(
+    poly: Noun,
+    step: usize,
+    field: &'static str,
+) -> Result<[u64; DIGEST_BELTS], MiningEffectError> {
+    let words = poly_words(poly, step, field)?;
+    if words.iter().any(|&word| word >= PRIME) {
+        return Err(MiningEffectError::NotBelt(field));
+    }
+    let len = (words.len() / step) as u64;
+    Ok(hash_pair(
+        &hash_varlen(&[1, step as u64]),
+        &hash_pair(&hash_varlen(&[1, len]), &hash_varlen(&words)),
+    ))
+}
+
+/// A `[leaf path]` merkle path whose leaf is a poly of `step` words per
+/// element and whose path is a list of digests
+fn proof_path(path: Noun, step: usize) -> Result<[u64; DIGEST_BELTS], MiningEffectError> {
+    let (leaf_poly, digests) = split(path, "merkle path")?;
+    check_list(digests, "merkle path")?;
+    for node in list_items(digests) {
+        digest(node, "merkle path")?;
+    }
+    Ok(hash_pair(
+        &mary(leaf_poly, step, "merkle path")?,
+        &leaf(digests, "merkle path")?,
+    ))
+}
+
+fn felts(words: Vec<u64>) -> Vec<[u64; 3]> {
+    words
+        .chunks_exact(3)
+        .map(|felt| [felt[0], felt[1], felt[2]])
+        .collect()
+}
+
+/// Bytes of an atom in `slab`, borrowed in place unless the atom is direct
+pub fn atom_bytes<'slab>(_slab: &'slab NounSlab, noun: Noun) -> Option<Cow<'slab, [u8]>> {
+    let atom = noun.as_atom().ok()?;
+    match atom.as_indirect() {
+        Ok(indirect) => {
+            // indirect atoms live in the slab, which is borrowed for 'slab
+            let bytes = unsafe {
+                std::slice::from_raw_parts(
+                    indirect.data_pointer() as *const u8,
+                    indirect.size() << 3,
+                )
+            };
+            Some(Cow::Borrowed(bytes))
+        }
+        Err(_) => Some(Cow::Owned(atom.as_ne_bytes().to_vec())),
+    }
+}
+
+/// Decode `[commitment nonce len p]`
+fn decode_puzzle(puzzle: Noun) -> Result<Puzzle, MiningEffectError> {
+    let truncated = |_| MiningEffectError::TruncatedProof("puzzle");
+    let cell = puzzle.as_cell().map_err(truncated)?;
+    let rest = cell.tail().as_cell().map_err(truncated)?;
+    let rest_tail = rest.tail().as_cell().map_err(truncated)?;
+    Ok(Puzzle {
+        block_commitment: digest(cell.head(), "puzzle commitment")?,
+        nonce: digest(rest.head(), "puzzle nonce")?,
+        length: atom(rest_tail.head(), "puzzle length")?,
+    })
+}
+
+pub(crate) fn split(noun: Noun, missing: &'static str) -> Result<(Noun, Noun), MiningEffectError> {
+    noun.as_cell()
+        .map(|cell| (cell.head(), cell.tail()))
+        .map_err(|_| MiningEffectError::Missing(missing))
+}
+
+fn expect_tag(noun: Noun, tag: &'static str) -> Result<(), MiningEffectError> {
+    if noun.eq_bytes(tag) {
+        Ok(())
+    } else {
+        Err(MiningEffectError::WrongTag(tag))
+    }
+}
+
+pub(crate) fn atom(noun: Noun, field: &'static str) -> Result<u64, MiningEffectError> {
+    noun.as_atom()
+        .and_then(|atom| atom.as_u64())
+        .map_err(|_| MiningEffectError::NotAnAtom(field))
+}
+
+pub(crate) fn digest(
+    noun: Noun,
+    field: &'static str,
+) -> Result<[u64; DIGEST_BELTS], MiningEffectError> {
+    noun_to_belts(noun)
+        .and_then(|belts| check_belts(field, belts))
+        .map_err(|source| MiningEffectError::Digest { field, source })
+}
+
+/// Items of a list, stopping at the first non-cell tail
+fn list_items(noun: Noun) -> impl Iterator<Item = Noun> {
+    let mut rest = noun;
+    std::iter::from_fn(move || {
+        let cell = rest.as_cell().ok()?;
+        rest = cell.tail();
+        Some(cell.head())
+    })
+}
+
+/// Check that `noun` is a `~`-terminated list
+fn check_list(noun: Noun, field: &'static str) -> Result<(), MiningEffectError> {
+    let mut rest = noun;
+    while let Ok(cell) = rest.as_cell() {
+        rest = cell.tail();
+    }
+    if atom(rest, field) == Ok(0) {
+        Ok(())
+    } else {
+        Err(MiningEffectError::TruncatedProof(field))
+    }
+}
+
+/// Declarative shape of an effect noun
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpectedSchema {
+    /// Any noun at all
+    Any,
+    /// Any atom
+    Atom,
+    /// An atom below the base field prime
+    Belt,
+    /// Exactly this atom
+    Exact(u64),
+    /// A `%tag` atom
+    Tag(&'static str),
+    /// Named fields of a tuple, the last field taking the rest of the noun
+    Tuple(Vec<(&'static str, ExpectedSchema)>),
+    /// A `~`-terminated list of items
+    List(Box<ExpectedSchema>),
+}
+
+impl ExpectedSchema {
+    /// Five belts, a tip5 noun digest
+    pub fn digest() -> Self {
+        ExpectedSchema::Tuple(
+            ["0", "1", "2", "3", "4"]
+                .into_iter()
+                .map(|name| (name, ExpectedSchema::Belt))
+                .collect(),
+        )
+    }
+
+    /// `[%command %pow prf dig block-commitment nonce]` from the miner kernel
+    pub fn pow_effect() -> Self {
+        let proof = ExpectedSchema::Tuple(vec![
+            ("version", ExpectedSchema::Exact(PROOF_VERSION)),
+            (
+                "objects",
+                ExpectedSchema::List(Box::new(ExpectedSchema::Any)),
+            ),
+            (
+                "hashes",
+                ExpectedSchema::List(Box::new(ExpectedSchema::digest())),
+            ),
+            ("read-index", ExpectedSchema::Atom),
+        ]);
+        ExpectedSchema::Tuple(vec![
+            ("tag", ExpectedSchema::Tag("command")),
+            ("command", ExpectedSchema::Tag("pow")),
+            ("prf", proof),
+            ("dig", ExpectedSchema::Atom),
+            ("block-commitment", ExpectedSchema::digest()),
+            ("nonce", ExpectedSchema::digest()),
+        ])
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            ExpectedSchema::Any => "any noun".to_string(),
+            ExpectedSchema::Atom => "an atom".to_string(),
+            ExpectedSchema::Belt => "a belt".to_string(),
+            ExpectedSchema::Exact(value) => format!("atom {}", value),
+            ExpectedSchema::Tag(tag) => format!("%{}", tag),
+            ExpectedSchema::Tuple(fields) => format!("a {}-tuple", fields.len()),
+            ExpectedSchema::List(_) => "a list".to_string(),
+        }
+    }
+}
+
+/// Where and how a noun first departs from its schema
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("at {path}: expected {expected}, found {found}")]
+pub struct SchemaMismatch {
+    /// Dotted field names from the root, with `[i]` for list items
+    pub path: String,
+    pub expected: String,
+    pub found: String,
+}
+
+/// Check `noun` against `schema`, reporting the first mismatch
+pub fn validate_effect_schema(noun: Noun, schema: &ExpectedSchema) -> Result<(), SchemaMismatch> {
+    let mut path = Vec::new();
+    check_schema(noun, schema, &mut path)
+}
+
+fn check_schema(
+    noun: Noun,
+    schema: &ExpectedSchema,
+    path: &mut Vec<String>,
+) -> Result<(), SchemaMismatch> {
+    let mismatch = |path: &[String], expected: String| SchemaMismatch {
+        path: if path.is_empty() {
+            "effect".to_string()
+        } else {
+            path.join(".")
+        },
+        expected,
+        found: describe_noun(noun),
+    };
+    match schema {
+        ExpectedSchema::Any => Ok(()),
+        ExpectedSchema::Atom if noun.is_atom() => Ok(()),
+        ExpectedSchema::Belt if atom_value(noun).is_some_and(|value| value < PRIME) => Ok(()),
+        ExpectedSchema::Exact(expected) if atom_value(noun) == Some(*expected) => Ok(()),
+        ExpectedSchema::Tag(tag) if noun.eq_bytes(tag) => Ok(()),
+        ExpectedSchema::Tuple(fields) => {
+            let mut rest = noun;
+            for (i, (name, field)) in fields.iter().enumerate() {
+                path.push(name.to_string());
+                let item = if i + 1 == fields.len() {
+                    rest
+                } else {
+                    let Ok(cell) = rest.as_cell() else {
+                        return Err(SchemaMismatch {
+                            found: describe_noun(rest),
+                            ..mismatch(&path[..], "a cell".to_string())
+                        });
+                    };
+                    rest = cell.tail();
+                    cell.head()
+                };
+                check_schema(item, field, path)?;
+                path.pop();
+            }
+            Ok(())
+        }
+        ExpectedSchema::List(item) => {
+            let mut rest = noun;
+            let mut index = 0;
+            while let Ok(cell) = rest.as_cell() {
+                path.push(format!("[{}]", index));
+                check_schema(cell.head(), item, path)?;
+                path.pop();
+                rest = cell.tail();
+                index += 1;
+            }
+            if atom_value(rest) == Some(0) {
+                Ok(())
+            } else {
+                path.push(format!("[{}]", index));
+                Err(SchemaMismatch {
+                    found: describe_noun(rest),
+                    ..mismatch(&path[..], "~".to_string())
+                })
+            }
+        }
+        _ => Err(mismatch(&path[..], schema.describe())),
+    }
+}
+
+fn atom_value(noun: Noun) -> Option<u64> {
+    noun.as_atom().ok().and_then(|atom| atom.as_u64().ok())
+}
+
+fn describe_noun(noun: Noun) -> String {
+    if noun.is_cell() {
+        return "a cell".to_string();
+    }
+    match atom_value(noun) {
+        Some(value) => format!("atom {}", value),
+        None => "a large atom".to_string(),
+    }
+}
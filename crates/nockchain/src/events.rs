@@ -0,0 +1,208 @@
+//! In-process bus of node events.
+//!
+//! Mining, verification, networking and fork choice each publish what
+//! happens to them on an [`EventBus`] as a [`NodeEvent`], and anything
+//! interested subscribes without the publisher knowing about it: metrics
+//! with [`record_metrics`], HTTP clients through the transaction API's
+//! `/events` stream.
+//!
+//! The bus is a [`broadcast`] channel. Publishing never blocks and is a no-op
+//! with nobody subscribed; a subscriber that falls more than
+//! [`EVENT_CAPACITY`] events behind misses the oldest ones and is told how
+//! many with [`broadcast::error::RecvError::Lagged`].
+
+use std::fmt::Display;
+use std::time::Duration;
+
+use libp2p::{Multiaddr, PeerId};
+use serde::{Serialize, Serializer};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+use crate::metrics::metrics;
+use crate::reorg::ReorgEvent;
+
+/// Events a subscriber may fall behind by before missing some
+pub const EVENT_CAPACITY: usize = 1024;
+
+/// Something that happened in the node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum NodeEvent {
+    /// The miner started an attempt on a candidate, from the kernel or a
+    /// block template
+    NewCandidate { length: u64 },
+    /// The miner found a proof and is submitting it to the node
+    ProofFound {
+        length: u64,
+        /// Time spent in the prove poke
+        #[serde(rename = "elapsed_ms", serialize_with = "millis")]
+        elapsed: Duration,
+    },
+    /// A verification request ran to completion
+    ProofVerified { length: u64, valid: bool },
+    /// A connection to a peer was established
+    PeerConnected {
+        #[serde(serialize_with = "display")]
+        peer: PeerId,
+        #[serde(serialize_with = "display")]
+        address: Multiaddr,
+    },
+    /// The heaviest chain switched branches
+    Reorg(ReorgEvent),
+}
+
+impl NodeEvent {
+    /// Name of the event, as tagged when serialized
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeEvent::NewCandidate { .. } => "new-candidate",
+            NodeEvent::ProofFound { .. } => "proof-found",
+            NodeEvent::ProofVerified { .. } => "proof-verified",
+            NodeEvent::PeerConnected { .. } => "peer-connected",
+            NodeEvent::Reorg(_) => "reorg",
+        }
+    }
+}
+
+fn display<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+fn millis<S: Serializer>(elapsed: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX))
+}
+
+/// Publishes [`NodeEvent`]s to every subscriber. Clones publish to the same
+/// subscribers.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<NodeEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(EVENT_CAPACITY)
+    }
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Send `event` to every current subscriber, returning how many there are
+    pub fn publish(&self, event: NodeEvent) -> usize {
+        debug!(event = event.name(), "publishing node event");
+        // nobody listening is fine
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// Events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+/// Count the events on `events` in the node's metrics until the bus is
+/// dropped. Reorgs are counted by [`crate::reorg::ChainFollower`] itself, so
+/// followers without a bus are counted too.
+pub async fn record_metrics(mut events: broadcast::Receiver<NodeEvent>) {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("metrics missed {missed} node events");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        match event {
+            NodeEvent::NewCandidate { .. } => metrics().mining_candidates.increment(),
+            NodeEvent::ProofFound { .. } => metrics().mining_proofs_found.increment(),
+            NodeEvent::ProofVerified { valid: true, .. } => {
+                metrics().verifier_proofs_valid.increment()
+            }
+            NodeEvent::ProofVerified { valid: false, .. } => {
+                metrics().verifier_proofs_invalid.increment()
+            }
+            NodeEvent::PeerConnected { .. } => metrics().peers_connected.increment(),
+            NodeEvent::Reorg(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bus_fans_out_to_subscribers() {
+        let bus = EventBus::new(4);
+        assert_eq!(bus.publish(NodeEvent::NewCandidate { length: 1 }), 0);
+
+        let mut first = bus.subscribe();
+        let mut second = bus.clone().subscribe();
+        let event = NodeEvent::ProofVerified {
+            length: 64,
+            valid: true,
+        };
+        assert_eq!(bus.publish(event.clone()), 2);
+        assert_eq!(first.recv().await.unwrap(), event);
+        assert_eq!(second.recv().await.unwrap(), event);
+
+        // a subscriber that falls behind is told how far
+        for length in 0..6 {
+            bus.publish(NodeEvent::NewCandidate { length });
+        }
+        assert!(matches!(
+            first.recv().await,
+            Err(broadcast::error::RecvError::Lagged(2))
+        ));
+        assert_eq!(
+            first.recv().await.unwrap(),
+            NodeEvent::NewCandidate { length: 2 }
+        );
+    }
+
+    #[test]
+    fn test_events_serialize_tagged() {
+        let found = NodeEvent::ProofFound {
+            length: 64,
+            elapsed: Duration::from_millis(1500),
+        };
+        assert_eq!(
+            serde_json::to_value(&found).unwrap(),
+            serde_json::json!({"event": "proof-found", "length": 64, "elapsed_ms": 1500})
+        );
+        assert_eq!(found.name(), "proof-found");
+
+        let peer = libp2p::identity::Keypair::generate_ed25519()
+            .public()
+            .to_peer_id();
+        let connected = NodeEvent::PeerConnected {
+            peer,
+            address: "/ip4/127.0.0.1/udp/3006/quic-v1".parse().unwrap(),
+        };
+        let value = serde_json::to_value(&connected).unwrap();
+        assert_eq!(value["event"], connected.name());
+        assert_eq!(value["peer"], peer.to_string());
+        assert_eq!(value["address"], "/ip4/127.0.0.1/udp/3006/quic-v1");
+
+        let reorg = NodeEvent::Reorg(ReorgEvent {
+            depth: 2,
+            fork_height: 10,
+            old_tip: [1; 5],
+            new_tip: [2; 5],
+            replayed: 5,
+        });
+        let value = serde_json::to_value(&reorg).unwrap();
+        assert_eq!(value["event"], "reorg");
+        assert_eq!(value["depth"], 2);
+    }
+}
@@ -0,0 +1,186 @@
+//! Fast sync from a kernel state snapshot.
+//!
+//! Instead of replaying every block since genesis, a new node can download
+//! a [`Snapshot`]: the kernel state as of some block, zstd-compressed, the
+//! page of that block and a [`SnapshotManifest`] naming the block and the
+//! blake3 digest of the archive. [`install`] checks the archive against the
+//! manifest, the page against the block the manifest names, and that block
+//! against the headers a [`LightClient`] has verified. The block must sit
+//! on the best header chain with enough blocks mined on top of it. The state
+//! is then restored and a [`ChainFollower`] picks up from that block, so the
+//! blocks that follow are fetched and applied as usual.
+//!
+//! Page headers commit to blocks, not to kernel state, so the proof of work
+//! only vouches for the block a snapshot claims to be taken at. The state
+//! itself is trusted to whoever published the manifest, and nodes that need
+//! more should replay from genesis.
+
+use std::io;
+use std::path::Path;
+
+use nockapp::noun::slab::NounSlab;
+use nockapp::CrownError;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+
+use crate::block_store::{BlockStore, BlockStoreError};
+use crate::codec::{compress, decompress, DEFAULT_ZSTD_LEVEL};
+use crate::light_client::{block_id_from_noun, BlockId, LightClient, ProofVerifier};
+use crate::reorg::{ChainFollower, ChainState, ReorgConfig, ReorgError};
+
+/// Blocks that must be mined on top of a snapshot's block by default
+pub const DEFAULT_MIN_CONFIRMATIONS: u64 = 10;
+
+#[derive(Debug, Error)]
+pub enum FastSyncError {
+    #[error("snapshot block {block:?} at height {height} is not on the best header chain")]
+    NotOnBestChain { height: u64, block: BlockId },
+    #[error("snapshot block has {confirmations} confirmations, {needed} are needed")]
+    TooRecent { confirmations: u64, needed: u64 },
+    #[error("snapshot archive is {actual} bytes, the manifest says {expected}")]
+    SizeMismatch { expected: u64, actual: u64 },
+    #[error("snapshot archive does not match the manifest digest")]
+    DigestMismatch,
+    #[error("snapshot page is not the block the manifest names")]
+    WrongPage,
+    #[error("block store already holds blocks up to height {0}")]
+    StoreNotEmpty(u64),
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("kernel error: {0}")]
+    Kernel(#[from] CrownError),
+    #[error("block store error: {0}")]
+    Store(#[from] BlockStoreError),
+    #[error(transparent)]
+    Reorg(#[from] ReorgError),
+}
+
+/// Names the block a snapshot was taken at and its archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub height: u64,
+    pub block: BlockId,
+    /// blake3 hash of the archive
+    pub digest: [u8; 32],
+    /// Size of the archive in bytes
+    pub size: u64,
+}
+
+/// Kernel state as of a block, as it is served to syncing nodes
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub manifest: SnapshotManifest,
+    /// The compressed state
+    pub archive: Vec<u8>,
+    /// The jammed page of the block the snapshot was taken at
+    pub page: Vec<u8>,
+}
+
+impl Snapshot {
+    /// Snapshot `state`, which has just applied `page`, block `block` at
+    /// `height`
+    pub async fn create<S: ChainState>(
+        state: &S,
+        height: u64,
+        block: BlockId,
+        page: &NounSlab,
+    ) -> Result<Self, FastSyncError> {
+        let archive = compress(&state.snapshot().await?, DEFAULT_ZSTD_LEVEL)?;
+        Ok(Snapshot {
+            manifest: SnapshotManifest {
+                height,
+                block,
+                digest: *blake3::hash(&archive).as_bytes(),
+                size: archive.len() as u64,
+            },
+            archive,
+            page: page.jam().to_vec(),
+        })
+    }
+}
+
+/// Check that `manifest` names a block on the best chain of `client` with
+/// at least `min_confirmations` blocks on top
+pub fn check_manifest<V: ProofVerifier>(
+    manifest: &SnapshotManifest,
+    client: &LightClient<V>,
+    min_confirmations: u64,
+) -> Result<(), FastSyncError> {
+    let not_on_chain = || FastSyncError::NotOnBestChain {
+        height: manifest.height,
+        block: manifest.block,
+    };
+    let best = client.best_header().ok_or_else(not_on_chain)?;
+    client
+        .best_chain()
+        .find(|h| h.digest == manifest.block && h.height == manifest.height)
+        .ok_or_else(not_on_chain)?;
+    let confirmations = best.height - manifest.height;
+    if confirmations < min_confirmations {
+        return Err(FastSyncError::TooRecent {
+            confirmations,
+            needed: min_confirmations,
+        });
+    }
+    Ok(())
+}
+
+/// Check a downloaded snapshot against its manifest
+pub fn check_snapshot(snapshot: &Snapshot) -> Result<(), FastSyncError> {
+    let manifest = &snapshot.manifest;
+    let size = snapshot.archive.len() as u64;
+    if size != manifest.size {
+        return Err(FastSyncError::SizeMismatch {
+            expected: manifest.size,
+            actual: size,
+        });
+    }
+    if *blake3::hash(&snapshot.archive).as_bytes() != manifest.digest {
+        return Err(FastSyncError::DigestMismatch);
+    }
+    let mut page = NounSlab::new();
+    let root = page
+        .cue_into(snapshot.page.clone().into())
+        .map_err(|_| FastSyncError::WrongPage)?;
+    let digest = root
+        .as_cell()
+        .ok()
+        .and_then(|page| block_id_from_noun(page.head()).ok());
+    if digest != Some(manifest.block) {
+        return Err(FastSyncError::WrongPage);
+    }
+    Ok(())
+}
+
+/// Check `snapshot` and restore it into `state`, returning a follower that
+/// continues from the snapshot's block. `store` must be empty, blocks below
+/// the snapshot are never downloaded.
+pub async fn install<S: ChainState, V: ProofVerifier>(
+    state: S,
+    snapshot: Snapshot,
+    client: &LightClient<V>,
+    min_confirmations: u64,
+    mut store: BlockStore,
+    snapshot_dir: impl AsRef<Path>,
+    config: ReorgConfig,
+) -> Result<ChainFollower<S>, FastSyncError> {
+    if let Some(tip) = store.tip_height() {
+        return Err(FastSyncError::StoreNotEmpty(tip));
+    }
+    check_manifest(&snapshot.manifest, client, min_confirmations)?;
+    check_snapshot(&snapshot)?;
+
+    let Snapshot {
+        manifest,
+        archive,
+        page,
+    } = snapshot;
+    state.restore(decompress(archive)?).await?;
+    store.prune_below(manifest.height)?;
+    store.put(manifest.height, manifest.block, &page)?;
+    info!("installed state snapshot at height {}", manifest.height);
+    ChainFollower::open(state, store, snapshot_dir, config)
+        .await
+        .map_err(Into::into)
+}
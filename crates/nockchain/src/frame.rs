@@ -0,0 +1,105 @@
+//! Length-prefixed framing for byte streams.
+//!
+//! Each frame is preceded by its length as 8 little-endian bytes, the
+//! framing the npc socket uses. The daemon socket, `nockchain-verify`'s
+//! proof and header streams all read frames through [`read_frame`], which
+//! never trusts a length further than the caller's limit: a frame over the
+//! limit is skipped rather than buffered, and a frame under it is read as it
+//! arrives rather than allocated up front, so a short stream claiming a huge
+//! frame costs no more memory than the bytes it sent.
+
+use std::io;
+
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest frame read unless configured otherwise, well above any jammed
+/// candidate or proof
+pub const DEFAULT_MAX_FRAME_BYTES: usize = 1 << 30;
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[error("frame is {bytes} bytes, the limit is {limit}")]
+pub struct FrameTooLarge {
+    pub bytes: usize,
+    pub limit: usize,
+}
+
+/// Read one frame, `None` if the stream ends before it starts. A frame over
+/// `limit` is skipped, so the frames after it still line up.
+pub async fn read_frame<R: AsyncRead + Unpin>(
+    input: &mut R,
+    limit: usize,
+) -> io::Result<Option<Result<Vec<u8>, FrameTooLarge>>> {
+    let mut size = [0u8; 8];
+    match input.read_exact(&mut size).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let size = u64::from_le_bytes(size);
+    let bytes = usize::try_from(size).unwrap_or(usize::MAX);
+    let mut body = (&mut *input).take(size);
+    if bytes > limit {
+        let skipped = tokio::io::copy(&mut body, &mut tokio::io::sink()).await?;
+        if skipped < size {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        return Ok(Some(Err(FrameTooLarge { bytes, limit })));
+    }
+    let mut frame = Vec::new();
+    body.read_to_end(&mut frame).await?;
+    if frame.len() < bytes {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(Some(Ok(frame)))
+}
+
+/// Write `frame` with its length in front and flush it
+pub async fn write_frame<W: AsyncWrite + Unpin>(output: &mut W, frame: &[u8]) -> io::Result<()> {
+    output
+        .write_all(&(frame.len() as u64).to_le_bytes())
+        .await?;
+    output.write_all(frame).await?;
+    output.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn framed(frame: &[u8]) -> Vec<u8> {
+        let mut bytes = (frame.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(frame);
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frames_are_skipped() {
+        let mut input = framed(&[1; 10]);
+        input.extend(framed(&[2; 3]));
+        let mut input = input.as_slice();
+        assert_eq!(
+            read_frame(&mut input, 4).await.unwrap(),
+            Some(Err(FrameTooLarge {
+                bytes: 10,
+                limit: 4
+            }))
+        );
+        assert_eq!(
+            read_frame(&mut input, 4).await.unwrap(),
+            Some(Ok(vec![2; 3]))
+        );
+        assert_eq!(read_frame(&mut input, 4).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_claimed_length_is_not_allocated_up_front() {
+        // claims the limit but ends after two bytes
+        let mut input = (DEFAULT_MAX_FRAME_BYTES as u64).to_le_bytes().to_vec();
+        input.extend([1, 2]);
+        let err = read_frame(&mut input.as_slice(), DEFAULT_MAX_FRAME_BYTES)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,325 @@
+//! Headers-first sync.
+//!
+//! A node that is far behind first downloads the chain's headers together
+//! with their proofs of work and checks them with a [`LightClient`], which is
+//! cheap next to running the state transition. Only once it knows the
+//! heaviest chain does it fetch full blocks, and it only takes blocks whose
+//! digest is a header on that chain, so a peer cannot waste its time with
+//! blocks from a lighter fork.
+//!
+//! [`HeaderSync`] is the state machine and leaves networking to its driver:
+//! the driver asks [`HeaderSync::next_request`] what to fetch, hands back
+//! headers with [`HeaderSync::on_headers`] and checks each downloaded page
+//! with [`HeaderSync::check_block`] before applying it. Every accepted header
+//! is appended to a log on disk, so after a restart the headers phase picks
+//! up from the last header held. How far blocks got is whatever the block
+//! store holds, which the driver passes in.
+//!
+//! [`follow_stream`] runs a bare light client over a stream of headers
+//! instead, for `nockchain-verify --headers`.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use bincode::config;
+use nockapp::noun::slab::NounSlab;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tracing::{debug, info, warn};
+
+use crate::frame::read_frame;
+use crate::light_client::{
+    block_id_from_noun, BlockHeader, BlockId, HeaderProof, LightClient, LightClientError,
+    ProofVerifier,
+};
+
+#[derive(Debug, Error)]
+pub enum HeaderSyncError {
+    #[error(transparent)]
+    Header(#[from] LightClientError),
+    #[error("block {0:?} is not on the best header chain")]
+    UnexpectedBlock(BlockId),
+    #[error("page noun has no digest")]
+    MalformedPage,
+    #[error("failed to encode header: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPhase {
+    /// Downloading and checking headers
+    Headers,
+    /// Downloading the blocks of the best header chain
+    Blocks,
+}
+
+/// What the driver should fetch next
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncRequest {
+    /// Up to `max` headers, with proofs, following `after` on the peer's
+    /// best chain, or from genesis
+    Headers { after: Option<BlockId>, max: usize },
+    /// These blocks of the best chain, in height order
+    Blocks(Vec<BlockId>),
+    /// Every block of the best header chain has been applied
+    Synced,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderSyncConfig {
+    /// Headers asked for at once. A peer sending fewer has no more.
+    pub header_batch: usize,
+    /// Blocks asked for at once
+    pub block_batch: usize,
+}
+
+impl Default for HeaderSyncConfig {
+    fn default() -> Self {
+        Self {
+            header_batch: 500,
+            block_batch: 16,
+        }
+    }
+}
+
+pub struct HeaderSync<V: ProofVerifier> {
+    client: LightClient<V>,
+    config: HeaderSyncConfig,
+    phase: SyncPhase,
+    log: File,
+}
+
+impl<V: ProofVerifier> HeaderSync<V> {
+    /// Sync headers into `client`, logging them to `path`. Headers logged
+    /// by an earlier run are put back into `client` without being checked
+    /// again.
+    pub fn open(
+        mut client: LightClient<V>,
+        path: impl AsRef<Path>,
+        config: HeaderSyncConfig,
+    ) -> Result<Self, HeaderSyncError> {
+        let path = path.as_ref();
+        let restored = restore_headers(&mut client, path)?;
+        if restored > 0 {
+            info!(
+                "restored {restored} headers, best at height {:?}",
+                client.best_header().map(|h| h.height)
+            );
+        }
+        let log = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            client,
+            config,
+            phase: SyncPhase::Headers,
+            log,
+        })
+    }
+
+    pub fn phase(&self) -> SyncPhase {
+        self.phase
+    }
+
+    pub fn client(&self) -> &LightClient<V> {
+        &self.client
+    }
+
+    /// What to fetch next, given the height of the last block applied
+    pub fn next_request(&self, applied: Option<u64>) -> SyncRequest {
+        if self.phase == SyncPhase::Headers {
+            return SyncRequest::Headers {
+                after: self.client.best_header().map(|h| h.digest),
+                max: self.config.header_batch,
+            };
+        }
+        let mut missing: Vec<BlockId> = self
+            .client
+            .best_chain()
+            .take_while(|h| applied.is_none_or(|applied| h.height > applied))
+            .map(|h| h.digest)
+            .collect();
+        if missing.is_empty() {
+            return SyncRequest::Synced;
+        }
+        missing.reverse();
+        missing.truncate(self.config.block_batch);
+        SyncRequest::Blocks(missing)
+    }
+
+    /// Check and store headers answering a [`SyncRequest::Headers`]. A
+    /// short batch ends the headers phase. Headers accepted before one
+    /// fails are kept.
+    ///
+    /// # Returns
+    /// How many headers were new
+    pub async fn on_headers(
+        &mut self,
+        headers: Vec<(BlockHeader, HeaderProof)>,
+    ) -> Result<usize, HeaderSyncError> {
+        let complete = headers.len() < self.config.header_batch;
+        let mut accepted = Vec::new();
+        let mut result = Ok(());
+        for (header, proof) in headers {
+            if self.client.header(&header.digest).is_some() {
+                continue;
+            }
+            if let Err(e) = self.client.accept_header(header.clone(), &proof).await {
+                result = Err(e);
+                break;
+            }
+            accepted.push(header);
+        }
+        for header in &accepted {
+            bincode::serde::encode_into_std_write(header, &mut self.log, config::standard())?;
+        }
+        self.log.flush()?;
+        result?;
+
+        debug!("accepted {} headers", accepted.len());
+        if complete {
+            info!(
+                "headers synced to height {:?}, fetching blocks",
+                self.client.best_header().map(|h| h.height)
+            );
+            self.phase = SyncPhase::Blocks;
+        }
+        Ok(accepted.len())
+    }
+
+    /// Go back to fetching headers, e.g. when a peer announces a new tip
+    pub fn refresh(&mut self) {
+        self.phase = SyncPhase::Headers;
+    }
+
+    /// The header of a downloaded page, if the page is on the best chain
+    pub fn check_block(&self, page: &NounSlab) -> Result<&BlockHeader, HeaderSyncError> {
+        let digest = unsafe { page.root() }
+            .as_cell()
+            .map_err(|_| HeaderSyncError::MalformedPage)
+            .and_then(|page| {
+                block_id_from_noun(page.head()).map_err(|_| HeaderSyncError::MalformedPage)
+            })?;
+        self.client
+            .best_chain()
+            .find(|h| h.digest == digest)
+            .ok_or(HeaderSyncError::UnexpectedBlock(digest))
+    }
+}
+
+/// Put the headers logged at `path` back into `client`. A header cut off
+/// by a crash is dropped from the log.
+fn restore_headers<V: ProofVerifier>(
+    client: &mut LightClient<V>,
+    path: &Path,
+) -> Result<usize, HeaderSyncError> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let mut offset = 0;
+    let mut restored = 0;
+    while offset < bytes.len() {
+        match bincode::serde::decode_from_slice::<BlockHeader, _>(
+            &bytes[offset..],
+            config::standard(),
+        ) {
+            Ok((header, read)) => {
+                client.insert_trusted(header);
+                offset += read;
+                restored += 1;
+            }
+            Err(e) => {
+                warn!("dropping unreadable header log tail at byte {offset}: {e}");
+                OpenOptions::new()
+                    .write(true)
+                    .open(path)?
+                    .set_len(offset as u64)?;
+                break;
+            }
+        }
+    }
+    Ok(restored)
+}
+
+/// One line of [`follow_stream`] output
+#[derive(Debug, Clone, Serialize)]
+pub struct HeaderVerdict {
+    /// Position of the header in the input stream, from 0
+    pub seq: u64,
+    pub height: Option<u64>,
+    pub digest: Option<BlockId>,
+    /// Whether the header became the tip of the best chain
+    pub best: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Check every length-prefixed jam of
+/// `[page-summary tx-ids=hash coinbase=hash msg=hash proof]`, as the
+/// kernel's `%heavy-header` peek gives it, read from
+/// `input` with `client`, writing a [`HeaderVerdict`] line to `output` for
+/// each. Headers have to come parents first. Frames over `limit` bytes are
+/// skipped. Returns the number of headers read.
+pub async fn follow_stream<V, R, W>(
+    client: &mut LightClient<V>,
+    mut input: R,
+    mut output: W,
+    limit: usize,
+) -> io::Result<u64>
+where
+    V: ProofVerifier,
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut seq = 0;
+    while let Some(frame) = read_frame(&mut input, limit).await? {
+        let verdict = match frame
+            .map_err(|e| e.to_string())
+            .and_then(|jam| split_frame(jam).map_err(|e| e.to_string()))
+        {
+            Ok((header, proof)) => {
+                let (height, digest) = (header.height, header.digest);
+                let outcome = client.accept_header(header, &proof).await;
+                HeaderVerdict {
+                    seq,
+                    height: Some(height),
+                    digest: Some(digest),
+                    best: *outcome.as_ref().unwrap_or(&false),
+                    error: outcome.err().map(|e| e.to_string()),
+                }
+            }
+            Err(error) => HeaderVerdict {
+                seq,
+                height: None,
+                digest: None,
+                best: false,
+                error: Some(error),
+            },
+        };
+        let mut line = serde_json::to_vec(&verdict)?;
+        line.push(b'\n');
+        output.write_all(&line).await?;
+        output.flush().await?;
+        seq += 1;
+    }
+    Ok(seq)
+}
+
+/// Split a jammed `[page-summary tx-ids coinbase msg proof]` into the header
+/// and what ties its proof to it
+fn split_frame(jam: Vec<u8>) -> Result<(BlockHeader, HeaderProof), LightClientError> {
+    let mut frame = NounSlab::new();
+    let root = frame
+        .cue_into(jam.into())
+        .map_err(|_| LightClientError::MalformedHeader("frame"))?;
+    let cell = root
+        .as_cell()
+        .map_err(|_| LightClientError::MalformedHeader("frame"))?;
+    let header = BlockHeader::from_noun(cell.head())?;
+    let proof = HeaderProof::from_noun(cell.tail())?;
+    Ok((header, proof))
+}
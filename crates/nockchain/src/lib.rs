@@ -1,5 +1,41 @@
+pub mod backend;
+pub mod block_store;
+pub mod candidate_queue;
+pub mod codec;
 pub mod config;
+pub mod consensus;
+pub mod crash_dump;
+pub mod daemon;
+pub mod effect;
+pub mod events;
+pub mod fast_sync;
+pub mod frame;
+pub mod header_sync;
+pub mod light_client;
+pub mod metrics;
 pub mod mining;
+pub mod network;
+pub mod nonce;
+pub mod observer;
+pub mod peek;
+pub mod poke;
+pub mod pool;
+pub mod profiling;
+pub mod progress;
+pub mod proof_archive;
+#[cfg(feature = "proto")]
+pub mod proto;
+pub mod prove_input;
+pub mod regression;
+pub mod reorg;
+pub mod stack;
+pub mod sync;
+pub mod template;
+pub mod trend;
+pub mod tx_api;
+pub mod vardiff;
+pub mod verifier;
+pub mod watchdog;
 
 use std::error::Error;
 use std::fs;
@@ -23,6 +59,7 @@ use nockvm::jets::hot::HotEntry;
 use nockvm::noun::{D, T};
 use nockvm_macros::tas;
 use tracing::{debug, info, instrument};
+use zkvm_jetpack::form::math::tip5::{select_tip5_backend, tip5_backend};
 
 use crate::mining::MiningKeyConfig;
 
@@ -186,6 +223,16 @@ pub async fn init_with_kernel(
         cli.validate()?;
     }
 
+    // before anything hashes, the backend is fixed on first use
+    if let Some(backend) = cli.as_ref().and_then(|c| c.tip5_backend) {
+        select_tip5_backend(backend)?;
+    }
+    let tip5 = tip5_backend();
+    info!("Hashing with the {tip5} tip5 backend");
+    metrics::metrics()
+        .tip5_backend
+        .swap(metrics::tip5_backend_id(tip5));
+
     let mut nockapp = boot::setup(
         kernel_jam,
         cli.as_ref().map(|c| c.nockapp_cli.clone()),
@@ -277,13 +324,10 @@ pub async fn init_with_kernel(
         } else { c.max_system_memory_fraction.map(memory_connection_limits::Behaviour::with_max_percentage) }
     });
 
-    let default_backbone_peers = if cli.as_ref().map(|c| c.fakenet).unwrap_or(false) {
-        config::TESTNET_BACKBONE_NODES
-    } else {
-        config::REALNET_BACKBONE_NODES
-    };
-
-    let backbone_peers = default_backbone_peers
+    let network = cli.as_ref().map(|c| c.network()).unwrap_or_default();
+    let backbone_peers = cli
+        .as_ref()
+        .map_or(network.backbone_nodes(), |c| c.backbone_nodes())
         .iter()
         .map(|multiaddr_str| {
             multiaddr_str
@@ -347,7 +391,15 @@ pub async fn init_with_kernel(
     // Create the born task that waits for all drivers to initialize
     let _born_task = driver_signals.create_born_task();
 
-    if cli.as_ref().map(|c| c.fakenet).unwrap_or(false) {
+    if let Some(set_constants) = network.set_constants_poke() {
+        info!("Joining {network} with its own consensus constants");
+        nockapp
+            .poke(nockapp::wire::SystemWire.to_wire(), set_constants)
+            .await
+            .expect("Failed to poke network constants");
+    }
+
+    if cli.as_ref().is_some_and(|c| c.fake_genesis()) {
         let message = cli
             .as_ref()
             .map(|c| c.genesis_message.clone())
@@ -408,10 +460,62 @@ pub async fn init_with_kernel(
 
     let mine = cli.as_ref().map_or(false, |c| c.mine);
 
-    let mining_driver =
-        crate::mining::create_mining_driver(mining_config, mine, Some(mining_init_tx));
+    // node events for whichever subsystems want them, metrics always do
+    let events = crate::events::EventBus::default();
+    tokio::spawn(crate::events::record_metrics(events.subscribe()));
+
+    let mut mining_options = crate::mining::MiningConfig {
+        state_dir: Some(nockapp::default_data_dir("nockchain").join("mining")),
+        stack_size: cli
+            .as_ref()
+            .map_or(Default::default(), |c| c.mining_stack_size),
+        extra_nonce: cli.as_ref().map_or(0, |c| c.mining_extra_nonce),
+        watchdog: cli
+            .as_ref()
+            .map_or(Default::default(), |c| crate::watchdog::Watchdog {
+                stall_timeout: c.mining_stall_timeout.map(std::time::Duration::from_secs),
+                max_restarts: c.mining_max_restarts,
+            }),
+        crash_dumps: cli.as_ref().and_then(|c| {
+            c.mining_crash_dump_dir
+                .as_ref()
+                .map(|dir| crate::crash_dump::CrashDumps::new(dir, c.mining_max_crash_dumps))
+        }),
+        events: events.clone(),
+        ..Default::default()
+    };
+    if let Some(path) = cli.as_ref().and_then(|c| c.kernel_path.as_deref()) {
+        let kernel = crate::backend::KernelJam::load_or_embedded(Some(path));
+        mining_options.backend = std::sync::Arc::new(crate::backend::KernelBackend::new(kernel));
+    }
+    if let Some(delay) = cli.as_ref().and_then(|c| c.mining_dry_run) {
+        mining_options = mining_options.dry_run(std::time::Duration::from_millis(delay));
+    }
+    let deterministic = cli.as_ref().and_then(|c| c.mining_deterministic);
+    mining_options.nonces = cli.as_ref().and_then(|c| match deterministic {
+        Some(seed) => c.mining_nonces.deterministic(seed).strategy(),
+        None => c.mining_nonces.strategy(),
+    });
+    if let Some(seed) = deterministic {
+        mining_options = mining_options.deterministic(seed);
+    }
+    let miner_peek = crate::peek::MinerPeek::attach(&mut mining_options);
+    let mining_driver = crate::mining::create_mining_driver(
+        mining_config,
+        mine,
+        Some(mining_init_tx),
+        mining_options,
+    );
     nockapp.add_io_driver(mining_driver).await;
 
+    let peer_events = events.clone();
+    let on_connected: nockchain_libp2p_io::nc::OnPeerConnected =
+        std::sync::Arc::new(move |peer, address: &Multiaddr| {
+            peer_events.publish(crate::events::NodeEvent::PeerConnected {
+                peer,
+                address: address.clone(),
+            });
+        });
     let libp2p_driver = nockchain_libp2p_io::nc::make_libp2p_driver(
         keypair,
         bind_multiaddrs,
@@ -422,9 +526,16 @@ pub async fn init_with_kernel(
         &force_peers,
         equix_builder,
         Some(libp2p_init_tx),
+        Some(on_connected),
     );
     nockapp.add_io_driver(libp2p_driver).await;
 
+    if let Some(bind) = cli.as_ref().and_then(|c| c.tx_api_bind) {
+        nockapp
+            .add_io_driver(crate::tx_api::tx_api_driver(bind, miner_peek, events))
+            .await;
+    }
+
     // Create the born driver that waits for the born signal
     let born_driver = driver_signals.create_born_driver();
 
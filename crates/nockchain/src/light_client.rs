@@ -0,0 +1,670 @@
+//! Header-only chain tracking.
+//!
+//! A light client downloads `page-summary`s together with the STARK proof of
+//! work for each page, checks the proof with a [`ProofVerifier`] and keeps the
+//! heaviest chain of headers it has seen. Each proof comes with the hashes
+//! of the parts of the page a summary leaves out, a [`HeaderProof`], so the
+//! client can rebuild the block commitment, check that the proof was mined
+//! for it and that the proof and commitment hash to the header's digest.
+//! It never runs the kernel's state transition, so it cannot validate
+//! transactions, only that each header carries valid work and extends a
+//! known parent. The work a header adds to
+//! its parent's must be what its target is worth, so a peer cannot claim
+//! weight it did not prove. Given a [`DifficultyAlgorithm`] it also checks
+//! each header's target, as far as the headers it holds reach back.
+//!
+//! [`ServiceVerifier`] checks proofs with the Rust verifier behind a
+//! [`VerificationService`]. `nockchain-verify --headers` runs a light client
+//! over a stream of headers, see [`crate::header_sync::follow_stream`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use ibig::UBig;
+use nockapp::noun::slab::NounSlab;
+use nockapp::noun::NounExt;
+use nockvm::noun::{Noun, D, T};
+use nockvm_macros::tas;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zkvm_jetpack::form::math::merkle::hash_pair;
+use zkvm_jetpack::form::math::tip5::{hash_varlen, tip5_noun_digest};
+
+use crate::consensus::difficulty::{
+    block_work, check_target, BlockTime, DifficultyAlgorithm, DifficultyError,
+};
+use crate::effect::{MiningEffectError, ProofView};
+use crate::verifier::{VerificationService, VerifyError};
+
+/// TIP5 digest identifying a page
+pub type BlockId = [u64; 5];
+
+#[derive(Debug, Error)]
+pub enum LightClientError {
+    #[error("malformed header noun: {0}")]
+    MalformedHeader(&'static str),
+    #[error("header {0:?} extends unknown parent {1:?}")]
+    UnknownParent(BlockId, BlockId),
+    #[error("header height {height} does not follow parent height {parent_height}")]
+    BadHeight { height: u64, parent_height: u64 },
+    #[error("accumulated work {actual:x} should be {expected:x}")]
+    BadAccumulatedWork { expected: UBig, actual: UBig },
+    #[error("proof of work failed to verify: {0}")]
+    InvalidProof(String),
+    #[error("malformed proof: {0}")]
+    MalformedProof(#[from] MiningEffectError),
+    #[error("proof was mined for block commitment {0:?}")]
+    WrongCommitment(BlockId),
+    #[error("header digest {claimed:?} should be {computed:?}")]
+    BadDigest { claimed: BlockId, computed: BlockId },
+    #[error("could not verify proof of work: {0}")]
+    Verify(#[from] VerifyError),
+    #[error(transparent)]
+    Difficulty(#[from] DifficultyError),
+}
+
+/// The fields of a `page-summary` needed to follow the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub digest: BlockId,
+    pub timestamp: u64,
+    pub epoch_counter: u64,
+    #[serde(with = "crate::codec::target")]
+    pub target: UBig,
+    #[serde(with = "crate::codec::target")]
+    pub accumulated_work: UBig,
+    pub height: u64,
+    pub parent: BlockId,
+}
+
+impl BlockHeader {
+    /// Parse a `page-summary`:
+    /// `[digest=block-id timestamp=@ epoch-counter=@ target=bignum accumulated-work=bignum height=@ud parent=block-id]`
+    pub fn from_noun(noun: Noun) -> Result<Self, LightClientError> {
+        let mut rest = noun;
+        let mut next = |what: &'static str| -> Result<Noun, LightClientError> {
+            let cell = rest
+                .as_cell()
+                .map_err(|_| LightClientError::MalformedHeader(what))?;
+            rest = cell.tail();
+            Ok(cell.head())
+        };
+        let digest = block_id_from_noun(next("digest")?)?;
+        let timestamp = atom_u64(next("timestamp")?, "timestamp")?;
+        let epoch_counter = atom_u64(next("epoch-counter")?, "epoch-counter")?;
+        let target = bignum_from_noun(next("target")?, "target")?;
+        let accumulated_work = bignum_from_noun(next("accumulated-work")?, "accumulated-work")?;
+        let height = atom_u64(next("height")?, "height")?;
+        let parent = block_id_from_noun(rest)?;
+        Ok(BlockHeader {
+            digest,
+            timestamp,
+            epoch_counter,
+            target,
+            accumulated_work,
+            height,
+            parent,
+        })
+    }
+}
+
+/// The rest of a page a light client needs to tie a header to its proof:
+/// the hashes the block commitment takes of the page's tx ids, coinbase and
+/// message, and the jammed proof from its `pow` field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderProof {
+    pub tx_ids: BlockId,
+    pub coinbase: BlockId,
+    pub msg: BlockId,
+    pub proof: Vec<u8>,
+}
+
+impl HeaderProof {
+    /// Parse `[tx-ids=hash coinbase=hash msg=hash proof]`, as the kernel's
+    /// `%heavy-header` peek gives it after the `page-summary`
+    pub fn from_noun(noun: Noun) -> Result<Self, LightClientError> {
+        let mut rest = noun;
+        let mut next = |what: &'static str| -> Result<Noun, LightClientError> {
+            let cell = rest
+                .as_cell()
+                .map_err(|_| LightClientError::MalformedHeader(what))?;
+            rest = cell.tail();
+            Ok(cell.head())
+        };
+        let tx_ids = block_id_from_noun(next("tx-ids")?)?;
+        let coinbase = block_id_from_noun(next("coinbase")?)?;
+        let msg = block_id_from_noun(next("msg")?)?;
+        let mut proof = NounSlab::new();
+        proof.copy_into(rest);
+        Ok(HeaderProof {
+            tx_ids,
+            coinbase,
+            msg,
+            proof: proof.jam().to_vec(),
+        })
+    }
+
+    /// `+block-commitment` of the page `header` summarizes
+    pub fn block_commitment(&self, header: &BlockHeader) -> BlockId {
+        let leaf = |value: u64| hash_varlen(&[1, value]);
+        let fields = [
+            header.parent,
+            self.tx_ids,
+            self.coinbase,
+            leaf(header.timestamp),
+            leaf(header.epoch_counter),
+            bignum_leaf(&header.target),
+            bignum_leaf(&header.accumulated_work),
+            leaf(header.height),
+        ];
+        fields
+            .iter()
+            .rev()
+            .fold(self.msg, |tail, field| hash_pair(field, &tail))
+    }
+
+    /// Check that the proof was mined for the page `header` summarizes and
+    /// that the two hash to the header's digest, as `+check-digest` does
+    pub fn check(&self, header: &BlockHeader) -> Result<(), LightClientError> {
+        let commitment = self.block_commitment(header);
+        let mut slab = NounSlab::new();
+        let root = slab
+            .cue_into(self.proof.clone().into())
+            .map_err(|_| MiningEffectError::TruncatedProof("jam"))?;
+        let proof = ProofView::new(&slab, root)?;
+        let puzzle = proof.puzzle()?;
+        if puzzle.block_commitment != commitment {
+            return Err(LightClientError::WrongCommitment(puzzle.block_commitment));
+        }
+        // `[[leaf+~ hash+(hash-proof pow)] commitment]`
+        let pow = hash_pair(&hash_varlen(&[1, 0]), &proof.hash()?);
+        let computed = hash_pair(&pow, &commitment);
+        if computed != header.digest {
+            return Err(LightClientError::BadDigest {
+                claimed: header.digest,
+                computed,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// `leaf+bignum`: the digest of the `[%bn limbs]` noun the kernel keeps
+fn bignum_leaf(value: &UBig) -> BlockId {
+    let mut slab = NounSlab::new();
+    // `+chunk` gives zero a single limb and no other number trailing zeros
+    let bytes = value.to_le_bytes();
+    let mut limbs: Vec<u64> = bytes
+        .chunks(4)
+        .map(|limb| {
+            let mut word = [0; 4];
+            word[..limb.len()].copy_from_slice(limb);
+            u32::from_le_bytes(word) as u64
+        })
+        .collect();
+    if limbs.is_empty() {
+        limbs.push(0);
+    }
+    let list = limbs
+        .iter()
+        .rev()
+        .fold(D(0), |tail, &limb| T(&mut slab, &[D(limb), tail]));
+    let bignum = T(&mut slab, &[D(tas!(b"bn")), list]);
+    tip5_noun_digest(bignum).expect("bignum limbs are belts")
+}
+
+/// Checks the proof of work attached to a header.
+///
+/// `proof` is the jammed `proof` from the page's `pow` field. The light
+/// client has already checked that it was mined for the header's page.
+pub trait ProofVerifier: Send + Sync {
+    fn verify<'a>(
+        &'a self,
+        header: &'a BlockHeader,
+        proof: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), LightClientError>>;
+}
+
+/// Checks proofs with a [`VerificationService`]: the proof has to verify
+/// and hash at or below the header's target.
+pub struct ServiceVerifier {
+    service: Arc<VerificationService>,
+    deadline: Option<Duration>,
+}
+
+impl ServiceVerifier {
+    pub fn new(service: Arc<VerificationService>) -> Self {
+        Self {
+            service,
+            deadline: None,
+        }
+    }
+
+    /// Give every proof this long to verify instead of the service default
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+}
+
+impl ProofVerifier for ServiceVerifier {
+    fn verify<'a>(
+        &'a self,
+        header: &'a BlockHeader,
+        proof: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), LightClientError>> {
+        Box::pin(async move {
+            let verdict = self
+                .service
+                .verify_jam(proof.to_vec(), self.deadline)
+                .await?;
+            match verdict.pow {
+                Some(pow) if verdict.valid && pow <= header.target => Ok(()),
+                Some(pow) if verdict.valid => Err(LightClientError::InvalidProof(format!(
+                    "proof hash {pow:x} is above target {:x}",
+                    header.target
+                ))),
+                _ => Err(LightClientError::InvalidProof("invalid proof".into())),
+            }
+        })
+    }
+}
+
+/// Tracks the heaviest chain of verified headers
+pub struct LightClient<V: ProofVerifier> {
+    verifier: V,
+    difficulty: Option<Arc<dyn DifficultyAlgorithm>>,
+    headers: HashMap<BlockId, BlockHeader>,
+    best: Option<BlockId>,
+}
+
+impl<V: ProofVerifier> LightClient<V> {
+    pub fn new(verifier: V) -> Self {
+        LightClient {
+            verifier,
+            difficulty: None,
+            headers: HashMap::new(),
+            best: None,
+        }
+    }
+
+    /// Also check every header's target against `algorithm`. Headers too
+    /// close to the trust anchor to look back far enough are let through.
+    pub fn with_difficulty(mut self, algorithm: Arc<dyn DifficultyAlgorithm>) -> Self {
+        self.difficulty = Some(algorithm);
+        self
+    }
+
+    /// Verify and store a header.
+    ///
+    /// The first header accepted is treated as the trust anchor; every later
+    /// header must extend one we already hold. Every header, the anchor
+    /// included, must hash with `proof` to its digest.
+    ///
+    /// # Returns
+    /// `true` if the header became the tip of the best chain
+    pub async fn accept_header(
+        &mut self,
+        header: BlockHeader,
+        proof: &HeaderProof,
+    ) -> Result<bool, LightClientError> {
+        if self.headers.contains_key(&header.digest) {
+            return Ok(false);
+        }
+        if !self.headers.is_empty() {
+            let parent =
+                self.headers
+                    .get(&header.parent)
+                    .ok_or(LightClientError::UnknownParent(
+                        header.digest,
+                        header.parent,
+                    ))?;
+            if header.height != parent.height + 1 {
+                return Err(LightClientError::BadHeight {
+                    height: header.height,
+                    parent_height: parent.height,
+                });
+            }
+            let expected = &parent.accumulated_work + block_work(&header.target);
+            if header.accumulated_work != expected {
+                return Err(LightClientError::BadAccumulatedWork {
+                    expected,
+                    actual: header.accumulated_work,
+                });
+            }
+            self.check_difficulty(&header)?;
+        }
+        proof.check(&header)?;
+        self.verifier.verify(&header, &proof.proof).await?;
+        Ok(self.insert(header))
+    }
+
+    /// Store a header checked earlier, e.g. one read back from disk, without
+    /// checking it again. Headers must come parents first, as they were
+    /// accepted.
+    ///
+    /// # Returns
+    /// `true` if the header became the tip of the best chain
+    pub fn insert_trusted(&mut self, header: BlockHeader) -> bool {
+        !self.headers.contains_key(&header.digest) && self.insert(header)
+    }
+
+    pub fn best_header(&self) -> Option<&BlockHeader> {
+        self.best.and_then(|id| self.headers.get(&id))
+    }
+
+    pub fn header(&self, id: &BlockId) -> Option<&BlockHeader> {
+        self.headers.get(id)
+    }
+
+    /// Walk the best chain from its tip back to the trust anchor
+    pub fn best_chain(&self) -> impl Iterator<Item = &BlockHeader> {
+        self.ancestry(self.best_header())
+    }
+
+    fn insert(&mut self, header: BlockHeader) -> bool {
+        let is_best = match self.best_header() {
+            Some(best) => header.accumulated_work > best.accumulated_work,
+            None => true,
+        };
+        if is_best {
+            self.best = Some(header.digest);
+        }
+        self.headers.insert(header.digest, header);
+        is_best
+    }
+
+    fn ancestry<'a>(
+        &'a self,
+        mut cursor: Option<&'a BlockHeader>,
+    ) -> impl Iterator<Item = &'a BlockHeader> {
+        std::iter::from_fn(move || {
+            let current = cursor?;
+            cursor = self.headers.get(&current.parent);
+            Some(current)
+        })
+    }
+
+    fn check_difficulty(&self, header: &BlockHeader) -> Result<(), LightClientError> {
+        let Some(algorithm) = &self.difficulty else {
+            return Ok(());
+        };
+        let mut recent: Vec<BlockTime> = self
+            .ancestry(self.headers.get(&header.parent))
+            .take(algorithm.window(header.height))
+            .map(BlockTime::from)
+            .collect();
+        recent.reverse();
+        match check_target(algorithm.as_ref(), &recent, &header.target) {
+            Err(DifficultyError::NotEnoughBlocks { .. }) => Ok(()),
+            res => res.map_err(Into::into),
+        }
+    }
+}
+
+fn atom_u64(noun: Noun, what: &'static str) -> Result<u64, LightClientError> {
+    noun.as_atom()
+        .and_then(|a| a.as_u64())
+        .map_err(|_| LightClientError::MalformedHeader(what))
+}
+
+pub(crate) fn block_id_from_noun(noun: Noun) -> Result<BlockId, LightClientError> {
+    let mut id = [0u64; 5];
+    let mut rest = noun;
+    for (i, limb) in id.iter_mut().enumerate() {
+        let elem = if i == 4 {
+            rest
+        } else {
+            let cell = rest
+                .as_cell()
+                .map_err(|_| LightClientError::MalformedHeader("block-id"))?;
+            rest = cell.tail();
+            cell.head()
+        };
+        *limb = atom_u64(elem, "block-id")?;
+    }
+    Ok(id)
+}
+
+/// A bignum is `[%bn p=(list u32)]` with the least significant limb first
+fn bignum_from_noun(noun: Noun, what: &'static str) -> Result<UBig, LightClientError> {
+    let cell = noun
+        .as_cell()
+        .map_err(|_| LightClientError::MalformedHeader(what))?;
+    if !cell.head().eq_bytes("bn") {
+        return Err(LightClientError::MalformedHeader(what));
+    }
+    let mut value = UBig::from(0u8);
+    let mut limbs = Vec::new();
+    let mut list = cell.tail();
+    while let Ok(c) = list.as_cell() {
+        let limb = atom_u64(c.head(), what)?;
+        if limb > u32::MAX as u64 {
+            return Err(LightClientError::MalformedHeader(what));
+        }
+        limbs.push(limb);
+        list = c.tail();
+    }
+    if atom_u64(list, what)? != 0 {
+        return Err(LightClientError::MalformedHeader(what));
+    }
+    for limb in limbs.into_iter().rev() {
+        value = (value << 32) + UBig::from(limb);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct AcceptAll;
+
+    impl ProofVerifier for AcceptAll {
+        fn verify<'a>(
+            &'a self,
+            _header: &'a BlockHeader,
+            _proof: &'a [u8],
+        ) -> BoxFuture<'a, Result<(), LightClientError>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    /// Attach a proof mined for `header`'s page, whose message is
+    /// `[id 0 0 0 0]` so that forks differ, and set the digest they hash to
+    fn seal(mut header: BlockHeader, id: u64) -> (BlockHeader, HeaderProof) {
+        let mut proof = HeaderProof {
+            tx_ids: [0; 5],
+            coinbase: [0; 5],
+            msg: [id, 0, 0, 0, 0],
+            proof: Vec::new(),
+        };
+        let mut slab = NounSlab::new();
+        let commitment = T(&mut slab, &proof.block_commitment(&header).map(D));
+        let nonce = T(&mut slab, &[id; 5].map(D));
+        let puzzle = T(
+            &mut slab,
+            &[D(tas!(b"puzzle")), commitment, nonce, D(64), D(0)],
+        );
+        let objects = T(&mut slab, &[puzzle, D(0)]);
+        let root = T(&mut slab, &[D(0), objects, D(0), D(0)]);
+        slab.set_root(root);
+        proof.proof = slab.jam().to_vec();
+        header.digest = [0; 5];
+        match proof.check(&header) {
+            Err(LightClientError::BadDigest { computed, .. }) => header.digest = computed,
+            other => panic!("unexpected {other:?}"),
+        }
+        (header, proof)
+    }
+
+    fn anchor(id: u64, height: u64) -> (BlockHeader, HeaderProof) {
+        let header = BlockHeader {
+            digest: [0; 5],
+            timestamp: 0,
+            epoch_counter: 0,
+            target: UBig::from(1u8),
+            accumulated_work: UBig::from(1u8),
+            height,
+            parent: [0; 5],
+        };
+        seal(header, id)
+    }
+
+    /// The header after `parent` at `target`, carrying the work it is worth
+    fn child(parent: &BlockHeader, target: UBig) -> BlockHeader {
+        BlockHeader {
+            digest: [0; 5],
+            timestamp: parent.timestamp,
+            epoch_counter: 0,
+            accumulated_work: &parent.accumulated_work + block_work(&target),
+            target,
+            height: parent.height + 1,
+            parent: parent.digest,
+        }
+    }
+
+    fn easy(divisor: u32) -> UBig {
+        crate::consensus::difficulty::max_target() / UBig::from(divisor)
+    }
+
+    #[tokio::test]
+    async fn test_heaviest_fork_wins() {
+        let mut client = LightClient::new(AcceptAll);
+        let (first, proof) = anchor(1, 0);
+        assert!(client.accept_header(first.clone(), &proof).await.unwrap());
+        let (second, proof) = seal(child(&first, easy(100)), 2);
+        assert!(client.accept_header(second.clone(), &proof).await.unwrap());
+        // a competing fork at the same height with more work
+        let (fork, proof) = seal(child(&first, easy(500)), 3);
+        assert!(client.accept_header(fork.clone(), &proof).await.unwrap());
+        // a lighter extension of the first fork does not take over
+        let (lighter, proof) = seal(child(&second, easy(100)), 4);
+        assert!(!client.accept_header(lighter, &proof).await.unwrap());
+
+        let chain: Vec<BlockId> = client.best_chain().map(|h| h.digest).collect();
+        assert_eq!(chain, vec![fork.digest, first.digest]);
+    }
+
+    #[tokio::test]
+    async fn test_proof_must_be_mined_for_the_header() {
+        let mut client = LightClient::new(AcceptAll);
+        let (first, proof) = anchor(1, 0);
+        client.accept_header(first.clone(), &proof).await.unwrap();
+        let (second, proof) = seal(child(&first, easy(10)), 2);
+
+        // a header claiming another block's proof
+        let (_, other) = seal(child(&first, easy(10)), 3);
+        assert!(matches!(
+            client.accept_header(second.clone(), &other).await,
+            Err(LightClientError::WrongCommitment(_))
+        ));
+        // the right proof under a digest it does not hash to
+        let mut renamed = second.clone();
+        renamed.digest = [7; 5];
+        assert!(matches!(
+            client.accept_header(renamed, &proof).await,
+            Err(LightClientError::BadDigest { .. })
+        ));
+        // a field of the summary changed after sealing
+        let mut retimed = second.clone();
+        retimed.timestamp += 1;
+        assert!(matches!(
+            client.accept_header(retimed, &proof).await,
+            Err(LightClientError::WrongCommitment(_))
+        ));
+        assert!(client.accept_header(second, &proof).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_checks_targets_past_the_anchor() {
+        use crate::consensus::difficulty::EpochRetarget;
+
+        let epochs = EpochRetarget {
+            blocks_per_epoch: 2,
+            target_epoch_duration: 100,
+            min_past_blocks: 1,
+            max_target: UBig::from(1_000u32),
+        };
+        let mut client = LightClient::new(AcceptAll).with_difficulty(Arc::new(epochs));
+        // the anchor is mid epoch, so the block closing it cannot be checked
+        let (first, proof) = anchor(1, 3);
+        client.accept_header(first.clone(), &proof).await.unwrap();
+        let (retargeted, proof) = seal(child(&first, UBig::from(7u8)), 2);
+        client
+            .accept_header(retargeted.clone(), &proof)
+            .await
+            .unwrap();
+
+        // an epoch closing 200 seconds after the one before doubles the target
+        let mut next = child(&retargeted, UBig::from(7u8));
+        next.timestamp = 200;
+        let (next, proof) = seal(next, 3);
+        client.accept_header(next.clone(), &proof).await.unwrap();
+        let (closing, proof) = seal(child(&next, UBig::from(7u8)), 4);
+        assert!(matches!(
+            client.accept_header(closing, &proof).await,
+            Err(LightClientError::Difficulty(
+                DifficultyError::WrongTarget { .. }
+            ))
+        ));
+        let (closing, proof) = seal(child(&next, UBig::from(14u8)), 4);
+        client.accept_header(closing, &proof).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rejects_orphans_and_bad_heights() {
+        let mut client = LightClient::new(AcceptAll);
+        let (first, proof) = anchor(1, 0);
+        client.accept_header(first.clone(), &proof).await.unwrap();
+        let mut orphan = child(&first, easy(10));
+        orphan.parent = [9, 0, 0, 0, 0];
+        let (orphan, proof) = seal(orphan, 2);
+        assert!(matches!(
+            client.accept_header(orphan, &proof).await,
+            Err(LightClientError::UnknownParent(..))
+        ));
+        let mut skipped = child(&first, easy(10));
+        skipped.height = 5;
+        let (skipped, proof) = seal(skipped, 2);
+        assert!(matches!(
+            client.accept_header(skipped, &proof).await,
+            Err(LightClientError::BadHeight { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_accumulated_work_must_match_the_target() {
+        let mut client = LightClient::new(AcceptAll);
+        let (first, proof) = anchor(1, 0);
+        client.accept_header(first.clone(), &proof).await.unwrap();
+        // claiming more work than the target is worth
+        let mut inflated = child(&first, easy(10));
+        inflated.accumulated_work += UBig::from(1_000_000u32);
+        let (inflated, proof) = seal(inflated, 2);
+        assert!(matches!(
+            client.accept_header(inflated, &proof).await,
+            Err(LightClientError::BadAccumulatedWork { .. })
+        ));
+        let (second, proof) = seal(child(&first, easy(10)), 2);
+        client.accept_header(second, &proof).await.unwrap();
+    }
+
+    #[test]
+    fn test_bignums_need_their_tag_and_u32_limbs() {
+        let mut slab = NounSlab::new();
+        let limbs = T(&mut slab, &[D(5), D(1), D(0)]);
+        let bignum = T(&mut slab, &[D(tas!(b"bn")), limbs]);
+        assert_eq!(
+            bignum_from_noun(bignum, "target").unwrap(),
+            UBig::from((1u64 << 32) + 5)
+        );
+        let untagged = T(&mut slab, &[D(tas!(b"xx")), limbs]);
+        assert!(bignum_from_noun(untagged, "target").is_err());
+        let wide = T(&mut slab, &[D(1 << 32), D(0)]);
+        let wide = T(&mut slab, &[D(tas!(b"bn")), wide]);
+        assert!(bignum_from_noun(wide, "target").is_err());
+    }
+}
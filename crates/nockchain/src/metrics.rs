@@ -0,0 +1,86 @@
+use gnort::*;
+
+metrics_struct![
+    NockchainMetrics,
+    // 0 portable, 1 avx2, 2 avx512, 3 neon, see `tip5_backend_id`
+    (tip5_backend, "nockchain.tip5_backend", Gauge),
+    (
+        mining_kernel_load_failures,
+        "nockchain.mining.kernel_load_failures",
+        Count
+    ),
+    (
+        mining_kernel_poke_failures,
+        "nockchain.mining.kernel_poke_failures",
+        Count
+    ),
+    (
+        mining_kernel_stalls,
+        "nockchain.mining.kernel_stalls",
+        Count
+    ),
+    (
+        mining_kernel_restarts,
+        "nockchain.mining.kernel_restarts",
+        Count
+    ),
+    // pokes waiting on a shared kernel, see `poke::PokeQueue`
+    (
+        poke_queue_verify_depth,
+        "nockchain.poke_queue.verify_depth",
+        Gauge
+    ),
+    (
+        poke_queue_mine_depth,
+        "nockchain.poke_queue.mine_depth",
+        Gauge
+    ),
+    (
+        poke_queue_verify_wait,
+        "nockchain.poke_queue.verify_wait",
+        TimingCount
+    ),
+    (
+        poke_queue_mine_wait,
+        "nockchain.poke_queue.mine_wait",
+        TimingCount
+    ),
+    // counted off the node's event bus, see `events::record_metrics`
+    (mining_candidates, "nockchain.mining.candidates", Count),
+    (mining_proofs_found, "nockchain.mining.proofs_found", Count),
+    (
+        verifier_proofs_valid,
+        "nockchain.verifier.proofs_valid",
+        Count
+    ),
+    (
+        verifier_proofs_invalid,
+        "nockchain.verifier.proofs_invalid",
+        Count
+    ),
+    (peers_connected, "nockchain.peers_connected", Count),
+    (reorgs, "nockchain.reorgs", Count),
+    // blocks rolled back by the latest reorg
+    (reorg_depth, "nockchain.reorg_depth", Gauge)
+];
+
+static METRICS: std::sync::OnceLock<NockchainMetrics> = std::sync::OnceLock::new();
+
+/// The node's metrics, registered with the global registry on first use
+pub fn metrics() -> &'static NockchainMetrics {
+    METRICS.get_or_init(|| {
+        NockchainMetrics::register(gnort::global_metrics_registry())
+            .expect("Failed to register metrics!")
+    })
+}
+
+/// Gauge value for the tip5 backend in use
+pub fn tip5_backend_id(backend: zkvm_jetpack::form::math::tip5::Tip5Backend) -> f64 {
+    use zkvm_jetpack::form::math::tip5::Tip5Backend;
+    match backend {
+        Tip5Backend::Portable => 0.0,
+        Tip5Backend::Avx2 => 1.0,
+        Tip5Backend::Avx512 => 2.0,
+        Tip5Backend::Neon => 3.0,
+    }
+}
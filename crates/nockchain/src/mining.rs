@@ -1,23 +1,44 @@
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use kernels::miner::KERNEL;
-use nockapp::kernel::checkpoint::JamPaths;
-use nockapp::kernel::form::Kernel;
+use nockapp::kernel::form::Entropy;
 use nockapp::nockapp::driver::{IODriverFn, NockAppHandle, PokeResult};
 use nockapp::nockapp::wire::Wire;
 use nockapp::nockapp::NockAppError;
+use nockapp::noun::pool::SlabPool;
 use nockapp::noun::slab::NounSlab;
 use nockapp::noun::{AtomExt, NounExt};
 use nockvm::noun::{Atom, D, T};
 use nockvm_macros::tas;
-use tempfile::tempdir;
-use tracing::{instrument, warn};
+use tokio::sync::oneshot;
+use tracing::{error, info, instrument, warn, Instrument};
+
+use crate::backend::{KernelBackend, ProvingBackend, SimulatedBackend};
+use crate::crash_dump::{CrashDumpBackend, CrashDumps};
+use crate::effect::{validate_effect_schema, ExpectedSchema, MiningEffect};
+use crate::events::{EventBus, NodeEvent};
+use crate::nonce::NonceStrategy;
+use crate::observer::{PokeComplete, PokeKind, PokeObserver, PokeObservers, PokeStart};
+use crate::profiling::{profile_span, ProfilePhase};
+use crate::progress::{ProgressReporter, ProgressSender, ProvePhase};
+use crate::prove_input::{belts_to_noun, ProveBlockInput, DIGEST_BELTS};
+use crate::stack::{format_words, is_out_of_memory, StackSize};
+use crate::template::{next_template, TemplateReceiver};
+use crate::watchdog::{Watchdog, WatchedBackend};
 
 pub enum MiningWire {
     Mined,
     Candidate,
     SetPubKey,
     Enable,
+    /// Check a proof with the miner kernel, see [`crate::verifier::verify_cause`]
+    Verify,
+    /// Ask the miner kernel for its [`crate::effect::MinerStatus`], see [`status_cause`]
+    Status,
+    /// Stop the miner kernel proving a block commitment, see [`cancel_cause`]
+    Cancel,
 }
 
 impl MiningWire {
@@ -27,10 +48,34 @@ impl MiningWire {
             MiningWire::SetPubKey => "setpubkey",
             MiningWire::Candidate => "candidate",
             MiningWire::Enable => "enable",
+            MiningWire::Verify => "verify",
+            MiningWire::Status => "status",
+            MiningWire::Cancel => "cancel",
         }
     }
 }
 
+/// The cause of a [`MiningWire::Status`] poke
+pub fn status_cause() -> NounSlab {
+    let mut slab = NounSlab::new();
+    let cause = T(&mut slab, &[D(tas!(b"status")), D(0)]);
+    slab.set_root(cause);
+    slab
+}
+
+/// The cause of a [`MiningWire::Cancel`] poke. Candidates for the mined
+/// `block_commitment` poked after it are answered with a
+/// [`crate::effect::CancelledEffect`] instead of a proof; one already being
+/// proven has to be stopped with [`crate::backend::Prover::cancel`]. The
+/// kernel remembers only the last 16 commitments cancelled.
+pub fn cancel_cause(block_commitment: &[u64; DIGEST_BELTS]) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let commitment = belts_to_noun(&mut slab, block_commitment);
+    let cause = T(&mut slab, &[D(tas!(b"cancel")), commitment]);
+    slab.set_root(cause);
+    slab
+}
+
 impl Wire for MiningWire {
     const VERSION: u64 = 1;
     const SOURCE: &'static str = "miner";
@@ -71,10 +116,121 @@ impl FromStr for MiningKeyConfig {
     }
 }
 
+/// Options for how mining attempts are run
+#[derive(Clone)]
+pub struct MiningConfig {
+    /// If set, the candidate being mined when the node shuts down is written
+    /// here and mining resumes from it on the next start.
+    pub state_dir: Option<PathBuf>,
+    /// NockStack size for the prover kernel
+    pub stack_size: StackSize,
+    /// Entropy for the prove poke, fixed values make attempts reproducible
+    pub entropy: Entropy,
+    /// What runs the prove poke, the miner kernel unless testing
+    pub backend: Arc<dyn ProvingBackend>,
+    /// Mixed into every candidate's block commitment when nonzero, see
+    /// [`ProveBlockInput::with_extra_nonce`]
+    pub extra_nonce: u64,
+    /// Block templates pushed by the node, mined alongside the kernel's
+    /// `%mine` candidates. A new template cancels a running attempt.
+    pub templates: Option<TemplateReceiver>,
+    /// Replaces the nonce of every candidate when set, otherwise the nonce
+    /// the node picked is proven
+    pub nonces: Option<Arc<dyn NonceStrategy>>,
+    /// Restarts the prover kernel when it fails or wedges
+    pub watchdog: Watchdog,
+    /// Reproducible mining, see [`MiningConfig::deterministic`]
+    pub deterministic: bool,
+    /// Told about every prove poke
+    pub observers: PokeObservers,
+    /// Where failed prove pokes are dumped for reproducing them, see
+    /// [`crate::crash_dump`]
+    pub crash_dumps: Option<CrashDumps>,
+    /// Told about every candidate attempted and proof found
+    pub events: EventBus,
+}
+
+impl Default for MiningConfig {
+    fn default() -> Self {
+        Self {
+            state_dir: None,
+            stack_size: StackSize::default(),
+            entropy: Entropy::default(),
+            backend: Arc::new(KernelBackend::default()),
+            extra_nonce: 0,
+            templates: None,
+            nonces: None,
+            watchdog: Watchdog::default(),
+            deterministic: false,
+            observers: PokeObservers::default(),
+            crash_dumps: None,
+            events: EventBus::default(),
+        }
+    }
+}
+
+impl MiningConfig {
+    /// Run the whole mining pipeline with [`SimulatedBackend`] in place of
+    /// the prover, taking `delay` per candidate. Mined proofs are fake and
+    /// rejected by the node, this only exercises the code around proving.
+    pub fn dry_run(self, delay: Duration) -> Self {
+        warn!("Mining dry run: candidates get fake proofs after {delay:?}");
+        Self {
+            backend: Arc::new(SimulatedBackend::new(delay)),
+            ..self
+        }
+    }
+
+    /// Mine reproducibly, so two runs on the same candidate produce
+    /// byte-identical proofs, e.g. to compare a branch against master.
+    ///
+    /// The prove poke gets `Entropy::Fixed(seed)`, and a new block template
+    /// queues behind the running attempt instead of cancelling it, so which
+    /// proofs come out no longer depends on when templates arrive. Nonces
+    /// are left to [`MiningConfig::nonces`], which must not be random for
+    /// runs to match; on the command line `random` becomes
+    /// [`crate::nonce::SeededNonces`] from `seed`. The poke's timestamp is
+    /// not fixed, the miner kernel ignores it. Each mined proof's blake3
+    /// fingerprint is logged for comparing runs.
+    pub fn deterministic(self, seed: u64) -> Self {
+        info!("Deterministic mining with entropy seed {seed}");
+        Self {
+            entropy: Entropy::Fixed(seed),
+            deterministic: true,
+            ..self
+        }
+    }
+}
+
+impl std::fmt::Debug for MiningConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MiningConfig")
+            .field("state_dir", &self.state_dir)
+            .field("stack_size", &self.stack_size)
+            .field("entropy", &self.entropy)
+            .field("extra_nonce", &self.extra_nonce)
+            .field("templates", &self.templates.is_some())
+            .field("nonces", &self.nonces.is_some())
+            .field("watchdog", &self.watchdog)
+            .field("deterministic", &self.deterministic)
+            .field("observers", &self.observers.len())
+            .field("crash_dumps", &self.crash_dumps.as_ref().map(|d| d.dir()))
+            .field("events", &self.events.subscribers())
+            .finish_non_exhaustive()
+    }
+}
+
+/// File the candidate being mined is persisted to when the node shuts down
+const CANDIDATE_FILE: &str = "candidate.jam";
+
+/// Idle slabs kept by the mining driver for copying candidates
+const CANDIDATE_POOL_SIZE: usize = 4;
+
 pub fn create_mining_driver(
     mining_config: Option<Vec<MiningKeyConfig>>,
     mine: bool,
     init_complete_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    config: MiningConfig,
 ) -> IODriverFn {
     Box::new(move |mut handle| {
         Box::pin(async move {
@@ -112,10 +268,44 @@ pub fn create_mining_driver(
                 return Ok(());
             }
             let mut next_attempt: Option<NounSlab> = None;
+            let mut current_candidate: Option<NounSlab> = None;
             let mut current_attempt: tokio::task::JoinSet<()> = tokio::task::JoinSet::new();
+            // tells the running attempt that a newer template has replaced it
+            let mut supersede: Option<oneshot::Sender<()>> = None;
+            let mut templates = config.templates.clone();
+            let pool = SlabPool::new(CANDIDATE_POOL_SIZE);
+
+            if let Some(candidate_slab) = config
+                .state_dir
+                .as_deref()
+                .and_then(take_persisted_candidate)
+            {
+                info!("Resuming mining on candidate persisted at last shutdown");
+                current_candidate = Some(pooled_clone(&pool, &candidate_slab));
+                let (cur_handle, attempt_handle) = handle.dup();
+                handle = cur_handle;
+                supersede = Some(spawn_attempt(
+                    &mut current_attempt,
+                    candidate_slab,
+                    attempt_handle,
+                    &config,
+                ));
+            }
 
             loop {
                 tokio::select! {
+                    _ = handle.exit.shutdown_requested() => {
+                        // prefer the newer candidate we were about to start on
+                        let candidate = next_attempt.take().or(current_candidate.take());
+                        if let (Some(dir), Some(candidate)) = (config.state_dir.as_deref(), candidate) {
+                            if let Err(e) = persist_candidate(dir, &candidate) {
+                                warn!("Could not persist mining candidate: {e}");
+                            }
+                        }
+                        // attempts cancel their own kernels on shutdown, wait for them to unwind
+                        while current_attempt.join_next().await.is_some() {}
+                        return Ok(());
+                    },
                     effect_res = handle.next_effect() => {
                         let Ok(effect) = effect_res else {
                           warn!("Error receiving effect in mining driver: {effect_res:?}");
@@ -128,16 +318,27 @@ pub fn create_mining_driver(
 
                         if effect_cell.head().eq_bytes("mine") {
                             let candidate_slab = {
-                                let mut slab = NounSlab::new();
+                                let mut slab = pool.take();
                                 slab.copy_into(effect_cell.tail());
                                 slab
                             };
+                            pool.give(effect);
                             if !current_attempt.is_empty() {
-                                next_attempt = Some(candidate_slab);
+                                if let Some(stale) = next_attempt.replace(candidate_slab) {
+                                    pool.give(stale);
+                                }
                             } else {
+                                if let Some(done) = current_candidate.replace(pooled_clone(&pool, &candidate_slab)) {
+                                    pool.give(done);
+                                }
                                 let (cur_handle, attempt_handle) = handle.dup();
                                 handle = cur_handle;
-                                current_attempt.spawn(mining_attempt(candidate_slab, attempt_handle));
+                                supersede = Some(spawn_attempt(
+                                    &mut current_attempt,
+                                    candidate_slab,
+                                    attempt_handle,
+                                    &config,
+                                ));
                             }
                         }
                     },
@@ -145,14 +346,54 @@ pub fn create_mining_driver(
                         if let Some(Err(e)) = mining_attempt_res {
                             warn!("Error during mining attempt: {e:?}");
                         }
-                        let Some(candidate_slab) = next_attempt else {
+                        supersede = None;
+                        if let Some(done) = current_candidate.take() {
+                            pool.give(done);
+                        }
+                        let Some(candidate_slab) = next_attempt.take() else {
                             continue;
                         };
-                        next_attempt = None;
+                        current_candidate = Some(pooled_clone(&pool, &candidate_slab));
                         let (cur_handle, attempt_handle) = handle.dup();
                         handle = cur_handle;
-                        current_attempt.spawn(mining_attempt(candidate_slab, attempt_handle));
-
+                        supersede = Some(spawn_attempt(
+                            &mut current_attempt,
+                            candidate_slab,
+                            attempt_handle,
+                            &config,
+                        ));
+                    }
+                    template = next_template(&mut templates), if templates.is_some() => {
+                        let Some(template) = template else {
+                            warn!("Block template sender dropped, mining only on kernel candidates");
+                            continue;
+                        };
+                        info!("New block template at height {}", template.height);
+                        let candidate_slab = template.candidate();
+                        if !current_attempt.is_empty() {
+                            if let Some(stale) = next_attempt.replace(candidate_slab) {
+                                pool.give(stale);
+                            }
+                            // the running attempt is stale now, stop it at its next safe point,
+                            // unless deterministic runs need every attempt to finish
+                            if config.deterministic {
+                                info!("Deterministic mining, starting the template after the running attempt");
+                            } else if let Some(supersede) = supersede.take() {
+                                let _ = supersede.send(());
+                            }
+                        } else {
+                            if let Some(done) = current_candidate.replace(pooled_clone(&pool, &candidate_slab)) {
+                                pool.give(done);
+                            }
+                            let (cur_handle, attempt_handle) = handle.dup();
+                            handle = cur_handle;
+                            supersede = Some(spawn_attempt(
+                                &mut current_attempt,
+                                candidate_slab,
+                                attempt_handle,
+                                &config,
+                            ));
+                        }
                     }
                 }
             }
@@ -161,32 +402,274 @@ pub fn create_mining_driver(
 }
 
 pub async fn mining_attempt(candidate: NounSlab, handle: NockAppHandle) -> () {
-    let snapshot_dir =
-        tokio::task::spawn_blocking(|| tempdir().expect("Failed to create temporary directory"))
-            .await
-            .expect("Failed to create temporary directory");
-    let hot_state = zkvm_jetpack::hot::produce_prover_hot_state();
-    let snapshot_path_buf = snapshot_dir.path().to_path_buf();
-    let jam_paths = JamPaths::new(snapshot_dir.path());
-    // Spawns a new std::thread for this mining attempt
-    let kernel =
-        Kernel::load_with_hot_state_huge(snapshot_path_buf, jam_paths, KERNEL, &hot_state, false)
-            .await
-            .expect("Could not load mining kernel");
-    let effects_slab = kernel
-        .poke(MiningWire::Candidate.to_wire(), candidate)
+    mining_attempt_with_progress(candidate, handle, None).await
+}
+
+/// Run a mining attempt, reporting its phases on `progress`
+pub async fn mining_attempt_with_progress(
+    candidate: NounSlab,
+    handle: NockAppHandle,
+    progress: Option<ProgressSender>,
+) -> () {
+    mining_attempt_with_config(candidate, handle, MiningConfig::default(), progress).await
+}
+
+/// Run a mining attempt with the given [`MiningConfig`], reporting its phases on `progress`
+pub async fn mining_attempt_with_config(
+    candidate: NounSlab,
+    handle: NockAppHandle,
+    config: MiningConfig,
+    progress: Option<ProgressSender>,
+) -> () {
+    attempt(candidate, handle, config, progress, None)
+        .instrument(profile_span(ProfilePhase::Attempt))
         .await
-        .expect("Could not poke mining kernel with candidate");
-    for effect in effects_slab.to_vec() {
-        let Ok(effect_cell) = (unsafe { effect.root().as_cell() }) else {
-            drop(effect);
+}
+
+/// Start an attempt that gives up once the returned sender fires
+fn spawn_attempt(
+    attempts: &mut tokio::task::JoinSet<()>,
+    candidate: NounSlab,
+    handle: NockAppHandle,
+    config: &MiningConfig,
+) -> oneshot::Sender<()> {
+    let (supersede, superseded) = oneshot::channel();
+    attempts.spawn(
+        attempt(candidate, handle, config.clone(), None, Some(superseded))
+            .instrument(profile_span(ProfilePhase::Attempt)),
+    );
+    supersede
+}
+
+async fn attempt(
+    candidate: NounSlab,
+    handle: NockAppHandle,
+    config: MiningConfig,
+    progress: Option<ProgressSender>,
+    mut superseded: Option<oneshot::Receiver<()>>,
+) {
+    let mut reporter = ProgressReporter::new(progress);
+    let (input, candidate) = match ProveBlockInput::try_from(&candidate) {
+        Ok(input) if config.extra_nonce != 0 || config.nonces.is_some() => {
+            let mut input = input.with_extra_nonce(config.extra_nonce);
+            if let Some(nonces) = &config.nonces {
+                match input.with_nonce(&nonces.next_nonce()) {
+                    Ok(renonced) => input = renonced,
+                    Err(e) => {
+                        reporter.phase(ProvePhase::Failed, None);
+                        error!("Nonce strategy produced an invalid nonce: {e}");
+                        return;
+                    }
+                }
+            }
+            let candidate = input.to_noun_slab();
+            (input, candidate)
+        }
+        Ok(input) => (input, candidate),
+        Err(e) => {
+            reporter.phase(ProvePhase::Failed, None);
+            error!("Refusing to prove invalid mining candidate: {e}");
+            return;
+        }
+    };
+    if superseded
+        .as_mut()
+        .is_some_and(|superseded| superseded.try_recv().is_ok())
+    {
+        info!("Mining candidate superseded before proving started");
+        reporter.phase(ProvePhase::Failed, None);
+        return;
+    }
+    tracing::Span::current().record("length", input.length());
+    config.events.publish(NodeEvent::NewCandidate {
+        length: input.length(),
+    });
+    let stack_words = config.stack_size.words_for(candidate_length(&candidate));
+    reporter.phase(ProvePhase::LoadingKernel, None);
+    // dump inside the watchdog, which drops a failed kernel with its state
+    let inner: Arc<dyn ProvingBackend> = match &config.crash_dumps {
+        Some(dumps) => Arc::new(CrashDumpBackend::new(config.backend.clone(), dumps.clone())),
+        None => config.backend.clone(),
+    };
+    let backend = WatchedBackend::new(inner, config.watchdog);
+    let prover = match backend
+        .load(stack_words)
+        .instrument(profile_span(ProfilePhase::LoadKernel))
+        .await
+    {
+        Ok(prover) => prover,
+        Err(e) => {
+            reporter.phase(ProvePhase::Failed, None);
+            error!("Could not load mining kernel: {e}");
+            return;
+        }
+    };
+    reporter.phase(ProvePhase::Proving, None);
+    let length = input.length();
+    config.observers.on_start(&PokeStart {
+        kind: PokeKind::Prove,
+        length,
+        slab_bytes: candidate.allocated_bytes(),
+    });
+    let poke_started = Instant::now();
+    let poke_failed = || {
+        config.observers.on_complete(&PokeComplete::failed(
+            PokeKind::Prove,
+            length,
+            poke_started.elapsed(),
+        ))
+    };
+    let prove = prover
+        .prove(candidate, config.entropy)
+        .instrument(profile_span(ProfilePhase::Poke));
+    let effects_slab = tokio::select! {
+        res = prove => match res {
+            Ok(effects) => effects,
+            Err(e) if is_out_of_memory(&e) => {
+                poke_failed();
+                reporter.phase(ProvePhase::Failed, None);
+                error!(
+                    "Mining kernel ran out of memory with a {} NockStack, set a larger --mining-stack-size",
+                    format_words(stack_words)
+                );
+                return;
+            }
+            Err(e) => {
+                // the watchdog has already restarted the kernel as often as allowed
+                poke_failed();
+                reporter.phase(ProvePhase::Failed, None);
+                error!("Giving up on mining candidate: {e:?}");
+                return;
+            }
+        },
+        _ = handle.exit.shutdown_requested() => {
+            info!("Shutting down, cancelling mining attempt");
+            prover.cancel();
+            poke_failed();
+            reporter.phase(ProvePhase::Failed, None);
+            return;
+        }
+        _ = superseded_by(superseded) => {
+            info!("Newer block template arrived, cancelling stale mining attempt");
+            prover.cancel();
+            poke_failed();
+            reporter.phase(ProvePhase::Failed, None);
+            return;
+        }
+    };
+    let proved_in = poke_started.elapsed();
+    config
+        .observers
+        .finished(PokeKind::Prove, length, proved_in, &effects_slab);
+    reporter.phase(ProvePhase::Submitting, None);
+    let pow_schema = ExpectedSchema::pow_effect();
+    let commands = profile_span(ProfilePhase::Extract).in_scope(|| mined_commands(&effects_slab));
+    for effect in commands {
+        let decoded = profile_span(ProfilePhase::Extract).in_scope(|| {
+            if let Err(mismatch) = validate_effect_schema(unsafe { *effect.root() }, &pow_schema) {
+                error!("Mining kernel effect does not match the %pow schema: {mismatch}");
+                return None;
+            }
+            MiningEffect::try_from(effect)
+                .inspect_err(|e| error!("Mining kernel produced a malformed effect: {e}"))
+                .ok()
+        });
+        let Some(effect) = decoded else {
             continue;
         };
-        if effect_cell.head().eq_bytes("command") {
-            handle
-                .poke(MiningWire::Mined.to_wire(), effect)
-                .await
-                .expect("Could not poke nockchain with mined PoW");
+        if let Err(e) = profile_span(ProfilePhase::Verify).in_scope(|| effect.verify(&input)) {
+            error!("Mining kernel produced an effect for the wrong candidate: {e}");
+            continue;
+        }
+        let effect = effect.into_slab();
+        config.events.publish(NodeEvent::ProofFound {
+            length,
+            elapsed: proved_in,
+        });
+        if config.deterministic {
+            info!(
+                "Mined proof with fingerprint {}",
+                proof_fingerprint(&effect)
+            );
+        }
+        handle
+            .poke(MiningWire::Mined.to_wire(), effect)
+            .instrument(profile_span(ProfilePhase::Submit))
+            .await
+            .expect("Could not poke nockchain with mined PoW");
+    }
+    reporter.phase(ProvePhase::Done, None);
+}
+
+/// Resolves once the driver supersedes the attempt, never if it cannot
+async fn superseded_by(superseded: Option<oneshot::Receiver<()>>) {
+    match superseded {
+        // a dropped sender only means the driver has moved on, not that this attempt is stale
+        Some(superseded) if superseded.await.is_ok() => {}
+        _ => std::future::pending().await,
+    }
+}
+
+/// The `%command` effects out of a prove poke's effects, which go back to the node
+pub fn mined_commands(effects: &NounSlab) -> Vec<NounSlab> {
+    effects
+        .to_vec()
+        .into_iter()
+        .filter(|effect| {
+            unsafe { effect.root().as_cell() }.is_ok_and(|cell| cell.head().eq_bytes("command"))
+        })
+        .collect()
+}
+
+/// blake3 hash of a mined effect's jam, equal across runs exactly when the
+/// effects are byte-identical
+pub fn proof_fingerprint(effect: &NounSlab) -> blake3::Hash {
+    blake3::hash(&effect.jam())
+}
+
+/// Proof length of a `[length commitment nonce]` candidate, 0 if it is malformed
+pub(crate) fn candidate_length(candidate: &NounSlab) -> u64 {
+    let root = unsafe { candidate.root() };
+    root.as_cell()
+        .ok()
+        .and_then(|cell| cell.head().as_atom().ok())
+        .and_then(|length| length.as_u64().ok())
+        .unwrap_or(0)
+}
+
+/// Copy of `slab`, built in a slab from `pool`
+fn pooled_clone(pool: &SlabPool, slab: &NounSlab) -> NounSlab {
+    let mut copy = pool.take();
+    copy.copy_into(unsafe { *slab.root() });
+    copy
+}
+
+fn persist_candidate(state_dir: &Path, candidate: &NounSlab) -> std::io::Result<()> {
+    std::fs::create_dir_all(state_dir)?;
+    let path = state_dir.join(CANDIDATE_FILE);
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, candidate.jam())?;
+    std::fs::rename(&tmp, &path)?;
+    info!("Persisted mining candidate to {:?}", path);
+    Ok(())
+}
+
+/// Load and remove the candidate persisted at the last shutdown, if any
+fn take_persisted_candidate(state_dir: &Path) -> Option<NounSlab> {
+    let path = state_dir.join(CANDIDATE_FILE);
+    let jam = std::fs::read(&path).ok()?;
+    if let Err(e) = std::fs::remove_file(&path) {
+        warn!("Could not remove persisted mining candidate: {e}");
+    }
+    let mut slab = NounSlab::new();
+    match slab.cue_into(jam.into()) {
+        Ok(noun) => {
+            slab.set_root(noun);
+            Some(slab)
+        }
+        Err(e) => {
+            warn!("Discarding unreadable persisted mining candidate: {e:?}");
+            None
         }
     }
 }
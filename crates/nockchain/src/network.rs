@@ -0,0 +1,265 @@
+//! Which network a node joins and the consensus constants that go with it.
+//!
+//! [`NetworkMode::Mainnet`] runs the kernel's built-in constants against the
+//! real genesis block. [`NetworkMode::Testnet`] makes up its own genesis
+//! block and starts mining far easier, with epochs of a day, for shared test
+//! networks that still prove with real parameters. [`NetworkMode::Regtest`]
+//! is for integration tests and local multi-node setups: every proof is a
+//! block and proof of work is not checked, so a build with the `dev-proving`
+//! feature mines a block every few seconds.
+//!
+//! Modes other than mainnet hand their [`BlockchainConstants`] to the kernel
+//! with the `%set-constants` command before it is born. The older
+//! `--fakenet` flag only makes up a genesis block, on mainnet it keeps the
+//! kernel's constants.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use ibig::UBig;
+use nockapp::noun::slab::NounSlab;
+use nockvm::noun::{Atom, Noun, D, T};
+use nockvm_macros::tas;
+
+use crate::backend::INSECURE_DEV_PROVING;
+use crate::config;
+use crate::consensus::difficulty::{max_target, DifficultyAlgorithm, EpochRetarget};
+
+/// Seconds in Urbit's `@dr`, which counts 2^-64 second units
+const DR_SECOND_SHIFT: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NetworkMode {
+    #[default]
+    Mainnet,
+    Testnet,
+    Regtest,
+}
+
+/// The kernel's `blockchain-constants`, in the order the kernel expects them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockchainConstants {
+    /// In bits
+    pub max_block_size: u64,
+    pub blocks_per_epoch: u64,
+    /// Seconds an epoch should take
+    pub target_epoch_duration: u64,
+    /// Seconds before a candidate's timestamp is refreshed
+    pub update_candidate_timestamp_interval: u64,
+    /// Seconds into the future a block's timestamp may be
+    pub max_future_timestamp: u64,
+    /// Blocks the median past timestamp is taken over
+    pub min_past_blocks: u64,
+    /// Target of the first epoch, the easiest the chain starts at
+    pub genesis_target: UBig,
+    /// Easiest target any epoch can retarget to
+    pub max_target: UBig,
+    pub check_pow: bool,
+    pub coinbase_timelock_min: u64,
+    /// Length of the proof of work puzzle
+    pub pow_len: u64,
+    pub max_coinbase_split: u64,
+    pub first_month_coinbase_min: u64,
+}
+
+impl Default for BlockchainConstants {
+    /// The kernel's mainnet constants
+    fn default() -> Self {
+        Self {
+            max_block_size: 8_000_000,
+            blocks_per_epoch: 2016,
+            target_epoch_duration: 14 * 24 * 60 * 60,
+            update_candidate_timestamp_interval: 2 * 60,
+            max_future_timestamp: 60 * 120,
+            min_past_blocks: 11,
+            genesis_target: max_target() >> 14,
+            max_target: max_target(),
+            check_pow: true,
+            coinbase_timelock_min: 100,
+            pow_len: 64,
+            max_coinbase_split: 2,
+            first_month_coinbase_min: 4383,
+        }
+    }
+}
+
+impl BlockchainConstants {
+    /// The `%set-constants` command that installs these constants
+    pub fn set_constants_poke(&self) -> NounSlab {
+        let mut slab = NounSlab::new();
+        let interval = UBig::from(self.update_candidate_timestamp_interval) << DR_SECOND_SHIFT;
+        let interval = Atom::from_ubig(&mut slab, &interval).as_noun();
+        let genesis_target = Atom::from_ubig(&mut slab, &self.genesis_target).as_noun();
+        let max_target = Atom::from_ubig(&mut slab, &self.max_target).as_noun();
+        let constants: [Noun; 13] = [
+            D(self.max_block_size),
+            D(self.blocks_per_epoch),
+            D(self.target_epoch_duration),
+            interval,
+            D(self.max_future_timestamp),
+            D(self.min_past_blocks),
+            genesis_target,
+            max_target,
+            // loobeans, 0 is yes
+            D(if self.check_pow { 0 } else { 1 }),
+            D(self.coinbase_timelock_min),
+            D(self.pow_len),
+            D(self.max_coinbase_split),
+            D(self.first_month_coinbase_min),
+        ];
+        let constants = T(&mut slab, &constants);
+        let poke = T(
+            &mut slab,
+            &[D(tas!(b"command")), D(tas!(b"set-constants")), constants],
+        );
+        slab.set_root(poke);
+        slab
+    }
+}
+
+impl NetworkMode {
+    pub const ALL: [NetworkMode; 3] = [
+        NetworkMode::Mainnet,
+        NetworkMode::Testnet,
+        NetworkMode::Regtest,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NetworkMode::Mainnet => "mainnet",
+            NetworkMode::Testnet => "testnet",
+            NetworkMode::Regtest => "regtest",
+        }
+    }
+
+    pub fn constants(&self) -> BlockchainConstants {
+        let mainnet = BlockchainConstants::default();
+        match self {
+            NetworkMode::Mainnet => mainnet,
+            NetworkMode::Testnet => BlockchainConstants {
+                blocks_per_epoch: 144,
+                target_epoch_duration: 24 * 60 * 60,
+                genesis_target: &mainnet.max_target >> 6,
+                coinbase_timelock_min: 10,
+                first_month_coinbase_min: 0,
+                ..mainnet
+            },
+            NetworkMode::Regtest => BlockchainConstants {
+                blocks_per_epoch: 16,
+                target_epoch_duration: 16 * 5,
+                update_candidate_timestamp_interval: 5,
+                genesis_target: mainnet.max_target.clone(),
+                check_pow: false,
+                coinbase_timelock_min: 1,
+                first_month_coinbase_min: 0,
+                ..mainnet
+            },
+        }
+    }
+
+    /// The kernel's retargeting rule under this mode's constants
+    pub fn difficulty(&self) -> Arc<dyn DifficultyAlgorithm> {
+        let constants = self.constants();
+        Arc::new(EpochRetarget {
+            blocks_per_epoch: constants.blocks_per_epoch,
+            target_epoch_duration: constants.target_epoch_duration,
+            min_past_blocks: constants.min_past_blocks,
+            max_target: constants.max_target,
+        })
+    }
+
+    /// Whether genesis is made up locally instead of following a Bitcoin block
+    pub fn fake_genesis(&self) -> bool {
+        *self != NetworkMode::Mainnet
+    }
+
+    /// The `%set-constants` poke for this mode, `None` on mainnet where the
+    /// kernel's own constants apply
+    pub fn set_constants_poke(&self) -> Option<NounSlab> {
+        match self {
+            NetworkMode::Mainnet => None,
+            _ => Some(self.constants().set_constants_poke()),
+        }
+    }
+
+    /// Peers dialed unless `--no-default-peers` is given
+    pub fn backbone_nodes(&self) -> &'static [&'static str] {
+        match self {
+            NetworkMode::Mainnet => config::REALNET_BACKBONE_NODES,
+            NetworkMode::Testnet => config::TESTNET_BACKBONE_NODES,
+            NetworkMode::Regtest => &[],
+        }
+    }
+
+    /// Whether this build can mine blocks this network accepts. Only
+    /// regtest, which does not check proof of work, takes dev-proving
+    /// proofs.
+    pub fn check_build(&self) -> Result<(), String> {
+        if INSECURE_DEV_PROVING && self.constants().check_pow {
+            return Err(format!(
+                "this build proves with INSECURE dev-proving parameters, which {self} rejects; run with --network regtest or build without the dev-proving feature"
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for NetworkMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        NetworkMode::ALL
+            .into_iter()
+            .find(|mode| mode.name() == s)
+            .ok_or_else(|| format!("Unknown network '{s}', expected mainnet, testnet or regtest"))
+    }
+}
+
+impl fmt::Display for NetworkMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nockapp::NounExt;
+
+    use super::*;
+
+    #[test]
+    fn test_network_modes_parse() {
+        for mode in NetworkMode::ALL {
+            assert_eq!(mode.to_string().parse(), Ok(mode));
+        }
+        assert_eq!(" RegTest ".parse(), Ok(NetworkMode::Regtest));
+        assert!("devnet".parse::<NetworkMode>().is_err());
+    }
+
+    #[test]
+    fn test_regtest_makes_every_proof_a_block() {
+        let constants = NetworkMode::Regtest.constants();
+        assert_eq!(constants.genesis_target, constants.max_target);
+        assert!(!constants.check_pow);
+        assert!(NetworkMode::Mainnet.set_constants_poke().is_none());
+        assert!(NetworkMode::Regtest.check_build().is_ok());
+    }
+
+    #[test]
+    fn test_set_constants_poke_shape() {
+        let slab = BlockchainConstants::default().set_constants_poke();
+        let poke = unsafe { *slab.root() }.as_cell().unwrap();
+        assert!(poke.head().eq_bytes(b"command"));
+        let command = poke.tail().as_cell().unwrap();
+        assert!(command.head().eq_bytes(b"set-constants"));
+        // the genesis target is a bignum atom well past 64 bits
+        let mut constants = command.tail();
+        for _ in 0..6 {
+            constants = constants.as_cell().unwrap().tail();
+        }
+        let genesis = constants.as_cell().unwrap().head().as_atom().unwrap();
+        assert!(genesis.as_u64().is_err());
+    }
+}
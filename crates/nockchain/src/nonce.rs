@@ -0,0 +1,235 @@
+//! Where mining attempts get their nonces from.
+//!
+//! By default every attempt proves the nonce the node put in its candidate.
+//! Setting [`crate::mining::MiningConfig::nonces`] to a [`NonceStrategy`]
+//! replaces that nonce with one drawn from the strategy: either counting
+//! through a range with [`SequentialNonces`], which repeats exactly between
+//! runs, drawing from the operating system's RNG with [`RandomNonces`], so
+//! independent miners never search the same nonces, or drawing from a
+//! [`Tip5Rng`] with [`SeededNonces`], which looks random but repeats for the
+//! same seed.
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rand::rngs::OsRng;
+use rand::Rng;
+use zkvm_jetpack::form::math::base::PRIME;
+use zkvm_jetpack::form::math::tip5::Tip5Rng;
+
+use crate::prove_input::DIGEST_BELTS;
+
+/// Hands out nonces for mining attempts
+pub trait NonceStrategy: Send + Sync {
+    /// The nonce for the next attempt. Every belt must be below [`PRIME`].
+    fn next_nonce(&self) -> [u64; DIGEST_BELTS];
+}
+
+/// Counts through a range of nonces, starting over once it is used up.
+/// The count goes in the first belt, the rest are 0.
+#[derive(Debug)]
+pub struct SequentialNonces {
+    range: Range<u64>,
+    next: AtomicU64,
+}
+
+impl SequentialNonces {
+    /// Count through `range`, cut off at [`PRIME`] so every nonce is a belt.
+    /// Panics if that leaves the range empty.
+    pub fn new(range: Range<u64>) -> Self {
+        let range = range.start..range.end.min(PRIME);
+        assert!(!range.is_empty(), "empty nonce range {range:?}");
+        Self {
+            next: AtomicU64::new(range.start),
+            range,
+        }
+    }
+
+    /// Count up from `start` through every belt
+    pub fn starting_at(start: u64) -> Self {
+        Self::new(start..PRIME)
+    }
+}
+
+impl NonceStrategy for SequentialNonces {
+    fn next_nonce(&self) -> [u64; DIGEST_BELTS] {
+        let count = self
+            .next
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                Some(if count + 1 >= self.range.end {
+                    self.range.start
+                } else {
+                    count + 1
+                })
+            })
+            .expect("nonce update never fails");
+        let mut nonce = [0; DIGEST_BELTS];
+        nonce[0] = count;
+        nonce
+    }
+}
+
+/// Draws every belt of every nonce uniformly from [`OsRng`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomNonces;
+
+impl NonceStrategy for RandomNonces {
+    fn next_nonce(&self) -> [u64; DIGEST_BELTS] {
+        std::array::from_fn(|_| OsRng.gen_range(0..PRIME))
+    }
+}
+
+/// Draws every belt of every nonce from a [`Tip5Rng`] seeded with a `u64`
+#[derive(Debug)]
+pub struct SeededNonces {
+    rng: Mutex<Tip5Rng>,
+}
+
+impl SeededNonces {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(Tip5Rng::from_u64(seed)),
+        }
+    }
+}
+
+impl NonceStrategy for SeededNonces {
+    fn next_nonce(&self) -> [u64; DIGEST_BELTS] {
+        let mut rng = self.rng.lock().expect("nonce rng lock poisoned");
+        std::array::from_fn(|_| rng.next_belt())
+    }
+}
+
+/// Which nonces to mine with, as chosen on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonceSource {
+    /// The nonce in each candidate, as the node picked it
+    #[default]
+    Candidate,
+    /// [`SequentialNonces`] from this start
+    Sequential(u64),
+    /// [`RandomNonces`]
+    Random,
+    /// [`SeededNonces`] from this seed
+    Seeded(u64),
+}
+
+impl NonceSource {
+    /// The strategy to put in [`crate::mining::MiningConfig::nonces`]
+    pub fn strategy(&self) -> Option<Arc<dyn NonceStrategy>> {
+        match self {
+            NonceSource::Candidate => None,
+            NonceSource::Sequential(start) => Some(Arc::new(SequentialNonces::starting_at(*start))),
+            NonceSource::Random => Some(Arc::new(RandomNonces)),
+            NonceSource::Seeded(seed) => Some(Arc::new(SeededNonces::new(*seed))),
+        }
+    }
+
+    /// The source to use when mining deterministically with `seed`: random
+    /// nonces would differ between runs, so they are seeded from it instead
+    pub fn deterministic(self, seed: u64) -> Self {
+        match self {
+            NonceSource::Random => NonceSource::Seeded(seed),
+            source => source,
+        }
+    }
+}
+
+impl FromStr for NonceSource {
+    type Err = String;
+
+    /// Parses `candidate`, `random`, `sequential`, `sequential:<start>` or
+    /// `seeded:<seed>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "candidate" => Ok(NonceSource::Candidate),
+            "random" => Ok(NonceSource::Random),
+            "sequential" => Ok(NonceSource::Sequential(0)),
+            other => {
+                if let Some(seed) = other.strip_prefix("seeded:") {
+                    let seed = seed.parse::<u64>().map_err(|e| e.to_string())?;
+                    return Ok(NonceSource::Seeded(seed));
+                }
+                let Some(start) = other.strip_prefix("sequential:") else {
+                    return Err(format!(
+                        "Invalid nonce source '{s}', expected candidate, random, sequential[:start] or seeded:<seed>"
+                    ));
+                };
+                let start = start.parse::<u64>().map_err(|e| e.to_string())?;
+                if start >= PRIME {
+                    return Err(format!("nonce start {start} is not a base field element"));
+                }
+                Ok(NonceSource::Sequential(start))
+            }
+        }
+    }
+}
+
+impl fmt::Display for NonceSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NonceSource::Candidate => write!(f, "candidate"),
+            NonceSource::Sequential(start) => write!(f, "sequential:{start}"),
+            NonceSource::Random => write!(f, "random"),
+            NonceSource::Seeded(seed) => write!(f, "seeded:{seed}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequential_nonces_wrap_around() {
+        let nonces = SequentialNonces::new(5..8);
+        let firsts: Vec<u64> = (0..5).map(|_| nonces.next_nonce()[0]).collect();
+        assert_eq!(firsts, [5, 6, 7, 5, 6]);
+        assert_eq!(nonces.next_nonce()[1..], [0; DIGEST_BELTS - 1]);
+    }
+
+    #[test]
+    fn test_random_nonces_are_belts() {
+        let nonces = RandomNonces;
+        let first = nonces.next_nonce();
+        assert!(first.iter().all(|&belt| belt < PRIME));
+        assert_ne!(first, nonces.next_nonce());
+    }
+
+    #[test]
+    fn test_seeded_nonces_repeat() {
+        let (a, b) = (SeededNonces::new(7), SeededNonces::new(7));
+        let first = a.next_nonce();
+        assert!(first.iter().all(|&belt| belt < PRIME));
+        assert_eq!(first, b.next_nonce());
+        assert_ne!(first, a.next_nonce());
+        assert_ne!(first, SeededNonces::new(8).next_nonce());
+
+        assert_eq!(NonceSource::Random.deterministic(7), NonceSource::Seeded(7));
+        assert_eq!(
+            NonceSource::Sequential(3).deterministic(7),
+            NonceSource::Sequential(3)
+        );
+    }
+
+    #[test]
+    fn test_nonce_source_parses() {
+        for source in [
+            NonceSource::Candidate,
+            NonceSource::Random,
+            NonceSource::Sequential(12),
+            NonceSource::Seeded(99),
+        ] {
+            assert_eq!(source.to_string().parse(), Ok(source));
+        }
+        assert_eq!("sequential".parse(), Ok(NonceSource::Sequential(0)));
+        assert!("sequential:x".parse::<NonceSource>().is_err());
+        assert!(format!("sequential:{PRIME}")
+            .parse::<NonceSource>()
+            .is_err());
+        assert!("lucky".parse::<NonceSource>().is_err());
+    }
+}
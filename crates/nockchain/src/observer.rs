@@ -0,0 +1,215 @@
+//! Hooks around kernel pokes.
+//!
+//! The mining attempt and [`crate::verifier::VerificationService`] report
+//! their prove and verify pokes to the [`PokeObservers`] in their config:
+//! when a poke starts, each effect it produces, and when it completes.
+//! Benchmarks, metrics and progress displays can watch the same pokes
+//! without the code running them knowing about any of them.
+//!
+//! Observers are called inline on the poking task, so they should return
+//! quickly and hand anything slow off to a channel.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use nockapp::noun::slab::NounSlab;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PokeKind {
+    /// A mining kernel proving a candidate
+    Prove,
+    /// A verifier kernel checking a proof
+    Verify,
+}
+
+/// A poke about to be sent to its kernel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PokeStart {
+    pub kind: PokeKind,
+    /// Proof length of the candidate or proof
+    pub length: u64,
+    /// Memory allocated for the poke's cause
+    pub slab_bytes: usize,
+}
+
+/// A poke that has finished, successfully or not
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PokeComplete {
+    pub kind: PokeKind,
+    pub length: u64,
+    /// Time from the poke being sent to it returning, not counting
+    /// kernel loading
+    pub elapsed: Duration,
+    /// Whether the kernel returned at all, rather than failing, being
+    /// cancelled or timing out. A verify poke that finds the proof invalid
+    /// still succeeded.
+    pub ok: bool,
+    /// Number of effects the poke produced. Verifiers hand back a verdict
+    /// rather than effects, so verify pokes always report none.
+    pub effects: usize,
+    /// Memory allocated for the poke's effects
+    pub slab_bytes: usize,
+}
+
+impl PokeComplete {
+    /// A poke that produced nothing
+    pub fn failed(kind: PokeKind, length: u64, elapsed: Duration) -> Self {
+        Self {
+            kind,
+            length,
+            elapsed,
+            ok: false,
+            effects: 0,
+            slab_bytes: 0,
+        }
+    }
+}
+
+/// Something watching kernel pokes. Every method does nothing by default.
+pub trait PokeObserver: Send + Sync {
+    fn on_start(&self, _start: &PokeStart) {}
+
+    /// One effect of a finished poke, before [`Self::on_complete`]
+    fn on_effect(&self, _kind: PokeKind, _effect: &NounSlab) {}
+
+    fn on_complete(&self, _complete: &PokeComplete) {}
+}
+
+/// The observers a mining or verification config reports to, in the order
+/// they were added
+#[derive(Clone, Default)]
+pub struct PokeObservers(Vec<Arc<dyn PokeObserver>>);
+
+impl PokeObservers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also report to `observer`
+    pub fn with(mut self, observer: Arc<dyn PokeObserver>) -> Self {
+        self.push(observer);
+        self
+    }
+
+    pub fn push(&mut self, observer: Arc<dyn PokeObserver>) {
+        self.0.push(observer);
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// True if nobody is watching, so callers can skip work only observers need
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Hand every effect of `effects` to [`PokeObserver::on_effect`], then
+    /// report the poke complete
+    pub fn finished(&self, kind: PokeKind, length: u64, elapsed: Duration, effects: &NounSlab) {
+        if self.is_empty() {
+            return;
+        }
+        let list = effects.to_vec();
+        for effect in &list {
+            self.on_effect(kind, effect);
+        }
+        self.on_complete(&PokeComplete {
+            kind,
+            length,
+            elapsed,
+            ok: true,
+            effects: list.len(),
+            slab_bytes: effects.allocated_bytes(),
+        });
+    }
+}
+
+impl PokeObserver for PokeObservers {
+    fn on_start(&self, start: &PokeStart) {
+        for observer in &self.0 {
+            observer.on_start(start);
+        }
+    }
+
+    fn on_effect(&self, kind: PokeKind, effect: &NounSlab) {
+        for observer in &self.0 {
+            observer.on_effect(kind, effect);
+        }
+    }
+
+    fn on_complete(&self, complete: &PokeComplete) {
+        for observer in &self.0 {
+            observer.on_complete(complete);
+        }
+    }
+}
+
+impl std::fmt::Debug for PokeObservers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PokeObservers").field(&self.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use nockvm::noun::{D, T};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl PokeObserver for Recorder {
+        fn on_start(&self, start: &PokeStart) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("start {}", start.length));
+        }
+
+        fn on_effect(&self, _kind: PokeKind, _effect: &NounSlab) {
+            self.0.lock().unwrap().push("effect".to_string());
+        }
+
+        fn on_complete(&self, complete: &PokeComplete) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("complete {} {}", complete.ok, complete.effects));
+        }
+    }
+
+    #[test]
+    fn test_observers_fan_out_in_order() {
+        let first = Arc::new(Recorder::default());
+        let second = Arc::new(Recorder::default());
+        let observers = PokeObservers::new()
+            .with(first.clone())
+            .with(second.clone());
+
+        let mut effects = NounSlab::new();
+        let list = T(&mut effects, &[D(1), D(2), D(0)]);
+        effects.set_root(list);
+        observers.on_start(&PokeStart {
+            kind: PokeKind::Prove,
+            length: 4,
+            slab_bytes: 0,
+        });
+        observers.finished(PokeKind::Prove, 4, Duration::ZERO, &effects);
+        observers.on_complete(&PokeComplete::failed(PokeKind::Verify, 4, Duration::ZERO));
+
+        let expected = [
+            "start 4",
+            "effect",
+            "effect",
+            "complete true 2",
+            "complete false 0",
+        ];
+        for recorder in [first, second] {
+            assert_eq!(*recorder.0.lock().unwrap(), expected);
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Read-only views of what the miner is doing.
+//!
+//! The node kernel answers the `[%mining ~]` peek with the candidate it hands
+//! the miner. How many prove pokes have run and the parameters they run with
+//! live on our side, in [`MiningConfig`]. [`MinerPeek`] puts the two together
+//! so the status RPC and tests can ask for the miner's state rather than
+//! piecing it together from the effects of past pokes.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use nockapp::driver::NockAppHandle;
+use nockapp::noun::slab::NounSlab;
+use nockapp::noun::AtomExt;
+use nockapp::utils::make_tas;
+use nockapp::utils::scry::ScryResult;
+use nockapp::NockAppError;
+use nockvm::noun::{Noun, D, T};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::effect::{self, MiningEffectError};
+use crate::mining::MiningConfig;
+use crate::observer::{PokeComplete, PokeKind, PokeObserver, PokeStart};
+use crate::prove_input::DIGEST_BELTS;
+
+#[derive(Debug, Error)]
+pub enum PeekError {
+    #[error("kernel does not answer the {0} peek")]
+    BadPath(&'static str),
+    #[error("kernel crashed answering the {0} peek")]
+    Crashed(&'static str),
+    #[error("kernel answered the {0} peek with something that is not a scry result")]
+    Invalid(&'static str),
+    #[error("malformed {peek} peek: {source}")]
+    Malformed {
+        peek: &'static str,
+        source: MiningEffectError,
+    },
+    #[error("peek failed: {0:?}")]
+    App(#[from] NockAppError),
+}
+
+/// The candidate block the kernel is mining on, from the `[%mining ~]` peek
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CandidateState {
+    /// Whether the kernel builds candidates at all, see `%enable-mining`
+    pub mining: bool,
+    pub height: u64,
+    /// Base58 id of the block the candidate builds on
+    pub parent: String,
+    pub block_commitment: [u64; DIGEST_BELTS],
+    /// Nonce of the latest `%mine` effect
+    pub nonce: [u64; DIGEST_BELTS],
+    pub tx_count: u64,
+    /// Number of coinbase locks the reward is split between
+    pub locks: u64,
+}
+
+/// Path of the `[%mining ~]` peek
+pub fn mining_path() -> NounSlab {
+    let mut slab = NounSlab::new();
+    let tag = make_tas(&mut slab, "mining").as_noun();
+    let path = T(&mut slab, &[tag, D(0)]);
+    slab.set_root(path);
+    slab
+}
+
+/// Decode the kernel's answer to [`mining_path`]. `None` means the kernel has
+/// no candidate, which is the case until a mining key is set.
+pub fn decode_candidate(result: &NounSlab) -> Result<Option<CandidateState>, PeekError> {
+    const PEEK: &str = "mining";
+    match ScryResult::from(unsafe { result.root() }) {
+        ScryResult::Some(state) => candidate_state(state)
+            .map(Some)
+            .map_err(|source| PeekError::Malformed { peek: PEEK, source }),
+        ScryResult::Nothing => Ok(None),
+        ScryResult::BadPath => Err(PeekError::BadPath(PEEK)),
+        ScryResult::Invalid => Err(PeekError::Invalid(PEEK)),
+    }
+}
+
+/// Decode `[mining height parent commitment nonce txs locks]`
+fn candidate_state(noun: Noun) -> Result<CandidateState, MiningEffectError> {
+    let (mining, rest) = effect::split(noun, "mining flag")?;
+    let (height, rest) = effect::split(rest, "height")?;
+    let (parent, rest) = effect::split(rest, "parent")?;
+    let (commitment, rest) = effect::split(rest, "block commitment")?;
+    let (nonce, rest) = effect::split(rest, "nonce")?;
+    let (txs, locks) = effect::split(rest, "tx count")?;
+    let parent = parent
+        .as_atom()
+        .ok()
+        .and_then(|atom| atom.into_string().ok())
+        .ok_or(MiningEffectError::NotAnAtom("parent"))?;
+    Ok(CandidateState {
+        // loobeans: %.y is 0
+        mining: effect::atom(mining, "mining flag")? == 0,
+        height: effect::atom(height, "height")?,
+        parent,
+        block_commitment: effect::digest(commitment, "block commitment")?,
+        nonce: effect::digest(nonce, "nonce")?,
+        tx_count: effect::atom(txs, "tx count")?,
+        locks: effect::atom(locks, "lock count")?,
+    })
+}
+
+/// Peek the candidate the kernel behind `handle` is mining on
+pub async fn peek_candidate(handle: &NockAppHandle) -> Result<Option<CandidateState>, PeekError> {
+    match handle.peek(mining_path()).await? {
+        Some(result) => decode_candidate(&result),
+        None => Err(PeekError::Crashed("mining")),
+    }
+}
+
+/// Prove pokes run so far, counted by [`AttemptCounter`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AttemptStats {
+    pub started: u64,
+    /// Pokes that returned effects, whether or not the proof met the target
+    pub succeeded: u64,
+    /// Pokes that failed, ran out of memory or were cancelled
+    pub failed: u64,
+}
+
+impl AttemptStats {
+    /// Pokes started but not yet finished
+    pub fn running(&self) -> u64 {
+        self.started
+            .saturating_sub(self.succeeded)
+            .saturating_sub(self.failed)
+    }
+}
+
+/// Counts the prove pokes of the mining attempts it observes.
+///
+/// Attempts that give up before their poke starts, e.g. because a newer
+/// template superseded them while the kernel loaded, are not counted.
+#[derive(Debug, Default)]
+pub struct AttemptCounter {
+    started: AtomicU64,
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl AttemptCounter {
+    pub fn stats(&self) -> AttemptStats {
+        AttemptStats {
+            started: self.started.load(Ordering::Relaxed),
+            succeeded: self.succeeded.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl PokeObserver for AttemptCounter {
+    fn on_start(&self, start: &PokeStart) {
+        if start.kind == PokeKind::Prove {
+            self.started.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn on_complete(&self, complete: &PokeComplete) {
+        if complete.kind != PokeKind::Prove {
+            return;
+        }
+        if complete.ok {
+            self.succeeded.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The parts of a [`MiningConfig`] worth reporting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MiningParams {
+    /// `auto` or a size in words, as `--mining-stack-size` takes it
+    pub stack_size: String,
+    pub extra_nonce: u64,
+    pub deterministic: bool,
+    /// Whether block templates are mined alongside the kernel's candidates
+    pub templates: bool,
+    /// Whether a nonce strategy replaces the nonces the kernel picks
+    pub nonce_strategy: bool,
+    pub stall_timeout_secs: Option<u64>,
+    pub max_restarts: u32,
+}
+
+impl From<&MiningConfig> for MiningParams {
+    fn from(config: &MiningConfig) -> Self {
+        Self {
+            stack_size: config.stack_size.to_string(),
+            extra_nonce: config.extra_nonce,
+            deterministic: config.deterministic,
+            templates: config.templates.is_some(),
+            nonce_strategy: config.nonces.is_some(),
+            stall_timeout_secs: config.watchdog.stall_timeout.map(|t| t.as_secs()),
+            max_restarts: config.watchdog.max_restarts,
+        }
+    }
+}
+
+/// Everything [`MinerPeek::state`] knows about the miner
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MinerState {
+    /// `None` until the kernel has a mining key to build candidates for
+    pub candidate: Option<CandidateState>,
+    pub attempts: AttemptStats,
+    pub params: MiningParams,
+}
+
+/// Read-only handle on the miner's state, see the module docs
+#[derive(Debug, Clone)]
+pub struct MinerPeek {
+    attempts: Arc<AttemptCounter>,
+    params: MiningParams,
+}
+
+impl MinerPeek {
+    /// Watch the attempts run with `config`. Call this before the config is
+    /// handed to [`crate::mining::create_mining_driver`], it adds an observer.
+    pub fn attach(config: &mut MiningConfig) -> Self {
+        let attempts = Arc::new(AttemptCounter::default());
+        config.observers.push(attempts.clone());
+        Self {
+            attempts,
+            params: MiningParams::from(&*config),
+        }
+    }
+
+    pub fn attempts(&self) -> AttemptStats {
+        self.attempts.stats()
+    }
+
+    pub fn params(&self) -> &MiningParams {
+        &self.params
+    }
+
+    /// Peek the candidate from the kernel behind `handle` and add what we
+    /// know on this side
+    pub async fn state(&self, handle: &NockAppHandle) -> Result<MinerState, PeekError> {
+        Ok(MinerState {
+            candidate: peek_candidate(handle).await?,
+            attempts: self.attempts(),
+            params: self.params.clone(),
+        })
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use nockapp::kernel::form::Kernel;
+use nockapp::noun::slab::NounSlab;
+use nockapp::wire::WireRepr;
+use nockapp::CrownError;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::oneshot;
+use tracing::warn;
+
+use crate::metrics::metrics;
+
+#[derive(Debug, Error)]
+pub enum PokeError {
+    #[error("poke timed out after {0:?}")]
+    TimedOut(Duration),
+    #[error("kernel error: {0}")]
+    Kernel(#[from] CrownError),
+}
+
+/// Poke `kernel`, interrupting the computation if it runs longer than `timeout`.
+///
+/// On timeout the kernel's cancel token is fired and we wait for the serf to
+/// unwind before returning [`PokeError::TimedOut`], so the kernel is idle and
+/// ready for the next poke once this returns.
+pub async fn poke_with_timeout(
+    kernel: &Kernel,
+    wire: WireRepr,
+    slab: NounSlab,
+    timeout: Duration,
+) -> Result<NounSlab, PokeError> {
+    let cancel_token = kernel.cancel_token();
+    let poke = kernel.poke(wire, slab);
+    tokio::pin!(poke);
+
+    tokio::select! {
+        res = &mut poke => Ok(res?),
+        _ = tokio::time::sleep(timeout) => {
+            if !cancel_token.cancel() {
+                // the poke finished between the timer firing and the cancellation
+                return Ok(poke.await?);
+            }
+            warn!("poke exceeded {:?}, cancelled", timeout);
+            if let Ok(effects) = poke.await {
+                // the serf completed the event before noticing the cancellation
+                return Ok(effects);
+            }
+            Err(PokeError::TimedOut(timeout))
+        }
+    }
+}
+
+/// Which of the pokes waiting on a [`PokeQueue`] goes first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PokePriority {
+    /// Checking a proof for a caller waiting on the verdict
+    Verify,
+    /// Proving a mining candidate
+    Mine,
+}
+
+/// Pokes waiting on a [`PokeQueue`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueDepth {
+    pub verify: usize,
+    pub mine: usize,
+    /// Priority of the poke holding the kernel, if any
+    pub running: Option<PokePriority>,
+}
+
+/// Verify pokes let past a waiting mine poke before it gets its turn
+pub const DEFAULT_VERIFY_BURST: usize = 4;
+
+/// Serializes the pokes of one kernel.
+///
+/// Callers [`acquire`](Self::acquire) a [`PokeTurn`] and poke while they hold
+/// it. Waiting verify pokes go before waiting mine pokes, so a verification
+/// waits for at most the prove already running rather than for every prove
+/// queued ahead of it. Once `verify_burst` verify pokes in a row have gone
+/// past a waiting mine poke, the mine poke gets the next turn, so a stream of
+/// verifications cannot stall mining either. Within a priority, turns go in
+/// the order they were asked for.
+///
+/// A caller that stops waiting, e.g. because its deadline passed, gives up
+/// its place in the queue when the [`acquire`](Self::acquire) future is
+/// dropped. Queue depths are published as the `nockchain.poke_queue.*`
+/// gauges and the time each turn waited as a timing of its priority.
+#[derive(Clone)]
+pub struct PokeQueue {
+    state: Arc<Mutex<QueueState>>,
+}
+
+/// Permission to poke the kernel behind a [`PokeQueue`], handed to the next
+/// waiter when dropped
+pub struct PokeTurn {
+    state: Arc<Mutex<QueueState>>,
+    priority: PokePriority,
+    waited: Duration,
+}
+
+struct QueueState {
+    verify_burst: usize,
+    running: Option<PokePriority>,
+    verify: VecDeque<Waiter>,
+    mine: VecDeque<Waiter>,
+    // verify pokes that have gone past a waiting mine poke since one last ran
+    streak: usize,
+    next_id: u64,
+}
+
+struct Waiter {
+    id: u64,
+    wake: oneshot::Sender<()>,
+}
+
+/// A place in the queue, given up if dropped before its turn comes
+struct Waiting {
+    state: Arc<Mutex<QueueState>>,
+    priority: PokePriority,
+    id: u64,
+    woken: oneshot::Receiver<()>,
+    granted: bool,
+}
+
+impl PokeQueue {
+    /// A queue letting at most `verify_burst` verify pokes in a row past a
+    /// waiting mine poke. With `0` verify pokes never go first.
+    pub fn new(verify_burst: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(QueueState {
+                verify_burst,
+                running: None,
+                verify: VecDeque::new(),
+                mine: VecDeque::new(),
+                streak: 0,
+                next_id: 0,
+            })),
+        }
+    }
+
+    /// Wait for a turn to poke at `priority`
+    pub async fn acquire(&self, priority: PokePriority) -> PokeTurn {
+        let asked = Instant::now();
+        let mut waiting = {
+            let mut state = lock(&self.state);
+            if state.running.is_none() {
+                state.grant(priority);
+                return self.turn(priority, asked);
+            }
+            let id = state.next_id;
+            state.next_id += 1;
+            let (wake, woken) = oneshot::channel();
+            state.waiting(priority).push_back(Waiter { id, wake });
+            state.publish();
+            Waiting {
+                state: self.state.clone(),
+                priority,
+                id,
+                woken,
+                granted: false,
+            }
+        };
+        // the sender is only dropped unsent once this waiter is gone
+        let _ = (&mut waiting.woken).await;
+        waiting.granted = true;
+        self.turn(priority, asked)
+    }
+
+    pub fn depth(&self) -> QueueDepth {
+        lock(&self.state).depth()
+    }
+
+    fn turn(&self, priority: PokePriority, asked: Instant) -> PokeTurn {
+        let waited = asked.elapsed();
+        let metrics = metrics();
+        match priority {
+            PokePriority::Verify => metrics.poke_queue_verify_wait.add_timing(&waited),
+            PokePriority::Mine => metrics.poke_queue_mine_wait.add_timing(&waited),
+        }
+        PokeTurn {
+            state: self.state.clone(),
+            priority,
+            waited,
+        }
+    }
+}
+
+impl Default for PokeQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_VERIFY_BURST)
+    }
+}
+
+impl std::fmt::Debug for PokeQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PokeQueue").field(&self.depth()).finish()
+    }
+}
+
+impl PokeTurn {
+    pub fn priority(&self) -> PokePriority {
+        self.priority
+    }
+
+    /// Time spent waiting for this turn
+    pub fn waited(&self) -> Duration {
+        self.waited
+    }
+}
+
+impl Drop for PokeTurn {
+    fn drop(&mut self) {
+        lock(&self.state).hand_on();
+    }
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        if self.granted {
+            return;
+        }
+        let mut state = lock(&self.state);
+        let queue = state.waiting(self.priority);
+        match queue.iter().position(|waiter| waiter.id == self.id) {
+            Some(index) => {
+                queue.remove(index);
+                state.publish();
+            }
+            // the turn was handed to us as we were dropped, pass it on
+            None if self.woken.try_recv().is_ok() => state.hand_on(),
+            None => {}
+        }
+    }
+}
+
+impl QueueState {
+    fn waiting(&mut self, priority: PokePriority) -> &mut VecDeque<Waiter> {
+        match priority {
+            PokePriority::Verify => &mut self.verify,
+            PokePriority::Mine => &mut self.mine,
+        }
+    }
+
+    fn grant(&mut self, priority: PokePriority) {
+        match priority {
+            PokePriority::Verify if !self.mine.is_empty() => self.streak += 1,
+            PokePriority::Verify => {}
+            PokePriority::Mine => self.streak = 0,
+        }
+        self.running = Some(priority);
+    }
+
+    /// Give the kernel to the next waiter, if any
+    fn hand_on(&mut self) {
+        self.running = None;
+        loop {
+            let verify_next = !self.verify.is_empty()
+                && (self.mine.is_empty() || self.streak < self.verify_burst);
+            let priority = if verify_next {
+                PokePriority::Verify
+            } else {
+                PokePriority::Mine
+            };
+            let Some(waiter) = self.waiting(priority).pop_front() else {
+                break;
+            };
+            if waiter.wake.send(()).is_ok() {
+                self.grant(priority);
+                break;
+            }
+        }
+        self.publish();
+    }
+
+    fn depth(&self) -> QueueDepth {
+        QueueDepth {
+            verify: self.verify.len(),
+            mine: self.mine.len(),
+            running: self.running,
+        }
+    }
+
+    fn publish(&self) {
+        let metrics = metrics();
+        metrics
+            .poke_queue_verify_depth
+            .swap(self.verify.len() as f64);
+        metrics.poke_queue_mine_depth.swap(self.mine.len() as f64);
+    }
+}
+
+fn lock(state: &Mutex<QueueState>) -> MutexGuard<'_, QueueState> {
+    // the state is consistent between statements, a panicking holder cannot break it
+    state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,223 @@
+//! Crediting pool workers for proofs that fall short of a block.
+//!
+//! A pool hands every worker the block commitment to mine with the worker's
+//! own extra nonce mixed in (see [`crate::prove_input::mix_extra_nonce`]),
+//! along with a share target well above the network target. Workers submit
+//! every `%pow` effect whose proof hash is below the share target.
+//! [`ShareValidator::accept`] checks such a submission against its
+//! [`ShareJob`], verifies the proof with the [`VerificationService`], and
+//! compares the proof hash the verifier kernel computed, not the one the
+//! worker claims, against the share target. A share that also meets the
+//! network target is a block.
+//!
+//! Each puzzle is credited once: a worker could otherwise resubmit the same
+//! commitment and nonce with a freshly randomized proof.
+//!
+//! The pool talks to its workers in [`PoolMessage`]s, one JSON object per
+//! line. Share targets are retargeted per worker by [`crate::vardiff`].
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ibig::UBig;
+use nockapp::noun::slab::NounSlab;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::effect::{MiningEffect, MiningEffectError};
+use crate::prove_input::{ProveBlockInput, DIGEST_BELTS};
+use crate::verifier::{VerificationService, VerifyError};
+
+#[derive(Debug, Error)]
+pub enum ShareError {
+    #[error("malformed share: {0}")]
+    Malformed(#[from] MiningEffectError),
+    #[error("share proves the wrong {0}")]
+    WrongJob(&'static str),
+    #[error("proof hash {pow:x} is above the share target {target:x}")]
+    AboveTarget { pow: UBig, target: UBig },
+    #[error("puzzle was already credited")]
+    Duplicate,
+    #[error("proof does not verify")]
+    Invalid,
+    #[error("share claims proof hash {claimed:x} but the proof hashes to {actual:x}")]
+    PowMismatch { claimed: UBig, actual: UBig },
+    #[error("could not verify share: {0}")]
+    Verify(#[from] VerifyError),
+}
+
+/// What the pool sends a worker
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PoolMessage {
+    /// Submit proofs hashing at or below this target from now on
+    SetTarget {
+        #[serde(with = "crate::codec::target")]
+        target: UBig,
+    },
+    ShareAccepted {
+        #[serde(with = "crate::codec::target")]
+        pow: UBig,
+        block: bool,
+    },
+    ShareRejected {
+        reason: String,
+    },
+}
+
+impl PoolMessage {
+    /// The reply to a share submission
+    pub fn share_result(result: &Result<AcceptedShare, ShareError>) -> Self {
+        match result {
+            Ok(share) => PoolMessage::ShareAccepted {
+                pow: share.pow.clone(),
+                block: share.block,
+            },
+            Err(e) => PoolMessage::ShareRejected {
+                reason: e.to_string(),
+            },
+        }
+    }
+}
+
+/// The work a pool handed to one worker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareJob {
+    /// Commitment the worker proves, with its extra nonce mixed in
+    pub commitment: [u64; DIGEST_BELTS],
+    pub length: u64,
+    /// Proof hashes at or below this earn a share
+    pub share_target: UBig,
+    /// Proof hashes at or below this are blocks
+    pub network_target: UBig,
+}
+
+impl ShareJob {
+    /// The job for a worker mining `input`, whose extra nonce is the worker's
+    pub fn new(input: &ProveBlockInput, share_target: UBig, network_target: UBig) -> Self {
+        Self {
+            commitment: input.mined_commitment(),
+            length: input.length(),
+            share_target,
+            network_target,
+        }
+    }
+}
+
+/// A share that verified and met its target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcceptedShare {
+    /// The proof hash, as computed by the verifier
+    pub pow: UBig,
+    pub nonce: [u64; DIGEST_BELTS],
+    /// Whether the proof also meets the network target
+    pub block: bool,
+}
+
+type PuzzleKey = ([u64; DIGEST_BELTS], [u64; DIGEST_BELTS]);
+
+/// Checks share submissions and remembers which puzzles were credited
+pub struct ShareValidator {
+    verifier: Arc<VerificationService>,
+    deadline: Option<Duration>,
+    credited: Mutex<HashSet<PuzzleKey>>,
+}
+
+impl ShareValidator {
+    pub fn new(verifier: Arc<VerificationService>) -> Self {
+        Self {
+            verifier,
+            deadline: None,
+            credited: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Give every share this long to verify instead of the service default
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Check a `%pow` effect submitted for `job`
+    pub async fn accept(
+        &self,
+        job: &ShareJob,
+        effect: NounSlab,
+    ) -> Result<AcceptedShare, ShareError> {
+        let effect = MiningEffect::try_from(effect)?;
+        if effect.block_commitment != job.commitment
+            || effect.puzzle.block_commitment != job.commitment
+        {
+            return Err(ShareError::WrongJob("block commitment"));
+        }
+        // the worker picks its own nonces, the proof just has to agree
+        if effect.puzzle.nonce != effect.nonce {
+            return Err(ShareError::WrongJob("nonce"));
+        }
+        if effect.puzzle.length != job.length {
+            return Err(ShareError::WrongJob("length"));
+        }
+        // turn away shares that do not even claim to meet the target before verifying
+        check_target(&effect.pow, &job.share_target)?;
+
+        let key = (job.commitment, effect.nonce);
+        if !self.credited().insert(key) {
+            return Err(ShareError::Duplicate);
+        }
+        let accepted = self.verify(job, &effect).await;
+        if accepted.is_err() {
+            // a rejected share has not used up its puzzle
+            self.credited().remove(&key);
+        }
+        accepted
+    }
+
+    /// Forget credited puzzles, e.g. once the pool moves to a new block
+    pub fn clear(&self) {
+        self.credited().clear();
+    }
+
+    async fn verify(
+        &self,
+        job: &ShareJob,
+        effect: &MiningEffect,
+    ) -> Result<AcceptedShare, ShareError> {
+        let verdict = self
+            .verifier
+            .verify(effect.proof_slab(), self.deadline)
+            .await?;
+        let pow = match verdict.pow {
+            Some(pow) if verdict.valid => pow,
+            _ => return Err(ShareError::Invalid),
+        };
+        if pow != effect.pow {
+            return Err(ShareError::PowMismatch {
+                claimed: effect.pow.clone(),
+                actual: pow,
+            });
+        }
+        check_target(&pow, &job.share_target)?;
+        Ok(AcceptedShare {
+            block: pow <= job.network_target,
+            pow,
+            nonce: effect.nonce,
+        })
+    }
+
+    fn credited(&self) -> std::sync::MutexGuard<'_, HashSet<PuzzleKey>> {
+        self.credited
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn check_target(pow: &UBig, target: &UBig) -> Result<(), ShareError> {
+    if pow > target {
+        return Err(ShareError::AboveTarget {
+            pow: pow.clone(),
+            target: target.clone(),
+        });
+    }
+    Ok(())
+}
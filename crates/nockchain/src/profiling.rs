@@ -0,0 +1,219 @@
+//! Per-phase timing spans for mining attempts and proof verifications.
+//!
+//! Every mining attempt is wrapped in a `mining_attempt` span, and every
+//! verification in a `verification` span, with a child span for each
+//! [`ProfilePhase`] it goes through. They are ordinary `tracing` spans under
+//! [`PROFILE_TARGET`], so any span-based layer picks them up unchanged: the
+//! OTLP exporter of `nockapp::observability` sends them to a tracing backend
+//! as a latency breakdown of each attempt, and profilers (tracing-flame, a
+//! pprof bridge, tokio-console) see them too. A handful of spans per proof
+//! costs nothing next to proving it.
+//!
+//! For a quick per-run profile without an external profiler, build with the
+//! `profiling` feature, install a [`RunProfile`] layer and dump it once the
+//! run is over:
+//!
+//! ```ignore
+//! use tracing_subscriber::prelude::*;
+//!
+//! let profile = nockchain::profiling::RunProfile::new();
+//! tracing_subscriber::registry().with(profile.clone()).init();
+//! // ... mine ...
+//! profile.dump("mining.folded")?;
+//! // inferno-flamegraph < mining.folded > mining.svg
+//! ```
+
+use tracing::field::Empty;
+use tracing::Span;
+
+/// Target of every profiling span, for filtering
+pub const PROFILE_TARGET: &str = "nockchain::profile";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfilePhase {
+    /// A whole mining attempt, the parent of the mining phases. Records the
+    /// candidate's `length`.
+    Attempt,
+    /// Booting the prover or verifier kernel and its hot state
+    LoadKernel,
+    /// The prove or verify poke
+    Poke,
+    /// Pulling `%pow` effects out of the poke's effects and decoding them
+    Extract,
+    /// Checking a decoded effect against its candidate
+    Verify,
+    /// Poking a mined proof back into the node
+    Submit,
+    /// A whole proof verification, the parent of the verifier's phases.
+    /// Records the proof's `length` and whether it was `valid`.
+    Verification,
+    /// Waiting for a free verifier kernel
+    Admit,
+}
+
+impl ProfilePhase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProfilePhase::Attempt => "mining_attempt",
+            ProfilePhase::LoadKernel => "load_kernel",
+            ProfilePhase::Poke => "poke",
+            ProfilePhase::Extract => "extract",
+            ProfilePhase::Verify => "verify",
+            ProfilePhase::Submit => "submit",
+            ProfilePhase::Verification => "verification",
+            ProfilePhase::Admit => "admit",
+        }
+    }
+}
+
+/// A span for `phase`
+pub fn profile_span(phase: ProfilePhase) -> Span {
+    // span names must be static, hence one macro call per phase
+    match phase {
+        ProfilePhase::Attempt => {
+            tracing::info_span!(target: PROFILE_TARGET, "mining_attempt", length = Empty)
+        }
+        ProfilePhase::LoadKernel => tracing::info_span!(target: PROFILE_TARGET, "load_kernel"),
+        ProfilePhase::Poke => tracing::info_span!(target: PROFILE_TARGET, "poke"),
+        ProfilePhase::Extract => tracing::info_span!(target: PROFILE_TARGET, "extract"),
+        ProfilePhase::Verify => tracing::info_span!(target: PROFILE_TARGET, "verify"),
+        ProfilePhase::Submit => tracing::info_span!(target: PROFILE_TARGET, "submit"),
+        ProfilePhase::Verification => tracing::info_span!(
+            target: PROFILE_TARGET,
+            "verification",
+            length = Empty,
+            valid = Empty
+        ),
+        ProfilePhase::Admit => tracing::info_span!(target: PROFILE_TARGET, "admit"),
+    }
+}
+
+#[cfg(feature = "profiling")]
+pub use run_profile::{PhaseTiming, RunProfile};
+
+#[cfg(feature = "profiling")]
+mod run_profile {
+    use std::collections::BTreeMap;
+    use std::io::Write;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use tracing::span::{Attributes, Id};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, Layer};
+    use tracing_subscriber::registry::LookupSpan;
+
+    use super::PROFILE_TARGET;
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct PhaseTiming {
+        pub count: u64,
+        /// Wall-clock time from span creation to close, summed over `count`
+        pub total: Duration,
+    }
+
+    /// A [`Layer`] totting up time spent in each profiling span, keyed by
+    /// its `;`-separated stack of profiling span names
+    #[derive(Debug, Clone, Default)]
+    pub struct RunProfile {
+        stacks: Arc<Mutex<BTreeMap<String, PhaseTiming>>>,
+    }
+
+    struct Started(Instant);
+
+    impl RunProfile {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Timings so far, by stack
+        pub fn timings(&self) -> BTreeMap<String, PhaseTiming> {
+            self.stacks.lock().expect("profile lock poisoned").clone()
+        }
+
+        /// Write the profile in folded-stack format, one `stack micros` line per
+        /// stack, as read by inferno and flamegraph.pl
+        pub fn write_folded<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+            for (stack, timing) in self.timings() {
+                writeln!(writer, "{stack} {}", timing.total.as_micros())?;
+            }
+            Ok(())
+        }
+
+        /// Dump the profile to `path`, see [`RunProfile::write_folded`]
+        pub fn dump(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+            let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+            self.write_folded(&mut file)?;
+            file.flush()
+        }
+    }
+
+    impl<S> Layer<S> for RunProfile
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            if attrs.metadata().target() != PROFILE_TARGET {
+                return;
+            }
+            if let Some(span) = ctx.span(id) {
+                span.extensions_mut().insert(Started(Instant::now()));
+            }
+        }
+
+        fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+            let Some(span) = ctx.span(&id) else {
+                return;
+            };
+            let Some(elapsed) = span.extensions().get::<Started>().map(|s| s.0.elapsed()) else {
+                return;
+            };
+            let stack = span
+                .scope()
+                .from_root()
+                .filter(|span| span.metadata().target() == PROFILE_TARGET)
+                .map(|span| span.name())
+                .collect::<Vec<_>>()
+                .join(";");
+            let mut stacks = self.stacks.lock().expect("profile lock poisoned");
+            let timing = stacks.entry(stack).or_default();
+            timing.count += 1;
+            timing.total += elapsed;
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use tracing_subscriber::prelude::*;
+
+        use super::*;
+        use crate::profiling::{profile_span, ProfilePhase};
+
+        #[test]
+        fn test_run_profile_records_nested_phases() {
+            let profile = RunProfile::new();
+            let subscriber = tracing_subscriber::registry().with(profile.clone());
+            tracing::subscriber::with_default(subscriber, || {
+                let _attempt = profile_span(ProfilePhase::Attempt).entered();
+                for _ in 0..2 {
+                    let _verify = profile_span(ProfilePhase::Verify).entered();
+                }
+                // spans outside the profile target are not recorded
+                let _other = tracing::info_span!("unrelated").entered();
+            });
+
+            let timings = profile.timings();
+            assert_eq!(
+                timings.keys().collect::<Vec<_>>(),
+                ["mining_attempt", "mining_attempt;verify"]
+            );
+            assert_eq!(timings["mining_attempt;verify"].count, 2);
+
+            let mut folded = Vec::new();
+            profile.write_folded(&mut folded).unwrap();
+            let folded = String::from_utf8(folded).unwrap();
+            assert!(folded.lines().all(|line| line.rsplit_once(' ').is_some()));
+        }
+    }
+}
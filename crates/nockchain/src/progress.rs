@@ -0,0 +1,129 @@
+//! Progress reporting for long-running prove pokes.
+//!
+//! The kernel does not emit anything until a proof is finished, so progress
+//! inside the proving phase is estimated from elapsed time against an expected
+//! duration (e.g. from a previous run at the same length). Phase transitions
+//! are reported as they happen.
+
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// How often a ticking phase reports progress
+pub const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProvePhase {
+    /// Booting the prover kernel and its hot state
+    LoadingKernel,
+    /// The prove poke is running
+    Proving,
+    /// Effects are being handed back to the node
+    Submitting,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressEvent {
+    pub phase: ProvePhase,
+    /// Estimated completion of the current phase, if an expected duration is known.
+    /// Never reaches 100 before the phase actually completes.
+    pub percent: Option<f32>,
+    /// Time since proving started
+    pub elapsed: Duration,
+}
+
+pub type ProgressSender = mpsc::UnboundedSender<ProgressEvent>;
+pub type ProgressReceiver = mpsc::UnboundedReceiver<ProgressEvent>;
+
+pub fn progress_channel() -> (ProgressSender, ProgressReceiver) {
+    mpsc::unbounded_channel()
+}
+
+/// Emits [`ProgressEvent`]s for one prove attempt
+pub struct ProgressReporter {
+    sender: Option<ProgressSender>,
+    start: Instant,
+    interval: Duration,
+    ticker: Option<JoinHandle<()>>,
+}
+
+impl ProgressReporter {
+    pub fn new(sender: Option<ProgressSender>) -> Self {
+        Self {
+            sender,
+            start: Instant::now(),
+            interval: DEFAULT_PROGRESS_INTERVAL,
+            ticker: None,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Enter `phase`. If `expected` is given, keep reporting estimated progress
+    /// every interval until the next phase begins.
+    pub fn phase(&mut self, phase: ProvePhase, expected: Option<Duration>) {
+        if let Some(ticker) = self.ticker.take() {
+            ticker.abort();
+        }
+        let Some(sender) = self.sender.clone() else {
+            return;
+        };
+        let finished = matches!(phase, ProvePhase::Done | ProvePhase::Failed);
+        let _ = sender.send(ProgressEvent {
+            phase,
+            percent: if finished {
+                Some(100.0)
+            } else {
+                expected.map(|_| 0.0)
+            },
+            elapsed: self.start.elapsed(),
+        });
+        if finished {
+            return;
+        }
+
+        let start = self.start;
+        let interval = self.interval;
+        self.ticker = Some(tokio::spawn(async move {
+            let phase_start = Instant::now();
+            let mut ticks = tokio::time::interval(interval);
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                let percent =
+                    expected.map(|expected| estimate_percent(phase_start.elapsed(), expected));
+                let event = ProgressEvent {
+                    phase,
+                    percent,
+                    elapsed: start.elapsed(),
+                };
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        }));
+    }
+}
+
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        if let Some(ticker) = self.ticker.take() {
+            ticker.abort();
+        }
+    }
+}
+
+/// Share of `expected` that has elapsed, capped at 99% since the estimate may be short
+fn estimate_percent(elapsed: Duration, expected: Duration) -> f32 {
+    if expected.is_zero() {
+        return 99.0;
+    }
+    let ratio = elapsed.as_secs_f32() / expected.as_secs_f32();
+    (ratio * 100.0).min(99.0)
+}
@@ -0,0 +1,525 @@
+//! Content-addressed storage for captured proofs.
+//!
+//! Each proof's jam is stored as a blob named after its TIP5 digest and
+//! sharded by the first two bytes of the hex digest:
+//!
+//! ```text
+//! <root>/index
+//! <root>/ab/cd/abcd...<80 hex digits>.jam.zst
+//! ```
+//!
+//! Blobs are zstd-compressed unless the archive is opened with compression
+//! turned off, in which case they end in `.jam`. The `index` file maps every
+//! digest to its metadata and a blake3 checksum of the blob as stored; the
+//! checksum is verified on every read so a corrupted archive is reported
+//! instead of handing back a bad proof. The index is rewritten atomically on
+//! every change, and an archive directory should only have one writer.
+//!
+//! Proving the same input twice gives the same proof, so
+//! [`ProofArchive::capture`] only writes a blob the first time it sees a
+//! digest and afterwards just records the new capture's metadata.
+//!
+//! Archives from before this layout (one `<digest>.proof` record per proof)
+//! and the JSON files the prove-block benchmarks leave in
+//! `benchmark_results/` can be brought in with
+//! [`ProofArchive::migrate_flat_records`] and
+//! [`ProofArchive::migrate_benchmark_results`].
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use bincode::config;
+use bincode::{Decode, Encode};
+use nockvm_macros::tas;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, warn};
+use zkvm_jetpack::form::address::{from_hex, to_hex};
+use zkvm_jetpack::form::math::tip5::hash_reader;
+
+use crate::codec::{compress, DEFAULT_ZSTD_LEVEL};
+
+/// TIP5 digest of a proof
+pub type ProofDigest = [u64; 5];
+
+const ARCHIVE_MAGIC: u64 = tas!(b"PRFJAM");
+/// Flat record holding the raw jam
+const ARCHIVE_VERSION_RAW: u32 = 1;
+/// Flat record holding a zstd-compressed jam
+const ARCHIVE_VERSION_ZSTD: u32 = 2;
+/// Extension of the flat records archives used to hold
+const RECORD_EXTENSION: &str = "proof";
+
+const INDEX_MAGIC: u64 = tas!(b"PRFIDX");
+/// Index without recaptures
+const INDEX_VERSION_V1: u32 = 1;
+const INDEX_VERSION: u32 = 2;
+const INDEX_FILE: &str = "index";
+const BLOB_EXTENSION: &str = "jam";
+const COMPRESSED_BLOB_EXTENSION: &str = "jam.zst";
+
+#[derive(Debug, Error)]
+pub enum ProofArchiveError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("failed to encode proof record: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+    #[error("failed to decode proof record: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+    #[error("record for {0} has bad magic bytes or unsupported version")]
+    BadFormat(String),
+    #[error("record for {0} failed its integrity check")]
+    ChecksumMismatch(String),
+    #[error("record stored under {0} has a different digest")]
+    DigestMismatch(String),
+}
+
+/// What we know about how a proof was produced
+#[derive(Encode, Decode, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ProofMetadata {
+    /// Length parameter passed to the prover
+    pub length: u64,
+    /// Block commitment the proof was made over
+    pub block_commitment: [u64; 5],
+    /// Nonce the proof was made with
+    pub nonce: [u64; 5],
+    /// Seconds since the unix epoch at capture time
+    pub captured_at: u64,
+    /// Wall-clock time spent proving, in milliseconds
+    pub prove_millis: u64,
+}
+
+/// A proof in the pre-index flat layout
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+struct ProofRecord {
+    magic_bytes: u64,
+    version: u32,
+    digest: ProofDigest,
+    /// blake3 hash of `jam` as stored
+    checksum: [u8; 32],
+    metadata: ProofMetadata,
+    /// Jammed proof, zstd-compressed in version 2 records
+    jam: Vec<u8>,
+}
+
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+struct IndexEntry {
+    /// blake3 hash of the blob as stored
+    checksum: [u8; 32],
+    compressed: bool,
+    metadata: ProofMetadata,
+    /// Later captures that produced the same proof
+    recaptures: Vec<ProofMetadata>,
+}
+
+#[derive(Encode, Decode, Debug)]
+struct ArchiveIndex {
+    magic_bytes: u64,
+    version: u32,
+    entries: BTreeMap<ProofDigest, IndexEntry>,
+}
+
+#[derive(Encode, Decode, Debug)]
+struct IndexEntryV1 {
+    checksum: [u8; 32],
+    compressed: bool,
+    metadata: ProofMetadata,
+}
+
+#[derive(Encode, Decode, Debug)]
+struct ArchiveIndexV1 {
+    magic_bytes: u64,
+    version: u32,
+    entries: BTreeMap<ProofDigest, IndexEntryV1>,
+}
+
+/// What [`ProofArchive::capture`] did with a proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureOutcome {
+    /// First time this digest was seen, the blob was written
+    Stored,
+    /// The proof was already archived, only its metadata was recorded
+    Deduplicated,
+}
+
+/// A proof read back from the archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedProof {
+    pub digest: ProofDigest,
+    pub metadata: ProofMetadata,
+    /// Jammed proof noun
+    #[serde(with = "serde_bytes")]
+    pub jam: Vec<u8>,
+}
+
+/// What a migration brought into the archive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub migrated: usize,
+    /// Files that could not be read as the expected format, left in place
+    pub skipped: usize,
+}
+
+/// On-disk proof archive rooted at a directory
+pub struct ProofArchive {
+    root: PathBuf,
+    /// zstd level for new records, `None` to store them uncompressed
+    compression_level: Option<i32>,
+    index: Mutex<BTreeMap<ProofDigest, IndexEntry>>,
+}
+
+impl ProofArchive {
+    /// Open an archive, creating the directory if it does not exist
+    pub fn open(root: impl AsRef<Path>) -> Result<Self, ProofArchiveError> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(&root)?;
+        let index = match fs::read(root.join(INDEX_FILE)) {
+            Ok(bytes) => decode_index(&bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(ProofArchive {
+            root,
+            compression_level: Some(DEFAULT_ZSTD_LEVEL),
+            index: Mutex::new(index),
+        })
+    }
+
+    /// Compress new records at `level`, or store them raw with `None`.
+    /// Existing records are readable either way.
+    pub fn with_compression_level(mut self, level: Option<i32>) -> Self {
+        self.compression_level = level;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Store a proof, replacing any existing record with the same digest
+    pub fn put(
+        &self,
+        digest: ProofDigest,
+        jam: &[u8],
+        metadata: ProofMetadata,
+    ) -> Result<(), ProofArchiveError> {
+        let mut index = self.lock_index();
+        self.store(&mut index, digest, jam, metadata)
+    }
+
+    /// Record a freshly made proof. If `digest` is already archived its blob
+    /// is left alone and only `metadata` is added to its captures.
+    pub fn capture(
+        &self,
+        digest: ProofDigest,
+        jam: &[u8],
+        metadata: ProofMetadata,
+    ) -> Result<CaptureOutcome, ProofArchiveError> {
+        let mut index = self.lock_index();
+        match index.get_mut(&digest) {
+            Some(entry) if self.blob_path_for(&digest, entry.compressed).exists() => {
+                entry.recaptures.push(metadata);
+                self.write_index(&index)?;
+                debug!("proof {} already archived", to_hex(&digest));
+                Ok(CaptureOutcome::Deduplicated)
+            }
+            _ => {
+                self.store(&mut index, digest, jam, metadata)?;
+                Ok(CaptureOutcome::Stored)
+            }
+        }
+    }
+
+    /// Metadata of every capture of `digest`, the one that stored it first
+    pub fn captures(&self, digest: &ProofDigest) -> Vec<ProofMetadata> {
+        self.lock_index()
+            .get(digest)
+            .map(|entry| {
+                std::iter::once(&entry.metadata)
+                    .chain(&entry.recaptures)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn store(
+        &self,
+        index: &mut BTreeMap<ProofDigest, IndexEntry>,
+        digest: ProofDigest,
+        jam: &[u8],
+        metadata: ProofMetadata,
+    ) -> Result<(), ProofArchiveError> {
+        let (compressed, stored) = match self.compression_level {
+            Some(level) => (true, compress(jam, level)?),
+            None => (false, jam.to_vec()),
+        };
+        let path = self.blob_path_for(&digest, compressed);
+        fs::create_dir_all(path.parent().expect("blobs live in a shard directory"))?;
+        // write to a temporary file first so a crash never leaves a torn blob
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, &stored)?;
+        fs::rename(&tmp, &path)?;
+        if let Some(old) = index.get(&digest) {
+            if old.compressed != compressed {
+                fs::remove_file(self.blob_path_for(&digest, old.compressed))?;
+            }
+        }
+        index.insert(
+            digest,
+            IndexEntry {
+                checksum: *blake3::hash(&stored).as_bytes(),
+                compressed,
+                metadata,
+                recaptures: Vec::new(),
+            },
+        );
+        self.write_index(index)?;
+        debug!("archived proof {}", to_hex(&digest));
+        Ok(())
+    }
+
+    /// Read a proof, checking its integrity. Returns `None` if it is not archived.
+    pub fn get(&self, digest: &ProofDigest) -> Result<Option<ArchivedProof>, ProofArchiveError> {
+        let Some(entry) = self.lock_index().get(digest).cloned() else {
+            return Ok(None);
+        };
+        let stored = fs::read(self.blob_path_for(digest, entry.compressed))?;
+        if *blake3::hash(&stored).as_bytes() != entry.checksum {
+            return Err(ProofArchiveError::ChecksumMismatch(to_hex(digest)));
+        }
+        let jam = if entry.compressed {
+            zstd::stream::decode_all(&stored[..])?
+        } else {
+            stored
+        };
+        Ok(Some(ArchivedProof {
+            digest: *digest,
+            metadata: entry.metadata,
+            jam,
+        }))
+    }
+
+    pub fn contains(&self, digest: &ProofDigest) -> bool {
+        self.lock_index().contains_key(digest)
+    }
+
+    pub fn remove(&self, digest: &ProofDigest) -> Result<bool, ProofArchiveError> {
+        let mut index = self.lock_index();
+        let Some(entry) = index.remove(digest) else {
+            return Ok(false);
+        };
+        self.write_index(&index)?;
+        match fs::remove_file(self.blob_path_for(digest, entry.compressed)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(true),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Digests of every archived proof, in ascending order
+    pub fn digests(&self) -> Result<Vec<ProofDigest>, ProofArchiveError> {
+        Ok(self.lock_index().keys().copied().collect())
+    }
+
+    /// Iterate over every archived proof, verifying each as it is read
+    pub fn iter(
+        &self,
+    ) -> Result<
+        impl Iterator<Item = Result<ArchivedProof, ProofArchiveError>> + '_,
+        ProofArchiveError,
+    > {
+        let digests = self.digests()?;
+        Ok(digests
+            .into_iter()
+            .filter_map(move |digest| self.get(&digest).transpose()))
+    }
+
+    /// Where the blob for `digest` is stored, if it is archived
+    pub fn blob_path(&self, digest: &ProofDigest) -> Option<PathBuf> {
+        let compressed = self.lock_index().get(digest)?.compressed;
+        Some(self.blob_path_for(digest, compressed))
+    }
+
+    /// Move `<digest>.proof` records from the old flat layout into the
+    /// index, deleting each once it is safely stored
+    pub fn migrate_flat_records(&self) -> Result<MigrationReport, ProofArchiveError> {
+        let mut report = MigrationReport::default();
+        for entry in fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(RECORD_EXTENSION) {
+                continue;
+            }
+            let name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string();
+            let proof = match decode_record(&fs::read(&path)?, &name) {
+                Ok(proof) if from_hex(&name) == Ok(proof.digest) => proof,
+                Ok(_) => {
+                    warn!("skipping proof record with mismatched digest: {:?}", path);
+                    report.skipped += 1;
+                    continue;
+                }
+                Err(e) => {
+                    warn!("skipping unreadable proof record {:?}: {}", path, e);
+                    report.skipped += 1;
+                    continue;
+                }
+            };
+            self.put(proof.digest, &proof.jam, proof.metadata)?;
+            fs::remove_file(&path)?;
+            report.migrated += 1;
+        }
+        Ok(report)
+    }
+
+    /// Import the JSON results the prove-block benchmarks write to
+    /// `benchmark_results/`. The files are left in place.
+    ///
+    /// Those results carry no proof digest, so each is archived under the
+    /// streaming TIP5 hash of its `proof_data` bytes.
+    pub fn migrate_benchmark_results(
+        &self,
+        dir: impl AsRef<Path>,
+    ) -> Result<MigrationReport, ProofArchiveError> {
+        let mut report = MigrationReport::default();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some((proof_data, mut metadata)) = fs::read(&path)
+                .ok()
+                .and_then(|bytes| parse_benchmark_result(&bytes))
+            else {
+                warn!("skipping unrecognized benchmark result: {:?}", path);
+                report.skipped += 1;
+                continue;
+            };
+            metadata.captured_at = entry
+                .metadata()?
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            let digest = hash_reader(&proof_data[..])?;
+            // reruns of the same benchmark usually produce the same proof
+            self.capture(digest, &proof_data, metadata)?;
+            report.migrated += 1;
+        }
+        Ok(report)
+    }
+
+    fn blob_path_for(&self, digest: &ProofDigest, compressed: bool) -> PathBuf {
+        let hex = to_hex(digest);
+        let extension = if compressed {
+            COMPRESSED_BLOB_EXTENSION
+        } else {
+            BLOB_EXTENSION
+        };
+        self.root
+            .join(&hex[0..2])
+            .join(&hex[2..4])
+            .join(format!("{hex}.{extension}"))
+    }
+
+    fn lock_index(&self) -> std::sync::MutexGuard<'_, BTreeMap<ProofDigest, IndexEntry>> {
+        self.index
+            .lock()
+            .expect("proof archive index lock poisoned")
+    }
+
+    fn write_index(
+        &self,
+        entries: &BTreeMap<ProofDigest, IndexEntry>,
+    ) -> Result<(), ProofArchiveError> {
+        let index = ArchiveIndex {
+            magic_bytes: INDEX_MAGIC,
+            version: INDEX_VERSION,
+            entries: entries.clone(),
+        };
+        let path = self.root.join(INDEX_FILE);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, bincode::encode_to_vec(&index, config::standard())?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+fn decode_index(bytes: &[u8]) -> Result<BTreeMap<ProofDigest, IndexEntry>, ProofArchiveError> {
+    let ((magic_bytes, version), _): ((u64, u32), usize) =
+        bincode::decode_from_slice(bytes, config::standard())?;
+    if magic_bytes != INDEX_MAGIC {
+        return Err(ProofArchiveError::BadFormat(INDEX_FILE.to_string()));
+    }
+    match version {
+        INDEX_VERSION => {
+            let (index, _): (ArchiveIndex, usize) =
+                bincode::decode_from_slice(bytes, config::standard())?;
+            Ok(index.entries)
+        }
+        INDEX_VERSION_V1 => {
+            let (index, _): (ArchiveIndexV1, usize) =
+                bincode::decode_from_slice(bytes, config::standard())?;
+            Ok(index
+                .entries
+                .into_iter()
+                .map(|(digest, entry)| {
+                    let entry = IndexEntry {
+                        checksum: entry.checksum,
+                        compressed: entry.compressed,
+                        metadata: entry.metadata,
+                        recaptures: Vec::new(),
+                    };
+                    (digest, entry)
+                })
+                .collect())
+        }
+        _ => Err(ProofArchiveError::BadFormat(INDEX_FILE.to_string())),
+    }
+}
+
+fn decode_record(bytes: &[u8], name: &str) -> Result<ArchivedProof, ProofArchiveError> {
+    let (record, _): (ProofRecord, usize) = bincode::decode_from_slice(bytes, config::standard())?;
+    if record.magic_bytes != ARCHIVE_MAGIC {
+        return Err(ProofArchiveError::BadFormat(name.to_string()));
+    }
+    if *blake3::hash(&record.jam).as_bytes() != record.checksum {
+        return Err(ProofArchiveError::ChecksumMismatch(name.to_string()));
+    }
+    let jam = match record.version {
+        ARCHIVE_VERSION_RAW => record.jam,
+        ARCHIVE_VERSION_ZSTD => zstd::stream::decode_all(&record.jam[..])?,
+        _ => return Err(ProofArchiveError::BadFormat(name.to_string())),
+    };
+    Ok(ArchivedProof {
+        digest: record.digest,
+        metadata: record.metadata,
+        jam,
+    })
+}
+
+/// `proof_data` and what metadata there is from a benchmark result file
+fn parse_benchmark_result(bytes: &[u8]) -> Option<(Vec<u8>, ProofMetadata)> {
+    let result: serde_json::Value = serde_json::from_slice(bytes).ok()?;
+    let input = result.get("input")?;
+    let digest = |field: &str| -> Option<[u64; 5]> {
+        let belts: Vec<u64> = serde_json::from_value(input.get(field)?.clone()).ok()?;
+        belts.try_into().ok()
+    };
+    let proof_data: Vec<u8> = serde_json::from_value(result.get("proof_data")?.clone()).ok()?;
+    let metadata = ProofMetadata {
+        length: input.get("length")?.as_u64()?,
+        block_commitment: digest("block_commitment")?,
+        nonce: digest("nonce")?,
+        captured_at: 0,
+        prove_millis: (result.get("duration_secs")?.as_f64()? * 1000.0) as u64,
+    };
+    Some((proof_data, metadata))
+}
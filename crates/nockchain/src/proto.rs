@@ -0,0 +1,238 @@
+//! Protobuf wire types for proof exchange.
+//!
+//! [`pb`] holds the types generated from `proto/nockchain/proof/v1/proof.proto`.
+//! The conversions here are the only sanctioned way in and out of them: every
+//! digest coming off the wire is checked like any other
+//! [`ProveBlockInput`], and a [`pb::ProofStructure`] carries each proof object
+//! as a jam so it round-trips to the exact proof noun.
+
+use std::time::Duration;
+
+use nockapp::noun::slab::{CueError, NounSlab};
+use nockvm::noun::{Atom, Noun, D, T};
+use thiserror::Error;
+
+use crate::effect::{MiningEffectError, ProofView};
+use crate::prove_input::{check_belts, ProveBlockInput, ProveInputError, DIGEST_BELTS};
+
+/// Generated protobuf types, package `nockchain.proof.v1`
+pub mod pb {
+    include!(concat!(env!("OUT_DIR"), "/nockchain.proof.v1.rs"));
+}
+
+#[derive(Debug, Error)]
+pub enum ProtoError {
+    #[error("message is missing {0}")]
+    Missing(&'static str),
+    #[error(transparent)]
+    Input(#[from] ProveInputError),
+    #[error(transparent)]
+    Proof(#[from] MiningEffectError),
+    #[error("proof object {index} does not cue: {source}")]
+    Cue { index: usize, source: CueError },
+}
+
+impl From<&[u64; DIGEST_BELTS]> for pb::Digest {
+    fn from(belts: &[u64; DIGEST_BELTS]) -> Self {
+        pb::Digest {
+            belts: belts.to_vec(),
+        }
+    }
+}
+
+fn digest_from_pb(
+    field: &'static str,
+    digest: Option<pb::Digest>,
+) -> Result<[u64; DIGEST_BELTS], ProtoError> {
+    let digest = digest.ok_or(ProtoError::Missing(field))?;
+    Ok(check_belts(field, digest.belts)?)
+}
+
+impl From<&ProveBlockInput> for pb::ProveBlockInput {
+    fn from(input: &ProveBlockInput) -> Self {
+        pb::ProveBlockInput {
+            length: input.length(),
+            block_commitment: Some(input.block_commitment().into()),
+            nonce: Some(input.nonce().into()),
+            extra_nonce: input.extra_nonce(),
+        }
+    }
+}
+
+impl TryFrom<pb::ProveBlockInput> for ProveBlockInput {
+    type Error = ProtoError;
+
+    fn try_from(input: pb::ProveBlockInput) -> Result<Self, Self::Error> {
+        Ok(ProveBlockInput::builder()
+            .length(input.length)
+            .block_commitment(&digest_from_pb("block_commitment", input.block_commitment)?)
+            .nonce(&digest_from_pb("nonce", input.nonce)?)
+            .extra_nonce(input.extra_nonce)
+            .build()?)
+    }
+}
+
+impl pb::ProofStructure {
+    /// Encode a proof read in place, jamming each object separately
+    pub fn from_view(proof: &ProofView<'_>) -> Result<Self, ProtoError> {
+        let objects = proof
+            .objects()
+            .map(|object| pb::ProofObject {
+                tag: object.tag().unwrap_or_default(),
+                jam: object.to_slab().jam().to_vec(),
+            })
+            .collect();
+        let hashes = proof
+            .hashes()
+            .map(|hash| hash.map(|hash| pb::Digest::from(&hash)))
+            .collect::<Result<_, _>>()?;
+        Ok(pb::ProofStructure {
+            version: proof.version(),
+            objects,
+            hashes,
+            read_index: proof
+                .read_index()
+                .ok_or(MiningEffectError::NotAnAtom("read index"))?,
+        })
+    }
+
+    /// Rebuild the `[version objects hashes read-index]` proof noun
+    pub fn to_proof_slab(&self) -> Result<NounSlab, ProtoError> {
+        let mut slab = NounSlab::new();
+        let mut objects = D(0);
+        for (index, object) in self.objects.iter().enumerate().rev() {
+            let object = slab
+                .cue_into(object.jam.clone().into())
+                .map_err(|source| ProtoError::Cue { index, source })?;
+            objects = T(&mut slab, &[object, objects]);
+        }
+        let mut hashes = D(0);
+        for hash in self.hashes.iter().rev() {
+            let hash = check_belts("proof hash", hash.belts.clone())?;
+            let hash = belts_to_noun(&mut slab, &hash);
+            hashes = T(&mut slab, &[hash, hashes]);
+        }
+        let version = Atom::new(&mut slab, self.version).as_noun();
+        let read_index = Atom::new(&mut slab, self.read_index).as_noun();
+        let proof = T(&mut slab, &[version, objects, hashes, read_index]);
+        slab.set_root(proof);
+        Ok(slab)
+    }
+}
+
+impl pb::VerificationResult {
+    /// Report the outcome of checking a proof for `input`
+    pub fn new(
+        input: &ProveBlockInput,
+        outcome: &Result<(), MiningEffectError>,
+        elapsed: Duration,
+    ) -> Self {
+        pb::VerificationResult {
+            input: Some(input.into()),
+            valid: outcome.is_ok(),
+            error: outcome
+                .as_ref()
+                .err()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            verify_micros: elapsed.as_micros() as u64,
+        }
+    }
+}
+
+fn belts_to_noun(slab: &mut NounSlab, belts: &[u64; DIGEST_BELTS]) -> Noun {
+    let nouns = belts.map(|belt| Atom::new(slab, belt).as_noun());
+    T(slab, &nouns)
+}
+
+#[cfg(test)]
+mod tests {
+    use nockvm::noun::Slots;
+    use nockvm_macros::tas;
+    use prost::Message;
+
+    use super::*;
+    use crate::effect::MiningEffect;
+
+    fn input() -> ProveBlockInput {
+        ProveBlockInput::builder()
+            .length(4)
+            .block_commitment(&[1, 2, 3, 4, 5])
+            .nonce(&[6, 7, 8, 9, 10])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_prove_block_input_roundtrip() {
+        let encoded = pb::ProveBlockInput::from(&input()).encode_to_vec();
+        let decoded = pb::ProveBlockInput::decode(&encoded[..]).unwrap();
+        assert_eq!(ProveBlockInput::try_from(decoded).unwrap(), input());
+
+        let short = pb::ProveBlockInput {
+            nonce: Some(pb::Digest { belts: vec![1, 2] }),
+            ..pb::ProveBlockInput::from(&input())
+        };
+        assert!(matches!(
+            ProveBlockInput::try_from(short),
+            Err(ProtoError::Input(ProveInputError::WrongShape {
+                got: 2,
+                ..
+            }))
+        ));
+        let missing = pb::ProveBlockInput {
+            block_commitment: None,
+            ..pb::ProveBlockInput::from(&input())
+        };
+        assert!(matches!(
+            ProveBlockInput::try_from(missing),
+            Err(ProtoError::Missing("block_commitment"))
+        ));
+    }
+
+    #[test]
+    fn test_proof_structure_roundtrip() {
+        // [%command %pow [0 [[%puzzle commitment nonce 4 0] ~] [hash ~] 3] 0 commitment nonce]
+        let mut slab = NounSlab::new();
+        let commitment = belts_to_noun(&mut slab, input().block_commitment());
+        let nonce = belts_to_noun(&mut slab, input().nonce());
+        let puzzle = T(
+            &mut slab,
+            &[D(tas!(b"puzzle")), commitment, nonce, D(4), D(0)],
+        );
+        let objects = T(&mut slab, &[puzzle, D(0)]);
+        let hash = belts_to_noun(&mut slab, &[11, 12, 13, 14, 15]);
+        let hashes = T(&mut slab, &[hash, D(0)]);
+        let proof = T(&mut slab, &[D(0), objects, hashes, D(3)]);
+        let effect = T(
+            &mut slab,
+            &[
+                D(tas!(b"command")),
+                D(tas!(b"pow")),
+                proof,
+                D(0),
+                commitment,
+                nonce,
+            ],
+        );
+        slab.set_root(effect);
+        let effect = MiningEffect::try_from(slab).unwrap();
+
+        let structure = pb::ProofStructure::from_view(&effect.proof()).unwrap();
+        assert_eq!(structure.objects[0].tag, "puzzle");
+        let decoded = pb::ProofStructure::decode(&structure.encode_to_vec()[..]).unwrap();
+        let rebuilt = decoded.to_proof_slab().unwrap();
+
+        let mut original = NounSlab::new();
+        original.copy_into(unsafe { *effect.slab().root() }.slot(14).unwrap());
+        assert_eq!(rebuilt.jam(), original.jam());
+
+        let result = pb::VerificationResult::new(
+            &input(),
+            &effect.verify(&input()),
+            Duration::from_millis(2),
+        );
+        assert!(result.valid);
+        assert_eq!(result.verify_micros, 2000);
+    }
+}
@@ -0,0 +1,321 @@
+//! Validated input for the prove-block poke.
+//!
+//! The miner kernel crashes on a malformed `[length block-commitment nonce]`
+//! cause, but only once proving is well under way. Building the cause through
+//! [`ProveBlockInputBuilder`] checks it up front instead.
+//!
+//! Pools give each worker its own search space with an extra nonce: a
+//! nonzero extra nonce is hashed into the block commitment that is proven
+//! (see [`mix_extra_nonce`]), so workers sharing a candidate never prove the
+//! same puzzle. Whoever accepts the proof must know the extra nonce to
+//! recover the node's commitment.
+
+use nockapp::noun::slab::NounSlab;
+use nockvm::noun::{Noun, D, T};
+use thiserror::Error;
+use zkvm_jetpack::form::math::base::PRIME;
+use zkvm_jetpack::form::math::tip5::hash_varlen;
+
+/// Number of belts in a block commitment or nonce
+pub const DIGEST_BELTS: usize = 5;
+
+/// Smallest proof length the prover accepts
+pub const MIN_LENGTH: u64 = 2;
+
+/// pow-len used by the network, the largest length worth proving with
+pub const MAX_LENGTH: u64 = 64;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ProveInputError {
+    #[error("missing {0}")]
+    Missing(&'static str),
+    #[error("length {0} must be a power of two between {MIN_LENGTH} and {MAX_LENGTH}")]
+    BadLength(u64),
+    #[error("{field} has {got} belts, expected {DIGEST_BELTS}")]
+    WrongShape { field: &'static str, got: usize },
+    #[error("{field}[{index}] = {value} is not a base field element")]
+    BeltOutOfRange {
+        field: &'static str,
+        index: usize,
+        value: u64,
+    },
+    #[error("candidate is not a [length block-commitment nonce] cell")]
+    BadNoun,
+}
+
+/// A checked `[length block-commitment nonce]` prove-block cause
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProveBlockInput {
+    length: u64,
+    block_commitment: [u64; DIGEST_BELTS],
+    nonce: [u64; DIGEST_BELTS],
+    extra_nonce: u64,
+}
+
+impl ProveBlockInput {
+    pub fn builder() -> ProveBlockInputBuilder {
+        ProveBlockInputBuilder::default()
+    }
+
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// The block commitment from the node, before any extra nonce
+    pub fn block_commitment(&self) -> &[u64; DIGEST_BELTS] {
+        &self.block_commitment
+    }
+
+    pub fn extra_nonce(&self) -> u64 {
+        self.extra_nonce
+    }
+
+    /// The block commitment that is actually proven
+    pub fn mined_commitment(&self) -> [u64; DIGEST_BELTS] {
+        if self.extra_nonce == 0 {
+            self.block_commitment
+        } else {
+            mix_extra_nonce(&self.block_commitment, self.extra_nonce)
+        }
+    }
+
+    /// The same candidate with a different extra nonce
+    pub fn with_extra_nonce(&self, extra_nonce: u64) -> Self {
+        ProveBlockInput {
+            extra_nonce,
+            ..self.clone()
+        }
+    }
+
+    pub fn nonce(&self) -> &[u64; DIGEST_BELTS] {
+        &self.nonce
+    }
+
+    /// The same candidate with a different nonce
+    pub fn with_nonce(&self, nonce: &[u64]) -> Result<Self, ProveInputError> {
+        Ok(ProveBlockInput {
+            nonce: check_belts("nonce", nonce.to_vec())?,
+            ..self.clone()
+        })
+    }
+
+    /// Build the cause noun expected by the miner kernel
+    pub fn to_noun_slab(&self) -> NounSlab {
+        let mut slab = NounSlab::new();
+        let commitment = belts_to_noun(&mut slab, &self.mined_commitment());
+        let nonce = belts_to_noun(&mut slab, &self.nonce);
+        let cause = T(&mut slab, &[D(self.length), commitment, nonce]);
+        slab.set_root(cause);
+        slab
+    }
+}
+
+impl TryFrom<&NounSlab> for ProveBlockInput {
+    type Error = ProveInputError;
+
+    /// Validate a cause noun, e.g. a candidate handed to us by the node. The
+    /// noun only carries the mined commitment, so the extra nonce is 0.
+    fn try_from(slab: &NounSlab) -> Result<Self, Self::Error> {
+        let root = unsafe { *slab.root() };
+        let cell = root.as_cell().map_err(|_| ProveInputError::BadNoun)?;
+        let length = cell
+            .head()
+            .as_atom()
+            .and_then(|atom| atom.as_u64())
+            .map_err(|_| ProveInputError::BadNoun)?;
+        let rest = cell
+            .tail()
+            .as_cell()
+            .map_err(|_| ProveInputError::BadNoun)?;
+        ProveBlockInput::builder()
+            .length(length)
+            .block_commitment(&noun_to_belts(rest.head())?)
+            .nonce(&noun_to_belts(rest.tail())?)
+            .build()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProveBlockInputBuilder {
+    length: Option<u64>,
+    block_commitment: Option<Vec<u64>>,
+    nonce: Option<Vec<u64>>,
+    extra_nonce: u64,
+}
+
+impl ProveBlockInputBuilder {
+    pub fn length(mut self, length: u64) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    pub fn block_commitment(mut self, belts: &[u64]) -> Self {
+        self.block_commitment = Some(belts.to_vec());
+        self
+    }
+
+    pub fn nonce(mut self, belts: &[u64]) -> Self {
+        self.nonce = Some(belts.to_vec());
+        self
+    }
+
+    pub fn extra_nonce(mut self, extra_nonce: u64) -> Self {
+        self.extra_nonce = extra_nonce;
+        self
+    }
+
+    pub fn build(self) -> Result<ProveBlockInput, ProveInputError> {
+        let length = self.length.ok_or(ProveInputError::Missing("length"))?;
+        if !(MIN_LENGTH..=MAX_LENGTH).contains(&length) || !length.is_power_of_two() {
+            return Err(ProveInputError::BadLength(length));
+        }
+        let block_commitment = check_belts(
+            "block_commitment",
+            self.block_commitment
+                .ok_or(ProveInputError::Missing("block_commitment"))?,
+        )?;
+        let nonce = check_belts(
+            "nonce",
+            self.nonce.ok_or(ProveInputError::Missing("nonce"))?,
+        )?;
+        Ok(ProveBlockInput {
+            length,
+            block_commitment,
+            nonce,
+            extra_nonce: self.extra_nonce,
+        })
+    }
+}
+
+/// Hash `extra_nonce` into `commitment`. The extra nonce is split into two
+/// 32-bit belts so every value is a valid base field element.
+pub fn mix_extra_nonce(commitment: &[u64; DIGEST_BELTS], extra_nonce: u64) -> [u64; DIGEST_BELTS] {
+    let mut input = [0; DIGEST_BELTS + 2];
+    input[..DIGEST_BELTS].copy_from_slice(commitment);
+    input[DIGEST_BELTS] = extra_nonce & 0xffff_ffff;
+    input[DIGEST_BELTS + 1] = extra_nonce >> 32;
+    hash_varlen(&input)
+}
+
+pub(crate) fn check_belts(
+    field: &'static str,
+    belts: Vec<u64>,
+) -> Result<[u64; DIGEST_BELTS], ProveInputError> {
+    let got = belts.len();
+    let belts: [u64; DIGEST_BELTS] = belts
+        .try_into()
+        .map_err(|_| ProveInputError::WrongShape { field, got })?;
+    if let Some((index, &value)) = belts.iter().enumerate().find(|(_, &b)| b >= PRIME) {
+        return Err(ProveInputError::BeltOutOfRange {
+            field,
+            index,
+            value,
+        });
+    }
+    Ok(belts)
+}
+
+pub(crate) fn belts_to_noun(slab: &mut NounSlab, belts: &[u64; DIGEST_BELTS]) -> Noun {
+    let nouns = belts.map(|belt| nockvm::noun::Atom::new(slab, belt).as_noun());
+    T(slab, &nouns)
+}
+
+/// Read a tuple of belts, returning however many there are so the builder can
+/// report a shape mismatch
+pub(crate) fn noun_to_belts(noun: Noun) -> Result<Vec<u64>, ProveInputError> {
+    let mut belts = Vec::with_capacity(DIGEST_BELTS);
+    let mut rest = noun;
+    loop {
+        match rest.as_cell() {
+            Ok(cell) => {
+                belts.push(atom_u64(cell.head())?);
+                rest = cell.tail();
+            }
+            Err(_) => {
+                belts.push(atom_u64(rest)?);
+                return Ok(belts);
+            }
+        }
+    }
+}
+
+fn atom_u64(noun: Noun) -> Result<u64, ProveInputError> {
+    noun.as_atom()
+        .and_then(|atom| atom.as_u64())
+        .map_err(|_| ProveInputError::BadNoun)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid() -> ProveBlockInputBuilder {
+        ProveBlockInput::builder()
+            .length(4)
+            .block_commitment(&[1, 2, 3, 4, 5])
+            .nonce(&[6, 7, 8, 9, 10])
+    }
+
+    #[test]
+    fn test_build_and_roundtrip() {
+        let input = valid().build().unwrap();
+        let slab = input.to_noun_slab();
+        assert_eq!(ProveBlockInput::try_from(&slab), Ok(input));
+    }
+
+    #[test]
+    fn test_extra_nonce_changes_mined_commitment() {
+        let input = valid().build().unwrap();
+        assert_eq!(input.mined_commitment(), *input.block_commitment());
+
+        let first = input.with_extra_nonce(1);
+        let second = input.with_extra_nonce(u64::MAX);
+        assert_eq!(first.block_commitment(), input.block_commitment());
+        assert_ne!(first.mined_commitment(), input.mined_commitment());
+        assert_ne!(first.mined_commitment(), second.mined_commitment());
+        assert!(second.mined_commitment().iter().all(|&belt| belt < PRIME));
+
+        let proven = ProveBlockInput::try_from(&first.to_noun_slab()).unwrap();
+        assert_eq!(*proven.block_commitment(), first.mined_commitment());
+        assert_eq!(proven.extra_nonce(), 0);
+    }
+
+    #[test]
+    fn test_rejects_invalid_input() {
+        assert_eq!(
+            valid().length(3).build(),
+            Err(ProveInputError::BadLength(3))
+        );
+        assert_eq!(
+            valid().length(128).build(),
+            Err(ProveInputError::BadLength(128))
+        );
+        assert_eq!(
+            valid().nonce(&[1, 2, 3]).build(),
+            Err(ProveInputError::WrongShape {
+                field: "nonce",
+                got: 3
+            })
+        );
+        assert_eq!(
+            valid().block_commitment(&[1, 2, PRIME, 4, 5]).build(),
+            Err(ProveInputError::BeltOutOfRange {
+                field: "block_commitment",
+                index: 2,
+                value: PRIME
+            })
+        );
+        assert_eq!(
+            ProveBlockInput::builder().length(4).build(),
+            Err(ProveInputError::Missing("block_commitment"))
+        );
+        assert_eq!(
+            valid().build().unwrap().with_nonce(&[PRIME, 0, 0, 0, 0]),
+            Err(ProveInputError::BeltOutOfRange {
+                field: "nonce",
+                index: 0,
+                value: PRIME
+            })
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! Branch-vs-baseline regression checks for the prover.
+//!
+//! A [`RegressionHarness`] proves a fixed set of candidates and either records
+//! the results as a named baseline or compares them against one. Comparisons
+//! report the change in proving time, whether the proofs hash the same, and
+//! the axes at which the effect nouns first differ.
+//!
+//! Baselines are zstd-compressed on disk; uncompressed baselines recorded
+//! before compression was added still load.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use bincode::config;
+use bincode::{Decode, Encode};
+use ibig::UBig;
+use nockapp::kernel::form::Entropy;
+use nockapp::noun::slab::{slab_noun_equality, NounSlab};
+use nockapp::CrownError;
+use nockvm::noun::Noun;
+use thiserror::Error;
+use tracing::info;
+
+use crate::backend::ProvingBackend;
+use crate::codec::{compress, decompress, DEFAULT_ZSTD_LEVEL};
+use crate::mining::candidate_length;
+use crate::stack::StackSize;
+use crate::trend::{Outcome, Tolerance};
+
+const BASELINE_EXTENSION: &str = "baseline";
+
+/// At most this many differing axes are reported per case
+const MAX_DIFFS: usize = 16;
+
+#[derive(Debug, Error)]
+pub enum RegressionError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("failed to encode baseline: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+    #[error("failed to decode baseline: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+    #[error("prove failed: {0}")]
+    Prove(#[from] CrownError),
+    #[error("no baseline named {0}")]
+    MissingBaseline(String),
+    #[error("baseline has {baseline} cases but the harness has {current}")]
+    CaseCountMismatch { baseline: usize, current: usize },
+    #[error("baseline effects for case {0} could not be cued")]
+    Cue(usize),
+}
+
+/// Result of proving one candidate
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+pub struct CaseResult {
+    pub duration_nanos: u64,
+    /// blake3 hash of the jammed effects
+    pub proof_hash: [u8; 32],
+    pub effects_jam: Vec<u8>,
+}
+
+impl CaseResult {
+    pub fn duration(&self) -> Duration {
+        Duration::from_nanos(self.duration_nanos)
+    }
+}
+
+/// A recorded set of results
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+pub struct Baseline {
+    pub name: String,
+    /// Branch the baseline was recorded on, if it was recorded in a git checkout
+    pub git_branch: Option<String>,
+    /// Seconds since the unix epoch
+    pub recorded_at: u64,
+    pub cases: Vec<CaseResult>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CaseComparison {
+    pub baseline_duration: Duration,
+    pub current_duration: Duration,
+    pub proof_hash_match: bool,
+    /// Axes into the effects noun at which baseline and current differ
+    pub diff_axes: Vec<UBig>,
+}
+
+impl CaseComparison {
+    /// Relative change in proving time, e.g. `-0.25` for 25% faster
+    pub fn time_delta(&self) -> f64 {
+        let baseline = self.baseline_duration.as_secs_f64();
+        if baseline == 0.0 {
+            return 0.0;
+        }
+        self.current_duration.as_secs_f64() / baseline - 1.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RegressionReport {
+    pub baseline: String,
+    pub baseline_branch: Option<String>,
+    pub current_branch: Option<String>,
+    pub cases: Vec<CaseComparison>,
+}
+
+impl RegressionReport {
+    pub fn proofs_match(&self) -> bool {
+        self.cases.iter().all(|case| case.proof_hash_match)
+    }
+
+    /// Relative change in total proving time over all cases
+    pub fn time_delta(&self) -> f64 {
+        let baseline: f64 = self
+            .cases
+            .iter()
+            .map(|case| case.baseline_duration.as_secs_f64())
+            .sum();
+        let current: f64 = self
+            .cases
+            .iter()
+            .map(|case| case.current_duration.as_secs_f64())
+            .sum();
+        if baseline == 0.0 {
+            return 0.0;
+        }
+        current / baseline - 1.0
+    }
+
+    /// The worst case judged with `tolerance`
+    pub fn outcome(&self, tolerance: Tolerance) -> Outcome {
+        self.cases
+            .iter()
+            .map(|case| tolerance.judge(case.time_delta(), Some(case.proof_hash_match)))
+            .max()
+            .unwrap_or(Outcome::Pass)
+    }
+}
+
+impl fmt::Display for RegressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "baseline {} ({}) vs {}",
+            self.baseline,
+            self.baseline_branch.as_deref().unwrap_or("unknown branch"),
+            self.current_branch.as_deref().unwrap_or("unknown branch"),
+        )?;
+        for (i, case) in self.cases.iter().enumerate() {
+            writeln!(
+                f,
+                "  case {}: {:.2?} -> {:.2?} ({:+.1}%), proof {}",
+                i,
+                case.baseline_duration,
+                case.current_duration,
+                case.time_delta() * 100.0,
+                if case.proof_hash_match {
+                    "matches"
+                } else {
+                    "DIFFERS"
+                },
+            )?;
+            for axis in &case.diff_axes {
+                writeln!(f, "    differs at axis {}", axis)?;
+            }
+        }
+        write!(
+            f,
+            "total: {:+.1}%, proofs {}",
+            self.time_delta() * 100.0,
+            if self.proofs_match() {
+                "match"
+            } else {
+                "differ"
+            }
+        )
+    }
+}
+
+/// Proves a fixed set of candidates and records or compares the results
+pub struct RegressionHarness {
+    dir: PathBuf,
+    backend: Arc<dyn ProvingBackend>,
+    candidates: Vec<NounSlab>,
+    stack_size: StackSize,
+    entropy: Entropy,
+    /// zstd level for recorded baselines, `None` to store them uncompressed
+    compression_level: Option<i32>,
+}
+
+impl RegressionHarness {
+    /// Baselines are stored in `dir`
+    pub fn new(
+        dir: impl AsRef<Path>,
+        backend: Arc<dyn ProvingBackend>,
+        candidates: Vec<NounSlab>,
+    ) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            backend,
+            candidates,
+            stack_size: StackSize::default(),
+            // proofs are only comparable if they were made with the same entropy
+            entropy: Entropy::Fixed(0),
+            compression_level: Some(DEFAULT_ZSTD_LEVEL),
+        }
+    }
+
+    pub fn with_stack_size(mut self, stack_size: StackSize) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    pub fn with_entropy(mut self, entropy: Entropy) -> Self {
+        self.entropy = entropy;
+        self
+    }
+
+    pub fn with_compression_level(mut self, level: Option<i32>) -> Self {
+        self.compression_level = level;
+        self
+    }
+
+    /// Prove every candidate and save the results as `baseline_name`
+    pub async fn record(&self, baseline_name: &str) -> Result<Baseline, RegressionError> {
+        let baseline = Baseline {
+            name: baseline_name.to_string(),
+            git_branch: current_git_branch(),
+            recorded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            cases: self.run().await?,
+        };
+        fs::create_dir_all(&self.dir)?;
+        let path = self.baseline_path(baseline_name);
+        let tmp = path.with_extension("tmp");
+        let mut encoded = bincode::encode_to_vec(&baseline, config::standard())?;
+        if let Some(level) = self.compression_level {
+            encoded = compress(&encoded, level)?;
+        }
+        fs::write(&tmp, encoded)?;
+        fs::rename(&tmp, &path)?;
+        info!("Recorded baseline {} at {:?}", baseline_name, path);
+        Ok(baseline)
+    }
+
+    /// Prove every candidate and compare against the saved `baseline_name`
+    pub async fn compare(&self, baseline_name: &str) -> Result<RegressionReport, RegressionError> {
+        let baseline = self
+            .load(baseline_name)?
+            .ok_or_else(|| RegressionError::MissingBaseline(baseline_name.to_string()))?;
+        if baseline.cases.len() != self.candidates.len() {
+            return Err(RegressionError::CaseCountMismatch {
+                baseline: baseline.cases.len(),
+                current: self.candidates.len(),
+            });
+        }
+        let current = self.run().await?;
+
+        let mut cases = Vec::with_capacity(current.len());
+        for (i, (old, new)) in baseline.cases.iter().zip(&current).enumerate() {
+            let proof_hash_match = old.proof_hash == new.proof_hash;
+            let diff_axes = if proof_hash_match {
+                Vec::new()
+            } else {
+                effects_diff(&old.effects_jam, &new.effects_jam).ok_or(RegressionError::Cue(i))?
+            };
+            cases.push(CaseComparison {
+                baseline_duration: old.duration(),
+                current_duration: new.duration(),
+                proof_hash_match,
+                diff_axes,
+            });
+        }
+        Ok(RegressionReport {
+            baseline: baseline.name,
+            baseline_branch: baseline.git_branch,
+            current_branch: current_git_branch(),
+            cases,
+        })
+    }
+
+    /// A saved baseline, if there is one named `baseline_name`
+    pub fn load(&self, baseline_name: &str) -> Result<Option<Baseline>, RegressionError> {
+        match fs::read(self.baseline_path(baseline_name)) {
+            Ok(bytes) => {
+                let bytes = decompress(bytes)?;
+                let (baseline, _) = bincode::decode_from_slice(&bytes, config::standard())?;
+                Ok(Some(baseline))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn run(&self) -> Result<Vec<CaseResult>, RegressionError> {
+        let mut results = Vec::with_capacity(self.candidates.len());
+        for candidate in &self.candidates {
+            let length = candidate_length(candidate);
+            let prover = self.backend.load(self.stack_size.words_for(length)).await?;
+            let start = Instant::now();
+            let effects = prover.prove(candidate.clone(), self.entropy).await?;
+            let duration = start.elapsed();
+            let effects_jam = effects.jam().to_vec();
+            results.push(CaseResult {
+                duration_nanos: duration.as_nanos() as u64,
+                proof_hash: *blake3::hash(&effects_jam).as_bytes(),
+                effects_jam,
+            });
+        }
+        Ok(results)
+    }
+
+    fn baseline_path(&self, baseline_name: &str) -> PathBuf {
+        self.dir
+            .join(baseline_name)
+            .with_extension(BASELINE_EXTENSION)
+    }
+}
+
+/// Name of the checked-out git branch, if we are in a git checkout
+pub fn current_git_branch() -> Option<String> {
+    let output = Command::new("git")
+        .args(["branch", "--show-current"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let branch = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!branch.is_empty()).then_some(branch)
+}
+
+/// Axes at which two jammed nouns differ, `None` if either fails to cue
+fn effects_diff(old_jam: &[u8], new_jam: &[u8]) -> Option<Vec<UBig>> {
+    let mut old_slab = NounSlab::new();
+    let old = old_slab.cue_into(old_jam.to_vec().into()).ok()?;
+    let mut new_slab = NounSlab::new();
+    let new = new_slab.cue_into(new_jam.to_vec().into()).ok()?;
+    let mut diffs = Vec::new();
+    noun_diff(old, new, UBig::from(1u8), &mut diffs);
+    Some(diffs)
+}
+
+/// Collect the outermost axes at which `a` and `b` differ
+pub fn noun_diff(a: Noun, b: Noun, axis: UBig, diffs: &mut Vec<UBig>) {
+    if diffs.len() >= MAX_DIFFS || slab_noun_equality(&a, &b) {
+        return;
+    }
+    match (a.as_cell(), b.as_cell()) {
+        (Ok(a), Ok(b)) => {
+            let head_axis = &axis * UBig::from(2u8);
+            let tail_axis = &head_axis + UBig::from(1u8);
+            noun_diff(a.head(), b.head(), head_axis, diffs);
+            noun_diff(a.tail(), b.tail(), tail_axis, diffs);
+        }
+        _ => diffs.push(axis),
+    }
+}
@@ -0,0 +1,388 @@
+//! Unwinding kernel state when the heaviest chain switches forks.
+//!
+//! Kernel state only moves forward: once a block has been applied there is
+//! no poke that takes it back out. [`ChainFollower`] keeps the blocks of the
+//! heaviest chain in a [`BlockStore`] and snapshots the [`ChainState`] every
+//! [`ReorgConfig::checkpoint_interval`] blocks. When fork choice moves to
+//! another branch, [`ChainFollower::switch_branch`] restores the newest
+//! snapshot at or below the fork point, replays the stored blocks up to the
+//! fork point, applies the new branch on top and broadcasts a
+//! [`ReorgEvent`] with the number of blocks rolled back.
+//!
+//! Snapshots are kept for the last [`ReorgConfig::max_depth`] blocks,
+//! deeper reorgs are refused. Replayed blocks are poked with
+//! [`ReorgWire::Replay`] and their effects are dropped, the node acted on
+//! them when it first heard the blocks. Their proofs were verified then
+//! too, so they are replayed with [`ChainState::apply_verified`].
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use futures::future::BoxFuture;
+use nockapp::kernel::form::Kernel;
+use nockapp::noun::slab::NounSlab;
+use nockapp::utils::make_tas;
+use nockapp::wire::{Wire, WireRepr};
+use nockapp::CrownError;
+use nockvm::noun::{D, T};
+use nockvm_macros::tas;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+use crate::block_store::{BlockStore, BlockStoreError};
+use crate::events::{EventBus, NodeEvent};
+use crate::light_client::BlockId;
+use crate::metrics::metrics;
+
+/// Version of the `%fact` poke the kernel expects for heard blocks
+const POKE_VERSION: u64 = 0;
+const SNAPSHOT_EXTENSION: &str = "state";
+/// Reorg events buffered for subscribers that fall behind
+const EVENT_CAPACITY: usize = 64;
+
+pub enum ReorgWire {
+    Replay,
+}
+
+impl Wire for ReorgWire {
+    const VERSION: u64 = 1;
+    const SOURCE: &'static str = "reorg";
+
+    fn to_wire(&self) -> WireRepr {
+        let tags = match self {
+            ReorgWire::Replay => vec!["replay".into()],
+        };
+        WireRepr::new(ReorgWire::SOURCE, ReorgWire::VERSION, tags)
+    }
+}
+
+/// State built by applying blocks in order, which can be saved and restored
+pub trait ChainState: Send + Sync {
+    /// Serialize the current state
+    fn snapshot(&self) -> BoxFuture<'_, Result<Vec<u8>, CrownError>>;
+
+    /// Replace the current state with one from [`Self::snapshot`]
+    fn restore(&self, snapshot: Vec<u8>) -> BoxFuture<'_, Result<(), CrownError>>;
+
+    /// Apply the next block, a page noun
+    fn apply(&self, page: NounSlab) -> BoxFuture<'_, Result<(), CrownError>>;
+
+    /// Apply the next block, whose proof of work was already verified
+    fn apply_verified(&self, page: NounSlab) -> BoxFuture<'_, Result<(), CrownError>> {
+        self.apply(page)
+    }
+}
+
+impl ChainState for Kernel {
+    fn snapshot(&self) -> BoxFuture<'_, Result<Vec<u8>, CrownError>> {
+        Box::pin(self.create_state_bytes())
+    }
+
+    fn restore(&self, snapshot: Vec<u8>) -> BoxFuture<'_, Result<(), CrownError>> {
+        Box::pin(self.import_state(snapshot))
+    }
+
+    fn apply(&self, page: NounSlab) -> BoxFuture<'_, Result<(), CrownError>> {
+        let poke = self.poke(ReorgWire::Replay.to_wire(), heard_block_fact(page));
+        Box::pin(async move {
+            poke.await?;
+            Ok(())
+        })
+    }
+
+    /// Only checks that the proof was mined for the page, not the proof
+    fn apply_verified(&self, page: NounSlab) -> BoxFuture<'_, Result<(), CrownError>> {
+        let poke = self.poke(ReorgWire::Replay.to_wire(), verified_block_command(page));
+        Box::pin(async move {
+            poke.await?;
+            Ok(())
+        })
+    }
+}
+
+/// The `[%fact %0 %heard-block page]` poke for a page noun
+pub fn heard_block_fact(mut page: NounSlab) -> NounSlab {
+    let root = unsafe { *page.root() };
+    let heard_block = make_tas(&mut page, "heard-block").as_noun();
+    let fact = T(
+        &mut page,
+        &[D(tas!(b"fact")), D(POKE_VERSION), heard_block, root],
+    );
+    page.set_root(fact);
+    page
+}
+
+/// The `[%command %verified-block page]` poke for a page noun
+pub fn verified_block_command(mut page: NounSlab) -> NounSlab {
+    let root = unsafe { *page.root() };
+    let verified_block = make_tas(&mut page, "verified-block").as_noun();
+    let command = T(&mut page, &[D(tas!(b"command")), verified_block, root]);
+    page.set_root(command);
+    page
+}
+
+#[derive(Debug, Error)]
+pub enum ReorgError {
+    #[error("fork point {0:?} is not on the current chain")]
+    UnknownForkPoint(BlockId),
+    #[error("reorg of {depth} blocks is deeper than the {max} that can be rolled back")]
+    TooDeep { depth: u64, max: u64 },
+    #[error("no snapshot at or below height {0} to roll back to")]
+    NoSnapshot(u64),
+    #[error("block at height {0} is missing from the block store")]
+    MissingBlock(u64),
+    #[error("block store error: {0}")]
+    Store(#[from] BlockStoreError),
+    #[error("kernel error: {0}")]
+    Kernel(#[from] CrownError),
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Emitted when the chain switches branches
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReorgEvent {
+    /// Blocks of the old branch that were rolled back
+    pub depth: u64,
+    /// Height of the last block both branches share
+    pub fork_height: u64,
+    pub old_tip: BlockId,
+    pub new_tip: BlockId,
+    /// Blocks applied after restoring the snapshot, shared blocks included
+    pub replayed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReorgConfig {
+    /// Snapshot the state every this many blocks, at least 1
+    pub checkpoint_interval: u64,
+    /// Deepest reorg that can be rolled back
+    pub max_depth: u64,
+}
+
+impl Default for ReorgConfig {
+    fn default() -> Self {
+        Self {
+            checkpoint_interval: 10,
+            max_depth: 100,
+        }
+    }
+}
+
+/// Applies the heaviest chain to a [`ChainState`], rolling back on reorgs
+pub struct ChainFollower<S: ChainState> {
+    state: S,
+    store: BlockStore,
+    config: ReorgConfig,
+    dir: PathBuf,
+    /// Snapshot files keyed by how many blocks had been applied, so the
+    /// state before genesis is 0
+    snapshots: BTreeMap<u64, PathBuf>,
+    events: broadcast::Sender<ReorgEvent>,
+    bus: Option<EventBus>,
+}
+
+impl<S: ChainState> ChainFollower<S> {
+    /// Follow the chain in `store`, whose blocks `state` has already
+    /// applied. Snapshots go in `dir`, snapshots left there by an earlier
+    /// run are removed and the current state becomes the first one.
+    pub async fn open(
+        state: S,
+        store: BlockStore,
+        dir: impl AsRef<Path>,
+        config: ReorgConfig,
+    ) -> Result<Self, ReorgError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some(SNAPSHOT_EXTENSION) {
+                fs::remove_file(&path)?;
+            }
+        }
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let mut follower = Self {
+            state,
+            store,
+            config,
+            dir,
+            snapshots: BTreeMap::new(),
+            events,
+            bus: None,
+        };
+        follower.take_snapshot().await?;
+        Ok(follower)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ReorgEvent> {
+        self.events.subscribe()
+    }
+
+    /// Also publish reorgs on `bus`
+    pub fn publish_to(&mut self, bus: EventBus) {
+        self.bus = Some(bus);
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn store(&self) -> &BlockStore {
+        &self.store
+    }
+
+    pub fn tip(&self) -> Option<(u64, BlockId)> {
+        let height = self.store.tip_height()?;
+        Some((height, self.store.digest_at(height)?))
+    }
+
+    /// Apply a block on top of the tip
+    pub async fn extend(&mut self, digest: BlockId, page: NounSlab) -> Result<(), ReorgError> {
+        self.append(digest, page, false).await
+    }
+
+    /// Apply a block on top of the tip whose proof of work was already
+    /// verified, e.g. by a [`crate::sync::SyncPipeline`]
+    pub async fn extend_verified(
+        &mut self,
+        digest: BlockId,
+        page: NounSlab,
+    ) -> Result<(), ReorgError> {
+        self.append(digest, page, true).await
+    }
+
+    async fn append(
+        &mut self,
+        digest: BlockId,
+        page: NounSlab,
+        verified: bool,
+    ) -> Result<(), ReorgError> {
+        let height = self.applied();
+        let jam = page.jam();
+        if verified {
+            self.state.apply_verified(page).await?;
+        } else {
+            self.state.apply(page).await?;
+        }
+        self.store.put(height, digest, &jam)?;
+        if (height + 1) % self.config.checkpoint_interval.max(1) == 0 {
+            self.take_snapshot().await?;
+        }
+        Ok(())
+    }
+
+    /// Switch to the branch that leaves the current chain after
+    /// `fork_point`, made of `branch` in order. Returns the event that was
+    /// broadcast.
+    ///
+    /// If replaying fails the state is left partway through and the follower
+    /// should be reopened from a fresh kernel.
+    pub async fn switch_branch(
+        &mut self,
+        fork_point: BlockId,
+        branch: Vec<(BlockId, NounSlab)>,
+    ) -> Result<ReorgEvent, ReorgError> {
+        let fork_height = self
+            .store
+            .height_of(&fork_point)
+            .ok_or(ReorgError::UnknownForkPoint(fork_point))?;
+        let (tip_height, old_tip) = self.tip().ok_or(ReorgError::UnknownForkPoint(fork_point))?;
+        let depth = tip_height - fork_height;
+        if depth > self.config.max_depth {
+            return Err(ReorgError::TooDeep {
+                depth,
+                max: self.config.max_depth,
+            });
+        }
+
+        let (applied, path) = self
+            .snapshots
+            .range(..=fork_height + 1)
+            .next_back()
+            .map(|(applied, path)| (*applied, path.clone()))
+            .ok_or(ReorgError::NoSnapshot(fork_height))?;
+        debug!("rolling back to the snapshot after {applied} blocks");
+        self.state.restore(fs::read(&path)?).await?;
+        // whatever was built on the old branch is gone
+        for stale in self.snapshots.split_off(&(applied + 1)).into_values() {
+            remove_snapshot(&stale)?;
+        }
+        self.store.truncate_above(fork_height)?;
+
+        for height in applied..=fork_height {
+            let page = self
+                .store
+                .get_slab(height)?
+                .ok_or(ReorgError::MissingBlock(height))?;
+            self.state.apply_verified(page).await?;
+        }
+        let mut replayed = fork_height + 1 - applied;
+        for (digest, page) in branch {
+            self.extend(digest, page).await?;
+            replayed += 1;
+        }
+
+        let (_, new_tip) = self.tip().expect("the fork point is still stored");
+        let event = ReorgEvent {
+            depth,
+            fork_height,
+            old_tip,
+            new_tip,
+            replayed,
+        };
+        metrics().reorgs.increment();
+        metrics().reorg_depth.swap(depth as f64);
+        if depth > 0 {
+            warn!("reorg of {depth} blocks at height {fork_height}");
+        }
+        // nobody listening is fine
+        let _ = self.events.send(event.clone());
+        if let Some(bus) = &self.bus {
+            bus.publish(NodeEvent::Reorg(event.clone()));
+        }
+        Ok(event)
+    }
+
+    /// Blocks applied so far, which is also the height of the next block
+    fn applied(&self) -> u64 {
+        self.store.tip_height().map_or(0, |tip| tip + 1)
+    }
+
+    async fn take_snapshot(&mut self) -> Result<(), ReorgError> {
+        let applied = self.applied();
+        let snapshot = self.state.snapshot().await?;
+        let path = self
+            .dir
+            .join(format!("{applied:012}"))
+            .with_extension(SNAPSHOT_EXTENSION);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, &snapshot)?;
+        fs::rename(&tmp, &path)?;
+        self.snapshots.insert(applied, path);
+        debug!("snapshotted state after {applied} blocks");
+
+        // keep the newest snapshot deep enough for the deepest reorg
+        let floor = applied.saturating_sub(self.config.max_depth);
+        let Some(&oldest_needed) = self.snapshots.range(..=floor).next_back().map(|(k, _)| k)
+        else {
+            return Ok(());
+        };
+        let keep = self.snapshots.split_off(&oldest_needed);
+        for old in std::mem::replace(&mut self.snapshots, keep).into_values() {
+            remove_snapshot(&old)?;
+            debug!("dropped snapshot {old:?} below the reorg window");
+        }
+        Ok(())
+    }
+}
+
+fn remove_snapshot(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,144 @@
+//! NockStack sizing for prover and verifier kernels.
+//!
+//! Proving allocates heavily on the NockStack, and the amount needed grows
+//! with the length of the proof. A stack that is too small makes the serf
+//! panic with an out-of-memory error, while the huge stack reserves a very
+//! large amount of address space even for trivial proofs.
+
+use std::fmt;
+use std::str::FromStr;
+
+use nockapp::utils::{NOCK_STACK_1KB, NOCK_STACK_SIZE, NOCK_STACK_SIZE_HUGE};
+use nockapp::CrownError;
+use nockvm::interpreter::{Error, Mote};
+
+/// 1GB of NockStack, in words
+const NOCK_STACK_1GB: usize = NOCK_STACK_1KB << 10 << 10;
+
+/// Stack the auto-sizer adds per unit of proof length
+const AUTO_STACK_PER_LENGTH: usize = NOCK_STACK_1GB / 2;
+
+/// How big a NockStack to give a kernel, in 8-byte words
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StackSize {
+    /// Pick a size from the proof length, see [`auto_stack_size`]
+    #[default]
+    Auto,
+    /// Always use this many words
+    Words(usize),
+}
+
+impl StackSize {
+    /// Stack size in words for a proof of `length`
+    pub fn words_for(&self, length: u64) -> usize {
+        match self {
+            StackSize::Auto => auto_stack_size(length),
+            StackSize::Words(words) => *words,
+        }
+    }
+}
+
+/// Heuristic stack size for a proof of `length`.
+///
+/// Starts from the default 8GB stack and grows linearly with the length,
+/// capped at [`NOCK_STACK_SIZE_HUGE`].
+pub fn auto_stack_size(length: u64) -> usize {
+    let extra = usize::try_from(length)
+        .unwrap_or(usize::MAX)
+        .saturating_mul(AUTO_STACK_PER_LENGTH);
+    NOCK_STACK_SIZE
+        .saturating_add(extra)
+        .min(NOCK_STACK_SIZE_HUGE)
+}
+
+impl FromStr for StackSize {
+    type Err = String;
+
+    /// Parses `auto`, or a size with a `KB`, `MB` or `GB` suffix (e.g. `16GB`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(StackSize::Auto);
+        }
+        let upper = s.to_ascii_uppercase();
+        let (digits, unit) = if let Some(n) = upper.strip_suffix("GB") {
+            (n, NOCK_STACK_1GB)
+        } else if let Some(n) = upper.strip_suffix("MB") {
+            (n, NOCK_STACK_1KB << 10)
+        } else if let Some(n) = upper.strip_suffix("KB") {
+            (n, NOCK_STACK_1KB)
+        } else {
+            return Err(format!(
+                "Invalid stack size '{s}'. Expected 'auto' or a size such as '16GB'"
+            ));
+        };
+        let count = digits
+            .trim()
+            .parse::<usize>()
+            .map_err(|e| format!("Invalid stack size '{s}': {e}"))?;
+        if count == 0 {
+            return Err("Stack size must be greater than zero".to_string());
+        }
+        count
+            .checked_mul(unit)
+            .map(StackSize::Words)
+            .ok_or_else(|| format!("Stack size '{s}' is too large"))
+    }
+}
+
+impl fmt::Display for StackSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StackSize::Auto => write!(f, "auto"),
+            StackSize::Words(words) => write!(f, "{}", format_words(*words)),
+        }
+    }
+}
+
+/// Human readable size of a stack of `words`
+pub fn format_words(words: usize) -> String {
+    let bytes = words as f64 * 8.0;
+    format!("{:.1}GB", bytes / (1u64 << 30) as f64)
+}
+
+/// Whether a poke failed because the kernel ran out of NockStack.
+///
+/// The serf thread panics when an allocation would overflow the stack, which
+/// drops the poke's result channel, so that is treated as an OOM as well.
+pub fn is_out_of_memory(err: &CrownError) -> bool {
+    match err {
+        CrownError::InterpreterError(e) => matches!(
+            e.0,
+            Error::Deterministic(Mote::Meme, _) | Error::NonDeterministic(Mote::Meme, _)
+        ),
+        CrownError::OneshotChannelError(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_stack_size_bounds() {
+        assert_eq!(auto_stack_size(0), NOCK_STACK_SIZE);
+        assert!(auto_stack_size(4) > auto_stack_size(2));
+        assert_eq!(auto_stack_size(u64::MAX), NOCK_STACK_SIZE_HUGE);
+    }
+
+    #[test]
+    fn test_parse_stack_size() {
+        assert_eq!("auto".parse::<StackSize>(), Ok(StackSize::Auto));
+        assert_eq!(
+            "16GB".parse::<StackSize>(),
+            Ok(StackSize::Words(16 * NOCK_STACK_1GB))
+        );
+        assert_eq!(
+            "512mb".parse::<StackSize>(),
+            Ok(StackSize::Words(512 * (NOCK_STACK_1KB << 10)))
+        );
+        assert!("0GB".parse::<StackSize>().is_err());
+        assert!("lots".parse::<StackSize>().is_err());
+    }
+}
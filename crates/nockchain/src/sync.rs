@@ -0,0 +1,187 @@
+//! Initial sync: verifying proofs of work in parallel, applying blocks in order.
+//!
+//! Checking a block's STARK proof dwarfs applying its state transition, and
+//! unlike the state transition it does not depend on the block before it.
+//! [`SyncPipeline`] verifies the proofs of the next few blocks at once on a
+//! [`VerificationService`], as many as the service can run and queue, and
+//! commits them to a [`ChainFollower`] strictly in height order as their
+//! turn comes. A block whose proof is invalid or misses its target stops
+//! the sync before it is applied, and nothing after it is applied either.
+//!
+//! The proof verified is the one in the page's `pow` field, and blocks are
+//! applied with [`ChainFollower::extend_verified`], so the kernel does not
+//! verify the proof a second time. It still checks that the proof's puzzle
+//! commits to the page it came in, which is cheap.
+
+use std::pin::pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::stream::{Stream, StreamExt};
+use ibig::UBig;
+use nockapp::noun::slab::NounSlab;
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::light_client::BlockId;
+use crate::reorg::{ChainFollower, ChainState, ReorgError};
+use crate::verifier::{VerificationService, VerifyError};
+
+#[derive(Debug, Error)]
+pub enum SyncError {
+    #[error("block {height} has no proof of work")]
+    NoProof { height: u64 },
+    #[error("block {height} does not carry a valid proof of work")]
+    InvalidProof { height: u64 },
+    #[error("block {height} proof hash {pow:x} is above its target {target:x}")]
+    AboveTarget {
+        height: u64,
+        pow: UBig,
+        target: UBig,
+    },
+    #[error("could not verify block {height}: {source}")]
+    Verify { height: u64, source: VerifyError },
+    #[error("expected block {expected} next, got {got}")]
+    OutOfOrder { expected: u64, got: u64 },
+    #[error("could not apply block {height}: {source}")]
+    Apply { height: u64, source: ReorgError },
+}
+
+/// A block downloaded for sync
+pub struct SyncBlock {
+    pub height: u64,
+    pub digest: BlockId,
+    /// The target the block claims to meet
+    pub target: UBig,
+    /// The page noun handed to the chain state, whose `pow` field holds
+    /// the proof that is verified
+    pub page: NounSlab,
+}
+
+/// How a sync went
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub applied: u64,
+    /// Verification time summed over every block, more than
+    /// [`Self::elapsed`] when blocks were verified in parallel
+    pub verify_time: Duration,
+    pub elapsed: Duration,
+}
+
+struct Verified {
+    height: u64,
+    digest: BlockId,
+    page: NounSlab,
+    verified_in: Duration,
+}
+
+pub struct SyncPipeline {
+    verifier: Arc<VerificationService>,
+    window: usize,
+    deadline: Option<Duration>,
+}
+
+impl SyncPipeline {
+    /// Verify as many blocks ahead as `verifier` can run and queue
+    pub fn new(verifier: Arc<VerificationService>) -> Self {
+        let config = verifier.config();
+        Self {
+            window: (config.max_concurrent + config.max_queued).max(1),
+            verifier,
+            deadline: None,
+        }
+    }
+
+    /// Verify at most `window` blocks ahead of the one being applied
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Give every proof this long to verify instead of the service default
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Verify and apply `blocks`, which must follow the tip of `follower` in
+    /// height order, until they run out or one fails
+    pub async fn run<S: ChainState>(
+        &self,
+        follower: &mut ChainFollower<S>,
+        blocks: impl Stream<Item = SyncBlock>,
+    ) -> Result<SyncReport, SyncError> {
+        let started = Instant::now();
+        let mut report = SyncReport::default();
+        // buffered hands results back in input order, whatever order they finish in
+        let mut verified = pin!(blocks.map(|block| self.verify(block)).buffered(self.window));
+        while let Some(block) = verified.next().await {
+            let block = block?;
+            let expected = follower.tip().map_or(0, |(height, _)| height + 1);
+            if block.height != expected {
+                return Err(SyncError::OutOfOrder {
+                    expected,
+                    got: block.height,
+                });
+            }
+            follower
+                .extend_verified(block.digest, block.page)
+                .await
+                .map_err(|source| SyncError::Apply {
+                    height: block.height,
+                    source,
+                })?;
+            report.applied += 1;
+            report.verify_time += block.verified_in;
+            debug!("applied block {}", block.height);
+        }
+        report.elapsed = started.elapsed();
+        info!(
+            "synced {} blocks in {:?}, {:?} spent verifying",
+            report.applied, report.elapsed, report.verify_time
+        );
+        Ok(report)
+    }
+
+    async fn verify(&self, block: SyncBlock) -> Result<Verified, SyncError> {
+        let SyncBlock {
+            height,
+            digest,
+            target,
+            page,
+        } = block;
+        let proof = page_proof(&page).ok_or(SyncError::NoProof { height })?;
+        let verdict = self
+            .verifier
+            .verify(proof, self.deadline)
+            .await
+            .map_err(|source| SyncError::Verify { height, source })?;
+        let pow = match verdict.pow {
+            Some(pow) if verdict.valid => pow,
+            _ => return Err(SyncError::InvalidProof { height }),
+        };
+        if pow > target {
+            return Err(SyncError::AboveTarget {
+                height,
+                pow,
+                target,
+            });
+        }
+        Ok(Verified {
+            height,
+            digest,
+            page,
+            verified_in: verdict.verified_in,
+        })
+    }
+}
+
+/// The proof in a page's `pow` field, a unit following the digest
+fn page_proof(page: &NounSlab) -> Option<NounSlab> {
+    let root = unsafe { *page.root() };
+    let pow = root.as_cell().ok()?.tail().as_cell().ok()?.head();
+    let proof = pow.as_cell().ok()?.tail();
+    let mut slab = NounSlab::new();
+    slab.copy_into(proof);
+    Some(slab)
+}
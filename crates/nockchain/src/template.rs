@@ -0,0 +1,112 @@
+//! Block templates pushed from the node to the mining driver.
+//!
+//! The node publishes every new block to mine on through a watch channel
+//! made by [`template_channel`]. Only the newest template matters, which is
+//! all a watch channel keeps, so a miner that falls behind skips straight to
+//! the latest one. A template that arrives while an attempt is running
+//! cancels that attempt at its next safe point rather than letting it finish
+//! proving a block that is already stale.
+
+use ibig::UBig;
+use nockapp::noun::slab::NounSlab;
+use tokio::sync::watch;
+
+use crate::consensus::difficulty::{next_target, BlockTime, DifficultyAlgorithm, DifficultyError};
+use crate::prove_input::ProveBlockInput;
+
+/// A block for the miner to work on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockTemplate {
+    /// Height of the block being mined
+    pub height: u64,
+    /// Proofs must hash below this to be a block
+    pub target: UBig,
+    /// The commitment, nonce and length to prove
+    pub input: ProveBlockInput,
+}
+
+impl BlockTemplate {
+    /// The template for the block after `recent`, which ends with the
+    /// parent, at the target `algorithm` gives it
+    pub fn next(
+        recent: &[BlockTime],
+        algorithm: &dyn DifficultyAlgorithm,
+        input: ProveBlockInput,
+    ) -> Result<Self, DifficultyError> {
+        let target = next_target(algorithm, recent)?;
+        Ok(Self {
+            height: recent.last().map_or(0, |parent| parent.height + 1),
+            target,
+            input,
+        })
+    }
+
+    /// The prove-block cause for this template
+    pub fn candidate(&self) -> NounSlab {
+        self.input.to_noun_slab()
+    }
+}
+
+pub type TemplateSender = watch::Sender<Option<BlockTemplate>>;
+pub type TemplateReceiver = watch::Receiver<Option<BlockTemplate>>;
+
+/// A channel with no template in it yet. The receiver goes in
+/// [`crate::mining::MiningConfig::templates`].
+pub fn template_channel() -> (TemplateSender, TemplateReceiver) {
+    watch::channel(None)
+}
+
+/// Wait for the next template. Returns `None`, and stops watching, once the
+/// sender is gone.
+pub(crate) async fn next_template(
+    templates: &mut Option<TemplateReceiver>,
+) -> Option<BlockTemplate> {
+    let receiver = templates.as_mut()?;
+    loop {
+        if receiver.changed().await.is_err() {
+            *templates = None;
+            return None;
+        }
+        // a cleared template is not worth switching for
+        if let Some(template) = receiver.borrow_and_update().clone() {
+            return Some(template);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(height: u64) -> BlockTemplate {
+        BlockTemplate {
+            height,
+            target: UBig::from(u64::MAX),
+            input: ProveBlockInput::builder()
+                .length(4)
+                .block_commitment(&[height, 2, 3, 4, 5])
+                .nonce(&[6, 7, 8, 9, 10])
+                .build()
+                .unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_next_template_skips_to_newest() {
+        let (sender, receiver) = template_channel();
+        let mut templates = Some(receiver);
+        for height in 1..=3 {
+            sender.send_replace(Some(template(height)));
+        }
+        let newest = next_template(&mut templates).await.unwrap();
+        assert_eq!(newest.height, 3);
+        assert_eq!(
+            ProveBlockInput::try_from(&newest.candidate()).unwrap(),
+            newest.input
+        );
+
+        drop(sender);
+        assert_eq!(next_template(&mut templates).await, None);
+        assert!(templates.is_none());
+    }
+}
@@ -0,0 +1,752 @@
+//! Benchmark timings tracked over time.
+//!
+//! A [`TrendStore`] keeps every timing of a benchmark as a time series per
+//! [`SeriesKey`]: the test, the proof length and the machine it ran on, so
+//! timings from different machines never share a baseline. A new timing is
+//! checked against the rolling baseline of its series, the median of the
+//! last [`TrendConfig::window`] timings before it, and is a regression when
+//! it is more than [`TrendConfig::threshold`] slower.
+//!
+//! The store is one JSON file, [`TREND_FILE`], in the benchmark results
+//! directory.
+//!
+//! [`TrendStore::snapshot`] freezes the baselines of every series as a
+//! [`NamedBaseline`], e.g. `main` before starting on a branch. Later timings
+//! are judged against it with a [`Tolerance`], as an [`Outcome`] of pass,
+//! warn or fail.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Name of the store inside its directory
+pub const TREND_FILE: &str = "trends.json";
+
+/// Subdirectory of the results directory [`NamedBaseline`]s are saved in
+pub const BASELINE_DIR: &str = "baselines";
+
+#[derive(Debug, Error)]
+pub enum TrendError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("bad trend store: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("no baseline named {0}")]
+    MissingBaseline(String),
+}
+
+/// `host-arch-cores`, what a [`SeriesKey`] names a machine by
+pub fn machine_id(host: &str, arch: &str, cores: usize) -> String {
+    format!("{host}-{arch}-{cores}")
+}
+
+/// This machine's host name, `unknown` if it has none
+pub fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// [`machine_id`] of this machine
+pub fn local_machine_id() -> String {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    machine_id(&hostname(), std::env::consts::ARCH, cores)
+}
+
+/// Which series a timing belongs to
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SeriesKey {
+    pub test: String,
+    pub length: u64,
+    pub machine: String,
+}
+
+impl SeriesKey {
+    pub fn new(test: impl Into<String>, length: u64, machine: impl Into<String>) -> Self {
+        Self {
+            test: test.into(),
+            length,
+            machine: machine.into(),
+        }
+    }
+}
+
+impl fmt::Display for SeriesKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} length {} on {}",
+            self.test, self.length, self.machine
+        )
+    }
+}
+
+/// One timing in a series
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    /// Seconds since the unix epoch
+    pub recorded_at: u64,
+    pub secs: f64,
+    pub proof_hash: String,
+    pub git_branch: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrendConfig {
+    /// Timings the rolling baseline is the median of
+    pub window: usize,
+    /// Relative slowdown past the baseline that counts as a regression,
+    /// e.g. `0.1` for 10%. The same speedup counts as an improvement.
+    pub threshold: f64,
+}
+
+impl Default for TrendConfig {
+    fn default() -> Self {
+        Self {
+            window: 5,
+            threshold: 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrendStatus {
+    /// Nothing earlier in the series to compare with
+    NoBaseline,
+    Steady,
+    Improvement,
+    Regression,
+}
+
+/// A timing checked against the baseline of its series
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrendCheck {
+    pub key: SeriesKey,
+    pub sample: Sample,
+    /// Rolling baseline in seconds, and the timings it was taken over
+    pub baseline: Option<(f64, usize)>,
+    pub status: TrendStatus,
+    /// Whether the proof hashes the same as the last one in the series
+    pub proof_match: Option<bool>,
+}
+
+impl TrendCheck {
+    /// Relative change from the baseline, e.g. `-0.25` for 25% faster
+    pub fn time_delta(&self) -> Option<f64> {
+        let (baseline, _) = self.baseline?;
+        (baseline > 0.0).then(|| self.sample.secs / baseline - 1.0)
+    }
+
+    pub fn is_regression(&self) -> bool {
+        self.status == TrendStatus::Regression
+    }
+}
+
+impl fmt::Display for TrendCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:.2}s", self.key, self.sample.secs)?;
+        let (Some((baseline, samples)), Some(delta)) = (self.baseline, self.time_delta()) else {
+            return write!(f, ", no baseline yet");
+        };
+        write!(
+            f,
+            " vs {baseline:.2}s median of {samples} ({:+.1}%)",
+            delta * 100.0
+        )?;
+        match self.status {
+            TrendStatus::Regression => write!(f, ", REGRESSION")?,
+            TrendStatus::Improvement => write!(f, ", improvement")?,
+            TrendStatus::Steady | TrendStatus::NoBaseline => {}
+        }
+        match self.proof_match {
+            Some(true) => write!(f, ", proof matches"),
+            Some(false) => write!(f, ", proof DIFFERS"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// How a timing did against a fixed baseline, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Outcome {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Outcome::Pass => "PASS",
+            Outcome::Warn => "WARN",
+            Outcome::Fail => "FAIL",
+        })
+    }
+}
+
+/// How much slower than its baseline a timing may be, as fractions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// Slowdown past which a timing warns
+    pub warn: f64,
+    /// Slowdown past which a timing fails
+    pub fail: f64,
+}
+
+impl Tolerance {
+    /// Fail past `percent` slower, warn past half of that
+    pub fn percent(percent: f64) -> Self {
+        let fail = percent / 100.0;
+        Self {
+            warn: fail / 2.0,
+            fail,
+        }
+    }
+
+    /// Judge a relative change in time, e.g. `0.07` for 7% slower, and
+    /// whether the proof matched. A changed proof warns whatever the time.
+    pub fn judge(&self, time_delta: f64, proof_match: Option<bool>) -> Outcome {
+        if time_delta > self.fail {
+            Outcome::Fail
+        } else if time_delta > self.warn || proof_match == Some(false) {
+            Outcome::Warn
+        } else {
+            Outcome::Pass
+        }
+    }
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self::percent(5.0)
+    }
+}
+
+/// The baseline of one series in a [`NamedBaseline`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub key: SeriesKey,
+    /// The series' rolling baseline when the snapshot was taken
+    pub secs: f64,
+    /// Timings the baseline was the median of
+    pub runs: usize,
+    /// Hash of the newest proof in the series
+    pub proof_hash: String,
+}
+
+/// Frozen baselines of every series, saved under a name to judge later
+/// timings against
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedBaseline {
+    pub name: String,
+    pub git_branch: Option<String>,
+    /// Seconds since the unix epoch
+    pub recorded_at: u64,
+    pub entries: Vec<BaselineEntry>,
+}
+
+impl NamedBaseline {
+    /// Where the baseline called `name` lives in the results directory `dir`
+    pub fn path(dir: impl AsRef<Path>, name: &str) -> PathBuf {
+        dir.as_ref().join(BASELINE_DIR).join(format!("{name}.json"))
+    }
+
+    /// Save into the [`BASELINE_DIR`] of `dir`, replacing any baseline of
+    /// the same name
+    pub fn save(&self, dir: impl AsRef<Path>) -> Result<PathBuf, TrendError> {
+        let path = Self::path(dir, &self.name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(path)
+    }
+
+    /// Load `baseline`: a file, if it names a JSON file, otherwise the
+    /// baseline of that name in the results directory `dir`
+    pub fn load(dir: impl AsRef<Path>, baseline: &str) -> Result<Self, TrendError> {
+        let file = Path::new(baseline);
+        let path = if file.extension().is_some_and(|ext| ext == "json") {
+            file.to_path_buf()
+        } else {
+            Self::path(dir, baseline)
+        };
+        match fs::read(&path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(TrendError::MissingBaseline(baseline.to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn entry(&self, key: &SeriesKey) -> Option<&BaselineEntry> {
+        self.entries.iter().find(|entry| &entry.key == key)
+    }
+
+    /// Judge `sample` of `key` against this baseline. Series the baseline
+    /// does not have pass, there is nothing to hold them to.
+    pub fn compare(&self, key: &SeriesKey, sample: &Sample, tolerance: Tolerance) -> BaselineCheck {
+        let entry = self.entry(key);
+        let mut check = BaselineCheck {
+            baseline: self.name.clone(),
+            key: key.clone(),
+            sample: sample.clone(),
+            baseline_secs: entry.map(|entry| entry.secs),
+            proof_match: entry.map(|entry| entry.proof_hash == sample.proof_hash),
+            outcome: Outcome::Pass,
+        };
+        if let Some(delta) = check.time_delta() {
+            check.outcome = tolerance.judge(delta, check.proof_match);
+        }
+        check
+    }
+}
+
+/// A timing judged against a [`NamedBaseline`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaselineCheck {
+    /// Name of the baseline
+    pub baseline: String,
+    pub key: SeriesKey,
+    pub sample: Sample,
+    /// The series' time in the baseline, `None` if it has none
+    pub baseline_secs: Option<f64>,
+    pub proof_match: Option<bool>,
+    pub outcome: Outcome,
+}
+
+impl BaselineCheck {
+    /// Relative change from the baseline, e.g. `-0.25` for 25% faster
+    pub fn time_delta(&self) -> Option<f64> {
+        let baseline = self.baseline_secs?;
+        (baseline > 0.0).then(|| self.sample.secs / baseline - 1.0)
+    }
+}
+
+impl fmt::Display for BaselineCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {:.2}s", self.outcome, self.key, self.sample.secs)?;
+        let (Some(baseline), Some(delta)) = (self.baseline_secs, self.time_delta()) else {
+            return write!(f, ", not in baseline {}", self.baseline);
+        };
+        write!(
+            f,
+            " vs {baseline:.2}s in {} ({:+.1}%)",
+            self.baseline,
+            delta * 100.0
+        )?;
+        match self.proof_match {
+            Some(false) => write!(f, ", proof DIFFERS"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Scale from the median absolute deviation to the standard deviation of
+/// normally distributed timings
+const MAD_SCALE: f64 = 1.4826;
+
+/// Timings more than this many scaled MADs from the median are outliers
+const OUTLIER_MADS: f64 = 3.0;
+
+/// Summary of repeated timings of the same benchmark
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimingStats {
+    pub runs: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    /// Sample standard deviation, 0 for a single run
+    pub stddev: f64,
+    /// Mean of the runs that are not outliers: those within three scaled
+    /// median absolute deviations of the median. With no spread around the
+    /// median no run is an outlier.
+    pub trimmed_mean: f64,
+    pub outliers: usize,
+}
+
+impl TimingStats {
+    /// Stats of `secs`, `None` if there are none
+    pub fn new(secs: &[f64]) -> Option<Self> {
+        let mut sorted = secs.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let median = median(&sorted)?;
+        let runs = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / runs as f64;
+        let stddev = if runs > 1 {
+            let square_sum: f64 = sorted.iter().map(|x| (x - mean).powi(2)).sum();
+            (square_sum / (runs - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+
+        let mut deviations: Vec<f64> = sorted.iter().map(|x| (x - median).abs()).collect();
+        deviations.sort_by(f64::total_cmp);
+        let cutoff = OUTLIER_MADS * MAD_SCALE * median_of(&deviations);
+        let kept: Vec<f64> = if cutoff > 0.0 {
+            sorted
+                .iter()
+                .copied()
+                .filter(|x| (x - median).abs() <= cutoff)
+                .collect()
+        } else {
+            sorted.clone()
+        };
+        Some(Self {
+            runs,
+            min: sorted[0],
+            max: sorted[runs - 1],
+            mean,
+            median,
+            stddev,
+            trimmed_mean: kept.iter().sum::<f64>() / kept.len() as f64,
+            outliers: runs - kept.len(),
+        })
+    }
+}
+
+impl fmt::Display for TimingStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "median {:.2}s, mean {:.2}s ± {:.2}s over {} runs",
+            self.median, self.mean, self.stddev, self.runs
+        )?;
+        if self.outliers > 0 {
+            write!(
+                f,
+                ", {:.2}s without {} outliers",
+                self.trimmed_mean, self.outliers
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Median of `sorted`, `None` if it is empty
+fn median(sorted: &[f64]) -> Option<f64> {
+    (!sorted.is_empty()).then(|| median_of(sorted))
+}
+
+fn median_of(sorted: &[f64]) -> f64 {
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Series {
+    key: SeriesKey,
+    samples: Vec<Sample>,
+}
+
+/// Every benchmark timing, by series
+#[derive(Debug)]
+pub struct TrendStore {
+    path: PathBuf,
+    config: TrendConfig,
+    series: BTreeMap<SeriesKey, Vec<Sample>>,
+}
+
+impl TrendStore {
+    /// Open the store in `dir`, empty if there is none yet
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, TrendError> {
+        let path = dir.as_ref().join(TREND_FILE);
+        let series = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice::<Vec<Series>>(&bytes)?
+                .into_iter()
+                .map(|series| (series.key, series.samples))
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            config: TrendConfig::default(),
+            series,
+        })
+    }
+
+    pub fn with_config(mut self, config: TrendConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &TrendConfig {
+        &self.config
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &SeriesKey> {
+        self.series.keys()
+    }
+
+    /// The timings of `key`, oldest first
+    pub fn series(&self, key: &SeriesKey) -> &[Sample] {
+        self.series.get(key).map_or(&[][..], Vec::as_slice)
+    }
+
+    /// Median of the last [`TrendConfig::window`] timings of `key`, and how
+    /// many timings that was
+    pub fn baseline(&self, key: &SeriesKey) -> Option<(f64, usize)> {
+        self.baseline_of(self.series(key))
+    }
+
+    fn baseline_of(&self, samples: &[Sample]) -> Option<(f64, usize)> {
+        let window = &samples[samples.len().saturating_sub(self.config.window)..];
+        let mut secs: Vec<f64> = window.iter().map(|sample| sample.secs).collect();
+        secs.sort_by(f64::total_cmp);
+        Some((median(&secs)?, secs.len()))
+    }
+
+    /// Check `sample` against the baseline of `key`, without recording it
+    pub fn check(&self, key: &SeriesKey, sample: &Sample) -> TrendCheck {
+        self.check_against(key, self.series(key), sample)
+    }
+
+    /// The newest timing of `key` checked against those before it, as
+    /// [`Self::record`] checked it
+    pub fn latest(&self, key: &SeriesKey) -> Option<TrendCheck> {
+        let (latest, earlier) = self.series(key).split_last()?;
+        Some(self.check_against(key, earlier, latest))
+    }
+
+    fn check_against(&self, key: &SeriesKey, earlier: &[Sample], sample: &Sample) -> TrendCheck {
+        let proof_match = earlier
+            .last()
+            .map(|last| last.proof_hash == sample.proof_hash);
+        let mut check = TrendCheck {
+            key: key.clone(),
+            sample: sample.clone(),
+            baseline: self.baseline_of(earlier),
+            status: TrendStatus::NoBaseline,
+            proof_match,
+        };
+        if let Some(delta) = check.time_delta() {
+            check.status = if delta > self.config.threshold {
+                TrendStatus::Regression
+            } else if delta < -self.config.threshold {
+                TrendStatus::Improvement
+            } else {
+                TrendStatus::Steady
+            };
+        }
+        check
+    }
+
+    /// Freeze the current baseline of every series as `name`
+    pub fn snapshot(&self, name: &str, git_branch: Option<String>) -> NamedBaseline {
+        let entries = self
+            .series
+            .iter()
+            .filter_map(|(key, samples)| {
+                let (secs, runs) = self.baseline_of(samples)?;
+                Some(BaselineEntry {
+                    key: key.clone(),
+                    secs,
+                    runs,
+                    proof_hash: samples.last()?.proof_hash.clone(),
+                })
+            })
+            .collect();
+        NamedBaseline {
+            name: name.to_string(),
+            git_branch,
+            recorded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            entries,
+        }
+    }
+
+    /// [`Self::check`] `sample`, then add it to its series
+    pub fn record(&mut self, key: SeriesKey, sample: Sample) -> TrendCheck {
+        let check = self.check(&key, &sample);
+        let samples = self.series.entry(key).or_default();
+        // keep each series in time order, whatever order results arrive in
+        let at = samples.partition_point(|s| s.recorded_at <= sample.recorded_at);
+        samples.insert(at, sample);
+        check
+    }
+
+    pub fn save(&self) -> Result<(), TrendError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let series: Vec<_> = self
+            .series
+            .iter()
+            .map(|(key, samples)| Series {
+                key: key.clone(),
+                samples: samples.clone(),
+            })
+            .collect();
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&series)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(recorded_at: u64, secs: f64, proof_hash: &str) -> Sample {
+        Sample {
+            recorded_at,
+            secs,
+            proof_hash: proof_hash.to_string(),
+            git_branch: None,
+        }
+    }
+
+    #[test]
+    fn test_rolling_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = SeriesKey::new("minimal", 2, "host-x86_64-8");
+        let mut store = TrendStore::open(dir.path())
+            .unwrap()
+            .with_config(TrendConfig {
+                window: 3,
+                threshold: 0.1,
+            });
+        assert_eq!(
+            store.record(key.clone(), sample(1, 100.0, "a")).status,
+            TrendStatus::NoBaseline
+        );
+        // an outlier early on drops out of the window
+        for (at, secs) in [(2, 500.0), (3, 10.0), (4, 11.0), (5, 9.0)] {
+            store.record(key.clone(), sample(at, secs, "a"));
+        }
+        assert_eq!(store.baseline(&key), Some((10.0, 3)));
+
+        let steady = store.check(&key, &sample(6, 10.5, "a"));
+        assert_eq!(
+            (steady.status, steady.proof_match),
+            (TrendStatus::Steady, Some(true))
+        );
+        let slow = store.check(&key, &sample(6, 12.0, "b"));
+        assert_eq!(
+            (slow.status, slow.proof_match),
+            (TrendStatus::Regression, Some(false))
+        );
+        let mut recorded = TrendStore::open(dir.path())
+            .unwrap()
+            .with_config(*store.config());
+        recorded.series = store.series.clone();
+        assert_eq!(recorded.record(key.clone(), slow.sample.clone()), slow);
+        assert_eq!(recorded.latest(&key), Some(slow.clone()));
+        assert!(slow.to_string().contains("REGRESSION"));
+        let fast = store.check(&key, &sample(6, 8.0, "a"));
+        assert_eq!(fast.status, TrendStatus::Improvement);
+
+        // other machines keep their own baselines
+        let other = SeriesKey::new("minimal", 2, "other-aarch64-4");
+        assert_eq!(
+            store.check(&other, &sample(6, 12.0, "a")).status,
+            TrendStatus::NoBaseline
+        );
+
+        store.save().unwrap();
+        let reopened = TrendStore::open(dir.path()).unwrap();
+        assert_eq!(reopened.series(&key), store.series(&key));
+        assert_eq!(reopened.keys().count(), 1);
+    }
+
+    #[test]
+    fn test_timing_stats() {
+        assert_eq!(TimingStats::new(&[]), None);
+        let one = TimingStats::new(&[4.0]).unwrap();
+        assert_eq!((one.median, one.stddev, one.outliers), (4.0, 0.0, 0));
+
+        // the 60s run was a machine hiccup
+        let stats = TimingStats::new(&[10.0, 11.0, 60.0, 9.0, 10.0]).unwrap();
+        assert_eq!((stats.runs, stats.min, stats.max), (5, 9.0, 60.0));
+        assert_eq!((stats.median, stats.mean), (10.0, 20.0));
+        assert!((stats.stddev - 22.37).abs() < 0.01);
+        assert_eq!((stats.trimmed_mean, stats.outliers), (10.0, 1));
+
+        // no spread, nothing to call an outlier
+        let flat = TimingStats::new(&[5.0, 5.0, 5.0, 7.0]).unwrap();
+        assert_eq!(
+            (flat.median, flat.trimmed_mean, flat.outliers),
+            (5.0, 5.5, 0)
+        );
+    }
+
+    #[test]
+    fn test_named_baselines() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = SeriesKey::new("minimal", 2, "host-x86_64-8");
+        let mut store = TrendStore::open(dir.path()).unwrap();
+        for (at, secs) in [(1, 9.0), (2, 10.0), (3, 11.0)] {
+            store.record(key.clone(), sample(at, secs, "a"));
+        }
+        let path = store
+            .snapshot("main", Some("master".to_string()))
+            .save(dir.path())
+            .unwrap();
+        assert_eq!(path, NamedBaseline::path(dir.path(), "main"));
+        let main = NamedBaseline::load(dir.path(), "main").unwrap();
+        assert_eq!(
+            main,
+            NamedBaseline::load("elsewhere", path.to_str().unwrap()).unwrap()
+        );
+        assert_eq!(
+            main.entry(&key).map(|entry| (entry.secs, entry.runs)),
+            Some((10.0, 3))
+        );
+        assert!(matches!(
+            NamedBaseline::load(dir.path(), "nope"),
+            Err(TrendError::MissingBaseline(name)) if name == "nope"
+        ));
+
+        let tolerance = Tolerance::percent(10.0);
+        let outcome = |secs, hash| {
+            main.compare(&key, &sample(4, secs, hash), tolerance)
+                .outcome
+        };
+        assert_eq!(outcome(10.4, "a"), Outcome::Pass);
+        assert_eq!(outcome(8.0, "b"), Outcome::Warn);
+        assert_eq!(outcome(10.6, "a"), Outcome::Warn);
+        assert_eq!(outcome(11.2, "a"), Outcome::Fail);
+        let check = main.compare(&key, &sample(4, 11.2, "a"), tolerance);
+        assert!(check.to_string().starts_with("FAIL minimal length 2"));
+
+        let new = SeriesKey::new("single", 64, "host-x86_64-8");
+        let unknown = main.compare(&new, &sample(4, 500.0, "a"), tolerance);
+        assert_eq!(
+            (unknown.outcome, unknown.baseline_secs),
+            (Outcome::Pass, None)
+        );
+        assert!(Outcome::Fail > Outcome::Warn && Outcome::Warn > Outcome::Pass);
+    }
+
+    #[test]
+    fn test_series_stay_in_time_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = SeriesKey::new("single", 64, "host-x86_64-8");
+        let mut store = TrendStore::open(dir.path()).unwrap();
+        for at in [3, 1, 2] {
+            store.record(key.clone(), sample(at, at as f64, "a"));
+        }
+        let times: Vec<_> = store.series(&key).iter().map(|s| s.recorded_at).collect();
+        assert_eq!(times, [1, 2, 3]);
+    }
+}
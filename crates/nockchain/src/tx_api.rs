@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::Stream;
+use nockapp::driver::{make_driver, IODriverFn, NockAppHandle, PokeResult};
+use nockapp::noun::slab::NounSlab;
+use nockapp::noun::AtomExt;
+use nockapp::utils::make_tas;
+use nockapp::utils::scry::ScryResult;
+use nockapp::wire::{Wire, WireRepr};
+use nockapp::NockAppError;
+use nockvm::noun::{Atom, Noun, D, T};
+use nockvm_macros::tas;
+use serde::Serialize;
+use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
+use tracing::{debug, info, warn};
+use zkvm_jetpack::form::address::{self, Digest};
+
+use crate::events::EventBus;
+use crate::light_client::block_id_from_noun;
+use crate::peek::{MinerPeek, MinerState};
+
+/// Version of the `%fact` poke the kernel expects for heard transactions
+const POKE_VERSION: u64 = 0;
+
+pub enum TxApiWire {
+    Submit,
+}
+
+impl Wire for TxApiWire {
+    const VERSION: u64 = 1;
+    const SOURCE: &'static str = "tx-api";
+
+    fn to_wire(&self) -> WireRepr {
+        let tags = match self {
+            TxApiWire::Submit => vec!["submit".into()],
+        };
+        WireRepr::new(TxApiWire::SOURCE, TxApiWire::VERSION, tags)
+    }
+}
+
+/// Lifecycle of a transaction submitted through the API
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum TxStatus {
+    /// The kernel accepted the transaction into its mempool
+    Accepted,
+    /// The transaction has been included in a block at `height`
+    Confirmed { height: u64 },
+    /// The transaction was refused, with a human readable reason
+    Rejected { reason: String },
+    /// We have no record of this transaction
+    Unknown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubmitResponse {
+    pub tx_id: Option<String>,
+    #[serde(flatten)]
+    pub status: TxStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatusResponse {
+    pub tx_id: String,
+    #[serde(flatten)]
+    pub status: TxStatus,
+}
+
+type Responder<T> = oneshot::Sender<T>;
+
+/// Requests forwarded from the HTTP handlers to the driver task, which owns the handle
+enum TxApiRequest {
+    Submit {
+        jam: Bytes,
+        resp: Responder<SubmitResponse>,
+    },
+    Status {
+        tx_id: String,
+        digest: Digest,
+        resp: Responder<StatusResponse>,
+    },
+    Mining {
+        resp: Responder<Option<MinerState>>,
+    },
+}
+
+/// Statuses of transactions submitted through this node, keyed by tx id address
+pub type TxStatusTable = Arc<RwLock<HashMap<String, TxStatus>>>;
+
+/// Transaction submission and status driver.
+///
+/// Serves four endpoints on `bind`:
+/// * `POST /sendrawtransaction` - body is a jammed raw transaction noun
+/// * `GET /gettransactionstatus/{tx_id}` - tx id is the address of the TIP5 hash
+/// * `GET /miningstatus` - the [`MinerState`] from `miner`
+/// * `GET /events` - server-sent events, one JSON [`crate::events::NodeEvent`]
+///   per event published on `events` while connected
+///
+/// Submissions return the transaction id as an address, see [`address`].
+/// Status takes either an address or the kernel's unchecked base58 id, which
+/// both endpoints used before addresses, and answers with the id as given.
+pub fn tx_api_driver(bind: SocketAddr, miner: MinerPeek, events: EventBus) -> IODriverFn {
+    make_driver(move |handle| async move {
+        let (tx, mut rx) = mpsc::channel::<TxApiRequest>(64);
+        let app = Router::new()
+            .route("/sendrawtransaction", post(send_raw_transaction))
+            .route("/gettransactionstatus/{tx_id}", get(get_transaction_status))
+            .route("/miningstatus", get(get_mining_status))
+            .route("/events", get(stream_events).with_state(events))
+            .with_state(tx);
+
+        let listener = tokio::net::TcpListener::bind(bind)
+            .await
+            .map_err(|_| NockAppError::OtherError)?;
+        info!("transaction api listening on {}", bind);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app.into_make_service()).await {
+                warn!("transaction api server exited: {e:?}");
+            }
+        });
+
+        let statuses: TxStatusTable = Arc::new(RwLock::new(HashMap::new()));
+        while let Some(req) = rx.recv().await {
+            match req {
+                TxApiRequest::Submit { jam, resp } => {
+                    let res = submit_raw_transaction(&handle, &statuses, jam).await;
+                    let _ = resp.send(res);
+                }
+                TxApiRequest::Status {
+                    tx_id,
+                    digest,
+                    resp,
+                } => {
+                    let status = transaction_status(&handle, &statuses, &digest).await;
+                    let _ = resp.send(StatusResponse { tx_id, status });
+                }
+                TxApiRequest::Mining { resp } => {
+                    let state = match miner.state(&handle).await {
+                        Ok(state) => Some(state),
+                        Err(e) => {
+                            warn!("failed to peek mining state: {e}");
+                            None
+                        }
+                    };
+                    let _ = resp.send(state);
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+async fn send_raw_transaction(
+    State(tx): State<mpsc::Sender<TxApiRequest>>,
+    jam: Bytes,
+) -> Result<Json<SubmitResponse>, StatusCode> {
+    let (resp, rx) = oneshot::channel();
+    tx.send(TxApiRequest::Submit { jam, resp })
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    rx.await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn get_transaction_status(
+    State(tx): State<mpsc::Sender<TxApiRequest>>,
+    Path(tx_id): Path<String>,
+) -> Result<Json<StatusResponse>, StatusCode> {
+    let digest = parse_tx_id(&tx_id).ok_or(StatusCode::BAD_REQUEST)?;
+    let (resp, rx) = oneshot::channel();
+    tx.send(TxApiRequest::Status {
+        tx_id,
+        digest,
+        resp,
+    })
+    .await
+    .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    rx.await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn get_mining_status(
+    State(tx): State<mpsc::Sender<TxApiRequest>>,
+) -> Result<Json<MinerState>, StatusCode> {
+    let (resp, rx) = oneshot::channel();
+    tx.send(TxApiRequest::Mining { resp })
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    rx.await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(Json)
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn stream_events(
+    State(events): State<EventBus>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let stream = futures::stream::unfold(events.subscribe(), |mut events| async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    let sse = Event::default().event(event.name()).json_data(&event);
+                    return Some((sse, events));
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    debug!("event stream client missed {missed} events");
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Cue a jammed raw transaction, poke it into the kernel as a heard tx and record the outcome
+async fn submit_raw_transaction(
+    handle: &NockAppHandle,
+    statuses: &TxStatusTable,
+    jam: Bytes,
+) -> SubmitResponse {
+    let mut slab = NounSlab::new();
+    let raw_tx = match slab.cue_into(jam) {
+        Ok(noun) => noun,
+        Err(e) => {
+            return SubmitResponse {
+                tx_id: None,
+                status: TxStatus::Rejected {
+                    reason: format!("could not cue transaction: {e:?}"),
+                },
+            }
+        }
+    };
+    let tx_id = match raw_tx_id(raw_tx) {
+        Ok(id) => address::encode(&id),
+        Err(e) => {
+            return SubmitResponse {
+                tx_id: None,
+                status: TxStatus::Rejected {
+                    reason: format!("malformed raw transaction: {e:?}"),
+                },
+            }
+        }
+    };
+
+    let heard_tx = make_tas(&mut slab, "heard-tx").as_noun();
+    let poke = T(
+        &mut slab,
+        &[D(tas!(b"fact")), D(POKE_VERSION), heard_tx, raw_tx],
+    );
+    slab.set_root(poke);
+
+    let status = match handle.poke(TxApiWire::Submit.to_wire(), slab).await {
+        Ok(PokeResult::Ack) => TxStatus::Accepted,
+        Ok(PokeResult::Nack) => TxStatus::Rejected {
+            reason: "transaction rejected by kernel".to_string(),
+        },
+        Err(e) => TxStatus::Rejected {
+            reason: format!("could not submit transaction: {e:?}"),
+        },
+    };
+    debug!("submitted transaction {}: {:?}", tx_id, status);
+    statuses.write().await.insert(tx_id.clone(), status.clone());
+
+    SubmitResponse {
+        tx_id: Some(tx_id),
+        status,
+    }
+}
+
+/// Look up a transaction, asking the kernel for its confirmation height if it is not yet known
+async fn transaction_status(
+    handle: &NockAppHandle,
+    statuses: &TxStatusTable,
+    digest: &Digest,
+) -> TxStatus {
+    let tx_id = address::encode(digest);
+    let known = statuses.read().await.get(&tx_id).cloned();
+    if let Some(status) = &known {
+        if *status != TxStatus::Accepted {
+            return status.clone();
+        }
+    }
+
+    match confirmation_height(handle, digest).await {
+        Ok(Some(height)) => {
+            let status = TxStatus::Confirmed { height };
+            statuses.write().await.insert(tx_id, status.clone());
+            status
+        }
+        Ok(None) => known.unwrap_or(TxStatus::Unknown),
+        Err(e) => {
+            warn!("failed to peek confirmation height for {}: {e:?}", tx_id);
+            known.unwrap_or(TxStatus::Unknown)
+        }
+    }
+}
+
+/// Peek `[%transaction-height base58-tx-id ~]`, which the kernel answers with the
+/// height of the block on the heaviest chain that included the transaction, or
+/// `[~ ~]` while it is unconfirmed.
+async fn confirmation_height(
+    handle: &NockAppHandle,
+    digest: &Digest,
+) -> Result<Option<u64>, NockAppError> {
+    let mut slab = NounSlab::new();
+    let tag = make_tas(&mut slab, "transaction-height").as_noun();
+    let id_atom = Atom::from_value(&mut slab, address::to_kernel_base58(digest))?;
+    let path = T(&mut slab, &[tag, id_atom.as_noun(), D(0)]);
+    slab.set_root(path);
+
+    let Some(res) = handle.peek(slab).await? else {
+        return Ok(None);
+    };
+    match ScryResult::from(unsafe { res.root() }) {
+        ScryResult::Some(height) => Ok(Some(height.as_atom()?.as_u64()?)),
+        ScryResult::Nothing => Ok(None),
+        ScryResult::BadPath | ScryResult::Invalid => Err(NockAppError::PeekFailed),
+    }
+}
+
+/// A tx id given as an address, or as the kernel's base58 name for it. The
+/// two never collide: an address is longer than any digest's kernel name.
+fn parse_tx_id(tx_id: &str) -> Option<Digest> {
+    address::decode(tx_id)
+        .or_else(|_| address::from_kernel_base58(tx_id))
+        .ok()
+}
+
+/// A raw transaction is `[id tx]`, where `id` is the TIP5 hash of the transaction
+fn raw_tx_id(raw_tx: Noun) -> Result<Digest, NockAppError> {
+    block_id_from_noun(raw_tx.as_cell()?.head()).map_err(|_| NockAppError::OtherError)
+}
@@ -0,0 +1,173 @@
+//! Per-worker share difficulty for pools.
+//!
+//! A fixed share target floods the pool with shares from fast workers and
+//! leaves slow ones going minutes without credit. [`Vardiff`] counts each
+//! worker's shares over [`VardiffConfig::retarget_interval`] and moves the
+//! worker's target so it submits about [`VardiffConfig::shares_per_minute`]:
+//! a smaller target when it submits too many, a larger one when it submits
+//! too few. Whenever a worker's target changes the pool sends it a
+//! [`crate::pool::PoolMessage::SetTarget`].
+//!
+//! Every method takes the current time, so retargeting can be driven from a
+//! timer in the pool and from synthetic clocks in tests.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use ibig::UBig;
+
+/// Scale of the fixed point factor targets are multiplied by
+const FACTOR_ONE: u64 = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VardiffConfig {
+    /// The share rate every worker is steered towards
+    pub shares_per_minute: f64,
+    /// How long shares are counted before a worker is retargeted
+    pub retarget_interval: Duration,
+    /// Rates within this fraction of the desired rate keep their target
+    pub tolerance: f64,
+    /// Most a target moves in one retarget, as a factor either way
+    pub max_step: f64,
+    /// Target a newly registered worker starts at
+    pub initial_target: UBig,
+    /// Hardest target handed out, a share this hard is already a block
+    pub min_target: UBig,
+    /// Easiest target handed out
+    pub max_target: UBig,
+}
+
+impl VardiffConfig {
+    /// Six shares a minute, starting workers at `initial_target`. Targets go
+    /// no harder than `network_target` and no easier than 256 times the
+    /// initial target.
+    pub fn new(initial_target: UBig, network_target: UBig) -> Self {
+        Self {
+            shares_per_minute: 6.0,
+            retarget_interval: Duration::from_secs(90),
+            tolerance: 0.3,
+            max_step: 4.0,
+            max_target: &initial_target << 8,
+            min_target: network_target,
+            initial_target,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct WorkerState {
+    target: UBig,
+    window_start: Instant,
+    shares: u64,
+}
+
+/// Tracks the share rate and target of every worker of a pool
+#[derive(Debug)]
+pub struct Vardiff {
+    config: VardiffConfig,
+    workers: Mutex<HashMap<String, WorkerState>>,
+}
+
+impl Vardiff {
+    pub fn new(config: VardiffConfig) -> Self {
+        Self {
+            config,
+            workers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &VardiffConfig {
+        &self.config
+    }
+
+    /// Start tracking `worker`, returning the target to send it. A worker
+    /// that was already registered keeps its target.
+    pub fn register(&self, worker: &str, now: Instant) -> UBig {
+        self.workers()
+            .entry(worker.to_string())
+            .or_insert_with(|| WorkerState {
+                target: self.clamp(self.config.initial_target.clone()),
+                window_start: now,
+                shares: 0,
+            })
+            .target
+            .clone()
+    }
+
+    /// Stop tracking a worker that disconnected
+    pub fn remove(&self, worker: &str) {
+        self.workers().remove(worker);
+    }
+
+    /// The target `worker` is mining at, `None` if it is not registered
+    pub fn target(&self, worker: &str) -> Option<UBig> {
+        self.workers().get(worker).map(|state| state.target.clone())
+    }
+
+    /// Count an accepted share from `worker`. Returns its new target if this
+    /// share closed a window and the target moved.
+    pub fn record_share(&self, worker: &str, now: Instant) -> Option<UBig> {
+        let mut workers = self.workers();
+        let state = workers.get_mut(worker)?;
+        state.shares += 1;
+        self.retarget(state, now)
+    }
+
+    /// Retarget every worker whose window has closed, including workers that
+    /// stopped submitting shares altogether. Returns the workers whose
+    /// target moved along with their new target.
+    pub fn tick(&self, now: Instant) -> Vec<(String, UBig)> {
+        let mut workers = self.workers();
+        let mut moved: Vec<(String, UBig)> = workers
+            .iter_mut()
+            .filter_map(|(worker, state)| {
+                self.retarget(state, now)
+                    .map(|target| (worker.clone(), target))
+            })
+            .collect();
+        moved.sort_by(|a, b| a.0.cmp(&b.0));
+        moved
+    }
+
+    fn retarget(&self, state: &mut WorkerState, now: Instant) -> Option<UBig> {
+        let elapsed = now.saturating_duration_since(state.window_start);
+        if elapsed < self.config.retarget_interval {
+            return None;
+        }
+        let rate = state.shares as f64 * 60.0 / elapsed.as_secs_f64();
+        state.window_start = now;
+        state.shares = 0;
+
+        let ratio = rate / self.config.shares_per_minute;
+        if (ratio - 1.0).abs() <= self.config.tolerance {
+            return None;
+        }
+        // too many shares means the target has to get smaller, and the other way round
+        let factor = (1.0 / ratio).clamp(1.0 / self.config.max_step, self.config.max_step);
+        let scaled = &state.target * UBig::from((factor * FACTOR_ONE as f64) as u64)
+            / UBig::from(FACTOR_ONE);
+        let target = self.clamp(scaled);
+        if target == state.target {
+            return None;
+        }
+        state.target = target.clone();
+        Some(target)
+    }
+
+    fn clamp(&self, target: UBig) -> UBig {
+        if target < self.config.min_target {
+            self.config.min_target.clone()
+        } else if target > self.config.max_target {
+            self.config.max_target.clone()
+        } else {
+            target
+        }
+    }
+
+    fn workers(&self) -> std::sync::MutexGuard<'_, HashMap<String, WorkerState>> {
+        self.workers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,650 @@
+//! Verifying STARK proofs of work outside the node.
+//!
+//! [`KernelVerifierBackend`] boots the verifier kernel, which pokes back
+//! `[%verified ok=? pow=(unit @ux)]` for a `[%verify proof]` cause, `pow`
+//! being the hash of a valid proof that mining targets are compared against. [`VerificationService`]
+//! sits in front of a backend when verification is exposed to other
+//! processes: it bounds how many kernels run at once, refuses new requests
+//! once too many are already waiting, and gives every request a deadline.
+//!
+//! A request that runs past its deadline cancels its kernel through the
+//! kernel's cancel token rather than leaving it to grind through an
+//! adversarial proof, and fails with [`VerifyError::Timeout`].
+//!
+//! Memory is budgeted the same way. Proofs larger than
+//! [`VerifierConfig::max_proof_bytes`] are refused up front, each kernel's
+//! NockStack is capped at [`VerifierConfig::max_stack_words`], and a kernel
+//! that runs out of stack fails its request with
+//! [`VerifyError::ResourceExhausted`] instead of taking the node down.
+//!
+//! [`serve_stream`] and [`serve_unix`] feed the service from a byte stream
+//! for pipelines and pool backends: each proof is a jam in a
+//! [`crate::frame`], and each outcome is written back as a [`StreamVerdict`]
+//! JSON line.
+
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
+use ibig::UBig;
+use kernels::verifier::KERNEL;
+use nockapp::kernel::checkpoint::JamPaths;
+use nockapp::kernel::form::{Entropy, Kernel};
+use nockapp::noun::slab::{CueError, NounSlab};
+use nockapp::noun::NounExt;
+use nockapp::wire::{Wire, WireRepr};
+use nockapp::CrownError;
+use nockvm::noun::{D, T};
+use nockvm_macros::tas;
+use serde::Serialize;
+use tempfile::{tempdir, TempDir};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::UnixListener;
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use tracing::{debug, info, warn, Instrument};
+
+use crate::effect::{MiningEffectError, ProofView, Puzzle};
+use crate::events::{EventBus, NodeEvent};
+use crate::frame::{read_frame, FrameTooLarge};
+use crate::observer::{PokeComplete, PokeKind, PokeObserver, PokeObservers, PokeStart};
+use crate::profiling::{profile_span, ProfilePhase};
+use crate::stack::{format_words, is_out_of_memory, StackSize};
+
+pub enum VerifierWire {
+    Verify,
+}
+
+impl Wire for VerifierWire {
+    const VERSION: u64 = 1;
+    const SOURCE: &'static str = "verifier";
+
+    fn to_wire(&self) -> WireRepr {
+        WireRepr::new(
+            VerifierWire::SOURCE,
+            VerifierWire::VERSION,
+            vec!["verify".into()],
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum VerifyError {
+    #[error("{queued} requests already waiting, the limit is {limit}")]
+    Overloaded { queued: usize, limit: usize },
+    #[error("verification timed out after {after:?}")]
+    Timeout { after: Duration },
+    #[error("verification service is shut down")]
+    Closed,
+    #[error("malformed proof: {0}")]
+    Proof(#[from] MiningEffectError),
+    #[error("verifier kernel failed: {0}")]
+    Kernel(#[from] CrownError),
+    #[error("could not read proof: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("proof is not a jammed noun: {0}")]
+    Cue(#[from] CueError),
+    #[error("verifier kernel did not produce a %verified effect")]
+    MissingVerdict,
+    #[error("verification exceeded its budget: {0}")]
+    ResourceExhausted(Resource),
+}
+
+/// The budget a [`VerifyError::ResourceExhausted`] request ran over
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    #[error("kernel ran out of its {} NockStack", format_words(*words))]
+    NockStack { words: usize },
+    #[error("proof is {bytes} bytes, the limit is {limit}")]
+    ProofSize { bytes: usize, limit: usize },
+}
+
+impl From<FrameTooLarge> for VerifyError {
+    fn from(FrameTooLarge { bytes, limit }: FrameTooLarge) -> Self {
+        VerifyError::ResourceExhausted(Resource::ProofSize { bytes, limit })
+    }
+}
+
+/// Largest proof accepted unless configured otherwise. The kernel caps a
+/// jammed proof at 125 kB, and this leaves room for one cued into a slab.
+pub const DEFAULT_MAX_PROOF_BYTES: usize = 64 << 20;
+
+/// How long a cancelled kernel gets to unwind before it is dropped anyway
+const CANCEL_GRACE: Duration = Duration::from_secs(10);
+
+/// Something that can load proof verifiers
+pub trait VerifierBackend: Send + Sync {
+    /// Start a verifier with a NockStack of `stack_words`
+    fn load(&self, stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Verifier>, CrownError>>;
+}
+
+/// What a verifier found out about a proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofCheck {
+    pub valid: bool,
+    /// The proof hash that mining targets are compared against, only
+    /// computed for valid proofs
+    pub pow: Option<UBig>,
+}
+
+impl ProofCheck {
+    pub fn valid(pow: UBig) -> Self {
+        Self {
+            valid: true,
+            pow: Some(pow),
+        }
+    }
+
+    pub fn invalid() -> Self {
+        Self {
+            valid: false,
+            pow: None,
+        }
+    }
+}
+
+/// A loaded verifier, good for one or more verify calls
+pub trait Verifier: Send + Sync {
+    /// Check the proof at the root of `proof`
+    fn verify(
+        &self,
+        proof: NounSlab,
+        entropy: Entropy,
+    ) -> BoxFuture<'static, Result<ProofCheck, VerifyError>>;
+
+    /// Interrupt an in-flight verify. Returns false if nothing was running.
+    fn cancel(&self) -> bool;
+}
+
+/// Verifies with the verifier kernel, booted fresh for every request
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KernelVerifierBackend;
+
+struct KernelVerifier {
+    kernel: Kernel,
+    // the kernel's snapshot directory, removed when the verifier is dropped
+    _snapshot_dir: TempDir,
+}
+
+impl VerifierBackend for KernelVerifierBackend {
+    fn load(&self, stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Verifier>, CrownError>> {
+        Box::pin(async move {
+            let snapshot_dir = tokio::task::spawn_blocking(tempdir).await??;
+            let hot_state = zkvm_jetpack::hot::produce_prover_hot_state();
+            let jam_paths = JamPaths::new(snapshot_dir.path());
+            let kernel = Kernel::load_with_stack_size(
+                snapshot_dir.path().to_path_buf(),
+                jam_paths,
+                KERNEL,
+                &hot_state,
+                stack_words,
+                false,
+            )
+            .await?;
+            Ok(Box::new(KernelVerifier {
+                kernel,
+                _snapshot_dir: snapshot_dir,
+            }) as Box<dyn Verifier>)
+        })
+    }
+}
+
+impl Verifier for KernelVerifier {
+    fn verify(
+        &self,
+        proof: NounSlab,
+        entropy: Entropy,
+    ) -> BoxFuture<'static, Result<ProofCheck, VerifyError>> {
+        let poke = self.kernel.poke_with_entropy(
+            VerifierWire::Verify.to_wire(),
+            verify_cause(&proof),
+            entropy,
+        );
+        Box::pin(async move { verdict(&poke.await?) })
+    }
+
+    fn cancel(&self) -> bool {
+        self.kernel.cancel_token().cancel()
+    }
+}
+
+/// The `[%verify proof]` cause of a verify poke, for the verifier kernel
+/// and the miner kernel's [`crate::mining::MiningWire::Verify`] alike
+pub fn verify_cause(proof: &NounSlab) -> NounSlab {
+    let mut cause = NounSlab::new();
+    cause.copy_into(unsafe { *proof.root() });
+    let proof = unsafe { *cause.root() };
+    let verify = T(&mut cause, &[D(tas!(b"verify")), proof]);
+    cause.set_root(verify);
+    cause
+}
+
+/// Read the `[%verified ok=? pow=(unit @ux)]` among a verify poke's effects
+pub(crate) fn verdict(effects: &NounSlab) -> Result<ProofCheck, VerifyError> {
+    effects
+        .to_vec()
+        .iter()
+        .find_map(|effect| {
+            let cell = unsafe { effect.root() }.as_cell().ok()?;
+            if !cell.head().eq_bytes("verified") {
+                return None;
+            }
+            let verdict = cell.tail().as_cell().ok()?;
+            // loobeans: & is 0, | is 1
+            let valid = match verdict.head().as_atom().ok()?.as_u64().ok()? {
+                0 => true,
+                1 => false,
+                _ => return None,
+            };
+            // a unit is ~ or [~ value]
+            let pow = match verdict.tail().as_cell() {
+                Ok(some) => Some(UBig::from_le_bytes(
+                    &some.tail().as_atom().ok()?.to_le_bytes(),
+                )),
+                Err(_) => None,
+            };
+            Some(ProofCheck { valid, pow })
+        })
+        .ok_or(VerifyError::MissingVerdict)
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifierConfig {
+    /// Verifier kernels allowed to run at once
+    pub max_concurrent: usize,
+    /// Requests allowed to wait for a free kernel before new ones are refused
+    pub max_queued: usize,
+    /// Deadline for requests that do not bring their own, counted from
+    /// when the request arrives, so time spent queued counts against it
+    pub default_deadline: Duration,
+    pub stack_size: StackSize,
+    /// Upper bound on the NockStack of any one kernel, whatever
+    /// [`Self::stack_size`] asks for
+    pub max_stack_words: Option<usize>,
+    /// Largest proof accepted, measured as the jam for files and as
+    /// allocated slab memory for nouns
+    pub max_proof_bytes: usize,
+    pub entropy: Entropy,
+    /// Told about every verify poke
+    pub observers: PokeObservers,
+    /// Told about every verification that runs to completion
+    pub events: EventBus,
+}
+
+impl Default for VerifierConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 2,
+            max_queued: 16,
+            default_deadline: Duration::from_secs(300),
+            stack_size: StackSize::default(),
+            max_stack_words: None,
+            max_proof_bytes: DEFAULT_MAX_PROOF_BYTES,
+            entropy: Entropy::Random,
+            observers: PokeObservers::default(),
+            events: EventBus::default(),
+        }
+    }
+}
+
+/// The outcome of a verification request that ran to completion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verdict {
+    pub valid: bool,
+    /// Hash of a valid proof, see [`ProofCheck::pow`]
+    pub pow: Option<UBig>,
+    /// The puzzle the proof claims to solve
+    pub puzzle: Puzzle,
+    /// Time spent waiting for a free kernel
+    pub queued_for: Duration,
+    /// Time spent loading the kernel and verifying
+    pub verified_in: Duration,
+}
+
+/// Admission control in front of a [`VerifierBackend`]
+pub struct VerificationService {
+    backend: Arc<dyn VerifierBackend>,
+    config: VerifierConfig,
+    permits: Arc<Semaphore>,
+    queued: AtomicUsize,
+}
+
+impl VerificationService {
+    pub fn new(backend: Arc<dyn VerifierBackend>, config: VerifierConfig) -> Self {
+        Self {
+            backend,
+            permits: Arc::new(Semaphore::new(config.max_concurrent)),
+            config,
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &VerifierConfig {
+        &self.config
+    }
+
+    /// Requests currently running a verifier
+    pub fn in_flight(&self) -> usize {
+        self.config.max_concurrent - self.permits.available_permits()
+    }
+
+    /// Requests currently waiting for a verifier
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Acquire)
+    }
+
+    /// Stop admitting requests. Waiting requests fail with [`VerifyError::Closed`].
+    pub fn close(&self) {
+        self.permits.close();
+    }
+
+    /// Verify a jammed proof read from `path`, see [`Self::verify`]
+    pub async fn verify_file(
+        &self,
+        path: impl AsRef<Path>,
+        deadline: Option<Duration>,
+    ) -> Result<Verdict, VerifyError> {
+        let path = path.as_ref();
+        let bytes = tokio::fs::metadata(path).await?.len();
+        self.check_proof_size(usize::try_from(bytes).unwrap_or(usize::MAX))?;
+        self.verify_jam(tokio::fs::read(path).await?, deadline)
+            .await
+    }
+
+    /// Verify a jammed proof, see [`Self::verify`]
+    pub async fn verify_jam(
+        &self,
+        jam: Vec<u8>,
+        deadline: Option<Duration>,
+    ) -> Result<Verdict, VerifyError> {
+        self.check_proof_size(jam.len())?;
+        let mut proof = NounSlab::new();
+        let root = proof.cue_into(jam.into())?;
+        proof.set_root(root);
+        self.verify(proof, deadline).await
+    }
+
+    /// Verify the proof at the root of `proof`, giving up after `deadline`
+    /// (or the configured default)
+    pub async fn verify(
+        &self,
+        proof: NounSlab,
+        deadline: Option<Duration>,
+    ) -> Result<Verdict, VerifyError> {
+        self.verify_proof(proof, deadline)
+            .instrument(profile_span(ProfilePhase::Verification))
+            .await
+    }
+
+    async fn verify_proof(
+        &self,
+        proof: NounSlab,
+        deadline: Option<Duration>,
+    ) -> Result<Verdict, VerifyError> {
+        let arrived = Instant::now();
+        let deadline = arrived + deadline.unwrap_or(self.config.default_deadline);
+        // reject oversized and malformed proofs before they take up a kernel
+        self.check_proof_size(proof.allocated_bytes())?;
+        let puzzle = ProofView::new(&proof, unsafe { *proof.root() })?.puzzle()?;
+        tracing::Span::current().record("length", puzzle.length);
+
+        let _permit = self
+            .admit(arrived, deadline)
+            .instrument(profile_span(ProfilePhase::Admit))
+            .await?;
+        let started = Instant::now();
+        let stack_words = self.stack_words(puzzle.length);
+        let exhausted = |err| match err {
+            VerifyError::Kernel(e) if is_out_of_memory(&e) => {
+                VerifyError::ResourceExhausted(Resource::NockStack { words: stack_words })
+            }
+            err => err,
+        };
+        let timeout = || VerifyError::Timeout {
+            after: arrived.elapsed(),
+        };
+        let load = self
+            .backend
+            .load(stack_words)
+            .instrument(profile_span(ProfilePhase::LoadKernel));
+        let verifier = tokio::time::timeout_at(deadline, load)
+            .await
+            .map_err(|_| timeout())?
+            .map_err(|e| exhausted(e.into()))?;
+        let observers = &self.config.observers;
+        observers.on_start(&PokeStart {
+            kind: PokeKind::Verify,
+            length: puzzle.length,
+            slab_bytes: proof.allocated_bytes(),
+        });
+        let poked = Instant::now();
+        let mut verify = verifier
+            .verify(proof, self.config.entropy)
+            .instrument(profile_span(ProfilePhase::Poke));
+        let check = tokio::select! {
+            check = &mut verify => {
+                observers.on_complete(&PokeComplete {
+                    ok: check.is_ok(),
+                    ..PokeComplete::failed(PokeKind::Verify, puzzle.length, poked.elapsed())
+                });
+                check.map_err(exhausted)?
+            }
+            _ = tokio::time::sleep_until(deadline) => {
+                observers.on_complete(&PokeComplete::failed(PokeKind::Verify, puzzle.length, poked.elapsed()));
+                if verifier.cancel() {
+                    // hold on to the permit until the kernel has actually stopped
+                    if tokio::time::timeout(CANCEL_GRACE, verify).await.is_err() {
+                        warn!("verifier kernel did not stop within {CANCEL_GRACE:?} of being cancelled");
+                    }
+                } else {
+                    debug!("verifier timed out with no poke running");
+                }
+                return Err(timeout());
+            }
+        };
+        tracing::Span::current().record("valid", check.valid);
+        self.config.events.publish(NodeEvent::ProofVerified {
+            length: puzzle.length,
+            valid: check.valid,
+        });
+        Ok(Verdict {
+            valid: check.valid,
+            pow: check.pow,
+            puzzle,
+            queued_for: started - arrived,
+            verified_in: started.elapsed(),
+        })
+    }
+
+    /// NockStack for a kernel verifying a proof of `length`, within the cap
+    pub fn stack_words(&self, length: u64) -> usize {
+        let words = self.config.stack_size.words_for(length);
+        self.config
+            .max_stack_words
+            .map_or(words, |max| words.min(max))
+    }
+
+    fn check_proof_size(&self, bytes: usize) -> Result<(), VerifyError> {
+        let limit = self.config.max_proof_bytes;
+        if bytes > limit {
+            return Err(VerifyError::ResourceExhausted(Resource::ProofSize {
+                bytes,
+                limit,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Take a kernel slot, queueing for one if there is room in the queue
+    async fn admit(
+        &self,
+        arrived: Instant,
+        deadline: Instant,
+    ) -> Result<OwnedSemaphorePermit, VerifyError> {
+        match self.permits.clone().try_acquire_owned() {
+            Ok(permit) => return Ok(permit),
+            Err(tokio::sync::TryAcquireError::Closed) => return Err(VerifyError::Closed),
+            Err(tokio::sync::TryAcquireError::NoPermits) => {}
+        }
+        let limit = self.config.max_queued;
+        self.queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                (queued < limit).then_some(queued + 1)
+            })
+            .map_err(|queued| VerifyError::Overloaded { queued, limit })?;
+        // give the slot back even if the caller drops us while we wait
+        let _slot = QueueSlot(&self.queued);
+        tokio::time::timeout_at(deadline, self.permits.clone().acquire_owned())
+            .await
+            .map_err(|_| VerifyError::Timeout {
+                after: arrived.elapsed(),
+            })?
+            .map_err(|_| VerifyError::Closed)
+    }
+}
+
+struct QueueSlot<'a>(&'a AtomicUsize);
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// One line of [`serve_stream`] output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StreamVerdict {
+    /// Position of the proof in its input stream, counting from 0. Lines
+    /// are written as verifications finish, which need not be input order.
+    pub seq: u64,
+    pub valid: bool,
+    /// Why the proof could not be verified, absent if it was
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub puzzle: Option<Puzzle>,
+    /// Hash of a valid proof in hex, see [`ProofCheck::pow`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pow: Option<String>,
+    pub queued_micros: u64,
+    pub verify_micros: u64,
+}
+
+impl StreamVerdict {
+    fn new(seq: u64, outcome: Result<Verdict, VerifyError>) -> Self {
+        match outcome {
+            Ok(verdict) => StreamVerdict {
+                seq,
+                valid: verdict.valid,
+                error: None,
+                puzzle: Some(verdict.puzzle),
+                pow: verdict.pow.map(|pow| format!("{pow:x}")),
+                queued_micros: verdict.queued_for.as_micros() as u64,
+                verify_micros: verdict.verified_in.as_micros() as u64,
+            },
+            Err(e) => StreamVerdict {
+                seq,
+                valid: false,
+                error: Some(e.to_string()),
+                puzzle: None,
+                pow: None,
+                queued_micros: 0,
+                verify_micros: 0,
+            },
+        }
+    }
+}
+
+/// Verify every length-prefixed proof read from `input`, writing a
+/// [`StreamVerdict`] line to `output` for each. Returns the number of proofs
+/// read once `input` is exhausted and every verdict is written.
+///
+/// Proofs are verified concurrently, but no more are read than the service
+/// can run and queue, so a fast producer is slowed down rather than refused.
+pub async fn serve_stream<R, W>(
+    service: &VerificationService,
+    input: R,
+    mut output: W,
+    deadline: Option<Duration>,
+) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let window = (service.config.max_concurrent + service.config.max_queued).max(1);
+    let limit = service.config.max_proof_bytes;
+    let (frames, mut received) = mpsc::channel(window);
+    let read = async move {
+        let mut input = input;
+        let mut seq = 0;
+        while let Some(frame) = read_frame(&mut input, limit).await? {
+            let frame = frame.map_err(VerifyError::from);
+            if frames.send((seq, frame)).await.is_err() {
+                break;
+            }
+            seq += 1;
+        }
+        Ok::<_, io::Error>(seq)
+    };
+    let verify = async move {
+        let mut pending = FuturesUnordered::new();
+        loop {
+            tokio::select! {
+                frame = received.recv(), if pending.len() < window => {
+                    let Some((seq, frame)) = frame else {
+                        break;
+                    };
+                    pending.push(async move {
+                        let outcome = match frame {
+                            Ok(jam) => service.verify_jam(jam, deadline).await,
+                            Err(e) => Err(e),
+                        };
+                        StreamVerdict::new(seq, outcome)
+                    });
+                }
+                Some(verdict) = pending.next() => write_verdict(&mut output, &verdict).await?,
+            }
+        }
+        while let Some(verdict) = pending.next().await {
+            write_verdict(&mut output, &verdict).await?;
+        }
+        output.flush().await
+    };
+    let (read, ()) = tokio::try_join!(read, verify)?;
+    Ok(read)
+}
+
+/// Accept connections on `listener` forever, serving each as a stream
+/// (see [`serve_stream`]) that shares `service` with the others
+pub async fn serve_unix(
+    service: Arc<VerificationService>,
+    listener: UnixListener,
+    deadline: Option<Duration>,
+) -> io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let service = service.clone();
+        tokio::spawn(async move {
+            let (input, output) = stream.into_split();
+            match serve_stream(&service, input, output, deadline).await {
+                Ok(proofs) => info!("verified {proofs} proofs for a socket client"),
+                Err(e) => warn!("verifier socket client failed: {e}"),
+            }
+        });
+    }
+}
+
+async fn write_verdict<W: AsyncWrite + Unpin>(
+    output: &mut W,
+    verdict: &StreamVerdict,
+) -> io::Result<()> {
+    let mut line = serde_json::to_vec(verdict)?;
+    line.push(b'\n');
+    output.write_all(&line).await?;
+    output.flush().await
+}
@@ -0,0 +1,215 @@
+//! Restarting mining kernels that fail or wedge.
+//!
+//! [`WatchedBackend`] wraps another [`ProvingBackend`]. When a load or a
+//! prove poke errors, or a poke runs past [`Watchdog::stall_timeout`], the
+//! kernel is cancelled and dropped, a fresh one is loaded, and the same
+//! candidate is proven again. The miner kernel keeps no state between
+//! candidates, so a freshly booted kernel resumes exactly where the wedged
+//! one would have. Running out of NockStack is not retried, a kernel of the
+//! same size would run out again. Every failure is counted in
+//! [`crate::metrics::NockchainMetrics`].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use nockapp::kernel::form::{Entropy, MemoryStats};
+use nockapp::noun::slab::NounSlab;
+use nockapp::CrownError;
+use tracing::{error, warn};
+
+use crate::backend::{Prover, ProvingBackend};
+use crate::metrics::metrics;
+use crate::stack::is_out_of_memory;
+
+/// When to give up on a mining kernel and start a new one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchdog {
+    /// A prove poke running longer than this is treated as wedged. `None`
+    /// waits for every poke however long it takes.
+    pub stall_timeout: Option<Duration>,
+    /// Fresh kernels tried per candidate after the first one fails
+    pub max_restarts: u32,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self {
+            stall_timeout: None,
+            max_restarts: 3,
+        }
+    }
+}
+
+/// A [`ProvingBackend`] whose provers restart their kernel on failure
+pub struct WatchedBackend {
+    inner: Arc<dyn ProvingBackend>,
+    watchdog: Watchdog,
+}
+
+impl WatchedBackend {
+    pub fn new(inner: Arc<dyn ProvingBackend>, watchdog: Watchdog) -> Self {
+        Self { inner, watchdog }
+    }
+}
+
+impl ProvingBackend for WatchedBackend {
+    fn load(&self, stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Prover>, CrownError>> {
+        Box::pin(async move {
+            let watched = Watched {
+                backend: self.inner.clone(),
+                watchdog: self.watchdog,
+                stack_words,
+                current: Mutex::new(None),
+                cancelled: AtomicBool::new(false),
+            };
+            let mut restarts = 0;
+            loop {
+                match watched.backend.load(stack_words).await {
+                    Ok(prover) => {
+                        watched.replace(Some(Arc::from(prover)));
+                        return Ok(Box::new(WatchedProver(Arc::new(watched))) as Box<dyn Prover>);
+                    }
+                    Err(e) => {
+                        metrics().mining_kernel_load_failures.increment();
+                        if !watched.restart(&mut restarts, &e) {
+                            return Err(e);
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+struct Watched {
+    backend: Arc<dyn ProvingBackend>,
+    watchdog: Watchdog,
+    stack_words: usize,
+    /// The kernel in use, `None` between a failure and a successful reload
+    current: Mutex<Option<Arc<dyn Prover>>>,
+    cancelled: AtomicBool,
+}
+
+impl Watched {
+    fn current(&self) -> Option<Arc<dyn Prover>> {
+        self.current
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn replace(&self, prover: Option<Arc<dyn Prover>>) {
+        *self
+            .current
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = prover;
+    }
+
+    /// Count a restart after `failure`, false once they are used up
+    fn restart(&self, restarts: &mut u32, failure: &CrownError) -> bool {
+        if self.cancelled.load(Ordering::SeqCst) {
+            return false;
+        }
+        if *restarts >= self.watchdog.max_restarts {
+            error!(
+                "Mining kernel failed after {} restarts, giving up: {failure}",
+                self.watchdog.max_restarts
+            );
+            return false;
+        }
+        *restarts += 1;
+        metrics().mining_kernel_restarts.increment();
+        warn!(
+            "Restarting mining kernel ({restarts}/{}) after: {failure}",
+            self.watchdog.max_restarts
+        );
+        true
+    }
+
+    async fn prove(&self, candidate: NounSlab, entropy: Entropy) -> Result<NounSlab, CrownError> {
+        let mut restarts = 0;
+        loop {
+            let prover = match self.current() {
+                Some(prover) => prover,
+                None => match self.backend.load(self.stack_words).await {
+                    Ok(prover) => {
+                        let prover: Arc<dyn Prover> = Arc::from(prover);
+                        self.replace(Some(prover.clone()));
+                        prover
+                    }
+                    Err(e) => {
+                        metrics().mining_kernel_load_failures.increment();
+                        if !self.restart(&mut restarts, &e) {
+                            return Err(e);
+                        }
+                        continue;
+                    }
+                },
+            };
+            let prove = prover.prove(candidate.clone(), entropy);
+            let res = match self.watchdog.stall_timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, prove).await {
+                    Ok(res) => {
+                        res.inspect_err(|_| metrics().mining_kernel_poke_failures.increment())
+                    }
+                    Err(_) => {
+                        metrics().mining_kernel_stalls.increment();
+                        prover.cancel();
+                        Err(CrownError::Unknown(format!(
+                            "mining kernel stalled for {timeout:?}"
+                        )))
+                    }
+                },
+                None => prove
+                    .await
+                    .inspect_err(|_| metrics().mining_kernel_poke_failures.increment()),
+            };
+            let failure = match res {
+                Ok(effects) => return Ok(effects),
+                Err(e) if is_out_of_memory(&e) => return Err(e),
+                Err(e) => e,
+            };
+            // tear the kernel down, the next try boots a fresh one
+            self.replace(None);
+            drop(prover);
+            if !self.restart(&mut restarts, &failure) {
+                return Err(failure);
+            }
+        }
+    }
+}
+
+struct WatchedProver(Arc<Watched>);
+
+impl Prover for WatchedProver {
+    fn prove(
+        &self,
+        candidate: NounSlab,
+        entropy: Entropy,
+    ) -> BoxFuture<'static, Result<NounSlab, CrownError>> {
+        let watched = self.0.clone();
+        Box::pin(async move { watched.prove(candidate, entropy).await })
+    }
+
+    fn cancel(&self) -> bool {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        self.0.current().is_some_and(|prover| prover.cancel())
+    }
+
+    fn warm_up(&self) -> BoxFuture<'static, Result<Duration, CrownError>> {
+        match self.0.current() {
+            Some(prover) => prover.warm_up(),
+            None => Box::pin(async { Ok(Duration::ZERO) }),
+        }
+    }
+
+    fn state(&self) -> Option<BoxFuture<'static, Result<Vec<u8>, CrownError>>> {
+        self.0.current()?.state()
+    }
+
+    fn memory_stats(&self) -> Option<MemoryStats> {
+        self.0.current()?.memory_stats()
+    }
+}
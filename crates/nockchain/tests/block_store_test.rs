@@ -0,0 +1,42 @@
+use nockchain::block_store::{BlockStore, BlockStoreError, PruneMode};
+
+fn digest(n: u64) -> [u64; 5] {
+    [n, 0, 0, 0, 0]
+}
+
+#[test]
+fn test_block_store_indexes_survive_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    {
+        let mut store = BlockStore::open(dir.path(), PruneMode::Archive).unwrap();
+        for height in 0..5 {
+            store
+                .put(height, digest(height + 100), &[height as u8])
+                .unwrap();
+        }
+        // a reorg replaces the block at height 4
+        store.put(4, digest(999), &[42]).unwrap();
+    }
+
+    let store = BlockStore::open(dir.path(), PruneMode::Archive).unwrap();
+    assert_eq!(store.tip_height(), Some(4));
+    assert_eq!(store.height_of(&digest(102)), Some(2));
+    assert_eq!(store.height_of(&digest(104)), None);
+    assert_eq!(store.get_by_digest(&digest(999)).unwrap(), Some(vec![42]));
+}
+
+#[test]
+fn test_block_store_keep_recent_prunes() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = BlockStore::open(dir.path(), PruneMode::KeepRecent(2)).unwrap();
+    for height in 0..10 {
+        store.put(height, digest(height), &[0]).unwrap();
+    }
+
+    assert_eq!(store.get_by_height(9).unwrap(), Some(vec![0]));
+    assert_eq!(store.get_by_height(7).unwrap(), Some(vec![0]));
+    assert!(matches!(
+        store.get_by_height(6),
+        Err(BlockStoreError::Pruned(6))
+    ));
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use nockchain::candidate_queue::{CandidatePriority, CandidateQueue, QueueError};
+use nockchain::prove_input::ProveBlockInput;
+
+fn input(nonce: u64) -> ProveBlockInput {
+    ProveBlockInput::builder()
+        .length(4)
+        .block_commitment(&[1, 2, 3, 4, 5])
+        .nonce(&[nonce, 0, 0, 0, 0])
+        .build()
+        .unwrap()
+}
+
+fn priority(height: u64, fee: u64) -> CandidatePriority {
+    CandidatePriority { height, fee }
+}
+
+#[tokio::test]
+async fn test_candidates_come_out_best_first() {
+    let queue = CandidateQueue::new(8);
+    queue.try_push(priority(10, 0), input(0)).unwrap();
+    queue.try_push(priority(11, 0), input(1)).unwrap();
+    queue.try_push(priority(10, 5), input(2)).unwrap();
+    queue.try_push(priority(11, 0), input(3)).unwrap();
+
+    let mut order = Vec::new();
+    while let Some((_, input)) = queue.try_pop() {
+        order.push(input.nonce()[0]);
+    }
+    // height beats fees, and equal priorities keep their arrival order
+    assert_eq!(order, [1, 3, 2, 0]);
+}
+
+#[tokio::test]
+async fn test_full_queue_applies_backpressure() {
+    let queue = Arc::new(CandidateQueue::new(1));
+    queue.try_push(priority(1, 0), input(0)).unwrap();
+    assert_eq!(
+        queue.try_push(priority(2, 0), input(1)),
+        Err(QueueError::Full(priority(2, 0), input(1)))
+    );
+
+    let pushed = tokio::spawn({
+        let queue = queue.clone();
+        async move { queue.push(priority(2, 0), input(1)).await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert!(!pushed.is_finished());
+    assert_eq!(queue.pop().await.unwrap().1, input(0));
+    pushed.await.unwrap().unwrap();
+    assert_eq!(queue.pop().await.unwrap().1, input(1));
+}
+
+#[tokio::test]
+async fn test_close_wakes_waiters_and_keeps_queued_candidates() {
+    let queue = Arc::new(CandidateQueue::new(1));
+    let waiting = tokio::spawn({
+        let queue = queue.clone();
+        async move { queue.pop().await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    queue.close();
+    assert_eq!(waiting.await.unwrap(), None);
+    assert_eq!(
+        queue.try_push(priority(1, 0), input(0)),
+        Err(QueueError::Closed)
+    );
+
+    let queue = CandidateQueue::new(2);
+    queue.try_push(priority(1, 0), input(0)).unwrap();
+    queue.close();
+    assert_eq!(queue.pop().await, None);
+    assert_eq!(queue.try_pop().map(|(_, input)| input), Some(input(0)));
+    assert!(queue.is_empty());
+}
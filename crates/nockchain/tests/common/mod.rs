@@ -0,0 +1,236 @@
+//! Helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+
+use futures::future::BoxFuture;
+use kernels::dumb::KERNEL;
+use nockapp::kernel::checkpoint::JamPaths;
+use nockapp::kernel::form::Kernel;
+use nockapp::noun::slab::NounSlab;
+use nockapp::utils::make_tas;
+use nockapp::utils::scry::ScryResult;
+use nockapp::wire::{SystemWire, Wire};
+use nockapp::{AtomExt, CrownError};
+use nockchain::light_client::{BlockHeader, HeaderProof, LightClientError};
+use nockchain::network::NetworkMode;
+use nockchain::peek::{decode_candidate, mining_path, CandidateState};
+use nockchain::reorg::ChainState;
+use nockvm::noun::{Atom, Noun, D, T};
+use nockvm_macros::tas;
+use zkvm_jetpack::hot::produce_node_hot_state;
+
+/// Remembers which blocks it applied. A page is a tuple ending in the
+/// block's number, or just the number.
+#[derive(Clone, Default)]
+pub struct Applied(pub Arc<Mutex<Vec<u64>>>);
+
+impl Applied {
+    pub fn blocks(&self) -> Vec<u64> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl ChainState for Applied {
+    fn snapshot(&self) -> BoxFuture<'_, Result<Vec<u8>, CrownError>> {
+        let bytes = serde_json::to_vec(&self.blocks()).unwrap();
+        Box::pin(async move { Ok(bytes) })
+    }
+
+    fn restore(&self, snapshot: Vec<u8>) -> BoxFuture<'_, Result<(), CrownError>> {
+        *self.0.lock().unwrap() = serde_json::from_slice(&snapshot).unwrap();
+        Box::pin(async { Ok(()) })
+    }
+
+    fn apply(&self, page: NounSlab) -> BoxFuture<'_, Result<(), CrownError>> {
+        let block = block_number(unsafe { *page.root() });
+        self.0.lock().unwrap().push(block);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+fn block_number(page: Noun) -> u64 {
+    let mut rest = page;
+    while let Ok(cell) = rest.as_cell() {
+        rest = cell.tail();
+    }
+    rest.as_atom().unwrap().as_u64().unwrap()
+}
+
+/// Attach a minimal proof mined for `header`'s page, whose message hash is
+/// `[id 0 0 0 0]` so that forks differ, and set the digest the two hash to
+pub fn seal(mut header: BlockHeader, id: u64) -> (BlockHeader, HeaderProof) {
+    let mut proof = HeaderProof {
+        tx_ids: [0; 5],
+        coinbase: [0; 5],
+        msg: [id, 0, 0, 0, 0],
+        proof: Vec::new(),
+    };
+    let mut slab = NounSlab::new();
+    let commitment = T(&mut slab, &proof.block_commitment(&header).map(D));
+    let nonce = T(&mut slab, &[id; 5].map(D));
+    let puzzle = T(
+        &mut slab,
+        &[D(tas!(b"puzzle")), commitment, nonce, D(64), D(0)],
+    );
+    let objects = T(&mut slab, &[puzzle, D(0)]);
+    let root = T(&mut slab, &[D(0), objects, D(0), D(0)]);
+    slab.set_root(root);
+    proof.proof = slab.jam().to_vec();
+    header.digest = [0; 5];
+    match proof.check(&header) {
+        Err(LightClientError::BadDigest { computed, .. }) => header.digest = computed,
+        other => panic!("unexpected {other:?}"),
+    }
+    (header, proof)
+}
+
+/// Mining keys of two miners, so their blocks differ
+pub const MINER_A: &str = "2qwq9dQRZfpFx8BDicghpMRnYGKZsZGxxhh9m362pzpM9aeo276pR1yHZPS41y3CW3vPKxeYM8p8fzZS8GXmDGzmNNCnVNekjrSYogqfEFMqwhHh5iCjaKPaDTwhupWqiXj6";
+pub const MINER_B: &str = "EHmKL2U3vXfS5GYAY5aVnGdukfDWwvkQPCZXnjvZVShsSQi3UAuA4tQQpVwGJMzc9FfpTY8pLDkqhBGfWutiF4prrCktUH9oAWJxkXQBzAavKDc95NR3DjmYwnnw8GuugnK";
+
+pub fn command(build: impl FnOnce(&mut NounSlab) -> Vec<Noun>) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let mut cause = vec![D(tas!(b"command"))];
+    cause.extend(build(&mut slab));
+    let root = T(&mut slab, &cause);
+    slab.set_root(root);
+    slab
+}
+
+/// A dumbnet kernel on regtest, past its init phase. With a mining key it
+/// builds candidates, and with `genesis` its first candidate is genesis.
+pub async fn regtest_kernel(dir: &tempfile::TempDir, key: Option<&str>, genesis: bool) -> Kernel {
+    let kernel = Kernel::load_with_hot_state(
+        dir.path().to_path_buf(),
+        JamPaths::new(dir.path()),
+        KERNEL,
+        &produce_node_hot_state(),
+        false,
+    )
+    .await
+    .expect("Could not load dumbnet kernel");
+    let mut pokes = vec![NetworkMode::Regtest
+        .set_constants_poke()
+        .expect("regtest has its own constants")];
+    if let Some(key) = key {
+        pokes.push(command(|slab| {
+            let tag = make_tas(slab, "set-mining-key").as_noun();
+            let key = Atom::from_value(slab, key).unwrap().as_noun();
+            vec![tag, key]
+        }));
+        pokes.push(command(|slab| {
+            vec![make_tas(slab, "enable-mining").as_noun(), D(0)]
+        }));
+    }
+    pokes.push(command(|slab| {
+        if genesis {
+            // a made up bitcoin block, which no one checks on regtest
+            let btc_hash = T(slab, &[D(0); 8]);
+            let template = T(slab, &[btc_hash, D(0), D(0)]);
+            vec![D(tas!(b"genesis")), template]
+        } else {
+            vec![D(tas!(b"btc-data")), D(0)]
+        }
+    }));
+    pokes.push(command(|_| vec![D(tas!(b"born")), D(0)]));
+    for poke in pokes {
+        kernel
+            .poke(SystemWire.to_wire(), poke)
+            .await
+            .expect("Boot poke failed");
+    }
+    kernel
+}
+
+pub fn peek(kernel: &Kernel, path: NounSlab) -> NounSlab {
+    tokio::task::block_in_place(|| kernel.peek_sync(path)).expect("Peek failed")
+}
+
+pub fn block_id(noun: Noun) -> [u64; 5] {
+    let mut id = [0; 5];
+    let mut rest = noun;
+    for limb in id.iter_mut() {
+        let atom = match rest.as_cell() {
+            Ok(cell) => {
+                rest = cell.tail();
+                cell.head()
+            }
+            Err(_) => rest,
+        };
+        *limb = atom.as_atom().unwrap().as_u64().unwrap();
+    }
+    id
+}
+
+/// The block at `height` on the kernel's heaviest chain
+pub fn heavy_block(kernel: &Kernel, height: u64) -> ([u64; 5], NounSlab) {
+    let mut path = NounSlab::new();
+    let tag = make_tas(&mut path, "heavy-n").as_noun();
+    let root = T(&mut path, &[tag, D(height), D(0)]);
+    path.set_root(root);
+    let res = peek(kernel, path);
+    let ScryResult::Some(page) = ScryResult::from(unsafe { res.root() }) else {
+        panic!("no block at height {height}");
+    };
+    let mut slab = NounSlab::new();
+    slab.copy_into(page);
+    (block_id(page.as_cell().unwrap().head()), slab)
+}
+
+pub fn heaviest(kernel: &Kernel) -> [u64; 5] {
+    let mut path = NounSlab::new();
+    let root = T(&mut path, &[D(tas!(b"heavy")), D(0)]);
+    path.set_root(root);
+    let res = peek(kernel, path);
+    let ScryResult::Some(id) = ScryResult::from(unsafe { res.root() }) else {
+        panic!("no heaviest block");
+    };
+    // a unit
+    block_id(id.as_cell().unwrap().tail())
+}
+
+/// The kernel's `%heavy-header` answer for `height`
+pub fn heavy_header(kernel: &Kernel, height: u64) -> (BlockHeader, HeaderProof) {
+    let mut path = NounSlab::new();
+    let tag = make_tas(&mut path, "heavy-header").as_noun();
+    let root = T(&mut path, &[tag, D(height), D(0)]);
+    path.set_root(root);
+    let res = peek(kernel, path);
+    let ScryResult::Some(answer) = ScryResult::from(unsafe { res.root() }) else {
+        panic!("no header at height {height}");
+    };
+    let answer = answer.as_cell().unwrap();
+    (
+        BlockHeader::from_noun(answer.head()).unwrap(),
+        HeaderProof::from_noun(answer.tail()).unwrap(),
+    )
+}
+
+/// Mine the kernel's candidate and return the block. Regtest does not
+/// check proofs of work, so an empty proof hashing to 0 will do.
+pub async fn mine(kernel: &Kernel) -> ([u64; 5], NounSlab) {
+    mine_with_proof(kernel, |slab, _| T(slab, &[D(0), D(0), D(0), D(0)])).await
+}
+
+/// Mine the kernel's candidate with the proof `build` makes for it
+pub async fn mine_with_proof(
+    kernel: &Kernel,
+    build: impl FnOnce(&mut NounSlab, &CandidateState) -> Noun,
+) -> ([u64; 5], NounSlab) {
+    let candidate = decode_candidate(&peek(kernel, mining_path()))
+        .unwrap()
+        .expect("kernel has no candidate");
+    let pow = command(|slab| {
+        let proof = build(slab, &candidate);
+        let commitment = T(slab, &candidate.block_commitment.map(D));
+        let nonce = T(slab, &candidate.nonce.map(D));
+        vec![D(tas!(b"pow")), proof, D(0), commitment, nonce]
+    });
+    kernel
+        .poke(SystemWire.to_wire(), pow)
+        .await
+        .expect("Pow poke failed");
+    heavy_block(kernel, candidate.height)
+}
@@ -0,0 +1,138 @@
+//! Failed prove pokes leave a crash dump that gives back their cause and state.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use nockapp::kernel::form::Entropy;
+use nockapp::noun::slab::NounSlab;
+use nockapp::CrownError;
+use nockchain::backend::{MockBackend, Prover, ProvingBackend};
+use nockchain::crash_dump::{CrashDumpBackend, CrashDumps};
+use nockvm::noun::{D, T};
+use tempfile::tempdir;
+
+const STATE: &[u8] = b"exported state";
+
+/// Crashes on every candidate, or waits to be cancelled if told to
+#[derive(Clone, Default)]
+struct CrashingBackend {
+    wait_for_cancel: bool,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ProvingBackend for CrashingBackend {
+    fn load(&self, _stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Prover>, CrownError>> {
+        let prover = self.clone();
+        Box::pin(async move { Ok(Box::new(prover) as Box<dyn Prover>) })
+    }
+}
+
+impl Prover for CrashingBackend {
+    fn prove(
+        &self,
+        _candidate: NounSlab,
+        _entropy: Entropy,
+    ) -> BoxFuture<'static, Result<NounSlab, CrownError>> {
+        let wait_for_cancel = self.wait_for_cancel;
+        let cancelled = self.cancelled.clone();
+        Box::pin(async move {
+            while wait_for_cancel && !cancelled.load(Ordering::SeqCst) {
+                tokio::task::yield_now().await;
+            }
+            Err(CrownError::WorkBail)
+        })
+    }
+
+    fn cancel(&self) -> bool {
+        self.cancelled.store(true, Ordering::SeqCst);
+        true
+    }
+
+    fn state(&self) -> Option<BoxFuture<'static, Result<Vec<u8>, CrownError>>> {
+        Some(Box::pin(async { Ok(STATE.to_vec()) }))
+    }
+}
+
+fn candidate(nonce: u64) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let root = T(&mut slab, &[D(4), D(nonce)]);
+    slab.set_root(root);
+    slab
+}
+
+async fn prove(backend: &CrashDumpBackend, nonce: u64) -> Result<NounSlab, CrownError> {
+    let prover = backend.load(0).await.unwrap();
+    prover.prove(candidate(nonce), Entropy::Fixed(7)).await
+}
+
+#[tokio::test]
+async fn test_failed_prove_is_dumped() {
+    let dir = tempdir().unwrap();
+    let dumps = CrashDumps::new(dir.path(), 4);
+    let backend = CrashDumpBackend::new(Arc::new(CrashingBackend::default()), dumps.clone());
+    assert!(matches!(
+        prove(&backend, 1).await,
+        Err(CrownError::WorkBail)
+    ));
+
+    let index = dumps.list().unwrap();
+    assert_eq!(index.len(), 1);
+    let dump = &index[0];
+    assert_eq!(dump.wire, "miner,1,candidate");
+    assert_eq!(dump.entropy, Some(7));
+    assert_eq!(dump.error, CrownError::WorkBail.to_string());
+    assert!(!dump.out_of_memory);
+    assert_eq!(dump.state_error, None);
+    assert_eq!(dumps.cause(dump).unwrap().jam(), candidate(1).jam());
+    assert_eq!(dumps.state(dump).unwrap().as_deref(), Some(STATE));
+}
+
+#[tokio::test]
+async fn test_successful_and_cancelled_proves_are_not_dumped() {
+    let dir = tempdir().unwrap();
+    let dumps = CrashDumps::new(dir.path(), 4);
+
+    let mut effects = NounSlab::new();
+    effects.set_root(D(0));
+    let ok = CrashDumpBackend::new(Arc::new(MockBackend::new(effects)), dumps.clone());
+    prove(&ok, 1).await.unwrap();
+
+    let crashing = CrashDumpBackend::new(
+        Arc::new(CrashingBackend {
+            wait_for_cancel: true,
+            ..Default::default()
+        }),
+        dumps.clone(),
+    );
+    let prover = crashing.load(0).await.unwrap();
+    let poke = prover.prove(candidate(2), Entropy::Random);
+    assert!(prover.cancel());
+    assert!(poke.await.is_err());
+
+    assert!(dumps.list().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_oldest_dumps_are_dropped() {
+    let dir = tempdir().unwrap();
+    let dumps = CrashDumps::new(dir.path(), 2);
+    let backend = CrashDumpBackend::new(Arc::new(CrashingBackend::default()), dumps.clone());
+    for nonce in 0..3 {
+        prove(&backend, nonce).await.unwrap_err();
+    }
+
+    let index = dumps.list().unwrap();
+    assert_eq!(index.len(), 2);
+    let causes: Vec<_> = index
+        .iter()
+        .map(|dump| dumps.cause(dump).unwrap().jam())
+        .collect();
+    assert_eq!(causes, [candidate(1).jam(), candidate(2).jam()]);
+    // the dropped dump's directory is gone with it
+    let dirs = std::fs::read_dir(dir.path())
+        .unwrap()
+        .filter(|entry| entry.as_ref().unwrap().path().is_dir())
+        .count();
+    assert_eq!(dirs, 2);
+}
@@ -0,0 +1,215 @@
+use ibig::UBig;
+use nockchain::consensus::difficulty::{
+    check_target, max_target, next_target, BlockTime, DifficultyError, EpochRetarget, MovingAverage,
+};
+use nockchain::network::NetworkMode;
+
+mod common;
+
+use common::{heavy_header, mine, regtest_kernel, MINER_A};
+
+/// Blocks from genesis `interval` seconds apart, all at `target`
+fn chain(blocks: u64, interval: u64, target: u64) -> Vec<BlockTime> {
+    (0..blocks)
+        .map(|height| BlockTime {
+            height,
+            timestamp: 1_700_000_000 + height * interval,
+            target: UBig::from(target),
+        })
+        .collect()
+}
+
+/// Mine `blocks` more blocks at whatever target `algorithm` asks for,
+/// `interval(target)` seconds apart
+fn extend(
+    chain: &mut Vec<BlockTime>,
+    algorithm: &MovingAverage,
+    blocks: usize,
+    interval: impl Fn(&UBig) -> u64,
+) {
+    for _ in 0..blocks {
+        let parent = chain.last().unwrap().clone();
+        let target = next_target(algorithm, chain).unwrap();
+        chain.push(BlockTime {
+            height: parent.height + 1,
+            timestamp: parent.timestamp + interval(&target),
+            target,
+        });
+    }
+}
+
+#[test]
+fn test_moving_average_holds_on_schedule() {
+    let algorithm = MovingAverage::new(10, 60);
+    let blocks = chain(30, 60, 1_000_000);
+    assert_eq!(
+        next_target(&algorithm, &blocks),
+        Ok(UBig::from(1_000_000u32))
+    );
+}
+
+#[test]
+fn test_moving_average_converges_on_hashrate() {
+    // a miner that needs target 4_000_000 to find a block every 60 seconds
+    let algorithm = MovingAverage::new(10, 60);
+    let mut blocks = chain(1, 60, 1_000_000);
+    extend(&mut blocks, &algorithm, 100, |target| {
+        (60 * 4_000_000u64).div_ceil(u64::try_from(target).unwrap())
+    });
+    let target = u64::try_from(&blocks.last().unwrap().target).unwrap();
+    assert!(
+        (3_800_000..=4_200_000).contains(&target),
+        "settled at {target}"
+    );
+}
+
+#[test]
+fn test_moving_average_steps_are_bounded() {
+    let algorithm = MovingAverage::new(5, 60);
+    // a day without blocks only makes the target max_step easier
+    let mut blocks = chain(6, 60, 1_000);
+    blocks.last_mut().unwrap().timestamp += 24 * 60 * 60;
+    assert_eq!(next_target(&algorithm, &blocks), Ok(UBig::from(4_000u32)));
+    // and blocks with the same timestamp only make it max_step harder
+    let blocks = chain(6, 0, 1_000);
+    assert_eq!(next_target(&algorithm, &blocks), Ok(UBig::from(250u32)));
+
+    let mut easiest = MovingAverage::new(5, 60);
+    easiest.max_target = UBig::from(2_000u32);
+    let mut blocks = chain(6, 600, 1_000);
+    blocks.last_mut().unwrap().target = UBig::from(2_000u32);
+    assert_eq!(next_target(&easiest, &blocks), Ok(UBig::from(2_000u32)));
+}
+
+#[test]
+fn test_moving_average_uses_what_there_is_near_genesis() {
+    let algorithm = MovingAverage::new(10, 60);
+    let blocks = chain(3, 30, 1_000);
+    assert_eq!(next_target(&algorithm, &blocks), Ok(UBig::from(500u32)));
+
+    // past genesis a short window means blocks are missing
+    assert_eq!(
+        next_target(&algorithm, &chain(20, 60, 1_000)[15..]),
+        Err(DifficultyError::NotEnoughBlocks {
+            height: 20,
+            needed: 11,
+            have: 5,
+        })
+    );
+}
+
+#[test]
+fn test_epoch_retarget_scales_by_epoch_duration() {
+    let algorithm = EpochRetarget {
+        blocks_per_epoch: 4,
+        target_epoch_duration: 400,
+        min_past_blocks: 1,
+        max_target: max_target(),
+    };
+    // mid epoch the target stays put however fast blocks come
+    let blocks = chain(6, 1, 1_000);
+    assert_eq!(next_target(&algorithm, &blocks), Ok(UBig::from(1_000u32)));
+
+    // genesis closes the 0th epoch, so the first takes 3 * 50 seconds
+    let blocks = chain(4, 50, 1_000);
+    assert_eq!(next_target(&algorithm, &blocks), Ok(UBig::from(375u32)));
+    // later epochs run from the block closing the one before, 4 * 50 of 400
+    let blocks = chain(8, 50, 1_000);
+    assert_eq!(next_target(&algorithm, &blocks), Ok(UBig::from(500u32)));
+    // too fast is clamped to a quarter of the epoch
+    let blocks = chain(8, 10, 1_000);
+    assert_eq!(next_target(&algorithm, &blocks), Ok(UBig::from(250u32)));
+    // too slow to four epochs
+    let blocks = chain(8, 1_000, 1_000);
+    assert_eq!(next_target(&algorithm, &blocks), Ok(UBig::from(4_000u32)));
+
+    // never easier than the max target
+    let capped = EpochRetarget {
+        max_target: UBig::from(3_000u32),
+        ..algorithm
+    };
+    assert_eq!(next_target(&capped, &blocks), Ok(UBig::from(3_000u32)));
+}
+
+#[test]
+fn test_epoch_retarget_measures_between_median_past_times() {
+    let algorithm = EpochRetarget {
+        blocks_per_epoch: 4,
+        target_epoch_duration: 400,
+        min_past_blocks: 3,
+        max_target: max_target(),
+    };
+    // from the median of blocks 5 to 7 to the median of blocks 9 to 11
+    let mut blocks = chain(12, 50, 1_000);
+    assert_eq!(next_target(&algorithm, &blocks), Ok(UBig::from(500u32)));
+    // a block claiming to come a day late does not move the median
+    blocks[11].timestamp += 24 * 60 * 60;
+    assert_eq!(next_target(&algorithm, &blocks), Ok(UBig::from(500u32)));
+    // pairs average down, like the kernel's +median
+    let blocks = chain(4, 75, 1_000);
+    let shorter = EpochRetarget {
+        min_past_blocks: 2,
+        ..algorithm.clone()
+    };
+    // (150 + 225) / 2 rounds down to 187 seconds after genesis
+    assert_eq!(next_target(&shorter, &blocks), Ok(UBig::from(467u32)));
+
+    // the window reaches back to the median before the epoch
+    let blocks = chain(12, 50, 1_000);
+    assert!(next_target(&algorithm, &blocks[5..]).is_ok());
+    assert_eq!(
+        next_target(&algorithm, &blocks[6..]),
+        Err(DifficultyError::NotEnoughBlocks {
+            height: 12,
+            needed: 7,
+            have: 6,
+        })
+    );
+}
+
+/// Every target a regtest kernel mines at is the one the kernel's rule
+/// under regtest's constants gives, across two retargets
+#[tokio::test(flavor = "multi_thread")]
+async fn test_epoch_retarget_matches_kernel_headers() {
+    let dir = tempfile::tempdir().unwrap();
+    let kernel = regtest_kernel(&dir, Some(MINER_A), true).await;
+    let algorithm = NetworkMode::Regtest.difficulty();
+    let epoch = NetworkMode::Regtest.constants().blocks_per_epoch;
+    let mut blocks: Vec<BlockTime> = Vec::new();
+    for height in 0..=2 * epoch {
+        mine(&kernel).await;
+        let (header, _) = heavy_header(&kernel, height);
+        if height > 0 {
+            assert_eq!(
+                check_target(algorithm.as_ref(), &blocks, &header.target),
+                Ok(()),
+                "target of block {height}"
+            );
+        }
+        blocks.push(BlockTime::from(&header));
+    }
+}
+
+#[test]
+fn test_check_target() {
+    let algorithm = MovingAverage::new(4, 60);
+    let blocks = chain(10, 60, 1_000);
+    assert_eq!(
+        check_target(&algorithm, &blocks, &UBig::from(1_000u32)),
+        Ok(())
+    );
+    assert_eq!(
+        check_target(&algorithm, &blocks, &UBig::from(999u32)),
+        Err(DifficultyError::WrongTarget {
+            expected: UBig::from(1_000u32),
+            actual: UBig::from(999u32),
+        })
+    );
+
+    let mut gap = blocks.clone();
+    gap.remove(7);
+    assert_eq!(
+        check_target(&algorithm, &gap, &UBig::from(1_000u32)),
+        Err(DifficultyError::NotConsecutive(8))
+    );
+}
@@ -0,0 +1,287 @@
+//! Malformed `%pow` effects must be rejected with a specific error, never a
+//! panic or a silently defaulted field.
+
+use std::borrow::Cow;
+
+use nockapp::noun::slab::NounSlab;
+use nockchain::effect::{atom_bytes, MiningEffect, MiningEffectError, ProofObject};
+use nockchain::prove_input::{ProveBlockInput, ProveInputError};
+use nockvm::noun::{IndirectAtom, Noun, D, T};
+use nockvm_macros::tas;
+use zkvm_jetpack::form::math::base::PRIME;
+
+const COMMITMENT: [u64; 5] = [1, 2, 3, 4, 5];
+const NONCE: [u64; 5] = [6, 7, 8, 9, 10];
+const LENGTH: u64 = 4;
+
+fn input() -> ProveBlockInput {
+    ProveBlockInput::builder()
+        .length(LENGTH)
+        .block_commitment(&COMMITMENT)
+        .nonce(&NONCE)
+        .build()
+        .unwrap()
+}
+
+fn digest(slab: &mut NounSlab, belts: &[u64]) -> Noun {
+    let nouns: Vec<Noun> = belts
+        .iter()
+        .map(|&belt| nockvm::noun::Atom::new(slab, belt).as_noun())
+        .collect();
+    T(slab, &nouns)
+}
+
+/// `[0 [[%puzzle commitment nonce len 0] ~] ~ 0]`
+fn proof(slab: &mut NounSlab, length: u64) -> Noun {
+    let commitment = digest(slab, &COMMITMENT);
+    let nonce = digest(slab, &NONCE);
+    let puzzle = T(
+        slab,
+        &[D(tas!(b"puzzle")), commitment, nonce, D(length), D(0)],
+    );
+    let objects = T(slab, &[puzzle, D(0)]);
+    T(slab, &[D(0), objects, D(0), D(0)])
+}
+
+/// `[%command %pow proof dig commitment nonce]` with `proof` swapped in
+fn effect_with(build_proof: impl FnOnce(&mut NounSlab) -> Noun, nonce: &[u64]) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let proof = build_proof(&mut slab);
+    let commitment = digest(&mut slab, &COMMITMENT);
+    let nonce = digest(&mut slab, nonce);
+    let effect = T(
+        &mut slab,
+        &[
+            D(tas!(b"command")),
+            D(tas!(b"pow")),
+            proof,
+            D(0x1234),
+            commitment,
+            nonce,
+        ],
+    );
+    slab.set_root(effect);
+    slab
+}
+
+fn effect_from(build: impl FnOnce(&mut NounSlab) -> Noun) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let root = build(&mut slab);
+    slab.set_root(root);
+    slab
+}
+
+fn decode(slab: NounSlab) -> Result<MiningEffect, MiningEffectError> {
+    MiningEffect::try_from(slab)
+}
+
+#[test]
+fn test_well_formed_effect_verifies() {
+    let effect = decode(effect_with(|slab| proof(slab, LENGTH), &NONCE)).unwrap();
+    assert_eq!(effect.block_commitment, COMMITMENT);
+    assert_eq!(effect.puzzle.length, LENGTH);
+    effect.verify(&input()).unwrap();
+}
+
+#[test]
+fn test_missing_tail() {
+    let bare = effect_from(|_| D(tas!(b"command")));
+    assert_eq!(
+        decode(bare).unwrap_err(),
+        MiningEffectError::Missing("command tag")
+    );
+
+    let no_proof = effect_from(|slab| T(slab, &[D(tas!(b"command")), D(tas!(b"pow"))]));
+    assert_eq!(
+        decode(no_proof).unwrap_err(),
+        MiningEffectError::Missing("proof")
+    );
+
+    let no_nonce = effect_from(|slab| {
+        let proof = proof(slab, LENGTH);
+        T(
+            slab,
+            &[D(tas!(b"command")), D(tas!(b"pow")), proof, D(0x1234), D(0)],
+        )
+    });
+    assert_eq!(
+        decode(no_nonce).unwrap_err(),
+        MiningEffectError::Missing("nonce")
+    );
+}
+
+#[test]
+fn test_wrong_tag() {
+    let log = effect_from(|slab| T(slab, &[D(tas!(b"log")), D(tas!(b"pow")), D(0)]));
+    assert_eq!(
+        decode(log).unwrap_err(),
+        MiningEffectError::WrongTag("command")
+    );
+
+    let other = effect_from(|slab| T(slab, &[D(tas!(b"command")), D(tas!(b"foo")), D(0)]));
+    assert_eq!(
+        decode(other).unwrap_err(),
+        MiningEffectError::WrongTag("pow")
+    );
+}
+
+#[test]
+fn test_truncated_proof() {
+    let atom_proof = effect_with(|_| D(0), &NONCE);
+    assert_eq!(
+        decode(atom_proof).unwrap_err(),
+        MiningEffectError::TruncatedProof("version")
+    );
+
+    let no_hashes = effect_with(|slab| T(slab, &[D(0), D(0), D(0)]), &NONCE);
+    assert_eq!(
+        decode(no_hashes).unwrap_err(),
+        MiningEffectError::TruncatedProof("hashes")
+    );
+
+    // an objects list that ends in something other than ~
+    let improper = effect_with(|slab| T(slab, &[D(0), D(7), D(0), D(0)]), &NONCE);
+    assert_eq!(
+        decode(improper).unwrap_err(),
+        MiningEffectError::TruncatedProof("objects")
+    );
+
+    let no_puzzle = effect_with(|slab| T(slab, &[D(0), D(0), D(0), D(0)]), &NONCE);
+    assert_eq!(
+        decode(no_puzzle).unwrap_err(),
+        MiningEffectError::MissingPuzzle
+    );
+
+    let version = effect_with(|slab| T(slab, &[D(1), D(0), D(0), D(0)]), &NONCE);
+    assert_eq!(
+        decode(version).unwrap_err(),
+        MiningEffectError::ProofVersion(1)
+    );
+}
+
+#[test]
+fn test_malformed_digest() {
+    let short = effect_with(|slab| proof(slab, LENGTH), &NONCE[..4]);
+    assert_eq!(
+        decode(short).unwrap_err(),
+        MiningEffectError::Digest {
+            field: "nonce",
+            source: ProveInputError::WrongShape {
+                field: "nonce",
+                got: 4
+            },
+        }
+    );
+
+    let out_of_range = effect_with(|slab| proof(slab, LENGTH), &[6, 7, PRIME, 9, 10]);
+    assert!(matches!(
+        decode(out_of_range).unwrap_err(),
+        MiningEffectError::Digest {
+            source: ProveInputError::BeltOutOfRange { index: 2, .. },
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_verify_rejects_mismatches() {
+    let wrong_nonce = decode(effect_with(|slab| proof(slab, LENGTH), &[0; 5])).unwrap();
+    assert_eq!(
+        wrong_nonce.verify(&input()),
+        Err(MiningEffectError::Mismatch("nonce"))
+    );
+
+    let wrong_length = decode(effect_with(|slab| proof(slab, 2), &NONCE)).unwrap();
+    assert_eq!(
+        wrong_length.verify(&input()),
+        Err(MiningEffectError::Mismatch("length"))
+    );
+}
+
+#[test]
+fn test_proof_view_reads_in_place() {
+    let effect = decode(effect_with(|slab| proof(slab, LENGTH), &NONCE)).unwrap();
+    let proof = effect.proof();
+    assert_eq!(proof.version(), 0);
+    assert_eq!(proof.read_index(), Some(0));
+    assert_eq!(proof.hashes().count(), 0);
+
+    let objects: Vec<_> = proof.objects().collect();
+    assert_eq!(objects.len(), 1);
+    assert!(objects[0].is("puzzle"));
+    assert_eq!(objects[0].tag().as_deref(), Some("puzzle"));
+    assert_eq!(proof.puzzle().unwrap(), effect.puzzle);
+
+    // indirect atoms are borrowed straight out of the slab
+    let mut slab = NounSlab::new();
+    let big = nockvm::noun::Atom::new(&mut slab, u64::MAX).as_noun();
+    slab.set_root(big);
+    let bytes = atom_bytes(&slab, big).unwrap();
+    assert!(matches!(bytes, Cow::Borrowed(_)));
+    assert_eq!(&bytes[..8], &u64::MAX.to_le_bytes());
+    assert!(matches!(atom_bytes(&slab, D(7)), Some(Cow::Owned(_))));
+}
+
+#[test]
+fn test_parse_objects_keeps_order() {
+    let effect = decode(effect_with(
+        |slab| {
+            let commitment = digest(slab, &COMMITMENT);
+            let nonce = digest(slab, &NONCE);
+            let puzzle = T(
+                slab,
+                &[D(tas!(b"puzzle")), commitment, nonce, D(LENGTH), D(0)],
+            );
+            let root = digest(slab, &[9, 9, 9, 9, 9]);
+            let m_root = T(slab, &[D(tas!(b"m-root")), root]);
+            // two belts plus the high marker word
+            let words: Vec<u8> = [11u64, 22, 1]
+                .iter()
+                .flat_map(|word| word.to_le_bytes())
+                .collect();
+            let dat = unsafe { IndirectAtom::new_raw_bytes_ref(slab, &words).normalize_as_atom() };
+            let terms = T(slab, &[D(tas!(b"terms")), D(2), dat.as_noun()]);
+            let heights = T(slab, &[D(tas!(b"heights")), D(3), D(4), D(0)]);
+            let m_path = T(slab, &[D(tas!(b"m-path")), D(0), D(0)]);
+            let objects = T(slab, &[m_root, puzzle, terms, heights, m_path, D(0)]);
+            T(slab, &[D(0), objects, D(0), D(0)])
+        },
+        &NONCE,
+    ))
+    .unwrap();
+
+    let objects = effect.proof().parse_objects().unwrap();
+    assert_eq!(objects.len(), 5);
+    assert_eq!(objects[0], ProofObject::MRoot([9, 9, 9, 9, 9]));
+    assert_eq!(objects[1], ProofObject::Puzzle(effect.puzzle.clone()));
+    assert_eq!(objects[2], ProofObject::Terms(vec![11, 22]));
+    assert_eq!(objects[3], ProofObject::Heights(vec![3, 4]));
+    assert!(matches!(
+        &objects[4],
+        ProofObject::Other { tag: Some(tag), .. } if tag == "m-path"
+    ));
+}
+
+#[test]
+fn test_parse_objects_rejects_short_poly() {
+    let effect = decode(effect_with(
+        |slab| {
+            let commitment = digest(slab, &COMMITMENT);
+            let nonce = digest(slab, &NONCE);
+            let puzzle = T(
+                slab,
+                &[D(tas!(b"puzzle")), commitment, nonce, D(LENGTH), D(0)],
+            );
+            // claims 4 felts but carries a single word
+            let codeword = T(slab, &[D(tas!(b"codeword")), D(4), D(1)]);
+            let objects = T(slab, &[puzzle, codeword, D(0)]);
+            T(slab, &[D(0), objects, D(0), D(0)])
+        },
+        &NONCE,
+    ))
+    .unwrap();
+    assert_eq!(
+        effect.proof().parse_objects(),
+        Err(MiningEffectError::TruncatedProof("codeword"))
+    );
+}
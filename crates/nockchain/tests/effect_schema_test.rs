@@ -0,0 +1,89 @@
+use nockapp::noun::slab::NounSlab;
+use nockchain::effect::{validate_effect_schema, ExpectedSchema, SchemaMismatch};
+use nockvm::noun::{Noun, D, T};
+use nockvm_macros::tas;
+
+fn digest(slab: &mut NounSlab, first: u64) -> Noun {
+    T(slab, &[D(first), D(0), D(0), D(0), D(0)])
+}
+
+/// A `%pow` effect whose proof hashes are `hashes`
+fn pow_effect(hashes: impl FnOnce(&mut NounSlab) -> Noun) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let hashes = hashes(&mut slab);
+    let proof = T(&mut slab, &[D(0), D(0), hashes, D(0)]);
+    let commitment = digest(&mut slab, 1);
+    let nonce = digest(&mut slab, 2);
+    let effect = T(
+        &mut slab,
+        &[
+            D(tas!(b"command")),
+            D(tas!(b"pow")),
+            proof,
+            D(0x1234),
+            commitment,
+            nonce,
+        ],
+    );
+    slab.set_root(effect);
+    slab
+}
+
+fn validate(slab: &NounSlab) -> Result<(), SchemaMismatch> {
+    validate_effect_schema(unsafe { *slab.root() }, &ExpectedSchema::pow_effect())
+}
+
+#[test]
+fn test_pow_effect_matches_schema() {
+    let effect = pow_effect(|slab| {
+        let hash = digest(slab, 3);
+        T(slab, &[hash, D(0)])
+    });
+    validate(&effect).unwrap();
+}
+
+#[test]
+fn test_schema_mismatch_reports_path() {
+    // second hash has only four belts
+    let short_hash = pow_effect(|slab| {
+        let good = digest(slab, 3);
+        let short = T(slab, &[D(1), D(2), D(3), D(4)]);
+        T(slab, &[good, short, D(0)])
+    });
+    assert_eq!(
+        validate(&short_hash).unwrap_err(),
+        SchemaMismatch {
+            path: "prf.hashes.[1].3".to_string(),
+            expected: "a cell".to_string(),
+            found: "atom 4".to_string(),
+        }
+    );
+
+    let improper = pow_effect(|_| D(7));
+    assert_eq!(
+        validate(&improper).unwrap_err(),
+        SchemaMismatch {
+            path: "prf.hashes.[0]".to_string(),
+            expected: "~".to_string(),
+            found: "atom 7".to_string(),
+        }
+    );
+
+    let mut wrong_tag = NounSlab::new();
+    let root = T(
+        &mut wrong_tag,
+        &[D(tas!(b"command")), D(tas!(b"pew")), D(0)],
+    );
+    wrong_tag.set_root(root);
+    let mismatch = validate(&wrong_tag).unwrap_err();
+    assert_eq!(mismatch.path, "command");
+    assert_eq!(mismatch.expected, "%pow");
+}
+
+#[test]
+fn test_schema_root_mismatch() {
+    let mismatch = validate_effect_schema(D(0), &ExpectedSchema::pow_effect()).unwrap_err();
+    assert_eq!(mismatch.path, "tag");
+    assert_eq!(mismatch.expected, "a cell");
+    assert_eq!(mismatch.found, "atom 0");
+}
@@ -0,0 +1,141 @@
+use std::sync::{Arc, Mutex};
+
+use futures::future::BoxFuture;
+use ibig::UBig;
+use nockapp::noun::slab::NounSlab;
+use nockchain::block_store::{BlockStore, PruneMode};
+use nockchain::consensus::difficulty::block_work;
+use nockchain::fast_sync::{install, FastSyncError, Snapshot};
+use nockchain::light_client::{BlockHeader, LightClient, LightClientError, ProofVerifier};
+use nockchain::reorg::ReorgConfig;
+use nockvm::noun::{D, T};
+
+mod common;
+
+use common::Applied;
+
+struct AcceptAll;
+
+impl ProofVerifier for AcceptAll {
+    fn verify<'a>(
+        &'a self,
+        _header: &'a BlockHeader,
+        _proof: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), LightClientError>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+fn digest(height: u64) -> [u64; 5] {
+    [height + 1, 0, 0, 0, 0]
+}
+
+fn page(height: u64) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let [a, b, c, d, e] = digest(height).map(D);
+    let id = T(&mut slab, &[a, b, c, d, e]);
+    let page = T(&mut slab, &[id, D(height)]);
+    slab.set_root(page);
+    slab
+}
+
+/// A light client trusting headers up to `tip`
+fn client(tip: u64) -> LightClient<AcceptAll> {
+    let mut client = LightClient::new(AcceptAll);
+    let target = UBig::from(1_000u32);
+    for height in 0..=tip {
+        let header = BlockHeader {
+            digest: digest(height),
+            timestamp: height * 60,
+            epoch_counter: 0,
+            accumulated_work: block_work(&target) * UBig::from(height + 1),
+            target: target.clone(),
+            height,
+            parent: [height, 0, 0, 0, 0],
+        };
+        client.insert_trusted(header);
+    }
+    client
+}
+
+/// A snapshot of a node that applied blocks up to `height`
+async fn snapshot(height: u64) -> Snapshot {
+    let state = Applied(Arc::new(Mutex::new((0..=height).collect())));
+    Snapshot::create(&state, height, digest(height), &page(height))
+        .await
+        .unwrap()
+}
+
+fn store(dir: &tempfile::TempDir) -> BlockStore {
+    BlockStore::open(dir.path().join("blocks"), PruneMode::Archive).unwrap()
+}
+
+#[tokio::test]
+async fn test_install_continues_from_the_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let client = client(20);
+    let mut follower = install(
+        Applied::default(),
+        snapshot(8).await,
+        &client,
+        10,
+        store(&dir),
+        dir.path().join("snapshots"),
+        ReorgConfig::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(follower.tip(), Some((8, digest(8))));
+    assert_eq!(follower.state().blocks(), (0..=8).collect::<Vec<_>>());
+
+    follower.extend(digest(9), page(9)).await.unwrap();
+    assert_eq!(follower.state().blocks(), (0..=9).collect::<Vec<_>>());
+    assert!(follower.store().get_slab(3).is_err());
+}
+
+#[tokio::test]
+async fn test_install_checks_the_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let client = client(20);
+    let try_install = |snapshot| {
+        install(
+            Applied::default(),
+            snapshot,
+            &client,
+            10,
+            store(&dir),
+            dir.path().join("snapshots"),
+            ReorgConfig::default(),
+        )
+    };
+
+    assert!(matches!(
+        try_install(snapshot(15).await).await,
+        Err(FastSyncError::TooRecent {
+            confirmations: 5,
+            needed: 10
+        })
+    ));
+
+    let mut forked = snapshot(5).await;
+    forked.manifest.block = [99, 0, 0, 0, 0];
+    assert!(matches!(
+        try_install(forked).await,
+        Err(FastSyncError::NotOnBestChain { height: 5, .. })
+    ));
+
+    let mut tampered = snapshot(5).await;
+    let last = tampered.archive.len() - 1;
+    tampered.archive[last] ^= 1;
+    assert!(matches!(
+        try_install(tampered).await,
+        Err(FastSyncError::DigestMismatch)
+    ));
+
+    let mut wrong_page = snapshot(5).await;
+    wrong_page.page = page(6).jam().to_vec();
+    assert!(matches!(
+        try_install(wrong_page).await,
+        Err(FastSyncError::WrongPage)
+    ));
+}
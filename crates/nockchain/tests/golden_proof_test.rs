@@ -0,0 +1,211 @@
+//! Golden proof fixtures.
+//!
+//! `tests/fixtures/golden/len-<n>.jam.zst` holds the zstd-compressed jam of
+//! `[cause effects]` for a small canonical prove-block poke. The proofs are
+//! re-derived with both the jetted prover and the plain Hoon prover and must
+//! match the fixture exactly, so a jet that changes its output (or a kernel
+//! change that alters proofs) fails here instead of on someone's disk. Jets
+//! with no simpler reference are also checked one at a time: proving with
+//! just their entries left out runs their Hoon arms on the inputs the
+//! prover gives them, which have to produce the same proof.
+//!
+//! Run with `cargo test -p nockchain --features golden-fixtures --test golden_proof_test`.
+//! After an intentional proof change, regenerate the fixtures with
+//! `GOLDEN_REGENERATE=1 cargo test -p nockchain --features golden-fixtures --test golden_proof_test -- --ignored`.
+#![cfg(feature = "golden-fixtures")]
+
+use std::fs;
+use std::path::PathBuf;
+
+use kernels::miner::KERNEL;
+use nockapp::kernel::checkpoint::JamPaths;
+use nockapp::kernel::form::Kernel;
+use nockapp::noun::slab::{slab_equality, NounSlab};
+use nockapp::wire::Wire;
+use nockvm::jets::hot::HotEntry;
+use nockvm::noun::{D, T};
+use tempfile::tempdir;
+use zkvm_jetpack::hot::produce_prover_hot_state;
+
+/// Proof lengths with a checked-in fixture
+const GOLDEN_LENGTHS: [u64; 2] = [2, 4];
+
+const COMPRESSION_LEVEL: i32 = 19;
+
+pub enum MiningWire {
+    Candidate,
+}
+
+impl Wire for MiningWire {
+    const VERSION: u64 = 1;
+    const SOURCE: &'static str = "miner";
+
+    fn to_wire(&self) -> nockapp::wire::WireRepr {
+        let tags = vec!["candidate".into()];
+        nockapp::wire::WireRepr::new(MiningWire::SOURCE, MiningWire::VERSION, tags)
+    }
+}
+
+fn fixture_path(length: u64) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/golden")
+        .join(format!("len-{}.jam.zst", length))
+}
+
+/// The canonical `[length block-commitment nonce]` cause for `length`
+fn golden_cause(length: u64) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let commitment = T(&mut slab, &[D(0x1), D(0x2), D(0x3), D(0x4), D(0x5)]);
+    let nonce = T(&mut slab, &[D(0x10), D(0x20), D(0x30), D(0x40), D(length)]);
+    let cause = T(&mut slab, &[D(length), commitment, nonce]);
+    slab.set_root(cause);
+    slab
+}
+
+async fn prove(cause: NounSlab, hot_state: &[HotEntry]) -> NounSlab {
+    let snapshot_dir = tempdir().expect("Could not create snapshot dir");
+    let jam_paths = JamPaths::new(snapshot_dir.path());
+    let kernel = Kernel::load_with_hot_state_huge(
+        snapshot_dir.path().to_path_buf(),
+        jam_paths,
+        KERNEL,
+        hot_state,
+        false,
+    )
+    .await
+    .expect("Could not load mining kernel");
+    kernel
+        .poke(MiningWire::Candidate.to_wire(), cause)
+        .await
+        .expect("Prove poke failed")
+}
+
+/// The prover's hot state without the jets of `arms`, which run as Hoon
+fn hot_state_without(arms: &[&[u8]]) -> Vec<HotEntry> {
+    let full = produce_prover_hot_state();
+    let hot_state: Vec<HotEntry> = full
+        .iter()
+        .copied()
+        .filter(|(path, _, _)| {
+            !matches!(path.last().and_then(|name| name.left()), Some(arm) if arms.contains(&arm))
+        })
+        .collect();
+    assert_eq!(
+        hot_state.len() + arms.len(),
+        full.len(),
+        "every arm should have one jet"
+    );
+    hot_state
+}
+
+/// Prove every fixture with the jets of `arms` left out
+async fn assert_hoon_arms_match_fixtures(arms: &[&[u8]]) {
+    let hot_state = hot_state_without(arms);
+    for length in GOLDEN_LENGTHS {
+        let (cause, expected) = load_fixture(length);
+        let effects = prove(cause, &hot_state).await;
+        assert!(
+            slab_equality(&effects, &expected),
+            "proof for length {} with {:?} run as Hoon differs from golden fixture",
+            length,
+            arms.iter()
+                .map(|arm| String::from_utf8_lossy(arm))
+                .collect::<Vec<_>>()
+        );
+    }
+}
+
+/// Load a fixture as `(cause, effects)`
+fn load_fixture(length: u64) -> (NounSlab, NounSlab) {
+    let path = fixture_path(length);
+    let compressed = fs::read(&path).unwrap_or_else(|e| {
+        panic!(
+            "Missing golden fixture {:?} ({e}), see the module docs to regenerate",
+            path
+        )
+    });
+    let jam = zstd::decode_all(&compressed[..]).expect("Could not decompress golden fixture");
+    let mut fixture = NounSlab::new();
+    let root = fixture
+        .cue_into(jam.into())
+        .expect("Could not cue golden fixture");
+    let cell = root.as_cell().expect("Golden fixture is not a cell");
+
+    let mut cause = NounSlab::new();
+    cause.copy_into(cell.head());
+    let mut effects = NounSlab::new();
+    effects.copy_into(cell.tail());
+    (cause, effects)
+}
+
+#[tokio::test]
+async fn test_golden_proofs_jetted() {
+    let hot_state = produce_prover_hot_state();
+    for length in GOLDEN_LENGTHS {
+        let (cause, expected) = load_fixture(length);
+        let effects = prove(cause, &hot_state).await;
+        assert!(
+            slab_equality(&effects, &expected),
+            "jetted proof for length {} differs from golden fixture",
+            length
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_golden_proofs_unjetted() {
+    for length in GOLDEN_LENGTHS {
+        let (cause, expected) = load_fixture(length);
+        let effects = prove(cause, &[]).await;
+        assert!(
+            slab_equality(&effects, &expected),
+            "unjetted proof for length {} differs from golden fixture",
+            length
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_mp_substitute_jets_match_hoon() {
+    // +mp-substitute-ultra as Hoon hands the mega jet the inputs it splits out
+    assert_hoon_arms_match_fixtures(&[b"mp-substitute-ultra"]).await;
+    assert_hoon_arms_match_fixtures(&[b"mp-substitute-ultra", b"mp-substitute-mega"]).await;
+}
+
+#[tokio::test]
+async fn test_compute_deep_jet_matches_hoon() {
+    assert_hoon_arms_match_fixtures(&[b"compute-deep"]).await;
+}
+
+#[tokio::test]
+async fn test_zerofier_jets_match_hoon() {
+    assert_hoon_arms_match_fixtures(&[b"vanishing-bpoly", b"bpdiv"]).await;
+}
+
+#[tokio::test]
+#[ignore]
+async fn regenerate_golden_fixtures() {
+    if std::env::var_os("GOLDEN_REGENERATE").is_none() {
+        return;
+    }
+    let hot_state = produce_prover_hot_state();
+    for length in GOLDEN_LENGTHS {
+        let cause = golden_cause(length);
+        let effects = prove(cause.clone(), &hot_state).await;
+
+        let mut fixture = NounSlab::new();
+        fixture.copy_into(unsafe { *cause.root() });
+        let cause_noun = unsafe { *fixture.root() };
+        fixture.copy_into(unsafe { *effects.root() });
+        let effects_noun = unsafe { *fixture.root() };
+        let root = T(&mut fixture, &[cause_noun, effects_noun]);
+        fixture.set_root(root);
+
+        let compressed = zstd::encode_all(&fixture.jam()[..], COMPRESSION_LEVEL)
+            .expect("Could not compress golden fixture");
+        let path = fixture_path(length);
+        fs::create_dir_all(path.parent().unwrap()).expect("Could not create fixture dir");
+        fs::write(&path, compressed).expect("Could not write golden fixture");
+        println!("Wrote golden fixture {:?}", path);
+    }
+}
@@ -0,0 +1,328 @@
+use std::fs::OpenOptions;
+
+use futures::future::BoxFuture;
+use ibig::UBig;
+use nockapp::noun::slab::NounSlab;
+use nockapp::utils::make_tas;
+use nockchain::consensus::difficulty::block_work;
+use nockchain::header_sync::{
+    follow_stream, HeaderSync, HeaderSyncConfig, HeaderSyncError, SyncPhase, SyncRequest,
+};
+use nockchain::light_client::{
+    BlockHeader, BlockId, HeaderProof, LightClient, LightClientError, ProofVerifier,
+};
+use nockvm::noun::{Atom, Noun, D, T};
+use nockvm_macros::tas;
+
+mod common;
+
+use common::{heavy_header, mine_with_proof, regtest_kernel, seal, MINER_A};
+
+/// Accepts every proof, leaving the light client's own checks
+struct AcceptAll;
+
+impl ProofVerifier for AcceptAll {
+    fn verify<'a>(
+        &'a self,
+        _header: &'a BlockHeader,
+        _proof: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), LightClientError>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+fn header(height: u64, parent: BlockId) -> BlockHeader {
+    let target = UBig::from(1_000u32);
+    BlockHeader {
+        digest: [0; 5],
+        timestamp: height * 60,
+        epoch_counter: 0,
+        accumulated_work: block_work(&target) * UBig::from(height + 1),
+        target,
+        height,
+        parent,
+    }
+}
+
+/// A chain of `len` sealed headers from height 0
+fn chain(len: u64) -> Vec<(BlockHeader, HeaderProof)> {
+    let mut chain: Vec<(BlockHeader, HeaderProof)> = Vec::new();
+    for height in 0..len {
+        let parent = chain.last().map_or([0; 5], |(parent, _)| parent.digest);
+        chain.push(seal(header(height, parent), height));
+    }
+    chain
+}
+
+fn digests(headers: &[(BlockHeader, HeaderProof)]) -> Vec<BlockId> {
+    headers.iter().map(|(header, _)| header.digest).collect()
+}
+
+/// A page noun, of which only the digest matters here
+fn page(digest: BlockId) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let id = T(&mut slab, &digest.map(D));
+    let page = T(&mut slab, &[id, D(0)]);
+    slab.set_root(page);
+    slab
+}
+
+const CONFIG: HeaderSyncConfig = HeaderSyncConfig {
+    header_batch: 4,
+    block_batch: 3,
+};
+
+fn open(path: &std::path::Path) -> HeaderSync<AcceptAll> {
+    HeaderSync::open(LightClient::new(AcceptAll), path, CONFIG).unwrap()
+}
+
+#[tokio::test]
+async fn test_headers_then_blocks() {
+    let dir = tempfile::tempdir().unwrap();
+    let chain = chain(6);
+    let mut sync = open(&dir.path().join("headers.log"));
+    assert_eq!(
+        sync.next_request(None),
+        SyncRequest::Headers {
+            after: None,
+            max: 4
+        }
+    );
+    assert_eq!(sync.on_headers(chain[0..4].to_vec()).await.unwrap(), 4);
+    assert_eq!(sync.phase(), SyncPhase::Headers);
+    assert_eq!(
+        sync.next_request(None),
+        SyncRequest::Headers {
+            after: Some(chain[3].0.digest),
+            max: 4
+        }
+    );
+    // the peer has only two more
+    assert_eq!(sync.on_headers(chain[4..6].to_vec()).await.unwrap(), 2);
+    assert_eq!(sync.phase(), SyncPhase::Blocks);
+
+    assert_eq!(
+        sync.next_request(None),
+        SyncRequest::Blocks(digests(&chain[0..3]))
+    );
+    assert_eq!(
+        sync.next_request(Some(3)),
+        SyncRequest::Blocks(digests(&chain[4..6]))
+    );
+    assert_eq!(sync.next_request(Some(5)), SyncRequest::Synced);
+
+    assert_eq!(
+        sync.check_block(&page(chain[2].0.digest)).unwrap().height,
+        2
+    );
+    assert!(matches!(
+        sync.check_block(&page([9; 5])),
+        Err(HeaderSyncError::UnexpectedBlock(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_restart_resumes_from_logged_headers() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("headers.log");
+    let chain = chain(8);
+    let mut sync = open(&path);
+    sync.on_headers(chain[0..4].to_vec()).await.unwrap();
+    // a proof mined for another block stops the batch, the headers before
+    // it are kept
+    let mut batch = chain[4..8].to_vec();
+    batch[2].1 = chain[7].1.clone();
+    assert!(matches!(
+        sync.on_headers(batch).await,
+        Err(HeaderSyncError::Header(LightClientError::WrongCommitment(
+            _
+        )))
+    ));
+    drop(sync);
+
+    // and a crash cut the last header short
+    let log = OpenOptions::new().write(true).open(&path).unwrap();
+    let len = log.metadata().unwrap().len();
+    log.set_len(len - 3).unwrap();
+
+    let mut sync = open(&path);
+    assert_eq!(sync.client().best_header().unwrap().height, 4);
+    assert_eq!(
+        sync.next_request(None),
+        SyncRequest::Headers {
+            after: Some(chain[4].0.digest),
+            max: 4
+        }
+    );
+    assert_eq!(sync.on_headers(chain[5..7].to_vec()).await.unwrap(), 2);
+    assert_eq!(sync.client().best_header().unwrap().height, 6);
+}
+
+/// A `bignum`, `[%bn (list u32)]` least significant limb first
+fn bignum(slab: &mut NounSlab, value: &UBig) -> Noun {
+    let mut limbs = Vec::new();
+    let mut rest = value.clone();
+    while rest != UBig::from(0u8) {
+        limbs.push(u64::try_from(&rest % UBig::from(1u64 << 32)).unwrap());
+        rest >>= 32;
+    }
+    let mut list = D(0);
+    for limb in limbs.into_iter().rev() {
+        list = T(slab, &[D(limb), list]);
+    }
+    T(slab, &[D(tas!(b"bn")), list])
+}
+
+/// A length-prefixed jam of `[page-summary tx-ids coinbase msg proof]`
+fn frame((header, proof): &(BlockHeader, HeaderProof)) -> Vec<u8> {
+    let mut slab = NounSlab::new();
+    let digest = T(&mut slab, &header.digest.map(D));
+    let parent = T(&mut slab, &header.parent.map(D));
+    let target = bignum(&mut slab, &header.target);
+    let work = bignum(&mut slab, &header.accumulated_work);
+    let summary = T(
+        &mut slab,
+        &[
+            digest,
+            D(header.timestamp),
+            D(header.epoch_counter),
+            target,
+            work,
+            D(header.height),
+            parent,
+        ],
+    );
+    let tx_ids = T(&mut slab, &proof.tx_ids.map(D));
+    let coinbase = T(&mut slab, &proof.coinbase.map(D));
+    let msg = T(&mut slab, &proof.msg.map(D));
+    let pow = slab.cue_into(proof.proof.clone().into()).unwrap();
+    let root = T(&mut slab, &[summary, tx_ids, coinbase, msg, pow]);
+    slab.set_root(root);
+    let jam = slab.jam();
+    let mut framed = (jam.len() as u64).to_le_bytes().to_vec();
+    framed.extend_from_slice(&jam);
+    framed
+}
+
+#[tokio::test]
+async fn test_follow_stream_reports_every_header() {
+    let chain = chain(2);
+    let mut inflated = header(2, chain[1].0.digest);
+    inflated.accumulated_work += UBig::from(1u8);
+    let inflated = seal(inflated, 2);
+    let mut input = Vec::new();
+    for header in [&chain[0], &chain[1], &inflated] {
+        input.extend(frame(header));
+    }
+    input.extend(3u64.to_le_bytes());
+    input.extend([0xff, 0xff, 0xff]);
+
+    let mut client = LightClient::new(AcceptAll);
+    let mut output = Vec::new();
+    let read = follow_stream(&mut client, input.as_slice(), &mut output, 1 << 20)
+        .await
+        .unwrap();
+    assert_eq!(read, 4);
+    assert_eq!(client.best_header().unwrap().height, 1);
+
+    let lines: Vec<serde_json::Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[1]["height"], 1);
+    assert_eq!(lines[1]["best"], true);
+    assert!(lines[1].get("error").is_none());
+    assert!(lines[2]["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("accumulated work"));
+    assert!(lines[3]["height"].is_null());
+    assert!(lines[3]["error"].is_string());
+}
+
+/// A `[len dat]` poly of `words`, with the high marker bit past the last
+fn poly(slab: &mut NounSlab, len: u64, words: &[u64]) -> Noun {
+    let mut dat = UBig::from(1u8) << (64 * words.len());
+    for (i, &word) in words.iter().enumerate() {
+        dat += UBig::from(word) << (64 * i);
+    }
+    let dat = Atom::from_ubig(slab, &dat).as_noun();
+    T(slab, &[D(len), dat])
+}
+
+/// A proof with an object of every kind the kernel hashes, answering the
+/// candidate's puzzle. Regtest takes it without verifying it.
+fn every_object(slab: &mut NounSlab, commitment: [u64; 5], nonce: [u64; 5]) -> Noun {
+    let fpoly = |slab: &mut NounSlab, seed: u64| poly(slab, 2, &[seed, 1, 2, 3, 4, 5]);
+    let path = |slab: &mut NounSlab, seed: u64| {
+        let a = T(slab, &[seed; 5].map(D));
+        let b = T(slab, &[seed + 1; 5].map(D));
+        T(slab, &[a, b, D(0)])
+    };
+    let commitment = T(slab, &commitment.map(D));
+    let nonce = T(slab, &nonce.map(D));
+    let puzzle = T(slab, &[D(tas!(b"puzzle")), commitment, nonce, D(64), D(0)]);
+    let root = T(slab, &[1, 2, 3, 4, 5].map(D));
+    let m_root = T(slab, &[D(tas!(b"m-root")), root]);
+    let words = fpoly(slab, 7);
+    let codeword = T(slab, &[D(tas!(b"codeword")), words]);
+    let words = poly(slab, 3, &[8, 9, 10]);
+    let terms = T(slab, &[D(tas!(b"terms")), words]);
+    let mut paths = Vec::new();
+    for seed in [11, 12, 13] {
+        let (leaf, nodes) = (fpoly(slab, seed), path(slab, seed));
+        paths.push(T(slab, &[leaf, nodes]));
+    }
+    let tag = make_tas(slab, "m-paths").as_noun();
+    let m_paths = T(slab, &[tag, paths[0], paths[1], paths[2]]);
+    let (leaf, nodes) = (fpoly(slab, 14), path(slab, 14));
+    let m_path = T(slab, &[D(tas!(b"m-path")), leaf, nodes]);
+    let (leaf, nodes) = (poly(slab, 1, &[15]), path(slab, 15));
+    let tag = make_tas(slab, "m-pathbf").as_noun();
+    let m_pathbf = T(slab, &[tag, leaf, nodes]);
+    let comp_root = T(slab, &[6, 7, 8, 9, 10].map(D));
+    let comp_m = T(slab, &[D(tas!(b"comp-m")), comp_root, D(16)]);
+    let words = fpoly(slab, 17);
+    let evals = T(slab, &[D(tas!(b"evals")), words]);
+    let heights = T(slab, &[D(tas!(b"heights")), D(3), D(4), D(0)]);
+    let words = poly(slab, 2, &[18, 19]);
+    let poly = T(slab, &[D(tas!(b"poly")), words]);
+    let objects = T(
+        slab,
+        &[
+            puzzle,
+            m_root,
+            codeword,
+            terms,
+            m_paths,
+            m_path,
+            m_pathbf,
+            comp_m,
+            evals,
+            heights,
+            poly,
+            D(0),
+        ],
+    );
+    T(slab, &[D(0), objects, D(0), D(0)])
+}
+
+/// The light client hashes headers and proofs the way the kernel does:
+/// every block a regtest kernel mines hashes to the digest it was given
+#[tokio::test(flavor = "multi_thread")]
+async fn test_kernel_headers_hash_to_their_digests() {
+    let dir = tempfile::tempdir().unwrap();
+    let kernel = regtest_kernel(&dir, Some(MINER_A), true).await;
+    let mut client = LightClient::new(AcceptAll);
+    for height in 0..3 {
+        let (digest, _) = mine_with_proof(&kernel, |slab, candidate| {
+            every_object(slab, candidate.block_commitment, candidate.nonce)
+        })
+        .await;
+        let (header, proof) = heavy_header(&kernel, height);
+        assert_eq!(header.digest, digest);
+        assert!(client.accept_header(header, &proof).await.unwrap());
+    }
+}
@@ -0,0 +1,128 @@
+//! Prove, verify and status requests to a [`KernelDaemon`] over its socket.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use ibig::UBig;
+use nockapp::kernel::form::Entropy;
+use nockapp::noun::slab::NounSlab;
+use nockapp::CrownError;
+use nockchain::backend::{ProvingBackend, SimulatedBackend};
+use nockchain::daemon::{
+    bind, DaemonClient, DaemonConfig, DaemonError, DaemonRequest, DaemonResponse, HostedKernel,
+    KernelDaemon,
+};
+use nockchain::poke::{PokeQueue, QueueDepth};
+use nockchain::prove_input::ProveBlockInput;
+use nockchain::verifier::{ProofCheck, Verifier, VerifierBackend, VerifyError};
+use nockvm::noun::D;
+use tempfile::tempdir;
+
+/// Finds every proof valid with hash 42
+struct ValidVerifier;
+
+impl VerifierBackend for ValidVerifier {
+    fn load(&self, _stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Verifier>, CrownError>> {
+        Box::pin(async move { Ok(Box::new(ValidVerifier) as Box<dyn Verifier>) })
+    }
+}
+
+impl Verifier for ValidVerifier {
+    fn verify(
+        &self,
+        _proof: NounSlab,
+        _entropy: Entropy,
+    ) -> BoxFuture<'static, Result<ProofCheck, VerifyError>> {
+        Box::pin(async move { Ok(ProofCheck::valid(UBig::from(42u8))) })
+    }
+
+    fn cancel(&self) -> bool {
+        false
+    }
+}
+
+fn input() -> ProveBlockInput {
+    ProveBlockInput::builder()
+        .length(4)
+        .block_commitment(&[1, 2, 3, 4, 5])
+        .nonce(&[6, 7, 8, 9, 10])
+        .build()
+        .unwrap()
+}
+
+fn daemon(verifier: Option<Arc<dyn VerifierBackend>>) -> Arc<KernelDaemon> {
+    let kernel = HostedKernel::new(
+        Arc::new(SimulatedBackend::new(Duration::ZERO)),
+        verifier,
+        PokeQueue::default(),
+    );
+    Arc::new(KernelDaemon::with_kernels(
+        vec![kernel],
+        &DaemonConfig::default(),
+    ))
+}
+
+#[tokio::test]
+async fn test_prove_verify_and_status_over_socket() {
+    let dir = tempdir().unwrap();
+    let socket = dir.path().join("daemon.sock");
+    let daemon = daemon(Some(Arc::new(ValidVerifier)));
+    daemon.preload().await.unwrap();
+    tokio::spawn(daemon.clone().serve(bind(&socket).await.unwrap()));
+
+    let mut client = DaemonClient::connect(&socket).await.unwrap();
+    let candidate = input().to_noun_slab();
+    let effects = client.prove(&candidate, Entropy::Fixed(0)).await.unwrap();
+    let expected = SimulatedBackend::default()
+        .load(0)
+        .await
+        .unwrap()
+        .prove(candidate, Entropy::Fixed(0))
+        .await
+        .unwrap();
+    assert_eq!(effects.jam(), expected.jam());
+
+    let check = client.verify(&effects).await.unwrap();
+    assert_eq!(check, ProofCheck::valid(UBig::from(42u8)));
+
+    let status = client.status().await.unwrap();
+    assert_eq!(status.kernels, vec![QueueDepth::default()]);
+    assert_eq!(status.served, 2);
+
+    // a second daemon cannot take over the socket of a live one
+    assert!(bind(&socket).await.is_err());
+}
+
+#[tokio::test]
+async fn test_failed_requests_are_answered() {
+    let daemon = daemon(None);
+    let (ours, theirs) = tokio::net::UnixStream::pair().unwrap();
+    tokio::spawn(async move { daemon.serve_connection(theirs).await });
+    let mut client = DaemonClient::new(ours);
+
+    let mut proof = NounSlab::new();
+    proof.set_root(D(0));
+    assert!(matches!(
+        client.verify(&proof).await,
+        Err(DaemonError::Refused(reason)) if reason.contains("no verifier")
+    ));
+
+    // the simulated prover refuses a cause that is not a candidate
+    assert!(matches!(
+        client.prove(&proof, Entropy::Random).await,
+        Err(DaemonError::Refused(_))
+    ));
+
+    let garbage = DaemonRequest::Prove {
+        candidate: vec![0xff; 3],
+        entropy: None,
+    };
+    assert!(matches!(
+        client.request(&garbage).await.unwrap(),
+        DaemonResponse::Error(_)
+    ));
+
+    // the connection survives its failed requests
+    assert_eq!(client.status().await.unwrap().served, 3);
+}
@@ -0,0 +1,97 @@
+//! The miner kernel's `%verify`, `%status` and `%cancel` wires: the causes
+//! we poke and the effects we decode from its answers.
+
+use nockapp::noun::slab::NounSlab;
+use nockapp::noun::NounExt;
+use nockapp::wire::{Wire, WireTag};
+use nockchain::effect::{find_effect, CancelledEffect, MinerStatus, MiningEffectError};
+use nockchain::mining::{cancel_cause, status_cause, MiningWire};
+use nockchain::verifier::verify_cause;
+use nockvm::noun::{Noun, D, T};
+use nockvm_macros::tas;
+
+const COMMITMENT: [u64; 5] = [1, 2, 3, 4, 5];
+
+fn slab_of(build: impl FnOnce(&mut NounSlab) -> Noun) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let root = build(&mut slab);
+    slab.set_root(root);
+    slab
+}
+
+fn cancelled(slab: &mut NounSlab, commitment: &[u64; 5]) -> Noun {
+    let belts = commitment.map(D);
+    let digest = T(slab, &belts);
+    T(slab, &[D(tas!(b"cancelled")), digest])
+}
+
+#[test]
+fn test_miner_wires_are_tagged_with_their_verb() {
+    for (wire, verb) in [
+        (MiningWire::Candidate, "candidate"),
+        (MiningWire::Verify, "verify"),
+        (MiningWire::Status, "status"),
+        (MiningWire::Cancel, "cancel"),
+    ] {
+        let repr = wire.to_wire();
+        assert_eq!(repr.source, MiningWire::SOURCE);
+        assert_eq!(repr.tags, [WireTag::String(verb.to_string())]);
+    }
+}
+
+#[test]
+fn test_causes_are_tagged() {
+    let status = status_cause();
+    let status = unsafe { status.root() }.as_cell().unwrap();
+    assert!(status.head().eq_bytes("status"));
+
+    let cancel = cancel_cause(&COMMITMENT);
+    let cancel = unsafe { cancel.root() }.as_cell().unwrap();
+    assert!(cancel.head().eq_bytes("cancel"));
+
+    let proof = slab_of(|slab| T(slab, &[D(0), D(0), D(0), D(0)]));
+    let verify = verify_cause(&proof);
+    let verify = unsafe { verify.root() }.as_cell().unwrap();
+    assert!(verify.head().eq_bytes("verify"));
+    assert!(verify.tail().is_cell(), "the proof follows the tag");
+}
+
+#[test]
+fn test_decode_status_effect() {
+    let status = slab_of(|slab| T(slab, &[D(tas!(b"status")), D(3), D(2), D(1)]));
+    assert_eq!(
+        MinerStatus::try_from(&status),
+        Ok(MinerStatus {
+            proofs: 3,
+            verifies: 2,
+            cancelled: 1,
+        })
+    );
+
+    let truncated = slab_of(|slab| T(slab, &[D(tas!(b"status")), D(3)]));
+    assert_eq!(
+        MinerStatus::try_from(&truncated),
+        Err(MiningEffectError::Missing("proofs"))
+    );
+    let wrong_tag = slab_of(|slab| T(slab, &[D(tas!(b"stats")), D(3), D(2), D(1)]));
+    assert_eq!(
+        MinerStatus::try_from(&wrong_tag),
+        Err(MiningEffectError::WrongTag("status"))
+    );
+}
+
+#[test]
+fn test_find_cancelled_among_effects() {
+    let effects = slab_of(|slab| {
+        let log = T(slab, &[D(tas!(b"log")), D(1)]);
+        let cancelled = cancelled(slab, &COMMITMENT);
+        T(slab, &[log, cancelled, D(0)])
+    });
+    assert_eq!(
+        find_effect::<CancelledEffect>(&effects),
+        Some(CancelledEffect {
+            block_commitment: COMMITMENT,
+        })
+    );
+    assert_eq!(find_effect::<MinerStatus>(&effects), None);
+}
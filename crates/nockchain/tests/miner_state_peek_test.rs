@@ -0,0 +1,127 @@
+//! Decoding the kernel's `[%mining ~]` peek and the attempt counts and
+//! parameters [`MinerPeek`] adds to it.
+
+use std::time::Duration;
+
+use nockapp::noun::slab::NounSlab;
+use nockapp::noun::{AtomExt, NounExt};
+use nockchain::mining::MiningConfig;
+use nockchain::observer::{PokeComplete, PokeKind, PokeObserver, PokeStart};
+use nockchain::peek::{
+    decode_candidate, mining_path, AttemptStats, CandidateState, MinerPeek, PeekError,
+};
+use nockchain::stack::StackSize;
+use nockvm::noun::{Atom, Noun, D, T};
+
+const PARENT: &str = "9yPePjfWAdUnzaQKyxcRXKRa5PpUzKKEwtpECBZsUYt9Jd7egSDEWoV";
+const COMMITMENT: [u64; 5] = [1, 2, 3, 4, 5];
+const NONCE: [u64; 5] = [6, 7, 8, 9, 10];
+
+/// `[~ ~ state]`, or `[~ ~]` without a state
+fn scry_result(state: Option<fn(&mut NounSlab) -> Noun>) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let root = match state {
+        Some(build) => {
+            let state = build(&mut slab);
+            T(&mut slab, &[D(0), D(0), state])
+        }
+        None => T(&mut slab, &[D(0), D(0)]),
+    };
+    slab.set_root(root);
+    slab
+}
+
+fn candidate(slab: &mut NounSlab) -> Noun {
+    let parent = Atom::from_value(slab, PARENT).unwrap().as_noun();
+    let commitment = T(slab, &COMMITMENT.map(D));
+    let nonce = T(slab, &NONCE.map(D));
+    T(slab, &[D(0), D(42), parent, commitment, nonce, D(3), D(1)])
+}
+
+fn prove_start() -> PokeStart {
+    PokeStart {
+        kind: PokeKind::Prove,
+        length: 2,
+        slab_bytes: 0,
+    }
+}
+
+#[test]
+fn test_mining_path() {
+    let path = mining_path();
+    let path = unsafe { path.root() }.as_cell().unwrap();
+    assert!(path.head().eq_bytes("mining"));
+    assert_eq!(path.tail().as_atom().unwrap().as_u64().unwrap(), 0);
+}
+
+#[test]
+fn test_decode_candidate() {
+    assert_eq!(
+        decode_candidate(&scry_result(Some(candidate))).unwrap(),
+        Some(CandidateState {
+            mining: true,
+            height: 42,
+            parent: PARENT.to_string(),
+            block_commitment: COMMITMENT,
+            nonce: NONCE,
+            tx_count: 3,
+            locks: 1,
+        })
+    );
+    assert_eq!(decode_candidate(&scry_result(None)).unwrap(), None);
+}
+
+#[test]
+fn test_decode_candidate_errors() {
+    let mut bad_path = NounSlab::new();
+    bad_path.set_root(D(0));
+    assert!(matches!(
+        decode_candidate(&bad_path),
+        Err(PeekError::BadPath("mining"))
+    ));
+
+    let truncated = scry_result(Some(|slab| T(slab, &[D(0), D(42)])));
+    assert!(matches!(
+        decode_candidate(&truncated),
+        Err(PeekError::Malformed { peek: "mining", .. })
+    ));
+}
+
+#[test]
+fn test_attempts_counted_through_config_observers() {
+    let mut config = MiningConfig {
+        stack_size: StackSize::Words(1 << 20),
+        extra_nonce: 7,
+        ..Default::default()
+    };
+    let peek = MinerPeek::attach(&mut config);
+    assert_eq!(config.observers.len(), 1);
+    assert_eq!(peek.params().extra_nonce, 7);
+    assert_eq!(
+        peek.params().stack_size,
+        StackSize::Words(1 << 20).to_string()
+    );
+
+    let observers = &config.observers;
+    observers.on_start(&prove_start());
+    observers.on_start(&prove_start());
+    observers.on_start(&PokeStart {
+        kind: PokeKind::Verify,
+        ..prove_start()
+    });
+    let mut no_effects = NounSlab::new();
+    no_effects.set_root(D(0));
+    observers.finished(PokeKind::Prove, 2, Duration::ZERO, &no_effects);
+    observers.on_complete(&PokeComplete::failed(PokeKind::Verify, 2, Duration::ZERO));
+
+    let attempts = peek.attempts();
+    assert_eq!(
+        attempts,
+        AttemptStats {
+            started: 2,
+            succeeded: 1,
+            failed: 0,
+        }
+    );
+    assert_eq!(attempts.running(), 1);
+}
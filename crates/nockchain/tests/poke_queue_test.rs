@@ -0,0 +1,133 @@
+//! Turn order of [`PokeQueue`]: verify pokes first, mine pokes not starved.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use nockchain::poke::{PokePriority, PokeQueue, QueueDepth};
+use tokio::task::JoinHandle;
+
+/// Ask for a turn in the background, recording `name` when it comes
+fn waiter(
+    queue: &PokeQueue,
+    priority: PokePriority,
+    name: &'static str,
+    order: &Arc<Mutex<Vec<&'static str>>>,
+) -> JoinHandle<()> {
+    let queue = queue.clone();
+    let order = order.clone();
+    tokio::spawn(async move {
+        let _turn = queue.acquire(priority).await;
+        order.lock().unwrap().push(name);
+    })
+}
+
+/// Wait until `verify` and `mine` pokes are queued
+async fn queued(queue: &PokeQueue, verify: usize, mine: usize) {
+    while (queue.depth().verify, queue.depth().mine) != (verify, mine) {
+        tokio::task::yield_now().await;
+    }
+}
+
+async fn run_in_turn(
+    queue: &PokeQueue,
+    waiters: &[(PokePriority, &'static str)],
+) -> Vec<&'static str> {
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let held = queue.acquire(PokePriority::Mine).await;
+    let (mut verify, mut mine) = (0, 0);
+    let mut tasks = Vec::new();
+    for &(priority, name) in waiters {
+        match priority {
+            PokePriority::Verify => verify += 1,
+            PokePriority::Mine => mine += 1,
+        }
+        tasks.push(waiter(queue, priority, name, &order));
+        // queue them in the order given
+        queued(queue, verify, mine).await;
+    }
+    drop(held);
+    for task in tasks {
+        task.await.unwrap();
+    }
+    let order = order.lock().unwrap().clone();
+    order
+}
+
+#[tokio::test]
+async fn test_idle_queue_grants_immediately() {
+    let queue = PokeQueue::default();
+    let turn = queue.acquire(PokePriority::Verify).await;
+    assert_eq!(turn.priority(), PokePriority::Verify);
+    assert_eq!(
+        queue.depth(),
+        QueueDepth {
+            verify: 0,
+            mine: 0,
+            running: Some(PokePriority::Verify),
+        }
+    );
+    drop(turn);
+    assert_eq!(queue.depth(), QueueDepth::default());
+}
+
+#[tokio::test]
+async fn test_verify_goes_before_waiting_mine() {
+    let queue = PokeQueue::default();
+    let order = run_in_turn(
+        &queue,
+        &[
+            (PokePriority::Mine, "mine 1"),
+            (PokePriority::Verify, "verify 1"),
+            (PokePriority::Mine, "mine 2"),
+            (PokePriority::Verify, "verify 2"),
+        ],
+    )
+    .await;
+    assert_eq!(order, ["verify 1", "verify 2", "mine 1", "mine 2"]);
+    assert_eq!(queue.depth(), QueueDepth::default());
+}
+
+#[tokio::test]
+async fn test_mine_gets_a_turn_after_a_verify_burst() {
+    let queue = PokeQueue::new(2);
+    let order = run_in_turn(
+        &queue,
+        &[
+            (PokePriority::Mine, "mine"),
+            (PokePriority::Verify, "verify 1"),
+            (PokePriority::Verify, "verify 2"),
+            (PokePriority::Verify, "verify 3"),
+        ],
+    )
+    .await;
+    assert_eq!(order, ["verify 1", "verify 2", "mine", "verify 3"]);
+
+    let strict = PokeQueue::new(0);
+    let order = run_in_turn(
+        &strict,
+        &[
+            (PokePriority::Mine, "mine"),
+            (PokePriority::Verify, "verify"),
+        ],
+    )
+    .await;
+    assert_eq!(order, ["mine", "verify"]);
+}
+
+#[tokio::test]
+async fn test_waiter_that_gives_up_leaves_the_queue() {
+    let queue = PokeQueue::default();
+    let held = queue.acquire(PokePriority::Mine).await;
+    let gave_up = tokio::time::timeout(
+        Duration::from_millis(10),
+        queue.acquire(PokePriority::Verify),
+    )
+    .await;
+    assert!(gave_up.is_err());
+    assert_eq!(queue.depth().verify, 0);
+
+    drop(held);
+    assert_eq!(queue.depth(), QueueDepth::default());
+    let turn = queue.acquire(PokePriority::Mine).await;
+    assert!(turn.waited() < Duration::from_secs(1));
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use ibig::UBig;
+use nockapp::kernel::form::Entropy;
+use nockapp::noun::slab::NounSlab;
+use nockapp::CrownError;
+use nockchain::backend::{ProvingBackend, SimulatedBackend};
+use nockchain::mining::mined_commands;
+use nockchain::pool::{ShareError, ShareJob, ShareValidator};
+use nockchain::prove_input::ProveBlockInput;
+use nockchain::verifier::{
+    ProofCheck, VerificationService, Verifier, VerifierBackend, VerifierConfig, VerifyError,
+};
+
+/// Finds every proof valid with the same hash, or every proof invalid
+#[derive(Clone)]
+struct FixedVerifier(Option<u64>);
+
+impl VerifierBackend for FixedVerifier {
+    fn load(&self, _stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Verifier>, CrownError>> {
+        let verifier = self.clone();
+        Box::pin(async move { Ok(Box::new(verifier) as Box<dyn Verifier>) })
+    }
+}
+
+impl Verifier for FixedVerifier {
+    fn verify(
+        &self,
+        _proof: NounSlab,
+        _entropy: Entropy,
+    ) -> BoxFuture<'static, Result<ProofCheck, VerifyError>> {
+        let check = match self.0 {
+            Some(pow) => ProofCheck::valid(UBig::from(pow)),
+            None => ProofCheck::invalid(),
+        };
+        Box::pin(async move { Ok(check) })
+    }
+
+    fn cancel(&self) -> bool {
+        false
+    }
+}
+
+fn share_validator(verifier: FixedVerifier) -> ShareValidator {
+    ShareValidator::new(Arc::new(VerificationService::new(
+        Arc::new(verifier),
+        VerifierConfig::default(),
+    )))
+}
+
+fn input(nonce: u64) -> ProveBlockInput {
+    ProveBlockInput::builder()
+        .length(4)
+        .block_commitment(&[1, 2, 3, 4, 5])
+        .nonce(&[nonce, 0, 0, 0, 0])
+        .extra_nonce(7)
+        .build()
+        .unwrap()
+}
+
+/// A `%pow` effect for `input` claiming proof hash 0
+async fn share(input: &ProveBlockInput) -> NounSlab {
+    let prover = SimulatedBackend::new(Duration::ZERO)
+        .load(0)
+        .await
+        .expect("simulated load failed");
+    let effects = prover
+        .prove(input.to_noun_slab(), Entropy::default())
+        .await
+        .expect("simulated prove failed");
+    mined_commands(&effects).remove(0)
+}
+
+fn job(share_target: u64, network_target: u64) -> ShareJob {
+    ShareJob::new(
+        &input(0),
+        UBig::from(share_target),
+        UBig::from(network_target),
+    )
+}
+
+#[tokio::test]
+async fn test_shares_are_credited_once_per_puzzle() {
+    let validator = share_validator(FixedVerifier(Some(0)));
+    let accepted = validator
+        .accept(&job(100, 0), share(&input(3)).await)
+        .await
+        .expect("share rejected");
+    assert_eq!(accepted.pow, UBig::from(0u8));
+    assert_eq!(accepted.nonce, [3, 0, 0, 0, 0]);
+    assert!(accepted.block);
+
+    assert!(matches!(
+        validator.accept(&job(100, 0), share(&input(3)).await).await,
+        Err(ShareError::Duplicate)
+    ));
+    validator
+        .accept(&job(100, 0), share(&input(4)).await)
+        .await
+        .expect("share for another nonce rejected");
+    validator.clear();
+    validator
+        .accept(&job(100, 0), share(&input(3)).await)
+        .await
+        .expect("share rejected after clearing");
+}
+
+#[tokio::test]
+async fn test_shares_are_checked_against_the_verifier() {
+    // the verifier disagrees with the hash the share claims
+    let validator = share_validator(FixedVerifier(Some(5)));
+    assert!(matches!(
+        validator.accept(&job(100, 0), share(&input(1)).await).await,
+        Err(ShareError::PowMismatch { .. })
+    ));
+
+    let validator = share_validator(FixedVerifier(None));
+    assert!(matches!(
+        validator.accept(&job(100, 0), share(&input(1)).await).await,
+        Err(ShareError::Invalid)
+    ));
+
+    // a share mined with another worker's extra nonce is for someone else's job
+    let validator = share_validator(FixedVerifier(Some(0)));
+    let other = input(2).with_extra_nonce(8);
+    assert!(matches!(
+        validator.accept(&job(100, 0), share(&other).await).await,
+        Err(ShareError::WrongJob("block commitment"))
+    ));
+}
@@ -0,0 +1,172 @@
+use nockchain::proof_archive::{
+    CaptureOutcome, ProofArchive, ProofArchiveError, ProofDigest, ProofMetadata,
+};
+use zkvm_jetpack::form::address::to_hex;
+
+#[test]
+fn test_proof_archive_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = ProofArchive::open(dir.path()).unwrap();
+    let digest = [1, 2, 3, 4, 5];
+    let metadata = ProofMetadata {
+        length: 4,
+        nonce: [1, 1, 1, 1, 1],
+        ..Default::default()
+    };
+
+    assert!(archive.get(&digest).unwrap().is_none());
+    archive
+        .put(digest, b"jammed proof", metadata.clone())
+        .unwrap();
+
+    let proof = archive
+        .get(&digest)
+        .unwrap()
+        .expect("proof should be archived");
+    assert_eq!(proof.jam, b"jammed proof");
+    assert_eq!(proof.metadata, metadata);
+    assert_eq!(archive.digests().unwrap(), vec![digest]);
+}
+
+#[test]
+fn test_proof_archive_detects_corruption() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = ProofArchive::open(dir.path()).unwrap();
+    let digest = [9, 9, 9, 9, 9];
+    archive
+        .put(digest, b"jammed proof", ProofMetadata::default())
+        .unwrap();
+
+    // flip a byte inside the stored jam
+    let path = archive.blob_path(&digest).unwrap();
+    let mut bytes = std::fs::read(&path).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    std::fs::write(&path, bytes).unwrap();
+
+    assert!(matches!(
+        archive.get(&digest),
+        Err(ProofArchiveError::ChecksumMismatch(_))
+    ));
+}
+
+#[test]
+fn test_proof_archive_compression_is_transparent() {
+    let dir = tempfile::tempdir().unwrap();
+    // a repetitive jam, like real proofs, compresses well
+    let jam: Vec<u8> = (0..64 * 1024).map(|i| (i % 7) as u8).collect();
+
+    let raw = ProofArchive::open(dir.path())
+        .unwrap()
+        .with_compression_level(None);
+    raw.put([1; 5], &jam, ProofMetadata::default()).unwrap();
+
+    // the index is read once on open, so open the second handle after the put
+    let compressed = ProofArchive::open(dir.path())
+        .unwrap()
+        .with_compression_level(Some(19));
+    compressed
+        .put([2; 5], &jam, ProofMetadata::default())
+        .unwrap();
+
+    let size = |digest: &ProofDigest| {
+        let path = compressed.blob_path(digest).unwrap();
+        std::fs::metadata(path).unwrap().len()
+    };
+    assert!(size(&[2; 5]) * 10 < size(&[1; 5]));
+
+    // a fresh handle reads both kinds of blob from the index
+    let reopened = ProofArchive::open(dir.path()).unwrap();
+    for archive in [&compressed, &reopened] {
+        assert_eq!(archive.get(&[1; 5]).unwrap().unwrap().jam, jam);
+        assert_eq!(archive.get(&[2; 5]).unwrap().unwrap().jam, jam);
+    }
+}
+
+#[test]
+fn test_proof_archive_shards_blobs_by_digest() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = ProofArchive::open(dir.path()).unwrap();
+    let digest = [0xabcd_0000_0000_0000, 2, 3, 4, 5];
+    archive
+        .put(digest, b"jammed proof", ProofMetadata::default())
+        .unwrap();
+
+    let hex = to_hex(&digest);
+    assert_eq!(
+        archive.blob_path(&digest).unwrap(),
+        dir.path().join("ab/cd").join(format!("{hex}.jam.zst"))
+    );
+    assert!(dir.path().join("index").exists());
+
+    assert!(archive.remove(&digest).unwrap());
+    assert!(!archive.contains(&digest));
+    assert!(ProofArchive::open(dir.path())
+        .unwrap()
+        .digests()
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_migrate_benchmark_results() {
+    let results = tempfile::tempdir().unwrap();
+    let result = serde_json::json!({
+        "input": {"length": 2, "block_commitment": [1, 2, 3, 4, 5], "nonce": [6, 7, 8, 9, 10]},
+        "duration_secs": 1.5,
+        "proof_hash": "0123456789abcdef",
+        "proof_data": [1, 2, 3],
+        "timestamp": "20250101_000000",
+        "test_name": "minimal",
+    });
+    std::fs::write(
+        results.path().join("minimal_20250101_000000.json"),
+        result.to_string(),
+    )
+    .unwrap();
+    std::fs::write(results.path().join("notes.json"), "not a result").unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let archive = ProofArchive::open(dir.path()).unwrap();
+    let report = archive.migrate_benchmark_results(results.path()).unwrap();
+    assert_eq!((report.migrated, report.skipped), (1, 1));
+
+    let digest = zkvm_jetpack::form::math::tip5::hash_reader(&[1u8, 2, 3][..]).unwrap();
+    let proof = archive.get(&digest).unwrap().unwrap();
+    assert_eq!(proof.jam, [1, 2, 3]);
+    assert_eq!(proof.metadata.length, 2);
+    assert_eq!(proof.metadata.nonce, [6, 7, 8, 9, 10]);
+    assert_eq!(proof.metadata.prove_millis, 1500);
+}
+
+#[test]
+fn test_capture_deduplicates_identical_proofs() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = ProofArchive::open(dir.path()).unwrap();
+    let digest = [7, 7, 7, 7, 7];
+    let run = |captured_at| ProofMetadata {
+        length: 2,
+        captured_at,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        archive.capture(digest, b"jammed proof", run(1)).unwrap(),
+        CaptureOutcome::Stored
+    );
+    let blob = archive.blob_path(&digest).unwrap();
+    let written = std::fs::metadata(&blob).unwrap().modified().unwrap();
+    assert_eq!(
+        archive.capture(digest, b"jammed proof", run(2)).unwrap(),
+        CaptureOutcome::Deduplicated
+    );
+    assert_eq!(
+        std::fs::metadata(&blob).unwrap().modified().unwrap(),
+        written
+    );
+
+    // captures survive reopening, and the first capture stays the primary one
+    let reopened = ProofArchive::open(dir.path()).unwrap();
+    assert_eq!(reopened.captures(&digest), vec![run(1), run(2)]);
+    assert_eq!(reopened.get(&digest).unwrap().unwrap().metadata, run(1));
+}
@@ -1,4 +1,5 @@
-use kernels::miner::KERNEL;
+// the dev-proving feature swaps in the reduced-parameter kernel
+use nockchain::backend::MINER_KERNEL as KERNEL;
 use nockapp::kernel::checkpoint::JamPaths;
 use nockapp::kernel::form::Kernel;
 use nockapp::noun::slab::NounSlab;
@@ -10,6 +11,8 @@ use zkvm_jetpack::hot::produce_prover_hot_state;
 use std::fs;
 use std::path::Path;
 use serde::{Deserialize, Serialize};
+use nockchain::regression::current_git_branch;
+use nockchain::trend::{local_machine_id, Sample, SeriesKey, TimingStats, TrendStore};
 
 /// Wire type for mining operations
 pub enum MiningWire {
@@ -43,6 +46,36 @@ struct ProofBenchmarkResult {
     proof_data: Vec<u8>,  // Serialized proof for verification
     timestamp: String,
     test_name: String,
+    /// Timings over every run, when there was more than one
+    #[serde(default)]
+    stats: Option<TimingStats>,
+    /// Where the time of the (last) run went
+    #[serde(default)]
+    phases: PhaseTimings,
+}
+
+/// Time spent in each phase of one run, in seconds. `duration_secs` is
+/// kernel load plus poke.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PhaseTimings {
+    /// Booting the miner kernel, hot state included
+    kernel_load_secs: f64,
+    /// The prove poke itself, where jets do their work
+    poke_secs: f64,
+    /// Pulling the proof data out of the effects
+    extraction_secs: f64,
+    /// Jamming the effects, as they are sent to the node
+    serialization_secs: f64,
+}
+
+impl std::fmt::Display for PhaseTimings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "load {:.2}s, poke {:.2}s, extraction {:.3}s, serialization {:.3}s",
+            self.kernel_load_secs, self.poke_secs, self.extraction_secs, self.serialization_secs
+        )
+    }
 }
 
 impl ProveBlockInput {
@@ -115,22 +148,39 @@ async fn fast_prove_block_benchmark_with_proof(
         false,
     )
     .await?;
+    let kernel_load = start_time.elapsed();
 
     // Convert input to noun format
     let candidate_slab = input.to_noun_slab();
 
     // Execute prove-block-inner through the kernel
+    let poke_start = Instant::now();
     let effects_slab = kernel
         .poke(MiningWire::Candidate.to_wire(), candidate_slab)
         .await?;
+    let poke = poke_start.elapsed();
 
     let duration = start_time.elapsed();
 
     // Extract proof data from effects
+    let extraction_start = Instant::now();
     let proof_data = extract_proof_data(&effects_slab)?;
     let proof_hash = calculate_proof_hash(&proof_data);
+    let extraction = extraction_start.elapsed();
+
+    let serialization_start = Instant::now();
+    let _jam = effects_slab.jam();
+    let serialization = serialization_start.elapsed();
+
+    let phases = PhaseTimings {
+        kernel_load_secs: kernel_load.as_secs_f64(),
+        poke_secs: poke.as_secs_f64(),
+        extraction_secs: extraction.as_secs_f64(),
+        serialization_secs: serialization.as_secs_f64(),
+    };
 
     println!("✅ Completed in {:.2?}", duration);
+    println!("⏱️  Phases: {}", phases);
     println!("🔍 Proof hash: {}", proof_hash);
 
     let result = ProofBenchmarkResult {
@@ -140,11 +190,49 @@ async fn fast_prove_block_benchmark_with_proof(
         proof_data,
         timestamp: chrono::Utc::now().to_rfc3339(),
         test_name: test_name.to_string(),
+        stats: None,
+        phases,
     };
 
     Ok(result)
 }
 
+/// Runs per benchmark, from BENCH_RUNS (default 1)
+fn bench_runs() -> usize {
+    std::env::var("BENCH_RUNS")
+        .ok()
+        .and_then(|runs| runs.parse().ok())
+        .filter(|&runs| runs > 0)
+        .unwrap_or(1)
+}
+
+/// Run the benchmark `bench_runs()` times. The result is the last run's
+/// proof, with the median time as its duration and stats over every run.
+async fn repeated_prove_block_benchmark(
+    input: ProveBlockInput,
+    test_name: &str,
+) -> Result<ProofBenchmarkResult, Box<dyn std::error::Error>> {
+    let runs = bench_runs();
+    let mut durations = Vec::with_capacity(runs);
+    let mut result = None;
+    for run in 1..=runs {
+        if runs > 1 {
+            println!("🔁 Run {}/{}", run, runs);
+        }
+        let run_result = fast_prove_block_benchmark_with_proof(input.clone(), test_name).await?;
+        durations.push(run_result.duration_secs);
+        result = Some(run_result);
+    }
+    let mut result = result.expect("at least one run");
+    if runs > 1 {
+        let stats = TimingStats::new(&durations).expect("at least one run");
+        println!("📊 {}", stats);
+        result.duration_secs = stats.median;
+        result.stats = Some(stats);
+    }
+    Ok(result)
+}
+
 /// Legacy function for backward compatibility
 async fn fast_prove_block_benchmark(
     input: ProveBlockInput,
@@ -191,38 +279,25 @@ fn save_benchmark_result(result: &ProofBenchmarkResult, filename: &str) -> Resul
     Ok(())
 }
 
-/// Load and compare benchmark result
-fn load_and_compare_result(filename: &str, current_result: &ProofBenchmarkResult) -> Result<(), Box<dyn std::error::Error>> {
-    let filepath = Path::new("benchmark_results").join(filename);
-
-    if !filepath.exists() {
-        println!("📝 No previous result found at: {}", filepath.display());
-        return Ok(());
-    }
-
-    let json_data = fs::read_to_string(&filepath)?;
-    let previous_result: ProofBenchmarkResult = serde_json::from_str(&json_data)?;
-
-    println!("🔍 Comparing with previous result:");
-    println!("   Previous time: {:.2}s", previous_result.duration_secs);
-    println!("   Current time:  {:.2}s", current_result.duration_secs);
+/// Add the result to its time series and check it against the rolling baseline
+fn record_trend(result: &ProofBenchmarkResult) -> Result<(), Box<dyn std::error::Error>> {
+    let mut store = TrendStore::open("benchmark_results")?;
+    let key = SeriesKey::new(&result.test_name, result.input.length, local_machine_id());
+    let sample = Sample {
+        recorded_at: chrono::Utc::now().timestamp() as u64,
+        secs: result.duration_secs,
+        proof_hash: result.proof_hash.clone(),
+        git_branch: current_git_branch(),
+    };
+    let check = store.record(key, sample);
+    store.save()?;
 
-    let speedup = previous_result.duration_secs / current_result.duration_secs;
-    if speedup > 1.0 {
-        println!("🚀 SPEEDUP: {:.2}x faster!", speedup);
-    } else if speedup < 1.0 {
-        println!("🐌 SLOWDOWN: {:.2}x slower", 1.0 / speedup);
-    } else {
-        println!("⚖️  Same performance");
+    println!("📈 {}", check);
+    if check.is_regression() {
+        println!("🐌 REGRESSION: more than {:.0}% slower than the rolling baseline", store.config().threshold * 100.0);
     }
-
-    // Compare proof correctness
-    if previous_result.proof_hash == current_result.proof_hash {
-        println!("✅ PROOF MATCH: Results are identical!");
-    } else {
-        println!("⚠️  PROOF DIFFERENT: Results differ - check implementation!");
-        println!("   Previous hash: {}", previous_result.proof_hash);
-        println!("   Current hash:  {}", current_result.proof_hash);
+    if check.proof_match == Some(false) {
+        println!("⚠️  PROOF DIFFERENT: Results differ from the last run - check implementation!");
     }
 
     Ok(())
@@ -330,7 +405,7 @@ async fn test_minimal_prove_block() {
     println!("   Length: {}", input.length);
     println!("   This should complete in under 5 minutes");
 
-    match fast_prove_block_benchmark_with_proof(input, "minimal_test").await {
+    match repeated_prove_block_benchmark(input, "minimal_test").await {
         Ok(result) => {
             println!("");
             println!("🎉 MINIMAL TEST COMPLETED!");
@@ -357,9 +432,9 @@ async fn test_minimal_prove_block() {
                 eprintln!("⚠️  Failed to save result: {}", e);
             }
 
-            // Compare with previous result if exists
-            if let Err(e) = load_and_compare_result(filename, &result) {
-                eprintln!("⚠️  Failed to compare with previous result: {}", e);
+            // Compare with the rolling baseline of earlier runs
+            if let Err(e) = record_trend(&result) {
+                eprintln!("⚠️  Failed to record trend: {}", e);
             }
 
             println!("");
@@ -394,7 +469,7 @@ async fn test_length_4_prove_block() {
     println!("   Length: {}", input.length);
     println!("   Expected time: 5-20 minutes");
 
-    match fast_prove_block_benchmark_with_proof(input, "length_4_test").await {
+    match repeated_prove_block_benchmark(input, "length_4_test").await {
         Ok(result) => {
             println!("");
             println!("🎉 LENGTH=4 TEST COMPLETED!");
@@ -421,9 +496,9 @@ async fn test_length_4_prove_block() {
                 eprintln!("⚠️  Failed to save result: {}", e);
             }
 
-            // Compare with previous result if exists
-            if let Err(e) = load_and_compare_result(filename, &result) {
-                eprintln!("⚠️  Failed to compare with previous result: {}", e);
+            // Compare with the rolling baseline of earlier runs
+            if let Err(e) = record_trend(&result) {
+                eprintln!("⚠️  Failed to record trend: {}", e);
             }
 
             println!("");
@@ -456,7 +531,7 @@ async fn test_minimal_prove_block_with_verification() {
 
     println!("🚀 Running test with proof verification...");
 
-    match fast_prove_block_benchmark_with_proof(input, "verification_test").await {
+    match repeated_prove_block_benchmark(input, "verification_test").await {
         Ok(result) => {
             println!("✅ Test completed in {:.2}s", result.duration_secs);
 
@@ -473,9 +548,9 @@ async fn test_minimal_prove_block_with_verification() {
             if let Err(e) = save_benchmark_result(&result, latest_filename) {
                 eprintln!("⚠️  Failed to save latest result: {}", e);
             } else {
-                // Compare with previous latest
-                if let Err(e) = load_and_compare_result(latest_filename, &result) {
-                    eprintln!("⚠️  Failed to compare results: {}", e);
+                // Compare with the rolling baseline of earlier runs
+                if let Err(e) = record_trend(&result) {
+                    eprintln!("⚠️  Failed to record trend: {}", e);
                 }
             }
 
@@ -0,0 +1,273 @@
+use futures::future::BoxFuture;
+use nockapp::kernel::form::Entropy;
+use nockapp::noun::slab::{slab_equality, NounSlab};
+use nockapp::CrownError;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use nockchain::backend::{
+    KernelJam, KernelJamError, MockBackend, Prover, ProvingBackend, SimulatedBackend, MINER_KERNEL,
+};
+use nockchain::effect::{validate_effect_schema, ExpectedSchema, MiningEffect};
+use nockchain::mining::{mined_commands, proof_fingerprint, MiningConfig};
+use nockchain::prove_input::ProveBlockInput;
+use nockchain::watchdog::{Watchdog, WatchedBackend};
+use nockvm::noun::{D, T};
+use nockvm_macros::tas;
+
+/// `[[%command %pow 1] [%log 2] [%command %pow 3] ~]`
+fn canned_effects() -> NounSlab {
+    let mut slab = NounSlab::new();
+    let first = T(&mut slab, &[D(tas!(b"command")), D(tas!(b"pow")), D(1)]);
+    let log = T(&mut slab, &[D(tas!(b"log")), D(2)]);
+    let second = T(&mut slab, &[D(tas!(b"command")), D(tas!(b"pow")), D(3)]);
+    let effects = T(&mut slab, &[first, log, second, D(0)]);
+    slab.set_root(effects);
+    slab
+}
+
+fn candidate(nonce: u64) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let commitment = T(&mut slab, &[D(1), D(2), D(3), D(4), D(5)]);
+    let nonce = T(&mut slab, &[D(nonce), D(0), D(0), D(0), D(0)]);
+    let cause = T(&mut slab, &[D(2), commitment, nonce]);
+    slab.set_root(cause);
+    slab
+}
+
+/// Its kernels fail the first `failures` pokes, by erroring or by hanging
+/// until cancelled, then prove like [`SimulatedBackend`]
+#[derive(Clone)]
+struct FlakyBackend {
+    failures: Arc<AtomicUsize>,
+    hang: bool,
+    loads: Arc<AtomicUsize>,
+}
+
+impl FlakyBackend {
+    fn new(failures: usize, hang: bool) -> Self {
+        Self {
+            failures: Arc::new(AtomicUsize::new(failures)),
+            hang,
+            loads: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn loads(&self) -> usize {
+        self.loads.load(Ordering::SeqCst)
+    }
+}
+
+impl ProvingBackend for FlakyBackend {
+    fn load(&self, _stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Prover>, CrownError>> {
+        self.loads.fetch_add(1, Ordering::SeqCst);
+        let prover = self.clone();
+        Box::pin(async move { Ok(Box::new(prover) as Box<dyn Prover>) })
+    }
+}
+
+impl Prover for FlakyBackend {
+    fn prove(
+        &self,
+        candidate: NounSlab,
+        entropy: Entropy,
+    ) -> BoxFuture<'static, Result<NounSlab, CrownError>> {
+        let failed = self
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok();
+        let hang = self.hang;
+        Box::pin(async move {
+            match (failed, hang) {
+                (true, true) => std::future::pending().await,
+                (true, false) => Err(CrownError::WorkBail),
+                (false, _) => SimulatedBackend::default().prove(candidate, entropy).await,
+            }
+        })
+    }
+
+    fn cancel(&self) -> bool {
+        false
+    }
+}
+
+#[tokio::test]
+async fn test_mock_backend_returns_canned_effects() {
+    let backend = MockBackend::new(canned_effects());
+    let prover = backend.load(0).await.expect("mock load failed");
+
+    for nonce in 0..3 {
+        let effects = prover
+            .prove(candidate(nonce), Entropy::Fixed(42))
+            .await
+            .expect("mock prove failed");
+        assert!(slab_equality(&effects, &canned_effects()));
+    }
+    assert!(!prover.cancel());
+
+    let candidates = backend.candidates();
+    assert_eq!(candidates.len(), 3);
+    assert!(slab_equality(&candidates[2], &candidate(2)));
+}
+
+#[tokio::test]
+async fn test_mined_commands_filters_effects() {
+    let backend = MockBackend::new(canned_effects());
+    let prover = backend.load(0).await.expect("mock load failed");
+    let effects = prover
+        .prove(candidate(0), Entropy::default())
+        .await
+        .expect("mock prove failed");
+
+    let commands = mined_commands(&effects);
+    assert_eq!(commands.len(), 2);
+    for (command, expected) in commands.iter().zip([1, 3]) {
+        let cell = unsafe { command.root() }.as_cell().unwrap();
+        assert!(unsafe { cell.head().raw_equals(&D(tas!(b"command"))) });
+        let pow = cell.tail().as_cell().unwrap();
+        assert!(unsafe { pow.tail().raw_equals(&D(expected)) });
+    }
+}
+
+#[tokio::test]
+async fn test_simulated_backend_proves_the_candidate() {
+    let backend = SimulatedBackend::new(Duration::ZERO);
+    let prover = backend.load(0).await.expect("simulated load failed");
+    let input = ProveBlockInput::builder()
+        .length(4)
+        .block_commitment(&[1, 2, 3, 4, 5])
+        .nonce(&[6, 7, 8, 9, 10])
+        .extra_nonce(3)
+        .build()
+        .unwrap();
+
+    let effects = prover
+        .prove(input.to_noun_slab(), Entropy::default())
+        .await
+        .expect("simulated prove failed");
+    let commands = mined_commands(&effects);
+    assert_eq!(commands.len(), 1);
+    let command = commands.into_iter().next().unwrap();
+    validate_effect_schema(unsafe { *command.root() }, &ExpectedSchema::pow_effect())
+        .expect("simulated effect does not match the %pow schema");
+    let effect = MiningEffect::try_from(command).expect("simulated effect is malformed");
+    assert_eq!(effect.verify(&input), Ok(()));
+    assert_eq!(backend.proven(), 1);
+
+    let mut malformed = NounSlab::new();
+    malformed.set_root(D(0));
+    assert!(prover.prove(malformed, Entropy::default()).await.is_err());
+    assert_eq!(backend.proven(), 1);
+}
+
+#[tokio::test]
+async fn test_deterministic_mining_reproduces_proofs() {
+    let config = MiningConfig {
+        backend: std::sync::Arc::new(SimulatedBackend::new(Duration::ZERO)),
+        ..Default::default()
+    }
+    .deterministic(7);
+    assert!(config.deterministic);
+    assert!(matches!(config.entropy, Entropy::Fixed(7)));
+
+    let mut fingerprints = Vec::new();
+    for _ in 0..2 {
+        let prover = config.backend.load(0).await.expect("simulated load failed");
+        let effects = prover
+            .prove(candidate(0), config.entropy)
+            .await
+            .expect("simulated prove failed");
+        let commands = mined_commands(&effects);
+        assert_eq!(commands.len(), 1);
+        fingerprints.push(proof_fingerprint(&commands[0]));
+    }
+    assert_eq!(fingerprints[0], fingerprints[1]);
+
+    let prover = config.backend.load(0).await.expect("simulated load failed");
+    let other = prover
+        .prove(candidate(1), config.entropy)
+        .await
+        .expect("simulated prove failed");
+    assert_ne!(
+        proof_fingerprint(&mined_commands(&other)[0]),
+        fingerprints[0]
+    );
+}
+
+#[tokio::test]
+async fn test_watchdog_restarts_failed_kernels() {
+    let flaky = FlakyBackend::new(2, false);
+    let backend = WatchedBackend::new(Arc::new(flaky.clone()), Watchdog::default());
+    let prover = backend.load(0).await.expect("watched load failed");
+    let effects = prover
+        .prove(candidate(0), Entropy::default())
+        .await
+        .expect("watchdog did not recover the kernel");
+    assert_eq!(mined_commands(&effects).len(), 1);
+    assert_eq!(flaky.loads(), 3);
+
+    // a kernel that hangs past the stall timeout every time is given up on
+    let wedged = FlakyBackend::new(usize::MAX, true);
+    let backend = WatchedBackend::new(
+        Arc::new(wedged.clone()),
+        Watchdog {
+            stall_timeout: Some(Duration::from_millis(10)),
+            max_restarts: 2,
+        },
+    );
+    let prover = backend.load(0).await.expect("watched load failed");
+    assert!(prover
+        .prove(candidate(0), Entropy::default())
+        .await
+        .is_err());
+    assert_eq!(wedged.loads(), 3);
+}
+
+#[test]
+fn test_kernel_jam_is_validated_on_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let embedded = KernelJam::embedded();
+    assert_eq!(embedded.bytes(), MINER_KERNEL);
+    assert_eq!(embedded.path(), None);
+
+    let mut trap = NounSlab::new();
+    let root = T(&mut trap, &[D(1), D(2)]);
+    trap.set_root(root);
+    let kernel_path = dir.path().join("miner.jam");
+    std::fs::write(&kernel_path, trap.jam()).unwrap();
+    let loaded = KernelJam::load(&kernel_path).unwrap();
+    assert_eq!(loaded.bytes(), &trap.jam()[..]);
+    assert_eq!(loaded.path(), Some(kernel_path.as_path()));
+    assert_ne!(loaded.hash(), embedded.hash());
+
+    let mut atom = NounSlab::new();
+    atom.set_root(D(42));
+    let atom_path = dir.path().join("atom.jam");
+    std::fs::write(&atom_path, atom.jam()).unwrap();
+    assert!(matches!(
+        KernelJam::load(&atom_path),
+        Err(KernelJamError::NotAKernel { .. })
+    ));
+    let empty_path = dir.path().join("empty.jam");
+    std::fs::write(&empty_path, b"").unwrap();
+    assert!(matches!(
+        KernelJam::load(&empty_path),
+        Err(KernelJamError::NotAKernel { .. })
+    ));
+    let missing = dir.path().join("missing.jam");
+    assert!(matches!(
+        KernelJam::load(&missing),
+        Err(KernelJamError::Io { .. })
+    ));
+
+    // a bad path falls back to the kernel built in
+    assert_eq!(KernelJam::load_or_embedded(Some(&atom_path)).path(), None);
+    assert_eq!(KernelJam::load_or_embedded(Some(&missing)).path(), None);
+    assert_eq!(
+        KernelJam::load_or_embedded(Some(&kernel_path)).path(),
+        Some(kernel_path.as_path())
+    );
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use ibig::UBig;
+use nockapp::noun::slab::NounSlab;
+use nockchain::backend::MockBackend;
+use nockchain::regression::{CaseComparison, RegressionError, RegressionHarness, RegressionReport};
+use nockchain::trend::{Outcome, Tolerance};
+use nockvm::noun::{D, T};
+use tempfile::tempdir;
+
+fn effects(pow: u64) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let effect = T(&mut slab, &[D(1), D(2), D(pow)]);
+    let effects = T(&mut slab, &[effect, D(0)]);
+    slab.set_root(effects);
+    slab
+}
+
+fn candidates() -> Vec<NounSlab> {
+    (0..2)
+        .map(|nonce| {
+            let mut slab = NounSlab::new();
+            let cause = T(&mut slab, &[D(2), D(0), D(nonce)]);
+            slab.set_root(cause);
+            slab
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_record_and_compare() {
+    let dir = tempdir().unwrap();
+    let baseline = RegressionHarness::new(
+        dir.path(),
+        Arc::new(MockBackend::new(effects(7))),
+        candidates(),
+    );
+    let recorded = baseline.record("main").await.unwrap();
+    assert_eq!(recorded.cases.len(), 2);
+    assert_eq!(baseline.load("main").unwrap(), Some(recorded));
+
+    // mock proofs take no time to speak of, only the proofs are judged
+    let loose = Tolerance::percent(1e9);
+    let report = baseline.compare("main").await.unwrap();
+    assert!(report.proofs_match());
+    assert!(report.cases.iter().all(|case| case.diff_axes.is_empty()));
+    assert_eq!(report.outcome(loose), Outcome::Pass);
+
+    // a branch whose proofs differ in the last element of the effect
+    let branch = RegressionHarness::new(
+        dir.path(),
+        Arc::new(MockBackend::new(effects(8))),
+        candidates(),
+    );
+    let report = branch.compare("main").await.unwrap();
+    assert!(!report.proofs_match());
+    assert_eq!(report.outcome(loose), Outcome::Warn);
+    // [[1 2 pow] ~]: pow sits at axis 2 -> 5 -> 11
+    assert_eq!(report.cases[0].diff_axes, vec![UBig::from(11u8)]);
+}
+
+#[tokio::test]
+async fn test_compare_errors() {
+    let dir = tempdir().unwrap();
+    let harness = RegressionHarness::new(
+        dir.path(),
+        Arc::new(MockBackend::new(effects(1))),
+        candidates(),
+    );
+    assert!(matches!(
+        harness.compare("missing").await,
+        Err(RegressionError::MissingBaseline(_))
+    ));
+
+    harness.record("short").await.unwrap();
+    let longer = RegressionHarness::new(
+        dir.path(),
+        Arc::new(MockBackend::new(effects(1))),
+        [candidates(), candidates()].concat(),
+    );
+    assert!(matches!(
+        longer.compare("short").await,
+        Err(RegressionError::CaseCountMismatch {
+            baseline: 2,
+            current: 4
+        })
+    ));
+}
+
+#[test]
+fn test_report_outcome_takes_the_worst_case() {
+    let case = |baseline_secs, current_secs| CaseComparison {
+        baseline_duration: Duration::from_secs(baseline_secs),
+        current_duration: Duration::from_secs(current_secs),
+        proof_hash_match: true,
+        diff_axes: Vec::new(),
+    };
+    let report = |cases| RegressionReport {
+        baseline: "main".to_string(),
+        baseline_branch: None,
+        current_branch: None,
+        cases,
+    };
+    let tolerance = Tolerance::percent(5.0);
+    assert_eq!(report(vec![]).outcome(tolerance), Outcome::Pass);
+    assert_eq!(
+        report(vec![case(100, 90), case(100, 103)]).outcome(tolerance),
+        Outcome::Warn
+    );
+    // one case 10% slower fails the report, even with the total within 5%
+    assert_eq!(
+        report(vec![case(100, 90), case(100, 110)]).outcome(tolerance),
+        Outcome::Fail
+    );
+}
@@ -0,0 +1,156 @@
+use nockapp::noun::slab::NounSlab;
+use nockapp::wire::{SystemWire, Wire};
+use nockchain::block_store::{BlockStore, PruneMode};
+use nockchain::reorg::{heard_block_fact, ChainFollower, ReorgConfig, ReorgError};
+use nockvm::noun::D;
+
+mod common;
+
+use common::{heaviest, mine, regtest_kernel, Applied, MINER_A, MINER_B};
+
+fn page(block: u64) -> NounSlab {
+    let mut slab = NounSlab::new();
+    slab.set_root(D(block));
+    slab
+}
+
+fn digest(block: u64) -> [u64; 5] {
+    [block, 0, 0, 0, 0]
+}
+
+fn branch(blocks: impl IntoIterator<Item = u64>) -> Vec<([u64; 5], NounSlab)> {
+    blocks
+        .into_iter()
+        .map(|block| (digest(block), page(block)))
+        .collect()
+}
+
+async fn follower(
+    dir: &tempfile::TempDir,
+    config: ReorgConfig,
+    blocks: impl IntoIterator<Item = u64>,
+) -> ChainFollower<Applied> {
+    let store = BlockStore::open(dir.path().join("blocks"), PruneMode::Archive).unwrap();
+    let mut follower = ChainFollower::open(
+        Applied::default(),
+        store,
+        dir.path().join("snapshots"),
+        config,
+    )
+    .await
+    .unwrap();
+    for (digest, page) in branch(blocks) {
+        follower.extend(digest, page).await.unwrap();
+    }
+    follower
+}
+
+#[tokio::test]
+async fn test_switch_branch_rolls_back_and_replays() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = ReorgConfig {
+        checkpoint_interval: 4,
+        max_depth: 10,
+    };
+    let mut follower = follower(&dir, config, 0..10).await;
+    let mut events = follower.subscribe();
+
+    // a heavier branch leaving the chain after block 6
+    let event = follower
+        .switch_branch(digest(6), branch(100..104))
+        .await
+        .unwrap();
+    assert_eq!(event.depth, 3);
+    assert_eq!(event.fork_height, 6);
+    assert_eq!(event.old_tip, digest(9));
+    assert_eq!(event.new_tip, digest(103));
+    // restored from the snapshot after 4 blocks, replayed 4 to 6 and the branch
+    assert_eq!(event.replayed, 7);
+    assert_eq!(events.recv().await.unwrap(), event);
+
+    assert_eq!(
+        follower.state().blocks(),
+        [0, 1, 2, 3, 4, 5, 6, 100, 101, 102, 103]
+    );
+    assert_eq!(follower.tip(), Some((10, digest(103))));
+    assert_eq!(follower.store().height_of(&digest(9)), None);
+
+    // and again off the new branch, from a snapshot taken on it
+    follower.extend(digest(104), page(104)).await.unwrap();
+    let event = follower
+        .switch_branch(digest(102), branch([200]))
+        .await
+        .unwrap();
+    assert_eq!(event.depth, 2);
+    // the snapshot after 8 blocks, then 101, 102 and the branch
+    assert_eq!(event.replayed, 3);
+    assert_eq!(
+        follower.state().blocks(),
+        [0, 1, 2, 3, 4, 5, 6, 100, 101, 102, 200]
+    );
+}
+
+#[tokio::test]
+async fn test_refuses_reorgs_past_the_window() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = ReorgConfig {
+        checkpoint_interval: 2,
+        max_depth: 3,
+    };
+    let mut follower = follower(&dir, config, 0..12).await;
+
+    assert!(matches!(
+        follower.switch_branch(digest(7), branch([100])).await,
+        Err(ReorgError::TooDeep { depth: 4, max: 3 })
+    ));
+    assert!(matches!(
+        follower.switch_branch(digest(50), branch([100])).await,
+        Err(ReorgError::UnknownForkPoint(_))
+    ));
+    // a refused reorg leaves the chain alone
+    assert_eq!(follower.state().blocks(), (0..12).collect::<Vec<_>>());
+
+    follower
+        .switch_branch(digest(8), branch([100]))
+        .await
+        .unwrap();
+    assert_eq!(follower.state().blocks(), [0, 1, 2, 3, 4, 5, 6, 7, 8, 100]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_kernel_rolls_back_to_the_other_branch() {
+    let dirs: Vec<_> = (0..3).map(|_| tempfile::tempdir().unwrap()).collect();
+    let miner_a = regtest_kernel(&dirs[0], Some(MINER_A), true).await;
+    let genesis = mine(&miner_a).await;
+    let block_a = mine(&miner_a).await;
+    // a second miner builds its own block on the same genesis
+    let miner_b = regtest_kernel(&dirs[1], Some(MINER_B), false).await;
+    miner_b
+        .poke(SystemWire.to_wire(), heard_block_fact(genesis.1.clone()))
+        .await
+        .unwrap();
+    let block_b = mine(&miner_b).await;
+    assert_ne!(block_a.0, block_b.0);
+
+    let store = BlockStore::open(dirs[2].path().join("blocks"), PruneMode::Archive).unwrap();
+    let mut follower = ChainFollower::open(
+        regtest_kernel(&dirs[2], None, false).await,
+        store,
+        dirs[2].path().join("snapshots"),
+        ReorgConfig::default(),
+    )
+    .await
+    .unwrap();
+    follower.extend(genesis.0, genesis.1).await.unwrap();
+    follower.extend(block_a.0, block_a.1).await.unwrap();
+    assert_eq!(heaviest(follower.state()), block_a.0);
+
+    // block b is no heavier, only a rollback gets the kernel off block a
+    let event = follower
+        .switch_branch(genesis.0, vec![block_b.clone()])
+        .await
+        .unwrap();
+    assert_eq!(event.depth, 1);
+    assert_eq!(event.new_tip, block_b.0);
+    assert_eq!(heaviest(follower.state()), block_b.0);
+}
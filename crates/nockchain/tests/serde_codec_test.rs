@@ -0,0 +1,105 @@
+//! Proof and benchmark records must round-trip through every [`Codec`], and
+//! the binary codecs must actually be smaller than JSON for proof data.
+
+use nockchain::codec::Codec;
+use nockchain::effect::{ProofObject, Puzzle};
+use nockchain::proof_archive::{ArchivedProof, ProofMetadata};
+use serde::{Deserialize, Serialize};
+
+const CODECS: [Codec; 3] = [Codec::Json, Codec::Bincode, Codec::Cbor];
+
+/// Shaped like the records the prove-block benchmarks write out
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct BenchmarkRecord {
+    length: u64,
+    duration_secs: f64,
+    proof_hash: String,
+    objects: Vec<ProofObject>,
+}
+
+fn puzzle() -> Puzzle {
+    Puzzle {
+        block_commitment: [1, 2, 3, 4, 5],
+        nonce: [6, 7, 8, 9, 10],
+        length: 4,
+    }
+}
+
+fn objects() -> Vec<ProofObject> {
+    // words above 2^53 are where JSON numbers go wrong
+    let big = u64::MAX - 12345;
+    vec![
+        ProofObject::MRoot([big, 2, 3, 4, 5]),
+        ProofObject::Puzzle(puzzle()),
+        ProofObject::Codeword((0..512).map(|i| [big - i, i, i * 3]).collect()),
+        ProofObject::Terms((0..2048).map(|i| big - i * 7).collect()),
+        ProofObject::CompM {
+            root: [9; 5],
+            num: 2,
+        },
+        ProofObject::Evals(vec![]),
+        ProofObject::Heights(vec![64, 128]),
+        ProofObject::Poly(vec![big]),
+        ProofObject::Other {
+            tag: Some("m-path".into()),
+            jam: vec![0xde, 0xad, 0xbe, 0xef],
+        },
+    ]
+}
+
+#[test]
+fn test_proof_objects_roundtrip_every_codec() {
+    let record = BenchmarkRecord {
+        length: 4,
+        duration_secs: 12.5,
+        proof_hash: "abc123".into(),
+        objects: objects(),
+    };
+    for codec in CODECS {
+        let bytes = codec.encode(&record).unwrap();
+        let decoded: BenchmarkRecord = codec.decode(&bytes).unwrap();
+        assert_eq!(decoded, record, "{codec:?}");
+    }
+}
+
+#[test]
+fn test_archived_proof_roundtrip_every_codec() {
+    let proof = ArchivedProof {
+        digest: [1, 2, 3, 4, 5],
+        metadata: ProofMetadata {
+            length: 2,
+            block_commitment: [1; 5],
+            nonce: [2; 5],
+            captured_at: 1_700_000_000,
+            prove_millis: 4200,
+        },
+        jam: (0..=255).collect(),
+    };
+    for codec in CODECS {
+        let bytes = codec.encode(&proof).unwrap();
+        assert_eq!(codec.decode::<ArchivedProof>(&bytes).unwrap(), proof);
+    }
+}
+
+#[test]
+fn test_polynomials_are_hex_in_json_and_compact_in_binary() {
+    let terms = ProofObject::Terms(vec![u64::MAX - 1, 1]);
+    let json = String::from_utf8(Codec::Json.encode(&terms).unwrap()).unwrap();
+    assert_eq!(json, r#"{"Terms":"fffffffffffffffe0000000000000001"}"#);
+
+    let bad = r#"{"Terms":"fffffffffffffffe00"}"#;
+    assert!(Codec::Json.decode::<ProofObject>(bad.as_bytes()).is_err());
+
+    let record = objects();
+    let json = Codec::Json.encode(&record).unwrap().len();
+    for codec in [Codec::Bincode, Codec::Cbor] {
+        assert!(codec.encode(&record).unwrap().len() < json, "{codec:?}");
+    }
+}
+
+#[test]
+fn test_bincode_rejects_trailing_bytes() {
+    let mut bytes = Codec::Bincode.encode(&puzzle()).unwrap();
+    bytes.push(0);
+    assert!(Codec::Bincode.decode::<Puzzle>(&bytes).is_err());
+}
@@ -0,0 +1,197 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use ibig::UBig;
+use nockapp::kernel::form::Entropy;
+use nockapp::noun::slab::NounSlab;
+use nockapp::CrownError;
+use nockchain::backend::{ProvingBackend, SimulatedBackend};
+use nockchain::block_store::{BlockStore, PruneMode};
+use nockchain::effect::{MiningEffect, ProofView};
+use nockchain::mining::mined_commands;
+use nockchain::prove_input::ProveBlockInput;
+use nockchain::reorg::{ChainFollower, ReorgConfig};
+use nockchain::sync::{SyncBlock, SyncError, SyncPipeline};
+use nockchain::verifier::{
+    ProofCheck, VerificationService, Verifier, VerifierBackend, VerifierConfig, VerifyError,
+};
+use nockvm::noun::{D, T};
+
+mod common;
+
+use common::Applied;
+
+/// Finds a proof's hash to be its nonce, and proofs with nonces in the
+/// list invalid. Lower nonces take longer, so blocks finish out of order.
+#[derive(Clone, Default)]
+struct NonceVerifier(Vec<u64>);
+
+impl VerifierBackend for NonceVerifier {
+    fn load(&self, _stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Verifier>, CrownError>> {
+        let verifier = self.clone();
+        Box::pin(async move { Ok(Box::new(verifier) as Box<dyn Verifier>) })
+    }
+}
+
+impl Verifier for NonceVerifier {
+    fn verify(
+        &self,
+        proof: NounSlab,
+        _entropy: Entropy,
+    ) -> BoxFuture<'static, Result<ProofCheck, VerifyError>> {
+        let nonce = ProofView::new(&proof, unsafe { *proof.root() })
+            .and_then(|view| view.puzzle())
+            .expect("proof has a puzzle")
+            .nonce[0];
+        let check = if self.0.contains(&nonce) {
+            ProofCheck::invalid()
+        } else {
+            ProofCheck::valid(UBig::from(nonce))
+        };
+        Box::pin(async move {
+            tokio::time::sleep(Duration::from_millis(20u64.saturating_sub(nonce * 2))).await;
+            Ok(check)
+        })
+    }
+
+    fn cancel(&self) -> bool {
+        false
+    }
+}
+
+/// A proof whose puzzle has `nonce`
+async fn proof(nonce: u64) -> NounSlab {
+    let input = ProveBlockInput::builder()
+        .length(4)
+        .block_commitment(&[1, 2, 3, 4, 5])
+        .nonce(&[nonce, 0, 0, 0, 0])
+        .build()
+        .unwrap();
+    let prover = SimulatedBackend::new(Duration::ZERO)
+        .load(0)
+        .await
+        .expect("simulated load failed");
+    let effects = prover
+        .prove(input.to_noun_slab(), Entropy::default())
+        .await
+        .expect("simulated prove failed");
+    let effect = MiningEffect::try_from(mined_commands(&effects).remove(0)).unwrap();
+    effect.proof_slab()
+}
+
+/// Block `height`, proved with nonce `height` against `target`. The page is
+/// `[digest pow height]`, enough for the pipeline and [`Applied`].
+async fn block(height: u64, target: u64) -> SyncBlock {
+    let digest = [height, 0, 0, 0, 0];
+    let mut page = NounSlab::new();
+    page.copy_into(unsafe { *proof(height).await.root() });
+    let pow = unsafe { *page.root() };
+    let id = T(&mut page, &digest.map(D));
+    let pow = T(&mut page, &[D(0), pow]);
+    let root = T(&mut page, &[id, pow, D(height)]);
+    page.set_root(root);
+    SyncBlock {
+        height,
+        digest,
+        target: UBig::from(target),
+        page,
+    }
+}
+
+async fn blocks(heights: impl IntoIterator<Item = u64>, target: u64) -> Vec<SyncBlock> {
+    let mut blocks = Vec::new();
+    for height in heights {
+        blocks.push(block(height, target).await);
+    }
+    blocks
+}
+
+async fn follower(dir: &tempfile::TempDir) -> ChainFollower<Applied> {
+    let store = BlockStore::open(dir.path().join("blocks"), PruneMode::Archive).unwrap();
+    ChainFollower::open(
+        Applied::default(),
+        store,
+        dir.path().join("snapshots"),
+        ReorgConfig::default(),
+    )
+    .await
+    .unwrap()
+}
+
+fn pipeline(verifier: NonceVerifier) -> SyncPipeline {
+    let service = VerificationService::new(Arc::new(verifier), VerifierConfig::default());
+    SyncPipeline::new(Arc::new(service)).with_window(4)
+}
+
+#[tokio::test]
+async fn test_sync_applies_blocks_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut follower = follower(&dir).await;
+    let report = pipeline(NonceVerifier::default())
+        .run(
+            &mut follower,
+            futures::stream::iter(blocks(0..10, 100).await),
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.applied, 10);
+    assert_eq!(follower.state().blocks(), (0..10).collect::<Vec<_>>());
+    assert_eq!(follower.tip(), Some((9, [9, 0, 0, 0, 0])));
+}
+
+#[tokio::test]
+async fn test_sync_stops_at_the_first_bad_block() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut follower = follower(&dir).await;
+    // later blocks verify before block 5 but are never applied
+    let result = pipeline(NonceVerifier(vec![5]))
+        .run(
+            &mut follower,
+            futures::stream::iter(blocks(0..8, 100).await),
+        )
+        .await;
+    assert!(matches!(result, Err(SyncError::InvalidProof { height: 5 })));
+    assert_eq!(follower.state().blocks(), [0, 1, 2, 3, 4]);
+
+    // picking up from the tip, with block 7 missing its target
+    let mut rest = blocks(5..10, 100).await;
+    rest[2] = block(7, 6).await;
+    let result = pipeline(NonceVerifier::default())
+        .run(&mut follower, futures::stream::iter(rest))
+        .await;
+    assert!(matches!(
+        result,
+        Err(SyncError::AboveTarget { height: 7, .. })
+    ));
+    assert_eq!(follower.state().blocks(), [0, 1, 2, 3, 4, 5, 6]);
+
+    let result = pipeline(NonceVerifier::default())
+        .run(&mut follower, futures::stream::iter(blocks([8], 100).await))
+        .await;
+    assert!(matches!(
+        result,
+        Err(SyncError::OutOfOrder {
+            expected: 7,
+            got: 8
+        })
+    ));
+}
+
+#[tokio::test]
+async fn test_sync_needs_a_proof_in_the_page() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut follower = follower(&dir).await;
+    let mut blocks = blocks(0..3, 100).await;
+    // a page whose pow is ~
+    let mut page = NounSlab::new();
+    let id = T(&mut page, &[1, 0, 0, 0, 0].map(D));
+    let root = T(&mut page, &[id, D(0), D(1)]);
+    page.set_root(root);
+    blocks[1].page = page;
+    let result = pipeline(NonceVerifier::default())
+        .run(&mut follower, futures::stream::iter(blocks))
+        .await;
+    assert!(matches!(result, Err(SyncError::NoProof { height: 1 })));
+    assert_eq!(follower.state().blocks(), [0]);
+}
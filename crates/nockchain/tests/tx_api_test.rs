@@ -0,0 +1,161 @@
+//! The transaction api in front of a dumbnet kernel booted on regtest.
+
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+use kernels::dumb::KERNEL;
+use nockapp::kernel::checkpoint::JamPaths;
+use nockapp::kernel::form::Kernel;
+use nockapp::noun::slab::NounSlab;
+use nockapp::utils::scry::ScryResult;
+use nockapp::wire::{SystemWire, Wire};
+use nockapp::{AtomExt, NockApp};
+use nockchain::events::EventBus;
+use nockchain::mining::MiningConfig;
+use nockchain::network::NetworkMode;
+use nockchain::peek::MinerPeek;
+use nockchain::tx_api::tx_api_driver;
+use nockvm::noun::{Atom, D, T};
+use nockvm_macros::tas;
+use serde_json::Value;
+use tempfile::TempDir;
+use zkvm_jetpack::form::address;
+use zkvm_jetpack::hot::produce_node_hot_state;
+
+const TX_ID: [u64; 5] = [1, 2, 3, 4, 5];
+
+fn command(tag: u64, arg: u64) -> NounSlab {
+    let mut slab = NounSlab::new();
+    let poke = T(&mut slab, &[D(tas!(b"command")), D(tag), D(arg)]);
+    slab.set_root(poke);
+    slab
+}
+
+/// A dumbnet kernel on regtest, past its init phase, with no blocks yet
+async fn regtest_node(dir: &TempDir) -> NockApp {
+    let kernel = Kernel::load_with_hot_state(
+        dir.path().to_path_buf(),
+        JamPaths::new(dir.path()),
+        KERNEL,
+        &produce_node_hot_state(),
+        false,
+    )
+    .await
+    .expect("Could not load dumbnet kernel");
+    let mut nockapp = NockApp::new(kernel, Duration::from_secs(1)).await;
+    let set_constants = NetworkMode::Regtest
+        .set_constants_poke()
+        .expect("regtest has its own constants");
+    for poke in [
+        set_constants,
+        command(tas!(b"btc-data"), 0),
+        command(tas!(b"born"), 0),
+    ] {
+        nockapp
+            .poke(SystemWire.to_wire(), poke)
+            .await
+            .expect("Boot poke failed");
+    }
+    nockapp
+}
+
+fn free_addr() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap()
+}
+
+/// `[id tx]` with a transaction the kernel will not recognize
+fn raw_tx() -> NounSlab {
+    let mut slab = NounSlab::new();
+    let id = T(&mut slab, &TX_ID.map(D));
+    let raw = T(&mut slab, &[id, D(0)]);
+    slab.set_root(raw);
+    slab
+}
+
+#[tokio::test]
+async fn test_kernel_answers_transaction_height() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut nockapp = regtest_node(&dir).await;
+
+    let mut path = NounSlab::new();
+    let tag = Atom::from_value(&mut path, "transaction-height")
+        .unwrap()
+        .as_noun();
+    let id = Atom::from_value(&mut path, address::to_kernel_base58(&TX_ID))
+        .unwrap()
+        .as_noun();
+    let root = T(&mut path, &[tag, id, D(0)]);
+    path.set_root(root);
+
+    let res = nockapp.peek(path).await.expect("Peek failed");
+    // a known path with nothing at it, not an unknown path
+    assert!(matches!(
+        ScryResult::from(unsafe { res.root() }),
+        ScryResult::Nothing
+    ));
+}
+
+#[tokio::test]
+async fn test_submit_and_query_status() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut nockapp = regtest_node(&dir).await;
+    let bind = free_addr();
+    let miner = MinerPeek::attach(&mut MiningConfig::default());
+    nockapp
+        .add_io_driver(tx_api_driver(bind, miner, EventBus::default()))
+        .await;
+    tokio::spawn(async move { nockapp.run().await });
+
+    let client = reqwest::Client::new();
+    let submit = async {
+        loop {
+            let sent = client
+                .post(format!("http://{bind}/sendrawtransaction"))
+                .body(raw_tx().jam().to_vec())
+                .send()
+                .await;
+            match sent {
+                Ok(res) => return res.text().await.unwrap(),
+                // the driver may not be listening yet
+                Err(_) => tokio::time::sleep(Duration::from_millis(50)).await,
+            }
+        }
+    };
+    let submitted: Value = serde_json::from_str(
+        &tokio::time::timeout(Duration::from_secs(30), submit)
+            .await
+            .expect("Transaction api never came up"),
+    )
+    .unwrap();
+    let tx_id = address::encode(&TX_ID);
+    // the kernel takes pokes on the tx-api wire instead of crashing on it
+    assert_eq!(submitted["status"], "accepted", "{submitted}");
+    assert_eq!(submitted["tx_id"], tx_id.as_str());
+
+    let status = client
+        .get(format!("http://{bind}/gettransactionstatus/{tx_id}"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let status: Value = serde_json::from_str(&status).unwrap();
+    assert_eq!(status["tx_id"], tx_id.as_str());
+    assert_eq!(status["status"], "accepted", "{status}");
+
+    // the kernel's base58 id the api took before addresses still works
+    let legacy = address::to_kernel_base58(&TX_ID);
+    let status = client
+        .get(format!("http://{bind}/gettransactionstatus/{legacy}"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let status: Value = serde_json::from_str(&status).unwrap();
+    assert_eq!(status["tx_id"], legacy.as_str());
+    assert_eq!(status["status"], "accepted", "{status}");
+}
@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+use ibig::UBig;
+use nockchain::pool::PoolMessage;
+use nockchain::vardiff::{Vardiff, VardiffConfig};
+
+fn vardiff() -> Vardiff {
+    let mut config = VardiffConfig::new(UBig::from(1_000_000u32), UBig::from(1_000u32));
+    config.shares_per_minute = 6.0;
+    config.retarget_interval = Duration::from_secs(60);
+    Vardiff::new(config)
+}
+
+fn secs(start: Instant, secs: u64) -> Instant {
+    start + Duration::from_secs(secs)
+}
+
+#[test]
+fn test_fast_worker_gets_a_harder_target() {
+    let vardiff = vardiff();
+    let start = Instant::now();
+    assert_eq!(vardiff.register("fast", start), UBig::from(1_000_000u32));
+
+    // twelve shares a minute, twice the desired rate
+    for share in 1..12 {
+        assert_eq!(vardiff.record_share("fast", secs(start, share * 5)), None);
+    }
+    let target = vardiff
+        .record_share("fast", secs(start, 60))
+        .expect("fast worker was not retargeted");
+    assert_eq!(target, UBig::from(500_000u32));
+    assert_eq!(vardiff.target("fast"), Some(target));
+}
+
+#[test]
+fn test_retargets_are_bounded() {
+    let vardiff = vardiff();
+    let start = Instant::now();
+    vardiff.register("idle", start);
+    vardiff.register("steady", start);
+    vardiff.register("flood", start);
+    for _ in 0..6 {
+        vardiff.record_share("steady", secs(start, 30));
+    }
+    for _ in 0..1_000 {
+        vardiff.record_share("flood", secs(start, 30));
+    }
+
+    // the idle worker gets at most max_step easier, the flooding one at most max_step harder
+    let moved = vardiff.tick(secs(start, 60));
+    assert_eq!(
+        moved,
+        [
+            ("flood".to_string(), UBig::from(250_000u32)),
+            ("idle".to_string(), UBig::from(4_000_000u32)),
+        ]
+    );
+    assert_eq!(vardiff.target("steady"), Some(UBig::from(1_000_000u32)));
+
+    // and never past the network target
+    for minute in 2..10 {
+        for _ in 0..1_000 {
+            vardiff.record_share("flood", secs(start, minute * 60 - 30));
+        }
+        vardiff.tick(secs(start, minute * 60));
+    }
+    assert_eq!(vardiff.target("flood"), Some(UBig::from(1_000u32)));
+
+    vardiff.remove("flood");
+    assert_eq!(vardiff.target("flood"), None);
+    assert_eq!(vardiff.record_share("flood", secs(start, 600)), None);
+}
+
+#[test]
+fn test_set_target_message_is_hex() {
+    let message = PoolMessage::SetTarget {
+        target: UBig::from(0xabcdefu32),
+    };
+    let json = serde_json::to_string(&message).unwrap();
+    assert_eq!(json, r#"{"type":"set_target","target":"abcdef"}"#);
+    assert_eq!(serde_json::from_str::<PoolMessage>(&json).unwrap(), message);
+}
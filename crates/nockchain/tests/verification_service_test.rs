@@ -0,0 +1,353 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use ibig::UBig;
+use nockapp::kernel::form::Entropy;
+use nockapp::noun::slab::NounSlab;
+use nockapp::CrownError;
+use nockchain::effect::MiningEffectError;
+use nockchain::observer::{PokeComplete, PokeKind, PokeObserver, PokeObservers, PokeStart};
+use nockchain::verifier::{
+    serve_stream, ProofCheck, Resource, VerificationService, Verifier, VerifierBackend,
+    VerifierConfig, VerifyError,
+};
+use nockvm::interpreter::{Error, Mote};
+use nockvm::noun::{D, T};
+use nockvm_macros::tas;
+use tokio::sync::{Notify, Semaphore};
+
+/// Verifies every proof as valid with hash 42, but only once the test releases it.
+/// Cancelling makes every pending verify report the proof invalid.
+#[derive(Clone)]
+struct GatedBackend {
+    gate: Arc<Semaphore>,
+    cancel: Arc<Notify>,
+    cancels: Arc<AtomicUsize>,
+}
+
+impl GatedBackend {
+    fn new() -> Self {
+        Self {
+            gate: Arc::new(Semaphore::new(0)),
+            cancel: Arc::new(Notify::new()),
+            cancels: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn cancels(&self) -> usize {
+        self.cancels.load(Ordering::SeqCst)
+    }
+
+    fn release(&self, verifies: usize) {
+        self.gate.add_permits(verifies);
+    }
+}
+
+impl VerifierBackend for GatedBackend {
+    fn load(&self, _stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Verifier>, CrownError>> {
+        let verifier = self.clone();
+        Box::pin(async move { Ok(Box::new(verifier) as Box<dyn Verifier>) })
+    }
+}
+
+impl Verifier for GatedBackend {
+    fn verify(
+        &self,
+        _proof: NounSlab,
+        _entropy: Entropy,
+    ) -> BoxFuture<'static, Result<ProofCheck, VerifyError>> {
+        let gate = self.gate.clone();
+        let cancel = self.cancel.clone();
+        Box::pin(async move {
+            tokio::select! {
+                permit = gate.acquire() => {
+                    permit.expect("gate closed").forget();
+                    Ok(ProofCheck::valid(UBig::from(42u8)))
+                }
+                _ = cancel.notified() => Ok(ProofCheck::invalid()),
+            }
+        })
+    }
+
+    fn cancel(&self) -> bool {
+        self.cancels.fetch_add(1, Ordering::SeqCst);
+        self.cancel.notify_waiters();
+        true
+    }
+}
+
+/// Runs out of NockStack on every proof
+struct ExhaustedBackend;
+
+impl VerifierBackend for ExhaustedBackend {
+    fn load(&self, _stack_words: usize) -> BoxFuture<'_, Result<Box<dyn Verifier>, CrownError>> {
+        Box::pin(async move { Ok(Box::new(ExhaustedBackend) as Box<dyn Verifier>) })
+    }
+}
+
+impl Verifier for ExhaustedBackend {
+    fn verify(
+        &self,
+        _proof: NounSlab,
+        _entropy: Entropy,
+    ) -> BoxFuture<'static, Result<ProofCheck, VerifyError>> {
+        let oom = CrownError::from(Error::Deterministic(Mote::Meme, D(0)));
+        Box::pin(async move { Err(VerifyError::Kernel(oom)) })
+    }
+
+    fn cancel(&self) -> bool {
+        false
+    }
+}
+
+/// `[0 [[%puzzle commitment nonce 4 0] ~] ~ 0]`
+fn proof() -> NounSlab {
+    let mut slab = NounSlab::new();
+    let commitment = T(&mut slab, &[D(1), D(2), D(3), D(4), D(5)]);
+    let nonce = T(&mut slab, &[D(6), D(7), D(8), D(9), D(10)]);
+    let puzzle = T(
+        &mut slab,
+        &[D(tas!(b"puzzle")), commitment, nonce, D(4), D(0)],
+    );
+    let objects = T(&mut slab, &[puzzle, D(0)]);
+    let proof = T(&mut slab, &[D(0), objects, D(0), D(0)]);
+    slab.set_root(proof);
+    slab
+}
+
+fn service(
+    backend: &GatedBackend,
+    max_concurrent: usize,
+    max_queued: usize,
+) -> Arc<VerificationService> {
+    Arc::new(VerificationService::new(
+        Arc::new(backend.clone()),
+        VerifierConfig {
+            max_concurrent,
+            max_queued,
+            ..Default::default()
+        },
+    ))
+}
+
+async fn wait_for(mut done: impl FnMut() -> bool) {
+    while !done() {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_verification_service_refuses_beyond_queue_limit() {
+    let backend = GatedBackend::new();
+    let service = service(&backend, 1, 1);
+
+    let running = tokio::spawn({
+        let service = service.clone();
+        async move { service.verify(proof(), None).await }
+    });
+    wait_for(|| service.in_flight() == 1).await;
+    let waiting = tokio::spawn({
+        let service = service.clone();
+        async move { service.verify(proof(), None).await }
+    });
+    wait_for(|| service.queued() == 1).await;
+
+    assert!(matches!(
+        service.verify(proof(), None).await,
+        Err(VerifyError::Overloaded {
+            queued: 1,
+            limit: 1
+        })
+    ));
+
+    backend.release(2);
+    for handle in [running, waiting] {
+        let verdict = handle.await.unwrap().expect("verify failed");
+        assert!(verdict.valid);
+        assert_eq!(verdict.pow, Some(UBig::from(42u8)));
+        assert_eq!(verdict.puzzle.length, 4);
+    }
+    assert_eq!(service.in_flight(), 0);
+    assert_eq!(service.queued(), 0);
+}
+
+#[tokio::test]
+async fn test_verification_service_applies_deadlines() {
+    let backend = GatedBackend::new();
+    let service = service(&backend, 1, 4);
+
+    // never released, so runs into its deadline while verifying and is cancelled
+    assert!(matches!(
+        service
+            .verify(proof(), Some(Duration::from_millis(20)))
+            .await,
+        Err(VerifyError::Timeout { after }) if after >= Duration::from_millis(20)
+    ));
+    assert_eq!(backend.cancels(), 1);
+    assert_eq!(service.in_flight(), 0);
+
+    // runs into its deadline while queued behind a running request, before
+    // it has a kernel to cancel
+    let running = tokio::spawn({
+        let service = service.clone();
+        async move { service.verify(proof(), None).await }
+    });
+    wait_for(|| service.in_flight() == 1).await;
+    assert!(matches!(
+        service
+            .verify(proof(), Some(Duration::from_millis(20)))
+            .await,
+        Err(VerifyError::Timeout { .. })
+    ));
+    assert_eq!(backend.cancels(), 1);
+    assert_eq!(service.queued(), 0);
+
+    backend.release(1);
+    assert!(running.await.unwrap().expect("verify failed").valid);
+}
+
+#[tokio::test]
+async fn test_verification_service_rejects_malformed_proofs() {
+    let backend = GatedBackend::new();
+    let service = service(&backend, 1, 1);
+
+    let mut slab = NounSlab::new();
+    let proof = T(&mut slab, &[D(0), D(0), D(0), D(0)]);
+    slab.set_root(proof);
+    assert!(matches!(
+        service.verify(slab, None).await,
+        Err(VerifyError::Proof(MiningEffectError::MissingPuzzle))
+    ));
+    assert_eq!(service.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn test_verification_service_enforces_memory_budgets() {
+    let service = VerificationService::new(
+        Arc::new(ExhaustedBackend),
+        VerifierConfig {
+            max_stack_words: Some(1 << 20),
+            ..Default::default()
+        },
+    );
+    assert_eq!(service.stack_words(4), 1 << 20);
+    assert!(matches!(
+        service.verify(proof(), None).await,
+        Err(VerifyError::ResourceExhausted(Resource::NockStack { words })) if words == 1 << 20
+    ));
+    assert_eq!(service.in_flight(), 0);
+
+    let proof = proof();
+    let limit = proof.allocated_bytes() - 1;
+    let service = VerificationService::new(
+        Arc::new(ExhaustedBackend),
+        VerifierConfig {
+            max_proof_bytes: limit,
+            ..Default::default()
+        },
+    );
+    assert!(matches!(
+        service.verify(proof, None).await,
+        Err(VerifyError::ResourceExhausted(Resource::ProofSize { limit: l, .. })) if l == limit
+    ));
+}
+
+/// Remembers every poke it hears about
+#[derive(Default)]
+struct RecordingObserver {
+    starts: Mutex<Vec<PokeStart>>,
+    completes: Mutex<Vec<PokeComplete>>,
+}
+
+impl PokeObserver for RecordingObserver {
+    fn on_start(&self, start: &PokeStart) {
+        self.starts.lock().unwrap().push(start.clone());
+    }
+
+    fn on_complete(&self, complete: &PokeComplete) {
+        self.completes.lock().unwrap().push(complete.clone());
+    }
+}
+
+#[tokio::test]
+async fn test_verification_service_reports_pokes_to_observers() {
+    let backend = GatedBackend::new();
+    let observer = Arc::new(RecordingObserver::default());
+    let service = VerificationService::new(
+        Arc::new(backend.clone()),
+        VerifierConfig {
+            observers: PokeObservers::new().with(observer.clone()),
+            ..Default::default()
+        },
+    );
+
+    backend.release(1);
+    assert!(service.verify(proof(), None).await.unwrap().valid);
+    assert!(matches!(
+        service
+            .verify(proof(), Some(Duration::from_millis(20)))
+            .await,
+        Err(VerifyError::Timeout { .. })
+    ));
+
+    let starts = observer.starts.lock().unwrap();
+    assert_eq!(starts.len(), 2);
+    assert!(starts.iter().all(|start| start.kind == PokeKind::Verify
+        && start.length == 4
+        && start.slab_bytes == proof().allocated_bytes()));
+    let completes = observer.completes.lock().unwrap();
+    assert_eq!(
+        completes.iter().map(|c| c.ok).collect::<Vec<_>>(),
+        [true, false]
+    );
+    assert!(completes.iter().all(|c| c.effects == 0));
+}
+
+fn frame(jam: &[u8]) -> Vec<u8> {
+    let mut frame = (jam.len() as u64).to_le_bytes().to_vec();
+    frame.extend_from_slice(jam);
+    frame
+}
+
+#[tokio::test]
+async fn test_serve_stream_writes_a_line_per_proof() {
+    let backend = GatedBackend::new();
+    backend.release(2);
+    let proof = proof().jam();
+    let service = VerificationService::new(
+        Arc::new(backend.clone()),
+        VerifierConfig {
+            max_concurrent: 1,
+            max_queued: 0,
+            max_proof_bytes: proof.len(),
+            ..Default::default()
+        },
+    );
+
+    let mut input = frame(&proof);
+    input.extend(frame(&vec![0; proof.len() + 1]));
+    input.extend(frame(&proof));
+    let mut output = Vec::new();
+    let proofs = serve_stream(&service, &input[..], &mut output, None)
+        .await
+        .expect("stream failed");
+    assert_eq!(proofs, 3);
+
+    let mut lines: Vec<serde_json::Value> = output
+        .split(|&byte| byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).expect("verdict is not JSON"))
+        .collect();
+    lines.sort_by_key(|line| line["seq"].as_u64());
+    assert_eq!(lines.len(), 3);
+    for seq in [0, 2] {
+        assert_eq!(lines[seq]["valid"], true);
+        assert_eq!(lines[seq]["puzzle"]["length"], 4);
+        assert_eq!(lines[seq]["pow"], "2a");
+        assert!(lines[seq].get("error").is_none());
+    }
+    assert_eq!(lines[1]["valid"], false);
+    assert!(lines[1]["error"].as_str().unwrap().contains("limit"));
+}
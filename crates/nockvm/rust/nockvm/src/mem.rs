@@ -737,7 +737,7 @@ impl NockStack {
     }
 
     /** Size **in 64-bit words** of this NockStack */
-    pub(crate) fn size(&self) -> usize {
+    pub fn size(&self) -> usize {
         self.size
     }
 
@@ -746,6 +746,11 @@ impl NockStack {
         self.least_space
     }
 
+    /** Reset the low-water-mark for space to the space currently free */
+    pub fn reset_least_space(&mut self) {
+        self.least_space = self.alloc_offset.abs_diff(self.stack_offset);
+    }
+
     /** Check to see if an allocation is in frame */
     #[inline]
     pub(crate) unsafe fn is_in_frame<T>(&self, ptr: *const T) -> bool {
@@ -0,0 +1,47 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use zkvm_jetpack::form::math::base::{bmul, bpow};
+use zkvm_jetpack::form::math::fext::{fmul_, fpow_};
+use zkvm_jetpack::form::{Belt, Felt};
+
+/// Right to left square-and-multiply, the baseline the windows are measured against
+fn square_and_multiply<T: Copy>(mut a: T, mut b: u64, one: T, mul: impl Fn(T, T) -> T) -> T {
+    let mut c = one;
+    while b > 0 {
+        if b & 1 == 1 {
+            c = mul(c, a);
+        }
+        a = mul(a, a);
+        b >>= 1;
+    }
+    c
+}
+
+fn bench_pow(c: &mut Criterion) {
+    let x = 0x1234_5678_9abc_def0u64;
+    let felt = Felt([Belt(x), Belt(x >> 7), Belt(x >> 13)]);
+    let mut group = c.benchmark_group("pow");
+    for bits in [8u32, 16, 32, 64] {
+        // alternating bits, so neither method gets an easy exponent
+        let exp = 0xa5a5_a5a5_a5a5_a5a5u64 >> (64 - bits);
+        group.bench_with_input(BenchmarkId::new("bpow", bits), &exp, |b, &exp| {
+            b.iter(|| bpow(black_box(x), black_box(exp)))
+        });
+        group.bench_with_input(BenchmarkId::new("bpow-naive", bits), &exp, |b, &exp| {
+            b.iter(|| square_and_multiply(black_box(x), black_box(exp), 1, bmul))
+        });
+        group.bench_with_input(BenchmarkId::new("fpow", bits), &exp, |b, &exp| {
+            b.iter(|| fpow_(black_box(&felt), black_box(exp)))
+        });
+        group.bench_with_input(BenchmarkId::new("fpow-naive", bits), &exp, |b, &exp| {
+            b.iter(|| {
+                square_and_multiply(black_box(felt), black_box(exp), Felt::one(), |a, b| {
+                    fmul_(&a, &b)
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_pow);
+criterion_main!(benches);
@@ -0,0 +1,214 @@
+//! Human readable encoding of tip5 digests.
+//!
+//! Block ids, transaction ids and lock hashes are all 5-belt tip5 digests.
+//! Wherever one is shown to or taken from a user it is written as an
+//! address: base58 of a version byte, the five belts as big endian `u64`s
+//! and a 4 byte checksum. The checksum is the low 32 bits of the first belt
+//! of the tip5 hash of the version and the digest, so a mistyped character
+//! is caught before it reaches the kernel.
+//!
+//! The kernel names digests in cords with its own unchecked base58 of the
+//! digest read as a base p number, [`to_kernel_base58`] produces that form
+//! for scries and pokes. Files and logs name a digest by [`to_hex`].
+//!
+//! Every parser here is strict: it takes only the spelling its encoder
+//! produces for some digest, with every belt in the field.
+
+use ibig::UBig;
+use thiserror::Error;
+
+use crate::form::math::base::{based_check, PRIME};
+use crate::form::math::tip5::{hash_varlen, DIGEST_LENGTH};
+
+/// Version byte of the current address format
+pub const ADDRESS_VERSION: u8 = 1;
+
+const CHECKSUM_LEN: usize = 4;
+const ADDRESS_LEN: usize = 1 + DIGEST_LENGTH * 8 + CHECKSUM_LEN;
+const HEX_LEN: usize = DIGEST_LENGTH * 16;
+
+pub type Digest = [u64; DIGEST_LENGTH];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("address is not base58: {0}")]
+    Base58(#[from] bs58::decode::Error),
+    #[error("address is {0} bytes, expected {}", ADDRESS_LEN)]
+    Length(usize),
+    #[error("unknown address version {0}")]
+    Version(u8),
+    #[error("address checksum does not match")]
+    Checksum,
+    #[error("address limb {0} is not a base field element")]
+    NotBelt(u64),
+    #[error("digest hex is {0} characters, expected {}", HEX_LEN)]
+    HexLength(usize),
+    #[error("digest hex has a non-hex character at {0}")]
+    HexDigit(usize),
+    #[error("not the kernel's base58 name of any digest")]
+    NonCanonical,
+}
+
+fn checksum(version: u8, digest: &Digest) -> [u8; CHECKSUM_LEN] {
+    let mut input = [0u64; DIGEST_LENGTH + 1];
+    input[0] = version as u64;
+    input[1..].copy_from_slice(digest);
+    (hash_varlen(&input)[0] as u32).to_be_bytes()
+}
+
+/// Encode `digest` as an address
+pub fn encode(digest: &Digest) -> String {
+    let mut bytes = Vec::with_capacity(ADDRESS_LEN);
+    bytes.push(ADDRESS_VERSION);
+    for belt in digest {
+        bytes.extend_from_slice(&belt.to_be_bytes());
+    }
+    bytes.extend_from_slice(&checksum(ADDRESS_VERSION, digest));
+    bs58::encode(bytes).into_string()
+}
+
+/// Decode an address produced by [`encode`]
+pub fn decode(address: &str) -> Result<Digest, AddressError> {
+    let bytes = bs58::decode(address.trim()).into_vec()?;
+    if bytes.len() != ADDRESS_LEN {
+        return Err(AddressError::Length(bytes.len()));
+    }
+    let (version, rest) = (bytes[0], &bytes[1..]);
+    if version != ADDRESS_VERSION {
+        return Err(AddressError::Version(version));
+    }
+    let mut digest = [0u64; DIGEST_LENGTH];
+    for (belt, chunk) in digest.iter_mut().zip(rest.chunks_exact(8)) {
+        *belt = u64::from_be_bytes(chunk.try_into().expect("chunk is 8 bytes"));
+        if !based_check(*belt) {
+            return Err(AddressError::NotBelt(*belt));
+        }
+    }
+    if rest[DIGEST_LENGTH * 8..] != checksum(version, &digest) {
+        return Err(AddressError::Checksum);
+    }
+    Ok(digest)
+}
+
+/// The kernel's base58 name for `digest`, as `tip5_hash_to_base58` gives it
+/// for a digest noun
+pub fn to_kernel_base58(digest: &Digest) -> String {
+    let p = UBig::from(PRIME);
+    let value = digest
+        .iter()
+        .rev()
+        .fold(UBig::from(0u64), |acc, belt| acc * &p + UBig::from(*belt));
+    bs58::encode(value.to_be_bytes()).into_string()
+}
+
+/// The digest [`to_kernel_base58`] names `name`
+pub fn from_kernel_base58(name: &str) -> Result<Digest, AddressError> {
+    let mut value = UBig::from_be_bytes(&bs58::decode(name).into_vec()?);
+    let p = UBig::from(PRIME);
+    let mut digest = [0u64; DIGEST_LENGTH];
+    for belt in digest.iter_mut() {
+        *belt = u64::try_from(&value % &p).expect("remainder is below p");
+        value /= &p;
+    }
+    // leading zero bytes and values past p^5 spell no digest
+    if value != UBig::from(0u64) || to_kernel_base58(&digest) != name {
+        return Err(AddressError::NonCanonical);
+    }
+    Ok(digest)
+}
+
+/// `digest` as 16 lowercase hex digits per belt, the first belt first
+pub fn to_hex(digest: &Digest) -> String {
+    digest.iter().map(|belt| format!("{belt:016x}")).collect()
+}
+
+/// Parse [`to_hex`], in either case
+pub fn from_hex(hex: &str) -> Result<Digest, AddressError> {
+    if hex.len() != HEX_LEN {
+        return Err(AddressError::HexLength(hex.len()));
+    }
+    if let Some(i) = hex.bytes().position(|b| !b.is_ascii_hexdigit()) {
+        return Err(AddressError::HexDigit(i));
+    }
+    let mut digest = [0u64; DIGEST_LENGTH];
+    for (belt, chunk) in digest.iter_mut().zip(hex.as_bytes().chunks_exact(16)) {
+        let chunk = std::str::from_utf8(chunk).expect("hex digits are ascii");
+        *belt = u64::from_str_radix(chunk, 16).expect("16 hex digits fit a u64");
+        if !based_check(*belt) {
+            return Err(AddressError::NotBelt(*belt));
+        }
+    }
+    Ok(digest)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DIGEST: Digest = [
+        0x6ef99e5f3447ffda,
+        0xdf94122d1a98ec99,
+        0xcbf1918337a0e197,
+        0x6cda1112891244ce,
+        0x6e420b8a615508d4,
+    ];
+
+    #[test]
+    fn test_address_roundtrip() {
+        let address = encode(&DIGEST);
+        assert_eq!(decode(&address), Ok(DIGEST));
+        assert_eq!(decode(&encode(&[0; 5])), Ok([0; 5]));
+    }
+
+    #[test]
+    fn test_address_catches_typos() {
+        let address = encode(&DIGEST);
+        for i in 0..address.len() {
+            let mut typo = address.clone().into_bytes();
+            typo[i] = if typo[i] == b'2' { b'3' } else { b'2' };
+            let typo = String::from_utf8(typo).unwrap();
+            assert!(decode(&typo).is_err(), "{typo} decoded");
+        }
+        assert!(matches!(decode("0OIl"), Err(AddressError::Base58(_))));
+        assert_eq!(
+            decode(&bs58::encode([1u8; 10]).into_string()),
+            Err(AddressError::Length(10))
+        );
+    }
+
+    #[test]
+    fn test_kernel_base58() {
+        // the vectors of tip5_hash_to_base58
+        assert_eq!(
+            to_kernel_base58(&[1, 2, 3, 4, 5]),
+            "2V9arU36gvtaofWmNowewoj9u7gbNA2qsJZEQ3WPky5mQ"
+        );
+        assert_eq!(
+            to_kernel_base58(&DIGEST),
+            "6UkUko9WTwwR6VVRXwPQpUy5pswdvNtoyHspY5n9nLVnBxzAgEyMwPR"
+        );
+        assert_eq!(from_kernel_base58(&to_kernel_base58(&DIGEST)), Ok(DIGEST));
+        assert_eq!(from_kernel_base58(&to_kernel_base58(&[0; 5])), Ok([0; 5]));
+
+        // a leading zero byte, and p^5 itself
+        let padded = format!("1{}", to_kernel_base58(&DIGEST));
+        assert_eq!(from_kernel_base58(&padded), Err(AddressError::NonCanonical));
+        let p5 = UBig::from(PRIME).pow(5);
+        let p5 = bs58::encode(p5.to_be_bytes()).into_string();
+        assert_eq!(from_kernel_base58(&p5), Err(AddressError::NonCanonical));
+    }
+
+    #[test]
+    fn test_hex_roundtrip() {
+        let hex = to_hex(&DIGEST);
+        assert_eq!(&hex[..16], "6ef99e5f3447ffda");
+        assert_eq!(from_hex(&hex), Ok(DIGEST));
+        assert_eq!(from_hex(&hex.to_uppercase()), Ok(DIGEST));
+
+        assert_eq!(from_hex(&hex[1..]), Err(AddressError::HexLength(79)));
+        let signed = format!("+{}", &hex[1..]);
+        assert_eq!(from_hex(&signed), Err(AddressError::HexDigit(0)));
+        let big = format!("{}{}", "f".repeat(16), &hex[16..]);
+        assert_eq!(from_hex(&big), Err(AddressError::NotBelt(u64::MAX)));
+    }
+}
@@ -6,6 +6,9 @@ pub const PRIME_128: u128 = 18446744069414584321;
 pub const H: u64 = 20033703337;
 pub const ORDER: u64 = 2_u64.pow(32);
 
+// the Goldilocks prime 2^64 - 2^32 + 1, as `+p` in the Hoon
+const _: () = assert!(PRIME_128 == (1 << 64) - (1 << 32) + 1 && PRIME as u128 == PRIME_128);
+
 #[derive(Debug)]
 pub enum FieldError {
     OrderedRootError,
@@ -68,27 +71,73 @@ pub fn bmul(a: u64, b: u64) -> u64 {
     reduce((a as u128) * (b as u128))
 }
 
+/// Exponents below this use plain square-and-multiply, the window table
+/// costs more than it saves on them
+const WINDOW_MIN_EXP: u64 = 1 << 8;
+/// Widest window of exponent bits taken in one multiplication
+const WINDOW_BITS: u32 = 4;
+
+/// `base^exp` by sliding windows over the exponent from its top bit. Each
+/// window ends in a set bit, so only the odd powers up to
+/// `base^(2^WINDOW_BITS - 1)` are tabled.
 #[inline(always)]
-pub fn bpow(mut a: u64, mut b: u64) -> u64 {
-    based!(a);
-    based!(b);
+pub fn window_pow<T: Copy>(base: T, exp: u64, one: T, mul: impl Fn(T, T) -> T) -> T {
+    if exp == 0 {
+        return one;
+    }
+    let top = 63 - exp.leading_zeros() as i32;
+    let bit = |i: i32| (exp >> i) & 1 == 1;
+    if exp < WINDOW_MIN_EXP {
+        let mut res = base;
+        for i in (0..top).rev() {
+            res = mul(res, res);
+            if bit(i) {
+                res = mul(res, base);
+            }
+        }
+        return res;
+    }
 
-    let mut c: u64 = 1;
-    if b == 0 {
-        return c;
+    let square = mul(base, base);
+    let mut odd = [base; 1 << (WINDOW_BITS - 1)];
+    let mut power = base;
+    for entry in odd.iter_mut().skip(1) {
+        power = mul(power, square);
+        *entry = power;
     }
+    // lowest bit of the window from `i`, and the table entry for it
+    let window = |i: i32| {
+        let mut j = (i + 1 - WINDOW_BITS as i32).max(0);
+        while !bit(j) {
+            j += 1;
+        }
+        let value = (exp >> j) & ((1 << (i - j + 1)) - 1);
+        (j, odd[(value >> 1) as usize])
+    };
 
-    while b > 1 {
-        if b & 1 == 0 {
-            a = reduce((a as u128) * (a as u128));
-            b /= 2;
-        } else {
-            c = reduce((c as u128) * (a as u128));
-            a = reduce((a as u128) * (a as u128));
-            b = (b - 1) / 2;
+    let (j, mut res) = window(top);
+    let mut i = j - 1;
+    while i >= 0 {
+        if !bit(i) {
+            res = mul(res, res);
+            i -= 1;
+            continue;
         }
+        let (j, power) = window(i);
+        for _ in j..=i {
+            res = mul(res, res);
+        }
+        res = mul(res, power);
+        i = j - 1;
     }
-    reduce((c as u128) * (a as u128))
+    res
+}
+
+#[inline(always)]
+pub fn bpow(a: u64, b: u64) -> u64 {
+    based!(a);
+    based!(b);
+    window_pow(a, b, 1, bmul)
 }
 
 #[inline(always)]
@@ -107,3 +156,38 @@ pub fn binv(a: u64) -> u64 {
 fn test_binv() {
     assert_eq!(bmul(binv(888), 888), 1);
 }
+
+#[test]
+fn test_bpow_matches_square_and_multiply() {
+    fn square_and_multiply(mut a: u64, mut b: u64) -> u64 {
+        let mut c = 1;
+        while b > 0 {
+            if b & 1 == 1 {
+                c = bmul(c, a);
+            }
+            a = bmul(a, a);
+            b >>= 1;
+        }
+        c
+    }
+    fn prop(a: u64, b: u64) -> bool {
+        let (a, b) = (a % PRIME, b % PRIME);
+        // exercise both the short exponent path and the windows
+        [b, b >> 32, b >> 56, b & 0xff, b | (1 << 62)]
+            .into_iter()
+            .all(|b| bpow(a, b) == square_and_multiply(a, b))
+    }
+    quickcheck::QuickCheck::new().quickcheck(prop as fn(u64, u64) -> bool);
+    for b in [
+        0,
+        1,
+        2,
+        WINDOW_MIN_EXP - 1,
+        WINDOW_MIN_EXP,
+        0x8000,
+        PRIME - 1,
+    ] {
+        assert_eq!(bpow(7, b), square_and_multiply(7, b));
+    }
+    assert_eq!(bpow(0, 0), 1);
+}
@@ -214,6 +214,29 @@ pub fn bp_fft(bp: &[Belt]) -> Result<Vec<Belt>, FieldError> {
     Ok(bp_ntt(bp, &root))
 }
 
+/// Coefficients of the polynomial taking the values `bp` over the subgroup
+/// of order `bp.len()`, the inverse of [`bp_fft`]
+#[inline(always)]
+pub fn bp_ifft(bp: &[Belt]) -> Result<Vec<Belt>, FieldError> {
+    let order: Belt = Belt(bp.len() as u64);
+    let root = order.ordered_root()?.inv();
+    let mut res = bp_ntt(bp, &root);
+    let scale = order.inv();
+    res.iter_mut().for_each(|b| *b = *b * scale);
+    Ok(res)
+}
+
+/// Interpolate the polynomial `p` with `p(offset * w^i) = values[i]`, where
+/// `w` generates the subgroup of order `values.len()`. The ifft gives `q`
+/// with `q(w^i) = values[i]`, and `p(x) = q(x / offset)`.
+#[inline(always)]
+pub fn bp_intercosate(offset: &Belt, values: &[Belt]) -> Result<Vec<Belt>, FieldError> {
+    let coeffs = bp_ifft(values)?;
+    let mut res = vec![Belt::zero(); coeffs.len()];
+    bp_shift(&coeffs, &offset.inv(), &mut res);
+    Ok(res)
+}
+
 pub fn bp_ntt(bp: &[Belt], root: &Belt) -> Vec<Belt> {
     let n = bp.len() as u32;
 
@@ -269,6 +292,16 @@ pub fn bp_shift(poly_a: &[Belt], belt_b: &Belt, poly_res: &mut [Belt]) {
     }
 }
 
+/// Compose a polynomial in evaluation form over a root of unity with the
+/// `n`th power of that root: rotate the evaluations left by `n`
+#[inline(always)]
+pub fn bp_shift_by_unity(bp: &[Belt], n: usize, res: &mut [Belt]) {
+    debug_assert!(n <= bp.len());
+    let (head, tail) = bp.split_at(n);
+    res[..tail.len()].copy_from_slice(tail);
+    res[tail.len()..].copy_from_slice(head);
+}
+
 #[inline(always)]
 pub fn bp_coseword(bp: &[Belt], offset: &Belt, order: u32, root: &Belt) -> Vec<Belt> {
     // shift
@@ -287,6 +320,39 @@ pub fn bpoly_zero_extend(a: &[Belt], res: &mut [Belt]) {
     res[a_len..res_len].fill(Belt::zero());
 }
 
+/// X^n - 1, which vanishes on the subgroup of order n. `res` has length
+/// `n + 1`.
+#[inline(always)]
+pub fn bp_vanishing(n: usize, res: &mut [Belt]) {
+    res.fill(Belt::zero());
+    res[n] = Belt::one();
+    res[0] = res[0] - Belt::one();
+}
+
+/// The `n` for which `b` is X^n - 1 with `n > 0`, ignoring trailing zeros
+pub fn vanishing_degree(b: &[Belt]) -> Option<usize> {
+    let n = b.iter().rposition(|c| !c.is_zero())?;
+    let minus_one = Belt::zero() - Belt::one();
+    let is_vanishing =
+        n > 0 && b[0] == minus_one && b[n].is_one() && b[1..n].iter().all(|c| c.is_zero());
+    is_vanishing.then_some(n)
+}
+
+/// Quotient of `a` by X^n - 1 in linear time. Reading from the top, the
+/// coefficient of x^j is that of x^(j+n) in `a` plus the coefficient of
+/// x^(j+n) in the quotient.
+pub fn bp_div_vanishing(a: &[Belt], n: usize) -> Vec<Belt> {
+    debug_assert!(n > 0);
+    let Some(len) = a.len().checked_sub(n) else {
+        return vec![];
+    };
+    let mut q = a[n..].to_vec();
+    for j in (0..len.saturating_sub(n)).rev() {
+        q[j] = q[j] + q[j + n];
+    }
+    q
+}
+
 #[inline(always)]
 pub fn bpdvr(a: &[Belt], b: &[Belt], q: &mut [Belt], res: &mut [Belt]) {
     if a.is_zero() {
@@ -428,3 +494,43 @@ pub fn normalize_bpoly(a: &mut Vec<Belt>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bp_intercosate_inverts_coseword() {
+        let p: Vec<Belt> = [3, 1, 4, 1, 5, 9, 2, 6].into_iter().map(Belt).collect();
+        assert_eq!(bp_ifft(&bp_fft(&p).unwrap()).unwrap(), p);
+
+        let offset = Belt(7);
+        let order = p.len() as u32;
+        let root = Belt(order as u64).ordered_root().unwrap();
+        let values = bp_coseword(&p, &offset, order, &root);
+        assert_eq!(bp_intercosate(&offset, &values).unwrap(), p);
+
+        assert_eq!(bp_ifft(&[Belt(5)]).unwrap(), vec![Belt(5)]);
+    }
+
+    #[test]
+    fn test_bp_div_vanishing() {
+        let mut z = vec![Belt::zero(); 4];
+        bp_vanishing(3, &mut z);
+        assert_eq!(vanishing_degree(&z), Some(3));
+        assert_eq!(
+            vanishing_degree(&[z.clone(), vec![Belt::zero()]].concat()),
+            Some(3)
+        );
+        assert_eq!(vanishing_degree(&[Belt::zero(), Belt::one()]), None);
+
+        // (2 + 7x + x^4) * (x^3 - 1), so no remainder
+        let q: Vec<Belt> = [2, 7, 0, 0, 1].into_iter().map(Belt).collect();
+        let a = bpmul_(&q, &z);
+        assert_eq!(bp_div_vanishing(&a, 3), q);
+
+        let mut zero = vec![Belt::zero(); 1];
+        bp_vanishing(0, &mut zero);
+        assert!(zero[0].is_zero());
+    }
+}
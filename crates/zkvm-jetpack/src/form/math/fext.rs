@@ -1,4 +1,5 @@
 use crate::form::bpoly::*;
+use crate::form::math::base::window_pow;
 use crate::form::poly::*;
 
 //==============================================================================
@@ -136,26 +137,7 @@ pub fn fpow_(term: &Felt, exponent: u64) -> Felt {
 
 #[inline(always)]
 pub fn fpow(term: &Felt, exponent: u64, c: &mut Felt) {
-    let a: &mut Felt = &mut term.clone();
-    let mut b: u64 = exponent;
-    *c = Felt::from([Belt(1), Belt(0), Belt(0)]);
-    if b == 0 {
-        return;
-    }
-
-    while b > 1 {
-        let a_clone = *a;
-
-        if b & 1 == 0 {
-            fmul(&a_clone, &a_clone, a);
-            b /= 2;
-        } else {
-            fmul(&c.clone(), a, c);
-            fmul(&a_clone, &a_clone, a);
-            b = (b - 1) / 2;
-        }
-    }
-    fmul(&c.clone(), a, c);
+    *c = window_pow(*term, exponent, Felt::one(), |a, b| fmul_(&a, &b));
 }
 
 #[inline(always)]
@@ -0,0 +1,316 @@
+use crate::form::mary::MarySlice;
+use crate::form::math::fext::fpow_;
+use crate::form::poly::*;
+
+//==============================================================================
+// extension field polynomial methods, the fpoly counterparts of bpoly.rs
+//==============================================================================
+
+#[inline(always)]
+pub fn fpscal(scalar: &Felt, fp: &[Felt], res: &mut [Felt]) {
+    for (res, f) in res.iter_mut().zip(fp.iter()) {
+        *res = *scalar * *f;
+    }
+}
+
+#[inline(always)]
+pub fn fp_hadamard(a: &[Felt], b: &[Felt], res: &mut [Felt]) {
+    assert_eq!(
+        a.len(),
+        b.len(),
+        "Unequal lengths: {}, {}",
+        a.len(),
+        b.len()
+    );
+    res.iter_mut()
+        .zip(a.iter())
+        .zip(b.iter())
+        .for_each(|((res_i, a_i), b_i)| {
+            *res_i = *a_i * *b_i;
+        });
+}
+
+/// q(x) = p(c*x), that is q_i = p_i * c^i
+#[inline(always)]
+pub fn fp_shift(poly_a: &[Felt], felt_c: &Felt, poly_res: &mut [Felt]) {
+    let mut felt_power = Felt::one();
+
+    for (res, a) in poly_res.iter_mut().zip(poly_a.iter()) {
+        *res = *a * felt_power;
+        felt_power = felt_power * *felt_c;
+    }
+}
+
+/// Sum of `polys` scaled by the matching `weights`. `res` is as long as the
+/// longest poly and zeroed first.
+#[inline(always)]
+pub fn fp_lincom(polys: &[&[Felt]], weights: &[Felt], res: &mut [Felt]) {
+    assert_eq!(polys.len(), weights.len());
+    res.fill(Felt::zero());
+    for (poly, weight) in polys.iter().zip(weights) {
+        for (res, f) in res.iter_mut().zip(poly.iter()) {
+            *res = *res + *weight * *f;
+        }
+    }
+}
+
+/// Length of `fp` without its trailing zeros, the length `fcan` leaves
+#[inline(always)]
+pub fn fp_canonical_len(fp: &[Felt]) -> usize {
+    fp.iter().rposition(|f| !f.is_zero()).map_or(0, |i| i + 1)
+}
+
+/// Quotient of `p` by `X - x`, shaped as `fpdiv` returns it: one shorter
+/// than the canonical `p`, or zero-fpoly when `p` is constant
+pub fn fp_div_linear(p: &[Felt], x: &Felt) -> Vec<Felt> {
+    let len = fp_canonical_len(p);
+    if len <= 1 {
+        return vec![Felt::zero()];
+    }
+    let mut q = vec![Felt::zero(); len - 1];
+    let mut carry = Felt::zero();
+    for i in (1..len).rev() {
+        carry = p[i] + *x * carry;
+        q[i - 1] = carry;
+    }
+    q
+}
+
+/// `acc + q` as `fpadd` gives it, as long as the longer of the two
+#[inline(always)]
+pub fn fpadd_in_place(acc: &mut Vec<Felt>, q: &[Felt]) {
+    if acc.len() < q.len() {
+        acc.resize(q.len(), Felt::zero());
+    }
+    for (a, b) in acc.iter_mut().zip(q) {
+        *a = *a + *b;
+    }
+}
+
+/// The sample of `+compute-deep`
+pub struct DeepSample<'a> {
+    pub trace_polys: Vec<MarySlice<'a>>,
+    pub trace_openings: &'a [Felt],
+    pub composition_pieces: Vec<&'a [Felt]>,
+    pub composition_piece_openings: &'a [Felt],
+    pub weights: &'a [Felt],
+    pub omicrons: &'a [Felt],
+    pub deep_challenge: Felt,
+    pub comp_eval_point: Felt,
+}
+
+/// `+weighted-linear-combo`: the sum of `weights[i] * (polys[i] - openings[i])
+/// / (X - x)` taking weights and openings from `idx`, and the index after
+/// them. The polys are folded before the one division. None where the Hoon
+/// would crash.
+fn deep_quotient<T: Copy>(
+    polys: &[&[T]],
+    lift: impl Fn(T) -> Felt,
+    openings: &[Felt],
+    weights: &[Felt],
+    idx: usize,
+    x: &Felt,
+) -> Option<(Vec<Felt>, usize)> {
+    if polys.is_empty() {
+        return Some((vec![Felt::zero()], idx));
+    }
+    let num = polys.len();
+    let ws = weights.get(idx..idx + num)?;
+    let opens = openings.get(idx..idx + num)?;
+    if polys.iter().any(|p| p.is_empty()) {
+        return None;
+    }
+
+    let len = polys.iter().map(|p| p.len()).max().unwrap_or(1);
+    let mut acc = vec![Felt::zero(); len];
+    for ((poly, w), open) in polys.iter().zip(ws).zip(opens) {
+        for (a, c) in acc.iter_mut().zip(poly.iter()) {
+            *a = *a + *w * lift(*c);
+        }
+        acc[0] = acc[0] - *w * *open;
+    }
+    Some((fp_div_linear(&acc, x), idx + num))
+}
+
+/// `+compute-deep`: the DEEP composition poly. The columns of every trace
+/// table are divided out at the deep challenge and its omicron shift, then
+/// again at `comp_eval_point`, and the composition pieces at the challenge
+/// to the number of pieces. None where the Hoon would crash.
+pub fn fp_compute_deep(s: &DeepSample) -> Option<Vec<Felt>> {
+    let lift = |b: u64| Felt::lift(Belt(b));
+    let mut acc = vec![Felt::zero()];
+    let mut num = 0;
+    for point in [s.deep_challenge, s.comp_eval_point] {
+        for (i, p) in s.trace_polys.iter().enumerate() {
+            let cols: Vec<&[u64]> = if p.len == 0 {
+                vec![]
+            } else if p.step == 0 {
+                return None;
+            } else {
+                p.dat.chunks_exact(p.step as usize).collect()
+            };
+            let omicron = s.omicrons.get(i)?;
+            let (first, n) = deep_quotient(&cols, lift, s.trace_openings, s.weights, num, &point)?;
+            let (second, n) = deep_quotient(
+                &cols,
+                lift,
+                s.trace_openings,
+                s.weights,
+                n,
+                &(*omicron * point),
+            )?;
+            fpadd_in_place(&mut acc, &first);
+            fpadd_in_place(&mut acc, &second);
+            num = n;
+        }
+    }
+
+    let x = fpow_(&s.deep_challenge, s.composition_pieces.len() as u64);
+    let (pieces, _) = deep_quotient(
+        &s.composition_pieces,
+        |f| f,
+        s.composition_piece_openings,
+        s.weights.get(num..).unwrap_or(&[]),
+        0,
+        &x,
+    )?;
+    fpadd_in_place(&mut acc, &pieces);
+    Some(acc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::form::mary::Mary;
+
+    fn felt(a: u64, b: u64, c: u64) -> Felt {
+        Felt([Belt(a), Belt(b), Belt(c)])
+    }
+
+    #[test]
+    fn test_fp_shift_matches_evaluation() {
+        // p(x) = 3 + 5x + 7x^2, so p(c*x) has coefficients 3, 5c, 7c^2
+        let p = [felt(3, 0, 0), felt(5, 0, 0), felt(7, 0, 0)];
+        let c = felt(2, 1, 0);
+        let mut q = [Felt::zero(); 3];
+        fp_shift(&p, &c, &mut q);
+        assert_eq!(q[0], p[0]);
+        assert_eq!(q[1], p[1] * c);
+        assert_eq!(q[2], p[2] * c * c);
+
+        let mut scaled = [Felt::zero(); 3];
+        fpscal(&c, &p, &mut scaled);
+        let mut pointwise = [Felt::zero(); 3];
+        fp_hadamard(&[c; 3], &p, &mut pointwise);
+        assert_eq!(scaled, pointwise);
+    }
+
+    #[test]
+    fn test_fp_lincom() {
+        let p = [felt(1, 2, 0), felt(3, 0, 0)];
+        let q = [felt(5, 0, 1)];
+        let (a, b) = (felt(2, 0, 0), felt(0, 1, 0));
+        let mut res = [Felt::one(); 2];
+        fp_lincom(&[&p, &q], &[a, b], &mut res);
+        assert_eq!(res, [a * p[0] + b * q[0], a * p[1]]);
+    }
+
+    fn eval(p: &[Felt], x: Felt) -> Felt {
+        p.iter().rev().fold(Felt::zero(), |acc, c| acc * x + *c)
+    }
+
+    #[test]
+    fn test_fp_div_linear() {
+        // (3 + 2x + x^2)(x - c) + 9, with trailing zeros fpdiv ignores
+        let c = felt(4, 1, 0);
+        let q = [felt(3, 0, 0), felt(2, 0, 0), felt(1, 0, 0)];
+        let mut p = vec![Felt::zero(); 6];
+        for (i, q_i) in q.iter().enumerate() {
+            p[i + 1] = p[i + 1] + *q_i;
+            p[i] = p[i] - c * *q_i;
+        }
+        p[0] = p[0] + felt(9, 0, 0);
+        assert_eq!(fp_div_linear(&p, &c), q);
+        assert_eq!(
+            fp_div_linear(&[felt(5, 0, 0), Felt::zero()], &c),
+            [Felt::zero()]
+        );
+    }
+
+    #[test]
+    fn test_compute_deep_matches_evaluate_deep() {
+        // the DEEP poly at r is the sum evaluate-deep makes from the columns at r
+        let tables = [
+            Mary {
+                step: 3,
+                len: 2,
+                dat: vec![1, 2, 3, 4, 5, 6],
+            },
+            Mary {
+                step: 2,
+                len: 1,
+                dat: vec![7, 8],
+            },
+        ];
+        let pieces = [vec![felt(1, 1, 0), felt(2, 0, 3)], vec![felt(0, 5, 0)]];
+        let omicrons = [felt(3, 0, 0), felt(0, 2, 1)];
+        let (z, w) = (felt(5, 6, 7), felt(8, 9, 1));
+        let zd = z * z;
+
+        let cols = |t: &Mary| -> Vec<Vec<Felt>> {
+            t.dat
+                .chunks_exact(t.step as usize)
+                .map(|c| c.iter().map(|b| Felt::lift(Belt(*b))).collect())
+                .collect()
+        };
+        let mut points = vec![];
+        for pt in [z, w] {
+            for (t, o) in tables.iter().zip(omicrons) {
+                for x in [pt, o * pt] {
+                    points.extend(cols(t).into_iter().map(|col| (col, x)));
+                }
+            }
+        }
+        let openings: Vec<_> = points.iter().map(|(col, x)| eval(col, *x)).collect();
+        let piece_openings: Vec<_> = pieces.iter().map(|p| eval(p, zd)).collect();
+        points.extend(pieces.iter().map(|p| (p.clone(), zd)));
+        let weights: Vec<_> = (0..points.len() as u64)
+            .map(|i| felt(i + 1, i, 0))
+            .collect();
+
+        let mut sample = DeepSample {
+            trace_polys: tables.iter().map(|t| t.as_slice()).collect(),
+            trace_openings: &openings,
+            composition_pieces: pieces.iter().map(|p| p.as_slice()).collect(),
+            composition_piece_openings: &piece_openings,
+            weights: &weights,
+            omicrons: &omicrons,
+            deep_challenge: z,
+            comp_eval_point: w,
+        };
+        let deep = fp_compute_deep(&sample).unwrap();
+
+        let r = felt(11, 13, 17);
+        let all_openings = openings.iter().chain(&piece_openings);
+        let expected = points
+            .iter()
+            .zip(all_openings)
+            .zip(&weights)
+            .fold(Felt::zero(), |acc, (((col, x), open), weight)| {
+                acc + *weight * (eval(col, r) - *open) / (r - *x)
+            });
+        assert_eq!(eval(&deep, r), expected);
+
+        sample.weights = &weights[1..];
+        assert!(fp_compute_deep(&sample).is_none());
+    }
+
+    #[test]
+    fn test_fp_canonical_len() {
+        let zero = Felt::zero();
+        assert_eq!(fp_canonical_len(&[]), 0);
+        assert_eq!(fp_canonical_len(&[zero, zero]), 0);
+        assert_eq!(fp_canonical_len(&[felt(1, 0, 0), zero]), 1);
+        assert_eq!(fp_canonical_len(&[zero, felt(0, 0, 4)]), 2);
+    }
+}
@@ -1,4 +1,7 @@
 use crate::form::mary::{MarySlice, MarySliceMut};
+use crate::form::math::bpoly::bp_ifft;
+use crate::form::math::FieldError;
+use crate::form::poly::Belt;
 
 #[inline(always)]
 pub fn mary_weld(a: MarySlice, b: MarySlice, res: MarySliceMut) {
@@ -11,6 +14,37 @@ pub fn mary_weld(a: MarySlice, b: MarySlice, res: MarySliceMut) {
     res.dat[a_len * step..res_len * step].copy_from_slice(b.dat);
 }
 
+/// Place the rows of `b` after those of `a`, row by row
+#[inline(always)]
+pub fn mary_weld_step(a: MarySlice, b: MarySlice, res: MarySliceMut) {
+    assert_eq!(a.len, b.len);
+    let a_step = a.step as usize;
+    let b_step = b.step as usize;
+    assert_eq!(res.step as usize, a_step + b_step);
+    for ((row, a_row), b_row) in res
+        .dat
+        .chunks_exact_mut(a_step + b_step)
+        .zip(a.dat.chunks_exact(a_step))
+        .zip(b.dat.chunks_exact(b_step))
+    {
+        row[..a_step].copy_from_slice(a_row);
+        row[a_step..].copy_from_slice(b_row);
+    }
+}
+
+/// Columns `[i, i + j)` of every row of `ma`
+#[inline(always)]
+pub fn mary_swag_step(ma: MarySlice, i: usize, j: usize, res: MarySliceMut) {
+    assert!(j > 0 && i + j <= ma.step as usize);
+    for (row, ma_row) in res
+        .dat
+        .chunks_exact_mut(j)
+        .zip(ma.dat.chunks_exact(ma.step as usize))
+    {
+        row.copy_from_slice(&ma_row[i..i + j]);
+    }
+}
+
 #[inline(always)]
 pub fn mary_transpose(fpolys: MarySlice, offset: usize, res: &mut MarySliceMut) {
     let step = fpolys.step as usize;
@@ -28,3 +62,85 @@ pub fn mary_transpose(fpolys: MarySlice, offset: usize, res: &mut MarySliceMut)
         }
     }
 }
+
+/// Interpolate each column of `table` over the subgroup of order
+/// `domain_len`, zero extending the columns to that height. `res` gets a
+/// row of `domain_len` coefficients per column.
+pub fn interpolate_table(
+    table: MarySlice,
+    domain_len: usize,
+    res: &mut MarySliceMut,
+) -> Result<(), FieldError> {
+    let num_cols = table.step as usize;
+    let num_rows = table.len as usize;
+    debug_assert!(num_rows <= domain_len);
+
+    // rows past the table are never written, so they stay zero
+    let mut values = vec![Belt::zero(); domain_len];
+    for (col, coeffs) in res.dat.chunks_exact_mut(domain_len).enumerate() {
+        for (row, value) in values[..num_rows].iter_mut().enumerate() {
+            *value = Belt(table.dat[row * num_cols + col]);
+        }
+        for (coeff, b) in coeffs.iter_mut().zip(bp_ifft(&values)?) {
+            *coeff = b.0;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::form::mary::Mary;
+
+    #[test]
+    fn test_weld_and_swag_step() {
+        let left = Mary {
+            step: 2,
+            len: 2,
+            dat: vec![1, 2, 3, 4],
+        };
+        let right = Mary {
+            step: 3,
+            len: 2,
+            dat: vec![5, 6, 7, 8, 9, 10],
+        };
+        let mut welded = Mary {
+            step: 5,
+            len: 2,
+            dat: vec![0; 10],
+        };
+        mary_weld_step(left.as_slice(), right.as_slice(), welded.as_mut_slice());
+        assert_eq!(welded.dat, vec![1, 2, 5, 6, 7, 3, 4, 8, 9, 10]);
+
+        let mut cols = Mary {
+            step: 3,
+            len: 2,
+            dat: vec![0; 6],
+        };
+        mary_swag_step(welded.as_slice(), 2, 3, cols.as_mut_slice());
+        assert_eq!(cols.dat, right.dat);
+    }
+
+    #[test]
+    fn test_interpolate_table_columns() {
+        // three rows of two columns, interpolated over a domain of four
+        let table = Mary {
+            step: 2,
+            len: 3,
+            dat: vec![1, 10, 2, 20, 3, 30],
+        };
+        let mut res = Mary {
+            step: 4,
+            len: 2,
+            dat: vec![0; 8],
+        };
+        interpolate_table(table.as_slice(), 4, &mut res.as_mut_slice()).unwrap();
+
+        for (col, values) in [[1, 2, 3, 0], [10, 20, 30, 0]].into_iter().enumerate() {
+            let coeffs = bp_ifft(&values.map(Belt)).unwrap();
+            let row: Vec<u64> = coeffs.into_iter().map(|b| b.0).collect();
+            assert_eq!(res.dat[col * 4..(col + 1) * 4], row[..]);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Tip5 merkle trees, laid out like the kernel's `merk-heap`.
+//!
+//! Nodes are stored as a heap: the root first, then each level left to
+//! right, with the leaves last. A node is `+hash-ten-cell` of its children,
+//! which is [`hash_10`] of the two digests side by side. Leaves are named
+//! by axis as `+index-to-axis` does, so leaf `i` of `n` is at `n + i`, and a
+//! [`MerkleProof`] is what `+build-merk-proof` gives and
+//! `+verify-merk-proof` accepts.
+
+use rayon::prelude::*;
+use thiserror::Error;
+
+use crate::form::address::Digest;
+use crate::form::math::tip5::{hash_10, hash_10_batch, DIGEST_LENGTH, RATE};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MerkleError {
+    #[error("a merkle tree needs a power of two leaves, got {0}")]
+    LeafCount(usize),
+    #[error("leaf {index} is out of range for a tree of {leaves} leaves")]
+    Index { index: usize, leaves: usize },
+    #[error("{0} digests do not pair up")]
+    OddPairs(usize),
+}
+
+/// Pairs [`hash_pairs`] hands each thread, smaller levels stay on the caller
+const PAR_CHUNK_PAIRS: usize = 1 << 10;
+
+fn pair_input(left: &Digest, right: &Digest) -> [u64; RATE] {
+    let mut input = [0; RATE];
+    input[..DIGEST_LENGTH].copy_from_slice(left);
+    input[DIGEST_LENGTH..].copy_from_slice(right);
+    input
+}
+
+/// `+hash-ten-cell` of two digests
+pub fn hash_pair(left: &Digest, right: &Digest) -> Digest {
+    hash_10(&pair_input(left, right))
+}
+
+/// `+hash-ten-cell` of each adjacent pair of `children`, the parents of one
+/// level of a tree. Each chunk of pairs is hashed in one [`hash_10_batch`],
+/// and the chunks of a big level in parallel.
+pub fn hash_pairs(children: &[Digest]) -> Result<Vec<Digest>, MerkleError> {
+    if children.len() % 2 != 0 {
+        return Err(MerkleError::OddPairs(children.len()));
+    }
+    let pairs: Vec<_> = children
+        .chunks_exact(2)
+        .map(|pair| pair_input(&pair[0], &pair[1]))
+        .collect();
+    if pairs.len() <= PAR_CHUNK_PAIRS {
+        return Ok(hash_10_batch(&pairs));
+    }
+    Ok(pairs
+        .par_chunks(PAR_CHUNK_PAIRS)
+        .map(hash_10_batch)
+        .collect::<Vec<_>>()
+        .concat())
+}
+
+/// `merk-proof`: the root and the sibling of each node from the leaf up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub root: Digest,
+    pub path: Vec<Digest>,
+}
+
+impl MerkleProof {
+    /// `+verify-merk-proof` of `leaf` at `axis`
+    pub fn verify(&self, leaf: &Digest, axis: u64) -> bool {
+        if axis == 0 {
+            return false;
+        }
+        let mut axis = axis;
+        let mut node = *leaf;
+        let mut path = self.path.iter();
+        while axis > 1 {
+            let Some(sibling) = path.next() else {
+                return false;
+            };
+            node = if axis % 2 == 0 {
+                hash_pair(&node, sibling)
+            } else {
+                hash_pair(sibling, &node)
+            };
+            axis /= 2;
+        }
+        path.next().is_none() && node == self.root
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree {
+    heap: Vec<Digest>,
+}
+
+impl MerkleTree {
+    /// Build the tree over `leaves`, whose count must be a power of two.
+    /// Each level is hashed with [`hash_pairs`].
+    pub fn new(leaves: &[Digest]) -> Result<Self, MerkleError> {
+        let n = leaves.len();
+        if !n.is_power_of_two() {
+            return Err(MerkleError::LeafCount(n));
+        }
+        let mut heap = vec![[0; DIGEST_LENGTH]; 2 * n - 1];
+        heap[n - 1..].copy_from_slice(leaves);
+        // a level of `width` nodes starts at `width - 1`
+        let mut width = n;
+        while width > 1 {
+            let parents = hash_pairs(&heap[width - 1..2 * width - 1])?;
+            width /= 2;
+            heap[width - 1..2 * width - 1].copy_from_slice(&parents);
+        }
+        Ok(MerkleTree { heap })
+    }
+
+    pub fn root(&self) -> Digest {
+        self.heap[0]
+    }
+
+    pub fn num_leaves(&self) -> usize {
+        self.heap.len().div_ceil(2)
+    }
+
+    /// Levels in the tree counting the root and the leaves, the depth
+    /// `+build-merk-heap` returns
+    pub fn depth(&self) -> u32 {
+        self.num_leaves().ilog2() + 1
+    }
+
+    /// `+index-to-axis` of leaf `index`
+    pub fn axis(&self, index: usize) -> u64 {
+        (self.num_leaves() + index) as u64
+    }
+
+    /// `+build-merk-proof` for leaf `index`, to be verified at
+    /// [`MerkleTree::axis`]
+    pub fn open(&self, index: usize) -> Result<MerkleProof, MerkleError> {
+        let leaves = self.num_leaves();
+        if index >= leaves {
+            return Err(MerkleError::Index { index, leaves });
+        }
+        let mut node = leaves - 1 + index;
+        let mut path = Vec::with_capacity(self.depth() as usize - 1);
+        while node != 0 {
+            let sibling = if node % 2 == 1 { node + 1 } else { node - 1 };
+            path.push(self.heap[sibling]);
+            node = (node - 1) / 2;
+        }
+        Ok(MerkleProof {
+            root: self.root(),
+            path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nockvm::jets::util::test::{assert_jet, assert_jet_err, init_context};
+    use nockvm::jets::JetErr;
+    use nockvm::noun::{D, T};
+
+    use super::*;
+    use crate::jets::tip5_jets::{hash_pairs_jet, vec_to_hoon_list};
+
+    fn leaves(n: u64) -> Vec<Digest> {
+        (0..n).map(|i| [i, i + 1, i + 2, i + 3, i + 4]).collect()
+    }
+
+    #[test]
+    fn test_merkle_open_and_verify() {
+        let leaves = leaves(8);
+        let tree = MerkleTree::new(&leaves).unwrap();
+        assert_eq!(tree.depth(), 4);
+        let left = hash_pair(
+            &hash_pair(&leaves[0], &leaves[1]),
+            &hash_pair(&leaves[2], &leaves[3]),
+        );
+        let right = hash_pair(
+            &hash_pair(&leaves[4], &leaves[5]),
+            &hash_pair(&leaves[6], &leaves[7]),
+        );
+        assert_eq!(tree.root(), hash_pair(&left, &right));
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = tree.open(i).unwrap();
+            assert_eq!(proof.path.len(), 3);
+            assert!(proof.verify(leaf, tree.axis(i)));
+            assert!(!proof.verify(leaf, tree.axis(i ^ 1)));
+            assert!(!proof.verify(&leaves[(i + 1) % 8], tree.axis(i)));
+
+            let mut short = proof.clone();
+            short.path.pop();
+            assert!(!short.verify(leaf, tree.axis(i)));
+        }
+        assert!(!tree.open(0).unwrap().verify(&leaves[0], 0));
+        assert_eq!(
+            tree.open(8),
+            Err(MerkleError::Index {
+                index: 8,
+                leaves: 8
+            })
+        );
+    }
+
+    #[test]
+    fn test_hash_pairs() {
+        // enough pairs to split across threads, and a partial last chunk
+        let children = leaves(2 * (2 * PAR_CHUNK_PAIRS as u64 + 3));
+        let expected: Vec<_> = children
+            .chunks_exact(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+        assert_eq!(hash_pairs(&children), Ok(expected));
+        assert_eq!(hash_pairs(&[]), Ok(vec![]));
+        assert_eq!(hash_pairs(&children[..3]), Err(MerkleError::OddPairs(3)));
+    }
+
+    #[test]
+    fn test_hash_pairs_jet() {
+        let mut context = init_context();
+        let digests = leaves(3);
+        let lists: Vec<_> = digests
+            .iter()
+            .map(|digest| vec_to_hoon_list(&mut context, digest))
+            .collect();
+        let sam = T(&mut context.stack, &[lists[0], lists[1], lists[2], D(0)]);
+        let parent = hash_pair(&digests[0], &digests[1]);
+        let parent = vec_to_hoon_list(&mut context, &parent);
+        // the odd one out is passed up as it is
+        let res = T(&mut context.stack, &[parent, lists[2], D(0)]);
+        assert_jet(&mut context, hash_pairs_jet, sam, res);
+
+        // hash-10 of pairs that are not two digests is left to the Hoon
+        let short = vec_to_hoon_list(&mut context, &[1, 2, 3]);
+        let sam = T(&mut context.stack, &[short, lists[0], D(0)]);
+        assert_jet_err(&mut context, hash_pairs_jet, sam, JetErr::Punt);
+    }
+
+    #[test]
+    fn test_merkle_shapes() {
+        let one = leaves(1);
+        let tree = MerkleTree::new(&one).unwrap();
+        assert_eq!((tree.root(), tree.depth(), tree.axis(0)), (one[0], 1, 1));
+        assert!(tree.open(0).unwrap().verify(&one[0], 1));
+
+        assert_eq!(MerkleTree::new(&leaves(6)), Err(MerkleError::LeafCount(6)));
+        assert_eq!(MerkleTree::new(&[]), Err(MerkleError::LeafCount(0)));
+    }
+}
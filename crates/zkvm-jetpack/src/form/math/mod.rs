@@ -1,7 +1,9 @@
 pub mod base;
 pub mod bpoly;
 pub mod fext;
+pub mod fpoly;
 pub mod mary;
+pub mod merkle;
 pub mod tip5;
 
 pub use base::*;
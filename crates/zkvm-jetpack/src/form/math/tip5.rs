@@ -1,4 +1,8 @@
-use crate::form::math::{badd, bmul, bpow, PRIME_128};
+use nockvm::noun::Noun;
+use rand::RngCore;
+use thiserror::Error;
+
+use crate::form::math::{badd, based_check, bmul, bpow, PRIME, PRIME_128};
 
 pub const DIGEST_LENGTH: usize = 5;
 pub const STATE_SIZE: usize = 16;
@@ -147,6 +151,52 @@ const ROUND_CONSTANTS: [u64; NUM_ROUNDS * STATE_SIZE] = [
     4565856221886323991,
 ];
 
+/// Montgomery form of `x`, i.e. `x * 2^64 mod p`
+pub const fn montify(x: u64) -> u64 {
+    ((x as u128 * R) % PRIME_128) as u64
+}
+
+/// `montify(1)`, which fills the capacity of a fixed-length sponge
+pub const MONT_ONE: u64 = montify(1);
+
+/// [`ROUND_CONSTANTS`] in Montgomery form, as [`permute`] adds them
+pub const MONT_ROUND_CONSTANTS: [u64; NUM_ROUNDS * STATE_SIZE] = {
+    let mut constants = [0; NUM_ROUNDS * STATE_SIZE];
+    let mut i = 0;
+    while i < constants.len() {
+        constants[i] = montify(ROUND_CONSTANTS[i]);
+        i += 1;
+    }
+    constants
+};
+
+/// Sponge domain, as in `+init-tip5-state`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Domain {
+    Variable,
+    Fixed,
+}
+
+pub const INIT_STATE_VARIABLE: [u64; STATE_SIZE] = [0; STATE_SIZE];
+
+pub const INIT_STATE_FIXED: [u64; STATE_SIZE] = {
+    let mut state = [0; STATE_SIZE];
+    let mut i = RATE;
+    while i < STATE_SIZE {
+        state[i] = MONT_ONE;
+        i += 1;
+    }
+    state
+};
+
+/// Initial sponge state for `domain`
+pub const fn init_tip5_state(domain: Domain) -> [u64; STATE_SIZE] {
+    match domain {
+        Domain::Variable => INIT_STATE_VARIABLE,
+        Domain::Fixed => INIT_STATE_FIXED,
+    }
+}
+
 const MDS_MATRIX_I64: [[i64; STATE_SIZE]; STATE_SIZE] = [
     [
         61402, 17845, 26798, 59689, 12021, 40901, 41351, 27521, 56951, 12034, 53865, 43244, 7454,
@@ -214,27 +264,306 @@ const MDS_MATRIX_I64: [[i64; STATE_SIZE]; STATE_SIZE] = [
     ],
 ];
 
+/// `+mds-matrix-first-column`, the column [`MDS_MATRIX_I64`] is the
+/// circulant of
+const MDS_FIRST_COLUMN: [i64; STATE_SIZE] = [
+    61402, 1108, 28750, 33823, 7454, 43244, 53865, 12034, 56951, 27521, 41351, 40901, 12021, 59689,
+    26798, 17845,
+];
+
+// The parameters are checked against each other at compile time, so an
+// edited constant fails the build instead of giving digests the kernel
+// disagrees with. `test_parameters_match_kernel` compares them with the Hoon.
+const _: () = {
+    assert!(NUM_ROUNDS == 5 || NUM_ROUNDS == 7);
+    assert!(RATE + CAPACITY == STATE_SIZE && DIGEST_LENGTH <= RATE);
+    assert!(1 << LOG2_STATE_SIZE == STATE_SIZE);
+
+    // montify multiplies by 2^64 and mont_reduction has to undo it
+    assert!(R == 1 << 64);
+    assert!(montify(R_INV) == 1);
+
+    let mut i = 0;
+    while i < ROUND_CONSTANTS.len() {
+        assert!(ROUND_CONSTANTS[i] < PRIME);
+        assert!(MONT_ROUND_CONSTANTS[i] < PRIME);
+        i += 1;
+    }
+
+    // the s-box table is x -> (x + 1)^3 - 1 mod 257
+    let mut x = 0;
+    while x < LOOKUP_TABLE.len() {
+        assert!(LOOKUP_TABLE[x] as usize == ((x + 1) * (x + 1) * (x + 1) - 1) % 257);
+        x += 1;
+    }
+
+    // circulant in the first column, with entries small enough that the
+    // batched linear layer can sum a row of products before reducing
+    let mut i = 0;
+    while i < STATE_SIZE {
+        let mut j = 0;
+        while j < STATE_SIZE {
+            let m = MDS_MATRIX_I64[i][j];
+            assert!(m == MDS_FIRST_COLUMN[(i + STATE_SIZE - j) % STATE_SIZE]);
+            assert!(m > 0 && m < 1 << 16);
+            j += 1;
+        }
+        i += 1;
+    }
+};
+
+/// Builds of [`permute`] for different CPU features. They all compute the
+/// same permutation: each is the portable code compiled with its target
+/// features enabled, so the compiler can vectorize the linear layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tip5Backend {
+    Portable,
+    Avx2,
+    Avx512,
+    Neon,
+}
+
+impl Tip5Backend {
+    /// Every backend, slowest first
+    pub const ALL: [Tip5Backend; 4] = [
+        Tip5Backend::Portable,
+        Tip5Backend::Neon,
+        Tip5Backend::Avx2,
+        Tip5Backend::Avx512,
+    ];
+
+    /// The fastest backend this CPU supports
+    pub fn detect() -> Self {
+        Self::ALL
+            .into_iter()
+            .rev()
+            .find(|backend| backend.is_available())
+            .unwrap_or(Tip5Backend::Portable)
+    }
+
+    /// Whether this CPU can run the backend
+    pub fn is_available(self) -> bool {
+        match self {
+            Tip5Backend::Portable => true,
+            #[cfg(target_arch = "x86_64")]
+            Tip5Backend::Avx2 => std::arch::is_x86_feature_detected!("avx2"),
+            #[cfg(target_arch = "x86_64")]
+            Tip5Backend::Avx512 => std::arch::is_x86_feature_detected!("avx512f"),
+            #[cfg(target_arch = "aarch64")]
+            Tip5Backend::Neon => std::arch::is_aarch64_feature_detected!("neon"),
+            _ => false,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tip5Backend::Portable => "portable",
+            Tip5Backend::Avx2 => "avx2",
+            Tip5Backend::Avx512 => "avx512",
+            Tip5Backend::Neon => "neon",
+        }
+    }
+}
+
+impl std::fmt::Display for Tip5Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for Tip5Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|backend| backend.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                format!("Unknown tip5 backend '{s}', expected portable, avx2, avx512 or neon")
+            })
+    }
+}
+
+/// Why [`select_tip5_backend`] refused a backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectBackendError {
+    /// This CPU lacks the backend's features
+    Unavailable(Tip5Backend),
+    /// Hashing already started with this backend
+    AlreadySelected(Tip5Backend),
+}
+
+impl std::fmt::Display for SelectBackendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelectBackendError::Unavailable(backend) => {
+                write!(f, "this CPU cannot run the {backend} tip5 backend")
+            }
+            SelectBackendError::AlreadySelected(backend) => {
+                write!(f, "tip5 backend already set to {backend}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SelectBackendError {}
+
+static BACKEND: std::sync::OnceLock<Tip5Backend> = std::sync::OnceLock::new();
+
+/// The backend [`permute`] runs on, detected on first use unless
+/// [`select_tip5_backend`] picked one earlier
+pub fn tip5_backend() -> Tip5Backend {
+    *BACKEND.get_or_init(Tip5Backend::detect)
+}
+
+/// Override detection, e.g. to benchmark a specific backend. Must run
+/// before the first hash.
+pub fn select_tip5_backend(backend: Tip5Backend) -> Result<(), SelectBackendError> {
+    if !backend.is_available() {
+        return Err(SelectBackendError::Unavailable(backend));
+    }
+    BACKEND.set(backend).or_else(|_| match tip5_backend() {
+        selected if selected == backend => Ok(()),
+        selected => Err(SelectBackendError::AlreadySelected(selected)),
+    })
+}
+
 pub fn permute(sponge: &mut [u64; 16]) {
+    match tip5_backend() {
+        // SAFETY: a backend is only selected if the CPU has its features
+        #[cfg(target_arch = "x86_64")]
+        Tip5Backend::Avx512 => unsafe { permute_avx512(sponge) },
+        #[cfg(target_arch = "x86_64")]
+        Tip5Backend::Avx2 => unsafe { permute_avx2(sponge) },
+        #[cfg(target_arch = "aarch64")]
+        Tip5Backend::Neon => unsafe { permute_neon(sponge) },
+        _ => permute_rounds(sponge),
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn permute_avx2(sponge: &mut [u64; 16]) {
+    permute_rounds(sponge)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f")]
+unsafe fn permute_avx512(sponge: &mut [u64; 16]) {
+    permute_rounds(sponge)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn permute_neon(sponge: &mut [u64; 16]) {
+    permute_rounds(sponge)
+}
+
+/// States [`permute_batch`] interleaves per call
+pub const PERMUTE_LANES: usize = 8;
+
+/// [`permute`] every state in `states`. They go through the rounds
+/// [`PERMUTE_LANES`] at a time with each layer run across all of them, which
+/// keeps independent work in flight and lets the MDS products be summed
+/// before a single reduction.
+pub fn permute_batch(states: &mut [[u64; STATE_SIZE]]) {
+    match tip5_backend() {
+        // SAFETY: a backend is only selected if the CPU has its features
+        #[cfg(target_arch = "x86_64")]
+        Tip5Backend::Avx512 => unsafe { permute_batch_avx512(states) },
+        #[cfg(target_arch = "x86_64")]
+        Tip5Backend::Avx2 => unsafe { permute_batch_avx2(states) },
+        #[cfg(target_arch = "aarch64")]
+        Tip5Backend::Neon => unsafe { permute_batch_neon(states) },
+        _ => permute_batch_lanes(states),
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn permute_batch_avx2(states: &mut [[u64; STATE_SIZE]]) {
+    permute_batch_lanes(states)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f")]
+unsafe fn permute_batch_avx512(states: &mut [[u64; STATE_SIZE]]) {
+    permute_batch_lanes(states)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn permute_batch_neon(states: &mut [[u64; STATE_SIZE]]) {
+    permute_batch_lanes(states)
+}
+
+// eight lanes, then four, then one at a time for what is left
+#[inline(always)]
+fn permute_batch_lanes(states: &mut [[u64; STATE_SIZE]]) {
+    let mut wide = states.chunks_exact_mut(PERMUTE_LANES);
+    for chunk in &mut wide {
+        permute_lanes::<PERMUTE_LANES>(chunk.try_into().expect("chunk is 8 states"));
+    }
+    let mut narrow = wide.into_remainder().chunks_exact_mut(PERMUTE_LANES / 2);
+    for chunk in &mut narrow {
+        permute_lanes::<{ PERMUTE_LANES / 2 }>(chunk.try_into().expect("chunk is 4 states"));
+    }
+    for state in narrow.into_remainder() {
+        permute_lanes::<1>(std::array::from_mut(state));
+    }
+}
+
+#[inline(always)]
+fn permute_lanes<const N: usize>(states: &mut [[u64; STATE_SIZE]; N]) {
+    for round in 0..NUM_ROUNDS {
+        // transposed so each element of the state is contiguous across lanes
+        let mut sboxed = [[0u64; N]; STATE_SIZE];
+        for (j, elem) in sboxed.iter_mut().enumerate() {
+            for (out, state) in elem.iter_mut().zip(states.iter()) {
+                *out = if j < NUM_SPLIT_AND_LOOKUP {
+                    split_and_lookup(state[j])
+                } else {
+                    bpow(state[j], 7)
+                };
+            }
+        }
+
+        // sixteen products of a 16 bit entry and a belt stay below 2^84
+        for i in 0..STATE_SIZE {
+            let mut acc = [0u128; N];
+            for (j, elem) in sboxed.iter().enumerate() {
+                let entry = MDS_MATRIX_I64[i][j] as u128;
+                for (sum, &x) in acc.iter_mut().zip(elem) {
+                    *sum += entry * x as u128;
+                }
+            }
+            let constant = MONT_ROUND_CONSTANTS[round * STATE_SIZE + i];
+            for (state, sum) in states.iter_mut().zip(acc) {
+                state[i] = badd(constant, (sum % PRIME_128) as u64);
+            }
+        }
+    }
+}
+
+// inlined into every backend so each is compiled with its own features
+#[inline(always)]
+fn permute_rounds(sponge: &mut [u64; 16]) {
     for i in 0..NUM_ROUNDS {
         let a = sbox_layer(array_ref![sponge, 0, STATE_SIZE]);
         let b = linear_layer(&a);
 
         for j in 0..STATE_SIZE {
-            let r_cons = (((ROUND_CONSTANTS[i * STATE_SIZE + j] as u128) * R) % PRIME_128) as u64;
-            sponge[j] = badd(r_cons, b[j]);
+            sponge[j] = badd(MONT_ROUND_CONSTANTS[i * STATE_SIZE + j], b[j]);
         }
     }
 }
 
+#[inline(always)]
 fn sbox_layer(state: &[u64; STATE_SIZE]) -> [u64; STATE_SIZE] {
     let mut res: [u64; STATE_SIZE] = [0; STATE_SIZE];
 
     for i in 0..NUM_SPLIT_AND_LOOKUP {
-        let mut bytes = state[i].to_le_bytes();
-        for i in 0..8 {
-            bytes[i] = LOOKUP_TABLE[bytes[i] as usize];
-        }
-        res[i] = u64::from_le_bytes(bytes);
+        res[i] = split_and_lookup(state[i]);
     }
 
     for j in NUM_SPLIT_AND_LOOKUP..STATE_SIZE {
@@ -243,6 +572,16 @@ fn sbox_layer(state: &[u64; STATE_SIZE]) -> [u64; STATE_SIZE] {
     res
 }
 
+#[inline(always)]
+fn split_and_lookup(x: u64) -> u64 {
+    let mut bytes = x.to_le_bytes();
+    for byte in bytes.iter_mut() {
+        *byte = LOOKUP_TABLE[*byte as usize];
+    }
+    u64::from_le_bytes(bytes)
+}
+
+#[inline(always)]
 fn linear_layer(state: &[u64; 16]) -> [u64; 16] {
     let mut result = [0u64; 16];
 
@@ -256,3 +595,602 @@ fn linear_layer(state: &[u64; 16]) -> [u64; 16] {
 
     result
 }
+
+/// `2^-64 mod p`, undoes [`montify`]
+const R_INV: u64 = 18446744065119617025;
+
+/// Bring a Montgomery-form element back to a belt
+pub fn mont_reduction(x: u64) -> u64 {
+    bmul(x, R_INV)
+}
+
+/// A tip5 sponge in the variable-length domain, as `+sponge` in the Hoon
+#[derive(Debug, Clone)]
+pub struct Sponge {
+    state: [u64; STATE_SIZE],
+}
+
+impl Default for Sponge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sponge {
+    pub fn new() -> Self {
+        Sponge {
+            state: init_tip5_state(Domain::Variable),
+        }
+    }
+
+    /// Resume from a `tip5-state`, which is kept in Montgomery form
+    pub fn from_state(state: [u64; STATE_SIZE]) -> Self {
+        Sponge { state }
+    }
+
+    pub fn state(&self) -> &[u64; STATE_SIZE] {
+        &self.state
+    }
+
+    /// Absorb one block of belts without padding
+    pub fn absorb_block(&mut self, block: &[u64; RATE]) {
+        for (lane, &belt) in self.state.iter_mut().zip(block) {
+            *lane = montify(belt);
+        }
+        permute(&mut self.state);
+    }
+
+    /// Absorb `input`, padded with `[1 0 ... 0]` to a multiple of [`RATE`]
+    pub fn absorb(&mut self, input: &[u64]) {
+        let mut blocks = input.chunks_exact(RATE);
+        for block in &mut blocks {
+            self.absorb_block(array_ref![block, 0, RATE]);
+        }
+        let rest = blocks.remainder();
+        let mut last = [0; RATE];
+        last[..rest.len()].copy_from_slice(rest);
+        last[rest.len()] = 1;
+        self.absorb_block(&last);
+    }
+
+    /// Squeeze out a full rate of belts
+    pub fn squeeze(&mut self) -> [u64; RATE] {
+        let mut output = [0; RATE];
+        for (out, &lane) in output.iter_mut().zip(&self.state[..RATE]) {
+            *out = mont_reduction(lane);
+        }
+        permute(&mut self.state);
+        output
+    }
+}
+
+/// The Fiat-Shamir PRNG `+tog`: challenges are squeezed out of a sponge that
+/// has absorbed the proof so far, so the prover and verifier draw the same
+/// ones
+#[derive(Debug, Clone)]
+pub struct Tog {
+    sponge: Sponge,
+}
+
+impl Tog {
+    pub fn new(sponge: Sponge) -> Self {
+        Tog { sponge }
+    }
+
+    pub fn sponge(&self) -> &Sponge {
+        &self.sponge
+    }
+
+    /// The next `n` belts. As `+belts` does, this squeezes `n / RATE + 1`
+    /// times, so a multiple of [`RATE`] still squeezes once more.
+    pub fn belts(&mut self, n: usize) -> Vec<u64> {
+        let mut output = Vec::with_capacity(n);
+        for _ in 0..n / RATE {
+            output.extend(self.sponge.squeeze());
+        }
+        let last = self.sponge.squeeze();
+        output.extend(&last[..n % RATE]);
+        output
+    }
+}
+
+/// A deterministic RNG over a tip5 sponge that has absorbed a seed digest.
+/// It squeezes a rate of belts at a time, so the same seed gives the same
+/// stream on every platform, and its first belts are the ones `+belts:tog`
+/// draws from that sponge.
+#[derive(Debug, Clone)]
+pub struct Tip5Rng {
+    sponge: Sponge,
+    block: [u64; RATE],
+    next: usize,
+}
+
+impl Tip5Rng {
+    pub fn from_digest(seed: &[u64; DIGEST_LENGTH]) -> Self {
+        let mut sponge = Sponge::new();
+        sponge.absorb(seed);
+        Tip5Rng {
+            sponge,
+            block: [0; RATE],
+            next: RATE,
+        }
+    }
+
+    /// Seeded with `seed` split into two 32 bit belts
+    pub fn from_u64(seed: u64) -> Self {
+        Self::from_digest(&[seed & 0xffff_ffff, seed >> 32, 0, 0, 0])
+    }
+
+    /// The next belt, uniform over the field
+    pub fn next_belt(&mut self) -> u64 {
+        if self.next == RATE {
+            self.block = self.sponge.squeeze();
+            self.next = 0;
+        }
+        self.next += 1;
+        self.block[self.next - 1]
+    }
+}
+
+impl RngCore for Tip5Rng {
+    /// The low half of a belt. Belts below `p - 1` cover every low half
+    /// equally often, so only `p - 1` itself is skipped.
+    fn next_u32(&mut self) -> u32 {
+        loop {
+            let belt = self.next_belt();
+            if belt < PRIME - 1 {
+                return belt as u32;
+            }
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let low = self.next_u32() as u64;
+        (self.next_u32() as u64) << 32 | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            chunk.copy_from_slice(&self.next_u32().to_le_bytes()[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// `+hash-varlen` of a list of belts
+pub fn hash_varlen(input: &[u64]) -> [u64; DIGEST_LENGTH] {
+    let mut sponge = Sponge::new();
+    sponge.absorb(input);
+    let output = sponge.squeeze();
+    *array_ref![output, 0, DIGEST_LENGTH]
+}
+
+/// `+mac-belts-list`: [`hash_varlen`] keyed with a digest, absorbed ahead of
+/// the message in the same sponge. The key is always five belts so it cannot
+/// run into the message, and only a digest of the state is squeezed out, so
+/// a tag cannot be extended to a longer message without the key.
+pub fn mac_varlen(key: &[u64; DIGEST_LENGTH], message: &[u64]) -> [u64; DIGEST_LENGTH] {
+    let mut input = Vec::with_capacity(DIGEST_LENGTH + message.len());
+    input.extend_from_slice(key);
+    input.extend_from_slice(message);
+    hash_varlen(&input)
+}
+
+/// Whether `tag` is [`mac_varlen`] of `message` under `key`, comparing every
+/// belt rather than stopping at the first that differs
+pub fn verify_mac(key: &[u64; DIGEST_LENGTH], message: &[u64], tag: &[u64; DIGEST_LENGTH]) -> bool {
+    mac_varlen(key, message)
+        .iter()
+        .zip(tag)
+        .fold(0, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+/// `+hash-10`: ten belts through one permutation of a fixed-length sponge
+pub fn hash_10(input: &[u64; RATE]) -> [u64; DIGEST_LENGTH] {
+    let mut state = init_tip5_state(Domain::Fixed);
+    for (lane, &belt) in state.iter_mut().zip(input) {
+        *lane = montify(belt);
+    }
+    permute(&mut state);
+    let mut digest = [0; DIGEST_LENGTH];
+    for (out, &lane) in digest.iter_mut().zip(&state[..DIGEST_LENGTH]) {
+        *out = mont_reduction(lane);
+    }
+    digest
+}
+
+/// [`hash_10`] of each input, permuted together with [`permute_batch`]
+pub fn hash_10_batch(inputs: &[[u64; RATE]]) -> Vec<[u64; DIGEST_LENGTH]> {
+    let mut states: Vec<[u64; STATE_SIZE]> = inputs
+        .iter()
+        .map(|input| {
+            let mut state = init_tip5_state(Domain::Fixed);
+            for (lane, &belt) in state.iter_mut().zip(input) {
+                *lane = montify(belt);
+            }
+            state
+        })
+        .collect();
+    permute_batch(&mut states);
+    states
+        .iter()
+        .map(|state| {
+            let mut digest = [0; DIGEST_LENGTH];
+            for (out, &lane) in digest.iter_mut().zip(&state[..DIGEST_LENGTH]) {
+                *out = mont_reduction(lane);
+            }
+            digest
+        })
+        .collect()
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum NounDigestError {
+    #[error("noun has a leaf that is not a base field element")]
+    NotBelt,
+}
+
+/// `+hash-noun-varlen`, the digest the kernel gives a noun: `+hash-varlen`
+/// of the leaf count, the leaves left to right and the Dyck word of the
+/// noun's shape. Every leaf has to be a belt.
+pub fn tip5_noun_digest(noun: Noun) -> Result<[u64; DIGEST_LENGTH], NounDigestError> {
+    enum Step {
+        Tree(Noun),
+        // the 1 between a cell's head and tail in the Dyck word
+        Tail,
+    }
+
+    let mut leaves = Vec::new();
+    let mut dyck = Vec::new();
+    let mut todo = vec![Step::Tree(noun)];
+    while let Some(step) = todo.pop() {
+        match step {
+            Step::Tail => dyck.push(1),
+            Step::Tree(tree) => match tree.as_cell() {
+                Ok(cell) => {
+                    dyck.push(0);
+                    todo.extend([Step::Tree(cell.tail()), Step::Tail, Step::Tree(cell.head())]);
+                }
+                Err(_) => {
+                    let leaf = tree
+                        .as_atom()
+                        .and_then(|atom| atom.as_u64())
+                        .map_err(|_| NounDigestError::NotBelt)?;
+                    if !based_check(leaf) {
+                        return Err(NounDigestError::NotBelt);
+                    }
+                    leaves.push(leaf);
+                }
+            },
+        }
+    }
+
+    let mut input = Vec::with_capacity(1 + leaves.len() + dyck.len());
+    input.push(leaves.len() as u64);
+    input.extend(leaves);
+    input.extend(dyck);
+    Ok(hash_varlen(&input))
+}
+
+/// Bytes packed into each belt by [`StreamHasher`], small enough to always be
+/// below p
+pub const BYTES_PER_BELT: usize = 7;
+
+const BLOCK_BYTES: usize = RATE * BYTES_PER_BELT;
+
+/// Incremental tip5 hash of a byte stream.
+///
+/// Bytes are packed little-endian, [`BYTES_PER_BELT`] to a belt, and
+/// absorbed a block of [`RATE`] belts at a time. The stream is terminated
+/// with a `0x01` byte before the last belt is zero-filled, so inputs that
+/// differ only in trailing zero bytes hash differently. This is a hash of
+/// bytes, not the hash of any Hoon noun.
+#[derive(Debug, Clone)]
+pub struct StreamHasher {
+    sponge: Sponge,
+    buffer: [u8; BLOCK_BYTES],
+    buffered: usize,
+}
+
+impl Default for StreamHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamHasher {
+    pub fn new() -> Self {
+        StreamHasher {
+            sponge: Sponge::new(),
+            buffer: [0; BLOCK_BYTES],
+            buffered: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let take = (BLOCK_BYTES - self.buffered).min(bytes.len());
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&bytes[..take]);
+            self.buffered += take;
+            bytes = &bytes[take..];
+            if self.buffered == BLOCK_BYTES {
+                let block = pack_belts(&self.buffer);
+                self.sponge.absorb_block(array_ref![block, 0, RATE]);
+                self.buffered = 0;
+            }
+        }
+    }
+
+    pub fn finalize(mut self) -> [u64; DIGEST_LENGTH] {
+        let mut tail = self.buffer[..self.buffered].to_vec();
+        tail.push(1);
+        self.sponge.absorb(&pack_belts(&tail));
+        let output = self.sponge.squeeze();
+        *array_ref![output, 0, DIGEST_LENGTH]
+    }
+}
+
+impl std::io::Write for StreamHasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hash everything `reader` produces without holding it all in memory
+pub fn hash_reader<R: std::io::Read>(mut reader: R) -> std::io::Result<[u64; DIGEST_LENGTH]> {
+    let mut hasher = StreamHasher::new();
+    std::io::copy(&mut reader, &mut hasher)?;
+    Ok(hasher.finalize())
+}
+
+/// Little-endian belts of [`BYTES_PER_BELT`] bytes each, the last zero-filled
+fn pack_belts(bytes: &[u8]) -> Vec<u64> {
+    bytes
+        .chunks(BYTES_PER_BELT)
+        .map(|chunk| {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod vectors;
+
+#[cfg(test)]
+mod tests {
+    use nockapp::noun::slab::NounSlab;
+    use nockvm::noun::{Atom, D, T};
+
+    use super::*;
+
+    #[test]
+    fn test_cached_montgomery_constants() {
+        // 2^64 mod p = 2^32 - 1
+        assert_eq!(MONT_ONE, (1 << 32) - 1);
+        for (cached, constant) in MONT_ROUND_CONSTANTS.iter().zip(ROUND_CONSTANTS) {
+            assert_eq!(*cached, (((constant as u128) * R) % PRIME_128) as u64);
+            assert!(*cached < PRIME);
+        }
+        assert_eq!(init_tip5_state(Domain::Variable), [0; STATE_SIZE]);
+        let fixed = init_tip5_state(Domain::Fixed);
+        assert!(fixed[..RATE].iter().all(|&x| x == 0));
+        assert!(fixed[RATE..].iter().all(|&x| x == MONT_ONE));
+    }
+
+    #[test]
+    fn test_mont_reduction_inverts_montify() {
+        for x in [0, 1, 2, 12345, PRIME - 1] {
+            assert_eq!(mont_reduction(montify(x)), x);
+        }
+    }
+
+    #[test]
+    fn test_tip5_backends_agree() {
+        let mut input = [0; STATE_SIZE];
+        for (i, lane) in input.iter_mut().enumerate() {
+            *lane = montify(i as u64 * 0x1234_5678_9abc);
+        }
+        let mut expected = input;
+        permute_rounds(&mut expected);
+
+        let detected = Tip5Backend::detect();
+        assert!(detected.is_available());
+        for backend in Tip5Backend::ALL.into_iter().filter(|b| b.is_available()) {
+            let mut state = input;
+            match backend {
+                #[cfg(target_arch = "x86_64")]
+                Tip5Backend::Avx512 => unsafe { permute_avx512(&mut state) },
+                #[cfg(target_arch = "x86_64")]
+                Tip5Backend::Avx2 => unsafe { permute_avx2(&mut state) },
+                #[cfg(target_arch = "aarch64")]
+                Tip5Backend::Neon => unsafe { permute_neon(&mut state) },
+                _ => permute_rounds(&mut state),
+            }
+            assert_eq!(state, expected, "{backend} disagrees with portable");
+            assert_eq!(backend.to_string().parse(), Ok(backend));
+        }
+
+        let mut state = input;
+        permute(&mut state);
+        assert_eq!(state, expected);
+        let selected = tip5_backend();
+        assert_eq!(select_tip5_backend(selected), Ok(()));
+        if selected != Tip5Backend::Portable {
+            assert_eq!(
+                select_tip5_backend(Tip5Backend::Portable),
+                Err(SelectBackendError::AlreadySelected(selected))
+            );
+        }
+    }
+
+    #[test]
+    fn test_permute_batch_matches_permute() {
+        // 13 states covers a full chunk, a half chunk and a single
+        let states: Vec<[u64; STATE_SIZE]> = (0..13u64)
+            .map(|n| core::array::from_fn(|i| montify(n * 0x9e37_79b9 + i as u64 * (PRIME >> 5))))
+            .collect();
+        let expected: Vec<_> = states
+            .iter()
+            .map(|state| {
+                let mut state = *state;
+                permute_rounds(&mut state);
+                state
+            })
+            .collect();
+
+        for len in [0, 1, 4, 8, 13] {
+            for backend in Tip5Backend::ALL.into_iter().filter(|b| b.is_available()) {
+                let mut batch = states[..len].to_vec();
+                match backend {
+                    #[cfg(target_arch = "x86_64")]
+                    Tip5Backend::Avx512 => unsafe { permute_batch_avx512(&mut batch) },
+                    #[cfg(target_arch = "x86_64")]
+                    Tip5Backend::Avx2 => unsafe { permute_batch_avx2(&mut batch) },
+                    #[cfg(target_arch = "aarch64")]
+                    Tip5Backend::Neon => unsafe { permute_batch_neon(&mut batch) },
+                    _ => permute_batch_lanes(&mut batch),
+                }
+                assert_eq!(batch, expected[..len], "{backend} batch of {len}");
+            }
+        }
+
+        let inputs: Vec<[u64; RATE]> = (0..9u64)
+            .map(|n| core::array::from_fn(|i| n + i as u64))
+            .collect();
+        let digests: Vec<_> = inputs.iter().map(hash_10).collect();
+        assert_eq!(hash_10_batch(&inputs), digests);
+    }
+
+    #[test]
+    fn test_tog_squeezes_like_hoon() {
+        let mut sponge = Sponge::new();
+        sponge.absorb(&[1, 2, 3]);
+
+        let mut expected = sponge.clone();
+        let first = expected.squeeze();
+        let second = expected.squeeze();
+
+        let mut tog = Tog::new(sponge.clone());
+        let belts = tog.belts(RATE + 2);
+        assert_eq!(belts[..RATE], first);
+        assert_eq!(belts[RATE..], second[..2]);
+        assert_eq!(tog.sponge().state(), expected.state());
+
+        // a whole rate squeezes a second time for nothing
+        let mut tog = Tog::new(sponge);
+        assert_eq!(tog.belts(RATE), first);
+        assert_eq!(tog.sponge().state(), expected.state());
+    }
+
+    #[test]
+    fn test_tip5_rng_is_deterministic() {
+        let seed = [1, 2, 3, 4, 5];
+        let mut rng = Tip5Rng::from_digest(&seed);
+        let belts: Vec<u64> = (0..RATE + 3).map(|_| rng.next_belt()).collect();
+        assert!(belts.iter().all(|&belt| belt < PRIME));
+
+        // the same belts tog draws from the seeded sponge
+        let mut sponge = Sponge::new();
+        sponge.absorb(&seed);
+        assert_eq!(belts[..RATE - 1], Tog::new(sponge).belts(RATE - 1));
+
+        let mut again = Tip5Rng::from_digest(&seed);
+        let mut bytes = [0; 7];
+        again.fill_bytes(&mut bytes);
+        assert_eq!(bytes[..4], (belts[0] as u32).to_le_bytes());
+        assert_eq!(bytes[4..], (belts[1] as u32).to_le_bytes()[..3]);
+        assert_eq!(
+            again.next_u64(),
+            (belts[3] as u32 as u64) << 32 | belts[2] as u32 as u64
+        );
+
+        assert_ne!(
+            Tip5Rng::from_u64(1).next_u64(),
+            Tip5Rng::from_u64(1 << 32).next_u64()
+        );
+        assert_ne!(Tip5Rng::from_u64(1).next_belt(), belts[0]);
+    }
+
+    #[test]
+    fn test_mac_is_keyed_hash_varlen() {
+        let key = [1, 2, 3, 4, 5];
+        let message = [6, 7, 8];
+        let tag = mac_varlen(&key, &message);
+        assert_eq!(tag, hash_varlen(&[1, 2, 3, 4, 5, 6, 7, 8]));
+        assert!(verify_mac(&key, &message, &tag));
+
+        assert_ne!(mac_varlen(&[1, 2, 3, 4, 6], &message), tag);
+        assert!(!verify_mac(&key, &message[..2], &tag));
+        let mut forged = tag;
+        forged[4] ^= 1;
+        assert!(!verify_mac(&key, &message, &forged));
+    }
+
+    #[test]
+    fn test_noun_digest_leaves_then_dyck_word() {
+        let mut slab = NounSlab::new();
+        assert_eq!(tip5_noun_digest(D(7)), Ok(hash_varlen(&[1, 7])));
+
+        // [1 [2 3]] and [[1 2] 3] share leaves but not a shape
+        let right = T(&mut slab, &[D(1), D(2), D(3)]);
+        let left = T(&mut slab, &[D(1), D(2)]);
+        let left = T(&mut slab, &[left, D(3)]);
+        assert_eq!(
+            tip5_noun_digest(right),
+            Ok(hash_varlen(&[3, 1, 2, 3, 0, 1, 0, 1]))
+        );
+        assert_eq!(
+            tip5_noun_digest(left),
+            Ok(hash_varlen(&[3, 1, 2, 3, 0, 0, 1, 1]))
+        );
+
+        let big = Atom::new(&mut slab, PRIME).as_noun();
+        let cell = T(&mut slab, &[D(1), big]);
+        assert_eq!(tip5_noun_digest(cell), Err(NounDigestError::NotBelt));
+    }
+
+    #[test]
+    fn test_stream_hasher_matches_one_shot() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 31 % 251) as u8).collect();
+        let expected = hash_reader(&data[..]).unwrap();
+
+        // split at awkward boundaries around the block size
+        let mut hasher = StreamHasher::new();
+        for chunk in data.chunks(BLOCK_BYTES - 3) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finalize(), expected);
+
+        // the 0x01 terminator keeps trailing zeros significant
+        let mut padded = data.clone();
+        padded.push(0);
+        assert_ne!(hash_reader(&padded[..]).unwrap(), expected);
+        assert_ne!(
+            hash_reader(&[][..]).unwrap(),
+            hash_reader(&[0][..]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_stream_hasher_is_hash_varlen_of_packed_belts() {
+        let data = [7u8; BLOCK_BYTES * 2 + 5];
+        let mut bytes = data.to_vec();
+        bytes.push(1);
+        assert_eq!(
+            hash_reader(&data[..]).unwrap(),
+            hash_varlen(&pack_belts(&bytes))
+        );
+    }
+}
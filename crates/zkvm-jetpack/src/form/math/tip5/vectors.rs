@@ -0,0 +1,223 @@
+//! Known-answer vectors for tip5.
+//!
+//! The digests come from the test constructions of the tip5 reference
+//! implementation: `hash_10` of a preimage that is fed its own digests, and
+//! the sum of `hash_varlen` over the prefixes `[0, 1, .., i)`. They are
+//! pinned here and checked against a permutation written straight from the
+//! spec, with the state kept in canonical form rather than Montgomery form,
+//! so a slip in the Montgomery bookkeeping of [`permute`] cannot pass
+//! unnoticed. The jets have to reproduce the same values.
+
+use nockvm::jets::util::test::{assert_jet, init_context};
+use nockvm::noun::{Atom, D, T};
+
+use super::*;
+use crate::form::math::PRIME;
+use crate::jets::tip5_jets::*;
+
+/// Permutation of the all zero state, in canonical form
+const PERMUTED_ZERO: [u64; STATE_SIZE] = [
+    155502567750458463,
+    8182590853653978671,
+    12908433085134355212,
+    3509016723989440229,
+    7255370741552616817,
+    16314477518670018249,
+    4348367446412623959,
+    15491371322325167525,
+    4324659676280763030,
+    16471348813075008849,
+    3951511490071235824,
+    1781868374752867223,
+    17722444417634630432,
+    2788221120958282469,
+    8087439103491064114,
+    6355570796988160926,
+];
+
+/// `hash_10` after feeding the preimage its own digest six times
+const HASH_10_CHAINED: [u64; DIGEST_LENGTH] = [
+    10260998226143127848,
+    72309482834443789,
+    9241467506511025696,
+    1913570776411718046,
+    3652622006117016575,
+];
+
+/// `hash_varlen` of the empty list
+const HASH_VARLEN_EMPTY: [u64; DIGEST_LENGTH] = [
+    11048995573592393898,
+    6655187932135147625,
+    8573492257662932655,
+    4379820112787053727,
+    3881663824627898703,
+];
+
+/// Sum of `hash_varlen` of `[0, 1, .., i)` for `i` below 20
+const HASH_VARLEN_SUM: [u64; DIGEST_LENGTH] = [
+    641925032738609145,
+    6241344549614653748,
+    8316805733934846971,
+    8895702489543224286,
+    1595014164458348151,
+];
+
+/// The spec's permutation on a canonical state. Split-and-lookup acts on the
+/// bytes of the Montgomery form, everything else on plain field elements.
+fn spec_permute(state: &mut [u64; STATE_SIZE]) {
+    for round in 0..NUM_ROUNDS {
+        for (i, x) in state.iter_mut().enumerate() {
+            if i < NUM_SPLIT_AND_LOOKUP {
+                let bytes = montify(*x).to_le_bytes().map(|b| LOOKUP_TABLE[b as usize]);
+                *x = mont_reduction(u64::from_le_bytes(bytes));
+            } else {
+                *x = bpow(*x, 7);
+            }
+        }
+        let mut mixed = [0; STATE_SIZE];
+        for (row, out) in MDS_MATRIX_I64.iter().zip(mixed.iter_mut()) {
+            for (m, x) in row.iter().zip(state.iter()) {
+                *out = badd(*out, bmul(*m as u64, *x));
+            }
+        }
+        for (i, x) in state.iter_mut().enumerate() {
+            *x = badd(mixed[i], ROUND_CONSTANTS[round * STATE_SIZE + i]);
+        }
+    }
+}
+
+fn chained_hash_10(hash: impl Fn(&[u64; RATE]) -> [u64; DIGEST_LENGTH]) -> [u64; DIGEST_LENGTH] {
+    let mut preimage = [0; RATE];
+    for i in 0..6 {
+        let digest = hash(&preimage);
+        preimage[i..i + DIGEST_LENGTH].copy_from_slice(&digest);
+    }
+    hash(&preimage)
+}
+
+fn summed_hash_varlen(hash: impl Fn(&[u64]) -> [u64; DIGEST_LENGTH]) -> [u64; DIGEST_LENGTH] {
+    let mut sum = [0; DIGEST_LENGTH];
+    for i in 0..20 {
+        let preimage: Vec<u64> = (0..i).collect();
+        for (s, d) in sum.iter_mut().zip(hash(&preimage)) {
+            *s = badd(*s, d);
+        }
+    }
+    sum
+}
+
+#[test]
+fn test_tip5_parameters() {
+    // the s-box table is x -> (x + 1)^3 - 1 mod 257
+    for (x, y) in LOOKUP_TABLE.iter().enumerate() {
+        assert_eq!(*y as u64, ((x as u64 + 1).pow(3) - 1) % 257);
+    }
+    // and the MDS matrix is circulant
+    for (i, row) in MDS_MATRIX_I64.iter().enumerate() {
+        for (j, m) in row.iter().enumerate() {
+            assert_eq!(*m, MDS_MATRIX_I64[0][(j + STATE_SIZE - i) % STATE_SIZE]);
+        }
+    }
+    assert!(ROUND_CONSTANTS.iter().all(|&c| c < PRIME));
+}
+
+/// The numbers in the `nth` `:~` list under `++  arm` of the kernel's tip5
+fn kernel_list(hoon: &str, arm: &str, nth: usize) -> Vec<u64> {
+    let arm = &hoon[hoon
+        .find(&format!("++  {arm}\n"))
+        .expect("arm is in three.hoon")..];
+    let list = arm.split(":~").nth(nth + 1).expect("arm has the list");
+    list[..list.find("==").expect("list is closed")]
+        .lines()
+        .flat_map(|line| line.split("::").next().unwrap_or("").split_whitespace())
+        .map(|n| n.replace('.', "").parse().expect("list holds numbers"))
+        .collect()
+}
+
+#[test]
+fn test_parameters_match_kernel() {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../hoon/common/ztd/three.hoon"
+    );
+    let hoon = std::fs::read_to_string(path).expect("three.hoon is readable");
+
+    let lookup: Vec<u64> = LOOKUP_TABLE.iter().map(|&x| x as u64).collect();
+    assert_eq!(kernel_list(&hoon, "lookup-table", 0), lookup);
+    // the first list is the 5 round variant
+    assert_eq!(NUM_ROUNDS, 7);
+    assert_eq!(kernel_list(&hoon, "round-constants", 1), ROUND_CONSTANTS);
+    let column: Vec<u64> = MDS_FIRST_COLUMN.iter().map(|&m| m as u64).collect();
+    assert_eq!(kernel_list(&hoon, "mds-matrix-first-column", 0), column);
+}
+
+#[test]
+fn test_permutation_vectors() {
+    let mut state = [0; STATE_SIZE];
+    spec_permute(&mut state);
+    assert_eq!(state, PERMUTED_ZERO);
+
+    let mut state = [0; STATE_SIZE];
+    permute(&mut state);
+    assert_eq!(state.map(mont_reduction), PERMUTED_ZERO);
+
+    // any state, not only zero
+    let canonical: [u64; STATE_SIZE] =
+        std::array::from_fn(|i| (i as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15) % PRIME);
+    let mut expected = canonical;
+    spec_permute(&mut expected);
+    let mut state = canonical.map(montify);
+    permute(&mut state);
+    assert_eq!(state.map(mont_reduction), expected);
+}
+
+#[test]
+fn test_hash_vectors() {
+    let spec_hash_10 = |input: &[u64; RATE]| {
+        let mut state = [1; STATE_SIZE];
+        state[..RATE].copy_from_slice(input);
+        spec_permute(&mut state);
+        *array_ref![state, 0, DIGEST_LENGTH]
+    };
+    assert_eq!(chained_hash_10(spec_hash_10), HASH_10_CHAINED);
+    assert_eq!(chained_hash_10(hash_10), HASH_10_CHAINED);
+
+    assert_eq!(hash_varlen(&[]), HASH_VARLEN_EMPTY);
+    assert_eq!(summed_hash_varlen(hash_varlen), HASH_VARLEN_SUM);
+}
+
+#[test]
+fn test_jets_reproduce_vectors() {
+    let mut context = init_context();
+
+    let zero = vec_to_hoon_list(&mut context, &[0; STATE_SIZE]);
+    let permuted = vec_to_hoon_list(&mut context, &PERMUTED_ZERO.map(montify));
+    assert_jet(&mut context, permutation_jet, zero, permuted);
+
+    let mut preimage = [0; RATE];
+    for i in 0..7 {
+        let digest = hash_10(&preimage);
+        let sam = vec_to_hoon_list(&mut context, &preimage);
+        let res = vec_to_hoon_list(&mut context, &digest);
+        assert_jet(&mut context, hash_10_jet, sam, res);
+        if i < 6 {
+            preimage[i..i + DIGEST_LENGTH].copy_from_slice(&digest);
+        } else {
+            assert_eq!(digest, HASH_10_CHAINED);
+        }
+    }
+
+    let empty = vec_to_hoon_list(&mut context, &HASH_VARLEN_EMPTY);
+    assert_jet(&mut context, hash_varlen_jet, D(0), empty);
+    let preimage: Vec<u64> = (0..19).collect();
+    let sam = vec_to_hoon_list(&mut context, &preimage);
+    let res = vec_to_hoon_list(&mut context, &hash_varlen(&preimage));
+    assert_jet(&mut context, hash_varlen_jet, sam, res);
+
+    let key = T(&mut context.stack, &[D(1), D(2), D(3), D(4), D(5)]);
+    let mac_sam = T(&mut context.stack, &[key, sam]);
+    let tag = mac_varlen(&[1, 2, 3, 4, 5], &preimage);
+    let tag = tag.map(|belt| Atom::new(&mut context.stack, belt).as_noun());
+    let tag = T(&mut context.stack, &tag);
+    assert_jet(&mut context, mac_belts_list_jet, mac_sam, tag);
+}
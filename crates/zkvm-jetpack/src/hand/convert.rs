@@ -271,8 +271,17 @@ fn not_cell<T>() -> core::result::Result<T, nockvm::noun::Error> {
 
 impl TryFrom<Noun> for HoonList {
     type Error = nockvm::noun::Error;
+
+    /// Fails unless `n` is `~` or a `~`-terminated list
     fn try_from(n: Noun) -> core::result::Result<Self, Self::Error> {
         if n.is_cell() {
+            let mut end = n;
+            while let Ok(cell) = end.as_cell() {
+                end = cell.tail();
+            }
+            if !unsafe { end.raw_equals(&D(0)) } {
+                return Err(Error::NotRepresentable);
+            }
             Ok(HoonList::from(n.as_cell().unwrap_or_else(|err| {
                 panic!(
                     "Panicked with {err:?} at {}:{} (git sha: {:?})",
@@ -281,6 +290,8 @@ impl TryFrom<Noun> for HoonList {
                     option_env!("GIT_SHA")
                 )
             })))
+        } else if unsafe { n.raw_equals(&D(0)) } {
+            Ok(HoonList { next: None })
         } else {
             not_cell()
         }
@@ -343,3 +354,148 @@ impl From<Noun> for HoonMapIter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nockapp::noun::slab::NounSlab;
+    use nockvm::noun::{NounAllocator, T};
+    use quickcheck::{Arbitrary, Gen, QuickCheck};
+
+    use super::*;
+
+    /// Nesting depth of generated nouns
+    const MAX_DEPTH: usize = 6;
+
+    /// Shape of a noun, built into a slab and read back out
+    #[derive(Debug, Clone, PartialEq)]
+    enum Shape {
+        Atom(u64),
+        Cell(Box<Shape>, Box<Shape>),
+    }
+
+    impl Shape {
+        fn arbitrary_depth(g: &mut Gen, depth: usize) -> Self {
+            if depth == 0 || bool::arbitrary(g) {
+                Shape::Atom(u64::arbitrary(g))
+            } else {
+                Shape::Cell(
+                    Box::new(Shape::arbitrary_depth(g, depth - 1)),
+                    Box::new(Shape::arbitrary_depth(g, depth - 1)),
+                )
+            }
+        }
+
+        fn build<A: NounAllocator>(&self, allocator: &mut A) -> Noun {
+            match self {
+                Shape::Atom(value) => Atom::new(allocator, *value).as_noun(),
+                Shape::Cell(head, tail) => {
+                    let head = head.build(allocator);
+                    let tail = tail.build(allocator);
+                    T(allocator, &[head, tail])
+                }
+            }
+        }
+
+        fn read(noun: Noun) -> Option<Self> {
+            if let Ok(cell) = noun.as_cell() {
+                Some(Shape::Cell(
+                    Box::new(Shape::read(cell.head())?),
+                    Box::new(Shape::read(cell.tail())?),
+                ))
+            } else {
+                Some(Shape::Atom(noun.as_atom().ok()?.as_u64().ok()?))
+            }
+        }
+    }
+
+    impl Arbitrary for Shape {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Shape::arbitrary_depth(g, MAX_DEPTH)
+        }
+    }
+
+    fn build_list(slab: &mut NounSlab, items: &[Shape]) -> Noun {
+        let mut list = D(0);
+        for item in items.iter().rev() {
+            let item = item.build(slab);
+            list = T(slab, &[item, list]);
+        }
+        list
+    }
+
+    fn tuple_roundtrip<const N: usize>(items: Vec<Shape>) -> bool {
+        if items.len() < N {
+            return true;
+        }
+        let items = &items[..N];
+        let mut slab = NounSlab::new();
+        let nouns: Vec<Noun> = items.iter().map(|item| item.build(&mut slab)).collect();
+        let tuple = if N == 1 {
+            nouns[0]
+        } else {
+            T(&mut slab, &nouns)
+        };
+
+        let Ok(parts) = tuple.uncell::<N>() else {
+            return false;
+        };
+        parts
+            .iter()
+            .zip(items)
+            .all(|(part, item)| Shape::read(*part).as_ref() == Some(item))
+    }
+
+    fn list_roundtrip(items: Vec<Shape>) -> bool {
+        let mut slab = NounSlab::new();
+        let list = build_list(&mut slab, &items);
+        let Ok(hoon_list) = HoonList::try_from(list) else {
+            return false;
+        };
+        let read: Option<Vec<Shape>> = hoon_list.map(Shape::read).collect();
+        read.as_ref() == Some(&items)
+    }
+
+    #[test]
+    fn test_tuple_roundtrip() {
+        let mut qc = QuickCheck::new();
+        qc.quickcheck(tuple_roundtrip::<1> as fn(Vec<Shape>) -> bool);
+        qc.quickcheck(tuple_roundtrip::<2> as fn(Vec<Shape>) -> bool);
+        qc.quickcheck(tuple_roundtrip::<3> as fn(Vec<Shape>) -> bool);
+        qc.quickcheck(tuple_roundtrip::<7> as fn(Vec<Shape>) -> bool);
+    }
+
+    #[test]
+    fn test_list_roundtrip() {
+        QuickCheck::new().quickcheck(list_roundtrip as fn(Vec<Shape>) -> bool);
+    }
+
+    #[test]
+    fn test_list_edge_cases() {
+        // empty list
+        assert!(list_roundtrip(vec![]));
+        assert_eq!(HoonList::try_from(D(0)).map(|l| l.count()), Ok(0));
+
+        // deep nesting in a single element
+        let mut deep = Shape::Atom(1);
+        for i in 0..64 {
+            deep = Shape::Cell(Box::new(Shape::Atom(i)), Box::new(deep));
+        }
+        assert!(list_roundtrip(vec![deep.clone(), Shape::Atom(0), deep]));
+
+        // trailing atom instead of ~ is not a list
+        let mut slab = NounSlab::new();
+        let improper = T(&mut slab, &[D(1), D(2), D(3)]);
+        assert!(HoonList::try_from(improper).is_err());
+
+        // a non-null atom is not a list either
+        assert!(HoonList::try_from(D(5)).is_err());
+    }
+
+    #[test]
+    fn test_tuple_too_short() {
+        let mut slab = NounSlab::new();
+        let pair = T(&mut slab, &[D(1), D(2)]);
+        assert!(pair.uncell::<3>().is_err());
+        assert!(D(1).uncell::<2>().is_err());
+    }
+}
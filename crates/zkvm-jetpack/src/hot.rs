@@ -6,6 +6,7 @@ use crate::jets::bp_jets::*;
 use crate::jets::cheetah_jets::*;
 use crate::jets::crypto_jets::*;
 use crate::jets::fext_jets::*;
+use crate::jets::fp_jets::*;
 use crate::jets::mary_jets::*;
 use crate::jets::tip5_jets::*;
 use crate::jets::verifier_jets::*;
@@ -20,6 +21,23 @@ pub fn produce_prover_hot_state() -> Vec<HotEntry> {
     jets.extend(KEYGEN_JETS);
     jets.extend(XTRA_JETS);
     jets.extend(EXTENSION_FIELD_JETS);
+    jets.extend(SCHNORR_JETS);
+
+    jets
+}
+
+/// Jets for the node kernel: the field, polynomial and STARK jets it checks
+/// proofs of work with, and the schnorr jets that check transaction
+/// signatures. Key derivation only happens in the wallet.
+pub fn produce_node_hot_state() -> Vec<HotEntry> {
+    let mut jets: Vec<HotEntry> = Vec::new();
+    jets.extend(BASE_FIELD_JETS);
+    jets.extend(BASE_POLY_JETS);
+    jets.extend(CURVE_JETS);
+    jets.extend(ZTD_JETS);
+    jets.extend(XTRA_JETS);
+    jets.extend(EXTENSION_FIELD_JETS);
+    jets.extend(SCHNORR_JETS);
 
     jets
 }
@@ -80,6 +98,27 @@ pub const XTRA_JETS: &[HotEntry] = &[
         1,
         evaluate_deep_jet,
     ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"misc-lib"),
+            Left(b"proof-lib"),
+            Left(b"utils"),
+            Left(b"fri"),
+            Left(b"table-lib"),
+            Left(b"stark-core"),
+            Left(b"compute-deep"),
+        ],
+        1,
+        compute_deep_jet,
+    ),
     (
         &[
             K_138,
@@ -95,6 +134,64 @@ pub const XTRA_JETS: &[HotEntry] = &[
         1,
         mary_transpose_jet,
     ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ave"),
+            Left(b"weld-step"),
+        ],
+        1,
+        mary_weld_step_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ave"),
+            Left(b"swag-step"),
+        ],
+        1,
+        mary_swag_step_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"zing-bpolys"),
+        ],
+        1,
+        zing_bpolys_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"zing-fpolys"),
+        ],
+        1,
+        zing_fpolys_jet,
+    ),
     (
         &[
             K_138,
@@ -129,6 +226,21 @@ pub const EXTENSION_FIELD_JETS: &[HotEntry] = &[
         1,
         bp_shift_jet,
     ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"bp-shift-by-unity"),
+        ],
+        1,
+        bp_shift_by_unity_jet,
+    ),
     (
         &[
             K_138,
@@ -144,6 +256,111 @@ pub const EXTENSION_FIELD_JETS: &[HotEntry] = &[
         1,
         bp_coseword_jet,
     ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"bp-ifft"),
+        ],
+        1,
+        bp_ifft_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"bp-intercosate"),
+        ],
+        1,
+        bp_intercosate_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"interpolate-table"),
+        ],
+        1,
+        interpolate_table_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"shift"),
+        ],
+        1,
+        shift_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"fpscal"),
+        ],
+        1,
+        fpscal_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"fp-hadamard"),
+        ],
+        1,
+        fp_hadamard_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"fp-lincom"),
+        ],
+        1,
+        fp_lincom_jet,
+    ),
     (
         &[
             K_138,
@@ -264,6 +481,21 @@ pub const EXTENSION_FIELD_JETS: &[HotEntry] = &[
         1,
         mp_substitute_mega_jet,
     ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"mp-substitute-ultra"),
+        ],
+        1,
+        mp_substitute_ultra_jet,
+    ),
 ];
 
 pub const BASE_FIELD_JETS: &[HotEntry] = &[
@@ -410,6 +642,34 @@ pub const BASE_POLY_JETS: &[HotEntry] = &[
         1,
         bpsub_jet,
     ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"bpdiv"),
+        ],
+        1,
+        bpdiv_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"vanishing-bpoly"),
+        ],
+        1,
+        vanishing_bpoly_jet,
+    ),
     (
         &[
             K_138,
@@ -482,23 +742,146 @@ pub const BASE_POLY_JETS: &[HotEntry] = &[
     ),
 ];
 
-pub const ZTD_JETS: &[HotEntry] = &[(
-    &[
-        K_138,
-        Left(b"one"),
-        Left(b"two"),
-        Left(b"tri"),
-        Left(b"qua"),
-        Left(b"pen"),
-        Left(b"zeke"),
-        Left(b"ext-field"),
-        Left(b"misc-lib"),
-        Left(b"tip5-lib"),
-        Left(b"permutation"),
-    ],
-    1,
-    permutation_jet,
-)];
+pub const ZTD_JETS: &[HotEntry] = &[
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"misc-lib"),
+            Left(b"tip5-lib"),
+            Left(b"permutation"),
+        ],
+        1,
+        permutation_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"misc-lib"),
+            Left(b"tip5-lib"),
+            Left(b"hash-varlen"),
+        ],
+        1,
+        hash_varlen_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"misc-lib"),
+            Left(b"tip5-lib"),
+            Left(b"hash-10"),
+        ],
+        1,
+        hash_10_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"misc-lib"),
+            Left(b"tip5-lib"),
+            Left(b"hash-noun-varlen"),
+        ],
+        1,
+        hash_noun_varlen_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"misc-lib"),
+            Left(b"tip5-lib"),
+            Left(b"hash-pairs"),
+        ],
+        1,
+        hash_pairs_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"misc-lib"),
+            Left(b"tip5-lib"),
+            Left(b"mac-belts-list"),
+        ],
+        1,
+        mac_belts_list_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"misc-lib"),
+            Left(b"tip5-lib"),
+            Left(b"sponge"),
+            Left(b"absorb"),
+        ],
+        1,
+        sponge_absorb_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"misc-lib"),
+            Left(b"tip5-lib"),
+            Left(b"tog"),
+            Left(b"belts"),
+        ],
+        1,
+        tog_belts_jet,
+    ),
+];
 
 pub const KEYGEN_JETS: &[HotEntry] = &[(
     &[
@@ -546,3 +929,44 @@ pub const CURVE_JETS: &[HotEntry] = &[(
     1,
     ch_scal_jet,
 )];
+
+pub const SCHNORR_JETS: &[HotEntry] = &[
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"misc-lib"),
+            Left(b"cheetah"),
+            Left(b"schnorr"),
+            Left(b"affine"),
+            Left(b"sign"),
+        ],
+        1,
+        schnorr_sign_jet,
+    ),
+    (
+        &[
+            K_138,
+            Left(b"one"),
+            Left(b"two"),
+            Left(b"tri"),
+            Left(b"qua"),
+            Left(b"pen"),
+            Left(b"zeke"),
+            Left(b"ext-field"),
+            Left(b"misc-lib"),
+            Left(b"cheetah"),
+            Left(b"schnorr"),
+            Left(b"affine"),
+            Left(b"verify"),
+        ],
+        1,
+        schnorr_verify_jet,
+    ),
+];
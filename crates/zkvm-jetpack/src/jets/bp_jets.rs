@@ -1,6 +1,6 @@
 use nockvm::interpreter::Context;
 use nockvm::jets::util::slot;
-use nockvm::jets::Result;
+use nockvm::jets::{JetErr, Result};
 use nockvm::noun::{Atom, IndirectAtom, Noun, D, T};
 
 use crate::form::math::bpoly::*;
@@ -152,6 +152,62 @@ pub fn bp_hadamard_jet(context: &mut Context, subject: Noun) -> Result {
     Ok(res_cell)
 }
 
+/// +vanishing-bpoly: X^n - 1. Not cached across calls like the Hoon's `~+`,
+/// since writing it out costs no more than copying it onto the stack.
+pub fn vanishing_bpoly_jet(context: &mut Context, subject: Noun) -> Result {
+    let n = slot(subject, 6)?;
+
+    let Ok(n_atom) = n.as_atom() else {
+        return jet_err();
+    };
+    let n_32 = n_atom.as_u32()?;
+    let (res_atom, res_poly): (IndirectAtom, &mut [Belt]) =
+        new_handle_mut_slice(&mut context.stack, Some(n_32 as usize + 1));
+    bp_vanishing(n_32 as usize, res_poly);
+
+    let res_cell = finalize_poly(&mut context.stack, Some(res_poly.len()), res_atom);
+
+    Ok(res_cell)
+}
+
+/// +bpdiv, jetted only for division by X^n - 1, which is linear in
+/// coefficient form and needs no zerofier inverses over the evaluation domain
+pub fn bpdiv_jet(context: &mut Context, subject: Noun) -> Result {
+    let sam = slot(subject, 6)?;
+    let a = slot(sam, 2)?;
+    let b = slot(sam, 3)?;
+
+    let (Ok(a_poly), Ok(b_poly)) = (BPolySlice::try_from(a), BPolySlice::try_from(b)) else {
+        return jet_err();
+    };
+    // Only division by a zerofier X^n - 1 is jetted, and only when the
+    // quotient is nonzero
+    let Some(n) = vanishing_degree(b_poly.0) else {
+        return Err(JetErr::Punt);
+    };
+    let a_len = a_poly
+        .0
+        .iter()
+        .rposition(|c| !c.is_zero())
+        .map_or(0, |i| i + 1);
+    if a_len <= n {
+        return Err(JetErr::Punt);
+    }
+    let quot = bp_div_vanishing(&a_poly.0[..a_len], n);
+    // bpdvr stops as soon as the remainder's degree drops below n, so it
+    // never emits the quotient's low zero coefficients
+    let low_zeros = quot.iter().take_while(|c| c.is_zero()).count();
+    let quot = &quot[low_zeros..];
+
+    let (res_atom, res_poly): (IndirectAtom, &mut [Belt]) =
+        new_handle_mut_slice(&mut context.stack, Some(quot.len()));
+    res_poly.copy_from_slice(quot);
+
+    let res_cell = finalize_poly(&mut context.stack, Some(res_poly.len()), res_atom);
+
+    Ok(res_cell)
+}
+
 pub fn bp_ntt_jet(context: &mut Context, subject: Noun) -> Result {
     let sam = slot(subject, 6)?;
     let bp = slot(sam, 2)?;
@@ -189,6 +245,53 @@ pub fn bp_fft_jet(context: &mut Context, subject: Noun) -> Result {
     Ok(res_cell)
 }
 
+pub fn bp_ifft_jet(context: &mut Context, subject: Noun) -> Result {
+    let p = slot(subject, 6)?;
+
+    let Ok(p_poly) = BPolySlice::try_from(p) else {
+        return jet_err();
+    };
+    if p_poly.0.is_empty() {
+        return jet_err();
+    }
+    let returned_bpoly = bp_ifft(p_poly.0)?;
+    let (res_atom, res_poly): (IndirectAtom, &mut [Belt]) =
+        new_handle_mut_slice(&mut context.stack, Some(returned_bpoly.len()));
+    res_poly.copy_from_slice(&returned_bpoly);
+
+    let res_cell: Noun = finalize_poly(&mut context.stack, Some(res_poly.len()), res_atom);
+
+    Ok(res_cell)
+}
+
+pub fn bp_intercosate_jet(context: &mut Context, subject: Noun) -> Result {
+    let sam = slot(subject, 6)?;
+    let offset = slot(sam, 2)?;
+    let order = slot(sam, 6)?;
+    let values = slot(sam, 7)?;
+
+    let (Ok(offset_belt), Ok(order_atom), Ok(values_poly)) = (
+        offset.as_belt(),
+        order.as_atom(),
+        BPolySlice::try_from(values),
+    ) else {
+        return jet_err();
+    };
+    let order_64 = order_atom.as_u64()?;
+    if !order_64.is_power_of_two() || values_poly.len() as u64 != order_64 || offset_belt.is_zero()
+    {
+        return jet_err();
+    }
+    let returned_bpoly = bp_intercosate(&offset_belt, values_poly.0)?;
+    let (res_atom, res_poly): (IndirectAtom, &mut [Belt]) =
+        new_handle_mut_slice(&mut context.stack, Some(returned_bpoly.len()));
+    res_poly.copy_from_slice(&returned_bpoly);
+
+    let res_cell = finalize_poly(&mut context.stack, Some(res_poly.len()), res_atom);
+
+    Ok(res_cell)
+}
+
 pub fn bp_shift_jet(context: &mut Context, subject: Noun) -> Result {
     let sam = slot(subject, 6)?;
     let bp = slot(sam, 2)?;
@@ -206,6 +309,34 @@ pub fn bp_shift_jet(context: &mut Context, subject: Noun) -> Result {
     Ok(res_cell)
 }
 
+pub fn bp_shift_by_unity_jet(context: &mut Context, subject: Noun) -> Result {
+    let sam = slot(subject, 6)?;
+    let bp = slot(sam, 2)?;
+    let n = slot(sam, 3)?;
+
+    let (Ok(bp_poly), Ok(n_atom)) = (BPolySlice::try_from(bp), n.as_atom()) else {
+        return jet_err();
+    };
+    let len = bp_poly.len();
+    if len <= 1 {
+        return Ok(bp);
+    }
+    // For n = 0 and n >= len the hoon welds on a one element zero array,
+    // leave those to it
+    let n = match n_atom.as_u64() {
+        Ok(n) if n != 0 && n < len as u64 => n as usize,
+        _ => return Err(JetErr::Punt),
+    };
+
+    let (res_atom, res_poly): (IndirectAtom, &mut [Belt]) =
+        new_handle_mut_slice(&mut context.stack, Some(len));
+    bp_shift_by_unity(bp_poly.0, n, res_poly);
+
+    let res_cell = finalize_poly(&mut context.stack, Some(res_poly.len()), res_atom);
+
+    Ok(res_cell)
+}
+
 pub fn bp_coseword_jet(context: &mut Context, subject: Noun) -> Result {
     let sam = slot(subject, 6)?;
     let p = slot(sam, 2)?;
@@ -1,3 +1,5 @@
+use std::sync::LazyLock;
+
 use ibig::UBig;
 use nockvm::interpreter::Context;
 use nockvm::jets::cold::{FromNounError, Nounable, NounableResult};
@@ -5,20 +7,22 @@ use nockvm::jets::util::{slot, BAIL_FAIL};
 use nockvm::jets::JetErr;
 use nockvm::noun::{Atom, Noun, NounAllocator, NO, T, YES};
 
-use crate::form::math::base::bneg;
+use crate::form::math::base::{based_check, bneg, PRIME};
 use crate::form::math::bpoly::{bpegcd, bpscal};
+use crate::form::math::tip5::{hash_varlen, DIGEST_LENGTH};
 use crate::form::Belt;
+use crate::jets::utils::jet_err;
 use crate::noun::noun_ext::AtomExt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) struct CheetahPoint {
+pub struct CheetahPoint {
     pub x: F6lt,
     pub y: F6lt,
     pub inf: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) struct F6lt(pub [Belt; 6]);
+pub struct F6lt(pub [Belt; 6]);
 
 #[inline(always)]
 pub(crate) fn make_n_belt<A: NounAllocator>(stack: &mut A, arr: &[Belt]) -> Noun {
@@ -229,11 +233,35 @@ fn ch_double_unsafe(x: &F6lt, y: &F6lt) -> Result<CheetahPoint, JetErr> {
     })
 }
 
-pub(crate) const A_ID: CheetahPoint = CheetahPoint {
+pub const A_ID: CheetahPoint = CheetahPoint {
     x: F6_ZERO,
     y: F6_ONE,
     inf: true,
 };
+/// The generator, `a-gen:curve`
+pub const A_GEN: CheetahPoint = CheetahPoint {
+    x: F6lt([
+        Belt(2754611494552410273),
+        Belt(8599518745794843693),
+        Belt(10526511002404673680),
+        Belt(4830863958577994148),
+        Belt(375185138577093320),
+        Belt(12938930721685970739),
+    ]),
+    y: F6lt([
+        Belt(15384029202802550068),
+        Belt(2774812795997841935),
+        Belt(14375303400746062753),
+        Belt(10708493419890101954),
+        Belt(13187678623570541764),
+        Belt(9990732138772505951),
+    ]),
+    inf: false,
+};
+/// Order of the generator, `g-order:curve`, in hex
+pub const G_ORDER_HEX: &str = "7af2599b3b3f22d0563fbf0f990a37b5327aa72330157722d443623eaed4accf";
+pub static G_ORDER: LazyLock<UBig> =
+    LazyLock::new(|| UBig::from_str_radix(G_ORDER_HEX, 16).expect("g-order is hex"));
 pub(crate) const F6_ZERO: F6lt = F6lt([Belt(0); 6]);
 pub(crate) const F6_ONE: F6lt = F6lt([Belt(1), Belt(0), Belt(0), Belt(0), Belt(0), Belt(0)]);
 
@@ -270,7 +298,7 @@ fn ch_neg(p: &CheetahPoint) -> CheetahPoint {
 }
 
 #[inline(always)]
-fn ch_add(p: &CheetahPoint, q: &CheetahPoint) -> Result<CheetahPoint, JetErr> {
+pub fn ch_add(p: &CheetahPoint, q: &CheetahPoint) -> Result<CheetahPoint, JetErr> {
     if p.inf {
         return Ok(*q);
     }
@@ -302,7 +330,7 @@ pub(crate) fn ch_scal(n: u64, p: &CheetahPoint) -> Result<CheetahPoint, JetErr>
 }
 
 #[inline(always)]
-pub(crate) fn ch_scal_big(n: &UBig, p: &CheetahPoint) -> Result<CheetahPoint, JetErr> {
+pub fn ch_scal_big(n: &UBig, p: &CheetahPoint) -> Result<CheetahPoint, JetErr> {
     let mut n_copy = n.clone();
     let zero = UBig::from(0u64);
     let mut p_copy = *p;
@@ -318,3 +346,154 @@ pub(crate) fn ch_scal_big(n: &UBig, p: &CheetahPoint) -> Result<CheetahPoint, Je
     }
     Ok(acc)
 }
+
+/// `+trunc-g-order`: the first four belts of a digest, read base p, mod
+/// the generator's order
+pub fn trunc_g_order(a: &[u64; DIGEST_LENGTH]) -> UBig {
+    let p = UBig::from(PRIME);
+    let sum = UBig::from(a[0])
+        + &p * UBig::from(a[1])
+        + &p * &p * UBig::from(a[2])
+        + &p * &p * &p * UBig::from(a[3]);
+    sum % &*G_ORDER
+}
+
+/// Hash the coordinates of `points` followed by `m`, as the schnorr
+/// transcripts do
+fn transcript_hash(points: &[&CheetahPoint], m: &[u64; DIGEST_LENGTH]) -> UBig {
+    let mut input = Vec::with_capacity(points.len() * 12 + DIGEST_LENGTH);
+    for point in points {
+        input.extend(point.x.0.iter().chain(&point.y.0).map(|b| b.0));
+    }
+    input.extend_from_slice(m);
+    trunc_g_order(&hash_varlen(&input))
+}
+
+/// `+sign:affine:schnorr`, returning `[chal sig]`. Fails where the Hoon
+/// asserts: a zero or out of range key, or a zero nonce, challenge or
+/// signature.
+pub fn schnorr_sign(sk: &UBig, m: &[u64; DIGEST_LENGTH]) -> Result<(UBig, UBig), JetErr> {
+    let zero = UBig::from(0u64);
+    if *sk == zero || *sk >= *G_ORDER {
+        return jet_err();
+    }
+    let pubkey = ch_scal_big(sk, &A_GEN)?;
+    let nonce = transcript_hash(&[&pubkey], m);
+    if nonce == zero {
+        return jet_err();
+    }
+    let scalar = ch_scal_big(&nonce, &A_GEN)?;
+    let chal = transcript_hash(&[&scalar, &pubkey], m);
+    if chal == zero {
+        return jet_err();
+    }
+    let sig = (nonce + &chal * sk) % &*G_ORDER;
+    if sig == zero {
+        return jet_err();
+    }
+    Ok((chal, sig))
+}
+
+/// `+verify:affine:schnorr`
+pub fn schnorr_verify(
+    pubkey: &CheetahPoint,
+    m: &[u64; DIGEST_LENGTH],
+    chal: &UBig,
+    sig: &UBig,
+) -> Result<bool, JetErr> {
+    let zero = UBig::from(0u64);
+    if *chal == zero || *chal >= *G_ORDER || *sig == zero || *sig >= *G_ORDER {
+        return Ok(false);
+    }
+    let scalar = ch_add(
+        &ch_scal_big(sig, &A_GEN)?,
+        &ch_neg(&ch_scal_big(chal, pubkey)?),
+    )?;
+    Ok(*chal == transcript_hash(&[&scalar, pubkey], m))
+}
+
+/// Read a list of belts. Anything that is not one punts to the Hoon, which
+/// crashes on it where it matters.
+fn belt_list(mut list: Noun) -> Result<Vec<u64>, JetErr> {
+    let mut belts = Vec::new();
+    while let Ok(cell) = list.as_cell() {
+        let belt = cell.head().as_atom().and_then(|a| a.as_u64());
+        match belt {
+            Ok(b) if based_check(b) => belts.push(b),
+            _ => return Err(JetErr::Punt),
+        }
+        list = cell.tail();
+    }
+    if list.as_atom().and_then(|a| a.as_u64()).ok() != Some(0) {
+        return Err(JetErr::Punt);
+    }
+    Ok(belts)
+}
+
+pub fn schnorr_sign_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let sam = slot(subject, 6)?;
+    let sk_belts = belt_list(slot(sam, 2)?)?;
+    let m = belt_list(slot(sam, 3)?)?;
+    let Ok(m) = <[u64; DIGEST_LENGTH]>::try_from(m) else {
+        return jet_err();
+    };
+    // +rep 5: the first belt is the least significant 32 bits
+    let mut sk = UBig::from(0u64);
+    for belt in sk_belts.iter().rev() {
+        if *belt >= 1 << 32 {
+            return jet_err();
+        }
+        sk = (sk << 32) + UBig::from(*belt);
+    }
+
+    let (chal, sig) = schnorr_sign(&sk, &m)?;
+    let chal = Atom::from_ubig(&mut context.stack, &chal).as_noun();
+    let sig = Atom::from_ubig(&mut context.stack, &sig).as_noun();
+    Ok(T(&mut context.stack, &[chal, sig]))
+}
+
+pub fn schnorr_verify_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let sam = slot(subject, 6)?;
+    let pubkey = CheetahPoint::from_noun(&mut context.stack, &slot(sam, 2)?)?;
+    let m = belt_list(slot(sam, 6)?)?;
+    let chal = slot(sam, 14)?.as_atom()?.as_ubig(&mut context.stack);
+    let sig = slot(sam, 15)?.as_atom()?.as_ubig(&mut context.stack);
+
+    let Ok(m) = <[u64; DIGEST_LENGTH]>::try_from(m) else {
+        return Ok(NO);
+    };
+    Ok(if schnorr_verify(&pubkey, &m, &chal, &sig)? {
+        YES
+    } else {
+        NO
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const M: [u64; DIGEST_LENGTH] = [8, 6, 7, 5, 3];
+
+    #[test]
+    fn test_schnorr_sign_verify() {
+        let sk = UBig::from(0x1234_5678_9abc_def0u64) << 100;
+        let pubkey = ch_scal_big(&sk, &A_GEN).unwrap();
+        let (chal, sig) = schnorr_sign(&sk, &M).unwrap();
+        assert!(schnorr_verify(&pubkey, &M, &chal, &sig).unwrap());
+
+        let other = [8, 6, 7, 5, 4];
+        assert!(!schnorr_verify(&pubkey, &other, &chal, &sig).unwrap());
+        let tampered = (&sig + UBig::from(1u64)) % &*G_ORDER;
+        assert!(!schnorr_verify(&pubkey, &M, &chal, &tampered).unwrap());
+        let wrong_key = ch_scal_big(&(sk + UBig::from(1u64)), &A_GEN).unwrap();
+        assert!(!schnorr_verify(&wrong_key, &M, &chal, &sig).unwrap());
+        assert!(!schnorr_verify(&pubkey, &M, &G_ORDER, &sig).unwrap());
+    }
+
+    #[test]
+    fn test_schnorr_rejects_bad_keys() {
+        assert!(schnorr_sign(&UBig::from(0u64), &M).is_err());
+        assert!(schnorr_sign(&G_ORDER, &M).is_err());
+    }
+}
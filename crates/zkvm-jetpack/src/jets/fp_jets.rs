@@ -0,0 +1,173 @@
+use nockvm::interpreter::Context;
+use nockvm::jets::util::slot;
+use nockvm::jets::{JetErr, Result};
+use nockvm::noun::{IndirectAtom, Noun};
+
+use crate::form::mary::MarySlice;
+use crate::form::math::fpoly::*;
+use crate::form::poly::*;
+use crate::hand::handle::*;
+use crate::hand::structs::HoonList;
+use crate::jets::utils::jet_err;
+use crate::noun::noun_ext::NounExt;
+
+pub fn fpscal_jet(context: &mut Context, subject: Noun) -> Result {
+    let sam = slot(subject, 6)?;
+    let c = slot(sam, 2)?;
+    let fp = slot(sam, 3)?;
+
+    let (Ok(c_felt), Ok(fp_poly)) = (c.as_felt(), FPolySlice::try_from(fp)) else {
+        return jet_err();
+    };
+
+    let (res, res_poly): (IndirectAtom, &mut [Felt]) =
+        new_handle_mut_slice(&mut context.stack, Some(fp_poly.len()));
+    fpscal(c_felt, fp_poly.0, res_poly);
+
+    let res_cell = finalize_poly(&mut context.stack, Some(res_poly.len()), res);
+
+    Ok(res_cell)
+}
+
+pub fn fp_hadamard_jet(context: &mut Context, subject: Noun) -> Result {
+    let sam = slot(subject, 6)?;
+    let fp = slot(sam, 2)?;
+    let fq = slot(sam, 3)?;
+
+    let (Ok(fp_poly), Ok(fq_poly)) = (FPolySlice::try_from(fp), FPolySlice::try_from(fq)) else {
+        return jet_err();
+    };
+
+    // The hoon canonicalizes both sides and special cases zero and one, only
+    // its general case is plain pointwise multiplication
+    let len = fp_poly.len();
+    if len <= 1
+        || fq_poly.len() != len
+        || fp_canonical_len(fp_poly.0) != len
+        || fp_canonical_len(fq_poly.0) != len
+    {
+        return Err(JetErr::Punt);
+    }
+
+    let (res, res_poly): (IndirectAtom, &mut [Felt]) =
+        new_handle_mut_slice(&mut context.stack, Some(len));
+    fp_hadamard(fp_poly.0, fq_poly.0, res_poly);
+
+    let res_cell = finalize_poly(&mut context.stack, Some(res_poly.len()), res);
+
+    Ok(res_cell)
+}
+
+pub fn shift_jet(context: &mut Context, subject: Noun) -> Result {
+    let sam = slot(subject, 6)?;
+    let fp = slot(sam, 2)?;
+    let c = slot(sam, 3)?;
+
+    let (Ok(fp_poly), Ok(c_felt)) = (FPolySlice::try_from(fp), c.as_felt()) else {
+        return jet_err();
+    };
+
+    let (res, res_poly): (IndirectAtom, &mut [Felt]) =
+        new_handle_mut_slice(&mut context.stack, Some(fp_poly.len()));
+    fp_shift(fp_poly.0, c_felt, res_poly);
+
+    let res_cell = finalize_poly(&mut context.stack, Some(res_poly.len()), res);
+
+    Ok(res_cell)
+}
+
+pub fn fp_lincom_jet(context: &mut Context, subject: Noun) -> Result {
+    let sam = slot(subject, 6)?;
+    let polys = slot(sam, 2)?;
+    let weights = slot(sam, 3)?;
+
+    let (Ok(polys), Ok(weights_poly)) = (HoonList::try_from(polys), FPolySlice::try_from(weights))
+    else {
+        return jet_err();
+    };
+    let Ok(polys) = polys
+        .map(|p| FPolySlice::try_from(p).map(|p| p.0))
+        .collect::<std::result::Result<Vec<_>, _>>()
+    else {
+        return jet_err();
+    };
+    if polys.len() != weights_poly.len() {
+        return jet_err();
+    }
+    // fpadd asserts neither side is empty, leave that to the hoon
+    if polys.iter().any(|p| p.is_empty()) {
+        return Err(JetErr::Punt);
+    }
+
+    // the sum starts from zero-fpoly, so it is never shorter than one
+    let len = polys.iter().map(|p| p.len()).max().unwrap_or(0).max(1);
+    let (res, res_poly): (IndirectAtom, &mut [Felt]) =
+        new_handle_mut_slice(&mut context.stack, Some(len));
+    fp_lincom(&polys, weights_poly.0, res_poly);
+
+    let res_cell = finalize_poly(&mut context.stack, Some(res_poly.len()), res);
+
+    Ok(res_cell)
+}
+
+pub fn compute_deep_jet(context: &mut Context, subject: Noun) -> Result {
+    let sam = slot(subject, 6)?;
+    let [trace, trace_opens, pieces, piece_opens, weights, omicrons, deep_challenge, comp_point] =
+        sam.uncell()?;
+
+    let (
+        Ok(trace),
+        Ok(trace_opens),
+        Ok(pieces),
+        Ok(piece_opens),
+        Ok(weights),
+        Ok(omicrons),
+        Ok(deep_challenge),
+        Ok(comp_point),
+    ) = (
+        HoonList::try_from(trace),
+        FPolySlice::try_from(trace_opens),
+        HoonList::try_from(pieces),
+        FPolySlice::try_from(piece_opens),
+        FPolySlice::try_from(weights),
+        FPolySlice::try_from(omicrons),
+        deep_challenge.as_felt(),
+        comp_point.as_felt(),
+    )
+    else {
+        return jet_err();
+    };
+    let (Ok(trace_polys), Ok(composition_pieces)) = (
+        trace
+            .map(MarySlice::try_from)
+            .collect::<std::result::Result<Vec<_>, _>>(),
+        pieces
+            .map(|p| FPolySlice::try_from(p).map(|p| p.0))
+            .collect::<std::result::Result<Vec<_>, _>>(),
+    ) else {
+        return jet_err();
+    };
+
+    let sample = DeepSample {
+        trace_polys,
+        trace_openings: trace_opens.0,
+        composition_pieces,
+        composition_piece_openings: piece_opens.0,
+        weights: weights.0,
+        omicrons: omicrons.0,
+        deep_challenge: *deep_challenge,
+        comp_eval_point: *comp_point,
+    };
+    // a sample the hoon would crash on, let it do so
+    let Some(deep) = fp_compute_deep(&sample) else {
+        return Err(JetErr::Punt);
+    };
+
+    let (res, res_poly): (IndirectAtom, &mut [Felt]) =
+        new_handle_mut_slice(&mut context.stack, Some(deep.len()));
+    res_poly.copy_from_slice(&deep);
+
+    let res_cell = finalize_poly(&mut context.stack, Some(res_poly.len()), res);
+
+    Ok(res_cell)
+}
@@ -5,8 +5,11 @@ use nockvm::noun::{IndirectAtom, Noun};
 use tracing::debug;
 
 use crate::form::mary::*;
+use crate::form::math::base::based_check;
 use crate::form::math::mary::*;
+use crate::form::poly::{BPolySlice, FPolySlice};
 use crate::hand::handle::{finalize_mary, new_handle_mut_mary};
+use crate::hand::structs::HoonList;
 use crate::jets::utils::jet_err;
 
 pub fn mary_swag_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
@@ -58,6 +61,55 @@ pub fn mary_weld_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetEr
     Ok(res_cell)
 }
 
+pub fn mary_weld_step_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let door = slot(subject, 7)?;
+    let ma = slot(door, 6)?;
+    let na = slot(subject, 6)?;
+
+    let (Ok(mary1), Ok(mary2)) = (MarySlice::try_from(ma), MarySlice::try_from(na)) else {
+        debug!("mary1 or mary2 is not a mary");
+        return jet_err();
+    };
+    // with a step 1 right side the hoon snips a belt off each row, leave
+    // that and mismatched lengths to it
+    if mary1.len != mary2.len || mary1.step == 0 || mary2.step <= 1 {
+        return Err(JetErr::Punt);
+    }
+    let step = (mary1.step + mary2.step) as usize;
+    let len = mary1.len as usize;
+    let (res, res_poly): (IndirectAtom, MarySliceMut) =
+        new_handle_mut_mary(&mut context.stack, step, len);
+
+    mary_weld_step(mary1, mary2, res_poly);
+    let res_cell = finalize_mary(&mut context.stack, step, len, res);
+    Ok(res_cell)
+}
+
+pub fn mary_swag_step_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let door = slot(subject, 7)?;
+    let ma = slot(door, 6)?;
+    let sam = slot(subject, 6)?;
+    let i = slot(sam, 2)?.as_atom()?.as_u64()? as usize;
+    let j = slot(sam, 3)?.as_atom()?.as_u64()? as usize;
+
+    let Ok(mary) = MarySlice::try_from(ma) else {
+        debug!("cannot convert mary arg to mary");
+        return jet_err();
+    };
+    if j == 0 || i.saturating_add(j) > mary.step as usize {
+        debug!("columns out of range");
+        return jet_err();
+    }
+
+    let len = mary.len as usize;
+    let (res, res_poly): (IndirectAtom, MarySliceMut) =
+        new_handle_mut_mary(&mut context.stack, j, len);
+
+    mary_swag_step(mary, i, j, res_poly);
+    let res_cell = finalize_mary(&mut context.stack, j, len, res);
+    Ok(res_cell)
+}
+
 pub fn mary_transpose_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
     let door = slot(subject, 7)?;
     let ma = slot(door, 6)?;
@@ -87,3 +139,105 @@ pub fn mary_transpose_jet(context: &mut Context, subject: Noun) -> Result<Noun,
 
     Ok(res_cell)
 }
+
+pub fn interpolate_table_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let sam = slot(subject, 6)?;
+    let table = slot(sam, 2)?;
+    let domain_len = slot(sam, 3)?;
+
+    let (Ok(table), Ok(domain_len)) = (MarySlice::try_from(table), domain_len.as_atom()?.as_u64())
+    else {
+        debug!("table is not a mary or domain-len is not an atom");
+        return jet_err();
+    };
+    let num_cols = table.step as usize;
+    let domain_len = domain_len as usize;
+    // empty and one row tables hit the zero-mary edge cases, leave them
+    // and malformed domains to the hoon
+    if num_cols == 0
+        || table.len == 0
+        || domain_len < 2
+        || !domain_len.is_power_of_two()
+        || domain_len < table.len as usize
+    {
+        return Err(JetErr::Punt);
+    }
+
+    let (res, mut res_poly): (IndirectAtom, MarySliceMut) =
+        new_handle_mut_mary(&mut context.stack, domain_len, num_cols);
+    interpolate_table(table, domain_len, &mut res_poly)?;
+
+    let res_cell = finalize_mary(&mut context.stack, domain_len, num_cols, res);
+    Ok(res_cell)
+}
+
+pub fn zing_bpolys_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let list = slot(subject, 6)?;
+
+    let Ok(list) = HoonList::try_from(list) else {
+        return jet_err();
+    };
+    let Ok(polys) = list
+        .map(BPolySlice::try_from)
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        debug!("list element is not a bpoly");
+        return jet_err();
+    };
+    // step 1 rows and the hoon's assertions on lengths and belts are left
+    // to it
+    let step = polys.first().map_or(0, |p| p.0.len());
+    if step <= 1
+        || polys
+            .iter()
+            .any(|p| p.0.len() != step || !p.0.iter().all(|b| based_check(b.0)))
+    {
+        return Err(JetErr::Punt);
+    }
+
+    let (res, res_poly): (IndirectAtom, MarySliceMut) =
+        new_handle_mut_mary(&mut context.stack, step, polys.len());
+    for (row, poly) in res_poly.dat.chunks_exact_mut(step).zip(&polys) {
+        for (word, belt) in row.iter_mut().zip(poly.0) {
+            *word = belt.0;
+        }
+    }
+
+    let res_cell = finalize_mary(&mut context.stack, step, polys.len(), res);
+    Ok(res_cell)
+}
+
+pub fn zing_fpolys_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let list = slot(subject, 6)?;
+
+    let Ok(list) = HoonList::try_from(list) else {
+        return jet_err();
+    };
+    let Ok(polys) = list
+        .map(FPolySlice::try_from)
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        debug!("list element is not an fpoly");
+        return jet_err();
+    };
+    let len = polys.first().map_or(0, |p| p.0.len());
+    if len == 0
+        || polys
+            .iter()
+            .any(|p| p.0.len() != len || !p.0.iter().flat_map(|f| f.0).all(|b| based_check(b.0)))
+    {
+        return Err(JetErr::Punt);
+    }
+
+    let step = 3 * len;
+    let (res, res_poly): (IndirectAtom, MarySliceMut) =
+        new_handle_mut_mary(&mut context.stack, step, polys.len());
+    for (row, poly) in res_poly.dat.chunks_exact_mut(step).zip(&polys) {
+        for (word, belt) in row.iter_mut().zip(poly.0.iter().flat_map(|f| f.0)) {
+            *word = belt.0;
+        }
+    }
+
+    let res_cell = finalize_mary(&mut context.stack, step, polys.len(), res);
+    Ok(res_cell)
+}
@@ -1,178 +1,211 @@
 use nockvm::interpreter::Context;
 use nockvm::jets::util::slot;
-use nockvm::jets::Result;
-use nockvm::noun::{IndirectAtom, Noun, D};
+use nockvm::jets::{JetErr, Result};
+use nockvm::mem::NockStack;
+use nockvm::noun::{IndirectAtom, Noun, D, T};
+use nockvm_macros::tas;
 
+use crate::form::math::base::bpow;
 use crate::form::math::bpoly::*;
+use crate::form::mega::{brek, MegaTyp};
 use crate::form::poly::*;
 use crate::hand::handle::*;
-use crate::hand::structs::{HoonMap, HoonMapIter};
+use crate::hand::structs::{HoonList, HoonMap, HoonMapIter};
 use crate::jets::utils::jet_err;
 use crate::noun::noun_ext::NounExt;
 
-use crate::form::mega::{brek, MegaTyp};
-use crate::form::math::base::bpow;
-
-
-fn zero_bpoly() -> BPolyVec {
-    BPolyVec::from(vec![0u64])
+/// Where `%com` terms look up the composition dependencies
+enum ComMap<'a> {
+    /// `com-map` as the Hoon passes it, `~` when there is none
+    Map(Option<HoonMap>),
+    /// Dependencies the jet has already substituted, by index
+    Deps(&'a [Vec<Belt>]),
 }
 
-fn lagrange_one_bpoly(len: usize) -> BPolyVec {
-    BPolyVec::from(vec![1u64; len])
+impl ComMap<'_> {
+    fn get(&self, stack: &mut NockStack, idx: usize) -> Option<&[Belt]> {
+        match self {
+            ComMap::Map(map) => {
+                let com = map.as_ref()?.get(stack, D(idx as u64))?;
+                BPolySlice::try_from(com).ok().map(|com| com.0)
+            }
+            ComMap::Deps(deps) => deps.get(idx).map(|dep| dep.as_slice()),
+        }
+    }
 }
 
-pub fn mp_substitute_mega_jet(context: &mut Context, subject: Noun) -> Result {
-    let sam = slot(subject, 6)?;
-    let stack = &mut context.stack;
-
-    let [p_noun, trace_evals_noun, height_noun, chal_map_noun, dyns_noun, com_map_noun] =
-        sam.uncell()?;
-
-    let Ok(trace_evals) = BPolySlice::try_from(trace_evals_noun) else {
-        return jet_err::<Noun>();
-    };
-    let Ok(height_atom) = height_noun.as_atom() else {
-        return jet_err::<Noun>();
-    };
-    let Ok(height) = height_atom.as_u64() else {
-        return jet_err::<Noun>();
-    };
-    let height_usize = height as usize;
-
-    let Ok(dyns) = BPolySlice::try_from(dyns_noun) else {
-        return jet_err::<Noun>();
-    };
-
-    let chal_map_opt: Option<HoonMap> = unsafe {
-        if chal_map_noun.raw_equals(&D(0)) {
-            None
-        } else {
-            HoonMap::try_from(chal_map_noun).ok()
-        }
-    };
-    let com_map_opt: Option<HoonMap> = unsafe {
-        if com_map_noun.raw_equals(&D(0)) {
-            None
-        } else {
-            HoonMap::try_from(com_map_noun).ok()
-        }
-    };
+fn hoon_map(map: Noun) -> Option<HoonMap> {
+    if unsafe { map.raw_equals(&D(0)) } {
+        None
+    } else {
+        HoonMap::try_from(map).ok()
+    }
+}
 
-    let mut acc_vec = zero_bpoly();
+fn hadamard_pow(acc: &mut Vec<Belt>, poly: &[Belt], exp: u64) {
+    for _ in 0..exp {
+        let mut res = vec![Belt(0); acc.len().min(poly.len())];
+        bp_hadamard(acc, poly, &mut res);
+        *acc = res;
+    }
+}
 
-    let mut p_iter = HoonMapIter::from(p_noun);
-    p_iter.try_fold((), |_, n| {
+/// +mp-substitute-mega: substitute the trace evaluations, challenges, dyns
+/// and composition dependencies into `p`, summing its terms in eval form
+fn mp_substitute_mega(
+    stack: &mut NockStack,
+    p: Noun,
+    trace_evals: &[Belt],
+    height: usize,
+    chal_map: Option<&HoonMap>,
+    dyns: &[Belt],
+    com_map: &ComMap,
+) -> std::result::Result<Vec<Belt>, JetErr> {
+    let len = 4 * height;
+    let mut acc = vec![Belt(0)];
+
+    for n in HoonMapIter::from(p) {
         let [k_noun, v_noun] = n.uncell()?;
-        let Ok(k) = BPolySlice::try_from(k_noun) else {
-            return jet_err::<()>();
-        };
-        let Ok(v) = v_noun.as_belt() else {
-            return jet_err::<()>();
+        let (Ok(k), Ok(v)) = (BPolySlice::try_from(k_noun), v_noun.as_belt()) else {
+            return jet_err();
         };
-
-        if v.0 == 0 {
-            return Ok(());
+        if v.is_zero() {
+            continue;
         }
 
-        let poly_len_for_var_com = 4 * height_usize;
-        let mut inner_acc_vec = lagrange_one_bpoly(poly_len_for_var_com);
-
-        for i in 0..k.len() {
-            let ter = k.0[i];
-
-            let (typ, idx, exp) = brek(ter);
-
+        let mut term = vec![Belt(1); len];
+        for ter in k.0 {
+            let (typ, idx, exp) = brek(*ter);
             match typ {
                 MegaTyp::Var => {
-                    let var_start_idx = idx * poly_len_for_var_com;
-                    let var_end_idx = var_start_idx + poly_len_for_var_com;
-
-                    if var_end_idx > trace_evals.len() {
-                        return jet_err::<()>();
-                    }
-                    let var_slice = &trace_evals.0[var_start_idx..var_end_idx];
-                    let hadamard_res_len = inner_acc_vec.len().min(var_slice.len());
-
-                    let mut temp_res_vec_belt: Vec<Belt> = vec![Belt::from(0u64); hadamard_res_len];
-                    let res_poly_slice = temp_res_vec_belt.as_mut_slice();
-
-                    for _ in 0..exp {
-                        let current_inner_acc_slice = &inner_acc_vec.0;
-                        bp_hadamard(current_inner_acc_slice, var_slice, res_poly_slice);
-                        inner_acc_vec = BPolyVec::from(res_poly_slice.iter().map(|&b| b.0).collect::<Vec<u64>>());
-                    }
+                    let Some(var) = trace_evals.get(idx * len..(idx + 1) * len) else {
+                        return jet_err();
+                    };
+                    hadamard_pow(&mut term, var, exp);
                 }
                 MegaTyp::Rnd => {
-                    let rnd_noun = chal_map_opt.as_ref()
-                        .and_then(|m| m.get(stack, D(idx as u64)))
-                        .ok_or_else(|| jet_err::<()>().unwrap_err())?;
-                    let Ok(rnd) = rnd_noun.as_belt() else {
-                        return jet_err::<()>();
+                    let Some(rnd) = chal_map.and_then(|m| m.get(stack, D(idx as u64))) else {
+                        return jet_err();
                     };
-
-                    let pow_rnd = bpow(rnd.0, exp);
-                    let mut temp_res_vec_belt: Vec<Belt> = vec![Belt::from(0u64); inner_acc_vec.len()];
-                    let res_poly_slice = temp_res_vec_belt.as_mut_slice();
-
-                    bpscal(Belt(pow_rnd), &inner_acc_vec.0, res_poly_slice);
-                    inner_acc_vec = BPolyVec::from(temp_res_vec_belt.into_iter().map(|b| b.0).collect::<Vec<u64>>());
+                    let Ok(rnd) = rnd.as_belt() else {
+                        return jet_err();
+                    };
+                    let scalar = Belt(bpow(rnd.0, exp));
+                    term = bpscal_(scalar, &term);
                 }
                 MegaTyp::Dyn => {
-                    if idx >= dyns.len() {
-                        return jet_err::<()>();
-                    }
-                    let dyn_val = dyns.0[idx];
-
-                    let pow_dyn = bpow(dyn_val.0, exp);
-                    let mut temp_res_vec_belt: Vec<Belt> = vec![Belt::from(0u64); inner_acc_vec.len()];
-                    let res_poly_slice = temp_res_vec_belt.as_mut_slice();
-
-                    bpscal(Belt(pow_dyn), &inner_acc_vec.0, res_poly_slice);
-                    inner_acc_vec = BPolyVec::from(temp_res_vec_belt.into_iter().map(|b| b.0).collect::<Vec<u64>>());
-                }
-                MegaTyp::Con => {
+                    let Some(dyn_val) = dyns.get(idx) else {
+                        return jet_err();
+                    };
+                    let scalar = Belt(bpow(dyn_val.0, exp));
+                    term = bpscal_(scalar, &term);
                 }
+                MegaTyp::Con => {}
                 MegaTyp::Com => {
-                    let com_noun = com_map_opt.as_ref()
-                        .and_then(|m| m.get(stack, D(idx as u64)))
-                        .ok_or_else(|| jet_err::<()>().unwrap_err())?;
-                    let Ok(com_slice) = BPolySlice::try_from(com_noun) else {
-                        return jet_err::<()>();
+                    let Some(com) = com_map.get(stack, idx) else {
+                        return jet_err();
                     };
-
-                    let hadamard_res_len = inner_acc_vec.len().min(com_slice.len());
-                    let mut temp_res_vec_belt: Vec<Belt> = vec![Belt::from(0u64); hadamard_res_len];
-                    let res_poly_slice = temp_res_vec_belt.as_mut_slice();
-
-                    for _ in 0..exp {
-                        let current_inner_acc_slice = &inner_acc_vec.0;
-                        bp_hadamard(current_inner_acc_slice, com_slice.0, res_poly_slice);
-                        inner_acc_vec = BPolyVec::from(res_poly_slice.iter().map(|&b| b.0).collect::<Vec<u64>>());
-                    }
+                    hadamard_pow(&mut term, com, exp);
                 }
             }
         }
 
-        let mut scaled_inner_vec_belt: Vec<Belt> = vec![Belt::from(0u64); inner_acc_vec.len()];
-        let scaled_poly_slice = scaled_inner_vec_belt.as_mut_slice();
-        bpscal(v, &inner_acc_vec.0, scaled_poly_slice);
-        let scaled_inner_bpolyvec = BPolyVec::from(scaled_inner_vec_belt.into_iter().map(|b| b.0).collect::<Vec<u64>>());
+        acc = bpadd_(&acc, &bpscal_(v, &term));
+    }
 
+    Ok(acc)
+}
 
-        let new_acc_len = acc_vec.len().max(scaled_inner_bpolyvec.len());
-        let mut new_acc_vec_belt: Vec<Belt> = vec![Belt::from(0u64); new_acc_len];
-        let new_acc_poly_slice = new_acc_vec_belt.as_mut_slice();
-        bpadd(&acc_vec.0, &scaled_inner_bpolyvec.0, new_acc_poly_slice);
-        acc_vec = BPolyVec::from(new_acc_vec_belt.into_iter().map(|b| b.0).collect::<Vec<u64>>());
+fn finalize_bpoly(stack: &mut NockStack, poly: &[Belt]) -> Noun {
+    let (res, res_poly): (IndirectAtom, &mut [Belt]) =
+        new_handle_mut_slice(stack, Some(poly.len()));
+    res_poly.copy_from_slice(poly);
+    finalize_poly(stack, Some(res_poly.len()), res)
+}
 
-        Ok(())
-    })?;
-    let (_final_res_atom, final_res_poly_slice): (IndirectAtom, &mut [Belt]) =
-        new_handle_mut_slice(stack, Some(acc_vec.len()));
-    final_res_poly_slice.copy_from_slice(&acc_vec.0);
+pub fn mp_substitute_mega_jet(context: &mut Context, subject: Noun) -> Result {
+    let sam = slot(subject, 6)?;
+    let stack = &mut context.stack;
 
-    let res_cell = finalize_poly(stack, Some(final_res_poly_slice.len()), _final_res_atom);
+    let [p_noun, trace_evals_noun, height_noun, chal_map_noun, dyns_noun, com_map_noun] =
+        sam.uncell()?;
+
+    let (Ok(trace_evals), Ok(height), Ok(dyns)) = (
+        BPolySlice::try_from(trace_evals_noun),
+        height_noun.as_atom(),
+        BPolySlice::try_from(dyns_noun),
+    ) else {
+        return jet_err();
+    };
+    let height = height.as_u64()? as usize;
+    let chal_map = hoon_map(chal_map_noun);
+    let com_map = ComMap::Map(hoon_map(com_map_noun));
+
+    let res = mp_substitute_mega(
+        stack,
+        p_noun,
+        trace_evals.0,
+        height,
+        chal_map.as_ref(),
+        dyns.0,
+        &com_map,
+    )?;
+
+    Ok(finalize_bpoly(stack, &res))
+}
+
+/// +mp-substitute-ultra: a `%mega` constraint is substituted directly, a
+/// `%comp` one has its dependencies substituted first and then each of its
+/// compositions with those in place of `%com` terms
+pub fn mp_substitute_ultra_jet(context: &mut Context, subject: Noun) -> Result {
+    let sam = slot(subject, 6)?;
+    let stack = &mut context.stack;
+
+    let [p_noun, trace_evals_noun, height_noun, chal_map_noun, dyns_noun] = sam.uncell()?;
 
-    Ok(res_cell)
-}
\ No newline at end of file
+    let (Ok(trace_evals), Ok(height), Ok(dyns)) = (
+        BPolySlice::try_from(trace_evals_noun),
+        height_noun.as_atom(),
+        BPolySlice::try_from(dyns_noun),
+    ) else {
+        return jet_err();
+    };
+    let height = height.as_u64()? as usize;
+    let chal_map = hoon_map(chal_map_noun);
+    let [tag, body] = p_noun.uncell()?;
+
+    let substitute = |stack: &mut NockStack, mp: Noun, com_map: &ComMap| {
+        mp_substitute_mega(
+            stack,
+            mp,
+            trace_evals.0,
+            height,
+            chal_map.as_ref(),
+            dyns.0,
+            com_map,
+        )
+    };
+
+    let polys = match tag.as_direct()?.data() {
+        tas!(b"mega") => vec![substitute(stack, body, &ComMap::Map(None))?],
+        tas!(b"comp") => {
+            let [dep, com] = body.uncell()?;
+            let (Ok(dep), Ok(com)) = (HoonList::try_from(dep), HoonList::try_from(com)) else {
+                return jet_err();
+            };
+            let deps = dep
+                .map(|mp| substitute(stack, mp, &ComMap::Map(None)))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            com.map(|mp| substitute(stack, mp, &ComMap::Deps(&deps)))
+                .collect::<std::result::Result<Vec<_>, _>>()?
+        }
+        _ => return jet_err(),
+    };
+
+    let mut list = D(0);
+    for poly in polys.iter().rev() {
+        let poly = finalize_bpoly(stack, poly);
+        list = T(stack, &[poly, list]);
+    }
+    Ok(list)
+}
@@ -3,6 +3,7 @@ pub mod bp_jets;
 pub mod cheetah_jets;
 pub mod crypto_jets;
 pub mod fext_jets;
+pub mod fp_jets;
 pub mod mary_jets;
 pub mod mega_jets;
 pub mod tip5_jets;
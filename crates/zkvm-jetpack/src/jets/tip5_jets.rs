@@ -3,8 +3,12 @@ use nockvm::jets::util::slot;
 use nockvm::jets::JetErr;
 use nockvm::noun::{Atom, Noun, D, T};
 
+use crate::form::math::based_check;
+use crate::form::math::merkle::hash_pairs;
 use crate::form::math::tip5::*;
+use crate::hand::structs::HoonList;
 use crate::jets::utils::jet_err;
+use crate::noun::noun_ext::NounExt;
 
 pub fn hoon_list_to_sponge(list: Noun) -> Result<[u64; STATE_SIZE], JetErr> {
     if list.is_atom() {
@@ -16,6 +20,9 @@ pub fn hoon_list_to_sponge(list: Noun) -> Result<[u64; STATE_SIZE], JetErr> {
     let mut i = 0;
 
     while current.is_cell() {
+        if i == STATE_SIZE {
+            return jet_err();
+        }
         let cell = current.as_cell()?;
         sponge[i] = cell.head().as_atom()?.as_u64()?;
         current = cell.tail();
@@ -47,3 +54,120 @@ pub fn permutation_jet(context: &mut Context, subject: Noun) -> Result<Noun, Jet
 
     Ok(new_sponge)
 }
+
+/// A list of belts, failing like `+absorb` does on anything out of field
+fn hoon_list_to_belts(list: Noun) -> Result<Vec<u64>, JetErr> {
+    let Ok(list) = HoonList::try_from(list) else {
+        return jet_err();
+    };
+    let mut belts = Vec::new();
+    for belt in list {
+        let belt = belt.as_atom()?.as_u64()?;
+        if !based_check(belt) {
+            return jet_err();
+        }
+        belts.push(belt);
+    }
+    Ok(belts)
+}
+
+pub fn hash_varlen_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let input = hoon_list_to_belts(slot(subject, 6)?)?;
+    let digest = hash_varlen(&input);
+
+    Ok(vec_to_hoon_list(context, &digest))
+}
+
+pub fn hash_10_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let input = hoon_list_to_belts(slot(subject, 6)?)?;
+    let Ok(input) = <[u64; RATE]>::try_from(input) else {
+        return jet_err();
+    };
+    let digest = hash_10(&input);
+
+    Ok(vec_to_hoon_list(context, &digest))
+}
+
+/// `+hash-pairs` of a level of digests, an odd one at the end passed up as
+/// it is. Lists that are not digests are left to the Hoon, which only needs
+/// each pair to weld to ten belts.
+pub fn hash_pairs_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let Ok(list) = HoonList::try_from(slot(subject, 6)?) else {
+        return jet_err();
+    };
+    let lists: Vec<Noun> = list.collect();
+    if lists.is_empty() {
+        return jet_err();
+    }
+    let paired = lists.len() / 2 * 2;
+    let mut children = Vec::with_capacity(paired);
+    for list in &lists[..paired] {
+        let Ok(digest) = <[u64; DIGEST_LENGTH]>::try_from(hoon_list_to_belts(*list)?) else {
+            return Err(JetErr::Punt);
+        };
+        children.push(digest);
+    }
+    let Ok(parents) = hash_pairs(&children) else {
+        return jet_err();
+    };
+
+    let mut res = D(0);
+    if let Some(odd) = lists.get(paired) {
+        res = T(&mut context.stack, &[*odd, res]);
+    }
+    for parent in parents.iter().rev() {
+        let parent = vec_to_hoon_list(context, parent);
+        res = T(&mut context.stack, &[parent, res]);
+    }
+    Ok(res)
+}
+
+pub fn hash_noun_varlen_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let Ok(digest) = tip5_noun_digest(slot(subject, 6)?) else {
+        return jet_err();
+    };
+
+    let digest = digest.map(|belt| Atom::new(&mut context.stack, belt).as_noun());
+    Ok(T(&mut context.stack, &digest))
+}
+
+pub fn mac_belts_list_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let [key, msg] = slot(subject, 6)?.uncell()?;
+    let mut belts = [0; DIGEST_LENGTH];
+    for (belt, noun) in belts.iter_mut().zip(key.uncell::<DIGEST_LENGTH>()?) {
+        *belt = noun.as_atom()?.as_u64()?;
+        if !based_check(*belt) {
+            return jet_err();
+        }
+    }
+    let msg = hoon_list_to_belts(msg)?;
+    let tag = mac_varlen(&belts, &msg);
+
+    let tag = tag.map(|belt| Atom::new(&mut context.stack, belt).as_noun());
+    Ok(T(&mut context.stack, &tag))
+}
+
+pub fn sponge_absorb_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let input = hoon_list_to_belts(slot(subject, 6)?)?;
+    let door = slot(subject, 7)?;
+    let mut sponge = Sponge::from_state(hoon_list_to_sponge(slot(door, 6)?)?);
+    sponge.absorb(&input);
+
+    let state = vec_to_hoon_list(context, sponge.state());
+    Ok(T(
+        &mut context.stack,
+        &[slot(door, 2)?, state, slot(door, 7)?],
+    ))
+}
+
+pub fn tog_belts_jet(context: &mut Context, subject: Noun) -> Result<Noun, JetErr> {
+    let n = slot(subject, 6)?.as_atom()?.as_u64()? as usize;
+    let door = slot(subject, 7)?;
+    let mut tog = Tog::new(Sponge::from_state(hoon_list_to_sponge(slot(door, 6)?)?));
+    let belts = tog.belts(n);
+
+    let belts = vec_to_hoon_list(context, &belts);
+    let state = vec_to_hoon_list(context, tog.sponge().state());
+    let rng = T(&mut context.stack, &[slot(door, 2)?, state, slot(door, 7)?]);
+    Ok(T(&mut context.stack, &[belts, rng]))
+}
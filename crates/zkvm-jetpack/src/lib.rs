@@ -1,3 +1,5 @@
+#![cfg_attr(target_arch = "x86_64", feature(avx512_target_feature))]
+
 pub mod form;
 pub mod hand;
 pub mod hot;